@@ -6,19 +6,22 @@ use std::iter;
 use std::mem;
 use std::str::FromStr;
 
-use proc_macro2::{Ident, Span, TokenStream};
-use quote::{quote, ToTokens};
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::spanned::Spanned;
 use syn::{
-    parse_macro_input, AttrStyle, Attribute, Data, DeriveInput, Expr, Fields, Index, Meta,
-    NestedMeta, Type, TypeGenerics, TypePath,
+    parse_macro_input, parse_quote, AttrStyle, Attribute, Data, DeriveInput, Field, Fields,
+    Generics, Index, Lit, Meta, MetaNameValue, NestedMeta, Type, TypeGenerics, TypePath,
 };
 
-#[proc_macro_derive(H5Type)]
+#[proc_macro_derive(H5Type, attributes(hdf5))]
 pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = input.ident;
-    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
-    let body = impl_trait(&name, &input.data, &input.attrs, &ty_generics);
+    let generics = add_bounds(input.generics.clone(), &input.data);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let (body, extra) = impl_trait(&name, &input.data, &input.attrs, &ty_generics);
     let dummy = Ident::new(&format!("_IMPL_H5TYPE_FOR_{}", name), Span::call_site());
     let expanded = quote! {
         #[allow(dead_code, unused_variables, unused_attributes)]
@@ -31,18 +34,80 @@ pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
                 fn type_descriptor() -> _h5::types::TypeDescriptor {
                     #body
                 }
+
+                #extra
             }
         };
     };
     proc_macro::TokenStream::from(expanded)
 }
 
+/// Returns `true` if the identifier is used anywhere within the type.
+fn mentions(tokens: TokenStream, ident: &Ident) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ref i) => i == ident,
+        TokenTree::Group(ref g) => mentions(g.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Adds `H5Type` bounds for type parameters used in stored fields, and `'static`
+/// bounds for all other type parameters.
+fn add_bounds(mut generics: Generics, data: &Data) -> Generics {
+    let types: Vec<_> = match *data {
+        Data::Struct(ref data) => data
+            .fields
+            .iter()
+            .filter(|f| !is_phantom_data(&f.ty) && !FieldAttrs::parse(&f.attrs).skip)
+            .map(|f| f.ty.clone())
+            .collect(),
+        _ => vec![],
+    };
+    let params: Vec<_> = generics.type_params().map(|p| p.ident.clone()).collect();
+    let predicates = &mut generics.make_where_clause().predicates;
+    for param in params {
+        if types.iter().any(|ty| mentions(ty.into_token_stream(), &param)) {
+            predicates.push(parse_quote!(#param: _h5::types::H5Type));
+        } else {
+            predicates.push(parse_quote!(#param: 'static));
+        }
+    }
+    generics
+}
+
 fn impl_compound<F>(
     ty: &Ident, ty_generics: &TypeGenerics, fields: &[F], names: &[String], types: &[Type],
 ) -> TokenStream
 where
     F: ToTokens,
 {
+    // field offsets are taken from the actual layout, but descriptor sizes come from the
+    // field types' own `H5Type` impls, which could be wrong; this can't be checked at
+    // compile time since descriptors aren't constants
+    let check = quote! {
+        let sizes = [#(::std::mem::size_of::<#types>()),*];
+        for (field, &size) in fields.iter().zip(sizes.iter()) {
+            assert_eq!(
+                field.ty.size(), size,
+                "H5Type descriptor of field {:?} of {} doesn't match its size",
+                field.name, stringify!(#ty),
+            );
+        }
+    };
+    // statics are shared by all instantiations of generic types, so these are always checked
+    let check = if quote!(#ty_generics).is_empty() {
+        quote! {
+            static CHECKED: ::std::sync::Once = ::std::sync::Once::new();
+            CHECKED.call_once(|| { #check });
+        }
+    } else {
+        check
+    };
+    let check = quote! {
+        if cfg!(debug_assertions) {
+            #check
+        }
+    };
     quote! {
         let origin: *const #ty #ty_generics = ::std::ptr::null();
         let mut fields = vec![#(
@@ -56,12 +121,179 @@ where
         for i in 0..fields.len() {
             fields[i].index = i;
         }
+        #check
         let size = ::std::mem::size_of::<#ty #ty_generics>();
         _h5::types::TypeDescriptor::Compound(_h5::types::CompoundType { fields, size })
     }
 }
 
-fn impl_enum(names: Vec<Ident>, values: Vec<Expr>, repr: &Ident) -> TokenStream {
+/// Returns `true` if the type is a primitive scalar (or an array of them), whose stored
+/// size is known to be equal to its size in memory.
+fn is_primitive(ty: &Type) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "bool", "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64", "usize", "f32",
+        "f64",
+    ];
+    match *ty {
+        Type::Path(TypePath { qself: None, ref path }) => {
+            path.leading_colon.is_none()
+                && path.segments.len() == 1
+                && path.segments[0].arguments.is_empty()
+                && PRIMITIVES.iter().any(|p| path.segments[0].ident == p)
+        }
+        Type::Array(ref array) => is_primitive(&array.elem),
+        Type::Paren(ref paren) => is_primitive(&paren.elem),
+        _ => false,
+    }
+}
+
+/// Compile-time checks that explicitly placed primitive fields fit into the stored size
+/// and don't overlap (other field types may be stored with a different size).
+fn impl_layout_checks(types: &[Type], offsets: &[usize], size: Option<usize>) -> TokenStream {
+    let assert = |ty: &Type, cond: TokenStream| {
+        quote_spanned! { ty.span()=>
+            const _: [(); 0 - !(#cond) as usize] = [];
+        }
+    };
+    let mut checks = TokenStream::new();
+    for (i, (ty, offset)) in types.iter().zip(offsets).enumerate() {
+        if !is_primitive(ty) {
+            continue;
+        }
+        let end = quote! { #offset + ::std::mem::size_of::<#ty>() };
+        if let Some(size) = size {
+            checks.extend(assert(ty, quote! { #end <= #size }));
+        }
+        for (other, other_offset) in types[..i].iter().zip(offsets) {
+            if is_primitive(other) {
+                let other_end = quote! { #other_offset + ::std::mem::size_of::<#other>() };
+                let cond = quote! { #end <= #other_offset || #other_end <= #offset };
+                checks.extend(assert(ty, cond));
+            }
+        }
+    }
+    checks
+}
+
+fn impl_layout(types: &[Type], offsets: &[Option<usize>], size: Option<usize>) -> TokenStream {
+    if offsets.iter().all(Option::is_none) && size.is_none() {
+        return TokenStream::new();
+    }
+    if offsets.iter().any(Option::is_none) && offsets.iter().any(Option::is_some) {
+        panic!("#[hdf5(offset = ...)] must be set for either all or none of the stored fields");
+    }
+    let set_offsets = offsets.iter().any(Option::is_some);
+    let checks = if set_offsets {
+        let offsets: Vec<_> = offsets.iter().map(|offset| offset.unwrap()).collect();
+        impl_layout_checks(types, &offsets, size)
+    } else {
+        TokenStream::new()
+    };
+    let offsets = offsets.iter().map(|offset| offset.unwrap_or(0));
+    let size = match size {
+        Some(size) => quote! { #size },
+        None => quote! { fields.iter().map(|f| f.offset + f.ty.size()).max().unwrap_or(0) },
+    };
+    quote! {
+        #[inline]
+        fn stored_type_descriptor() -> Option<_h5::types::TypeDescriptor> {
+            #checks
+            let mut fields = match <Self as _h5::types::H5Type>::type_descriptor().to_c_repr() {
+                _h5::types::TypeDescriptor::Compound(compound) => compound.fields,
+                _ => unreachable!(),
+            };
+            let stored = vec![#(<#types as _h5::types::H5Type>::stored_type_descriptor()),*];
+            let offsets: &[usize] = &[#(#offsets),*];
+            for (i, (field, stored)) in fields.iter_mut().zip(stored).enumerate() {
+                if let Some(stored) = stored {
+                    field.ty = stored;
+                }
+                if #set_offsets {
+                    field.offset = offsets[i];
+                }
+            }
+            let size = #size;
+            Some(_h5::types::TypeDescriptor::Compound(_h5::types::CompoundType { fields, size }))
+        }
+    }
+}
+
+fn impl_defaults<F>(skipped: &[F], defaulted: &[F], default_names: &[String]) -> TokenStream
+where
+    F: ToTokens,
+{
+    if skipped.is_empty() && defaulted.is_empty() {
+        return TokenStream::new();
+    }
+    quote! {
+        #[inline]
+        fn default_members() -> &'static [&'static str] {
+            &[#(#default_names),*]
+        }
+
+        #[inline]
+        unsafe fn fill_defaults(value: *mut Self, missing: &[&str]) {
+            #(
+                ::std::ptr::write(
+                    &mut (*value).#skipped as *mut _,
+                    ::std::default::Default::default(),
+                );
+            )*
+            #(
+                if missing.contains(&#default_names) {
+                    ::std::ptr::write(
+                        &mut (*value).#defaulted as *mut _,
+                        ::std::default::Default::default(),
+                    );
+                }
+            )*
+        }
+    }
+}
+
+fn impl_struct<F>(
+    ty: &Ident, ty_generics: &TypeGenerics, fields: Vec<(F, String, &Field)>, attrs: &[Attribute],
+) -> (TokenStream, TokenStream)
+where
+    F: ToTokens,
+{
+    let (mut members, mut names, mut types, mut offsets) = (vec![], vec![], vec![], vec![]);
+    let (mut skipped, mut defaulted, mut default_names) = (vec![], vec![], vec![]);
+    for (member, name, field) in fields {
+        let attrs = FieldAttrs::parse(&field.attrs);
+        let name = attrs.rename.unwrap_or(name);
+        let member = member.into_token_stream();
+        if attrs.skip {
+            skipped.push(member);
+            continue;
+        }
+        if attrs.default {
+            defaulted.push(member.clone());
+            default_names.push(name.clone());
+        }
+        members.push(member);
+        names.push(name);
+        types.push(field.ty.clone());
+        offsets.push(attrs.offset);
+    }
+    if members.is_empty() {
+        panic!("Cannot derive H5Type for structs with all fields skipped");
+    }
+    check_unique_names(&names);
+    let defaults = impl_defaults(&skipped, &defaulted, &default_names);
+    let layout = impl_layout(&types, &offsets, find_size(attrs));
+    (impl_compound(ty, ty_generics, &members, &names, &types), quote! { #defaults #layout })
+}
+
+fn impl_transparent(fields: &[&Field]) -> (TokenStream, TokenStream) {
+    if fields.len() != 1 || !hdf5_attrs(&fields[0].attrs).is_empty() {
+        panic!("#[repr(transparent)] structs must have one plain non-PhantomData field");
+    }
+    let ty = &fields[0].ty;
+    (quote! { <#ty as _h5::types::H5Type>::type_descriptor() }, TokenStream::new())
+}
+
+fn impl_enum(ty: &Ident, variants: Vec<Ident>, names: Vec<String>, repr: &Ident) -> TokenStream {
     let size = Ident::new(
         &format!(
             "U{}",
@@ -71,6 +303,7 @@ fn impl_enum(names: Vec<Ident>, values: Vec<Expr>, repr: &Ident) -> TokenStream
     );
     let signed = repr.to_string().starts_with('i');
     let repr = iter::repeat(repr);
+    let ty = iter::repeat(ty);
     quote! {
         _h5::types::TypeDescriptor::Enum(
             _h5::types::EnumType {
@@ -78,8 +311,8 @@ fn impl_enum(names: Vec<Ident>, values: Vec<Expr>, repr: &Ident) -> TokenStream
                 signed: #signed,
                 members: vec![#(
                     _h5::types::EnumMember {
-                        name: stringify!(#names).to_owned(),
-                        value: (#values) as #repr as _,
+                        name: #names.to_owned(),
+                        value: #ty::#variants as #repr as _,
                     }
                 ),*],
             }
@@ -96,6 +329,13 @@ fn is_phantom_data(ty: &Type) -> bool {
     }
 }
 
+const REPR_C_MSG: &str =
+    "H5Type requires #[repr(C)] for structs (or #[repr(transparent)] for newtypes)";
+
+fn is_transparent(attrs: &[Attribute]) -> bool {
+    find_repr(attrs, &["transparent"]).is_some()
+}
+
 fn find_repr(attrs: &[Attribute], expected: &[&str]) -> Option<Ident> {
     for attr in attrs.iter() {
         if attr.style == AttrStyle::Outer {
@@ -116,6 +356,105 @@ fn find_repr(attrs: &[Attribute], expected: &[&str]) -> Option<Ident> {
     None
 }
 
+fn hdf5_attrs(attrs: &[Attribute]) -> Vec<Meta> {
+    let mut metas = Vec::new();
+    for attr in attrs.iter() {
+        if attr.style == AttrStyle::Outer && attr.path.is_ident("hdf5") {
+            match attr.parse_meta() {
+                Ok(Meta::List(ref list)) => {
+                    for item in list.nested.iter() {
+                        match *item {
+                            NestedMeta::Meta(ref meta) => metas.push(meta.clone()),
+                            _ => panic!("Invalid #[hdf5(...)] attribute"),
+                        }
+                    }
+                }
+                _ => panic!("Invalid #[hdf5(...)] attribute"),
+            }
+        }
+    }
+    metas
+}
+
+fn rename_value(meta: &Meta) -> Option<String> {
+    match *meta {
+        Meta::NameValue(MetaNameValue { ref ident, lit: Lit::Str(ref s), .. })
+            if ident == "rename" =>
+        {
+            Some(s.value())
+        }
+        _ => None,
+    }
+}
+
+fn find_rename(attrs: &[Attribute]) -> Option<String> {
+    let mut rename = None;
+    for meta in hdf5_attrs(attrs) {
+        match rename_value(&meta) {
+            Some(name) => rename = Some(name),
+            None => panic!("Unsupported #[hdf5(...)] attribute: {}", meta.name()),
+        }
+    }
+    rename
+}
+
+fn usize_value(meta: &Meta, name: &str) -> Option<usize> {
+    match *meta {
+        Meta::NameValue(MetaNameValue { ref ident, lit: Lit::Int(ref n), .. }) if ident == name => {
+            Some(n.value() as _)
+        }
+        _ => None,
+    }
+}
+
+fn find_size(attrs: &[Attribute]) -> Option<usize> {
+    let mut size = None;
+    for meta in hdf5_attrs(attrs) {
+        match usize_value(&meta, "size") {
+            Some(value) => size = Some(value),
+            None => panic!("Unsupported #[hdf5(...)] attribute: {}", meta.name()),
+        }
+    }
+    size
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    default: bool,
+    skip: bool,
+    offset: Option<usize>,
+}
+
+impl FieldAttrs {
+    fn parse(attrs: &[Attribute]) -> Self {
+        let mut field_attrs = Self::default();
+        for meta in hdf5_attrs(attrs) {
+            match meta {
+                Meta::Word(ref ident) if ident == "default" => field_attrs.default = true,
+                Meta::Word(ref ident) if ident == "skip" => field_attrs.skip = true,
+                _ if rename_value(&meta).is_some() => field_attrs.rename = rename_value(&meta),
+                _ if usize_value(&meta, "offset").is_some() => {
+                    field_attrs.offset = usize_value(&meta, "offset")
+                }
+                _ => panic!("Unsupported #[hdf5(...)] attribute: {}", meta.name()),
+            }
+        }
+        if field_attrs.default && field_attrs.skip {
+            panic!("#[hdf5(default)] and #[hdf5(skip)] cannot be used together");
+        }
+        field_attrs
+    }
+}
+
+fn check_unique_names(names: &[String]) {
+    for (i, name) in names.iter().enumerate() {
+        if names[..i].contains(name) {
+            panic!("Duplicate H5Type member name: {:?}", name);
+        }
+    }
+}
+
 fn pluck<'a, I, F, T, S>(iter: I, func: F) -> Vec<S>
 where
     I: Iterator<Item = &'a T>,
@@ -127,7 +466,7 @@ where
 
 fn impl_trait(
     ty: &Ident, data: &Data, attrs: &[Attribute], ty_generics: &TypeGenerics,
-) -> TokenStream {
+) -> (TokenStream, TokenStream) {
     match *data {
         Data::Struct(ref data) => match data.fields {
             Fields::Unit => {
@@ -139,11 +478,15 @@ fn impl_trait(
                 if fields.is_empty() {
                     panic!("Cannot derive H5Type for empty structs");
                 }
-                find_repr(attrs, &["C"]).expect("H5Type requires #[repr(C)] for structs");
-                let types = pluck(fields.iter(), |f| f.ty.clone());
-                let fields = pluck(fields.iter(), |f| f.ident.clone().unwrap());
-                let names = fields.iter().map(|f| f.to_string()).collect::<Vec<_>>();
-                impl_compound(ty, ty_generics, &fields, &names, &types)
+                if is_transparent(attrs) {
+                    return impl_transparent(&fields);
+                }
+                find_repr(attrs, &["C"]).expect(REPR_C_MSG);
+                let fields = pluck(fields.iter(), |&f| {
+                    let ident = f.ident.clone().unwrap();
+                    (ident.clone(), ident.to_string(), f)
+                });
+                impl_struct(ty, ty_generics, fields, attrs)
             }
             Fields::Unnamed(ref fields) => {
                 let (index, fields): (Vec<Index>, Vec<_>) = fields
@@ -156,10 +499,22 @@ fn impl_trait(
                 if fields.is_empty() {
                     panic!("Cannot derive H5Type for empty tuple structs");
                 }
-                find_repr(attrs, &["C"]).expect("H5Type requires #[repr(C)] for structs");
-                let names = (0..fields.len()).map(|f| f.to_string()).collect::<Vec<_>>();
-                let types = pluck(fields.iter(), |f| f.ty.clone());
-                impl_compound(ty, ty_generics, &index, &names, &types)
+                if is_transparent(attrs) {
+                    return impl_transparent(&fields);
+                }
+                find_repr(attrs, &["C"]).expect(REPR_C_MSG);
+                let mut next = 0;
+                let fields = index
+                    .into_iter()
+                    .zip(fields)
+                    .map(|(index, f)| {
+                        let skip = FieldAttrs::parse(&f.attrs).skip;
+                        let name = next.to_string();
+                        next += if skip { 0 } else { 1 };
+                        (index, name, f)
+                    })
+                    .collect();
+                impl_struct(ty, ty_generics, fields, attrs)
             }
         },
         Data::Enum(ref data) => {
@@ -173,9 +528,12 @@ fn impl_trait(
                 &["i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "isize", "usize"];
             let repr = find_repr(attrs, enum_reprs)
                 .expect("H5Type can only be derived for enums with explicit representation");
-            let names = pluck(variants.iter(), |v| v.ident.clone());
-            let values = pluck(variants.iter(), |v| v.discriminant.clone().unwrap().1);
-            impl_enum(names, values, &repr)
+            let idents = pluck(variants.iter(), |v| v.ident.clone());
+            let names = pluck(variants.iter(), |v| {
+                find_rename(&v.attrs).unwrap_or_else(|| v.ident.to_string())
+            });
+            check_unique_names(&names);
+            (impl_enum(ty, idents, names, &repr), TokenStream::new())
         }
         Data::Union(_) => {
             panic!("Cannot derive H5Type for tagged unions");
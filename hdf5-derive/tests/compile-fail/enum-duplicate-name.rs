@@ -0,0 +1,13 @@
+extern crate hdf5_derive;
+use hdf5_derive::H5Type;
+
+#[derive(H5Type)]
+//~^ ERROR proc-macro derive
+//~^^ HELP Duplicate H5Type member name: "Y"
+#[repr(u8)]
+enum Foo {
+    X = 1,
+    #[hdf5(rename = "Y")]
+    Z = 2,
+    Y = 3,
+}
@@ -0,0 +1,12 @@
+extern crate hdf5_derive;
+use hdf5_derive::H5Type;
+
+#[derive(H5Type)]
+//~^ ERROR proc-macro derive
+//~^^ HELP Duplicate H5Type member name: "a"
+#[repr(C)]
+struct Foo {
+    a: i64,
+    #[hdf5(rename = "a")]
+    b: u64,
+}
@@ -0,0 +1,15 @@
+extern crate hdf5_derive;
+use hdf5_derive::H5Type;
+
+#[derive(H5Type)]
+#[repr(C)]
+#[hdf5(size = 8)]
+struct Foo {
+    #[hdf5(offset = 0)]
+    a: u32,
+    #[hdf5(offset = 6)]
+    b: [u8; 3],
+    //~^ ERROR evaluation of constant value failed
+}
+
+fn main() {}
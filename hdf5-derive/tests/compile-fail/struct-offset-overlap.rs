@@ -0,0 +1,14 @@
+extern crate hdf5_derive;
+use hdf5_derive::H5Type;
+
+#[derive(H5Type)]
+#[repr(C)]
+struct Foo {
+    #[hdf5(offset = 0)]
+    a: u32,
+    #[hdf5(offset = 2)]
+    b: u16,
+    //~^ ERROR evaluation of constant value failed
+}
+
+fn main() {}
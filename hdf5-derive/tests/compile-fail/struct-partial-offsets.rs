@@ -0,0 +1,12 @@
+extern crate hdf5_derive;
+use hdf5_derive::H5Type;
+
+#[derive(H5Type)]
+//~^ ERROR proc-macro derive
+//~^^ HELP #[hdf5(offset = ...)] must be set for either all or none of the stored fields
+#[repr(C)]
+struct Foo {
+    #[hdf5(offset = 4)]
+    a: i32,
+    b: u8,
+}
@@ -104,6 +104,35 @@ fn test_enum_simple() {
     assert_eq!(E1::type_descriptor().size(), 2);
 }
 
+const BASE: u64 = 1 << 40;
+
+#[derive(H5Type, Clone, Copy)]
+#[repr(u64)]
+#[allow(dead_code)]
+enum E2 {
+    #[hdf5(rename = "first value")]
+    A = 7,
+    B = BASE + 1,
+    #[hdf5(rename = "C")]
+    Max = u64::max_value(),
+}
+
+#[test]
+fn test_enum_rename_sparse() {
+    assert_eq!(
+        E2::type_descriptor(),
+        TD::Enum(EnumType {
+            size: IntSize::U8,
+            signed: false,
+            members: vec![
+                EnumMember { name: "first value".into(), value: 7 },
+                EnumMember { name: "B".into(), value: (1 << 40) + 1 },
+                EnumMember { name: "C".into(), value: u64::max_value() },
+            ]
+        })
+    );
+}
+
 #[test]
 fn test_enum_base_type() {
     macro_rules! check_base_type {
@@ -200,3 +229,234 @@ fn test_phantom_data() {
     assert_eq!(G3::<String>::type_descriptor(), C3::type_descriptor());
     assert_eq!(G4::<String>::type_descriptor(), C4::type_descriptor());
 }
+
+#[derive(H5Type, Default)]
+#[repr(C)]
+struct D1 {
+    x: i16,
+    #[hdf5(skip)]
+    cache: Vec<u8>,
+    #[hdf5(default)]
+    y: f64,
+}
+
+#[derive(H5Type)]
+#[repr(C)]
+struct D2(#[hdf5(skip)] String, u8, #[hdf5(default)] u32);
+
+#[test]
+fn test_default_skip() {
+    assert_eq!(
+        D1::type_descriptor(),
+        TD::Compound(CompoundType {
+            fields: vec![
+                CompoundField::typed::<i16>("x", 0, 0),
+                CompoundField::typed::<f64>("y", 32, 1),
+            ],
+            size: 40,
+        })
+    );
+    assert_eq!(D1::default_members(), &["y"]);
+    assert_eq!(D2::default_members(), &["1"]);
+    match D2::type_descriptor() {
+        TD::Compound(c) => {
+            assert_eq!(c.fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), ["0", "1"])
+        }
+        _ => panic!(),
+    }
+
+    let mut d = D1 { x: 1, cache: vec![1], y: 2.0 };
+    unsafe {
+        let p = &mut d as *mut D1;
+        ::std::ptr::drop_in_place(&mut (*p).cache);
+        D1::fill_defaults(p, &["y"]);
+    }
+    assert_eq!((d.x, d.cache.len(), d.y), (1, 0, 0.0));
+    assert_eq!(A::default_members().len(), 0);
+}
+
+#[derive(H5Type)]
+#[repr(C)]
+struct R1 {
+    #[hdf5(rename = "Time (s)")]
+    time: f64,
+    #[hdf5(rename = "type", default)]
+    kind: u8,
+}
+
+#[derive(H5Type)]
+#[repr(C)]
+struct R2(#[hdf5(rename = "x")] f32, f32);
+
+#[test]
+fn test_compound_rename() {
+    assert_eq!(
+        R1::type_descriptor(),
+        TD::Compound(CompoundType {
+            fields: vec![
+                CompoundField::typed::<f64>("Time (s)", 0, 0),
+                CompoundField::typed::<u8>("type", 8, 1),
+            ],
+            size: 16,
+        })
+    );
+    assert_eq!(R1::default_members(), &["type"]);
+    assert_eq!(
+        R2::type_descriptor(),
+        TD::Compound(CompoundType {
+            fields: vec![
+                CompoundField::typed::<f32>("x", 0, 0),
+                CompoundField::typed::<f32>("1", 4, 1),
+            ],
+            size: 8,
+        })
+    );
+}
+
+#[derive(H5Type, Clone, Copy)]
+#[repr(transparent)]
+struct Meters(f64);
+
+#[derive(H5Type)]
+#[repr(transparent)]
+#[allow(dead_code)]
+struct Wrapper<T: 'static> {
+    inner: FixedAscii<[u8; 6]>,
+    tag: PhantomData<T>,
+}
+
+#[derive(H5Type)]
+#[repr(C)]
+struct Segment(Meters, Meters);
+
+#[test]
+fn test_transparent() {
+    assert_eq!(Meters::type_descriptor(), f64::type_descriptor());
+    assert_eq!(Wrapper::<u8>::type_descriptor(), TD::FixedAscii(6));
+    assert_eq!(
+        Segment::type_descriptor(),
+        TD::Compound(CompoundType {
+            fields: vec![
+                CompoundField::typed::<f64>("0", 0, 0),
+                CompoundField::typed::<f64>("1", 8, 1),
+            ],
+            size: 16,
+        })
+    );
+}
+
+#[derive(H5Type)]
+#[repr(C)]
+struct Point<T> {
+    x: T,
+    y: T,
+}
+
+#[derive(H5Type)]
+#[repr(C)]
+struct Tagged<T, U> {
+    value: [T; 2],
+    tag: PhantomData<U>,
+    #[hdf5(skip)]
+    note: Option<U>,
+}
+
+#[test]
+fn test_generics_implicit_bounds() {
+    assert_eq!(
+        Point::<f32>::type_descriptor(),
+        TD::Compound(CompoundType {
+            fields: vec![
+                CompoundField::typed::<f32>("x", 0, 0),
+                CompoundField::typed::<f32>("y", 4, 1),
+            ],
+            size: 8,
+        })
+    );
+    assert_eq!(Point::<Point<u8>>::type_descriptor().size(), 4);
+    match Tagged::<i16, String>::type_descriptor() {
+        TD::Compound(c) => {
+            assert_eq!(c.fields.len(), 1);
+            assert_eq!(c.fields[0].ty, TD::FixedArray(Box::new(TD::Integer(IntSize::U2)), 2));
+        }
+        _ => panic!(),
+    }
+}
+
+#[derive(H5Type)]
+#[repr(C)]
+#[hdf5(size = 16)]
+struct L1 {
+    #[hdf5(offset = 8)]
+    a: u32,
+    #[hdf5(offset = 0)]
+    b: u16,
+    #[hdf5(skip)]
+    cache: Vec<u8>,
+    #[hdf5(offset = 12)]
+    c: u8,
+}
+
+#[derive(H5Type)]
+#[repr(C)]
+struct L2(#[hdf5(offset = 3)] L1, #[hdf5(offset = 0)] [u8; 3]);
+
+#[derive(H5Type)]
+#[repr(C)]
+#[hdf5(size = 8)]
+struct L3 {
+    x: u16,
+}
+
+#[test]
+fn test_explicit_layout() {
+    let fields = vec![
+        CompoundField::typed::<u32>("a", 8, 0),
+        CompoundField::typed::<u16>("b", 0, 1),
+        CompoundField::typed::<u8>("c", 12, 2),
+    ];
+    let l1 = TD::Compound(CompoundType { fields, size: 16 });
+    assert_eq!(L1::stored_type_descriptor(), Some(l1.clone()));
+    assert_eq!(L1::type_descriptor().size(), mem::size_of::<L1>());
+    assert_eq!(
+        L2::stored_type_descriptor(),
+        Some(TD::Compound(CompoundType {
+            fields: vec![
+                CompoundField::new("0", l1, 3, 0),
+                CompoundField::typed::<[u8; 3]>("1", 0, 1),
+            ],
+            size: 19,
+        }))
+    );
+    assert_eq!(
+        L3::stored_type_descriptor(),
+        Some(TD::Compound(CompoundType {
+            fields: vec![CompoundField::typed::<u16>("x", 0, 0)],
+            size: 8
+        }))
+    );
+    assert_eq!(A::stored_type_descriptor(), None);
+}
+
+#[allow(dead_code)]
+struct Wide(u32);
+
+unsafe impl H5Type for Wide {
+    fn type_descriptor() -> TypeDescriptor {
+        TD::Unsigned(IntSize::U8)
+    }
+}
+
+#[derive(H5Type)]
+#[repr(C)]
+struct HasWide {
+    a: u8,
+    b: Wide,
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "H5Type descriptor of field \"b\" of HasWide doesn't match its size")]
+fn test_field_size_mismatch() {
+    HasWide::type_descriptor();
+}
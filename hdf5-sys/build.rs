@@ -570,6 +570,9 @@ pub struct Config {
 impl Config {
     pub fn emit_link_flags(&self) {
         println!("cargo:rustc-link-lib=dylib=hdf5");
+        if cfg!(feature = "hl") {
+            println!("cargo:rustc-link-lib=dylib=hdf5_hl");
+        }
         for dir in &self.link_paths {
             println!("cargo:rustc-link-search=native={}", dir.to_str().unwrap());
         }
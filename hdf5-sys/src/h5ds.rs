@@ -0,0 +1,21 @@
+use crate::internal_prelude::*;
+
+pub type H5DS_iterate_t = Option<
+    extern "C" fn(dset: hid_t, dim: c_uint, scale: hid_t, visitor_data: *mut c_void) -> herr_t,
+>;
+
+extern "C" {
+    pub fn H5DSattach_scale(did: hid_t, dsid: hid_t, idx: c_uint) -> herr_t;
+    pub fn H5DSdetach_scale(did: hid_t, dsid: hid_t, idx: c_uint) -> herr_t;
+    pub fn H5DSset_scale(dsid: hid_t, dimname: *const c_char) -> herr_t;
+    pub fn H5DSget_num_scales(did: hid_t, dim: c_uint) -> c_int;
+    pub fn H5DSset_label(did: hid_t, idx: c_uint, label: *const c_char) -> herr_t;
+    pub fn H5DSget_label(did: hid_t, idx: c_uint, label: *mut c_char, size: size_t) -> ssize_t;
+    pub fn H5DSget_scale_name(did: hid_t, name: *mut c_char, size: size_t) -> ssize_t;
+    pub fn H5DSis_scale(did: hid_t) -> htri_t;
+    pub fn H5DSiterate_scales(
+        did: hid_t, dim: c_uint, ds_idx: *mut c_int, visitor: H5DS_iterate_t,
+        visitor_data: *mut c_void,
+    ) -> herr_t;
+    pub fn H5DSis_attached(did: hid_t, dsid: hid_t, idx: c_uint) -> htri_t;
+}
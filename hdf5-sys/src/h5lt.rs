@@ -0,0 +1,23 @@
+use crate::internal_prelude::*;
+
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, PartialOrd, Debug)]
+pub enum H5LT_lang_t {
+    H5LT_LANG_ERR = -1,
+    H5LT_DDL = 0,
+    H5LT_C = 1,
+    H5LT_FORTRAN = 2,
+    H5LT_NO_LANG = 3,
+}
+
+pub use self::H5LT_lang_t::*;
+
+extern "C" {
+    pub fn H5LTtext_to_dtype(text: *const c_char, lang_type: H5LT_lang_t) -> hid_t;
+    pub fn H5LTdtype_to_text(
+        dtype: hid_t, str: *mut c_char, lang_type: H5LT_lang_t, len: *mut size_t,
+    ) -> herr_t;
+    pub fn H5LTset_attribute_string(
+        loc_id: hid_t, obj_name: *const c_char, attr_name: *const c_char, attr_data: *const c_char,
+    ) -> herr_t;
+}
@@ -35,6 +35,17 @@ pub mod h5z;
 #[cfg(hdf5_1_8_15)]
 pub mod h5pl;
 
+#[cfg(feature = "hl")]
+pub mod h5ds;
+#[cfg(feature = "hl")]
+pub mod h5im;
+#[cfg(feature = "hl")]
+pub mod h5lt;
+#[cfg(feature = "hl")]
+pub mod h5pt;
+#[cfg(feature = "hl")]
+pub mod h5tb;
+
 #[allow(non_camel_case_types)]
 mod internal_prelude {
     pub use crate::h5::{
@@ -83,6 +94,7 @@ pub fn emit_cfg_flags() {
     check_and_emit!(hdf5_1_10_2);
     check_and_emit!(hdf5_1_10_3);
     check_and_emit!(hdf5_1_10_4);
+    check_and_emit!(hdf5_1_10_5);
     check_and_emit!(h5_have_direct);
     check_and_emit!(h5_have_parallel);
     check_and_emit!(h5_have_threadsafe);
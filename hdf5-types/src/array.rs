@@ -1,9 +1,11 @@
 use std::fmt;
+use std::iter::FromIterator;
 use std::marker::PhantomData;
 use std::mem;
-use std::ops::Deref;
+use std::ops::{Deref, Index, IndexMut};
 use std::ptr;
-use std::slice;
+use std::slice::{self, SliceIndex};
+use std::vec;
 
 /* This trait is borrowed from arrayvec::Array (C) @bluss */
 pub unsafe trait Array: 'static {
@@ -14,49 +16,24 @@ pub unsafe trait Array: 'static {
     fn capacity() -> usize;
 }
 
-macro_rules! impl_array {
-    () => ();
+unsafe impl<T: 'static, const N: usize> Array for [T; N] {
+    type Item = T;
 
-    ($n:expr, $($ns:expr,)*) => (
-        unsafe impl<T: 'static> Array for [T; $n] {
-            type Item = T;
-
-            #[inline(always)]
-            fn as_ptr(&self) -> *const T {
-                self as *const _ as *const _
-            }
-
-            #[inline(always)]
-            fn as_mut_ptr(&mut self) -> *mut T {
-                self as *mut _ as *mut _
-            }
+    #[inline(always)]
+    fn as_ptr(&self) -> *const T {
+        self as *const _ as *const _
+    }
 
-            #[inline(always)]
-            fn capacity() -> usize {
-                $n
-            }
-        }
+    #[inline(always)]
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self as *mut _ as *mut _
+    }
 
-        impl_array!($($ns,)*);
-    );
-}
-
-impl_array!(
-    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25,
-    26, 27, 28, 29, 30, 31,
-);
-impl_array!(
-    32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55,
-    56, 57, 58, 59, 60, 61, 62, 63,
-);
-impl_array!(
-    64, 70, 72, 80, 90, 96, 100, 110, 120, 128, 130, 140, 150, 160, 170, 180, 190, 192, 200, 210,
-    220, 224, 230, 240, 250,
-);
-impl_array!(
-    256, 300, 384, 400, 500, 512, 600, 700, 768, 800, 900, 1000, 1024, 2048, 4096, 8192, 16384,
-    32768,
-);
+    #[inline(always)]
+    fn capacity() -> usize {
+        N
+    }
+}
 
 #[repr(C)]
 pub struct VarLenArray<T: Copy> {
@@ -101,6 +78,20 @@ impl<T: Copy> VarLenArray<T> {
     pub fn as_slice(&self) -> &[T] {
         self
     }
+
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        if self.len == 0 || self.ptr.is_null() {
+            &mut []
+        } else {
+            unsafe { slice::from_raw_parts_mut(self.ptr as *mut T, self.len()) }
+        }
+    }
+
+    #[inline]
+    pub fn to_vec(&self) -> Vec<T> {
+        self.as_slice().to_vec()
+    }
 }
 
 impl<T: Copy> Drop for VarLenArray<T> {
@@ -137,6 +128,22 @@ impl<T: Copy> Deref for VarLenArray<T> {
     }
 }
 
+impl<T: Copy, I: SliceIndex<[T]>> Index<I> for VarLenArray<T> {
+    type Output = I::Output;
+
+    #[inline]
+    fn index(&self, index: I) -> &I::Output {
+        &self.as_slice()[index]
+    }
+}
+
+impl<T: Copy, I: SliceIndex<[T]>> IndexMut<I> for VarLenArray<T> {
+    #[inline]
+    fn index_mut(&mut self, index: I) -> &mut I::Output {
+        &mut self.as_mut_slice()[index]
+    }
+}
+
 impl<'a, T: Copy> From<&'a [T]> for VarLenArray<T> {
     #[inline]
     fn from(arr: &[T]) -> VarLenArray<T> {
@@ -144,10 +151,54 @@ impl<'a, T: Copy> From<&'a [T]> for VarLenArray<T> {
     }
 }
 
-impl<T: Copy> Into<Vec<T>> for VarLenArray<T> {
+impl<T: Copy> From<Vec<T>> for VarLenArray<T> {
+    #[inline]
+    fn from(vec: Vec<T>) -> VarLenArray<T> {
+        VarLenArray::from_slice(&vec)
+    }
+}
+
+impl<T: Copy> From<VarLenArray<T>> for Vec<T> {
     #[inline]
-    fn into(self) -> Vec<T> {
-        self.iter().cloned().collect()
+    fn from(arr: VarLenArray<T>) -> Vec<T> {
+        arr.to_vec()
+    }
+}
+
+impl<T: Copy> FromIterator<T> for VarLenArray<T> {
+    #[inline]
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> VarLenArray<T> {
+        VarLenArray::from(iter.into_iter().collect::<Vec<_>>())
+    }
+}
+
+impl<T: Copy> IntoIterator for VarLenArray<T> {
+    type Item = T;
+    type IntoIter = vec::IntoIter<T>;
+
+    #[inline]
+    fn into_iter(self) -> vec::IntoIter<T> {
+        self.to_vec().into_iter()
+    }
+}
+
+impl<'a, T: Copy> IntoIterator for &'a VarLenArray<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T: Copy> IntoIterator for &'a mut VarLenArray<T> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    #[inline]
+    fn into_iter(self) -> slice::IterMut<'a, T> {
+        self.as_mut_slice().iter_mut()
     }
 }
 
@@ -195,6 +246,20 @@ impl<T: Copy + fmt::Debug> fmt::Debug for VarLenArray<T> {
     }
 }
 
+#[cfg(feature = "serde")]
+impl<T: Copy + serde::Serialize> serde::Serialize for VarLenArray<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.as_slice().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Copy + serde::Deserialize<'de>> serde::Deserialize<'de> for VarLenArray<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<T>::deserialize(deserializer).map(Into::into)
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::{Array, VarLenArray};
@@ -205,6 +270,7 @@ pub mod tests {
     pub fn test_array_trait() {
         type T = [u32; 256];
         assert_eq!(<T as Array>::capacity(), 256);
+        assert_eq!(<[u8; 1001] as Array>::capacity(), 1001);
         let mut arr = [1, 2, 3];
         assert_eq!(arr.as_ptr(), &arr[0] as *const _);
         assert_eq!(arr.as_mut_ptr(), &mut arr[0] as *mut _);
@@ -243,4 +309,33 @@ pub mod tests {
         let v: Vec<_> = a.iter().cloned().collect();
         assert_eq!(v, vec![1, 2, 3]);
     }
+
+    #[test]
+    pub fn test_vla_conversions() {
+        let mut a = S::from(vec![1, 2, 3]);
+        assert_eq!(a[1], 2);
+        assert_eq!(&a[1..], &[2, 3]);
+        a[0] = 5;
+        for x in &mut a {
+            *x += 1;
+        }
+        assert_eq!(a.to_vec(), vec![6, 3, 4]);
+        assert_eq!((&a).into_iter().max(), Some(&6));
+        let b: S = a.iter().map(|x| x * 2).collect();
+        assert_eq!(b, [12, 6, 8]);
+        assert_eq!(b.into_iter().collect::<Vec<_>>(), vec![12, 6, 8]);
+        assert_eq!(Vec::from(a), vec![6, 3, 4]);
+        let mut e = S::default();
+        assert!(e.as_mut_slice().is_empty());
+        assert!(Vec::from(e).is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn test_vla_serde() {
+        use serde::de::value::{Error, SeqDeserializer};
+        use serde::Deserialize;
+        let de = SeqDeserializer::<_, Error>::new(vec![1_u16, 2, 3].into_iter());
+        assert_eq!(S::deserialize(de).unwrap(), [1, 2, 3]);
+    }
 }
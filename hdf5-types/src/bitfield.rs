@@ -0,0 +1,59 @@
+use crate::h5type::{BitfieldType, H5Type, IntSize, TypeDescriptor};
+
+/// Integer wrapper stored as an HDF5 bitfield type using all bits of `T`.
+///
+/// Bitfields with a smaller precision or a non-zero bit offset (as described by
+/// `BitfieldType`) can be read into it; HDF5 shifts the significant bits down so
+/// that they start at bit 0.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Bitfield<T>(pub T);
+
+macro_rules! impl_bitfield {
+    ($ty:ty, $size:expr) => {
+        impl Bitfield<$ty> {
+            /// Returns `true` if the bit at position `bit` is set.
+            #[inline]
+            pub fn is_set(self, bit: u32) -> bool {
+                self.0.checked_shr(bit).map_or(false, |v| v & 1 != 0)
+            }
+        }
+
+        impl From<$ty> for Bitfield<$ty> {
+            #[inline]
+            fn from(bits: $ty) -> Self {
+                Self(bits)
+            }
+        }
+
+        unsafe impl H5Type for Bitfield<$ty> {
+            #[inline]
+            fn type_descriptor() -> TypeDescriptor {
+                TypeDescriptor::Bitfield(BitfieldType::new($size))
+            }
+        }
+    };
+}
+
+impl_bitfield!(u8, IntSize::U1);
+impl_bitfield!(u16, IntSize::U2);
+impl_bitfield!(u32, IntSize::U4);
+impl_bitfield!(u64, IntSize::U8);
+
+#[cfg(test)]
+pub mod tests {
+    use super::Bitfield;
+    use crate::h5type::{BitfieldType, H5Type, IntSize, TypeDescriptor as TD};
+
+    #[test]
+    pub fn test_bitfield() {
+        assert_eq!(
+            Bitfield::<u16>::type_descriptor(),
+            TD::Bitfield(BitfieldType { size: IntSize::U2, precision: 16, offset: 0 })
+        );
+        assert_eq!(Bitfield::<u64>::type_descriptor().size(), 8);
+        let flags = Bitfield::from(0b1010_u8);
+        assert!(flags.is_set(1) && flags.is_set(3));
+        assert!(!flags.is_set(0) && !flags.is_set(8));
+    }
+}
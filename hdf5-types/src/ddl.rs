@@ -0,0 +1,448 @@
+//! Parsing HDF5 DDL datatype descriptions into type descriptors.
+
+use std::mem;
+use std::os::raw::{c_char, c_int, c_long, c_longlong, c_short};
+
+use crate::h5type::{
+    BitfieldType, CompoundField, CompoundType, EnumMember, EnumType, FloatSize, IntSize,
+    OpaqueType, ReferenceType, TypeDescriptor as TD,
+};
+use crate::parse::{Parser, Result};
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn int_size(bits: &str) -> Option<IntSize> {
+    bits.parse::<usize>().ok().filter(|b| b % 8 == 0).and_then(|b| IntSize::from_int(b / 8))
+}
+
+/// Returns the descriptor of an `H5T_NATIVE_*` type.
+fn native_type(name: &str) -> Option<TD> {
+    fn int<T>(signed: bool) -> TD {
+        let size = IntSize::from_int(mem::size_of::<T>()).unwrap();
+        if signed {
+            TD::Integer(size)
+        } else {
+            TD::Unsigned(size)
+        }
+    }
+    Some(match name {
+        "CHAR" => int::<c_char>(c_char::min_value() != 0),
+        "SCHAR" => int::<c_char>(true),
+        "UCHAR" => int::<c_char>(false),
+        "SHORT" => int::<c_short>(true),
+        "USHORT" => int::<c_short>(false),
+        "INT" => int::<c_int>(true),
+        "UINT" => int::<c_int>(false),
+        "LONG" => int::<c_long>(true),
+        "ULONG" => int::<c_long>(false),
+        "LLONG" => int::<c_longlong>(true),
+        "ULLONG" => int::<c_longlong>(false),
+        "FLOAT" => TD::Float(FloatSize::U4),
+        "DOUBLE" => TD::Float(FloatSize::U8),
+        _ => return None,
+    })
+}
+
+impl<'a> Parser<'a> {
+    fn word(&mut self) -> Result<&'a str> {
+        match self.take_while(is_word_char) {
+            "" => self.error("expected keyword"),
+            word => Ok(word),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<()> {
+        let start = self.pos;
+        if self.take_while(is_word_char) == keyword {
+            Ok(())
+        } else {
+            self.pos = start;
+            self.skip_whitespace();
+            self.error(format!("expected {}", keyword))
+        }
+    }
+
+    /// Parses a (possibly negative) enum member value into its two's complement bits.
+    fn enum_value(&mut self) -> Result<u64> {
+        let negative = self.eat('-');
+        let value = self.integer()? as u64;
+        Ok(if negative { (value as i64).wrapping_neg() as u64 } else { value })
+    }
+
+    /// Parses a predefined atomic type such as `H5T_STD_I32LE` or `H5T_IEEE_F64BE`.
+    fn atomic(&mut self, start: usize, name: &str) -> Result<TD> {
+        let native = if cfg!(target_endian = "little") { "LE" } else { "BE" };
+        if let Some(native_name) = name.strip_prefix("H5T_NATIVE_") {
+            if let Some(desc) = native_type(native_name) {
+                return Ok(desc);
+            }
+        }
+        let (body, order) = name.split_at(name.len().saturating_sub(2));
+        let desc = if let Some(bits) = body.strip_prefix("H5T_STD_I") {
+            int_size(bits).map(TD::Integer)
+        } else if let Some(bits) = body.strip_prefix("H5T_STD_U") {
+            int_size(bits).map(TD::Unsigned)
+        } else if let Some(bits) = body.strip_prefix("H5T_STD_B") {
+            int_size(bits).map(|size| TD::Bitfield(BitfieldType::new(size)))
+        } else if let Some(bits) = body.strip_prefix("H5T_IEEE_F") {
+            let bits = bits.parse::<usize>().ok().filter(|b| b % 8 == 0);
+            bits.and_then(|b| FloatSize::from_int(b / 8)).map(TD::Float)
+        } else {
+            None
+        }
+        .filter(|desc| desc.size() < 16);
+        self.pos = start;
+        let known_order = order == "LE" || order == "BE";
+        match desc {
+            Some(desc) if known_order && (order == native || desc.size() == 1) => {
+                self.pos += name.len();
+                Ok(desc)
+            }
+            Some(_) if known_order => self.error("non-native byte order is not supported"),
+            _ => self.error(format!("unsupported datatype: {}", name)),
+        }
+    }
+
+    /// Parses a datatype in DDL syntax.
+    fn ddl_type(&mut self) -> Result<TD> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let name = self.word()?;
+        match name {
+            "H5T_STRING" => self.string_type(),
+            "H5T_OPAQUE" => {
+                self.expect('{')?;
+                self.keyword("OPQ_SIZE")?;
+                let size = self.integer()?;
+                self.expect(';')?;
+                self.keyword("OPQ_TAG")?;
+                let tag = self.quoted()?.to_owned();
+                self.expect(';')?;
+                self.expect('}')?;
+                Ok(TD::Opaque(OpaqueType { size, tag }))
+            }
+            "H5T_COMPOUND" => self.compound_type(),
+            "H5T_ARRAY" => {
+                self.expect('{')?;
+                let mut dims = Vec::new();
+                while self.eat('[') {
+                    dims.push(self.integer()?);
+                    self.expect(']')?;
+                }
+                if dims.is_empty() {
+                    return self.error("expected array dimensions");
+                }
+                let base = self.ddl_type()?;
+                self.expect('}')?;
+                Ok(dims.iter().rev().fold(base, |ty, &len| TD::FixedArray(Box::new(ty), len)))
+            }
+            "H5T_VLEN" => {
+                self.expect('{')?;
+                let base = self.ddl_type()?;
+                self.expect('}')?;
+                Ok(TD::VarLenArray(Box::new(base)))
+            }
+            "H5T_ENUM" => self.enum_type(),
+            "H5T_REFERENCE" => {
+                self.expect('{')?;
+                let pos = self.pos;
+                let ty = match self.word()? {
+                    "H5T_STD_REF_OBJECT" | "H5T_STD_REF_OBJ" => ReferenceType::Object,
+                    "H5T_STD_REF_DSETREG" => ReferenceType::DatasetRegion,
+                    other => {
+                        self.pos = pos;
+                        self.skip_whitespace();
+                        return self.error(format!("unsupported reference type: {}", other));
+                    }
+                };
+                self.expect('}')?;
+                Ok(TD::Reference(ty))
+            }
+            _ => self.atomic(start, name),
+        }
+    }
+
+    fn string_type(&mut self) -> Result<TD> {
+        self.expect('{')?;
+        let (mut size, mut utf8) = (None, false);
+        while !self.eat('}') {
+            self.skip_whitespace();
+            let start = self.pos;
+            match self.word()? {
+                "STRSIZE" => {
+                    size = Some(match self.peek() {
+                        Some(c) if c.is_ascii_digit() => Some(self.integer()?),
+                        _ => {
+                            self.keyword("H5T_VARIABLE")?;
+                            None
+                        }
+                    });
+                }
+                "CSET" => {
+                    let pos = self.pos;
+                    utf8 = match self.word()? {
+                        "H5T_CSET_ASCII" => false,
+                        "H5T_CSET_UTF8" => true,
+                        other => {
+                            self.pos = pos;
+                            self.skip_whitespace();
+                            return self.error(format!("unsupported character set: {}", other));
+                        }
+                    };
+                }
+                "STRPAD" | "CTYPE" => {
+                    self.word()?;
+                }
+                other => {
+                    self.pos = start;
+                    return self.error(format!("unexpected string property: {}", other));
+                }
+            }
+            self.expect(';')?;
+        }
+        match (size, utf8) {
+            (None, _) => self.error("missing STRSIZE"),
+            (Some(Some(0)), _) => self.error("string size must be positive"),
+            (Some(Some(len)), false) => Ok(TD::FixedAscii(len)),
+            (Some(Some(len)), true) => Ok(TD::FixedUnicode(len)),
+            (Some(None), false) => Ok(TD::VarLenAscii),
+            (Some(None), true) => Ok(TD::VarLenUnicode),
+        }
+    }
+
+    /// Parses compound members; members without an explicit `: offset` follow the
+    /// previous one directly.
+    fn compound_type(&mut self) -> Result<TD> {
+        self.expect('{')?;
+        let (mut fields, mut offset, mut size) = (Vec::<CompoundField>::new(), 0, 0);
+        while !self.eat('}') {
+            let ty = self.ddl_type()?;
+            let name_pos = self.pos;
+            let name = self.quoted()?;
+            if fields.iter().any(|f| f.name == name) {
+                self.pos = name_pos;
+                self.skip_whitespace();
+                return self.error(format!("duplicate member name: {:?}", name));
+            }
+            if self.eat(':') {
+                offset = self.integer()?;
+            }
+            self.expect(';')?;
+            let end = offset + ty.size();
+            fields.push(CompoundField::new(name, ty, offset, fields.len()));
+            size = size.max(end);
+            offset = end;
+        }
+        fields.sort_by_key(|f| f.offset);
+        Ok(TD::Compound(CompoundType { fields, size }))
+    }
+
+    fn enum_type(&mut self) -> Result<TD> {
+        self.expect('{')?;
+        self.skip_whitespace();
+        let base_pos = self.pos;
+        let (size, signed) = match self.ddl_type()? {
+            TD::Integer(size) => (size, true),
+            TD::Unsigned(size) => (size, false),
+            _ => {
+                self.pos = base_pos;
+                return self.error("enum base type must be an integer");
+            }
+        };
+        self.expect(';')?;
+        let mut members = Vec::new();
+        while !self.eat('}') {
+            let name = self.quoted()?.to_owned();
+            let value = self.enum_value()?;
+            self.expect(';')?;
+            members.push(EnumMember { name, value });
+        }
+        let bool_members = [
+            EnumMember { name: "FALSE".to_owned(), value: 0 },
+            EnumMember { name: "TRUE".to_owned(), value: 1 },
+        ];
+        if size == IntSize::U1 && members == bool_members {
+            Ok(TD::Boolean)
+        } else {
+            Ok(TD::Enum(EnumType { size, signed, members }))
+        }
+    }
+}
+
+impl TD {
+    /// Parses a datatype description in HDF5 DDL syntax (as printed by `h5dump` or
+    /// `H5LTdtype_to_text()`) into a type descriptor.
+    ///
+    /// Besides the predefined atomic types (e.g. `H5T_STD_I32LE`, `H5T_IEEE_F64LE` or
+    /// `H5T_NATIVE_INT`), `H5T_STRING`, `H5T_OPAQUE`, `H5T_COMPOUND`, `H5T_ARRAY`,
+    /// `H5T_VLEN`, `H5T_ENUM` and `H5T_REFERENCE` types are supported; multi-byte types
+    /// must use the native byte order. Since DDL doesn't record the total size of
+    /// compound types, their trailing padding is not preserved.
+    pub fn from_ddl_str(text: &str) -> Result<Self> {
+        let mut parser = Parser::new("DDL datatype", text);
+        let desc = parser.ddl_type()?;
+        parser.end()?;
+        Ok(desc)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::h5type::{
+        CompoundField, CompoundType, EnumMember, EnumType, FloatSize, H5Type, IntSize, OpaqueType,
+        ReferenceType, TypeDescriptor as TD,
+    };
+
+    fn parse(s: &str) -> TD {
+        TD::from_ddl_str(s).unwrap()
+    }
+
+    fn parse_err(s: &str) -> String {
+        TD::from_ddl_str(s).unwrap_err().to_string()
+    }
+
+    #[cfg(target_endian = "little")]
+    fn native(name: &str) -> String {
+        format!("{}LE", name)
+    }
+
+    #[cfg(target_endian = "big")]
+    fn native(name: &str) -> String {
+        format!("{}BE", name)
+    }
+
+    #[test]
+    pub fn test_ddl_atomic() {
+        assert_eq!(parse(&native("H5T_STD_I16")), TD::Integer(IntSize::U2));
+        assert_eq!(parse(&native("H5T_STD_U64")), TD::Unsigned(IntSize::U8));
+        assert_eq!(parse("H5T_STD_U8BE"), TD::Unsigned(IntSize::U1));
+        assert_eq!(parse(&native("H5T_IEEE_F32")), TD::Float(FloatSize::U4));
+        assert_eq!(parse(" H5T_NATIVE_DOUBLE "), TD::Float(FloatSize::U8));
+        assert_eq!(parse("H5T_NATIVE_INT"), i32::type_descriptor());
+        assert_eq!(parse("H5T_NATIVE_UCHAR"), TD::Unsigned(IntSize::U1));
+        assert_eq!(parse("H5T_NATIVE_LLONG"), TD::Integer(IntSize::U8));
+        assert_eq!(
+            parse(" H5T_REFERENCE { H5T_STD_REF_OBJECT }"),
+            TD::Reference(ReferenceType::Object)
+        );
+        assert_eq!(
+            parse("H5T_REFERENCE { H5T_STD_REF_DSETREG }"),
+            TD::Reference(ReferenceType::DatasetRegion)
+        );
+    }
+
+    #[test]
+    pub fn test_ddl_composite() {
+        let string = |size: &str, cset: &str| {
+            format!(
+                "H5T_STRING {{ STRSIZE {}; STRPAD H5T_STR_NULLTERM; CSET {}; CTYPE H5T_C_S1; }}",
+                size, cset
+            )
+        };
+        assert_eq!(parse(&string("5", "H5T_CSET_ASCII")), TD::FixedAscii(5));
+        assert_eq!(parse(&string("5", "H5T_CSET_UTF8")), TD::FixedUnicode(5));
+        assert_eq!(parse(&string("H5T_VARIABLE", "H5T_CSET_ASCII")), TD::VarLenAscii);
+        assert_eq!(parse(&string("H5T_VARIABLE", "H5T_CSET_UTF8")), TD::VarLenUnicode);
+        assert_eq!(
+            parse("H5T_OPAQUE {\n  OPQ_SIZE 3;\n  OPQ_TAG \"blob\";\n}"),
+            TD::Opaque(OpaqueType { size: 3, tag: "blob".to_owned() })
+        );
+        assert_eq!(
+            parse("H5T_ARRAY { [2][3] H5T_NATIVE_FLOAT }"),
+            <[[f32; 3]; 2]>::type_descriptor()
+        );
+        assert_eq!(
+            parse("H5T_VLEN { H5T_NATIVE_DOUBLE }"),
+            TD::VarLenArray(Box::new(TD::Float(FloatSize::U8)))
+        );
+        assert_eq!(
+            parse(&format!("H5T_ENUM {{ {}; \"FALSE\" 0; \"TRUE\" 1; }}", native("H5T_STD_I8"))),
+            TD::Boolean
+        );
+        assert_eq!(
+            parse("H5T_ENUM { H5T_NATIVE_SHORT; \"A\" -1; \"B\" 7; }"),
+            TD::Enum(EnumType {
+                size: IntSize::U2,
+                signed: true,
+                members: vec![
+                    EnumMember { name: "A".to_owned(), value: u64::max_value() },
+                    EnumMember { name: "B".to_owned(), value: 7 },
+                ],
+            })
+        );
+        assert_eq!(
+            parse(
+                "H5T_COMPOUND {
+                    H5T_NATIVE_DOUBLE \"x\" : 8;
+                    H5T_COMPOUND { H5T_NATIVE_UCHAR \"a\"; H5T_NATIVE_UCHAR \"b\"; } \"y\" : 0;
+                    H5T_NATIVE_INT \"z\";
+                }"
+            ),
+            TD::Compound(CompoundType {
+                fields: vec![
+                    CompoundField::new(
+                        "y",
+                        TD::Compound(CompoundType {
+                            fields: vec![
+                                CompoundField::typed::<u8>("a", 0, 0),
+                                CompoundField::typed::<u8>("b", 1, 1),
+                            ],
+                            size: 2,
+                        }),
+                        0,
+                        1
+                    ),
+                    CompoundField::typed::<i32>("z", 2, 2),
+                    CompoundField::typed::<f64>("x", 8, 0),
+                ],
+                size: 16,
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_ddl_errors() {
+        let non_native =
+            if cfg!(target_endian = "little") { "H5T_IEEE_F64BE" } else { "H5T_IEEE_F64LE" };
+        assert_eq!(
+            parse_err(non_native),
+            "invalid DDL datatype (at 0): non-native byte order is not supported"
+        );
+        assert_eq!(
+            parse_err("H5T_FOO"),
+            "invalid DDL datatype (at 0): unsupported datatype: H5T_FOO"
+        );
+        assert_eq!(
+            parse_err("H5T_NATIVE_LDOUBLE"),
+            "invalid DDL datatype (at 0): unsupported datatype: H5T_NATIVE_LDOUBLE"
+        );
+        assert_eq!(
+            parse_err("undefined integer"),
+            "invalid DDL datatype (at 0): unsupported datatype: undefined"
+        );
+        assert_eq!(parse_err("H5T_COMPOUND {"), "invalid DDL datatype (at 14): expected keyword");
+        assert_eq!(
+            parse_err("H5T_VLEN { H5T_NATIVE_INT"),
+            "invalid DDL datatype (at 25): expected '}'"
+        );
+        assert_eq!(parse_err(""), "invalid DDL datatype (at 0): expected keyword");
+        assert_eq!(
+            parse_err("H5T_COMPOUND { H5T_NATIVE_INT \"a\"; H5T_NATIVE_INT \"a\"; }"),
+            "invalid DDL datatype (at 50): duplicate member name: \"a\""
+        );
+        assert_eq!(
+            parse_err("H5T_STRING { CSET H5T_CSET_ASCII; }"),
+            "invalid DDL datatype (at 35): missing STRSIZE"
+        );
+        assert_eq!(
+            parse_err("H5T_ENUM { H5T_NATIVE_FLOAT; }"),
+            "invalid DDL datatype (at 11): enum base type must be an integer"
+        );
+        assert_eq!(
+            parse_err("H5T_NATIVE_INT;"),
+            "invalid DDL datatype (at 14): unexpected trailing characters"
+        );
+    }
+}
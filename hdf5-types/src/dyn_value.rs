@@ -0,0 +1,197 @@
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+
+use crate::h5type::{
+    hvl_t, BitfieldType, CompoundType, EnumType, FloatSize, IntSize, ReferenceType, TypeDescriptor,
+};
+
+/// A value of a type that is only known at runtime.
+///
+/// Integers are widened to 128 bits and floats to `f64`; both fixed-size and
+/// variable-length arrays are represented as `Array`, and all string types as `String`.
+/// Bitfields are decoded as `UInt` holding only their significant bits.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DynValue {
+    Bool(bool),
+    Int(i128),
+    UInt(u128),
+    Float(f64),
+    /// Enum value with the name of the matching member, if any.
+    Enum {
+        name: Option<String>,
+        value: i128,
+    },
+    String(String),
+    Array(Vec<DynValue>),
+    /// Compound fields in the order of their indices.
+    Compound(Vec<(String, DynValue)>),
+    Opaque {
+        tag: String,
+        data: Vec<u8>,
+    },
+    /// Raw bytes of an object or dataset region reference.
+    Reference {
+        ty: ReferenceType,
+        data: Vec<u8>,
+    },
+}
+
+unsafe fn read_int(buf: *const u8, size: IntSize, signed: bool) -> i128 {
+    macro_rules! read {
+        ($ty:ty) => {
+            i128::from(ptr::read_unaligned(buf as *const $ty))
+        };
+    }
+    match (size, signed) {
+        (IntSize::U1, true) => read!(i8),
+        (IntSize::U2, true) => read!(i16),
+        (IntSize::U4, true) => read!(i32),
+        (IntSize::U8, true) => read!(i64),
+        (IntSize::U16, true) => ptr::read_unaligned(buf as *const i128),
+        (IntSize::U1, false) => read!(u8),
+        (IntSize::U2, false) => read!(u16),
+        (IntSize::U4, false) => read!(u32),
+        (IntSize::U8, false) => read!(u64),
+        (IntSize::U16, false) => ptr::read_unaligned(buf as *const u128) as i128,
+    }
+}
+
+unsafe fn read_enum(buf: *const u8, enum_type: &EnumType) -> DynValue {
+    let value = read_int(buf, enum_type.size, enum_type.signed);
+    let bits = (enum_type.size as usize * 8).min(64);
+    let mask = if bits == 64 { !0 } else { (1_u64 << bits) - 1 };
+    let name = enum_type
+        .members
+        .iter()
+        .find(|m| m.value & mask == value as u64 & mask)
+        .map(|m| m.name.clone());
+    DynValue::Enum { name, value }
+}
+
+unsafe fn read_bitfield(buf: *const u8, bitfield: &BitfieldType) -> DynValue {
+    let bits = read_int(buf, bitfield.size, false) as u128;
+    let mask = if bitfield.precision >= 128 { !0 } else { (1_u128 << bitfield.precision) - 1 };
+    DynValue::UInt(bits.checked_shr(bitfield.offset as u32).unwrap_or(0) & mask)
+}
+
+unsafe fn read_compound(buf: *const u8, compound: &CompoundType) -> DynValue {
+    let mut fields: Vec<_> = compound.fields.iter().collect();
+    fields.sort_by_key(|f| f.index);
+    DynValue::Compound(
+        fields
+            .into_iter()
+            .map(|f| (f.name.clone(), DynValue::from_raw(&f.ty, buf.add(f.offset))))
+            .collect(),
+    )
+}
+
+unsafe fn read_array(buf: *const u8, ty: &TypeDescriptor, len: usize) -> DynValue {
+    DynValue::Array((0..len).map(|i| DynValue::from_raw(ty, buf.add(i * ty.size()))).collect())
+}
+
+unsafe fn read_fixed_string(buf: *const u8, len: usize) -> DynValue {
+    let bytes = slice::from_raw_parts(buf, len);
+    let bytes = bytes.iter().position(|&c| c == 0).map_or(bytes, |n| &bytes[..n]);
+    DynValue::String(String::from_utf8_lossy(bytes).into_owned())
+}
+
+unsafe fn read_varlen_string(buf: *const u8) -> DynValue {
+    let p = ptr::read_unaligned(buf as *const *const c_char);
+    if p.is_null() {
+        DynValue::String(String::new())
+    } else {
+        DynValue::String(CStr::from_ptr(p).to_string_lossy().into_owned())
+    }
+}
+
+impl DynValue {
+    /// Decodes a value of type `desc` stored in memory at `buf`.
+    ///
+    /// # Safety
+    ///
+    /// `buf` must point to `desc.size()` bytes laid out as `desc` describes (no
+    /// alignment is required); pointers to variable-length data must be valid.
+    pub unsafe fn from_raw(desc: &TypeDescriptor, buf: *const u8) -> Self {
+        use self::TypeDescriptor as TD;
+
+        match *desc {
+            TD::Integer(size) => DynValue::Int(read_int(buf, size, true)),
+            TD::Unsigned(IntSize::U16) => DynValue::UInt(ptr::read_unaligned(buf as *const u128)),
+            TD::Unsigned(size) => DynValue::UInt(read_int(buf, size, false) as u128),
+            TD::Float(FloatSize::U4) => {
+                DynValue::Float(f64::from(ptr::read_unaligned(buf as *const f32)))
+            }
+            TD::Float(FloatSize::U8) => DynValue::Float(ptr::read_unaligned(buf as *const f64)),
+            TD::Boolean => DynValue::Bool(*buf != 0),
+            TD::Enum(ref enum_type) => read_enum(buf, enum_type),
+            TD::Compound(ref compound) => read_compound(buf, compound),
+            TD::FixedArray(ref ty, len) => read_array(buf, ty, len),
+            TD::FixedAscii(len) | TD::FixedUnicode(len) => read_fixed_string(buf, len),
+            TD::VarLenArray(ref ty) => {
+                let vl = ptr::read_unaligned(buf as *const hvl_t);
+                read_array(vl.p as *const u8, ty, vl.len)
+            }
+            TD::VarLenAscii | TD::VarLenUnicode => read_varlen_string(buf),
+            TD::Bitfield(ref bitfield) => read_bitfield(buf, bitfield),
+            TD::Opaque(ref opaque) => DynValue::Opaque {
+                tag: opaque.tag.clone(),
+                data: slice::from_raw_parts(buf, opaque.size).to_vec(),
+            },
+            TD::Reference(ty) => {
+                DynValue::Reference { ty, data: slice::from_raw_parts(buf, ty.size()).to_vec() }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::DynValue;
+    use crate::array::VarLenArray;
+    use crate::h5type::H5Type;
+    use crate::string::{FixedAscii, VarLenUnicode};
+
+    unsafe fn decode<T: H5Type>(value: &T) -> DynValue {
+        DynValue::from_raw(&T::type_descriptor(), value as *const _ as *const u8)
+    }
+
+    #[test]
+    pub fn test_dyn_scalars() {
+        unsafe {
+            assert_eq!(decode(&-3_i8), DynValue::Int(-3));
+            assert_eq!(decode(&u64::max_value()), DynValue::UInt(u128::from(u64::max_value())));
+            assert_eq!(decode(&i128::min_value()), DynValue::Int(i128::min_value()));
+            assert_eq!(decode(&1.5_f32), DynValue::Float(1.5));
+            assert_eq!(decode(&true), DynValue::Bool(true));
+        }
+    }
+
+    #[test]
+    pub fn test_dyn_nested() {
+        let s: VarLenUnicode = "foo".parse().unwrap();
+        let value = (1_u16, [FixedAscii::<[_; 4]>::from_ascii(b"ab").unwrap(); 2], s);
+        let arr = VarLenArray::from_slice(&[0.5_f64, 2.0]);
+        unsafe {
+            assert_eq!(
+                decode(&value),
+                DynValue::Compound(vec![
+                    ("0".to_owned(), DynValue::UInt(1)),
+                    (
+                        "1".to_owned(),
+                        DynValue::Array(vec![
+                            DynValue::String("ab".to_owned()),
+                            DynValue::String("ab".to_owned())
+                        ])
+                    ),
+                    ("2".to_owned(), DynValue::String("foo".to_owned())),
+                ])
+            );
+            assert_eq!(
+                decode(&arr),
+                DynValue::Array(vec![DynValue::Float(0.5), DynValue::Float(2.0)])
+            );
+        }
+    }
+}
@@ -7,9 +7,9 @@ use crate::string::{FixedAscii, FixedUnicode, VarLenAscii, VarLenUnicode};
 
 #[allow(non_camel_case_types)]
 #[repr(C)]
-struct hvl_t {
-    len: usize,
-    p: *mut c_void,
+pub(crate) struct hvl_t {
+    pub len: usize,
+    pub p: *mut c_void,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -18,6 +18,12 @@ pub enum IntSize {
     U2 = 2,
     U4 = 4,
     U8 = 8,
+    /// 128-bit integers (`i128` / `u128`), stored as 16-byte integers with full precision.
+    ///
+    /// HDF5 itself converts between these and the other integer types, but many
+    /// readers (e.g. NumPy / h5py) have no 128-bit integer type and will not be able
+    /// to read such data natively.
+    U16 = 16,
 }
 
 impl IntSize {
@@ -30,6 +36,8 @@ impl IntSize {
             Some(IntSize::U4)
         } else if size == 8 {
             Some(IntSize::U8)
+        } else if size == 16 {
+            Some(IntSize::U16)
         } else {
             None
         }
@@ -138,6 +146,46 @@ impl CompoundType {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitfieldType {
+    pub size: IntSize,
+    /// Number of significant bits.
+    pub precision: usize,
+    /// Position of the first significant bit.
+    pub offset: usize,
+}
+
+impl BitfieldType {
+    /// Creates a bitfield type where all bits are significant.
+    pub fn new(size: IntSize) -> Self {
+        Self { size, precision: size as usize * 8, offset: 0 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpaqueType {
+    pub size: usize,
+    pub tag: String,
+}
+
+/// Kind of an HDF5 reference.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReferenceType {
+    /// Object reference (`hobj_ref_t`, 8 bytes).
+    Object,
+    /// Dataset region reference (`hdset_reg_ref_t`, 12 bytes).
+    DatasetRegion,
+}
+
+impl ReferenceType {
+    pub fn size(self) -> usize {
+        match self {
+            ReferenceType::Object => 8,
+            ReferenceType::DatasetRegion => 12,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum TypeDescriptor {
     Integer(IntSize),
@@ -152,6 +200,9 @@ pub enum TypeDescriptor {
     VarLenArray(Box<TypeDescriptor>),
     VarLenAscii,
     VarLenUnicode,
+    Opaque(OpaqueType),
+    Bitfield(BitfieldType),
+    Reference(ReferenceType),
 }
 
 impl TypeDescriptor {
@@ -168,6 +219,9 @@ impl TypeDescriptor {
             FixedAscii(len) | FixedUnicode(len) => len,
             VarLenArray(_) => mem::size_of::<hvl_t>(),
             VarLenAscii | VarLenUnicode => mem::size_of::<*const u8>(),
+            Opaque(ref opaque) => opaque.size,
+            Bitfield(ref bitfield) => bitfield.size as _,
+            Reference(ty) => ty.size(),
         }
     }
 
@@ -179,8 +233,9 @@ impl TypeDescriptor {
                 compound.fields.iter().map(|f| f.ty.c_alignment()).max().unwrap_or(1)
             }
             FixedArray(ref ty, _) => ty.c_alignment(),
-            FixedAscii(_) | FixedUnicode(_) => 1,
+            FixedAscii(_) | FixedUnicode(_) | Opaque(_) => 1,
             VarLenArray(_) => mem::size_of::<usize>(),
+            Reference(ReferenceType::DatasetRegion) => 1,
             _ => self.size(),
         }
     }
@@ -210,6 +265,33 @@ impl TypeDescriptor {
 
 pub unsafe trait H5Type: 'static {
     fn type_descriptor() -> TypeDescriptor;
+
+    /// Layout used when storing the type in a file, if it's specified explicitly (e.g. via
+    /// `#[hdf5(offset = ...)]` and `#[hdf5(size = ...)]` on derived compound types).
+    ///
+    /// By default (`None`), the C layout of `type_descriptor()` is stored, or the packed
+    /// layout if requested; members are matched by name when reading or writing.
+    #[inline]
+    fn stored_type_descriptor() -> Option<TypeDescriptor> {
+        None
+    }
+
+    /// Names of compound members which may be missing from the stored data.
+    #[doc(hidden)]
+    #[inline]
+    fn default_members() -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Initializes the fields of a value that were not read from the stored data: the
+    /// skipped fields, and the fields of `missing` members listed in `default_members()`.
+    ///
+    /// This is called for every compound value read, even if no conversion took place, so
+    /// skipped fields never hold stored bytes. The fields are overwritten without being
+    /// dropped.
+    #[doc(hidden)]
+    #[inline]
+    unsafe fn fill_defaults(_value: *mut Self, _missing: &[&str]) {}
 }
 
 macro_rules! impl_h5type {
@@ -231,6 +313,8 @@ impl_h5type!(u8, Unsigned, IntSize::U1);
 impl_h5type!(u16, Unsigned, IntSize::U2);
 impl_h5type!(u32, Unsigned, IntSize::U4);
 impl_h5type!(u64, Unsigned, IntSize::U8);
+impl_h5type!(i128, Integer, IntSize::U16);
+impl_h5type!(u128, Unsigned, IntSize::U16);
 impl_h5type!(f32, Float, FloatSize::U4);
 impl_h5type!(f64, Float, FloatSize::U8);
 
@@ -303,6 +387,25 @@ macro_rules! impl_tuple {
 
 impl_tuple! { A, B, C, D, E, F, G, H, I, J, K, L }
 
+/// Complex numbers are stored as a compound type with two fields, `r` and `i`, holding
+/// the real and the imaginary part respectively; this matches the convention used by
+/// h5py (NumPy complex types).
+#[cfg(feature = "num-complex")]
+unsafe impl<T: H5Type> H5Type for num_complex::Complex<T> {
+    #[inline]
+    fn type_descriptor() -> TypeDescriptor {
+        // `Complex<T>` is `#[repr(C)]`, so the imaginary part immediately follows the real one
+        let size = mem::size_of::<T>();
+        TypeDescriptor::Compound(CompoundType {
+            fields: vec![
+                CompoundField::typed::<T>("r", 0, 0),
+                CompoundField::typed::<T>("i", size, 1),
+            ],
+            size: mem::size_of::<Self>(),
+        })
+    }
+}
+
 unsafe impl<T: Array<Item = I>, I: H5Type> H5Type for T {
     #[inline]
     fn type_descriptor() -> TypeDescriptor {
@@ -367,6 +470,8 @@ pub mod tests {
         assert_eq!(u16::type_descriptor(), TD::Unsigned(IntSize::U2));
         assert_eq!(u32::type_descriptor(), TD::Unsigned(IntSize::U4));
         assert_eq!(u64::type_descriptor(), TD::Unsigned(IntSize::U8));
+        assert_eq!(i128::type_descriptor(), TD::Integer(IntSize::U16));
+        assert_eq!(u128::type_descriptor(), TD::Unsigned(IntSize::U16));
         assert_eq!(f32::type_descriptor(), TD::Float(FloatSize::U4));
         assert_eq!(f64::type_descriptor(), TD::Float(FloatSize::U8));
 
@@ -400,6 +505,9 @@ pub mod tests {
         type T = [u32; 256];
         assert_eq!(T::type_descriptor(), TD::FixedArray(Box::new(TD::Unsigned(IntSize::U4)), 256));
         assert_eq!(S::type_descriptor(), TD::FixedArray(Box::new(T::type_descriptor()), 4));
+        type U = [f32; 4097];
+        assert_eq!(U::type_descriptor(), TD::FixedArray(Box::new(TD::Float(FloatSize::U4)), 4097));
+        assert_eq!(U::type_descriptor().size(), 4 * 4097);
     }
 
     #[test]
@@ -458,6 +566,34 @@ pub mod tests {
         assert_eq!(mem::size_of::<T2>(), 16);
     }
 
+    #[test]
+    #[cfg(feature = "num-complex")]
+    pub fn test_complex() {
+        use num_complex::Complex;
+
+        assert_eq!(
+            Complex::<f32>::type_descriptor(),
+            TD::Compound(CompoundType {
+                fields: vec![
+                    CompoundField::typed::<f32>("r", 0, 0),
+                    CompoundField::typed::<f32>("i", 4, 1),
+                ],
+                size: 8,
+            })
+        );
+        assert_eq!(
+            Complex::<f64>::type_descriptor(),
+            TD::Compound(CompoundType {
+                fields: vec![
+                    CompoundField::typed::<f64>("r", 0, 0),
+                    CompoundField::typed::<f64>("i", 8, 1),
+                ],
+                size: 16,
+            })
+        );
+        assert_eq!(Complex::<f64>::type_descriptor().size(), mem::size_of::<Complex<f64>>());
+    }
+
     #[test]
     pub fn test_tuple_various_reprs() {
         type T = (i8, u64, f32, bool);
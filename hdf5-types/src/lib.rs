@@ -6,11 +6,27 @@
 extern crate quickcheck;
 
 mod array;
+mod bitfield;
+mod ddl;
+mod dyn_value;
 mod h5type;
+mod numpy;
+mod opaque;
+mod parse;
+mod reference;
 mod string;
 
 pub use self::array::{Array, VarLenArray};
+pub use self::bitfield::Bitfield;
+pub use self::dyn_value::DynValue;
 pub use self::h5type::{
-    CompoundField, CompoundType, EnumMember, EnumType, FloatSize, H5Type, IntSize, TypeDescriptor,
+    BitfieldType, CompoundField, CompoundType, EnumMember, EnumType, FloatSize, H5Type, IntSize,
+    OpaqueType, ReferenceType, TypeDescriptor,
 };
+pub use self::opaque::{FixedOpaque, OpaqueTag};
+pub use self::parse::TypeParseError;
+pub use self::reference::{ObjectReference, RegionReference};
 pub use self::string::{FixedAscii, FixedUnicode, StringError, VarLenAscii, VarLenUnicode};
+
+#[cfg(feature = "num-complex")]
+pub use num_complex::Complex;
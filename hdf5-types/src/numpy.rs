@@ -0,0 +1,291 @@
+//! Parsing NumPy dtype strings into type descriptors.
+
+use crate::h5type::{CompoundField, CompoundType, FloatSize, IntSize, TypeDescriptor as TD};
+use crate::parse::{Parser, Result};
+
+impl<'a> Parser<'a> {
+    /// Parses a shape such as `(2, 3)`, `(4,)` or `()`.
+    fn shape(&mut self) -> Result<Vec<usize>> {
+        self.expect('(')?;
+        let mut shape = Vec::new();
+        while !self.eat(')') {
+            shape.push(self.integer()?);
+            if !self.eat(',') {
+                self.expect(')')?;
+                break;
+            }
+        }
+        Ok(shape)
+    }
+
+    /// Parses a dtype: either a simple type string or a structured list of fields.
+    fn dtype(&mut self) -> Result<TD> {
+        match self.peek() {
+            Some('[') => self.structured(),
+            Some('(') => {
+                let shape = self.shape()?;
+                Ok(with_shape(self.simple()?, &shape))
+            }
+            _ => self.simple(),
+        }
+    }
+
+    /// Parses a typestr such as `<f8`, `|b1`, `S16` or `c16`.
+    fn simple(&mut self) -> Result<TD> {
+        let native = if cfg!(target_endian = "little") { '<' } else { '>' };
+        let order = match self.peek() {
+            Some(c @ '<') | Some(c @ '>') | Some(c @ '=') | Some(c @ '|') => {
+                self.pos += 1;
+                c
+            }
+            _ => '=',
+        };
+        let start = self.pos;
+        let kind = match self.input[self.pos..].chars().next() {
+            Some(kind) if kind.is_ascii_alphabetic() || kind == '?' => kind,
+            _ => return self.error("expected type code"),
+        };
+        self.pos += 1;
+        let size = match self.input[self.pos..].chars().next() {
+            Some(c) if c.is_ascii_digit() => Some(self.integer()?),
+            _ => None,
+        };
+        let int_size = |size: usize| IntSize::from_int(size).filter(|&s| s != IntSize::U16);
+        let desc = match (kind, size) {
+            ('?', None) | ('b', Some(1)) => Some(TD::Boolean),
+            ('i', Some(size)) => int_size(size).map(TD::Integer),
+            ('u', Some(size)) => int_size(size).map(TD::Unsigned),
+            ('f', Some(size)) => FloatSize::from_int(size).map(TD::Float),
+            ('c', Some(size)) => FloatSize::from_int(size / 2).filter(|_| size % 2 == 0).map(|f| {
+                let half = size / 2;
+                TD::Compound(CompoundType {
+                    fields: vec![
+                        CompoundField::new("r", TD::Float(f), 0, 0),
+                        CompoundField::new("i", TD::Float(f), half, 1),
+                    ],
+                    size,
+                })
+            }),
+            ('S', Some(len)) | ('a', Some(len)) if len > 0 => Some(TD::FixedAscii(len)),
+            _ => None,
+        };
+        let desc = match desc {
+            Some(desc) => desc,
+            None => {
+                self.pos = start;
+                return self.error(format!("unsupported type code: {:?}", &self.typestr()));
+            }
+        };
+        let byte_sized = desc.size() == 1 || kind == 'S' || kind == 'a';
+        if order != '=' && order != '|' && order != native && !byte_sized {
+            self.pos = start - 1;
+            return self.error("non-native byte order is not supported");
+        }
+        Ok(desc)
+    }
+
+    fn typestr(&self) -> &'a str {
+        let rest = &self.input[self.pos..];
+        &rest[..rest.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or_else(|| rest.len())]
+    }
+
+    /// Parses a structured dtype in the `dtype.descr` form, e.g. `[('a', '<i4'), ...]`.
+    ///
+    /// Fields are laid out back to back; unnamed void fields (`('', '|V4')`) are padding.
+    fn structured(&mut self) -> Result<TD> {
+        self.expect('[')?;
+        let (mut fields, mut offset) = (Vec::new(), 0);
+        while !self.eat(']') {
+            self.skip_whitespace();
+            let field_start = self.pos;
+            self.expect('(')?;
+            let name = self.quoted()?;
+            self.expect(',')?;
+            let padding = if name.is_empty() { self.padding()? } else { None };
+            let ty = match padding {
+                Some(size) => {
+                    offset += size;
+                    None
+                }
+                None if self.peek() == Some('[') => Some(self.structured()?),
+                None => {
+                    let typestr_pos = self.pos;
+                    let typestr = self.quoted()?;
+                    let mut parser = Parser::new(self.syntax, typestr);
+                    let ty = parser.dtype().and_then(|ty| parser.end().map(|_| ty));
+                    Some(ty.map_err(|err| err.offset_by(typestr_pos + 1))?)
+                }
+            };
+            if let Some(mut ty) = ty {
+                if self.eat(',') && self.peek() != Some(')') {
+                    let shape = match self.peek() {
+                        Some('(') => self.shape()?,
+                        _ => vec![self.integer()?],
+                    };
+                    ty = with_shape(ty, &shape);
+                }
+                if fields.iter().any(|f: &CompoundField| f.name == name) {
+                    self.pos = field_start;
+                    return self.error(format!("duplicate field name: {:?}", name));
+                }
+                let size = ty.size();
+                fields.push(CompoundField::new(name, ty, offset, fields.len()));
+                offset += size;
+            }
+            self.eat(',');
+            self.expect(')')?;
+            if !self.eat(',') {
+                self.expect(']')?;
+                break;
+            }
+        }
+        if fields.is_empty() {
+            return self.error("structured dtype has no fields");
+        }
+        Ok(TD::Compound(CompoundType { fields, size: offset }))
+    }
+
+    /// Parses the format of an unnamed field if it is a void type, returning its size.
+    fn padding(&mut self) -> Result<Option<usize>> {
+        let start = self.pos;
+        let typestr = self.quoted()?;
+        let typestr = typestr.trim_start_matches('|');
+        if let Some(Ok(size)) = typestr.strip_prefix('V').map(str::parse) {
+            return Ok(Some(size));
+        }
+        self.pos = start;
+        Ok(None)
+    }
+}
+
+fn with_shape(ty: TD, shape: &[usize]) -> TD {
+    shape.iter().rev().fold(ty, |ty, &len| TD::FixedArray(Box::new(ty), len))
+}
+
+impl TD {
+    /// Parses a NumPy dtype string into a type descriptor.
+    ///
+    /// Supported are the array-protocol type strings of booleans (`?`, `b1`), integers
+    /// (`i1` ... `i8`, `u1` ... `u8`), floats (`f4`, `f8`), complex numbers (`c8`, `c16`;
+    /// stored as compounds with `r` and `i` fields, as in h5py) and byte strings (`S16`),
+    /// optionally prefixed with a byte order (`<`, `>`, `=`, `|`) and a subarray shape
+    /// (`(2, 3)<f8`). Structured dtypes are given in the `dtype.descr` form, e.g.
+    /// `[('x', '<f8'), ('tags', 'S4', (2,))]`; unnamed void fields denote padding.
+    ///
+    /// Only the native byte order is supported for multi-byte types.
+    pub fn from_numpy_str(dtype: &str) -> Result<Self> {
+        let mut parser = Parser::new("numpy dtype", dtype);
+        let desc = parser.dtype()?;
+        parser.end()?;
+        Ok(desc)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::h5type::{CompoundField, CompoundType, FloatSize, IntSize, TypeDescriptor};
+    use crate::parse::TypeParseError;
+
+    fn parse(s: &str) -> TypeDescriptor {
+        TypeDescriptor::from_numpy_str(s).unwrap()
+    }
+
+    fn parse_err(s: &str) -> String {
+        TypeDescriptor::from_numpy_str(s).unwrap_err().to_string()
+    }
+
+    #[test]
+    pub fn test_numpy_simple() {
+        use self::TypeDescriptor as TD;
+
+        assert_eq!(parse("<f8"), TD::Float(FloatSize::U8));
+        assert_eq!(parse("=f4"), TD::Float(FloatSize::U4));
+        assert_eq!(parse("|b1"), TD::Boolean);
+        assert_eq!(parse("?"), TD::Boolean);
+        assert_eq!(parse("|i1"), TD::Integer(IntSize::U1));
+        assert_eq!(parse("<u2"), TD::Unsigned(IntSize::U2));
+        assert_eq!(parse("i8"), TD::Integer(IntSize::U8));
+        assert_eq!(parse("S16"), TD::FixedAscii(16));
+        assert_eq!(parse("|S3"), TD::FixedAscii(3));
+        assert_eq!(
+            parse("<c16"),
+            TD::Compound(CompoundType {
+                fields: vec![
+                    CompoundField::new("r", TD::Float(FloatSize::U8), 0, 0),
+                    CompoundField::new("i", TD::Float(FloatSize::U8), 8, 1),
+                ],
+                size: 16,
+            })
+        );
+        assert_eq!(
+            parse("(2, 3)<i4"),
+            TD::FixedArray(Box::new(TD::FixedArray(Box::new(TD::Integer(IntSize::U4)), 3)), 2)
+        );
+        assert_eq!(parse(" (4,)u1 "), TD::FixedArray(Box::new(TD::Unsigned(IntSize::U1)), 4));
+    }
+
+    #[test]
+    pub fn test_numpy_structured() {
+        use self::TypeDescriptor as TD;
+
+        assert_eq!(
+            parse("[('a', '<i4'), ('', '|V4'), (\"b\", '<f8'), ('c', 'S2', (2,)), ('d', '?', 3)]"),
+            TD::Compound(CompoundType {
+                fields: vec![
+                    CompoundField::new("a", TD::Integer(IntSize::U4), 0, 0),
+                    CompoundField::new("b", TD::Float(FloatSize::U8), 8, 1),
+                    CompoundField::new("c", TD::FixedArray(Box::new(TD::FixedAscii(2)), 2), 16, 2),
+                    CompoundField::new("d", TD::FixedArray(Box::new(TD::Boolean), 3), 20, 3),
+                ],
+                size: 23,
+            })
+        );
+        assert_eq!(
+            parse("[('p', [('x', '<f4'), ('y', '<f4')]), ('n', '|u1')]"),
+            TD::Compound(CompoundType {
+                fields: vec![
+                    CompoundField::new(
+                        "p",
+                        TD::Compound(CompoundType {
+                            fields: vec![
+                                CompoundField::new("x", TD::Float(FloatSize::U4), 0, 0),
+                                CompoundField::new("y", TD::Float(FloatSize::U4), 4, 1),
+                            ],
+                            size: 8,
+                        }),
+                        0,
+                        0
+                    ),
+                    CompoundField::new("n", TD::Unsigned(IntSize::U1), 8, 1),
+                ],
+                size: 9,
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_numpy_errors() {
+        let non_native = if cfg!(target_endian = "little") { ">f8" } else { "<f8" };
+        assert_eq!(
+            parse_err(non_native),
+            "invalid numpy dtype (at 0): non-native byte order is not supported"
+        );
+        assert_eq!(parse("|u1"), parse(if non_native == ">f8" { ">u1" } else { "<u1" }));
+        assert_eq!(parse_err("<f2"), "invalid numpy dtype (at 1): unsupported type code: \"f2\"");
+        assert_eq!(parse_err("<U8"), "invalid numpy dtype (at 1): unsupported type code: \"U8\"");
+        assert_eq!(parse_err("S0"), "invalid numpy dtype (at 0): unsupported type code: \"S0\"");
+        assert_eq!(parse_err("<f8x"), "invalid numpy dtype (at 3): unexpected trailing characters");
+        assert_eq!(parse_err("[]"), "invalid numpy dtype (at 2): structured dtype has no fields");
+        assert_eq!(
+            parse_err("[('a', '<f8'), ('a', '<i4')]"),
+            "invalid numpy dtype (at 15): duplicate field name: \"a\""
+        );
+        assert_eq!(
+            parse_err("[('a', '<q8')]"),
+            "invalid numpy dtype (at 9): unsupported type code: \"q8\""
+        );
+        assert_eq!(parse_err("[('a', 'f8'"), "invalid numpy dtype (at 11): expected ')'");
+        let err: TypeParseError = TypeDescriptor::from_numpy_str("[('a'").unwrap_err();
+        assert_eq!(err.position(), 5);
+    }
+}
@@ -0,0 +1,98 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::slice;
+
+use crate::array::Array;
+use crate::h5type::{H5Type, OpaqueType, TypeDescriptor};
+
+/// Tag identifying the contents of an opaque datatype.
+///
+/// HDF5 only converts between opaque types with identical tags, so the tag acts as a
+/// self-describing label of the stored blobs (e.g. `"application/x-protobuf"`).
+pub trait OpaqueTag: 'static {
+    /// Tag string; must be non-empty and shorter than 256 bytes.
+    const TAG: &'static str;
+}
+
+/// Fixed-size opaque blob stored as an HDF5 opaque type tagged with `T::TAG`.
+///
+/// The blob size is determined by the byte array type `A`, e.g. `[u8; 16]`.
+#[repr(transparent)]
+pub struct FixedOpaque<A: Array<Item = u8>, T: OpaqueTag> {
+    data: A,
+    tag: PhantomData<T>,
+}
+
+impl<A: Array<Item = u8>, T: OpaqueTag> FixedOpaque<A, T> {
+    pub fn new(data: A) -> Self {
+        Self { data, tag: PhantomData }
+    }
+
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.data.as_ptr(), A::capacity()) }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> A {
+        self.data
+    }
+
+    #[inline]
+    pub fn tag() -> &'static str {
+        T::TAG
+    }
+}
+
+impl<A: Array<Item = u8> + Clone, T: OpaqueTag> Clone for FixedOpaque<A, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.data.clone())
+    }
+}
+
+impl<A: Array<Item = u8> + Copy, T: OpaqueTag> Copy for FixedOpaque<A, T> {}
+
+impl<A: Array<Item = u8>, T: OpaqueTag> PartialEq for FixedOpaque<A, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<A: Array<Item = u8>, T: OpaqueTag> Eq for FixedOpaque<A, T> {}
+
+impl<A: Array<Item = u8>, T: OpaqueTag> fmt::Debug for FixedOpaque<A, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FixedOpaque").field("tag", &T::TAG).field("data", &self.as_bytes()).finish()
+    }
+}
+
+unsafe impl<A: Array<Item = u8>, T: OpaqueTag> H5Type for FixedOpaque<A, T> {
+    #[inline]
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::Opaque(OpaqueType { size: A::capacity(), tag: T::TAG.to_owned() })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{FixedOpaque, OpaqueTag};
+    use crate::h5type::{H5Type, OpaqueType, TypeDescriptor as TD};
+
+    struct Blob;
+
+    impl OpaqueTag for Blob {
+        const TAG: &'static str = "blob";
+    }
+
+    #[test]
+    pub fn test_fixed_opaque() {
+        type T = FixedOpaque<[u8; 3], Blob>;
+        assert_eq!(T::type_descriptor(), TD::Opaque(OpaqueType { size: 3, tag: "blob".into() }));
+        let x = T::new([1, 2, 3]);
+        assert_eq!(x.as_bytes(), &[1, 2, 3]);
+        assert_eq!(x, x.clone());
+        assert_eq!(T::tag(), "blob");
+        assert_eq!(format!("{:?}", x), "FixedOpaque { tag: \"blob\", data: [1, 2, 3] }");
+        assert_eq!(x.into_inner(), [1, 2, 3]);
+    }
+}
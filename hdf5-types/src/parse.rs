@@ -0,0 +1,122 @@
+//! Cursor shared by the parsers of textual datatype descriptions.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+/// Error returned when parsing a textual datatype description fails, see
+/// `TypeDescriptor::from_numpy_str()` and `TypeDescriptor::from_ddl_str()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeParseError {
+    syntax: &'static str,
+    message: String,
+    position: usize,
+}
+
+impl TypeParseError {
+    /// Returns the byte offset into the parsed string at which parsing failed.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub(crate) fn offset_by(mut self, offset: usize) -> Self {
+        self.position += offset;
+        self
+    }
+}
+
+impl StdError for TypeParseError {}
+
+impl fmt::Display for TypeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid {} (at {}): {}", self.syntax, self.position, self.message)
+    }
+}
+
+pub(crate) type Result<T> = ::std::result::Result<T, TypeParseError>;
+
+pub(crate) struct Parser<'a> {
+    pub syntax: &'static str,
+    pub input: &'a str,
+    pub pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(syntax: &'static str, input: &'a str) -> Self {
+        Self { syntax, input, pos: 0 }
+    }
+
+    pub fn error<T>(&self, message: impl Into<String>) -> Result<T> {
+        Err(TypeParseError { syntax: self.syntax, message: message.into(), position: self.pos })
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    pub fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.input[self.pos..].chars().next()
+    }
+
+    pub fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expect(&mut self, c: char) -> Result<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            self.error(format!("expected {:?}", c))
+        }
+    }
+
+    /// Consumes a run of characters matching `pred` (possibly empty).
+    pub fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        self.skip_whitespace();
+        let rest = &self.input[self.pos..];
+        let len = rest.find(|c: char| !pred(c)).unwrap_or_else(|| rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    pub fn integer(&mut self) -> Result<usize> {
+        let start = self.pos;
+        match self.take_while(|c| c.is_ascii_digit()).parse() {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                self.pos = start;
+                self.skip_whitespace();
+                self.error("expected integer")
+            }
+        }
+    }
+
+    pub fn quoted(&mut self) -> Result<&'a str> {
+        let quote = match self.peek() {
+            Some(c @ '\'') | Some(c @ '"') => c,
+            _ => return self.error("expected quoted string"),
+        };
+        let start = self.pos + 1;
+        match self.input[start..].find(quote) {
+            Some(len) => {
+                self.pos = start + len + 1;
+                Ok(&self.input[start..start + len])
+            }
+            None => self.error("unterminated string"),
+        }
+    }
+
+    pub fn end(&mut self) -> Result<()> {
+        if self.peek().is_some() {
+            self.error("unexpected trailing characters")
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::h5type::{H5Type, ReferenceType, TypeDescriptor};
+
+/// Object reference (`hobj_ref_t`) to a group, dataset or named datatype.
+///
+/// References are only meaningful within the file they were created in; a zeroed
+/// (default) reference doesn't point to any object.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ObjectReference(u64);
+
+impl ObjectReference {
+    /// Creates a reference from the object address it contains.
+    #[inline]
+    pub fn from_raw(addr: u64) -> Self {
+        Self(addr)
+    }
+
+    /// Returns the object address contained in the reference.
+    #[inline]
+    pub fn into_raw(self) -> u64 {
+        self.0
+    }
+
+    #[inline]
+    pub fn is_null(self) -> bool {
+        self.0 == 0
+    }
+}
+
+unsafe impl H5Type for ObjectReference {
+    #[inline]
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::Reference(ReferenceType::Object)
+    }
+}
+
+/// Dataset region reference (`hdset_reg_ref_t`) to a selection within a dataset.
+///
+/// References are only meaningful within the file they were created in; a zeroed
+/// (default) reference doesn't point to any region.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct RegionReference([u8; 12]);
+
+impl RegionReference {
+    /// Creates a reference from its raw bytes.
+    #[inline]
+    pub fn from_raw(bytes: [u8; 12]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the raw bytes of the reference.
+    #[inline]
+    pub fn into_raw(self) -> [u8; 12] {
+        self.0
+    }
+
+    #[inline]
+    pub fn is_null(self) -> bool {
+        self.0 == [0; 12]
+    }
+}
+
+unsafe impl H5Type for RegionReference {
+    #[inline]
+    fn type_descriptor() -> TypeDescriptor {
+        TypeDescriptor::Reference(ReferenceType::DatasetRegion)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::mem;
+
+    use super::{ObjectReference, RegionReference};
+    use crate::h5type::{H5Type, ReferenceType, TypeDescriptor as TD};
+
+    #[test]
+    pub fn test_references() {
+        assert_eq!(ObjectReference::type_descriptor(), TD::Reference(ReferenceType::Object));
+        assert_eq!(RegionReference::type_descriptor(), TD::Reference(ReferenceType::DatasetRegion));
+        assert_eq!(mem::size_of::<RegionReference>(), RegionReference::type_descriptor().size());
+        assert!(ObjectReference::default().is_null());
+        assert!(!ObjectReference::from_raw(800).is_null());
+        assert_eq!(RegionReference::from_raw([1; 12]).into_raw(), [1; 12]);
+
+        type T = (u8, ObjectReference, RegionReference);
+        assert_eq!(mem::size_of::<T>(), T::type_descriptor().size());
+    }
+}
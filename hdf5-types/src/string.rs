@@ -17,6 +17,7 @@ pub enum StringError {
     InternalNull,
     InsufficientCapacity,
     AsciiError(AsAsciiStrError),
+    Utf8Error(str::Utf8Error),
     #[doc(hidden)]
     __Incomplete,
 }
@@ -27,12 +28,19 @@ impl From<AsAsciiStrError> for StringError {
     }
 }
 
+impl From<str::Utf8Error> for StringError {
+    fn from(err: str::Utf8Error) -> Self {
+        StringError::Utf8Error(err)
+    }
+}
+
 impl StdError for StringError {
     fn description(&self) -> &str {
         match self {
             StringError::InternalNull => "variable length string with internal null",
             StringError::InsufficientCapacity => "insufficient capacity for fixed sized string",
             StringError::AsciiError(err) => err.description(),
+            StringError::Utf8Error(_) => "invalid utf-8 in fixed sized string",
             _ => "",
         }
     }
@@ -444,6 +452,30 @@ impl<A: Array<Item = u8>> FixedAscii<A> {
         let s = AsciiStr::from_ascii(bytes)?;
         unsafe { Ok(Self::from_bytes(s.as_bytes())) }
     }
+
+    /// Creates a string replacing non-ASCII bytes with `?`, truncated to the capacity.
+    pub fn from_ascii_lossy<B: ?Sized + AsRef<[u8]>>(bytes: &B) -> Self {
+        let bytes: Vec<u8> = bytes
+            .as_ref()
+            .iter()
+            .take(A::capacity())
+            .map(|&c| if c.is_ascii() { c } else { b'?' })
+            .collect();
+        unsafe { Self::from_bytes(&bytes) }
+    }
+
+    /// Returns the contents as `&str`, failing if they are not valid UTF-8.
+    ///
+    /// HDF5 doesn't validate the contents of strings, so files written by other producers
+    /// may contain arbitrary bytes even in ASCII strings.
+    pub fn try_as_str(&self) -> Result<&str, StringError> {
+        Ok(str::from_utf8(self.as_bytes())?)
+    }
+
+    /// Returns the contents replacing invalid UTF-8 sequences with `U+FFFD`.
+    pub fn to_str_lossy(&self) -> Cow<str> {
+        String::from_utf8_lossy(self.as_bytes())
+    }
 }
 
 impl<A: Array<Item = u8>> AsAsciiStr for FixedAscii<A> {
@@ -534,6 +566,25 @@ impl<A: Array<Item = u8>> FixedUnicode<A> {
     pub unsafe fn from_str_unchecked<S: Borrow<str>>(s: S) -> Self {
         Self::from_bytes(s.borrow().as_bytes())
     }
+
+    /// Creates a string truncated to the capacity at a character boundary if needed.
+    pub fn from_str_lossy(s: &str) -> Self {
+        let mut len = s.len().min(A::capacity());
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        unsafe { Self::from_bytes(&s.as_bytes()[..len]) }
+    }
+
+    /// Returns the contents as `&str`, failing if they are not valid UTF-8.
+    pub fn try_as_str(&self) -> Result<&str, StringError> {
+        Ok(str::from_utf8(self.as_bytes())?)
+    }
+
+    /// Returns the contents replacing invalid UTF-8 sequences with `U+FFFD`.
+    pub fn to_str_lossy(&self) -> Cow<str> {
+        String::from_utf8_lossy(self.as_bytes())
+    }
 }
 
 impl<A> FromStr for FixedUnicode<A>
@@ -659,6 +710,21 @@ pub mod tests {
         assert!(U::from_str("\0\0\0").unwrap().is_empty());
     }
 
+    #[test]
+    pub fn test_lossy_strict() {
+        type A = FixedAscii<[u8; 4]>;
+        type U = FixedUnicode<[u8; 4]>;
+        assert_eq!(A::from_ascii_lossy("a\u{e9}bcd").as_str(), "a??b");
+        assert_eq!(A::from_ascii_lossy("ab").as_str(), "ab");
+        assert_eq!(U::from_str_lossy("ab\u{e9}\u{e9}").as_str(), "ab\u{e9}");
+        assert_eq!(U::from_str_lossy("\u{20ac}").as_str(), "\u{20ac}");
+        assert!(U::from_str("ab\u{e9}\u{e9}").is_err());
+        let invalid = unsafe { A::from_ascii_unchecked(b"a\xffb") };
+        assert_eq!(invalid.to_str_lossy(), "a\u{fffd}b");
+        assert!(invalid.try_as_str().is_err());
+        assert_eq!(A::from_ascii("abc").unwrap().try_as_str(), Ok("abc"));
+    }
+
     macro_rules! test_default {
         ($test_name:ident, $ty:ident) => {
             #[test]
@@ -0,0 +1,199 @@
+//! Reusable scratch buffers for staging reads and writes.
+//!
+//! Readers and writers stage data in temporary buffers whenever it can't be transferred
+//! to or from the caller's memory directly: runtime-typed and string reads, and writes of
+//! compound values whose padding has to be zeroed first. Instead of being freed, released
+//! buffers are kept in a per-thread pool and handed out again, so repeated small reads
+//! and writes in a loop don't allocate each time.
+//!
+//! The pool of each thread retains at most `capacity()` bytes (1 MiB by default); larger
+//! buffers are freed as usual. Setting the capacity to zero disables pooling.
+
+use std::cell::RefCell;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Default number of bytes retained by the pool of each thread.
+pub const DEFAULT_CAPACITY: usize = 1 << 20;
+
+const WORD: usize = mem::size_of::<u64>();
+
+static CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_CAPACITY);
+
+#[derive(Default)]
+struct Pool {
+    free: Vec<Vec<u64>>,
+    retained: usize,
+}
+
+impl Pool {
+    fn take(&mut self, words: usize) -> Option<Vec<u64>> {
+        let index = (0..self.free.len())
+            .filter(|&i| self.free[i].capacity() >= words)
+            .min_by_key(|&i| self.free[i].capacity())?;
+        let buf = self.free.swap_remove(index);
+        self.retained -= buf.capacity() * WORD;
+        Some(buf)
+    }
+
+    fn give(&mut self, buf: Vec<u64>) {
+        let bytes = buf.capacity() * WORD;
+        if bytes != 0 && self.retained + bytes <= capacity() {
+            self.retained += bytes;
+            self.free.push(buf);
+        }
+    }
+
+    fn trim(&mut self) {
+        let capacity = capacity();
+        while self.retained > capacity {
+            let buf = self.free.pop().unwrap();
+            self.retained -= buf.capacity() * WORD;
+        }
+    }
+}
+
+thread_local! {
+    static POOL: RefCell<Pool> = RefCell::new(Pool::default());
+}
+
+/// Sets the maximum number of bytes retained by the pool of each thread, releasing
+/// buffers of the current thread beyond it; zero disables pooling.
+pub fn set_capacity(bytes: usize) {
+    CAPACITY.store(bytes, Ordering::SeqCst);
+    POOL.with(|pool| pool.borrow_mut().trim());
+}
+
+/// Returns the maximum number of bytes retained by the pool of each thread.
+pub fn capacity() -> usize {
+    CAPACITY.load(Ordering::SeqCst)
+}
+
+/// Returns the number of bytes currently retained by the pool of the current thread.
+pub fn retained() -> usize {
+    POOL.with(|pool| pool.borrow().retained)
+}
+
+/// Releases all buffers retained by the pool of the current thread.
+pub fn clear() {
+    POOL.with(|pool| *pool.borrow_mut() = Pool::default());
+}
+
+/// A zero-initialized byte buffer taken from the pool of the current thread, which is
+/// returned to it when dropped.
+pub(crate) struct Scratch {
+    buf: Vec<u64>,
+    offset: usize,
+    len: usize,
+}
+
+impl Scratch {
+    /// Returns a zeroed buffer of `len` bytes, aligned for any primitive type.
+    pub fn zeroed(len: usize) -> Self {
+        Self::zeroed_aligned(len, WORD)
+    }
+
+    /// Returns a zeroed buffer of `len` bytes starting at a multiple of `align`.
+    pub fn zeroed_aligned(len: usize, align: usize) -> Self {
+        let extra = if align > WORD { align - 1 } else { 0 };
+        let words = (len + extra + WORD - 1) / WORD;
+        let mut buf = POOL.with(|pool| pool.borrow_mut().take(words)).unwrap_or_default();
+        buf.clear();
+        buf.resize(words, 0);
+        let offset = buf.as_ptr().cast::<u8>().align_offset(align.max(1));
+        Self { buf, offset, len }
+    }
+}
+
+impl Deref for Scratch {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.buf.as_ptr().cast::<u8>().add(self.offset), self.len) }
+    }
+}
+
+impl DerefMut for Scratch {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe {
+            slice::from_raw_parts_mut(self.buf.as_mut_ptr().cast::<u8>().add(self.offset), self.len)
+        }
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let buf = mem::take(&mut self.buf);
+        // the pool may already be gone if this runs while the thread shuts down
+        let _ = POOL.try_with(|pool| pool.borrow_mut().give(buf));
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{capacity, clear, retained, set_capacity, Scratch, DEFAULT_CAPACITY};
+    use crate::internal_prelude::*;
+    use crate::types::VarLenUnicode;
+
+    #[test]
+    pub fn test_scratch_pool() {
+        clear();
+        let first = Scratch::zeroed(100);
+        assert_eq!(first.len(), 100);
+        assert!(first.iter().all(|&b| b == 0));
+        let ptr = first.as_ptr();
+        drop(first);
+        assert_eq!(retained(), 104);
+
+        let mut second = Scratch::zeroed(20);
+        assert_eq!(second.as_ptr(), ptr);
+        assert_eq!(retained(), 0);
+        second[3] = 1;
+        drop(second);
+        assert!(Scratch::zeroed(100).iter().all(|&b| b == 0));
+
+        let aligned = Scratch::zeroed_aligned(10, 64);
+        assert_eq!(aligned.as_ptr() as usize % 64, 0);
+        assert_eq!(aligned.len(), 10);
+        drop(aligned);
+        clear();
+        assert_eq!(retained(), 0);
+
+        with_tmp_file(|file| {
+            let ds = file.new_dataset::<u16>().create("x", 4).unwrap();
+            ds.write(&[1_u16, 2, 3, 4]).unwrap();
+            let strings = file.new_dataset::<VarLenUnicode>().create("s", 2).unwrap();
+            strings.write(&["a".parse::<VarLenUnicode>().unwrap(), "bc".parse().unwrap()]).unwrap();
+
+            clear();
+            let values = ds.as_reader().read_dyn_array().unwrap();
+            assert_eq!(values.len(), 4);
+            let held = retained();
+            assert!(held > 0);
+            ds.as_reader().read_dyn_array().unwrap();
+            assert_eq!(retained(), held);
+
+            let arena = strings.as_reader().read_string_arena().unwrap();
+            assert_eq!(arena.iter().collect::<Vec<_>>(), vec!["a", "bc"]);
+            clear();
+        });
+
+        // everything is kept in one test since the capacity is shared by all threads
+        clear();
+        assert_eq!(capacity(), DEFAULT_CAPACITY);
+        drop(Scratch::zeroed(DEFAULT_CAPACITY + 1));
+        assert_eq!(retained(), 0);
+
+        drop((Scratch::zeroed(64), Scratch::zeroed(64)));
+        assert_eq!(retained(), 128);
+        set_capacity(64);
+        assert_eq!(retained(), 64);
+        set_capacity(0);
+        assert_eq!(retained(), 0);
+        drop(Scratch::zeroed(8));
+        assert_eq!(retained(), 0);
+        set_capacity(DEFAULT_CAPACITY);
+    }
+}
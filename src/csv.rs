@@ -0,0 +1,449 @@
+//! CSV export and import of tabular (one-dimensional compound) datasets.
+//!
+//! `Dataset::export_csv()` writes one line per element of the dataset and one column per
+//! compound field, preceded by a header line with the field names. `Group::import_csv()`
+//! creates a compound dataset from CSV data, with a field per column; column types can be
+//! specified by name and are otherwise inferred from the values:
+//!
+//! - `bool` if all values are `true` or `false`,
+//! - `i64` if all values are integers,
+//! - `f64` if all values are numbers, with empty values stored as `NaN`,
+//! - variable-length UTF-8 strings otherwise.
+//!
+//! Fields are quoted as described in RFC 4180 when they contain the delimiter, quotes or
+//! line breaks. Only fields of scalar types (numbers, booleans, enums and strings) can be
+//! exported; enum values are written as member names where possible.
+
+use std::ffi::CString;
+use std::io::{Read, Write};
+
+use hdf5_sys::h5d::{H5Dcreate2, H5Dwrite};
+use hdf5_types::{CompoundField, CompoundType, DynValue, FloatSize, IntSize, TypeDescriptor as TD};
+
+use crate::describe::type_name;
+use crate::internal_prelude::*;
+
+/// Options for `Dataset::export_csv()` and `Group::import_csv()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvOptions {
+    /// Field delimiter (default: `,`).
+    pub delimiter: char,
+    /// Whether the first line holds the column names (default: `true`). Without a header,
+    /// imported columns are named `column0`, `column1` and so on.
+    pub header: bool,
+    /// Types of imported columns by name; the types of other columns are inferred.
+    pub column_types: Vec<(String, TD)>,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self { delimiter: ',', header: true, column_types: Vec::new() }
+    }
+}
+
+fn csv_fields(dataset: &Dataset) -> Result<Vec<CompoundField>> {
+    ensure!(dataset.ndim() == 1, "CSV export requires a one-dimensional dataset");
+    match dataset.dtype()?.to_descriptor()? {
+        TD::Compound(compound) => {
+            let mut fields = compound.fields;
+            fields.sort_by_key(|f| f.index);
+            for field in &fields {
+                ensure!(
+                    is_scalar(&field.ty),
+                    "field '{}' of type {} can't be exported to CSV",
+                    field.name,
+                    type_name(&field.ty)
+                );
+            }
+            Ok(fields)
+        }
+        dtype => fail!("CSV export requires a compound datatype, got {}", type_name(&dtype)),
+    }
+}
+
+fn is_scalar(ty: &TD) -> bool {
+    matches!(
+        ty,
+        TD::Integer(_)
+            | TD::Unsigned(_)
+            | TD::Float(_)
+            | TD::Boolean
+            | TD::Enum(_)
+            | TD::FixedAscii(_)
+            | TD::FixedUnicode(_)
+            | TD::VarLenAscii
+            | TD::VarLenUnicode
+    )
+}
+
+fn format_field(value: &DynValue, ty: &TD) -> String {
+    match value {
+        DynValue::Float(v) if *ty == TD::Float(FloatSize::U4) => format!("{:?}", *v as f32),
+        DynValue::Float(v) => format!("{:?}", v),
+        DynValue::Enum { name: Some(name), .. } | DynValue::String(name) => name.clone(),
+        DynValue::Enum { name: None, value: v } | DynValue::Int(v) => v.to_string(),
+        DynValue::UInt(v) => v.to_string(),
+        DynValue::Bool(v) => v.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn write_field(line: &mut String, field: &str, delimiter: char) {
+    if field.contains(|c| c == delimiter || c == '"' || c == '\n' || c == '\r') {
+        line.push('"');
+        line.push_str(&field.replace('"', "\"\""));
+        line.push('"');
+    } else {
+        line.push_str(field);
+    }
+}
+
+fn write_line<W: Write>(writer: &mut W, fields: &[String], delimiter: char) -> Result<()> {
+    let mut line = String::new();
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            line.push(delimiter);
+        }
+        write_field(&mut line, field, delimiter);
+    }
+    line.push('\n');
+    writer.write_all(line.as_bytes()).map_err(|err| format!("unable to write CSV: {}", err))?;
+    Ok(())
+}
+
+/// Splits CSV text into records, returning each with the line number it starts at.
+fn parse_records(text: &str, delimiter: char) -> Result<Vec<(usize, Vec<String>)>> {
+    let mut records = Vec::new();
+    let (mut record, mut field) = (Vec::new(), String::new());
+    let (mut line, mut start_line) = (1, 1);
+    let (mut quoted, mut was_quoted) = (false, false);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                _ => {
+                    line += (c == '\n') as usize;
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() && !was_quoted => {
+                quoted = true;
+                was_quoted = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                if !record.is_empty() || !field.is_empty() || was_quoted {
+                    record.push(field.split_off(0));
+                    records.push((start_line, record.split_off(0)));
+                }
+                was_quoted = false;
+                line += 1;
+                start_line = line;
+            }
+            c if c == delimiter => {
+                record.push(field.split_off(0));
+                was_quoted = false;
+            }
+            _ => {
+                ensure!(!was_quoted, "line {}: unexpected characters after quoted field", line);
+                field.push(c);
+            }
+        }
+    }
+    ensure!(!quoted, "line {}: unterminated quoted field", start_line);
+    if !record.is_empty() || !field.is_empty() || was_quoted {
+        record.push(field);
+        records.push((start_line, record));
+    }
+    Ok(records)
+}
+
+fn infer_type(values: &[&str]) -> TD {
+    let non_empty: Vec<&str> = values.iter().map(|v| v.trim()).filter(|v| !v.is_empty()).collect();
+    let all = |f: &dyn Fn(&str) -> bool| non_empty.iter().all(|v| f(v));
+    let complete = non_empty.len() == values.len();
+    if non_empty.is_empty() {
+        TD::VarLenUnicode
+    } else if complete && all(&|v| v == "true" || v == "false") {
+        TD::Boolean
+    } else if complete && all(&|v| v.parse::<i64>().is_ok()) {
+        TD::Integer(IntSize::U8)
+    } else if all(&|v| v.parse::<f64>().is_ok()) {
+        TD::Float(FloatSize::U8)
+    } else {
+        TD::VarLenUnicode
+    }
+}
+
+macro_rules! write_int {
+    ($buf:expr, $value:expr, $size:expr, $($variant:ident => $ty:ty),*) => {
+        match $size {
+            $(IntSize::$variant => {
+                let converted = <$ty as std::convert::TryFrom<_>>::try_from($value).ok()?;
+                write_bytes($buf, &converted.to_ne_bytes());
+            })*
+        }
+    };
+}
+
+fn write_bytes(buf: &mut [u8], bytes: &[u8]) {
+    buf[..bytes.len()].copy_from_slice(bytes);
+}
+
+/// Encodes a CSV value into `buf`, returning `None` if it isn't valid for the type.
+/// Variable-length strings are stored as pointers into `strings`.
+fn encode(text: &str, ty: &TD, buf: &mut [u8], strings: &mut Vec<CString>) -> Option<()> {
+    let number = text.trim();
+    match ty {
+        TD::Integer(size) => {
+            let int: i128 = number.parse().ok()?;
+            write_int!(buf, int, size, U1 => i8, U2 => i16, U4 => i32, U8 => i64, U16 => i128);
+        }
+        TD::Unsigned(size) => {
+            let uint: u128 = number.parse().ok()?;
+            write_int!(buf, uint, size, U1 => u8, U2 => u16, U4 => u32, U8 => u64, U16 => u128);
+        }
+        TD::Float(FloatSize::U4) => {
+            let float = if number.is_empty() { f32::NAN } else { number.parse().ok()? };
+            write_bytes(buf, &float.to_ne_bytes());
+        }
+        TD::Float(FloatSize::U8) => {
+            let float = if number.is_empty() { f64::NAN } else { number.parse().ok()? };
+            write_bytes(buf, &float.to_ne_bytes());
+        }
+        TD::Boolean => buf[0] = number.parse::<bool>().ok()? as u8,
+        TD::FixedAscii(len) | TD::FixedUnicode(len) => {
+            if text.len() > *len || (*ty == TD::FixedAscii(*len) && !text.is_ascii()) {
+                return None;
+            }
+            write_bytes(buf, text.as_bytes());
+        }
+        TD::VarLenAscii | TD::VarLenUnicode => {
+            if *ty == TD::VarLenAscii && !text.is_ascii() {
+                return None;
+            }
+            let string = CString::new(text).ok()?;
+            write_bytes(buf, &(string.as_ptr() as usize).to_ne_bytes());
+            strings.push(string);
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+impl Dataset {
+    /// Writes a one-dimensional compound dataset as CSV, see the module documentation.
+    pub fn export_csv<W: Write>(&self, mut writer: W, options: &CsvOptions) -> Result<()> {
+        let fields = csv_fields(self)?;
+        if options.header {
+            let names: Vec<_> = fields.iter().map(|f| f.name.clone()).collect();
+            write_line(&mut writer, &names, options.delimiter)?;
+        }
+        for record in self.read_dyn_array()?.iter() {
+            let values = match record {
+                DynValue::Compound(values) => values,
+                _ => fail!("unexpected non-compound value"),
+            };
+            let line: Vec<_> = values
+                .iter()
+                .zip(&fields)
+                .map(|((_, value), field)| format_field(value, &field.ty))
+                .collect();
+            write_line(&mut writer, &line, options.delimiter)?;
+        }
+        writer.flush().map_err(|err| format!("unable to write CSV: {}", err))?;
+        Ok(())
+    }
+
+    /// Returns the dataset as CSV text, see `export_csv()`.
+    pub fn to_csv(&self, options: &CsvOptions) -> Result<String> {
+        let mut buf = Vec::new();
+        self.export_csv(&mut buf, options)?;
+        String::from_utf8(buf).map_err(|err| err.to_string().into())
+    }
+}
+
+impl Group {
+    /// Creates a one-dimensional compound dataset from CSV data, see the module
+    /// documentation.
+    pub fn import_csv<R: Read>(
+        &self, name: &str, mut reader: R, options: &CsvOptions,
+    ) -> Result<Dataset> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).map_err(|err| format!("unable to read CSV: {}", err))?;
+        let mut records = parse_records(&text, options.delimiter)?;
+        let names = if options.header {
+            ensure!(!records.is_empty(), "CSV header is missing");
+            records.remove(0).1
+        } else {
+            let ncols = records.first().map_or(0, |(_, record)| record.len());
+            (0..ncols).map(|i| format!("column{}", i)).collect()
+        };
+        ensure!(!names.is_empty(), "CSV data has no columns");
+        for (line, record) in &records {
+            ensure!(
+                record.len() == names.len(),
+                "line {}: expected {} fields, got {}",
+                line,
+                names.len(),
+                record.len()
+            );
+        }
+        for (name, _) in &options.column_types {
+            ensure!(names.contains(name), "column '{}' not found in CSV data", name);
+        }
+
+        let mut fields = Vec::with_capacity(names.len());
+        for (index, name) in names.iter().enumerate() {
+            ensure!(!names[..index].contains(name), "duplicate CSV column '{}'", name);
+            let ty = if let Some((_, ty)) = options.column_types.iter().find(|(n, _)| n == name) {
+                ty.clone()
+            } else {
+                let values: Vec<_> = records.iter().map(|(_, r)| r[index].as_str()).collect();
+                infer_type(&values)
+            };
+            ensure!(is_scalar(&ty) && !matches!(ty, TD::Enum(_)), "unsupported column type");
+            fields.push(CompoundField::new(name, ty, 0, index));
+        }
+        let compound = CompoundType { fields, size: 0 }.to_c_repr();
+
+        let mut buf = vec![0_u8; buffer_size(compound.size, records.len())?];
+        let mut strings = Vec::new();
+        for (row, (line, record)) in records.iter().enumerate() {
+            for (field, value) in compound.fields.iter().zip(record) {
+                let offset = row * compound.size + field.offset;
+                let encoded = encode(value, &field.ty, &mut buf[offset..], &mut strings);
+                ensure!(
+                    encoded.is_some(),
+                    "line {}: invalid value {:?} for column '{}' of type {}",
+                    line,
+                    value,
+                    field.name,
+                    type_name(&field.ty)
+                );
+            }
+        }
+
+        let dtype = Datatype::from_descriptor(&TD::Compound(compound))?;
+        let space = Dataspace::try_new(records.len(), false)?;
+        let name = to_cstring(name)?;
+        h5lock!({
+            let dataset = Dataset::from_id(h5try!(H5Dcreate2(
+                self.id(),
+                name.as_ptr(),
+                dtype.id(),
+                space.id(),
+                H5P_DEFAULT,
+                H5P_DEFAULT,
+                H5P_DEFAULT
+            )))?;
+            if !records.is_empty() {
+                let buf_ptr = buf.as_ptr() as *const c_void;
+                h5try!(H5Dwrite(dataset.id(), dtype.id(), H5S_ALL, H5S_ALL, H5P_DEFAULT, buf_ptr));
+            }
+            Ok(dataset)
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use hdf5_types::{FloatSize, IntSize, TypeDescriptor as TD, VarLenUnicode};
+
+    use super::CsvOptions;
+    use crate::internal_prelude::*;
+
+    type Record = (u32, f32, bool, VarLenUnicode);
+
+    #[test]
+    pub fn test_csv_roundtrip() {
+        with_tmp_file(|file| {
+            let records: Vec<Record> = vec![
+                (1, 0.1, true, "plain".parse().unwrap()),
+                (2, -2.0, false, "a, \"b\"".parse().unwrap()),
+                (3, f32::INFINITY, true, "x\ny".parse().unwrap()),
+            ];
+            let ds = file.new_dataset::<Record>().create("records", 3).unwrap();
+            ds.write(&records).unwrap();
+            let csv = ds.to_csv(&CsvOptions::default()).unwrap();
+            assert_eq!(
+                csv,
+                "0,1,2,3\n1,0.1,true,plain\n2,-2.0,false,\"a, \"\"b\"\"\"\n\
+                 3,inf,true,\"x\ny\"\n"
+            );
+
+            let mut options = CsvOptions::default();
+            options.column_types.push(("0".into(), TD::Unsigned(IntSize::U4)));
+            options.column_types.push(("1".into(), TD::Float(FloatSize::U4)));
+            let imported = file.import_csv("imported", csv.as_bytes(), &options).unwrap();
+            assert_eq!(imported.read_raw::<Record>().unwrap(), records);
+            assert_eq!(imported.to_csv(&CsvOptions::default()).unwrap(), csv);
+        })
+    }
+
+    #[test]
+    pub fn test_csv_import_inferred() {
+        with_tmp_file(|file| {
+            let csv = "a;b;c;d;e\r\n1;1.5;true;x;\r\n-2;;false;\"y;z\";\r\n\r\n3;4;true;5;\r\n";
+            let options = CsvOptions { delimiter: ';', ..CsvOptions::default() };
+            let ds = file.import_csv("data", csv.as_bytes(), &options).unwrap();
+            assert_eq!(ds.shape(), vec![3]);
+            let dtype = ds.dtype().unwrap().to_descriptor().unwrap();
+            let types: Vec<_> = match dtype {
+                TD::Compound(compound) => compound.fields.into_iter().map(|f| f.ty).collect(),
+                _ => panic!("expected a compound type"),
+            };
+            assert_eq!(
+                types,
+                vec![
+                    TD::Integer(IntSize::U8),
+                    TD::Float(FloatSize::U8),
+                    TD::Boolean,
+                    TD::VarLenUnicode,
+                    TD::VarLenUnicode
+                ]
+            );
+            let text = ds.to_csv(&options).unwrap();
+            assert_eq!(text, "a;b;c;d;e\n1;1.5;true;x;\n-2;NaN;false;\"y;z\";\n3;4.0;true;5;\n");
+
+            let options = CsvOptions { header: false, ..CsvOptions::default() };
+            let ds = file.import_csv("no_header", &b"1,x\n2,y\n"[..], &options).unwrap();
+            assert_eq!(ds.to_csv(&CsvOptions::default()).unwrap(), "column0,column1\n1,x\n2,y\n");
+        })
+    }
+
+    #[test]
+    pub fn test_csv_errors() {
+        with_tmp_file(|file| {
+            let import = |name, csv: &str, options: &CsvOptions| {
+                file.import_csv(name, csv.as_bytes(), options)
+            };
+            let defaults = CsvOptions::default();
+            assert_err!(import("a", "x,y\n1\n", &defaults), "line 2: expected 2 fields, got 1");
+            assert_err!(import("b", "x\n\"1\n", &defaults), "line 2: unterminated quoted field");
+            assert_err!(import("c", "x,x\n1,2\n", &defaults), "duplicate CSV column 'x'");
+            assert_err!(import("d", "", &defaults), "CSV header is missing");
+            let mut options = CsvOptions::default();
+            options.column_types.push(("x".into(), TD::Unsigned(IntSize::U1)));
+            assert_err!(
+                import("e", "x\n255\n256\n", &options),
+                "line 3: invalid value \"256\" for column 'x' of type u8"
+            );
+            options.column_types.push(("y".into(), TD::Boolean));
+            assert_err!(import("f", "x\n1\n", &options), "column 'y' not found");
+
+            let ds = file.new_dataset::<u32>().create("plain", 2).unwrap();
+            assert_err!(ds.to_csv(&CsvOptions::default()), "requires a compound datatype");
+            let ds = file.new_dataset::<[u8; 2]>().create("arr", (2, 2)).unwrap();
+            assert_err!(ds.to_csv(&CsvOptions::default()), "requires a one-dimensional dataset");
+        })
+    }
+}
@@ -0,0 +1,454 @@
+//! Human-readable dumps of the structure of files and groups.
+//!
+//! `Group::describe()` (and thus `File::describe()`) returns a value implementing `Display`
+//! which renders the group as an indented tree similar to the output of `h5ls -r`: groups
+//! are shown with a trailing slash, datasets with their type, shape, layout and filters,
+//! attributes (prefixed with `@`) with their type and value, and soft/external links with
+//! their targets. For example:
+//!
+//! ```text
+//! /
+//!   @title: utf8 = "run 1"
+//!   data: f32 {100/Inf, 3}, chunked {10, 3}, shuffle, deflate(4)
+//!     @units: utf8 = "m"
+//!   raw/
+//!     events: {t: f64, id: u32} {1000}
+//!   latest -> /raw/events
+//! ```
+//!
+//! Objects which can't be read are shown with an error message instead, so that the dump
+//! as a whole never fails.
+
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::os::raw::c_ulong;
+use std::ptr;
+
+use hdf5_sys::h5l::{H5L_info_t, H5L_type_t, H5Lget_info, H5Lget_val, H5Lunpack_elink_val};
+use hdf5_types::{DynValue, IntSize, ReferenceType, TypeDescriptor as TD};
+
+use crate::filters::Filter;
+use crate::hl::plist::dataset_create::Layout;
+use crate::internal_prelude::*;
+use crate::stats::object_key;
+
+/// Options controlling the output of `Group::describe_with()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DescribeOptions {
+    /// Number of levels of nested groups to expand; `Some(0)` only lists the members of
+    /// the described group itself. Groups which aren't expanded show their member count.
+    pub max_depth: Option<usize>,
+    /// Whether to show attributes of groups and datasets.
+    pub attributes: bool,
+    /// Maximum number of elements of attribute values to show; values are omitted if 0.
+    pub max_elements: usize,
+    /// Maximum number of characters of string values to show.
+    pub max_string_len: usize,
+}
+
+impl Default for DescribeOptions {
+    fn default() -> Self {
+        Self { max_depth: None, attributes: true, max_elements: 8, max_string_len: 64 }
+    }
+}
+
+/// Tree dump of a group, see the module documentation.
+#[derive(Debug)]
+pub struct Describe<'a> {
+    group: &'a Group,
+    options: DescribeOptions,
+}
+
+impl Display for Describe<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut dumper = Dumper { f, options: &self.options, seen: HashMap::new() };
+        if let Some(key) = object_key(self.group.id()) {
+            dumper.seen.insert(key, self.group.name());
+        }
+        dumper.line(0, &self.group.name())?;
+        dumper.group(self.group, 1)
+    }
+}
+
+impl Group {
+    /// Returns a tree dump of the group and its members with default options.
+    pub fn describe(&self) -> Describe {
+        self.describe_with(DescribeOptions::default())
+    }
+
+    /// Returns a tree dump of the group and its members.
+    pub fn describe_with(&self, options: DescribeOptions) -> Describe {
+        Describe { group: self, options }
+    }
+}
+
+/// A link in a group, with hard links resolved to the objects they point to.
+pub(crate) enum Member {
+    Group(Group),
+    Dataset(Dataset),
+    Datatype(Datatype),
+    SoftLink(String),
+    ExternalLink { file: String, path: String },
+}
+
+impl Member {
+    pub(crate) fn open(parent: &Group, name: &str) -> Result<Self> {
+        let c_name = to_cstring(name)?;
+        h5lock!({
+            let mut info = H5L_info_t::default();
+            h5try!(H5Lget_info(parent.id(), c_name.as_ptr(), &mut info, H5P_DEFAULT));
+            match info.type_ {
+                H5L_type_t::H5L_TYPE_HARD => {}
+                H5L_type_t::H5L_TYPE_SOFT | H5L_type_t::H5L_TYPE_EXTERNAL => {
+                    let size = *info.u.val_size();
+                    let mut buf = vec![0_u8; size];
+                    let buf_ptr = buf.as_mut_ptr() as *mut c_void;
+                    h5try!(H5Lget_val(parent.id(), c_name.as_ptr(), buf_ptr, size, H5P_DEFAULT));
+                    if info.type_ == H5L_type_t::H5L_TYPE_SOFT {
+                        return Ok(Self::SoftLink(string_from_cstr(buf.as_ptr() as *const _)));
+                    }
+                    let (mut file, mut path) = (ptr::null(), ptr::null());
+                    let mut flags: c_uint = 0;
+                    h5try!(H5Lunpack_elink_val(buf_ptr, size, &mut flags, &mut file, &mut path));
+                    let (file, path) = (string_from_cstr(file), string_from_cstr(path));
+                    return Ok(Self::ExternalLink { file, path });
+                }
+                link_type => fail!("unsupported link type: {:?}", link_type),
+            }
+            let _e = silence_errors();
+            if let Ok(group) = parent.group(name) {
+                Ok(Self::Group(group))
+            } else if let Ok(dataset) = parent.dataset(name) {
+                Ok(Self::Dataset(dataset))
+            } else if let Ok(datatype) = parent.datatype(name) {
+                Ok(Self::Datatype(datatype))
+            } else {
+                fail!("unable to open object '{}'", name)
+            }
+        })
+    }
+}
+
+struct Dumper<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    options: &'a DescribeOptions,
+    seen: HashMap<(c_ulong, haddr_t), String>,
+}
+
+impl Dumper<'_, '_> {
+    fn line(&mut self, depth: usize, text: &str) -> fmt::Result {
+        writeln!(self.f, "{:indent$}{}", "", text, indent = 2 * depth)
+    }
+
+    fn group(&mut self, group: &Group, depth: usize) -> fmt::Result {
+        self.attrs(group, depth)?;
+        match group.member_names() {
+            Ok(names) => names.iter().try_for_each(|name| self.member(group, name, depth)),
+            Err(err) => self.line(depth, &format!("<error: {}>", err)),
+        }
+    }
+
+    fn member(&mut self, parent: &Group, name: &str, depth: usize) -> fmt::Result {
+        match Member::open(parent, name) {
+            Ok(Member::Group(group)) => {
+                let key = object_key(group.id());
+                if let Some(path) = key.and_then(|key| self.seen.get(&key)) {
+                    let text = format!("{}/ (same as {})", name, path);
+                    return self.line(depth, &text);
+                }
+                if let Some(key) = key {
+                    self.seen.insert(key, group.name());
+                }
+                if self.options.max_depth.map_or(false, |max_depth| depth > max_depth) {
+                    self.line(depth, &format!("{}/ ({} members)", name, group.len()))
+                } else {
+                    self.line(depth, &format!("{}/", name))?;
+                    self.group(&group, depth + 1)
+                }
+            }
+            Ok(Member::Dataset(dataset)) => {
+                self.line(depth, &format!("{}: {}", name, describe_dataset(&dataset)))?;
+                self.attrs(&dataset, depth + 1)
+            }
+            Ok(Member::Datatype(datatype)) => {
+                let dtype = datatype.to_descriptor();
+                let text = dtype.as_ref().map_or_else(error, type_name);
+                self.line(depth, &format!("{}: datatype {}", name, text))
+            }
+            Ok(Member::SoftLink(target)) => self.line(depth, &format!("{} -> {}", name, target)),
+            Ok(Member::ExternalLink { file, path }) => {
+                self.line(depth, &format!("{} -> {}:{}", name, file, path))
+            }
+            Err(err) => self.line(depth, &format!("{}: {}", name, error(&err))),
+        }
+    }
+
+    fn attrs(&mut self, location: &Location, depth: usize) -> fmt::Result {
+        if !self.options.attributes {
+            return Ok(());
+        }
+        let names = match location.attr_names() {
+            Ok(names) => names,
+            Err(err) => return self.line(depth, &format!("@{}", error(&err))),
+        };
+        for name in names {
+            let attr = location.attr(&name);
+            let text = attr.as_ref().map_or_else(error, |attr| describe_attr(attr, self.options));
+            self.line(depth, &format!("@{}: {}", name, text))?;
+        }
+        Ok(())
+    }
+}
+
+fn error(err: &Error) -> String {
+    format!("<error: {}>", err)
+}
+
+fn describe_shape(space: &Dataspace) -> String {
+    if space.is_null() {
+        return "null".into();
+    } else if space.ndim() == 0 {
+        return "scalar".into();
+    }
+    let extents = space.extents();
+    let dims: Vec<_> = extents
+        .as_slice()
+        .iter()
+        .map(|extent| match extent.max {
+            _ if extent.is_fixed() => extent.dim.to_string(),
+            Some(max) => format!("{}/{}", extent.dim, max),
+            None => format!("{}/Inf", extent.dim),
+        })
+        .collect();
+    format!("{{{}}}", dims.join(", "))
+}
+
+fn describe_filter(filter: &Filter) -> String {
+    match filter {
+        Filter::Deflate(level) => format!("deflate({})", level),
+        Filter::Shuffle => "shuffle".into(),
+        Filter::Fletcher32 => "fletcher32".into(),
+        Filter::SZip(nn, pixels_per_block) => {
+            format!("szip({}, {})", if *nn { "nn" } else { "ec" }, pixels_per_block)
+        }
+        Filter::ScaleOffset(factor) => format!("scaleoffset({})", factor),
+        Filter::User { id, name, .. } if name.is_empty() => format!("filter({})", id),
+        Filter::User { name, .. } => name.clone(),
+    }
+}
+
+fn describe_dataset(dataset: &Dataset) -> String {
+    let dtype = dataset.dtype().and_then(|dtype| dtype.to_descriptor());
+    let space = dataset.space();
+    let mut parts = vec![format!(
+        "{} {}",
+        dtype.as_ref().map_or_else(error, type_name),
+        space.as_ref().map_or_else(error, describe_shape)
+    )];
+    match dataset.dcpl().map(|dcpl| dcpl.layout()) {
+        Ok(Layout::Chunked) => {
+            let chunks = dataset.chunks().unwrap_or_default();
+            let chunks: Vec<_> = chunks.iter().map(ToString::to_string).collect();
+            parts.push(format!("chunked {{{}}}", chunks.join(", ")));
+        }
+        Ok(Layout::Compact) => parts.push("compact".into()),
+        #[cfg(hdf5_1_10_0)]
+        Ok(Layout::Virtual) => parts.push("virtual".into()),
+        Ok(Layout::Contiguous) | Err(_) => {}
+    }
+    parts.extend(dataset.filters().pipeline().iter().map(describe_filter));
+    parts.join(", ")
+}
+
+fn describe_attr(attr: &Attribute, options: &DescribeOptions) -> String {
+    let dtype = match attr.dtype().and_then(|dtype| dtype.to_descriptor()) {
+        Ok(dtype) => dtype,
+        Err(err) => return error(&err),
+    };
+    let mut text = type_name(&dtype);
+    match attr.space() {
+        Ok(space) if space.ndim() > 0 || space.is_null() => {
+            text = format!("{} {}", text, describe_shape(&space));
+        }
+        Ok(_) => {}
+        Err(err) => return format!("{} {}", text, error(&err)),
+    }
+    if options.max_elements == 0 || attr.is_null() {
+        return text;
+    }
+    let value = match attr.read_dyn_array() {
+        Ok(values) if attr.is_scalar() => {
+            values.iter().next().map_or_else(String::new, |v| format_value(v, options))
+        }
+        Ok(values) => format_seq(values.iter(), values.len(), options),
+        Err(err) => error(&err),
+    };
+    format!("{} = {}", text, value)
+}
+
+fn format_seq<'a, I>(values: I, len: usize, options: &DescribeOptions) -> String
+where
+    I: Iterator<Item = &'a DynValue>,
+{
+    let mut items: Vec<_> =
+        values.take(options.max_elements).map(|value| format_value(value, options)).collect();
+    if len > options.max_elements {
+        items.push("...".into());
+    }
+    format!("[{}]", items.join(", "))
+}
+
+pub(crate) fn format_value(value: &DynValue, options: &DescribeOptions) -> String {
+    match value {
+        DynValue::Bool(v) => v.to_string(),
+        DynValue::Int(v) => v.to_string(),
+        DynValue::UInt(v) => v.to_string(),
+        DynValue::Float(v) => format!("{:?}", v),
+        DynValue::Enum { name: Some(name), .. } => name.clone(),
+        DynValue::Enum { name: None, value } => value.to_string(),
+        DynValue::String(s) if s.chars().count() > options.max_string_len => {
+            format!("{:?}...", s.chars().take(options.max_string_len).collect::<String>())
+        }
+        DynValue::String(s) => format!("{:?}", s),
+        DynValue::Array(values) => format_seq(values.iter(), values.len(), options),
+        DynValue::Compound(fields) => {
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, format_value(value, options)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+        DynValue::Opaque { data, .. } => format!("<{} bytes>", data.len()),
+        DynValue::Reference { ty: ReferenceType::Object, .. } => "<object reference>".into(),
+        DynValue::Reference { ty: ReferenceType::DatasetRegion, .. } => "<region reference>".into(),
+    }
+}
+
+fn int_name(size: IntSize, signed: bool) -> String {
+    format!("{}{}", if signed { "i" } else { "u" }, size as usize * 8)
+}
+
+/// Returns a short description of a datatype, e.g. `f32`, `[u8; 4]` or `{x: f64, y: f64}`.
+pub(crate) fn type_name(dtype: &TD) -> String {
+    match dtype {
+        TD::Integer(size) => int_name(*size, true),
+        TD::Unsigned(size) => int_name(*size, false),
+        TD::Float(size) => format!("f{}", *size as usize * 8),
+        TD::Boolean => "bool".into(),
+        TD::Enum(enum_type) => {
+            let members: Vec<_> = enum_type
+                .members
+                .iter()
+                .map(|m| {
+                    if enum_type.signed {
+                        format!("{} = {}", m.name, m.value as i64)
+                    } else {
+                        format!("{} = {}", m.name, m.value)
+                    }
+                })
+                .collect();
+            let base = int_name(enum_type.size, enum_type.signed);
+            format!("enum {} {{{}}}", base, members.join(", "))
+        }
+        TD::Compound(compound) => {
+            let fields: Vec<_> = compound
+                .fields
+                .iter()
+                .map(|field| format!("{}: {}", field.name, type_name(&field.ty)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+        TD::FixedArray(ty, len) => format!("[{}; {}]", type_name(ty), len),
+        TD::FixedAscii(len) => format!("ascii({})", len),
+        TD::FixedUnicode(len) => format!("utf8({})", len),
+        TD::VarLenArray(ty) => format!("[{}]", type_name(ty)),
+        TD::VarLenAscii => "ascii".into(),
+        TD::VarLenUnicode => "utf8".into(),
+        TD::Opaque(opaque) if opaque.tag.is_empty() => format!("opaque({})", opaque.size),
+        TD::Opaque(opaque) => format!("opaque({}, {:?})", opaque.size, opaque.tag),
+        TD::Bitfield(bitfield) => format!("b{}", bitfield.size as usize * 8),
+        TD::Reference(ReferenceType::Object) => "object reference".into(),
+        TD::Reference(ReferenceType::DatasetRegion) => "region reference".into(),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use hdf5_types::{TypeDescriptor as TD, VarLenUnicode};
+
+    use super::{type_name, DescribeOptions};
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_type_name() {
+        assert_eq!(type_name(&u8::type_descriptor()), "u8");
+        assert_eq!(type_name(&i64::type_descriptor()), "i64");
+        assert_eq!(type_name(&<[f32; 3]>::type_descriptor()), "[f32; 3]");
+        assert_eq!(type_name(&VarLenUnicode::type_descriptor()), "utf8");
+        assert_eq!(type_name(&<(bool, u16)>::type_descriptor()), "{0: bool, 1: u16}");
+        let vlen = TD::VarLenArray(Box::new(TD::FixedAscii(4)));
+        assert_eq!(type_name(&vlen), "[ascii(4)]");
+    }
+
+    #[test]
+    pub fn test_describe() {
+        with_tmp_file(|file| {
+            file.set_attr_str("title", "run 1").unwrap();
+            let data = file
+                .new_dataset::<f32>()
+                .chunk((10, 3))
+                .shuffle(true)
+                .create_with_extents("data", (Extent::unlimited(100), 3))
+                .unwrap();
+            data.set_attr("scale", &1.5_f64).unwrap();
+            let range: Vec<i32> = (0..10).collect();
+            data.new_attr::<i32>().with_data("range", range.as_slice()).unwrap();
+            let raw = file.create_group("raw").unwrap();
+            raw.new_dataset::<(f64, u32)>().create("events", 5).unwrap();
+            raw.create_group("nested").unwrap().new_dataset::<u8>().create("x", ()).unwrap();
+            file.link_soft("/raw/events", "latest").unwrap();
+            file.link_hard("/raw", "/raw/nested/up").unwrap();
+
+            let expected = "\
+/
+  @title: utf8 = \"run 1\"
+  data: f32 {100/Inf, 3}, chunked {10, 3}, shuffle
+    @range: i32 {10} = [0, 1, 2, 3, 4, 5, 6, 7, ...]
+    @scale: f64 = 1.5
+  latest -> /raw/events
+  raw/
+    events: {0: f64, 1: u32} {5}
+    nested/
+      up/ (same as /raw)
+      x: u8 scalar
+";
+            assert_eq!(file.describe().to_string(), expected);
+
+            let options = DescribeOptions {
+                max_depth: Some(0),
+                attributes: true,
+                max_elements: 0,
+                max_string_len: 3,
+            };
+            let expected_short = "\
+/
+  @title: utf8
+  data: f32 {100/Inf, 3}, chunked {10, 3}, shuffle
+    @range: i32 {10}
+    @scale: f64
+  latest -> /raw/events
+  raw/ (2 members)
+";
+            assert_eq!(file.describe_with(options).to_string(), expected_short);
+
+            let options = DescribeOptions { max_elements: 2, ..options };
+            let text = file.describe_with(options).to_string();
+            assert!(text.contains("@title: utf8 = \"run\"...\n"));
+            assert!(text.contains("@range: i32 {10} = [0, 1, ...]\n"));
+
+            let options = DescribeOptions { attributes: false, ..DescribeOptions::default() };
+            let text = raw.describe_with(options).to_string();
+            assert!(text.starts_with("/raw\n  events: "));
+        })
+    }
+}
@@ -0,0 +1,503 @@
+//! Structural and element-wise comparison of groups and files, similar to `h5diff`.
+//!
+//! `diff()` walks two trees in parallel and reports objects that exist in only one of
+//! them, objects of different kinds, link targets, datatypes and shapes that differ, and
+//! (unless disabled) datasets and attributes whose values differ. Paths in the report are
+//! relative to the compared groups, so that two groups of the same file can be compared;
+//! attributes are reported as `path@name`.
+//!
+//! Data is compared element by element after reading each dataset into memory. Numeric
+//! values of different types (e.g. `f32` and `f64`) are compared by value, with the
+//! tolerances given in `DiffOptions`; two NaNs are considered equal.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::{self, Display};
+use std::os::raw::c_ulong;
+
+use ndarray::Dimension as _;
+
+use hdf5_types::{DynValue, TypeDescriptor as TD};
+
+use crate::describe::{format_value, type_name, DescribeOptions, Member};
+use crate::internal_prelude::*;
+use crate::stats::object_key;
+
+/// Options controlling the comparison made by `diff()`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiffOptions {
+    /// Numeric values are equal if they differ by at most this amount.
+    pub abs_tolerance: f64,
+    /// Numeric values are equal if they differ by at most this fraction of the larger
+    /// of their magnitudes.
+    pub rel_tolerance: f64,
+    /// Whether to compare the values of datasets, or only their types and shapes.
+    pub compare_data: bool,
+    /// Whether to compare attributes.
+    pub compare_attrs: bool,
+    /// Maximum number of differing values recorded per dataset or attribute; all of
+    /// them are counted regardless.
+    pub max_samples: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            abs_tolerance: 0.0,
+            rel_tolerance: 0.0,
+            compare_data: true,
+            compare_attrs: true,
+            max_samples: 10,
+        }
+    }
+}
+
+/// A pair of differing values at the same position of two datasets or attributes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValueDifference {
+    pub index: Vec<Ix>,
+    pub first: DynValue,
+    pub second: DynValue,
+}
+
+/// A single difference found by `diff()`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Difference {
+    /// The object or attribute only exists in the first tree.
+    OnlyInFirst(String),
+    /// The object or attribute only exists in the second tree.
+    OnlyInSecond(String),
+    /// The objects are of different kinds, e.g. a group and a dataset.
+    Kind { path: String, first: &'static str, second: &'static str },
+    /// Soft or external links point to different targets.
+    Link { path: String, first: String, second: String },
+    /// Datasets, attributes or named datatypes have different datatypes.
+    Type { path: String, first: TD, second: TD },
+    /// Datasets or attributes have different shapes, so their values aren't compared.
+    Shape { path: String, first: Vec<Ix>, second: Vec<Ix> },
+    /// Datasets or attributes have values that differ.
+    Values { path: String, count: usize, total: usize, samples: Vec<ValueDifference> },
+    /// The object couldn't be read in one of the trees.
+    Error { path: String, message: String },
+}
+
+impl Difference {
+    /// Returns the path of the differing object, relative to the compared groups.
+    pub fn path(&self) -> &str {
+        match self {
+            Self::OnlyInFirst(path)
+            | Self::OnlyInSecond(path)
+            | Self::Kind { path, .. }
+            | Self::Link { path, .. }
+            | Self::Type { path, .. }
+            | Self::Shape { path, .. }
+            | Self::Values { path, .. }
+            | Self::Error { path, .. } => path,
+        }
+    }
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::OnlyInFirst(path) => write!(f, "{}: only in first", path),
+            Self::OnlyInSecond(path) => write!(f, "{}: only in second", path),
+            Self::Kind { path, first, second } => write!(f, "{}: {} vs {}", path, first, second),
+            Self::Link { path, first, second } => {
+                write!(f, "{}: link to {} vs {}", path, first, second)
+            }
+            Self::Type { path, first, second } => {
+                write!(f, "{}: type {} vs {}", path, type_name(first), type_name(second))
+            }
+            Self::Shape { path, first, second } => {
+                write!(f, "{}: shape {:?} vs {:?}", path, first, second)
+            }
+            Self::Values { path, count, total, samples } => {
+                write!(f, "{}: {} of {} values differ", path, count, total)?;
+                let options = DescribeOptions::default();
+                for sample in samples {
+                    let (first, second) = (&sample.first, &sample.second);
+                    let (first, second) =
+                        (format_value(first, &options), format_value(second, &options));
+                    write!(f, "\n  {:?}: {} vs {}", sample.index, first, second)?;
+                }
+                Ok(())
+            }
+            Self::Error { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+/// Result of `diff()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiffReport {
+    /// Differences in the order they were found; members are visited in name order.
+    pub differences: Vec<Difference>,
+    /// Number of objects (groups, datasets, named datatypes and links) compared.
+    pub objects: usize,
+}
+
+impl DiffReport {
+    /// Returns `true` if no differences were found.
+    pub fn is_identical(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for difference in &self.differences {
+            writeln!(f, "{}", difference)?;
+        }
+        write!(f, "{} objects compared, {} differences", self.objects, self.differences.len())
+    }
+}
+
+/// Compares two groups (or files) recursively, see the module documentation.
+///
+/// Fails only if the member names of either of the groups can't be listed; problems with
+/// reading individual objects are reported as `Difference::Error`.
+pub fn diff(first: &Group, second: &Group, options: DiffOptions) -> Result<DiffReport> {
+    let mut differ = Differ { options, report: DiffReport::default(), seen: HashSet::new() };
+    differ.group(first, second, "")?;
+    Ok(differ.report)
+}
+
+fn kind(member: &Member) -> &'static str {
+    match member {
+        Member::Group(_) => "group",
+        Member::Dataset(_) => "dataset",
+        Member::Datatype(_) => "datatype",
+        Member::SoftLink(_) => "soft link",
+        Member::ExternalLink { .. } => "external link",
+    }
+}
+
+fn is_numeric(dtype: &TD) -> bool {
+    matches!(dtype, TD::Integer(_) | TD::Unsigned(_) | TD::Float(_))
+}
+
+fn as_int(value: &DynValue) -> Option<i128> {
+    match *value {
+        DynValue::Int(v) => Some(v),
+        DynValue::UInt(v) if v <= i128::max_value() as u128 => Some(v as _),
+        _ => None,
+    }
+}
+
+fn as_float(value: &DynValue) -> Option<f64> {
+    match *value {
+        DynValue::Int(v) => Some(v as _),
+        DynValue::UInt(v) => Some(v as _),
+        DynValue::Float(v) => Some(v),
+        _ => None,
+    }
+}
+
+struct Differ {
+    options: DiffOptions,
+    report: DiffReport,
+    seen: HashSet<((c_ulong, haddr_t), (c_ulong, haddr_t))>,
+}
+
+impl Differ {
+    fn push(&mut self, difference: Difference) {
+        self.report.differences.push(difference);
+    }
+
+    fn group(&mut self, first: &Group, second: &Group, path: &str) -> Result<()> {
+        if let Some(key) = object_key(first.id()).zip(object_key(second.id())) {
+            if !self.seen.insert(key) {
+                // hard links to a pair of groups that has already been compared
+                return Ok(());
+            }
+        }
+        self.attrs(first, second, if path.is_empty() { "/" } else { path });
+        let first_names: BTreeSet<_> = first.member_names()?.into_iter().collect();
+        let second_names: BTreeSet<_> = second.member_names()?.into_iter().collect();
+        for name in first_names.union(&second_names) {
+            let path = format!("{}/{}", path, name);
+            self.report.objects += 1;
+            match (first_names.contains(name), second_names.contains(name)) {
+                (true, false) => self.push(Difference::OnlyInFirst(path)),
+                (false, true) => self.push(Difference::OnlyInSecond(path)),
+                _ => self.member(first, second, name, &path),
+            }
+        }
+        Ok(())
+    }
+
+    fn member(&mut self, first: &Group, second: &Group, name: &str, path: &str) {
+        let (first, second) = match (Member::open(first, name), Member::open(second, name)) {
+            (Ok(first), Ok(second)) => (first, second),
+            (Err(err), _) | (_, Err(err)) => {
+                let message = err.to_string();
+                return self.push(Difference::Error { path: path.into(), message });
+            }
+        };
+        match (&first, &second) {
+            (Member::Group(first), Member::Group(second)) => {
+                if let Err(err) = self.group(first, second, path) {
+                    let message = err.to_string();
+                    self.push(Difference::Error { path: path.into(), message });
+                }
+            }
+            (Member::Dataset(first), Member::Dataset(second)) => {
+                self.container(first, second, path, self.options.compare_data);
+                self.attrs(first, second, path);
+            }
+            (Member::Datatype(first), Member::Datatype(second)) => {
+                match (first.to_descriptor(), second.to_descriptor()) {
+                    (Ok(first), Ok(second)) if first != second => {
+                        self.push(Difference::Type { path: path.into(), first, second });
+                    }
+                    (Err(err), _) | (_, Err(err)) => {
+                        let message = err.to_string();
+                        self.push(Difference::Error { path: path.into(), message });
+                    }
+                    _ => {}
+                }
+            }
+            (Member::SoftLink(first), Member::SoftLink(second)) if first != second => {
+                let (first, second) = (first.clone(), second.clone());
+                self.push(Difference::Link { path: path.into(), first, second });
+            }
+            (
+                Member::ExternalLink { file: first_file, path: first_path },
+                Member::ExternalLink { file: second_file, path: second_path },
+            ) if (first_file, first_path) != (second_file, second_path) => {
+                let first = format!("{}:{}", first_file, first_path);
+                let second = format!("{}:{}", second_file, second_path);
+                self.push(Difference::Link { path: path.into(), first, second });
+            }
+            _ if kind(&first) != kind(&second) => {
+                let (first, second) = (kind(&first), kind(&second));
+                self.push(Difference::Kind { path: path.into(), first, second });
+            }
+            _ => {}
+        }
+    }
+
+    fn attrs(&mut self, first: &Location, second: &Location, path: &str) {
+        if !self.options.compare_attrs {
+            return;
+        }
+        let names = first.attr_names().and_then(|a| second.attr_names().map(|b| (a, b)));
+        let (first_names, second_names): (BTreeSet<_>, BTreeSet<_>) = match names {
+            Ok((a, b)) => (a.into_iter().collect(), b.into_iter().collect()),
+            Err(err) => {
+                let message = err.to_string();
+                return self.push(Difference::Error { path: path.into(), message });
+            }
+        };
+        for name in first_names.union(&second_names) {
+            let path = format!("{}@{}", path, name);
+            match (first_names.contains(name), second_names.contains(name)) {
+                (true, false) => self.push(Difference::OnlyInFirst(path)),
+                (false, true) => self.push(Difference::OnlyInSecond(path)),
+                _ => match (first.attr(name), second.attr(name)) {
+                    (Ok(first), Ok(second)) => self.container(&first, &second, &path, true),
+                    (Err(err), _) | (_, Err(err)) => {
+                        let message = err.to_string();
+                        self.push(Difference::Error { path, message });
+                    }
+                },
+            }
+        }
+    }
+
+    fn container(&mut self, first: &Container, second: &Container, path: &str, data: bool) {
+        let path = path.to_owned();
+        let dtypes = first
+            .dtype()
+            .and_then(|a| a.to_descriptor())
+            .and_then(|a| second.dtype().and_then(|b| b.to_descriptor()).map(|b| (a, b)));
+        let (first_dtype, second_dtype) = match dtypes {
+            Ok(dtypes) => dtypes,
+            Err(err) => return self.push(Difference::Error { path, message: err.to_string() }),
+        };
+        let comparable =
+            first_dtype == second_dtype || (is_numeric(&first_dtype) && is_numeric(&second_dtype));
+        if first_dtype != second_dtype {
+            let (first, second) = (first_dtype, second_dtype);
+            self.push(Difference::Type { path: path.clone(), first, second });
+        }
+        if first.is_null() != second.is_null() || first.shape() != second.shape() {
+            let (first, second) = (first.shape(), second.shape());
+            return self.push(Difference::Shape { path, first, second });
+        }
+        if !data || !comparable || first.is_null() {
+            return;
+        }
+        let (first, second) = match (first.read_dyn_array(), second.read_dyn_array()) {
+            (Ok(first), Ok(second)) => (first, second),
+            (Err(err), _) | (_, Err(err)) => {
+                return self.push(Difference::Error { path, message: err.to_string() })
+            }
+        };
+        let mut count = 0;
+        let mut samples = Vec::new();
+        for ((index, a), b) in first.indexed_iter().zip(second.iter()) {
+            if !self.values_equal(a, b) {
+                count += 1;
+                if samples.len() < self.options.max_samples {
+                    let index = index.slice().to_vec();
+                    samples.push(ValueDifference { index, first: a.clone(), second: b.clone() });
+                }
+            }
+        }
+        if count > 0 {
+            self.push(Difference::Values { path, count, total: first.len(), samples });
+        }
+    }
+
+    fn values_equal(&self, first: &DynValue, second: &DynValue) -> bool {
+        match (first, second) {
+            (DynValue::Array(a), DynValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| self.values_equal(a, b))
+            }
+            (DynValue::Compound(a), DynValue::Compound(b)) => {
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|((a_name, a), (b_name, b))| {
+                        a_name == b_name && self.values_equal(a, b)
+                    })
+            }
+            _ => {
+                if first == second {
+                    return true;
+                }
+                let (a, b) = match (as_float(first), as_float(second)) {
+                    (Some(a), Some(b)) if a.is_nan() && b.is_nan() => return true,
+                    (Some(a), Some(b)) => (a, b),
+                    _ => return false,
+                };
+                // integers are subtracted exactly, since they may not be representable as f64
+                let diff = match (as_int(first), as_int(second)) {
+                    (Some(a), Some(b)) => a.checked_sub(b).map_or(f64::INFINITY, |d| d.abs() as _),
+                    _ => (a - b).abs(),
+                };
+                diff <= self.options.abs_tolerance
+                    || diff <= self.options.rel_tolerance * a.abs().max(b.abs())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use hdf5_types::{DynValue, TypeDescriptor as TD};
+
+    use super::{diff, DiffOptions, Difference, ValueDifference};
+    use crate::internal_prelude::*;
+
+    fn populate(group: &Group) {
+        group.set_attr_str("title", "run").unwrap();
+        group.new_dataset::<f64>().create("x", 4).unwrap().write(&[1., 2., 3., 4.]).unwrap();
+        let sub = group.create_group("sub").unwrap();
+        sub.new_dataset::<i32>().create("y", (2, 2)).unwrap();
+        sub.link_soft("/x", "link").unwrap();
+        group.link_hard("sub", "sub/again").unwrap();
+    }
+
+    #[test]
+    pub fn test_diff_identical() {
+        with_tmp_file(|file| {
+            let (a, b) = (file.create_group("a").unwrap(), file.create_group("b").unwrap());
+            populate(&a);
+            populate(&b);
+            let report = diff(&a, &b, DiffOptions::default()).unwrap();
+            assert!(report.is_identical(), "{}", report);
+            assert_eq!(report.objects, 5);
+            assert!(diff(&file, &file, DiffOptions::default()).unwrap().is_identical());
+        })
+    }
+
+    #[test]
+    pub fn test_diff_structure() {
+        with_tmp_file(|file| {
+            let (a, b) = (file.create_group("a").unwrap(), file.create_group("b").unwrap());
+            populate(&a);
+            populate(&b);
+            a.create_group("extra").unwrap();
+            b.new_attr::<u8>().create("flag", ()).unwrap();
+            b.unlink("x").unwrap();
+            b.new_dataset::<f32>().create("x", 3).unwrap();
+            let sub = b.group("sub").unwrap();
+            sub.unlink("y").unwrap();
+            sub.create_group("y").unwrap();
+            sub.unlink("link").unwrap();
+            sub.link_soft("/elsewhere", "link").unwrap();
+
+            let report = diff(&a, &b, DiffOptions::default()).unwrap();
+            let expected = vec![
+                Difference::OnlyInSecond("/@flag".into()),
+                Difference::OnlyInFirst("/extra".into()),
+                Difference::Link {
+                    path: "/sub/link".into(),
+                    first: "/x".into(),
+                    second: "/elsewhere".into(),
+                },
+                Difference::Kind { path: "/sub/y".into(), first: "dataset", second: "group" },
+                Difference::Type {
+                    path: "/x".into(),
+                    first: f64::type_descriptor(),
+                    second: f32::type_descriptor(),
+                },
+                Difference::Shape { path: "/x".into(), first: vec![4], second: vec![3] },
+            ];
+            assert_eq!(report.differences, expected);
+            assert_eq!(report.to_string().lines().next().unwrap(), "/@flag: only in second");
+        })
+    }
+
+    #[test]
+    pub fn test_diff_values() {
+        with_tmp_file(|file| {
+            let a = file.new_dataset::<f64>().create("a", 4).unwrap();
+            a.write(&[1., 2., 3., f64::NAN]).unwrap();
+            let b = file.new_dataset::<f32>().create("b", 4).unwrap();
+            b.write(&[1., 2.5, 3.01, f32::NAN]).unwrap();
+            let (ga, gb) = (file.create_group("ga").unwrap(), file.create_group("gb").unwrap());
+            file.link_hard("a", "ga/x").unwrap();
+            file.link_hard("b", "gb/x").unwrap();
+
+            let report = diff(&ga, &gb, DiffOptions::default()).unwrap();
+            assert_eq!(report.differences.len(), 2);
+            assert!(matches!(&report.differences[0], Difference::Type { first: TD::Float(_), .. }));
+            match &report.differences[1] {
+                Difference::Values { path, count, total, samples } => {
+                    assert_eq!((path.as_str(), *count, *total), ("/x", 2, 4));
+                    let sample = ValueDifference {
+                        index: vec![1],
+                        first: DynValue::Float(2.),
+                        second: DynValue::Float(2.5),
+                    };
+                    assert_eq!(samples[0], sample);
+                }
+                difference => panic!("unexpected difference: {:?}", difference),
+            }
+
+            let options =
+                DiffOptions { abs_tolerance: 0.1, max_samples: 0, ..DiffOptions::default() };
+            let report = diff(&ga, &gb, options).unwrap();
+            match &report.differences[1] {
+                Difference::Values { count, samples, .. } => {
+                    assert_eq!((*count, samples.len()), (1, 0));
+                }
+                difference => panic!("unexpected difference: {:?}", difference),
+            }
+            let options = DiffOptions { rel_tolerance: 0.25, ..DiffOptions::default() };
+            assert_eq!(diff(&ga, &gb, options).unwrap().differences.len(), 1);
+            let options = DiffOptions { compare_data: false, ..DiffOptions::default() };
+            assert_eq!(diff(&ga, &gb, options).unwrap().differences.len(), 1);
+
+            let (big1, big2) = ([1_u64 << 60, 1], [(1_i64 << 60) + 1, 1]);
+            file.new_dataset::<u64>().create("big1", 2).unwrap().write(&big1).unwrap();
+            file.new_dataset::<i64>().create("big2", 2).unwrap().write(&big2).unwrap();
+            let (g1, g2) = (file.create_group("g1").unwrap(), file.create_group("g2").unwrap());
+            file.link_hard("big1", "g1/x").unwrap();
+            file.link_hard("big2", "g2/x").unwrap();
+            let report = diff(&g1, &g2, DiffOptions::default()).unwrap();
+            assert!(matches!(&report.differences[1], Difference::Values { count: 1, .. }));
+        })
+    }
+}
@@ -1,5 +1,9 @@
+use std::convert::TryFrom;
+use std::ops::{Range, RangeInclusive, RangeTo};
 use std::slice;
 
+use crate::error::Result;
+
 /// A scalar integer type used by `Dimension` trait for indexing.
 pub type Ix = usize;
 
@@ -9,17 +13,22 @@ pub trait Dimension {
 
     fn dims(&self) -> Vec<Ix>;
 
+    /// Returns the total number of elements; panics if it overflows `Ix`.
     fn size(&self) -> Ix {
+        self.try_size().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Returns the total number of elements, failing if it overflows `Ix`.
+    fn try_size(&self) -> Result<Ix> {
         let dims = self.dims();
-        if dims.is_empty() {
-            1
-        } else {
-            dims.iter().product()
+        match dims.iter().try_fold(1 as Ix, |size, &dim| size.checked_mul(dim)) {
+            Some(size) => Ok(size),
+            None => fail!("number of elements in shape {:?} overflows usize", dims),
         }
     }
 }
 
-impl<'a, T: Dimension> Dimension for &'a T {
+impl<'a, T: Dimension + ?Sized> Dimension for &'a T {
     fn ndim(&self) -> usize {
         Dimension::ndim(*self)
     }
@@ -27,6 +36,14 @@ impl<'a, T: Dimension> Dimension for &'a T {
     fn dims(&self) -> Vec<Ix> {
         Dimension::dims(*self)
     }
+
+    fn size(&self) -> Ix {
+        Dimension::size(*self)
+    }
+
+    fn try_size(&self) -> Result<Ix> {
+        Dimension::try_size(*self)
+    }
 }
 
 impl Dimension for Vec<Ix> {
@@ -82,3 +99,238 @@ impl Dimension for Ix {
         vec![*self]
     }
 }
+
+impl Dimension for [Ix] {
+    fn ndim(&self) -> usize {
+        self.len()
+    }
+
+    fn dims(&self) -> Vec<Ix> {
+        self.to_vec()
+    }
+}
+
+impl<const N: usize> Dimension for [Ix; N] {
+    fn ndim(&self) -> usize {
+        N
+    }
+
+    fn dims(&self) -> Vec<Ix> {
+        self.to_vec()
+    }
+}
+
+impl Dimension for [u64] {
+    fn ndim(&self) -> usize {
+        self.len()
+    }
+
+    fn dims(&self) -> Vec<Ix> {
+        self.iter().map(|&x| Ix::try_from(x).expect("dimension size overflows usize")).collect()
+    }
+}
+
+/// A range is a 1-dimensional shape with as many elements as the range has.
+impl Dimension for Range<Ix> {
+    fn ndim(&self) -> usize {
+        1
+    }
+
+    fn dims(&self) -> Vec<Ix> {
+        vec![self.end.saturating_sub(self.start)]
+    }
+}
+
+impl Dimension for RangeInclusive<Ix> {
+    fn ndim(&self) -> usize {
+        1
+    }
+
+    fn dims(&self) -> Vec<Ix> {
+        vec![if self.is_empty() { 0 } else { self.end() - self.start() + 1 }]
+    }
+}
+
+impl Dimension for RangeTo<Ix> {
+    fn ndim(&self) -> usize {
+        1
+    }
+
+    fn dims(&self) -> Vec<Ix> {
+        vec![self.end]
+    }
+}
+
+macro_rules! impl_ndarray_dim {
+    ($($ty:ty),*) => ($(
+        impl Dimension for $ty {
+            fn ndim(&self) -> usize {
+                ndarray::Dimension::ndim(self)
+            }
+
+            fn dims(&self) -> Vec<Ix> {
+                ndarray::Dimension::slice(self).to_vec()
+            }
+        }
+    )*)
+}
+
+impl_ndarray_dim! {
+    ndarray::Ix0, ndarray::Ix1, ndarray::Ix2, ndarray::Ix3, ndarray::Ix4, ndarray::Ix5,
+    ndarray::Ix6, ndarray::IxDyn
+}
+
+/// Current and maximum size of a single axis of a dataspace.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Extent {
+    /// Current size of the axis.
+    pub dim: Ix,
+    /// Maximum size of the axis, or `None` if it's unlimited.
+    pub max: Option<Ix>,
+}
+
+impl Extent {
+    /// Creates a fixed-size axis (its maximum size equals the current size).
+    pub fn new(dim: Ix) -> Self {
+        Self { dim, max: Some(dim) }
+    }
+
+    /// Creates an axis which may grow without limits.
+    pub fn unlimited(dim: Ix) -> Self {
+        Self { dim, max: None }
+    }
+
+    /// Sets the maximum size of the axis.
+    pub fn max(mut self, max: Ix) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Returns `true` if the axis can't be resized.
+    pub fn is_fixed(&self) -> bool {
+        self.max == Some(self.dim)
+    }
+}
+
+impl From<Ix> for Extent {
+    fn from(dim: Ix) -> Self {
+        Self::new(dim)
+    }
+}
+
+/// Current and maximum sizes of all axes of a dataspace.
+///
+/// Can be constructed from tuples whose elements are either sizes of fixed axes or
+/// `Extent` values, e.g. `(Extent::new(10).max(1000), 3)`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Extents(Vec<Extent>);
+
+impl Extents {
+    /// Creates extents from the given axes.
+    pub fn new(extents: Vec<Extent>) -> Self {
+        Self(extents)
+    }
+
+    /// Returns the axes.
+    pub fn as_slice(&self) -> &[Extent] {
+        &self.0
+    }
+
+    /// Returns the maximum sizes of all axes (`None` meaning unlimited).
+    pub fn maxdims(&self) -> Vec<Option<Ix>> {
+        self.0.iter().map(|e| e.max).collect()
+    }
+
+    /// Returns `true` if any of the axes can be resized.
+    pub fn is_resizable(&self) -> bool {
+        self.0.iter().any(|e| !e.is_fixed())
+    }
+}
+
+impl Dimension for Extents {
+    fn ndim(&self) -> usize {
+        self.0.len()
+    }
+
+    fn dims(&self) -> Vec<Ix> {
+        self.0.iter().map(|e| e.dim).collect()
+    }
+}
+
+impl From<Vec<Extent>> for Extents {
+    fn from(extents: Vec<Extent>) -> Self {
+        Self(extents)
+    }
+}
+
+impl From<&[Extent]> for Extents {
+    fn from(extents: &[Extent]) -> Self {
+        Self(extents.to_vec())
+    }
+}
+
+impl From<Extent> for Extents {
+    fn from(extent: Extent) -> Self {
+        Self(vec![extent])
+    }
+}
+
+impl From<Ix> for Extents {
+    fn from(dim: Ix) -> Self {
+        Self(vec![dim.into()])
+    }
+}
+
+impl From<()> for Extents {
+    fn from(_: ()) -> Self {
+        Self::default()
+    }
+}
+
+macro_rules! impl_extents_tuple {
+    () => ();
+
+    ($head:ident, $($tail:ident,)*) => (
+        #[allow(non_snake_case)]
+        impl<$head: Into<Extent>, $($tail: Into<Extent>,)*> From<($head, $($tail,)*)> for Extents {
+            fn from(($head, $($tail,)*): ($head, $($tail,)*)) -> Self {
+                Self(vec![$head.into(), $($tail.into(),)*])
+            }
+        }
+
+        impl_extents_tuple! { $($tail,)* }
+    )
+}
+
+impl_extents_tuple! { A, B, C, D, E, F, G, H, I, J, K, L, }
+
+#[cfg(test)]
+pub mod tests {
+    use super::{Dimension, Extent, Extents};
+
+    #[test]
+    pub fn test_extents() {
+        let e: Extents = (Extent::new(10).max(1000), 3, Extent::unlimited(0)).into();
+        assert_eq!((e.ndim(), e.dims(), e.size()), (3, vec![10, 3, 0], 0));
+        assert_eq!(e.maxdims(), vec![Some(1000), Some(3), None]);
+        assert!(e.is_resizable());
+
+        let e: Extents = (4, 5).into();
+        assert_eq!(e.as_slice(), &[Extent::new(4), Extent::new(5)]);
+        assert!(!e.is_resizable());
+        assert_eq!(Extents::from(7), Extents::new(vec![Extent::new(7)]));
+        assert_eq!(Extents::from(()).ndim(), 0);
+        assert!(Extent::new(2).is_fixed() && !Extent::new(2).max(3).is_fixed());
+    }
+
+    #[test]
+    pub fn test_try_size() {
+        assert_eq!(().try_size().unwrap(), 1);
+        assert_eq!((2, 0, 3).try_size().unwrap(), 0);
+        assert_eq!(vec![usize::max_value(), 1].try_size().unwrap(), usize::max_value());
+        assert_err!(
+            vec![usize::max_value(), 2].try_size(),
+            &format!("number of elements in shape [{}, 2] overflows usize", usize::max_value())
+        );
+    }
+}
@@ -1,8 +1,14 @@
-use std::cell::RefCell;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
 use std::error::Error as StdError;
 use std::fmt;
 use std::ops::Index;
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
+use std::slice;
+#[cfg(not(h5_have_threadsafe))]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use lazy_static::lazy_static;
 use ndarray::ShapeError;
@@ -12,11 +18,42 @@ use parking_lot::Mutex;
 
 use hdf5_sys::h5e::{
     H5E_error2_t, H5Eclose_stack, H5Eget_current_stack, H5Eget_msg, H5Eprint2, H5Eset_auto2,
-    H5Ewalk2, H5E_DEFAULT, H5E_WALK_DOWNWARD,
+    H5Eset_current_stack, H5Ewalk2, H5E_DEFAULT, H5E_WALK_DOWNWARD,
 };
+use hdf5_types::TypeParseError;
 
+use crate::globals::{
+    H5E_ALREADYEXISTS, H5E_ARGS, H5E_ATOM, H5E_CANTALLOC, H5E_CANTCONVERT, H5E_CANTLOCK,
+    H5E_DATATYPE, H5E_EXISTS, H5E_FILEEXISTS, H5E_NOFILTER, H5E_NOSPACE, H5E_NOTFOUND, H5E_NOTHDF5,
+    H5E_NOTREGISTERED, H5E_PLINE, H5E_PLUGIN,
+};
 use crate::internal_prelude::*;
 
+/// A category of errors, derived from the major/minor message codes of the error stack,
+/// that can be matched on instead of parsing the error messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// An object, attribute or link does not exist.
+    ObjectNotFound,
+    /// An object, attribute, link or file already exists.
+    AlreadyExists,
+    /// The datatypes are incompatible and cannot be converted.
+    TypeMismatch,
+    /// Memory or file space could not be allocated.
+    NoSpace,
+    /// A filter required by the dataset is not available.
+    FilterUnavailable,
+    /// The file is locked by another process.
+    FileLocked,
+    /// The file is not an HDF5 file.
+    NotHdf5,
+    /// An invalid argument or identifier was passed to the library.
+    InvalidArgument,
+    /// Any other error, including errors raised by the high-level Rust API.
+    Other,
+}
+
+/// A single frame of the HDF5 error stack.
 #[derive(Clone)]
 pub struct ErrorFrame {
     desc: String,
@@ -24,6 +61,10 @@ pub struct ErrorFrame {
     major: String,
     minor: String,
     description: String,
+    file: String,
+    line: u32,
+    major_id: hid_t,
+    minor_id: hid_t,
 }
 
 impl ErrorFrame {
@@ -34,13 +75,67 @@ impl ErrorFrame {
             major: major.into(),
             minor: minor.into(),
             description: format!("{}(): {}", func, desc),
+            file: String::new(),
+            line: 0,
+            major_id: H5I_INVALID_HID,
+            minor_id: H5I_INVALID_HID,
         }
     }
 
+    /// Sets the source location (file name and line) in the library.
+    pub fn with_location(mut self, file: &str, line: u32) -> Self {
+        self.file = file.into();
+        self.line = line;
+        self
+    }
+
+    /// Sets the identifiers of the major and minor error messages.
+    pub fn with_ids(mut self, major_id: hid_t, minor_id: hid_t) -> Self {
+        self.major_id = major_id;
+        self.minor_id = minor_id;
+        self
+    }
+
     pub fn desc(&self) -> &str {
         self.desc.as_ref()
     }
 
+    /// Returns the name of the library function where the error occurred.
+    pub fn func(&self) -> &str {
+        self.func.as_ref()
+    }
+
+    /// Returns the major error message, e.g. "Property lists".
+    pub fn major(&self) -> &str {
+        self.major.as_ref()
+    }
+
+    /// Returns the minor error message, e.g. "Unable to free object".
+    pub fn minor(&self) -> &str {
+        self.minor.as_ref()
+    }
+
+    /// Returns the library source file where the error occurred (may be empty).
+    pub fn file(&self) -> &str {
+        self.file.as_ref()
+    }
+
+    /// Returns the line in the library source file where the error occurred.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Returns the identifier of the major error message, which can be compared against
+    /// the `H5E_*` globals in `hdf5_sys::h5e`.
+    pub fn major_id(&self) -> hid_t {
+        self.major_id
+    }
+
+    /// Returns the identifier of the minor error message.
+    pub fn minor_id(&self) -> hid_t {
+        self.minor_id
+    }
+
     pub fn description(&self) -> &str {
         self.description.as_ref()
     }
@@ -48,6 +143,45 @@ impl ErrorFrame {
     pub fn detail(&self) -> Option<String> {
         Some(format!("Error in {}(): {} [{}: {}]", self.func, self.desc, self.major, self.minor))
     }
+
+    /// Returns the error category of this frame based on its message codes.
+    pub fn kind(&self) -> ErrorKind {
+        let (major, minor) = (self.major_id, self.minor_id);
+        if major == H5I_INVALID_HID && minor == H5I_INVALID_HID {
+            ErrorKind::Other
+        } else if major == *H5E_PLUGIN
+            || minor == *H5E_NOFILTER
+            || (major == *H5E_PLINE && (minor == *H5E_NOTFOUND || minor == *H5E_NOTREGISTERED))
+        {
+            ErrorKind::FilterUnavailable
+        } else if major == *H5E_DATATYPE || minor == *H5E_CANTCONVERT {
+            ErrorKind::TypeMismatch
+        } else if minor == *H5E_NOSPACE || minor == *H5E_CANTALLOC {
+            ErrorKind::NoSpace
+        } else if minor == *H5E_CANTLOCK {
+            ErrorKind::FileLocked
+        } else if minor == *H5E_NOTHDF5 {
+            ErrorKind::NotHdf5
+        } else if minor == *H5E_EXISTS || minor == *H5E_ALREADYEXISTS || minor == *H5E_FILEEXISTS {
+            ErrorKind::AlreadyExists
+        } else if minor == *H5E_NOTFOUND {
+            ErrorKind::ObjectNotFound
+        } else if major == *H5E_ARGS || major == *H5E_ATOM {
+            ErrorKind::InvalidArgument
+        } else {
+            ErrorKind::Other
+        }
+    }
+}
+
+impl fmt::Debug for ErrorFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}(): {} [{}: {}]", self.func, self.desc, self.major, self.minor)?;
+        if !self.file.is_empty() {
+            write!(f, " at {}:{}", self.file, self.line)?;
+        }
+        Ok(())
+    }
 }
 
 #[must_use]
@@ -60,12 +194,135 @@ impl Default for SilenceErrors {
     }
 }
 
+type CustomErrorHandler = Arc<dyn Fn(&ErrorStack) + Send + Sync>;
+
+lazy_static! {
+    static ref CUSTOM_ERROR_HANDLER: Mutex<Option<CustomErrorHandler>> = Mutex::default();
+}
+
+#[cfg(not(h5_have_threadsafe))]
 lazy_static! {
     static ref ERROR_HANDLER: Mutex<RefCell<usize>> = Mutex::default();
 }
 
+#[cfg(not(h5_have_threadsafe))]
+static ERROR_HANDLER_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+// In threadsafe builds, the library maintains a separate error stack (along with the
+// automatic error reporting settings) for each thread, so errors are silenced per thread.
+#[cfg(h5_have_threadsafe)]
+thread_local! {
+    static ERROR_HANDLER: RefCell<usize> = RefCell::new(0);
+    static ERROR_HANDLER_INSTALLED: Cell<bool> = Cell::new(false);
+}
+
+thread_local! {
+    static IN_CUSTOM_ERROR_HANDLER: Cell<bool> = Cell::new(false);
+    static CALLBACK_PANIC: RefCell<Option<String>> = RefCell::new(None);
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|&msg| msg.to_owned())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_owned())
+}
+
+/// Runs the body of a callback invoked by the library, catching panics so that they
+/// don't unwind across the FFI boundary; `on_panic` is returned to the library instead,
+/// and the panic message is attached to the error returned by the pending library call.
+pub fn catch_callback_panic<T, F: FnOnce() -> T>(on_panic: T, func: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(func)).unwrap_or_else(|payload| {
+        let msg = panic_message(&*payload);
+        // only the first panic is kept if the library keeps calling back after a failure
+        let _ = CALLBACK_PANIC.try_with(|panic| panic.borrow_mut().get_or_insert(msg).len());
+        on_panic
+    })
+}
+
+fn take_callback_panic() -> Option<String> {
+    CALLBACK_PANIC.try_with(|panic| panic.borrow_mut().take()).ok().flatten()
+}
+
+/// Provides access to the silence counter of the error stack used by the current thread.
+fn with_silence_counter<T, F: FnOnce(&mut usize) -> T>(func: F) -> T {
+    #[cfg(h5_have_threadsafe)]
+    {
+        ERROR_HANDLER.with(|counter| func(&mut *counter.borrow_mut()))
+    }
+    #[cfg(not(h5_have_threadsafe))]
+    {
+        let guard = ERROR_HANDLER.lock();
+        let result = func(&mut *guard.borrow_mut());
+        result
+    }
+}
+
+/// Installs the crate's error handler for the error stack of the current thread; this is
+/// done once on the first library call (in each thread, for threadsafe builds), so that
+/// custom error handlers apply to all threads.
+pub(crate) fn init_error_handler() {
+    #[cfg(h5_have_threadsafe)]
+    {
+        // the stack may not be accessible while thread-local storage is being destroyed
+        let installed = ERROR_HANDLER_INSTALLED.try_with(|installed| installed.replace(true));
+        if installed == Ok(false) {
+            h5lock!(H5Eset_auto2(H5E_DEFAULT, Some(default_error_handler), ptr::null_mut()));
+        }
+    }
+    #[cfg(not(h5_have_threadsafe))]
+    {
+        if !ERROR_HANDLER_INSTALLED.load(Ordering::SeqCst) {
+            // taking the library lock installs the handler, see `init_error_handler_locked()`
+            crate::sync::sync(|| ());
+        }
+    }
+}
+
+/// Same as `init_error_handler()`, but called with the library lock held.
+pub(crate) fn init_error_handler_locked() {
+    #[cfg(h5_have_threadsafe)]
+    init_error_handler();
+    #[cfg(not(h5_have_threadsafe))]
+    {
+        // the flag is only set once the handler is in place, so that no thread proceeds
+        // without it; both happen under the lock, so the handler is installed only once
+        if !ERROR_HANDLER_INSTALLED.load(Ordering::SeqCst) {
+            unsafe { H5Eset_auto2(H5E_DEFAULT, Some(default_error_handler), ptr::null_mut()) };
+            ERROR_HANDLER_INSTALLED.store(true, Ordering::SeqCst);
+        }
+    }
+}
+
+#[cfg(feature = "log")]
+fn log_error_stack(stack: &ErrorStack) {
+    log::error!(target: "hdf5", "{:?}", stack);
+}
+
 extern "C" fn default_error_handler(estack: hid_t, _cdata: *mut c_void) -> herr_t {
-    unsafe { H5Eprint2(estack, ptr::null_mut()) }
+    let handler = CUSTOM_ERROR_HANDLER.lock().clone();
+    // with the `log` feature, library errors are logged instead of being printed to stderr
+    #[cfg(feature = "log")]
+    let handler = handler.or_else(|| Some(Arc::new(log_error_stack) as CustomErrorHandler));
+    match handler {
+        Some(handler) => {
+            // errors raised from within the handler itself are not reported to avoid recursion
+            if !IN_CUSTOM_ERROR_HANDLER.with(|flag| flag.replace(true)) {
+                // the stack is copied and restored so that it can still be queried by the caller
+                let stack_id = h5lock!(H5Eget_current_stack());
+                if stack_id >= 0 {
+                    if let Ok(Some(stack)) = ErrorStack::from_stack_id(stack_id) {
+                        catch_callback_panic((), || handler(&stack));
+                    }
+                    h5lock!(H5Eset_current_stack(stack_id));
+                }
+                IN_CUSTOM_ERROR_HANDLER.with(|flag| flag.set(false));
+            }
+            0
+        }
+        None => unsafe { H5Eprint2(estack, ptr::null_mut()) },
+    }
 }
 
 impl SilenceErrors {
@@ -75,21 +332,26 @@ impl SilenceErrors {
     }
 
     fn silence(on: bool) {
-        let guard = ERROR_HANDLER.lock();
-        let counter = &mut *guard.borrow_mut();
-        if on {
-            *counter += 1;
-            if *counter == 1 {
-                h5lock!(H5Eset_auto2(H5E_DEFAULT, None, ptr::null_mut()));
-            }
-        } else {
-            if *counter > 0 {
-                *counter -= 1;
-            }
-            if *counter == 0 {
-                h5lock!(H5Eset_auto2(H5E_DEFAULT, Some(default_error_handler), ptr::null_mut()));
+        init_error_handler();
+        with_silence_counter(|counter| {
+            if on {
+                *counter += 1;
+                if *counter == 1 {
+                    h5lock!(H5Eset_auto2(H5E_DEFAULT, None, ptr::null_mut()));
+                }
+            } else {
+                if *counter > 0 {
+                    *counter -= 1;
+                }
+                if *counter == 0 {
+                    h5lock!(H5Eset_auto2(
+                        H5E_DEFAULT,
+                        Some(default_error_handler),
+                        ptr::null_mut()
+                    ));
+                }
             }
-        }
+        })
     }
 }
 
@@ -103,10 +365,38 @@ pub fn silence_errors() -> SilenceErrors {
     SilenceErrors::new()
 }
 
+/// Runs a closure with automatic error printing disabled, e.g. when probing for
+/// objects that may not exist.
+pub fn silence_errors_scoped<T, F: FnOnce() -> T>(func: F) -> T {
+    let _e = silence_errors();
+    func()
+}
+
+/// Installs a custom handler that is called with the error stack instead of printing it
+/// to stderr (or logging it, if the `log` feature is enabled) whenever a library error
+/// occurs while errors are not silenced.
+///
+/// This allows routing HDF5 diagnostics into application logs; the handler is called
+/// while the library lock is held, so library errors raised by the handler itself are
+/// ignored, and returned errors are not affected by it.
+pub fn set_error_handler<F>(handler: F)
+where
+    F: Fn(&ErrorStack) + Send + Sync + 'static,
+{
+    *CUSTOM_ERROR_HANDLER.lock() = Some(Arc::new(handler));
+    init_error_handler();
+}
+
+/// Removes the custom error handler, reverting to the default error reporting.
+pub fn reset_error_handler() {
+    *CUSTOM_ERROR_HANDLER.lock() = None;
+}
+
 #[derive(Clone)]
 pub struct ErrorStack {
     frames: Vec<ErrorFrame>,
     description: Option<String>,
+    context: Option<String>,
 }
 
 impl Index<usize> for ErrorStack {
@@ -117,6 +407,15 @@ impl Index<usize> for ErrorStack {
     }
 }
 
+impl<'a> IntoIterator for &'a ErrorStack {
+    type Item = &'a ErrorFrame;
+    type IntoIter = slice::Iter<'a, ErrorFrame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.frames()
+    }
+}
+
 impl Default for ErrorStack {
     fn default() -> Self {
         Self::new()
@@ -131,42 +430,48 @@ struct CallbackData {
 impl ErrorStack {
     // This low-level function is not thread-safe and has to be synchronized by the user
     pub fn query() -> Result<Option<Self>> {
+        // known HDF5 bug: H5Eget_msg() may corrupt the current stack, so we copy it first
+        let stack_id = h5lock!(H5Eget_current_stack());
+        ensure!(stack_id >= 0, "failed to copy the current error stack");
+        let stack = Self::from_stack_id(stack_id);
+        h5lock!(H5Eclose_stack(stack_id));
+        stack
+    }
+
+    fn from_stack_id(stack_id: hid_t) -> Result<Option<Self>> {
         extern "C" fn callback(
             _: c_uint, err_desc: *const H5E_error2_t, data: *mut c_void,
         ) -> herr_t {
-            unsafe {
-                let data = &mut *(data as *mut CallbackData);
-                if data.err.is_some() {
-                    return 0;
-                }
-                let closure = |e: H5E_error2_t| -> Result<ErrorFrame> {
-                    let (desc, func) = (string_from_cstr(e.desc), string_from_cstr(e.func_name));
-                    let major = get_h5_str(|m, s| H5Eget_msg(e.maj_num, ptr::null_mut(), m, s))?;
-                    let minor = get_h5_str(|m, s| H5Eget_msg(e.min_num, ptr::null_mut(), m, s))?;
-                    Ok(ErrorFrame::new(&desc, &func, &major, &minor))
-                };
-                match closure(*err_desc) {
-                    Ok(frame) => {
-                        data.stack.push(frame);
-                    }
-                    Err(err) => {
-                        data.err = Some(err);
-                    }
+            let data = unsafe { &mut *(data as *mut CallbackData) };
+            if data.err.is_some() {
+                return 0;
+            }
+            let closure = |e: H5E_error2_t| -> Result<ErrorFrame> {
+                let (desc, func) = (string_from_cstr(e.desc), string_from_cstr(e.func_name));
+                let major =
+                    get_h5_str(|m, s| unsafe { H5Eget_msg(e.maj_num, ptr::null_mut(), m, s) })?;
+                let minor =
+                    get_h5_str(|m, s| unsafe { H5Eget_msg(e.min_num, ptr::null_mut(), m, s) })?;
+                let file = string_from_cstr(e.file_name);
+                Ok(ErrorFrame::new(&desc, &func, &major, &minor)
+                    .with_location(&file, e.line as _)
+                    .with_ids(e.maj_num, e.min_num))
+            };
+            match panic::catch_unwind(AssertUnwindSafe(|| closure(unsafe { *err_desc }))) {
+                Ok(Ok(frame)) => data.stack.push(frame),
+                Ok(Err(err)) => data.err = Some(err),
+                Err(payload) => {
+                    let msg = panic_message(&*payload);
+                    data.err = Some(format!("panic while walking the error stack: {}", msg).into());
                 }
-                0
             }
+            0
         }
 
         let mut data = CallbackData { stack: Self::new(), err: None };
         let data_ptr: *mut c_void = &mut data as *mut _ as *mut _;
 
-        // known HDF5 bug: H5Eget_msg() may corrupt the current stack, so we copy it first
-        let stack_id = h5lock!(H5Eget_current_stack());
-        ensure!(stack_id >= 0, "failed to copy the current error stack");
-        h5lock!({
-            H5Ewalk2(stack_id, H5E_WALK_DOWNWARD, Some(callback), data_ptr);
-            H5Eclose_stack(stack_id);
-        });
+        h5lock!(H5Ewalk2(stack_id, H5E_WALK_DOWNWARD, Some(callback), data_ptr));
 
         match (data.err, data.stack.is_empty()) {
             (Some(err), _) => Err(err),
@@ -176,7 +481,7 @@ impl ErrorStack {
     }
 
     pub fn new() -> Self {
-        Self { frames: Vec::new(), description: None }
+        Self { frames: Vec::new(), description: None, context: None }
     }
 
     pub fn len(&self) -> usize {
@@ -185,21 +490,52 @@ impl ErrorStack {
 
     pub fn push(&mut self, frame: ErrorFrame) {
         self.frames.push(frame);
-        if !self.is_empty() {
-            let top_desc = self.frames[0].description().to_owned();
-            if self.len() == 1 {
-                self.description = Some(top_desc);
-            } else {
-                self.description =
-                    Some(format!("{}: {}", top_desc, self.frames[self.len() - 1].desc()));
-            }
-        }
+        self.update_description();
+    }
+
+    /// Prefixes the description with the context of the failed operation, e.g. the path
+    /// of the object involved.
+    pub fn with_context(mut self, context: &str) -> Self {
+        self.context = Some(match self.context {
+            Some(ref inner) => format!("{}: {}", context, inner),
+            None => context.into(),
+        });
+        self.update_description();
+        self
+    }
+
+    /// Returns the context attached via `with_context()`, if any.
+    pub fn context(&self) -> Option<&str> {
+        self.context.as_deref()
+    }
+
+    fn update_description(&mut self) {
+        let desc = if self.is_empty() {
+            None
+        } else if self.len() == 1 {
+            Some(self.frames[0].description().to_owned())
+        } else {
+            let top_desc = self.frames[0].description();
+            Some(format!("{}: {}", top_desc, self.frames[self.len() - 1].desc()))
+        };
+        self.description = match (&self.context, desc) {
+            (Some(context), Some(desc)) => Some(format!("{}: {}", context, desc)),
+            (Some(context), None) => Some(format!("{}: unknown library error", context)),
+            (None, desc) => desc,
+        };
     }
 
     pub fn is_empty(&self) -> bool {
         self.frames.is_empty()
     }
 
+    /// Returns an iterator over the frames of the stack, starting from the API function
+    /// that was called (the top frame) down to the innermost frame where the error was
+    /// raised.
+    pub fn frames(&self) -> slice::Iter<ErrorFrame> {
+        self.frames.iter()
+    }
+
     pub fn top(&self) -> Option<&ErrorFrame> {
         if self.is_empty() {
             None
@@ -218,6 +554,27 @@ impl ErrorStack {
     pub fn detail(&self) -> Option<String> {
         self.top().and_then(ErrorFrame::detail)
     }
+
+    /// Returns the error category, determined by the innermost frame of the stack that
+    /// can be categorized (the outer frames usually only report the failed API call).
+    pub fn kind(&self) -> ErrorKind {
+        self.frames
+            .iter()
+            .rev()
+            .map(ErrorFrame::kind)
+            .find(|&kind| kind != ErrorKind::Other)
+            .unwrap_or(ErrorKind::Other)
+    }
+}
+
+impl fmt::Debug for ErrorStack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.description())?;
+        for (i, frame) in self.frames().enumerate() {
+            write!(f, "\n  #{:03}: {:?}", i, frame)?;
+        }
+        Ok(())
+    }
 }
 
 /// The error type for HDF5-related functions.
@@ -248,6 +605,30 @@ impl Error {
             Error::HDF5(ref stack) => stack.description(),
         }
     }
+
+    /// Prefixes the error description with the context of the failed operation.
+    pub fn with_context(self, context: &str) -> Self {
+        match self {
+            Error::Internal(desc) => Error::Internal(format!("{}: {}", context, desc)),
+            Error::HDF5(stack) => Error::HDF5(stack.with_context(context)),
+        }
+    }
+
+    /// Returns the error category; errors raised by the high-level API are `Other`.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::HDF5(ref stack) => stack.kind(),
+            Error::Internal(_) => ErrorKind::Other,
+        }
+    }
+
+    /// Returns the error stack captured at the time of failure if this is a library error.
+    pub fn stack(&self) -> Option<&ErrorStack> {
+        match *self {
+            Error::HDF5(ref stack) => Some(stack),
+            Error::Internal(_) => None,
+        }
+    }
 }
 
 impl From<&str> for Error {
@@ -262,10 +643,13 @@ impl From<String> for Error {
     }
 }
 
+/// The alternate form (`{:#?}`) of library errors lists all frames of the error stack,
+/// including the source locations in the library.
 impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Error::Internal(ref desc) => f.write_str(desc),
+            Error::HDF5(ref stack) if f.alternate() => write!(f, "{:?}", stack),
             Error::HDF5(ref stack) => f.write_str(stack.description()),
         }
     }
@@ -289,17 +673,41 @@ impl From<ShapeError> for Error {
     }
 }
 
+impl From<TypeParseError> for Error {
+    fn from(err: TypeParseError) -> Self {
+        err.to_string().into()
+    }
+}
+
+/// Attaches context, e.g. the paths of the file and of the object involved, to errors
+/// raised by high-level operations.
+pub trait ResultExt<T> {
+    fn with_context<F: FnOnce() -> String>(self, func: F) -> Result<T>;
+}
+
+impl<T> ResultExt<T> for Result<T> {
+    fn with_context<F: FnOnce() -> String>(self, func: F) -> Self {
+        self.map_err(|err| err.with_context(&func()))
+    }
+}
+
 pub fn h5check<T>(value: T) -> Result<T>
 where
     T: Integer + Zero + Bounded,
 {
     let maybe_error =
         if T::min_value() < T::zero() { value < T::zero() } else { value == T::zero() };
-
-    if maybe_error {
-        Error::query().map_or_else(|| Ok(value), Err)
-    } else {
-        Ok(value)
+    let panicked = take_callback_panic().map(|msg| format!("panic in callback: {}", msg));
+
+    match (maybe_error, panicked) {
+        (true, panicked) => match (Error::query(), panicked) {
+            (Some(err), Some(msg)) => Err(err.with_context(&msg)),
+            (Some(err), None) => Err(err),
+            (None, Some(msg)) => Err(msg.into()),
+            (None, None) => Ok(value),
+        },
+        (false, Some(msg)) => Err(msg.into()),
+        (false, None) => Ok(value),
     }
 }
 
@@ -310,7 +718,7 @@ pub mod tests {
     use crate::globals::H5P_ROOT;
     use crate::internal_prelude::*;
 
-    use super::ErrorStack;
+    use super::{ErrorFrame, ErrorStack};
 
     #[test]
     pub fn test_error_stack() {
@@ -353,9 +761,183 @@ pub mod tests {
              [Object atom: Unable to find atom information (already closed?)]"
         );
 
+        let (top, bottom) = (&stack[0], &stack[stack.len() - 1]);
+        assert_eq!(
+            (top.func(), top.major(), top.minor()),
+            ("H5Pclose", "Property lists", "Unable to free object")
+        );
+        assert_eq!(top.file(), "H5P.c");
+        assert!(top.line() > 0);
+        assert_eq!(top.major_id(), *crate::globals::H5E_PLIST);
+        assert_eq!(bottom.func(), "H5I_dec_ref");
+        assert_eq!(bottom.major_id(), *crate::globals::H5E_ATOM);
+        assert_ne!(bottom.minor_id(), top.minor_id());
+
+        let debug = format!("{:?}", stack);
+        assert_eq!(debug.lines().count(), stack.len() + 1);
+        assert!(debug.starts_with("H5Pclose(): can't close: can't locate ID\n  #000: H5Pclose(): "));
+        assert!(debug.contains(&format!(" at H5P.c:{}\n", top.line())));
+        let err = Error::HDF5(stack.clone());
+        assert_eq!(format!("{:?}", err), stack.description());
+        assert_eq!(format!("{:#?}", err), debug);
+        assert_eq!(err.stack().unwrap().len(), stack.len());
+        assert!(Error::from("foo").stack().is_none());
+
+        let funcs: Vec<_> = stack.frames().map(ErrorFrame::func).collect();
+        assert_eq!(funcs.len(), stack.len());
+        assert_eq!((funcs[0], funcs[funcs.len() - 1]), ("H5Pclose", "H5I_dec_ref"));
+        let plist_frames = (&stack).into_iter().filter(|f| f.major() == "Property lists");
+        assert_eq!(plist_frames.map(ErrorFrame::func).next(), Some("H5Pclose"));
+
         let empty_stack = ErrorStack::new();
         assert!(empty_stack.is_empty());
         assert_eq!(empty_stack.len(), 0);
+        assert_eq!(empty_stack.frames().len(), 0);
+    }
+
+    #[test]
+    pub fn test_error_handler() {
+        use std::ptr;
+        use std::sync::Arc;
+
+        use hdf5_sys::h5e::{H5E_auto2_t, H5Eget_auto2, H5Eset_auto2, H5E_DEFAULT};
+        use parking_lot::Mutex;
+
+        use super::{default_error_handler, reset_error_handler, set_error_handler};
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let handler_errors = errors.clone();
+        set_error_handler(move |stack| handler_errors.lock().push(stack.description().to_owned()));
+
+        let result = h5lock!({
+            // other tests may be silencing errors concurrently, so force the handler here
+            let mut func: H5E_auto2_t = None;
+            let mut data = ptr::null_mut();
+            H5Eget_auto2(H5E_DEFAULT, &mut func, &mut data);
+            H5Eset_auto2(H5E_DEFAULT, Some(default_error_handler), ptr::null_mut());
+            let plist_id = H5Pcreate(*H5P_ROOT);
+            H5Pclose(plist_id);
+            let result = h5call!(H5Pclose(plist_id));
+            H5Eset_auto2(H5E_DEFAULT, func, data);
+            result
+        });
+        reset_error_handler();
+
+        assert_err!(result, "H5Pclose(): can't close: can't locate ID");
+        assert_eq!(errors.lock().as_slice(), ["H5Pclose(): can't close: can't locate ID"]);
+
+        set_error_handler(|_| panic!("handler failed"));
+        let result = h5lock!({
+            let mut func: H5E_auto2_t = None;
+            let mut data = ptr::null_mut();
+            H5Eget_auto2(H5E_DEFAULT, &mut func, &mut data);
+            H5Eset_auto2(H5E_DEFAULT, Some(default_error_handler), ptr::null_mut());
+            let result = h5call!(H5Pclose(H5I_INVALID_HID));
+            H5Eset_auto2(H5E_DEFAULT, func, data);
+            result
+        });
+        reset_error_handler();
+        assert_err!(result, "panic in callback: handler failed: H5Pclose(): not a property list");
+        assert!(h5call!(H5Pclose(H5Pcreate(*H5P_ROOT))).is_ok());
+    }
+
+    #[test]
+    pub fn test_silence_errors_scoped() {
+        let result = super::silence_errors_scoped(|| {
+            let plist_id = h5try!(H5Pcreate(*H5P_ROOT));
+            h5try!(H5Pclose(plist_id));
+            h5call!(H5Pclose(plist_id))
+        });
+        assert_err!(result, "can't locate ID");
+    }
+
+    #[test]
+    pub fn test_error_kind() {
+        use hdf5_sys::h5d::H5Dread;
+        use hdf5_sys::h5s::H5S_ALL;
+        use hdf5_types::FixedAscii;
+
+        use super::ErrorKind;
+
+        let _e = silence_errors();
+
+        with_tmp_file(|file| {
+            let kind = |err: Error| err.kind();
+            assert_eq!(file.group("foo").map_err(kind).err(), Some(ErrorKind::ObjectNotFound));
+            file.create_group("foo").unwrap();
+            assert_eq!(
+                file.create_group("foo").map_err(kind).err(),
+                Some(ErrorKind::AlreadyExists)
+            );
+            let ds = file.new_dataset::<i32>().create("ds", 3).unwrap();
+            // bypass the conversion checks in the high-level API
+            let dtype = Datatype::from_type::<FixedAscii<[u8; 4]>>().unwrap();
+            let mut buf = vec![0_u8; 12];
+            let result = h5call!(H5Dread(
+                ds.id(),
+                dtype.id(),
+                H5S_ALL,
+                H5S_ALL,
+                H5P_DEFAULT,
+                buf.as_mut_ptr() as *mut _
+            ));
+            assert_eq!(result.map_err(kind).err(), Some(ErrorKind::TypeMismatch));
+        });
+
+        with_tmp_path(|path| {
+            std::fs::write(&path, b"foo").unwrap();
+            assert_eq!(File::open(&path).map_err(|e| e.kind()).err(), Some(ErrorKind::NotHdf5));
+        });
+
+        let result = h5call!(H5Pclose(H5I_INVALID_HID));
+        assert_eq!(result.map_err(|e| e.kind()).err(), Some(ErrorKind::InvalidArgument));
+        assert_eq!(Error::from("foo").kind(), ErrorKind::Other);
+        assert_eq!(ErrorStack::new().kind(), ErrorKind::Other);
+    }
+
+    #[test]
+    pub fn test_error_context() {
+        let err = Error::from("foo").with_context("bar").with_context("baz");
+        assert_eq!(err.description(), "baz: bar: foo");
+
+        let mut stack = ErrorStack::new().with_context("bar");
+        assert_eq!(stack.description(), "bar: unknown library error");
+        stack.push(ErrorFrame::new("desc", "func", "major", "minor"));
+        let err = Error::HDF5(stack).with_context("baz");
+        assert_eq!(err.description(), "baz: bar: func(): desc");
+        assert_eq!(err.stack().unwrap().context(), Some("baz: bar"));
+        assert_eq!(err.stack().unwrap().top().unwrap().description(), "func(): desc");
+    }
+
+    #[test]
+    #[cfg(h5_have_threadsafe)]
+    pub fn test_silence_errors_per_thread() {
+        use std::ptr;
+        use std::thread;
+
+        use hdf5_sys::h5e::{H5E_auto2_t, H5Eget_auto2, H5E_DEFAULT};
+
+        use super::default_error_handler;
+
+        fn auto_func() -> H5E_auto2_t {
+            let mut func: H5E_auto2_t = None;
+            let mut data = ptr::null_mut();
+            h5lock!(H5Eget_auto2(H5E_DEFAULT, &mut func, &mut data));
+            func
+        }
+
+        let _e = silence_errors();
+        assert_eq!(auto_func(), None);
+        thread::spawn(|| {
+            assert_eq!(auto_func(), Some(default_error_handler as _));
+            let _e = silence_errors();
+            assert_eq!(auto_func(), None);
+            let result = h5call!(H5Pclose(H5I_INVALID_HID)).map_err(|e| e.kind());
+            assert_eq!(result.err(), Some(super::ErrorKind::InvalidArgument));
+        })
+        .join()
+        .unwrap();
+        assert_eq!(auto_func(), None);
     }
 
     #[test]
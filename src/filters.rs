@@ -1,28 +1,255 @@
+use std::collections::BTreeMap;
+use std::ptr;
+
 use crate::globals::H5P_DATASET_CREATE;
 use crate::internal_prelude::*;
 
 use hdf5_sys::{
     h5p::{
-        H5Pcreate, H5Pget_filter2, H5Pget_nfilters, H5Pset_deflate, H5Pset_fletcher32,
-        H5Pset_scaleoffset, H5Pset_shuffle, H5Pset_szip,
+        H5Pcreate, H5Pget_filter2, H5Pget_filter_by_id2, H5Pget_nfilters, H5Pmodify_filter,
+        H5Pset_deflate, H5Pset_filter, H5Pset_fletcher32, H5Pset_scaleoffset, H5Pset_shuffle,
+        H5Pset_szip,
     },
     h5t::{H5Tget_class, H5T_FLOAT, H5T_INTEGER},
     h5z::{
         H5Z_filter_t, H5Zfilter_avail, H5Zget_filter_info, H5Z_FILTER_CONFIG_DECODE_ENABLED,
         H5Z_FILTER_CONFIG_ENCODE_ENABLED, H5Z_FILTER_DEFLATE, H5Z_FILTER_FLETCHER32,
-        H5Z_FILTER_SCALEOFFSET, H5Z_FILTER_SHUFFLE, H5Z_FILTER_SZIP, H5Z_SO_FLOAT_DSCALE,
-        H5Z_SO_INT, H5_SZIP_EC_OPTION_MASK, H5_SZIP_NN_OPTION_MASK,
+        H5Z_FILTER_SCALEOFFSET, H5Z_FILTER_SHUFFLE, H5Z_FILTER_SZIP, H5Z_FLAG_MANDATORY,
+        H5Z_FLAG_OPTIONAL, H5Z_SO_FLOAT_DSCALE, H5Z_SO_FLOAT_ESCALE, H5Z_SO_INT,
+        H5_SZIP_EC_OPTION_MASK, H5_SZIP_NN_OPTION_MASK,
     },
 };
 
+/// Returns `true` if a filter with the specified identifier is registered.
+pub fn is_available(id: H5Z_filter_t) -> bool {
+    h5lock!(H5Zfilter_avail(id) == 1)
+}
+
 /// Returns `true` if gzip filter is available.
 pub fn gzip_available() -> bool {
-    h5lock!(H5Zfilter_avail(H5Z_FILTER_DEFLATE) == 1)
+    is_available(H5Z_FILTER_DEFLATE)
 }
 
 /// Returns `true` if szip filter is available.
 pub fn szip_available() -> bool {
-    h5lock!(H5Zfilter_avail(H5Z_FILTER_SZIP) == 1)
+    is_available(H5Z_FILTER_SZIP)
+}
+
+/// Registered identifier of the zstd filter plugin (not bundled with HDF5).
+pub const ZSTD_FILTER_ID: H5Z_filter_t = 32015;
+
+/// Returns `true` if zstd filter plugin is available.
+pub fn zstd_available() -> bool {
+    is_available(ZSTD_FILTER_ID)
+}
+
+/// Compression presets, see `DatasetBuilder::compress_preset()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Preset {
+    /// Optimized for speed: zstd level 1 or gzip level 1.
+    Fast,
+    /// Balancing speed and size: zstd level 3 or gzip level 4.
+    Balanced,
+    /// Optimized for size: zstd level 19 or gzip level 9.
+    Best,
+}
+
+impl Preset {
+    /// Returns the gzip compression level used by the preset.
+    pub fn gzip_level(self) -> u8 {
+        match self {
+            Self::Fast => 1,
+            Self::Balanced => 4,
+            Self::Best => 9,
+        }
+    }
+
+    /// Returns the zstd compression level used by the preset.
+    pub fn zstd_level(self) -> u8 {
+        match self {
+            Self::Fast => 1,
+            Self::Balanced => 3,
+            Self::Best => 19,
+        }
+    }
+}
+
+/// Encoding and decoding capabilities of a registered filter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FilterInfo {
+    /// Whether the filter can be used for writing data.
+    pub encode_enabled: bool,
+    /// Whether the filter can be used for reading data.
+    pub decode_enabled: bool,
+}
+
+/// Returns encoding/decoding capabilities of a filter with the specified identifier.
+///
+/// Fails if the filter is not available.
+pub fn filter_info(id: H5Z_filter_t) -> Result<FilterInfo> {
+    ensure!(is_available(id), "Filter not available: {}", id);
+    let mut flags: c_uint = 0;
+    h5try!(H5Zget_filter_info(id, &mut flags));
+    Ok(FilterInfo {
+        encode_enabled: flags & H5Z_FILTER_CONFIG_ENCODE_ENABLED != 0,
+        decode_enabled: flags & H5Z_FILTER_CONFIG_DECODE_ENABLED != 0,
+    })
+}
+
+/// A single entry of the filter pipeline.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Filter {
+    /// Gzip compression with a given level.
+    Deflate(u8),
+    /// Byte shuffling.
+    Shuffle,
+    /// Fletcher32 checksum.
+    Fletcher32,
+    /// Szip compression, `(nn, pixels_per_block)`.
+    SZip(bool, u8),
+    /// Scale-offset filter with a given factor.
+    ScaleOffset(u32),
+    /// Any other filter, identified by its registered filter id.
+    ///
+    /// When reading a filter pipeline, `name` is the name stored in the file (or of the
+    /// registered filter); it is ignored when the filter is applied to a dataset.
+    User { id: H5Z_filter_t, cd_values: Vec<c_uint>, name: String },
+}
+
+impl Filter {
+    /// Returns the identifier of the filter.
+    pub fn id(&self) -> H5Z_filter_t {
+        match *self {
+            Self::Deflate(_) => H5Z_FILTER_DEFLATE,
+            Self::Shuffle => H5Z_FILTER_SHUFFLE,
+            Self::Fletcher32 => H5Z_FILTER_FLETCHER32,
+            Self::SZip(_, _) => H5Z_FILTER_SZIP,
+            Self::ScaleOffset(_) => H5Z_FILTER_SCALEOFFSET,
+            Self::User { id, .. } => id,
+        }
+    }
+}
+
+fn is_builtin(id: H5Z_filter_t) -> bool {
+    [
+        H5Z_FILTER_DEFLATE,
+        H5Z_FILTER_SHUFFLE,
+        H5Z_FILTER_FLETCHER32,
+        H5Z_FILTER_SZIP,
+        H5Z_FILTER_SCALEOFFSET,
+    ]
+    .contains(&id)
+}
+
+fn is_optional_by_default(id: H5Z_filter_t) -> bool {
+    is_builtin(id) && id != H5Z_FILTER_FLETCHER32
+}
+
+fn set_filter_optional(plist_id: hid_t, id: H5Z_filter_t, optional: bool) -> Result<()> {
+    let mut flags: c_uint = 0;
+    let mut values: Vec<c_uint> = vec![0; 16];
+    let mut n_elements: size_t = values.len();
+    loop {
+        h5try!(H5Pget_filter_by_id2(
+            plist_id,
+            id,
+            &mut flags,
+            &mut n_elements,
+            values.as_mut_ptr(),
+            0,
+            ptr::null_mut(),
+            ptr::null_mut()
+        ));
+        if n_elements <= values.len() {
+            break;
+        }
+        values.resize(n_elements, 0);
+    }
+    flags = if optional { H5Z_FLAG_OPTIONAL } else { H5Z_FLAG_MANDATORY };
+    h5try!(H5Pmodify_filter(plist_id, id, flags, n_elements, values.as_ptr()));
+    Ok(())
+}
+
+/// A filter as stored in the pipeline of a creation property list.
+pub(crate) struct RawFilter {
+    pub id: H5Z_filter_t,
+    pub flags: c_uint,
+    pub cd_values: Vec<c_uint>,
+    pub name: String,
+}
+
+/// Returns the filters of a creation property list in the order they are applied.
+pub(crate) fn raw_pipeline(plist: &PropertyList) -> Result<Vec<RawFilter>> {
+    h5lock!({
+        let id = plist.id();
+        let n_filters: c_int = h5try!(H5Pget_nfilters(id));
+        let mut pipeline = Vec::with_capacity(n_filters as _);
+
+        for idx in 0..n_filters {
+            let mut flags: c_uint = 0;
+            let mut values: Vec<c_uint> = vec![0; 16];
+            let mut n_elements: size_t = values.len();
+            let mut name: Vec<c_char> = vec![0; 257];
+            let mut filter_config: c_uint = 0;
+
+            let mut get_filter = |values: &mut Vec<c_uint>, n_elements: &mut size_t| {
+                H5Pget_filter2(
+                    id,
+                    idx as _,
+                    &mut flags,
+                    n_elements,
+                    values.as_mut_ptr(),
+                    256,
+                    name.as_mut_ptr(),
+                    &mut filter_config,
+                )
+            };
+            let mut code = get_filter(&mut values, &mut n_elements);
+            while n_elements > values.len() {
+                values.resize(n_elements, 0);
+                code = get_filter(&mut values, &mut n_elements);
+            }
+            values.truncate(n_elements);
+            let code = h5check(code)?;
+            let name = string_from_cstr(name.as_ptr());
+            pipeline.push(RawFilter { id: code, flags, cd_values: values, name });
+        }
+
+        Ok(pipeline)
+    })
+}
+
+/// Returns the filters of a dataset creation property list in the order they are
+/// applied, along with whether each of them is optional.
+pub(crate) fn dcpl_pipeline(dcpl: &PropertyList) -> Result<Vec<(Filter, bool)>> {
+    let mut pipeline = Vec::new();
+    for raw in raw_pipeline(dcpl)? {
+        let values = raw.cd_values;
+        let filter = match raw.id {
+            H5Z_FILTER_DEFLATE => Filter::Deflate(values[0] as _),
+            H5Z_FILTER_SZIP => {
+                let nn = match values[0] {
+                    v if v & H5_SZIP_EC_OPTION_MASK != 0 => false,
+                    v if v & H5_SZIP_NN_OPTION_MASK != 0 => true,
+                    _ => fail!("Unknown szip method: {:?}", values[0]),
+                };
+                Filter::SZip(nn, values[1] as _)
+            }
+            H5Z_FILTER_SHUFFLE => Filter::Shuffle,
+            H5Z_FILTER_FLETCHER32 => Filter::Fletcher32,
+            H5Z_FILTER_SCALEOFFSET => {
+                ensure!(
+                    values[0] != H5Z_SO_FLOAT_ESCALE as c_uint,
+                    "Unsupported scale-offset method: E-scale"
+                );
+                Filter::ScaleOffset(values[1])
+            }
+            id if id > 0 => Filter::User { id, cd_values: values, name: raw.name },
+            id => fail!("Unsupported filter: {:?}", id),
+        };
+        pipeline.push((filter, raw.flags & H5Z_FLAG_OPTIONAL != 0));
+    }
+    Ok(pipeline)
 }
 
 /// HDF5 filters and compression options.
@@ -33,11 +260,21 @@ pub struct Filters {
     shuffle: bool,
     fletcher32: bool,
     scale_offset: Option<u32>,
+    user: Vec<Filter>,
+    optional: BTreeMap<H5Z_filter_t, bool>,
 }
 
 impl Default for Filters {
     fn default() -> Self {
-        Self { gzip: None, szip: None, shuffle: false, fletcher32: false, scale_offset: None }
+        Self {
+            gzip: None,
+            szip: None,
+            shuffle: false,
+            fletcher32: false,
+            scale_offset: None,
+            user: Vec::new(),
+            optional: BTreeMap::new(),
+        }
     }
 }
 
@@ -108,7 +345,15 @@ impl Filters {
         self.fletcher32
     }
 
-    /// Enable scale-offset filter with a specified factor (0 means automatic).
+    /// Enable scale-offset filter with a specified factor.
+    ///
+    /// For integer datatypes, the factor is the minimum number of bits to keep
+    /// (0 means it is computed automatically, which makes the compression lossless).
+    ///
+    /// For floating-point datatypes, the D-scale method is used and the factor is the
+    /// number of decimal digits to retain after the decimal point; it must be positive.
+    /// This compression is lossy: values are only retained with the absolute precision of
+    /// `0.5 * 10^-factor`.
     pub fn scale_offset(&mut self, scale_offset: u32) -> &mut Self {
         self.scale_offset = Some(scale_offset);
         self
@@ -125,6 +370,73 @@ impl Filters {
         self.scale_offset
     }
 
+    /// Append a user-defined (or any other non-builtin) filter to the pipeline.
+    ///
+    /// User filters are applied after all of the builtin ones, in the order of insertion.
+    pub fn user(&mut self, id: H5Z_filter_t, cd_values: &[c_uint]) -> &mut Self {
+        self.user.push(Filter::User { id, cd_values: cd_values.to_vec(), name: String::new() });
+        self
+    }
+
+    /// Remove all user-defined filters.
+    pub fn no_user(&mut self) -> &mut Self {
+        self.user.clear();
+        self
+    }
+
+    pub(crate) fn remove_user(&mut self, id: H5Z_filter_t) -> &mut Self {
+        self.user.retain(|filter| filter.id() != id);
+        self
+    }
+
+    /// Get the current user-defined filters (all of them are `Filter::User`).
+    pub fn get_user(&self) -> &[Filter] {
+        &self.user
+    }
+
+    /// Mark a filter in the pipeline as optional or mandatory.
+    ///
+    /// If an optional filter fails when writing a chunk (e.g. because compressing it
+    /// would not make it any smaller), the chunk is stored without it, whereas a failure
+    /// of a mandatory filter causes the write to fail. By default, gzip, szip, shuffle and
+    /// scale-offset filters are optional, while fletcher32 and user filters are mandatory.
+    ///
+    /// The setting has no effect if the filter is not enabled.
+    pub fn optional(&mut self, id: H5Z_filter_t, optional: bool) -> &mut Self {
+        if optional == is_optional_by_default(id) {
+            self.optional.remove(&id);
+        } else {
+            self.optional.insert(id, optional);
+        }
+        self
+    }
+
+    /// Returns `true` if a filter with the specified identifier is optional.
+    pub fn is_optional(&self, id: H5Z_filter_t) -> bool {
+        self.optional.get(&id).cloned().unwrap_or_else(|| is_optional_by_default(id))
+    }
+
+    /// Returns the complete filter pipeline in the order the filters are applied.
+    pub fn pipeline(&self) -> Vec<Filter> {
+        let mut pipeline = Vec::new();
+        if self.fletcher32 {
+            pipeline.push(Filter::Fletcher32);
+        }
+        if let Some(offset) = self.scale_offset {
+            pipeline.push(Filter::ScaleOffset(offset));
+        }
+        if self.shuffle {
+            pipeline.push(Filter::Shuffle);
+        }
+        if let Some(level) = self.gzip {
+            pipeline.push(Filter::Deflate(level));
+        } else if let Some((nn, pixels_per_block)) = self.szip {
+            pipeline.push(Filter::SZip(nn, pixels_per_block));
+        }
+        pipeline.extend(self.user.iter().cloned());
+        pipeline
+    }
+
     /// Enable gzip filter with default settings (compression level 4).
     pub fn gzip_default(&mut self) -> &mut Self {
         self.gzip = Some(4);
@@ -144,6 +456,7 @@ impl Filters {
             || self.shuffle
             || self.fletcher32
             || self.scale_offset.is_some()
+            || !self.user.is_empty()
     }
 
     /// Verify whether the filters configuration is valid.
@@ -170,101 +483,65 @@ impl Filters {
         if self.scale_offset.is_some() && self.fletcher32 {
             fail!("Cannot use lossy scale-offset filter with fletcher32.");
         }
+        for filter in &self.user {
+            match filter.id() {
+                id if is_builtin(id) => {
+                    fail!("Builtin filter cannot be added as a user filter: {}", id)
+                }
+                id => ensure!(id > 0, "Invalid user filter id: {}", id),
+            }
+        }
         Ok(())
     }
 
     #[doc(hidden)]
     pub fn from_dcpl(dcpl: &PropertyList) -> Result<Self> {
         let mut filters = Self::default();
-        h5lock!({
-            let id = dcpl.id();
-            let n_filters: c_int = h5try!(H5Pget_nfilters(id));
-
-            for idx in 0..n_filters {
-                let flags: *mut c_uint = &mut 0;
-                let n_elements: *mut size_t = &mut 16;
-
-                let mut values: Vec<c_uint> = Vec::with_capacity(16);
-                values.set_len(16);
-
-                let mut name: Vec<c_char> = Vec::with_capacity(256);
-                name.set_len(256);
-
-                let filter_config: *mut c_uint = &mut 0;
-
-                let code = H5Pget_filter2(
-                    id,
-                    idx as _,
-                    flags,
-                    n_elements,
-                    values.as_mut_ptr(),
-                    256,
-                    name.as_mut_ptr(),
-                    filter_config,
-                );
-                name.push(0);
-
-                match code {
-                    H5Z_FILTER_DEFLATE => {
-                        filters.gzip(values[0] as _);
-                    }
-                    H5Z_FILTER_SZIP => {
-                        let nn = match values[0] {
-                            v if v & H5_SZIP_EC_OPTION_MASK != 0 => false,
-                            v if v & H5_SZIP_NN_OPTION_MASK != 0 => true,
-                            _ => fail!("Unknown szip method: {:?}", values[0]),
-                        };
-                        filters.szip(nn, values[1] as _);
-                    }
-                    H5Z_FILTER_SHUFFLE => {
-                        filters.shuffle(true);
-                    }
-                    H5Z_FILTER_FLETCHER32 => {
-                        filters.fletcher32(true);
-                    }
-                    H5Z_FILTER_SCALEOFFSET => {
-                        filters.scale_offset(values[1]);
-                    }
-                    _ => fail!("Unsupported filter: {:?}", code),
-                };
-            }
-
-            Ok(())
-        })
-        .and(filters.validate().and(Ok(filters)))
+        for (filter, optional) in dcpl_pipeline(dcpl)? {
+            filters.optional(filter.id(), optional);
+            match filter {
+                Filter::Deflate(level) => filters.gzip(level),
+                Filter::SZip(nn, pixels_per_block) => filters.szip(nn, pixels_per_block),
+                Filter::Shuffle => filters.shuffle(true),
+                Filter::Fletcher32 => filters.fletcher32(true),
+                Filter::ScaleOffset(offset) => filters.scale_offset(offset),
+                user @ Filter::User { .. } => {
+                    filters.user.push(user);
+                    &mut filters
+                }
+            };
+        }
+        filters.validate().and(Ok(filters))
     }
 
     fn ensure_available(&self, name: &str, code: H5Z_filter_t) -> Result<()> {
-        ensure!(h5lock!(H5Zfilter_avail(code) == 1), "Filter not available: {}", name);
-
-        let flags: *mut c_uint = &mut 0;
-        h5try!(H5Zget_filter_info(code, flags));
-
-        ensure!(
-            unsafe { *flags & H5Z_FILTER_CONFIG_ENCODE_ENABLED != 0 },
-            "Encoding is not enabled for filter: {}",
-            name
-        );
-        ensure!(
-            unsafe { *flags & H5Z_FILTER_CONFIG_DECODE_ENABLED != 0 },
-            "Decoding is not enabled for filter: {}",
-            name
-        );
+        ensure!(is_available(code), "Filter not available: {}", name);
+        let info = filter_info(code)?;
+        ensure!(info.encode_enabled, "Encoding is not enabled for filter: {}", name);
+        ensure!(info.decode_enabled, "Decoding is not enabled for filter: {}", name);
         Ok(())
     }
 
     #[doc(hidden)]
     pub fn to_dcpl(&self, datatype: &Datatype) -> Result<PropertyList> {
+        h5lock!({
+            let plist = PropertyList::from_id(H5Pcreate(*H5P_DATASET_CREATE))?;
+            self.apply_to_dcpl(&plist, datatype)?;
+            Ok(plist)
+        })
+    }
+
+    /// Appends the filters to the pipeline of a dataset creation property list.
+    pub(crate) fn apply_to_dcpl(&self, plist: &PropertyList, datatype: &Datatype) -> Result<()> {
         self.validate()?;
 
         h5lock!({
-            let plist = PropertyList::from_id(H5Pcreate(*H5P_DATASET_CREATE))?;
             let id = plist.id();
 
             // fletcher32
             if self.fletcher32 {
                 self.ensure_available("fletcher32", H5Z_FILTER_FLETCHER32)?;
-                H5Pset_fletcher32(id);
+                h5try!(H5Pset_fletcher32(id));
             }
 
             // scale-offset
@@ -272,14 +549,14 @@ impl Filters {
                 self.ensure_available("scaleoffset", H5Z_FILTER_SCALEOFFSET)?;
                 match H5Tget_class(datatype.id()) {
                     H5T_INTEGER => {
-                        H5Pset_scaleoffset(id, H5Z_SO_INT, offset as _);
+                        h5try!(H5Pset_scaleoffset(id, H5Z_SO_INT, offset as _));
                     }
                     H5T_FLOAT => {
                         ensure!(
                             offset > 0,
                             "Can only use positive scale-offset factor with floats"
                         );
-                        H5Pset_scaleoffset(id, H5Z_SO_FLOAT_DSCALE, offset as _);
+                        h5try!(H5Pset_scaleoffset(id, H5Z_SO_FLOAT_DSCALE, offset as _));
                     }
                     _ => {
                         fail!("Can only use scale/offset with integer/float datatypes.");
@@ -303,16 +580,38 @@ impl Filters {
                 h5try!(H5Pset_szip(id, options, c_uint::from(pixels_per_block)));
             }
 
-            Ok(plist)
+            // user-defined filters
+            for filter in &self.user {
+                if let Filter::User { id: code, ref cd_values, .. } = *filter {
+                    self.ensure_available(&format!("{}", code), code)?;
+                    let flags =
+                        if self.is_optional(code) { H5Z_FLAG_OPTIONAL } else { H5Z_FLAG_MANDATORY };
+                    h5try!(H5Pset_filter(id, code, flags, cd_values.len(), cd_values.as_ptr()));
+                }
+            }
+
+            // optional/mandatory overrides for builtin filters
+            let pipeline = self.pipeline();
+            for (&code, &optional) in &self.optional {
+                if is_builtin(code) && pipeline.iter().any(|f| f.id() == code) {
+                    set_filter_optional(id, code, optional)?;
+                }
+            }
+
+            Ok(())
         })
     }
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::{gzip_available, szip_available};
+    use super::{filter_info, gzip_available, is_available, szip_available, Filter, FilterInfo};
     use crate::internal_prelude::*;
 
+    use hdf5_sys::h5z::{
+        H5Z_FILTER_DEFLATE, H5Z_FILTER_FLETCHER32, H5Z_FILTER_NBIT, H5Z_FILTER_SHUFFLE,
+    };
+
     fn make_filters<T: H5Type>(filters: &Filters) -> Result<Filters> {
         let datatype = Datatype::from_type::<T>().unwrap();
         let dcpl = filters.to_dcpl(&datatype)?;
@@ -323,6 +622,20 @@ pub mod tests {
         assert_eq!(make_filters::<T>(filters).unwrap(), *filters);
     }
 
+    #[test]
+    pub fn test_filter_info() {
+        let _e = silence_errors();
+
+        assert!(is_available(H5Z_FILTER_SHUFFLE));
+        assert!(is_available(H5Z_FILTER_FLETCHER32));
+        assert!(!is_available(32000));
+
+        let info = FilterInfo { encode_enabled: true, decode_enabled: true };
+        assert_eq!(filter_info(H5Z_FILTER_SHUFFLE).unwrap(), info);
+        assert_eq!(filter_info(H5Z_FILTER_FLETCHER32).unwrap(), info);
+        assert_err!(filter_info(32000), "Filter not available: 32000");
+    }
+
     #[test]
     pub fn test_szip() {
         let _e = silence_errors();
@@ -457,6 +770,61 @@ pub mod tests {
         assert_eq!(filters2, filters);
     }
 
+    #[test]
+    pub fn test_user_filters() {
+        let _e = silence_errors();
+
+        assert!(Filters::new().get_user().is_empty());
+        let mut filters = Filters::new();
+        filters.shuffle(true).user(H5Z_FILTER_NBIT, &[1, 2, 3]);
+        assert_eq!(
+            filters.get_user(),
+            &[Filter::User { id: H5Z_FILTER_NBIT, cd_values: vec![1, 2, 3], name: "".into() }]
+        );
+        assert_eq!(filters.pipeline()[0], Filter::Shuffle);
+        assert_eq!(filters.pipeline()[1].id(), H5Z_FILTER_NBIT);
+        assert!(filters.clone().no_user().get_user().is_empty());
+
+        let filters2 = make_filters::<u32>(&filters).unwrap();
+        assert!(filters2.get_shuffle());
+        assert_eq!(
+            filters2.get_user(),
+            &[Filter::User { id: H5Z_FILTER_NBIT, cd_values: vec![1, 2, 3], name: "nbit".into() }]
+        );
+
+        assert_err!(
+            make_filters::<u32>(Filters::new().user(H5Z_FILTER_DEFLATE, &[])),
+            "Builtin filter cannot be added as a user filter: 1"
+        );
+        assert_err!(make_filters::<u32>(Filters::new().user(32000, &[])), "Filter not available");
+    }
+
+    #[test]
+    pub fn test_optional() {
+        assert!(!Filters::new().is_optional(H5Z_FILTER_FLETCHER32));
+        assert!(Filters::new().is_optional(H5Z_FILTER_SHUFFLE));
+        assert!(!Filters::new().is_optional(H5Z_FILTER_NBIT));
+        assert!(Filters::new().optional(H5Z_FILTER_FLETCHER32, true).is_optional(3));
+        assert!(!Filters::new().optional(H5Z_FILTER_SHUFFLE, false).is_optional(2));
+        assert_eq!(*Filters::new().optional(H5Z_FILTER_SHUFFLE, true), Filters::new());
+
+        check_roundtrip::<u32>(Filters::new().shuffle(true).optional(H5Z_FILTER_SHUFFLE, false));
+        check_roundtrip::<u32>(
+            Filters::new().fletcher32(true).optional(H5Z_FILTER_FLETCHER32, true),
+        );
+        if gzip_available() {
+            check_roundtrip::<u32>(Filters::new().gzip(4).optional(H5Z_FILTER_DEFLATE, false));
+        }
+
+        let filters = make_filters::<u32>(
+            Filters::new().user(H5Z_FILTER_NBIT, &[]).optional(H5Z_FILTER_NBIT, true),
+        )
+        .unwrap();
+        assert!(filters.is_optional(H5Z_FILTER_NBIT));
+        let filters = make_filters::<u32>(Filters::new().user(H5Z_FILTER_NBIT, &[])).unwrap();
+        assert!(!filters.is_optional(H5Z_FILTER_NBIT));
+    }
+
     #[test]
     pub fn test_has_filters() {
         assert_eq!(Filters::default().has_filters(), false);
@@ -465,5 +833,6 @@ pub mod tests {
         assert_eq!(Filters::default().fletcher32(true).has_filters(), true);
         assert_eq!(Filters::default().shuffle(true).has_filters(), true);
         assert_eq!(Filters::default().scale_offset(2).has_filters(), true);
+        assert_eq!(Filters::default().user(H5Z_FILTER_NBIT, &[]).has_filters(), true);
     }
 }
@@ -57,6 +57,13 @@ impl Registry {
     }
 }
 
+/// A reference-counted HDF5 identifier shared by all objects wrapping it.
+///
+/// Handles (and hence all objects built on top of them) are `Send` and `Sync`
+/// regardless of whether the library was built as threadsafe: every library call made
+/// by this crate, including reference counting in `Clone` and `Drop`, is serialized via
+/// the global lock (see `sync()`), so objects may be freely shared and dropped across
+/// threads.
 pub struct Handle {
     id: Arc<RwLock<hid_t>>,
 }
@@ -88,9 +95,11 @@ impl Handle {
     }
 
     pub fn incref(&self) {
-        if is_valid_user_id(self.id()) {
-            h5lock!(H5Iinc_ref(self.id()));
-        }
+        h5lock!({
+            if is_valid_user_id(self.id()) {
+                H5Iinc_ref(self.id());
+            }
+        })
     }
 
     pub fn decref(&self) {
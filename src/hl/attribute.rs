@@ -0,0 +1,814 @@
+use std::ffi::CStr;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::ptr;
+use std::str::FromStr;
+use std::vec;
+
+use ndarray::ArrayView;
+
+use hdf5_sys::{
+    h5::{hsize_t, H5_index_t, H5_iter_order_t},
+    h5a::{
+        H5A_info_t, H5A_operator2_t, H5Acreate2, H5Adelete, H5Aget_create_plist, H5Aget_name,
+        H5Aiterate2, H5Aread, H5Awrite,
+    },
+    h5d::H5Dvlen_reclaim,
+};
+
+use hdf5_types::{TypeDescriptor, VarLenUnicode};
+
+use crate::hl::datatype::{stored_descriptor, ByteOrder, StringPadding};
+use crate::hl::plist::attribute_create::{AttributeCreate, AttributeCreateBuilder, CharEncoding};
+use crate::internal_prelude::*;
+
+/// Represents the HDF5 attribute object.
+#[repr(transparent)]
+#[derive(Clone)]
+pub struct Attribute(Handle);
+
+impl ObjectClass for Attribute {
+    const NAME: &'static str = "attribute";
+    const VALID_TYPES: &'static [H5I_type_t] = &[H5I_ATTR];
+
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+
+    fn short_repr(&self) -> Option<String> {
+        Some(format!("\"{}\"", self.name()))
+    }
+}
+
+impl Debug for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.debug_fmt(f)
+    }
+}
+
+impl Deref for Attribute {
+    type Target = Container;
+
+    fn deref(&self) -> &Container {
+        unsafe { self.transmute() }
+    }
+}
+
+impl Attribute {
+    /// Returns the name of the attribute (or empty string if the attribute is invalid).
+    pub fn name(&self) -> String {
+        h5lock!(get_h5_str(|m, s| H5Aget_name(self.id(), s, m)).unwrap_or_else(|_| "".to_string()))
+    }
+
+    /// Returns a copy of the attribute creation property list.
+    pub fn create_plist(&self) -> Result<AttributeCreate> {
+        h5lock!(AttributeCreate::from_id(h5try!(H5Aget_create_plist(self.id()))))
+    }
+
+    /// A short alias for `create_plist()`.
+    pub fn acpl(&self) -> Result<AttributeCreate> {
+        self.create_plist()
+    }
+
+    /// Reads a scalar attribute, allowing conversions up to a given level.
+    ///
+    /// Unlike `read_scalar()` which allows any conversion, this makes narrowing reads
+    /// (e.g. `f64` into `f32`) an explicit decision: with `Conversion::NoOp` the stored
+    /// datatype must match `T` exactly, `Conversion::Hard` additionally allows compiled
+    /// conversions between numeric types, and `Conversion::Soft` allows any conversion.
+    pub fn read_scalar_as<T: H5Type>(&self, conv: Conversion) -> Result<T> {
+        self.as_reader().conversion(conv).read_scalar()
+    }
+
+    /// Reads a scalar string attribute.
+    ///
+    /// Both fixed-length and variable-length strings are supported, in either ASCII or
+    /// UTF-8 encoding.
+    pub fn read_string(&self) -> Result<String> {
+        ensure!(
+            self.is_scalar(),
+            "expected a scalar string attribute, got shape {:?}",
+            self.shape()
+        );
+        self.read_strings().map(|mut strings| strings.remove(0))
+    }
+
+    /// Reads all elements of a string attribute in row-major order.
+    ///
+    /// Both fixed-length and variable-length strings are supported, in either ASCII or
+    /// UTF-8 encoding.
+    pub fn read_strings(&self) -> Result<Vec<String>> {
+        h5lock!({
+            let datatype = self.dtype()?;
+            let varlen = match datatype.to_descriptor()? {
+                TypeDescriptor::VarLenAscii | TypeDescriptor::VarLenUnicode => true,
+                TypeDescriptor::FixedAscii(_) | TypeDescriptor::FixedUnicode(_) => false,
+                _ => fail!("expected a string attribute"),
+            };
+            let dataspace = self.space()?;
+            let (size, count) = (datatype.size(), dataspace.try_size()?);
+
+            let bytes: Vec<Vec<u8>> = if varlen {
+                let mut buf: Vec<*mut c_char> = vec![ptr::null_mut(); count];
+                let buf_ptr = buf.as_mut_ptr() as *mut c_void;
+                h5try!(H5Aread(self.id(), datatype.id(), buf_ptr));
+                let bytes = buf
+                    .iter()
+                    .map(|&ptr| {
+                        if ptr.is_null() {
+                            Vec::new()
+                        } else {
+                            CStr::from_ptr(ptr).to_bytes().to_vec()
+                        }
+                    })
+                    .collect();
+                h5try!(H5Dvlen_reclaim(datatype.id(), dataspace.id(), H5P_DEFAULT, buf_ptr));
+                bytes
+            } else {
+                let mut buf: Vec<u8> = vec![0; buffer_size(size, count)?];
+                h5try!(H5Aread(self.id(), datatype.id(), buf.as_mut_ptr() as *mut c_void));
+                buf.chunks(size)
+                    .map(|chunk| {
+                        let len = chunk.iter().position(|&c| c == 0).unwrap_or(size);
+                        chunk[..len].to_vec()
+                    })
+                    .collect()
+            };
+            bytes
+                .into_iter()
+                .map(|s| String::from_utf8(s).map_err(|_| "invalid UTF-8 string".into()))
+                .collect()
+        })
+    }
+}
+
+pub(crate) fn to_varlen_unicode(s: &str) -> Result<VarLenUnicode> {
+    VarLenUnicode::from_str(s).map_err(|err| format!("invalid string: {}", err).into())
+}
+
+/// Order in which the attributes of an object are iterated over.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttrOrder {
+    /// Ascending alphanumeric order of attribute names.
+    Name,
+    /// Descending alphanumeric order of attribute names.
+    NameDesc,
+    /// Ascending creation order (creation order must be tracked for the object).
+    Creation,
+    /// Descending creation order (creation order must be tracked for the object).
+    CreationDesc,
+}
+
+impl Default for AttrOrder {
+    fn default() -> Self {
+        Self::Name
+    }
+}
+
+impl AttrOrder {
+    pub(crate) fn to_raw(self) -> (H5_index_t, H5_iter_order_t) {
+        use self::H5_index_t::{H5_INDEX_CRT_ORDER, H5_INDEX_NAME};
+        use self::H5_iter_order_t::{H5_ITER_DEC, H5_ITER_INC};
+        match self {
+            Self::Name => (H5_INDEX_NAME, H5_ITER_INC),
+            Self::NameDesc => (H5_INDEX_NAME, H5_ITER_DEC),
+            Self::Creation => (H5_INDEX_CRT_ORDER, H5_ITER_INC),
+            Self::CreationDesc => (H5_INDEX_CRT_ORDER, H5_ITER_DEC),
+        }
+    }
+}
+
+pub(crate) fn attr_names(obj: &Location, order: AttrOrder) -> Result<Vec<String>> {
+    extern "C" fn attributes_callback(
+        _id: hid_t, attr_name: *const c_char, _info: *const H5A_info_t, op_data: *mut c_void,
+    ) -> herr_t {
+        catch_callback_panic(-1, || {
+            let other_data: &mut Vec<String> = unsafe { &mut *(op_data as *mut Vec<String>) };
+
+            other_data.push(string_from_cstr(attr_name));
+
+            0 // Continue iteration
+        })
+    }
+
+    let callback_fn: H5A_operator2_t = Some(attributes_callback);
+    let iteration_position: *mut hsize_t = &mut 0;
+    let mut result: Vec<String> = Vec::new();
+    let other_data: *mut c_void = &mut result as *mut _ as *mut c_void;
+    let (idx_type, order) = order.to_raw();
+
+    h5call!(H5Aiterate2(obj.id(), idx_type, order, iteration_position, callback_fn, other_data))?;
+
+    Ok(result)
+}
+
+/// Copies an attribute into a new attribute attached to `dst`, preserving its datatype
+/// and dataspace.
+pub(crate) fn copy_attr(attr: &Attribute, dst: &Location) -> Result<Attribute> {
+    h5lock!({
+        let datatype = attr.dtype()?;
+        let dataspace = attr.space()?;
+        let name = to_cstring(attr.name())?;
+        let copy = Attribute::from_id(h5try!(H5Acreate2(
+            dst.id(),
+            name.as_ptr(),
+            datatype.id(),
+            dataspace.id(),
+            H5P_DEFAULT,
+            H5P_DEFAULT
+        )))?;
+
+        // read the data without any conversion, so variable-length data stays in place
+        let mut buf: Vec<u8> = vec![0; buffer_size(datatype.size(), dataspace.try_size()?)?];
+        let buf_ptr = buf.as_mut_ptr() as *mut c_void;
+        h5try!(H5Aread(attr.id(), datatype.id(), buf_ptr));
+        let written = h5check(H5Awrite(copy.id(), datatype.id(), buf_ptr));
+        h5try!(H5Dvlen_reclaim(datatype.id(), dataspace.id(), H5P_DEFAULT, buf_ptr));
+
+        if let Err(err) = written {
+            drop(copy);
+            H5Adelete(dst.id(), name.as_ptr());
+            return Err(err);
+        }
+        Ok(copy)
+    })
+}
+
+/// Iterator over the attributes of an object, see `Location::attrs()`.
+///
+/// Attribute names are collected upfront; the attributes are opened lazily.
+pub struct AttributeIter<'a> {
+    obj: &'a Location,
+    names: vec::IntoIter<String>,
+}
+
+impl<'a> AttributeIter<'a> {
+    pub(crate) fn new(obj: &'a Location, order: AttrOrder) -> Result<Self> {
+        Ok(Self { obj, names: attr_names(obj, order)?.into_iter() })
+    }
+}
+
+impl<'a> Iterator for AttributeIter<'a> {
+    type Item = Result<Attribute>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.names.next().map(|name| self.obj.attr(&name))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.names.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for AttributeIter<'a> {}
+
+#[derive(Clone)]
+pub struct AttributeBuilder<T> {
+    packed: bool,
+    parent: Result<Handle>,
+    acpl: AttributeCreateBuilder,
+    string_padding: Option<StringPadding>,
+    string_encoding: Option<CharEncoding>,
+    byte_order: Option<ByteOrder>,
+    phantom: PhantomData<T>,
+}
+
+impl<T: H5Type> AttributeBuilder<T> {
+    /// Create a new attribute builder and bind it to the parent object.
+    ///
+    /// The parent may be any named object: a file (root group), a group, a dataset or a
+    /// committed datatype (see `Datatype::as_location()`).
+    pub fn new(parent: &Location) -> Self {
+        h5lock!({
+            // Store the reference to the parent handle and try to increase its reference count.
+            let handle = Handle::try_new(parent.id());
+            if let Ok(ref handle) = handle {
+                handle.incref();
+            }
+
+            Self {
+                packed: false,
+                parent: handle,
+                acpl: AttributeCreateBuilder::default(),
+                string_padding: None,
+                string_encoding: None,
+                byte_order: None,
+                phantom: PhantomData,
+            }
+        })
+    }
+
+    /// Stores compound types without padding, unless `T` has an explicit stored layout.
+    pub fn packed(&mut self, packed: bool) -> &mut Self {
+        self.packed = packed;
+        self
+    }
+
+    /// Set the padding of the stored strings if `T` is a string type (see
+    /// `DatasetBuilder::string_padding()`).
+    pub fn string_padding(&mut self, padding: StringPadding) -> &mut Self {
+        self.string_padding = Some(padding);
+        self
+    }
+
+    /// Set the character set of the stored strings if `T` is a string type (see
+    /// `DatasetBuilder::string_encoding()`).
+    pub fn string_encoding(&mut self, encoding: CharEncoding) -> &mut Self {
+        self.string_encoding = Some(encoding);
+        self
+    }
+
+    /// Set the byte order of the stored data (see `DatasetBuilder::byte_order()`).
+    pub fn byte_order(&mut self, order: ByteOrder) -> &mut Self {
+        self.byte_order = Some(order);
+        self
+    }
+
+    /// Sets current attribute creation property list to a given one.
+    pub fn set_create_plist(&mut self, acpl: &AttributeCreate) -> Result<&mut Self> {
+        AttributeCreateBuilder::from_plist(acpl).map(|acpl| {
+            self.acpl = acpl;
+            self
+        })
+    }
+
+    /// A short alias for `set_create_plist()`.
+    pub fn set_acpl(&mut self, acpl: &AttributeCreate) -> Result<&mut Self> {
+        self.set_create_plist(acpl)
+    }
+
+    /// Returns the builder object for the attribute creation property list.
+    pub fn create_plist(&mut self) -> &mut AttributeCreateBuilder {
+        &mut self.acpl
+    }
+
+    /// A short alias for `create_plist()`.
+    pub fn acpl(&mut self) -> &mut AttributeCreateBuilder {
+        self.create_plist()
+    }
+
+    /// Allows accessing the builder object for the attribute creation property list.
+    pub fn with_create_plist<F>(&mut self, func: F) -> &mut Self
+    where
+        F: Fn(&mut AttributeCreateBuilder) -> &mut AttributeCreateBuilder,
+    {
+        func(&mut self.acpl);
+        self
+    }
+
+    /// A short alias for `with_create_plist()`.
+    pub fn with_acpl<F>(&mut self, func: F) -> &mut Self
+    where
+        F: Fn(&mut AttributeCreateBuilder) -> &mut AttributeCreateBuilder,
+    {
+        self.with_create_plist(func)
+    }
+
+    /// Create the attribute and link it to its parent object.
+    pub fn create<D: Dimension>(&self, name: &str, shape: D) -> Result<Attribute> {
+        self.create_with_space(name, &Dataspace::try_new(&shape, false)?)
+    }
+
+    /// Create an attribute with a null dataspace (holding no elements) and link it to its
+    /// parent object.
+    pub fn create_null(&self, name: &str) -> Result<Attribute> {
+        self.create_with_space(name, &Dataspace::try_new_null()?)
+    }
+
+    fn create_with_space(&self, name: &str, dataspace: &Dataspace) -> Result<Attribute> {
+        let type_descriptor = stored_descriptor::<T>(self.packed);
+        h5lock!({
+            let datatype = Datatype::from_descriptor(&type_descriptor)?.with_storage_options(
+                self.string_padding,
+                self.string_encoding,
+                self.byte_order,
+            )?;
+            let parent = try_ref_clone!(self.parent);
+            let acpl = self.acpl.finish()?;
+            let name = to_cstring(name)?;
+            Attribute::from_id(h5try!(H5Acreate2(
+                parent.id(),
+                name.as_ptr(),
+                datatype.id(),
+                dataspace.id(),
+                acpl.id(),
+                H5P_DEFAULT
+            )))
+        })
+    }
+
+    /// Create the attribute with the shape of the provided data and write the data into it.
+    ///
+    /// The input argument must be convertible to an array view (this includes slices).
+    /// If writing the data fails, the newly created attribute is removed.
+    pub fn with_data<'d, A, D>(&self, name: &str, data: A) -> Result<Attribute>
+    where
+        A: Into<ArrayView<'d, T, D>>,
+        D: ndarray::Dimension,
+    {
+        let view = data.into();
+        let attr = self.create(name, view.shape().to_vec())?;
+        self.write_or_delete(name, attr, |attr| attr.as_writer().write(view))
+    }
+
+    /// Create a scalar attribute and write the value into it.
+    pub fn create_scalar(&self, name: &str, value: &T) -> Result<Attribute> {
+        let attr = self.create(name, ())?;
+        self.write_or_delete(name, attr, |attr| attr.write_scalar(value))
+    }
+
+    fn write_or_delete<F>(&self, name: &str, attr: Attribute, write: F) -> Result<Attribute>
+    where
+        F: FnOnce(&Attribute) -> Result<()>,
+    {
+        if let Err(err) = write(&attr) {
+            drop(attr);
+            if let Ok(ref parent) = self.parent {
+                let name = to_cstring(name)?;
+                h5lock!(H5Adelete(parent.id(), name.as_ptr()));
+            }
+            return Err(err);
+        }
+        Ok(attr)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use hdf5_sys::{
+        h5g::H5Gcreate2,
+        h5p::{
+            H5Pcreate, H5Pset_attr_creation_order, H5P_CRT_ORDER_INDEXED, H5P_CRT_ORDER_TRACKED,
+        },
+    };
+
+    use crate::globals::H5P_GROUP_CREATE;
+    use crate::internal_prelude::*;
+
+    use super::AttrOrder;
+
+    #[test]
+    pub fn test_create_attr() {
+        with_tmp_file(|file| {
+            let attr = file.new_attr::<u32>().create("foo", (2, 3)).unwrap();
+            assert_eq!(attr.name(), "foo");
+            assert_eq!(attr.shape(), vec![2, 3]);
+            assert!(attr.dtype().unwrap().is::<u32>());
+            assert_eq!(format!("{:?}", attr), "<HDF5 attribute: \"foo\">");
+
+            let ds = file.new_dataset::<u8>().create("bar", 1).unwrap();
+            ds.new_attr::<i64>().create("baz", ()).unwrap().write_scalar(&-1).unwrap();
+            assert_eq!(ds.attr("baz").unwrap().read_scalar::<i64>().unwrap(), -1);
+
+            assert_err!(file.new_attr::<u32>().create("foo", 1), "unable to create attribute");
+            assert_err!(file.attr("x"), "can't locate attribute");
+        })
+    }
+
+    #[test]
+    pub fn test_named_datatype_attr() {
+        with_tmp_file(|file| {
+            let group = file.create_group("foo").unwrap();
+            let dtype = Datatype::from_type::<u32>().unwrap();
+            assert!(!dtype.is_committed());
+            assert_err!(dtype.as_location(), "datatype is not committed");
+            group.commit_datatype("bar/baz", &dtype).unwrap();
+            assert!(dtype.is_committed());
+
+            let loc = dtype.as_location().unwrap();
+            assert_eq!(loc.name(), "/foo/bar/baz");
+            AttributeBuilder::<u8>::new(&loc).create_scalar("a", &1).unwrap();
+            loc.set_attr_str("units", "m").unwrap();
+
+            let dtype = file.datatype("foo/bar/baz").unwrap();
+            assert!(dtype.is_committed());
+            assert!(dtype.is::<u32>());
+            let loc = dtype.as_location().unwrap();
+            assert_eq!(loc.attr_names().unwrap(), vec!["a", "units"]);
+            assert_eq!(loc.get_attr::<u8>("a").unwrap(), 1);
+            assert_eq!(loc.get_attr_string("units").unwrap(), "m");
+
+            let ds = file.new_dataset::<u8>().create("ds", 1).unwrap();
+            AttributeBuilder::<u8>::new(&ds).create("a", ()).unwrap();
+            AttributeBuilder::<u8>::new(&file).create("a", ()).unwrap();
+            assert!(ds.attr_exists("a") && file.attr_exists("a"));
+
+            assert_err!(file.datatype("ds"), "not a named datatype");
+            assert_err!(file.datatype("x"), "doesn't exist");
+            assert_err!(file.attr("x"), "can't locate attribute");
+        })
+    }
+
+    #[test]
+    pub fn test_delete_rename_attr() {
+        with_tmp_file(|file| {
+            file.new_attr::<u32>().create("foo", ()).unwrap().write_scalar(&42).unwrap();
+            file.rename_attr("foo", "bar").unwrap();
+            assert!(file.attr("foo").is_err());
+            assert_eq!(file.attr("bar").unwrap().read_scalar::<u32>().unwrap(), 42);
+            assert_err!(file.rename_attr("foo", "baz"), "can't locate attribute");
+
+            file.delete_attr("bar").unwrap();
+            assert!(file.attr("bar").is_err());
+            assert_err!(file.delete_attr("bar"), "unable to delete attribute");
+        })
+    }
+
+    #[test]
+    pub fn test_with_data() {
+        with_tmp_file(|file| {
+            let attr = file.new_attr::<i32>().with_data("foo", &[1, 2, 3]).unwrap();
+            assert_eq!(attr.shape(), vec![3]);
+            assert_eq!(file.attr("foo").unwrap().read_raw::<i32>().unwrap(), vec![1, 2, 3]);
+
+            let arr = ndarray::arr2(&[[1_u16, 2], [3, 4], [5, 6]]);
+            let attr = file.new_attr::<u16>().with_data("bar", &arr).unwrap();
+            assert_eq!(attr.shape(), vec![3, 2]);
+            assert_eq!(attr.read_2d::<u16>().unwrap(), arr);
+
+            let attr = file.new_attr::<u8>().create_scalar("baz", &42).unwrap();
+            assert!(attr.is_scalar());
+            assert_eq!(file.attr("baz").unwrap().read_scalar::<u8>().unwrap(), 42);
+
+            assert_err!(
+                file.new_attr::<u8>().create_scalar("baz", &1),
+                "unable to create attribute"
+            );
+            assert_eq!(file.attr("baz").unwrap().read_scalar::<u8>().unwrap(), 42);
+
+            let arr = ndarray::arr2(&[[1_u16, 2], [3, 4]]);
+            assert_err!(
+                file.new_attr::<u16>().with_data("qux", &arr.t()),
+                "input array is not in standard layout"
+            );
+            assert!(file.attr("qux").is_err());
+        })
+    }
+
+    #[test]
+    pub fn test_set_get_attr() {
+        with_tmp_file(|file| {
+            assert!(!file.attr_exists("foo"));
+            file.set_attr("foo", &1_i16).unwrap();
+            assert!(file.attr_exists("foo"));
+            assert_eq!(file.get_attr::<i16>("foo").unwrap(), 1);
+
+            file.set_attr("foo", &2_i16).unwrap();
+            assert!(file.attr("foo").unwrap().dtype().unwrap().is::<i16>());
+            assert_eq!(file.get_attr::<i16>("foo").unwrap(), 2);
+
+            file.set_attr("foo", &7_u64).unwrap();
+            assert!(file.attr("foo").unwrap().dtype().unwrap().is::<u64>());
+            assert_eq!(file.get_attr::<u64>("foo").unwrap(), 7);
+
+            file.new_attr::<u64>().create("bar", 3).unwrap();
+            file.set_attr("bar", &8_u64).unwrap();
+            assert!(file.attr("bar").unwrap().is_scalar());
+            assert_eq!(file.get_attr::<u64>("bar").unwrap(), 8);
+
+            assert_err!(file.get_attr::<u8>("baz"), "can't locate attribute");
+        })
+    }
+
+    #[test]
+    pub fn test_attr_opt() {
+        use hdf5_sys::h5e::{H5Eget_num, H5E_DEFAULT};
+
+        with_tmp_file(|file| {
+            file.set_attr("foo", &1_u32).unwrap();
+            assert_eq!(file.attr_opt("foo").unwrap().unwrap().name(), "foo");
+            assert_eq!(file.read_attr_or("foo", 2_u32).unwrap(), 1);
+
+            assert!(file.attr_opt("bar").unwrap().is_none());
+            assert_eq!(file.read_attr_or("bar", 2_u32).unwrap(), 2);
+            assert_eq!(h5lock!(H5Eget_num(H5E_DEFAULT)), 0);
+
+            file.new_attr::<u32>().create("arr", 3).unwrap();
+            assert_err!(file.read_attr_or("arr", 0_u32), "expected scalar");
+            assert_err!(file.attr_opt("a\0b"), "null byte");
+        })
+    }
+
+    #[test]
+    pub fn test_attr_char_encoding() {
+        use crate::plist::attribute_create::CharEncoding;
+
+        with_tmp_file(|file| {
+            let attr = file.new_attr::<u8>().create("ascii", ()).unwrap();
+            assert_eq!(attr.create_plist().unwrap().char_encoding(), CharEncoding::Ascii);
+
+            let name = "\u{3b1}\u{3b2}\u{3b3}";
+            let attr = file
+                .new_attr::<u8>()
+                .with_acpl(|p| p.char_encoding(CharEncoding::Utf8))
+                .create(name, ())
+                .unwrap();
+            assert_eq!(attr.name(), name);
+            assert_eq!(attr.acpl().unwrap().char_encoding(), CharEncoding::Utf8);
+            assert_eq!(
+                file.attr(name).unwrap().acpl().unwrap().char_encoding(),
+                CharEncoding::Utf8
+            );
+            assert_eq!(file.attr_names().unwrap(), vec!["ascii", name]);
+
+            let acpl = attr.create_plist().unwrap();
+            let attr =
+                file.new_attr::<u8>().set_create_plist(&acpl).unwrap().create("x", ()).unwrap();
+            assert_eq!(attr.acpl().unwrap(), acpl);
+            assert_eq!(format!("{:?}", acpl), "AttributeCreate { char_encoding: Utf8 }");
+        })
+    }
+
+    #[test]
+    pub fn test_read_scalar_as() {
+        with_tmp_file(|file| {
+            let attr = file.set_attr("f", &1.5_f64).unwrap();
+            let value = attr.read_scalar_as::<f32>(Conversion::Hard).unwrap();
+            assert!((value - 1.5).abs() < 1e-6);
+            assert_err!(
+                attr.read_scalar_as::<f32>(Conversion::NoOp),
+                "no-op conversion path required; available: hard conversion"
+            );
+
+            let attr = file.set_attr("u", &300_u64).unwrap();
+            #[cfg(target_pointer_width = "64")]
+            assert_eq!(attr.read_scalar_as::<usize>(Conversion::NoOp).unwrap(), 300);
+            assert_eq!(attr.read_scalar_as::<u16>(Conversion::Hard).unwrap(), 300);
+            assert_eq!(attr.read_scalar_as::<u8>(Conversion::Hard).unwrap(), 255);
+            assert_eq!(attr.read_scalar_as::<u64>(Conversion::NoOp).unwrap(), 300);
+
+            let attr = file.new_attr::<u8>().create("arr", 2).unwrap();
+            assert_err!(attr.read_scalar_as::<u8>(Conversion::Soft), "expected scalar");
+        })
+    }
+
+    #[test]
+    pub fn test_string_attrs() {
+        use std::str::FromStr;
+
+        use crate::types::{FixedAscii, FixedUnicode, VarLenAscii, VarLenUnicode};
+
+        with_tmp_file(|file| {
+            let attr = file.set_attr_str("str", "foo").unwrap();
+            assert!(attr.is_scalar());
+            assert!(attr.dtype().unwrap().is::<VarLenUnicode>());
+            assert_eq!(file.get_attr_string("str").unwrap(), "foo");
+            file.set_attr_str("str", &"b\u{e4}r".to_owned()).unwrap();
+            assert_eq!(file.get_attr_string("str").unwrap(), "b\u{e4}r");
+            assert_eq!(file.get_attr_strings("str").unwrap(), vec!["b\u{e4}r"]);
+
+            let strings = vec!["a".to_owned(), "".to_owned(), "ccc".to_owned()];
+            assert_eq!(file.set_attr_strings("strs", &strings).unwrap().shape(), vec![3]);
+            assert_eq!(file.get_attr_strings("strs").unwrap(), strings);
+            file.set_attr_strings("strs", &["x", "y"]).unwrap();
+            assert_eq!(file.get_attr_strings("strs").unwrap(), vec!["x", "y"]);
+            assert_err!(file.get_attr_string("strs"), "expected a scalar string attribute");
+
+            file.set_attr("vla", &VarLenAscii::from_ascii("abc").unwrap()).unwrap();
+            assert_eq!(file.get_attr_string("vla").unwrap(), "abc");
+            file.set_attr("fa", &FixedAscii::<[_; 6]>::from_ascii("abc").unwrap()).unwrap();
+            assert_eq!(file.get_attr_string("fa").unwrap(), "abc");
+            let fu = [
+                FixedUnicode::<[_; 4]>::from_str("\u{e4}").unwrap(),
+                FixedUnicode::<[_; 4]>::from_str("abcd").unwrap(),
+            ];
+            file.new_attr::<FixedUnicode<[_; 4]>>().with_data("fu", &fu).unwrap();
+            assert_eq!(file.get_attr_strings("fu").unwrap(), vec!["\u{e4}", "abcd"]);
+
+            file.set_attr("int", &1).unwrap();
+            assert_err!(file.get_attr_string("int"), "expected a string attribute");
+            assert_err!(file.set_attr_str("nul", "a\0b"), "invalid string");
+            assert!(!file.attr_exists("nul"));
+        })
+    }
+
+    #[test]
+    pub fn test_copy_attrs() {
+        use std::str::FromStr;
+
+        use crate::types::{FixedAscii, VarLenArray, VarLenUnicode};
+
+        with_tmp_file(|file| {
+            let src = file.create_group("src").unwrap();
+            let dst = file.new_dataset::<u8>().create("dst", 1).unwrap();
+
+            src.new_attr::<i32>().with_data("int", &ndarray::arr2(&[[1, 2], [3, 4]])).unwrap();
+            let s = VarLenUnicode::from_str("h\u{e9}llo").unwrap();
+            src.set_attr("vlen_str", &s).unwrap();
+            let fs = FixedAscii::<[_; 8]>::from_ascii(b"abc").unwrap();
+            src.set_attr("fixed_str", &fs).unwrap();
+            let va = [VarLenArray::from_slice(&[1_u16, 2, 3]), VarLenArray::from_slice(&[4])];
+            src.new_attr::<VarLenArray<u16>>().with_data("vlen_arr", &va).unwrap();
+            src.set_attr("tuple", &(-1_i8, 2_u64)).unwrap();
+
+            src.copy_attrs_to(&dst).unwrap();
+            assert_eq!(dst.attr_names().unwrap(), src.attr_names().unwrap());
+
+            let int = dst.attr("int").unwrap();
+            assert_eq!(int.shape(), vec![2, 2]);
+            assert_eq!(int.dtype().unwrap(), src.attr("int").unwrap().dtype().unwrap());
+            assert_eq!(int.read_raw::<i32>().unwrap(), vec![1, 2, 3, 4]);
+            assert_eq!(dst.get_attr::<VarLenUnicode>("vlen_str").unwrap(), s);
+            assert_eq!(dst.get_attr::<FixedAscii<[_; 8]>>("fixed_str").unwrap(), fs);
+            assert_eq!(dst.attr("vlen_arr").unwrap().read_raw::<VarLenArray<u16>>().unwrap(), va);
+            assert_eq!(dst.get_attr::<(i8, u64)>("tuple").unwrap(), (-1, 2));
+
+            assert_err!(src.copy_attrs_to(&dst), "unable to create attribute");
+        })
+    }
+
+    #[test]
+    pub fn test_attr_names() {
+        with_tmp_file(|file| {
+            assert!(file.attr_names().unwrap().is_empty());
+            assert_eq!(file.attrs().unwrap().count(), 0);
+
+            for name in &["b", "c", "a"] {
+                file.new_attr::<u8>().create(name, ()).unwrap();
+            }
+            assert_eq!(file.attr_names().unwrap(), vec!["a", "b", "c"]);
+            assert_eq!(file.attr_names_ordered(AttrOrder::NameDesc).unwrap(), vec!["c", "b", "a"]);
+
+            let attrs = file.attrs().unwrap();
+            assert_eq!(attrs.len(), 3);
+            let names: Vec<_> = attrs.map(|attr| attr.unwrap().name()).collect();
+            assert_eq!(names, vec!["a", "b", "c"]);
+
+            let ds = file.new_dataset::<u8>().create("ds", 1).unwrap();
+            ds.new_attr::<u8>().create("x", 2).unwrap();
+            let attrs: Vec<_> = ds.attrs().unwrap().collect::<Result<_>>().unwrap();
+            assert_eq!(attrs.len(), 1);
+            assert_eq!(attrs[0].shape(), vec![2]);
+        })
+    }
+
+    #[test]
+    pub fn test_attr_names_creation_order() {
+        with_tmp_file(|file| {
+            let group = h5lock!({
+                let gcpl = PropertyList::from_id(H5Pcreate(*H5P_GROUP_CREATE)).unwrap();
+                let flags = H5P_CRT_ORDER_TRACKED | H5P_CRT_ORDER_INDEXED;
+                H5Pset_attr_creation_order(gcpl.id(), flags);
+                let name = to_cstring("foo").unwrap();
+                Group::from_id(H5Gcreate2(
+                    file.id(),
+                    name.as_ptr(),
+                    H5P_DEFAULT,
+                    gcpl.id(),
+                    H5P_DEFAULT,
+                ))
+                .unwrap()
+            });
+            for name in &["b", "c", "a"] {
+                group.new_attr::<u8>().create(name, ()).unwrap();
+            }
+            assert_eq!(group.attr_names_ordered(AttrOrder::Creation).unwrap(), vec!["b", "c", "a"]);
+            assert_eq!(
+                group.attr_names_ordered(AttrOrder::CreationDesc).unwrap(),
+                vec!["a", "c", "b"]
+            );
+            let names: Vec<_> = group
+                .attrs_ordered(AttrOrder::Creation)
+                .unwrap()
+                .map(|a| a.unwrap().name())
+                .collect();
+            assert_eq!(names, vec!["b", "c", "a"]);
+        })
+    }
+
+    #[test]
+    pub fn test_attr_by_index() {
+        with_tmp_file(|file| {
+            let gcpl =
+                PropertyList::from_id(h5call!(H5Pcreate(*H5P_GROUP_CREATE)).unwrap()).unwrap();
+            h5call!(H5Pset_attr_creation_order(gcpl.id(), H5P_CRT_ORDER_TRACKED)).unwrap();
+            let name = to_cstring("foo").unwrap();
+            let group = Group::from_id(
+                h5call!(H5Gcreate2(file.id(), name.as_ptr(), H5P_DEFAULT, gcpl.id(), H5P_DEFAULT))
+                    .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(group.num_attrs().unwrap(), 0);
+            for name in &["b", "c", "a"] {
+                group.new_attr::<u8>().create(name, ()).unwrap();
+            }
+            assert_eq!(group.num_attrs().unwrap(), 3);
+            let names = |order| {
+                (0..group.num_attrs().unwrap())
+                    .map(|i| group.attr_by_index(i, order).unwrap().name())
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(names(AttrOrder::Name), vec!["a", "b", "c"]);
+            assert_eq!(names(AttrOrder::NameDesc), vec!["c", "b", "a"]);
+            assert_eq!(names(AttrOrder::Creation), vec!["b", "c", "a"]);
+            assert_eq!(names(AttrOrder::CreationDesc), vec!["a", "c", "b"]);
+            assert_err!(group.attr_by_index(3, AttrOrder::Name), "invalid index specified");
+            assert_err!(file.attr_by_index(0, AttrOrder::Name), "unable to open attribute");
+        })
+    }
+}
@@ -1,19 +1,256 @@
+use std::ffi::CStr;
 use std::fmt::{self, Debug};
 use std::mem;
-use std::ops::Deref;
+use std::ops::{Deref, Index, Range};
+use std::ptr;
+use std::slice;
+use std::str;
+use std::time::Instant;
 
 use ndarray::{Array, Array1, Array2, ArrayD, ArrayView, ArrayView1};
 use ndarray::{SliceInfo, SliceOrIndex};
 
-use hdf5_sys::h5a::{H5Aget_space, H5Aget_storage_size, H5Aget_type, H5Aread, H5Awrite};
-use hdf5_sys::h5d::{H5Dget_space, H5Dget_storage_size, H5Dget_type, H5Dread, H5Dwrite};
+use hdf5_sys::h5a::{
+    H5Aget_name, H5Aget_space, H5Aget_storage_size, H5Aget_type, H5Aread, H5Awrite,
+};
+use hdf5_sys::h5d::{
+    H5Dget_space, H5Dget_storage_size, H5Dget_type, H5Dread, H5Dvlen_reclaim, H5Dwrite,
+};
+use hdf5_sys::{h5s::H5Sget_select_npoints, h5t::H5Tget_size};
+use hdf5_types::{DynValue, TypeDescriptor as TD};
+
+use crate::buffers::Scratch;
+use crate::hl::datatype::{StringPadding, TypeClass};
+use crate::hl::plist::dataset_transfer::DatasetTransfer;
+#[cfg(h5_have_parallel)]
+use crate::hl::plist::dataset_transfer::{DatasetTransferBuilder, TransferMode};
 
 use crate::internal_prelude::*;
 
+/// Array of runtime-typed values, see `Container::read_dyn_array()`.
+pub type DynArray = ArrayD<DynValue>;
+
+/// Returns names of the top-level compound members of `T` which are absent in the
+/// stored datatype, or `None` if `T` is not a compound type.
+///
+/// Fails if any of the absent members has no default value.
+fn missing_members<T: H5Type>(file_dtype: &Datatype) -> Result<Option<Vec<String>>> {
+    let mem_fields = match T::type_descriptor() {
+        TD::Compound(compound) => compound.fields,
+        _ => return Ok(None),
+    };
+    let file_names = file_dtype.compound_member_names()?;
+    let missing: Vec<_> =
+        mem_fields.into_iter().map(|f| f.name).filter(|n| !file_names.contains(n)).collect();
+    let required: Vec<_> =
+        missing.iter().filter(|&n| !T::default_members().contains(&n.as_str())).collect();
+    ensure!(required.is_empty(), "missing compound members without default: {:?}", required);
+    Ok(Some(missing))
+}
+
+/// Returns the byte ranges of a value of the given type not covered by any of its stored
+/// members, i.e. the padding and skipped fields of compound types.
+fn unstored_ranges(desc: &TD) -> Vec<Range<usize>> {
+    fn mark(desc: &TD, offset: usize, stored: &mut [bool]) {
+        match desc {
+            TD::Compound(compound) => {
+                for field in &compound.fields {
+                    mark(&field.ty, offset + field.offset, stored);
+                }
+            }
+            TD::FixedArray(ty, len) => {
+                for i in 0..*len {
+                    mark(ty, offset + i * ty.size(), stored);
+                }
+            }
+            _ => stored[offset..offset + desc.size()].iter_mut().for_each(|s| *s = true),
+        }
+    }
+    let mut stored = vec![false; desc.size()];
+    mark(desc, 0, &mut stored);
+    let mut ranges: Vec<Range<usize>> = vec![];
+    for (i, _) in stored.iter().enumerate().filter(|(_, &s)| !s) {
+        match ranges.last_mut() {
+            Some(range) if range.end == i => range.end += 1,
+            _ => ranges.push(i..i + 1),
+        }
+    }
+    ranges
+}
+
+/// Returns the number of elements of a memory buffer described by `mspace` up to the last
+/// selected one (strided views don't necessarily span the whole extent of the dataspace).
+fn selected_len(mspace: &Dataspace) -> Result<usize> {
+    if mspace.selection_npoints()? == 0 {
+        return Ok(0);
+    }
+    let bounds = mspace.selection_bounds()?;
+    let last = mspace.dims().iter().zip(&bounds).fold(0, |i, (&dim, b)| i * dim + b.end - 1);
+    Ok(last + 1)
+}
+
+/// Copies `size` values to a scratch buffer with their unstored bytes zeroed, if they have
+/// any, since they'd otherwise be written to the file as is when no conversion is done
+/// (leaking e.g. heap addresses of skipped fields).
+fn zero_unstored<T: H5Type>(buf: *const T, size: usize) -> Option<Scratch> {
+    let ranges = match T::type_descriptor() {
+        desc @ TD::Compound(_) | desc @ TD::FixedArray(..) => unstored_ranges(&desc),
+        _ => return None,
+    };
+    if ranges.is_empty() {
+        return None;
+    }
+    let elem_size = mem::size_of::<T>();
+    let mut copy = Scratch::zeroed_aligned(size * elem_size, mem::align_of::<T>());
+    unsafe { ptr::copy_nonoverlapping(buf as *const u8, copy.as_mut_ptr(), copy.len()) };
+    for value in copy.chunks_mut(elem_size.max(1)) {
+        for range in &ranges {
+            value[range.clone()].iter_mut().for_each(|b| *b = 0);
+        }
+    }
+    Some(copy)
+}
+
+/// Describes a non-contiguous view with positive strides as a strided hyperslab of a
+/// C-contiguous block of memory starting at its first element, returning the shape of
+/// the block and the hyperslab stride along each axis, or `None` if not expressible.
+fn strided_layout(shape: &[Ix], strides: &[isize]) -> Option<(Vec<Ix>, Vec<Ix>)> {
+    let mut dims = vec![1; shape.len()];
+    let mut steps = vec![1; shape.len()];
+    // axes of length 1 don't affect the memory layout, so only the others are matched
+    let axes: Vec<_> = (0..shape.len()).filter(|&i| shape[i] > 1).collect();
+    let mut unit = 1; // number of elements per index step along the current axis of the block
+    for (j, &i) in axes.iter().enumerate().rev() {
+        let stride = strides[i];
+        if stride <= 0 || stride as Ix % unit != 0 {
+            return None;
+        }
+        steps[i] = stride as Ix / unit;
+        let extent = (shape[i] - 1) * steps[i] + 1;
+        dims[i] = if j == 0 {
+            extent
+        } else {
+            let outer = strides[axes[j - 1]];
+            if outer <= 0 || outer as Ix % unit != 0 || outer as Ix / unit < extent {
+                return None;
+            }
+            outer as Ix / unit
+        };
+        unit *= dims[i];
+    }
+    Some((dims, steps))
+}
+
+/// Handling of stored enum values without a matching member when reading enums by name,
+/// see `Container::read_enum()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownEnumValue<T> {
+    /// Fail the read.
+    Error,
+    /// Substitute the given catch-all value.
+    Fallback(T),
+}
+
+impl<T: Clone> UnknownEnumValue<T> {
+    fn resolve(&self, name: Option<&str>, value: i128) -> Result<T> {
+        match (self, name) {
+            (UnknownEnumValue::Fallback(fallback), _) => Ok(fallback.clone()),
+            (UnknownEnumValue::Error, Some(name)) => fail!("unknown enum member: {:?}", name),
+            (UnknownEnumValue::Error, None) => fail!("unknown enum value: {}", value),
+        }
+    }
+}
+
+/// Strings of a dataset/attribute stored contiguously in a single buffer, see
+/// `Container::read_string_arena()`.
+///
+/// Element `i` spans `offsets()[i]..offsets()[i + 1]` of the buffer, with elements in
+/// memory order of the dataset/attribute.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StringArena {
+    data: String,
+    offsets: Vec<usize>,
+    shape: Vec<Ix>,
+}
+
+impl StringArena {
+    fn with_capacity(size: usize, shape: Vec<Ix>) -> Self {
+        let mut offsets = Vec::with_capacity(size + 1);
+        offsets.push(0);
+        Self { data: String::new(), offsets, shape }
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Result<()> {
+        self.data.push_str(str::from_utf8(bytes).map_err(|_| "invalid UTF-8 string")?);
+        self.offsets.push(self.data.len());
+        Ok(())
+    }
+
+    /// Returns the shape of the dataset/attribute the strings were read from.
+    pub fn shape(&self) -> &[Ix] {
+        &self.shape
+    }
+
+    /// Returns the number of strings.
+    pub fn len(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    /// Returns `true` if there are no strings.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the string at position `index` in memory order, if any.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        let (start, end) = (*self.offsets.get(index)?, *self.offsets.get(index + 1)?);
+        Some(&self.data[start..end])
+    }
+
+    /// Returns an iterator over all strings in memory order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &str> + '_ {
+        self.offsets.windows(2).map(move |w| &self.data[w[0]..w[1]])
+    }
+
+    /// Returns the buffer holding all strings back to back.
+    pub fn as_str(&self) -> &str {
+        &self.data
+    }
+
+    /// Returns the `len() + 1` byte offsets delimiting the strings within the buffer.
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    /// Copies the strings into an array of owned strings.
+    pub fn to_array(&self) -> Result<ArrayD<String>> {
+        let strings = self.iter().map(ToOwned::to_owned).collect();
+        Ok(ArrayD::from_shape_vec(self.shape.clone(), strings)?)
+    }
+}
+
+impl Index<usize> for StringArena {
+    type Output = str;
+
+    fn index(&self, index: usize) -> &str {
+        self.get(index).expect("string index out of bounds")
+    }
+}
+
+#[cfg(h5_have_parallel)]
+fn collective_xfer(xfer: Option<&DatasetTransfer>) -> Result<DatasetTransfer> {
+    let mut builder = match xfer {
+        Some(plist) => DatasetTransferBuilder::from_plist(plist)?,
+        None => DatasetTransferBuilder::new(),
+    };
+    builder.mpio_transfer_mode(TransferMode::Collective).finish()
+}
+
 #[derive(Debug)]
 pub struct Reader<'a> {
     obj: &'a Container,
     conv: Conversion,
+    xfer: Option<DatasetTransfer>,
 }
 
 impl<'a> Reader<'a> {
@@ -21,7 +258,7 @@ impl<'a> Reader<'a> {
     ///
     /// Any conversions (including hard/soft) are allowed by default.
     pub fn new(obj: &'a Container) -> Self {
-        Self { obj, conv: Conversion::Soft }
+        Self { obj, conv: Conversion::Soft, xfer: None }
     }
 
     /// Set maximum allowed conversion level.
@@ -36,21 +273,104 @@ impl<'a> Reader<'a> {
         self
     }
 
-    fn read_into_buf<T: H5Type>(
+    /// Set the dataset transfer property list used for reading.
+    ///
+    /// Transfer properties only apply to datasets and are ignored for attributes.
+    pub fn transfer_plist(mut self, plist: &DatasetTransfer) -> Self {
+        self.xfer = Some(plist.clone());
+        self
+    }
+
+    /// A short alias for `transfer_plist()`.
+    pub fn dxpl(self, plist: &DatasetTransfer) -> Self {
+        self.transfer_plist(plist)
+    }
+
+    /// Returns `true` if reading the object as `T` involves a datatype conversion.
+    ///
+    /// Binary-identical memory and stored types are transferred as is, skipping the
+    /// conversion setup altogether.
+    pub fn will_convert<T: H5Type>(&self) -> Result<bool> {
+        let file_dtype = self.obj.dtype()?;
+        let mem_dtype = Datatype::from_type::<T>()?.with_char_encoding_of(&file_dtype, true)?;
+        if mem_dtype == file_dtype {
+            return Ok(false);
+        }
+        Ok(!matches!(file_dtype.conv_path(&mem_dtype), Some(Conversion::NoOp)))
+    }
+
+    /// Use collective MPI-IO for reading, keeping any other transfer properties.
+    ///
+    /// All processes that opened the file must take part in a collective transfer.
+    #[cfg(h5_have_parallel)]
+    pub fn collective(mut self) -> Result<Self> {
+        self.xfer = Some(collective_xfer(self.xfer.as_ref())?);
+        Ok(self)
+    }
+
+    fn xfer_id(&self) -> hid_t {
+        self.xfer.as_ref().map_or(H5P_DEFAULT, |plist| plist.id())
+    }
+
+    fn read_from_obj(
+        &self, tp_id: hid_t, fspace_id: hid_t, mspace_id: hid_t, buf: *mut c_void,
+    ) -> Result<()> {
+        let obj_id = self.obj.id();
+        let started = Instant::now();
+        let result = if self.obj.is_attr() {
+            h5call!(H5Aread(obj_id, tp_id, buf))
+        } else {
+            h5call!(H5Dread(obj_id, tp_id, mspace_id, fspace_id, self.xfer_id(), buf))
+        };
+        if crate::stats::is_enabled() {
+            crate::stats::record_call(self.obj, false);
+        }
+        result.and(Ok(())).with_context(|| self.obj.error_context("reading"))?;
+        let elapsed = started.elapsed();
+        if crate::stats::is_enabled() {
+            crate::stats::record(self.obj, false, tp_id, mspace_id, elapsed);
+        }
+        h5log!(
+            trace,
+            "read {} bytes from {} in {:?}",
+            self.obj.transfer_size(tp_id, mspace_id),
+            self.obj.describe(),
+            elapsed
+        );
+        Ok(())
+    }
+
+    pub(crate) fn read_into_buf<T: H5Type>(
         &self, buf: *mut T, fspace: Option<&Dataspace>, mspace: Option<&Dataspace>,
     ) -> Result<()> {
         let file_dtype = self.obj.dtype()?;
-        let mem_dtype = Datatype::from_type::<T>()?;
-        file_dtype.ensure_convertible(&mem_dtype, self.conv)?;
-        let (obj_id, tp_id) = (self.obj.id(), mem_dtype.id());
-
+        let mem_dtype = Datatype::from_type::<T>()?.with_char_encoding_of(&file_dtype, true)?;
+        let missing = if mem_dtype == file_dtype {
+            // binary-identical types need no conversion and have no missing members, but
+            // the skipped fields of compound types still have to be initialized
+            match T::type_descriptor() {
+                TD::Compound(_) => Some(vec![]),
+                _ => None,
+            }
+        } else {
+            file_dtype.ensure_convertible(&mem_dtype, self.conv)?;
+            missing_members::<T>(&file_dtype)?
+        };
+        let tp_id = mem_dtype.id();
         let fspace_id = fspace.map_or(H5S_ALL, |f| f.id());
         let mspace_id = mspace.map_or(H5S_ALL, |m| m.id());
 
-        if self.obj.is_attr() {
-            h5try!(H5Aread(obj_id, tp_id, buf as *mut _));
-        } else {
-            h5try!(H5Dread(obj_id, tp_id, mspace_id, fspace_id, H5P_DEFAULT, buf as *mut _));
+        self.read_from_obj(tp_id, fspace_id, mspace_id, buf as *mut _)?;
+
+        if let Some(missing) = missing {
+            let missing: Vec<_> = missing.iter().map(String::as_str).collect();
+            let size = match mspace {
+                Some(mspace) => mspace.try_size()?,
+                None => self.obj.space()?.try_size()?,
+            };
+            for i in 0..size {
+                unsafe { T::fill_defaults(buf.add(i), &missing) };
+            }
         }
         Ok(())
     }
@@ -131,18 +451,60 @@ impl<'a> Reader<'a> {
                 vec.set_len(size);
             }
 
-            self.read_into_buf(vec.as_mut_ptr(), Some(&fspace), Some(&mspace))?;
+            if let Err(err) = self.read_into_buf(vec.as_mut_ptr(), Some(&fspace), Some(&mspace)) {
+                // don't drop uninitialized elements, which may own memory (e.g. strings)
+                unsafe { vec.set_len(0) };
+                return Err(err);
+            }
 
             let arr = ArrayD::from_shape_vec(reduced_shape, vec)?;
             Ok(arr.into_dimensionality()?)
         }
     }
 
+    /// Reads the elements selected in `selection` into a vector, in selection order.
+    ///
+    /// The selection must have been made on a dataspace of the same shape as the dataset
+    /// (see e.g. `Dataspace::select_hyperslab()`); it is not modified, so the same
+    /// dataspace may be used to read from several datasets.
+    pub fn read_selection<T: H5Type>(&self, selection: &Dataspace) -> Result<Vec<T>> {
+        let mut vec = Vec::new();
+        self.read_selection_into(selection, &mut vec)?;
+        Ok(vec)
+    }
+
+    /// Reads the elements selected in `selection` into an existing vector, replacing its
+    /// contents; see `read_selection()`.
+    ///
+    /// The vector's allocation is reused when large enough, so repeatedly reading into
+    /// the same vector (e.g. in a hot loop) doesn't allocate on every call.
+    pub fn read_selection_into<T: H5Type>(
+        &self, selection: &Dataspace, vec: &mut Vec<T>,
+    ) -> Result<()> {
+        ensure!(!self.obj.is_attr(), "selections cannot be used on attribute datasets");
+        let shape = self.obj.get_shape()?;
+        let sel_shape = selection.dims();
+        ensure!(
+            sel_shape == shape,
+            "selection shape mismatch: dataset = {:?}, selection = {:?}",
+            shape,
+            sel_shape
+        );
+        let size = selection.selection_npoints()?;
+        if size == 0 {
+            vec.clear();
+            return Ok(());
+        }
+        let mspace = Dataspace::try_new(size, false)?;
+        self.read_into_vec(vec, size, Some(selection), Some(&mspace))
+    }
+
     /// Reads a dataset/attribute into an n-dimensional array.
     ///
     /// If the array has a fixed number of dimensions, it must match the dimensionality
     /// of the dataset/attribute.
     pub fn read<T: H5Type, D: ndarray::Dimension>(&self) -> Result<Array<T, D>> {
+        self.obj.ensure_not_null()?;
         let shape = self.obj.get_shape()?;
         if let Some(ndim) = D::NDIM {
             let obj_ndim = shape.ndim();
@@ -155,12 +517,34 @@ impl<'a> Reader<'a> {
 
     /// Reads a dataset/attribute into a vector in memory order.
     pub fn read_raw<T: H5Type>(&self) -> Result<Vec<T>> {
-        let size = self.obj.space()?.size();
-        let mut vec = Vec::with_capacity(size);
+        let mut vec = Vec::new();
+        self.read_raw_into(&mut vec)?;
+        Ok(vec)
+    }
+
+    /// Reads a dataset/attribute into an existing vector in memory order, replacing its
+    /// contents.
+    ///
+    /// The vector's allocation is reused when large enough, so repeatedly reading into
+    /// the same vector (e.g. in a hot loop) doesn't allocate on every call.
+    pub fn read_raw_into<T: H5Type>(&self, vec: &mut Vec<T>) -> Result<()> {
+        let size = self.obj.space()?.try_size()?;
+        self.read_into_vec(vec, size, None, None)
+    }
+
+    fn read_into_vec<T: H5Type>(
+        &self, vec: &mut Vec<T>, size: usize, fspace: Option<&Dataspace>,
+        mspace: Option<&Dataspace>,
+    ) -> Result<()> {
+        vec.clear();
+        vec.reserve(size);
         unsafe {
             vec.set_len(size);
         }
-        self.read_into_buf(vec.as_mut_ptr(), None, None).map(|_| vec)
+        self.read_into_buf(vec.as_mut_ptr(), fspace, mspace).map_err(|err| {
+            unsafe { vec.set_len(0) };
+            err
+        })
     }
 
     /// Reads a dataset/attribute into a 1-dimensional array.
@@ -202,8 +586,143 @@ impl<'a> Reader<'a> {
         self.read()
     }
 
+    /// Reads a dataset/attribute of any type into an array of runtime-typed values.
+    ///
+    /// This doesn't require the type to be known at compile time; the conversion
+    /// level setting is ignored since the data is always read in its own type.
+    pub fn read_dyn_array(&self) -> Result<DynArray> {
+        self.obj.ensure_not_null()?;
+        let desc = self.obj.dtype()?.to_descriptor()?;
+        let mem_dtype = Datatype::from_descriptor(&desc)?;
+        let space = self.obj.space()?;
+        let (elem_size, size) = (desc.size(), space.try_size()?);
+        let mut buf = Scratch::zeroed(buffer_size(elem_size, size)?);
+        let buf_ptr = buf.as_mut_ptr() as *mut c_void;
+        h5lock!({
+            self.read_from_obj(mem_dtype.id(), H5S_ALL, H5S_ALL, buf_ptr)?;
+            let values: Vec<_> = (0..size)
+                .map(|i| DynValue::from_raw(&desc, buf.as_ptr().add(i * elem_size)))
+                .collect();
+            h5try!(H5Dvlen_reclaim(mem_dtype.id(), space.id(), self.xfer_id(), buf_ptr));
+            Ok(ArrayD::from_shape_vec(space.dims(), values)?)
+        })
+    }
+
+    /// Reads the member names and values of an enum dataset/attribute.
+    fn read_enum_members(&self) -> Result<ArrayD<(Option<String>, i128)>> {
+        self.obj.dtype()?.ensure_class(TypeClass::Enum)?;
+        Ok(self.read_dyn_array()?.map(|value| match *value {
+            DynValue::Enum { ref name, value } => (name.clone(), value),
+            _ => unreachable!(),
+        }))
+    }
+
+    /// Reads an enum dataset/attribute into the enum type `T`, matching the stored values
+    /// by member name rather than by raw value.
+    ///
+    /// This allows the stored enum to assign different values to its members than `T`
+    /// does; `unknown` determines how values not named by any member of `T` are handled.
+    pub fn read_enum<T: H5Type + Clone>(&self, unknown: UnknownEnumValue<T>) -> Result<ArrayD<T>> {
+        let enum_type = match T::type_descriptor() {
+            TD::Enum(enum_type) => enum_type,
+            desc => fail!("expected enum type, got {:?}", desc),
+        };
+        let size = enum_type.size as usize;
+        ensure!(size == mem::size_of::<T>(), "enum type size mismatch");
+        let members = self.read_enum_members()?;
+        let shape = members.shape().to_vec();
+        let values = members
+            .into_raw_vec()
+            .into_iter()
+            .map(|(name, value)| {
+                let name = name.as_ref().map(String::as_str);
+                match enum_type.members.iter().find(|m| Some(m.name.as_str()) == name) {
+                    Some(member) => {
+                        let bytes = member.value.to_ne_bytes();
+                        let start = if cfg!(target_endian = "big") { 8 - size } else { 0 };
+                        Ok(unsafe { ptr::read_unaligned(bytes[start..].as_ptr() as *const T) })
+                    }
+                    None => unknown.resolve(name, value),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ArrayD::from_shape_vec(shape, values)?)
+    }
+
+    /// Reads an enum dataset/attribute as the names of its members; `unknown` determines
+    /// how values without a matching member are handled.
+    pub fn read_enum_names(&self, unknown: UnknownEnumValue<&str>) -> Result<ArrayD<String>> {
+        let members = self.read_enum_members()?;
+        let shape = members.shape().to_vec();
+        let names = members
+            .into_raw_vec()
+            .into_iter()
+            .map(|(name, value)| match name {
+                Some(name) => Ok(name),
+                None => unknown.resolve(None, value).map(ToOwned::to_owned),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ArrayD::from_shape_vec(shape, names)?)
+    }
+
+    /// Reads a string dataset/attribute into a single contiguous buffer.
+    ///
+    /// Unlike reading owned `String` values (e.g. via `Attribute::read_strings()`), this
+    /// avoids a separate allocation per element: variable-length strings are fetched in
+    /// one pass and copied into the arena before being released, and fixed-length strings
+    /// are stripped of their padding. Fails if any string is not valid UTF-8.
+    pub fn read_string_arena(&self) -> Result<StringArena> {
+        let dtype = self.obj.dtype()?;
+        dtype.ensure_class(TypeClass::String)?;
+        let space = self.obj.space()?;
+        let size = space.try_size()?;
+        let elem_size = dtype.size();
+        let mut arena = StringArena::with_capacity(size, space.dims());
+        if dtype.is_variable_str() {
+            let mut buf = Scratch::zeroed(buffer_size(mem::size_of::<*const c_char>(), size)?);
+            let buf_ptr = buf.as_mut_ptr() as *mut c_void;
+            h5lock!({
+                self.read_from_obj(dtype.id(), H5S_ALL, H5S_ALL, buf_ptr)?;
+                let ptrs = slice::from_raw_parts(buf_ptr as *const *const c_char, size);
+                let total = ptrs
+                    .iter()
+                    .filter(|p| !p.is_null())
+                    .map(|&p| CStr::from_ptr(p).to_bytes().len())
+                    .sum();
+                arena.data.reserve(total);
+                let copied = ptrs.iter().try_for_each(|&p| {
+                    arena.push(if p.is_null() { &[] } else { CStr::from_ptr(p).to_bytes() })
+                });
+                h5try!(H5Dvlen_reclaim(dtype.id(), space.id(), self.xfer_id(), buf_ptr));
+                copied
+            })?;
+        } else {
+            let mut buf = Scratch::zeroed(buffer_size(elem_size, size)?);
+            self.read_from_obj(dtype.id(), H5S_ALL, H5S_ALL, buf.as_mut_ptr() as *mut c_void)?;
+            let space_pad = dtype.string_padding() == Some(StringPadding::SpacePad);
+            arena.data.reserve(buf.len());
+            for bytes in buf.chunks(elem_size.max(1)).take(size) {
+                let mut bytes = bytes.iter().position(|&c| c == 0).map_or(bytes, |n| &bytes[..n]);
+                if space_pad {
+                    let len = bytes.iter().rposition(|&c| c != b' ').map_or(0, |n| n + 1);
+                    bytes = &bytes[..len];
+                }
+                arena.push(bytes)?;
+            }
+        }
+        Ok(arena)
+    }
+
+    /// Reads a scalar dataset/attribute of any type as a runtime-typed value.
+    pub fn read_dyn_value(&self) -> Result<DynValue> {
+        let obj_ndim = self.obj.get_shape()?.ndim();
+        ensure!(obj_ndim == 0, "ndim mismatch: expected scalar, got {}", obj_ndim);
+        Ok(self.read_dyn_array()?.into_raw_vec().pop().ok_or("empty dataspace")?)
+    }
+
     /// Reads a scalar dataset/attribute.
     pub fn read_scalar<T: H5Type>(&self) -> Result<T> {
+        self.obj.ensure_not_null()?;
         let obj_ndim = self.obj.get_shape()?.ndim();
         ensure!(obj_ndim == 0, "ndim mismatch: expected scalar, got {}", obj_ndim);
         let mut val = mem::MaybeUninit::<T>::uninit();
@@ -215,6 +734,7 @@ impl<'a> Reader<'a> {
 pub struct Writer<'a> {
     obj: &'a Container,
     conv: Conversion,
+    xfer: Option<DatasetTransfer>,
 }
 
 impl<'a> Writer<'a> {
@@ -222,7 +742,7 @@ impl<'a> Writer<'a> {
     ///
     /// Any conversions (including hard/soft) are allowed by default.
     pub fn new(obj: &'a Container) -> Self {
-        Self { obj, conv: Conversion::Soft }
+        Self { obj, conv: Conversion::Soft, xfer: None }
     }
 
     /// Set maximum allowed conversion level.
@@ -237,22 +757,110 @@ impl<'a> Writer<'a> {
         self
     }
 
+    /// Set the dataset transfer property list used for writing.
+    ///
+    /// Transfer properties only apply to datasets and are ignored for attributes.
+    pub fn transfer_plist(mut self, plist: &DatasetTransfer) -> Self {
+        self.xfer = Some(plist.clone());
+        self
+    }
+
+    /// A short alias for `transfer_plist()`.
+    pub fn dxpl(self, plist: &DatasetTransfer) -> Self {
+        self.transfer_plist(plist)
+    }
+
+    /// Returns `true` if writing values of type `T` to the object involves a datatype conversion.
+    ///
+    /// Binary-identical memory and stored types are transferred as is, skipping the
+    /// conversion setup altogether.
+    pub fn will_convert<T: H5Type>(&self) -> Result<bool> {
+        let file_dtype = self.obj.dtype()?;
+        let mem_dtype = Datatype::from_type::<T>()?.with_char_encoding_of(&file_dtype, false)?;
+        if mem_dtype == file_dtype {
+            return Ok(false);
+        }
+        Ok(!matches!(mem_dtype.conv_path(&file_dtype), Some(Conversion::NoOp)))
+    }
+
+    /// Use collective MPI-IO for writing, keeping any other transfer properties.
+    ///
+    /// All processes that opened the file must take part in a collective transfer.
+    #[cfg(h5_have_parallel)]
+    pub fn collective(mut self) -> Result<Self> {
+        self.xfer = Some(collective_xfer(self.xfer.as_ref())?);
+        Ok(self)
+    }
+
+    fn xfer_id(&self) -> hid_t {
+        self.xfer.as_ref().map_or(H5P_DEFAULT, |plist| plist.id())
+    }
+
+    /// Returns the memory dataspace describing a non-contiguous view, so that it can be
+    /// written without copying; `None` if the view is contiguous.
+    fn view_mspace<T, D: ndarray::Dimension>(
+        &self, view: &ArrayView<T, D>,
+    ) -> Result<Option<Dataspace>> {
+        if view.is_standard_layout() {
+            return Ok(None);
+        }
+        let layout = if self.obj.is_attr() {
+            None // attributes can only be written from contiguous memory
+        } else {
+            strided_layout(view.shape(), view.strides())
+        };
+        match layout {
+            Some((dims, steps)) => {
+                let mspace = Dataspace::try_new(&dims, false)?;
+                mspace.select_hyperslab(&vec![0; dims.len()], Some(&steps), view.shape(), None)?;
+                Ok(Some(mspace))
+            }
+            None => fail!("input array is not in standard layout or is not contiguous"),
+        }
+    }
+
     fn write_from_buf<T: H5Type>(
         &self, buf: *const T, fspace: Option<&Dataspace>, mspace: Option<&Dataspace>,
     ) -> Result<()> {
         let file_dtype = self.obj.dtype()?;
-        let mem_dtype = Datatype::from_type::<T>()?;
-        mem_dtype.ensure_convertible(&file_dtype, self.conv)?;
+        let mem_dtype = Datatype::from_type::<T>()?.with_char_encoding_of(&file_dtype, false)?;
+        let zeroed = if mem_dtype == file_dtype {
+            let size = match mspace {
+                Some(mspace) => selected_len(mspace)?,
+                None => self.obj.space()?.try_size()?,
+            };
+            zero_unstored(buf, size)
+        } else {
+            mem_dtype.ensure_convertible(&file_dtype, self.conv)?;
+            None
+        };
+        let buf = zeroed.as_ref().map_or(buf, |zeroed| zeroed.as_ptr() as *const T);
         let (obj_id, tp_id) = (self.obj.id(), mem_dtype.id());
 
         let fspace_id = fspace.map_or(H5S_ALL, |f| f.id());
         let mspace_id = mspace.map_or(H5S_ALL, |m| m.id());
 
-        if self.obj.is_attr() {
-            h5try!(H5Awrite(obj_id, tp_id, buf as *const _));
+        let started = Instant::now();
+        let result = if self.obj.is_attr() {
+            h5call!(H5Awrite(obj_id, tp_id, buf as *const _))
         } else {
-            h5try!(H5Dwrite(obj_id, tp_id, mspace_id, fspace_id, H5P_DEFAULT, buf as *const _));
+            h5call!(H5Dwrite(obj_id, tp_id, mspace_id, fspace_id, self.xfer_id(), buf as *const _))
+        };
+        if crate::stats::is_enabled() {
+            crate::stats::record_call(self.obj, true);
         }
+        result.and(Ok(())).with_context(|| self.obj.error_context("writing"))?;
+        let elapsed = started.elapsed();
+        if crate::stats::is_enabled() {
+            crate::stats::record(self.obj, true, tp_id, mspace_id, elapsed);
+        }
+        h5log!(
+            trace,
+            "wrote {} bytes to {} in {:?}",
+            self.obj.transfer_size(tp_id, mspace_id),
+            self.obj.describe(),
+            elapsed
+        );
         Ok(())
     }
 
@@ -306,15 +914,10 @@ impl<'a> Writer<'a> {
                 }
             }
 
-            let mspace = Dataspace::try_new(&slice_shape, false)?;
-
-            // FIXME - we can handle non-standard input arrays by creating a memory space
-            // that reflects the same slicing/ordering that this ArrayView represents.
-            // we could also convert the array into a standard layout, but this is probably expensive.
-            ensure!(
-                view.is_standard_layout(),
-                "input array is not in standard layout or is not contiguous"
-            );
+            let mspace = match self.view_mspace(&view)? {
+                Some(mspace) => mspace,
+                None => Dataspace::try_new(&slice_shape, false)?,
+            };
 
             if slice_shape != data_shape_hydrated {
                 fail!(
@@ -328,6 +931,41 @@ impl<'a> Writer<'a> {
         }
     }
 
+    /// Writes the elements of a 1-dimensional array view into the elements selected in
+    /// `selection`, in selection order.
+    ///
+    /// The selection must have been made on a dataspace of the same shape as the dataset,
+    /// and the number of elements in the view must match the number of selected elements.
+    pub fn write_selection<'b, A, T>(&self, arr: A, selection: &Dataspace) -> Result<()>
+    where
+        A: Into<ArrayView1<'b, T>>,
+        T: H5Type,
+    {
+        ensure!(!self.obj.is_attr(), "selections cannot be used on attribute datasets");
+        let view = arr.into();
+        let view_mspace = self.view_mspace(&view)?;
+        let shape = self.obj.get_shape()?;
+        let sel_shape = selection.dims();
+        ensure!(
+            sel_shape == shape,
+            "selection shape mismatch: dataset = {:?}, selection = {:?}",
+            shape,
+            sel_shape
+        );
+        let (src, dst) = (view.len(), selection.selection_npoints()?);
+        if src != dst {
+            fail!("length mismatch when writing selection: memory = {}, selection = {}", src, dst);
+        }
+        if src == 0 {
+            return Ok(());
+        }
+        let mspace = match view_mspace {
+            Some(mspace) => mspace,
+            None => Dataspace::try_new(src, false)?,
+        };
+        self.write_from_buf(view.as_ptr(), Some(selection), Some(&mspace))
+    }
+
     /// Writes an n-dimensional array view into a dataset/attribute.
     ///
     /// The shape of the view must match the shape of the dataset/attribute exactly.
@@ -339,18 +977,16 @@ impl<'a> Writer<'a> {
         D: ndarray::Dimension,
     {
         let view = arr.into();
-        ensure!(
-            view.is_standard_layout(),
-            "input array is not in standard layout or is not contiguous"
-        );
+        let mspace = self.view_mspace(&view)?;
 
+        self.obj.ensure_not_null()?;
         let src = view.shape();
         let dst = &*self.obj.get_shape()?;
         if src != dst {
             fail!("shape mismatch when writing: memory = {:?}, destination = {:?}", src, dst);
         }
 
-        self.write_from_buf(view.as_ptr(), None, None)
+        self.write_from_buf(view.as_ptr(), None, mspace.as_ref())
     }
 
     /// Writes a 1-dimensional array view into a dataset/attribute in memory order.
@@ -364,21 +1000,19 @@ impl<'a> Writer<'a> {
         T: H5Type,
     {
         let view = arr.into();
-        ensure!(
-            view.is_standard_layout(),
-            "input array is not in standard layout or is not contiguous"
-        );
+        let mspace = self.view_mspace(&view)?;
 
         let src = view.len();
-        let dst = self.obj.get_shape()?.size();
+        let dst = self.obj.space()?.try_size()?;
         if src != dst {
             fail!("length mismatch when writing: memory = {:?}, destination = {:?}", src, dst);
         }
-        self.write_from_buf(view.as_ptr(), None, None)
+        self.write_from_buf(view.as_ptr(), None, mspace.as_ref())
     }
 
     /// Writes a scalar dataset/attribute.
     pub fn write_scalar<T: H5Type>(&self, val: &T) -> Result<()> {
+        self.obj.ensure_not_null()?;
         let ndim = self.obj.get_shape()?.ndim();
         ensure!(ndim == 0, "ndim mismatch: expected scalar, got {}", ndim);
         self.write_from_buf(val as *const _, None, None)
@@ -421,14 +1055,45 @@ impl Container {
         get_id_type(self.id()) == H5I_ATTR
     }
 
+    /// Describes this dataset/attribute for log and error messages, e.g. "/foo/bar in
+    /// data.h5" or "attribute baz of /foo in data.h5".
+    fn describe(&self) -> String {
+        if self.is_attr() {
+            let name = h5lock!(get_h5_str(|m, s| H5Aget_name(self.id(), s, m)))
+                .unwrap_or_else(|_| "".to_string());
+            format!("attribute {} of {} in {}", name, self.name(), self.filename())
+        } else {
+            format!("{} in {}", self.name(), self.filename())
+        }
+    }
+
+    fn error_context(&self, op: &str) -> String {
+        format!("{} {}", op, self.describe())
+    }
+
+    /// Returns the size in bytes of the data of type `tp_id` selected in `mspace_id`.
+    pub(crate) fn transfer_size(&self, tp_id: hid_t, mspace_id: hid_t) -> usize {
+        let npoints = if mspace_id == H5S_ALL {
+            self.size()
+        } else {
+            h5lock!(H5Sget_select_npoints(mspace_id)).max(0) as usize
+        };
+        npoints * h5lock!(H5Tget_size(tp_id))
+    }
+
+    fn ensure_not_null(&self) -> Result<()> {
+        ensure!(!self.is_null(), "cannot access elements of a null dataspace");
+        Ok(())
+    }
+
     /// Creates a reader wrapper for this dataset/attribute, allowing to
-    /// set custom type conversion options when reading.
+    /// set custom type conversion and transfer options when reading.
     pub fn as_reader(&self) -> Reader {
         Reader::new(self)
     }
 
     /// Creates a writer wrapper for this dataset/attribute, allowing to
-    /// set custom type conversion options when writing.
+    /// set custom type conversion and transfer options when writing.
     pub fn as_writer(&self) -> Writer {
         Writer::new(self)
     }
@@ -453,7 +1118,7 @@ impl Container {
 
     #[doc(hidden)]
     pub fn get_shape(&self) -> Result<Vec<Ix>> {
-        self.space().map(|s| s.dims())
+        self.space()?.try_dims()
     }
 
     /// Returns the shape of the dataset/attribute.
@@ -468,12 +1133,20 @@ impl Container {
 
     /// Returns the total number of elements in the dataset/attribute.
     pub fn size(&self) -> usize {
-        self.shape().size()
+        self.space().ok().map_or(0, |s| s.size())
     }
 
     /// Returns whether this dataset/attribute is a scalar.
     pub fn is_scalar(&self) -> bool {
-        self.ndim() == 0
+        self.space().ok().map_or(false, |s| s.ndim() == 0 && !s.is_null())
+    }
+
+    /// Returns whether this dataset/attribute has a null dataspace, i.e. holds no elements.
+    ///
+    /// Such datasets/attributes can be only read into (and written from) empty vectors,
+    /// e.g. via `read_raw()`; all other reads and writes fail.
+    pub fn is_null(&self) -> bool {
+        self.space().ok().map_or(false, |s| s.is_null())
     }
 
     /// Returns the amount of file space required for the dataset/attribute. Note that this
@@ -499,6 +1172,12 @@ impl Container {
         self.as_reader().read_raw()
     }
 
+    /// Reads a dataset/attribute into an existing vector in memory order, reusing its
+    /// allocation.
+    pub fn read_raw_into<T: H5Type>(&self, vec: &mut Vec<T>) -> Result<()> {
+        self.as_reader().read_raw_into(vec)
+    }
+
     /// Reads a dataset/attribute into a 1-dimensional array.
     ///
     /// The dataset/attribute must be 1-dimensional.
@@ -557,6 +1236,47 @@ impl Container {
         self.as_reader().read_scalar()
     }
 
+    /// Reads the elements selected in `selection` into a vector, in selection order.
+    ///
+    /// The selection must have been made on a dataspace of the same shape as the dataset.
+    pub fn read_selection<T: H5Type>(&self, selection: &Dataspace) -> Result<Vec<T>> {
+        self.as_reader().read_selection(selection)
+    }
+
+    /// Reads the elements selected in `selection` into an existing vector, reusing its
+    /// allocation.
+    pub fn read_selection_into<T: H5Type>(
+        &self, selection: &Dataspace, vec: &mut Vec<T>,
+    ) -> Result<()> {
+        self.as_reader().read_selection_into(selection, vec)
+    }
+
+    /// Reads a dataset/attribute of any type into an array of runtime-typed values.
+    pub fn read_dyn_array(&self) -> Result<DynArray> {
+        self.as_reader().read_dyn_array()
+    }
+
+    /// Reads a scalar dataset/attribute of any type as a runtime-typed value.
+    pub fn read_dyn_value(&self) -> Result<DynValue> {
+        self.as_reader().read_dyn_value()
+    }
+
+    /// Reads a string dataset/attribute into a single contiguous buffer.
+    pub fn read_string_arena(&self) -> Result<StringArena> {
+        self.as_reader().read_string_arena()
+    }
+
+    /// Reads an enum dataset/attribute into the enum type `T`, matching the stored values
+    /// by member name rather than by raw value.
+    pub fn read_enum<T: H5Type + Clone>(&self, unknown: UnknownEnumValue<T>) -> Result<ArrayD<T>> {
+        self.as_reader().read_enum(unknown)
+    }
+
+    /// Reads an enum dataset/attribute as the names of its members.
+    pub fn read_enum_names(&self, unknown: UnknownEnumValue<&str>) -> Result<ArrayD<String>> {
+        self.as_reader().read_enum_names(unknown)
+    }
+
     /// Writes an n-dimensional array view into a dataset/attribute.
     ///
     /// The shape of the view must match the shape of the dataset/attribute exactly.
@@ -602,4 +1322,17 @@ impl Container {
     pub fn write_scalar<T: H5Type>(&self, val: &T) -> Result<()> {
         self.as_writer().write_scalar(val)
     }
+
+    /// Writes the elements of a 1-dimensional array view into the elements selected in
+    /// `selection`, in selection order.
+    ///
+    /// The selection must have been made on a dataspace of the same shape as the dataset,
+    /// and the number of elements in the view must match the number of selected elements.
+    pub fn write_selection<'b, A, T>(&self, arr: A, selection: &Dataspace) -> Result<()>
+    where
+        A: Into<ArrayView1<'b, T>>,
+        T: H5Type,
+    {
+        self.as_writer().write_selection(arr, selection)
+    }
 }
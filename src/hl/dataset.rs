@@ -1,25 +1,41 @@
 use std::fmt::{self, Debug};
 use std::mem;
 use std::ops::Deref;
+#[cfg(feature = "log")]
+use std::time::Instant;
 
+use ndarray::{ArrayD, ArrayView, SliceInfo, SliceOrIndex};
 use num_integer::div_floor;
 
 #[cfg(hdf5_1_10_5)]
 use hdf5_sys::h5d::{H5Dget_chunk_info, H5Dget_num_chunks};
+#[cfg(hdf5_1_10_3)]
+use hdf5_sys::h5d::{H5Dget_chunk_storage_size, H5Dread_chunk};
+#[cfg(hdf5_1_10_5)]
+use hdf5_sys::h5p::H5Pset_dset_no_attrs_hint;
 use hdf5_sys::{
     h5::HADDR_UNDEF,
     h5d::{
-        H5D_fill_value_t, H5D_layout_t, H5Dcreate2, H5Dcreate_anon, H5Dget_create_plist,
-        H5Dget_offset, H5Dset_extent, H5D_FILL_TIME_ALLOC,
+        H5D_fill_value_t, H5D_layout_t, H5Dcreate2, H5Dcreate_anon, H5Dget_access_plist,
+        H5Dget_create_plist, H5Dget_offset, H5Dset_extent, H5D_FILL_TIME_ALLOC,
     },
     h5p::{
-        H5Pcreate, H5Pfill_value_defined, H5Pget_chunk, H5Pget_fill_value, H5Pget_layout,
-        H5Pget_obj_track_times, H5Pset_chunk, H5Pset_create_intermediate_group, H5Pset_fill_time,
-        H5Pset_fill_value, H5Pset_obj_track_times,
+        H5Pfill_value_defined, H5Pget_chunk, H5Pget_fill_value, H5Pget_layout,
+        H5Pget_obj_track_times, H5Pset_chunk, H5Pset_fill_time, H5Pset_fill_value,
+        H5Pset_obj_track_times,
     },
+    h5r::{H5Rcreate, H5R_DATASET_REGION},
 };
 
-use crate::globals::H5P_LINK_CREATE;
+use hdf5_types::RegionReference;
+
+use crate::filters::{zstd_available, Preset, ZSTD_FILTER_ID};
+use crate::hl::datatype::{stored_descriptor, ByteOrder, StringPadding};
+use crate::hl::plist::attribute_create::CharEncoding;
+use crate::hl::plist::dataset_access::{DatasetAccess, DatasetAccessBuilder};
+use crate::hl::plist::dataset_create::DatasetCreate;
+use crate::hl::plist::file_create::AttrPhaseChangeInfo;
+use crate::hl::plist::link_create::{LinkCreate, LinkCreateBuilder};
 use crate::internal_prelude::*;
 
 /// Represents the HDF5 dataset object.
@@ -90,10 +106,31 @@ impl ChunkInfo {
     }
 }
 
+/// Result of a resilient read, see `Dataset::read_resilient()`.
+#[derive(Clone, Debug)]
+pub struct ResilientRead<T> {
+    /// Dataset contents; regions of chunks that could not be read contain the fill value.
+    pub data: ArrayD<T>,
+    /// Per-chunk error mask indexed by chunk coordinates; `true` if the chunk failed to read.
+    pub failed: ArrayD<bool>,
+}
+
+impl<T> ResilientRead<T> {
+    /// Returns `true` if all chunks have been read successfully.
+    pub fn is_complete(&self) -> bool {
+        self.failed.iter().all(|&f| !f)
+    }
+
+    /// Returns the number of chunks that failed to read.
+    pub fn num_failed(&self) -> usize {
+        self.failed.iter().filter(|&&f| f).count()
+    }
+}
+
 impl Dataset {
     /// Returns whether this dataset is resizable along some axis.
     pub fn is_resizable(&self) -> bool {
-        h5lock!(self.space().ok().map_or(false, |s| s.resizable()))
+        h5lock!(self.space().ok().map_or(false, |s| s.extents().is_resizable()))
     }
 
     /// Returns whether this dataset has a chunked layout.
@@ -139,6 +176,42 @@ impl Dataset {
         }))
     }
 
+    /// Reads the raw bytes of the chunk at the given logical offset bypassing the filter
+    /// pipeline, returning them (e.g. still compressed) along with the chunk's filter mask.
+    ///
+    /// Unlike regular reads, decoding the returned bytes doesn't require holding the
+    /// library lock, so it may be done by the caller concurrently for multiple chunks.
+    #[cfg(hdf5_1_10_3)]
+    pub fn read_raw_chunk(&self, offset: &[Ix]) -> Result<(Vec<u8>, u32)> {
+        ensure!(self.is_chunked(), "dataset is not chunked");
+        ensure!(
+            offset.len() == self.ndim(),
+            "chunk offset has {} dims, dataset has {}",
+            offset.len(),
+            self.ndim()
+        );
+        let offset: Vec<hsize_t> = offset.iter().map(|&x| x as _).collect();
+        h5lock!({
+            let mut size: hsize_t = 0;
+            h5try!(H5Dget_chunk_storage_size(self.id(), offset.as_ptr(), &mut size));
+            let mut buf = vec![0_u8; size as _];
+            let mut filter_mask = 0_u32;
+            h5try!(H5Dread_chunk(
+                self.id(),
+                H5P_DEFAULT,
+                offset.as_ptr(),
+                &mut filter_mask,
+                buf.as_mut_ptr() as *mut _
+            ));
+            Ok((buf, filter_mask))
+        })
+    }
+
+    /// Returns the I/O statistics recorded for this dataset, see `hdf5::stats`.
+    pub fn io_stats(&self) -> crate::stats::IoStats {
+        crate::stats::get(self)
+    }
+
     /// Returns the chunk shape if the dataset is chunked.
     pub fn chunks(&self) -> Option<Vec<Ix>> {
         h5lock!({
@@ -167,6 +240,71 @@ impl Dataset {
         .unwrap_or_else(|_: crate::error::Error| Filters::default())
     }
 
+    /// Reads the dataset chunk by chunk, tolerating chunks that fail to read.
+    ///
+    /// If a chunk cannot be read (e.g., a filter required to decode it is not available,
+    /// or the chunk data is corrupted), the corresponding region is filled with the fill
+    /// value and the chunk is marked in the returned error mask, instead of failing the
+    /// whole read. Contiguous datasets are treated as a single chunk.
+    ///
+    /// This is meant for salvaging data from partially damaged files; errors unrelated to
+    /// individual chunks (e.g. incompatible datatypes) are still returned as errors.
+    pub fn read_resilient<T: H5Type + Clone>(&self) -> Result<ResilientRead<T>> {
+        let file_dtype = self.dtype()?;
+        file_dtype.ensure_convertible(&Datatype::from_type::<T>()?, Conversion::Soft)?;
+        let fill_value: T = self.fill_value()?.ok_or("Fill value is undefined")?;
+
+        let shape = self.shape();
+        let chunk = self.chunks().unwrap_or_else(|| shape.clone());
+        let grid: Vec<Ix> = shape
+            .iter()
+            .zip(&chunk)
+            .map(|(&s, &c)| if c == 0 { 0 } else { (s + c - 1) / c })
+            .collect();
+
+        let mut data = ArrayD::from_elem(shape.clone(), fill_value);
+        let mut failed = ArrayD::from_elem(grid, false);
+        let reader = self.as_reader();
+        let _e = silence_errors();
+
+        if shape.is_empty() {
+            let value = reader.read_scalar::<T>();
+            match value {
+                Ok(value) => data[[].as_ref()] = value,
+                Err(_) => failed[[].as_ref()] = true,
+            }
+            return Ok(ResilientRead { data, failed });
+        }
+
+        for (index, chunk_failed) in failed.indexed_iter_mut() {
+            let slice: Vec<SliceOrIndex> = (0..shape.len())
+                .map(|i| {
+                    let start = index[i] * chunk[i];
+                    let end = (start + chunk[i]).min(shape[i]);
+                    SliceOrIndex::Slice { start: start as _, end: Some(end as _), step: 1 }
+                })
+                .collect();
+
+            let fspace = self.space()?;
+            let extents = fspace.select_slice(&slice)?;
+            let mspace = Dataspace::try_new(&extents, false)?;
+            let size = extents.size();
+            let mut buf: Vec<T> = Vec::with_capacity(size);
+
+            if reader.read_into_buf(buf.as_mut_ptr(), Some(&fspace), Some(&mspace)).is_ok() {
+                unsafe {
+                    buf.set_len(size);
+                }
+                let slice = SliceInfo::<_, ndarray::IxDyn>::new(slice)?;
+                data.slice_mut(slice.as_ref()).assign(&ArrayView::from_shape(extents, &buf)?);
+            } else {
+                *chunk_failed = true;
+            }
+        }
+
+        Ok(ResilientRead { data, failed })
+    }
+
     /// Returns `true` if object modification time is tracked by the dataset.
     pub fn tracks_times(&self) -> bool {
         h5lock!({
@@ -178,6 +316,14 @@ impl Dataset {
         })
     }
 
+    /// Returns attribute storage phase change thresholds of the dataset.
+    pub fn attr_phase_change(&self) -> Result<AttrPhaseChangeInfo> {
+        h5lock!({
+            let dcpl = PropertyList::from_id(h5try!(H5Dget_create_plist(self.id())))?;
+            AttrPhaseChangeInfo::get(dcpl.id())
+        })
+    }
+
     /// Returns the absolute byte offset of the dataset in the file if such offset is defined
     /// (which is not the case for datasets that are chunked, compact or not allocated yet).
     pub fn offset(&self) -> Option<u64> {
@@ -212,6 +358,26 @@ impl Dataset {
         })
     }
 
+    /// Returns a copy of the dataset access property list.
+    pub fn access_plist(&self) -> Result<DatasetAccess> {
+        h5lock!(DatasetAccess::from_id(h5try!(H5Dget_access_plist(self.id()))))
+    }
+
+    /// A short alias for `access_plist()`.
+    pub fn dapl(&self) -> Result<DatasetAccess> {
+        self.access_plist()
+    }
+
+    /// Returns a copy of the dataset creation property list.
+    pub fn create_plist(&self) -> Result<DatasetCreate> {
+        h5lock!(DatasetCreate::from_id(h5try!(H5Dget_create_plist(self.id()))))
+    }
+
+    /// A short alias for `create_plist()`.
+    pub fn dcpl(&self) -> Result<DatasetCreate> {
+        self.create_plist()
+    }
+
     fn dcpl_id(&self) -> Result<hid_t> {
         h5call!(H5Dget_create_plist(self.id()))
     }
@@ -224,6 +390,35 @@ impl Dataset {
         h5try!(H5Dset_extent(self.id(), dims.as_ptr()));
         Ok(())
     }
+
+    /// Creates a dataset region reference to a slice of the dataset.
+    ///
+    /// The slice must have as many dimensions as the dataset; see
+    /// [`Dataspace::select_slice()`](struct.Dataspace.html#method.select_slice).
+    pub fn region_reference<S>(&self, slice: S) -> Result<RegionReference>
+    where
+        S: AsRef<[SliceOrIndex]>,
+    {
+        let space = self.space()?;
+        let ndim = slice.as_ref().len();
+        ensure!(
+            ndim == space.ndim(),
+            "slice dimension mismatch: dataset has {} dims, slice has {} dims",
+            space.ndim(),
+            ndim
+        );
+        space.select_slice(slice)?;
+        let mut reference = RegionReference::default();
+        let ptr = &mut reference as *mut _ as *mut _;
+        h5try!(H5Rcreate(
+            ptr,
+            self.id(),
+            b".\0".as_ptr() as *const _,
+            H5R_DATASET_REGION,
+            space.id()
+        ));
+        Ok(reference)
+    }
 }
 
 #[derive(Clone)]
@@ -235,6 +430,14 @@ pub struct DatasetBuilder<T> {
     track_times: bool,
     resizable: bool,
     fill_value: Option<T>,
+    attr_phase_change: Option<AttrPhaseChangeInfo>,
+    #[cfg(hdf5_1_10_5)]
+    minimize_header: Option<bool>,
+    string_padding: Option<StringPadding>,
+    string_encoding: Option<CharEncoding>,
+    byte_order: Option<ByteOrder>,
+    dapl: DatasetAccessBuilder,
+    lcpl: LinkCreateBuilder,
 }
 
 impl<T: H5Type> DatasetBuilder<T> {
@@ -247,6 +450,9 @@ impl<T: H5Type> DatasetBuilder<T> {
                 handle.incref();
             }
 
+            let mut lcpl = LinkCreateBuilder::default();
+            lcpl.create_intermediate_group(true);
+
             Self {
                 packed: false,
                 filters: Filters::default(),
@@ -255,10 +461,19 @@ impl<T: H5Type> DatasetBuilder<T> {
                 track_times: false,
                 resizable: false,
                 fill_value: None,
+                attr_phase_change: None,
+                #[cfg(hdf5_1_10_5)]
+                minimize_header: None,
+                string_padding: None,
+                string_encoding: None,
+                byte_order: None,
+                dapl: DatasetAccessBuilder::default(),
+                lcpl,
             }
         })
     }
 
+    /// Stores compound types without padding, unless `T` has an explicit stored layout.
     pub fn packed(&mut self, packed: bool) -> &mut Self {
         self.packed = packed;
         self
@@ -311,6 +526,52 @@ impl<T: H5Type> DatasetBuilder<T> {
         self
     }
 
+    /// Set attribute storage phase change thresholds.
+    ///
+    /// Setting `max_compact` to 0 makes the dataset use dense attribute storage from the
+    /// start, which is preferable for datasets expected to carry many attributes. For
+    /// further details, see `AttrPhaseChangeInfo`.
+    pub fn attr_phase_change(&mut self, max_compact: u32, min_dense: u32) -> &mut Self {
+        self.attr_phase_change = Some(AttrPhaseChangeInfo { max_compact, min_dense });
+        self
+    }
+
+    /// Create the dataset with a minimized object header, without space reserved for
+    /// attributes; this considerably reduces the file size for many small datasets.
+    ///
+    /// Attributes can still be added, but may then be stored less efficiently. If not
+    /// set, the file-wide default is used, see `File::set_minimize_dataset_headers()`.
+    #[cfg(hdf5_1_10_5)]
+    pub fn minimize_header(&mut self, minimize: bool) -> &mut Self {
+        self.minimize_header = Some(minimize);
+        self
+    }
+
+    /// Set the padding of the stored strings if `T` is a string type.
+    ///
+    /// By default, fixed-length strings are null-padded and variable-length strings are
+    /// null-terminated; data is converted to the selected padding when written.
+    pub fn string_padding(&mut self, padding: StringPadding) -> &mut Self {
+        self.string_padding = Some(padding);
+        self
+    }
+
+    /// Set the character set of the stored strings if `T` is a string type.
+    ///
+    /// By default, the character set follows `T` (e.g. ASCII for `VarLenAscii`); note
+    /// that h5py stores Python strings as UTF-8, while C producers mostly use ASCII.
+    pub fn string_encoding(&mut self, encoding: CharEncoding) -> &mut Self {
+        self.string_encoding = Some(encoding);
+        self
+    }
+
+    /// Set the byte order of the stored data (native by default), e.g. to produce files
+    /// for big-endian systems; data is converted when written and read.
+    pub fn byte_order(&mut self, order: ByteOrder) -> &mut Self {
+        self.byte_order = Some(order);
+        self
+    }
+
     /// Enable gzip compression with a specified level (0-9).
     pub fn gzip(&mut self, level: u8) -> &mut Self {
         self.filters.gzip(level);
@@ -338,16 +599,169 @@ impl<T: H5Type> DatasetBuilder<T> {
         self
     }
 
-    /// Enable scale-offset filter with a specified factor (0 means automatic).
+    /// Enable scale-offset filter with a specified factor.
+    ///
+    /// See `Filters::scale_offset()` for the meaning of the factor for integer and
+    /// floating-point datatypes.
     pub fn scale_offset(&mut self, scale_offset: u32) -> &mut Self {
         self.filters.scale_offset(scale_offset);
         self
     }
 
-    fn make_dcpl<D: Dimension>(&self, datatype: &Datatype, shape: D) -> Result<PropertyList> {
+    // Dataset Creation Property List
+
+    /// Sets the layout, filters, time tracking and attribute phase change thresholds
+    /// from a given dataset creation property list, e.g. that of an existing dataset.
+    ///
+    /// Compact and virtual layouts are not supported by the builder and are replaced by
+    /// the contiguous layout.
+    pub fn set_create_plist(&mut self, dcpl: &DatasetCreate) -> Result<&mut Self> {
+        self.chunk = match dcpl.get_chunk()? {
+            Some(chunk) => Chunk::Manual(chunk),
+            None => Chunk::None,
+        };
+        self.filters = dcpl.get_filters()?;
+        self.track_times = dcpl.get_obj_track_times()?;
+        self.attr_phase_change = Some(dcpl.get_attr_phase_change()?);
+        #[cfg(hdf5_1_10_5)]
+        {
+            self.minimize_header = Some(dcpl.get_minimize_header()?);
+        }
+        Ok(self)
+    }
+
+    /// A short alias for `set_create_plist()`.
+    pub fn set_dcpl(&mut self, dcpl: &DatasetCreate) -> Result<&mut Self> {
+        self.set_create_plist(dcpl)
+    }
+
+    // Dataset Access Property List
+
+    /// Sets current dataset access property list to a given one.
+    pub fn set_access_plist(&mut self, dapl: &DatasetAccess) -> Result<&mut Self> {
+        DatasetAccessBuilder::from_plist(dapl).map(|dapl| {
+            self.dapl = dapl;
+            self
+        })
+    }
+
+    /// A short alias for `set_access_plist()`.
+    pub fn set_dapl(&mut self, dapl: &DatasetAccess) -> Result<&mut Self> {
+        self.set_access_plist(dapl)
+    }
+
+    /// Returns the builder object for the dataset access property list.
+    pub fn access_plist(&mut self) -> &mut DatasetAccessBuilder {
+        &mut self.dapl
+    }
+
+    /// A short alias for `access_plist()`.
+    pub fn dapl(&mut self) -> &mut DatasetAccessBuilder {
+        self.access_plist()
+    }
+
+    /// Allows accessing the builder object for the dataset access property list.
+    pub fn with_access_plist<F>(&mut self, func: F) -> &mut Self
+    where
+        F: Fn(&mut DatasetAccessBuilder) -> &mut DatasetAccessBuilder,
+    {
+        func(&mut self.dapl);
+        self
+    }
+
+    /// A short alias for `with_access_plist()`.
+    pub fn with_dapl<F>(&mut self, func: F) -> &mut Self
+    where
+        F: Fn(&mut DatasetAccessBuilder) -> &mut DatasetAccessBuilder,
+    {
+        self.with_access_plist(func)
+    }
+
+    // Link Creation Property List
+
+    /// Sets current link creation property list to a given one.
+    ///
+    /// Note that missing intermediate groups are created by default, but not if a
+    /// property list without this setting is given.
+    pub fn set_link_create_plist(&mut self, lcpl: &LinkCreate) -> Result<&mut Self> {
+        LinkCreateBuilder::from_plist(lcpl).map(|lcpl| {
+            self.lcpl = lcpl;
+            self
+        })
+    }
+
+    /// A short alias for `set_link_create_plist()`.
+    pub fn set_lcpl(&mut self, lcpl: &LinkCreate) -> Result<&mut Self> {
+        self.set_link_create_plist(lcpl)
+    }
+
+    /// Returns the builder object for the link creation property list.
+    pub fn link_create_plist(&mut self) -> &mut LinkCreateBuilder {
+        &mut self.lcpl
+    }
+
+    /// A short alias for `link_create_plist()`.
+    pub fn lcpl(&mut self) -> &mut LinkCreateBuilder {
+        self.link_create_plist()
+    }
+
+    /// Allows accessing the builder object for the link creation property list.
+    pub fn with_link_create_plist<F>(&mut self, func: F) -> &mut Self
+    where
+        F: Fn(&mut LinkCreateBuilder) -> &mut LinkCreateBuilder,
+    {
+        func(&mut self.lcpl);
+        self
+    }
+
+    /// A short alias for `with_link_create_plist()`.
+    pub fn with_lcpl<F>(&mut self, func: F) -> &mut Self
+    where
+        F: Fn(&mut LinkCreateBuilder) -> &mut LinkCreateBuilder,
+    {
+        self.with_link_create_plist(func)
+    }
+
+    /// Enable a compression preset: shuffle followed by zstd if the zstd filter plugin is
+    /// available, or by gzip otherwise.
+    ///
+    /// Previously set gzip, szip and zstd filters are replaced; other filters are kept.
+    /// Unless chunk shape has been set manually, it is inferred from the dataset shape.
+    /// If gzip is not available either, creating the dataset will fail.
+    pub fn compress_preset(&mut self, preset: Preset) -> &mut Self {
+        self.filters.no_gzip().no_szip().remove_user(ZSTD_FILTER_ID).shuffle(true);
+        if zstd_available() {
+            let level = c_uint::from(preset.zstd_level());
+            self.filters.user(ZSTD_FILTER_ID, &[level]);
+        } else {
+            self.filters.gzip(preset.gzip_level());
+        }
+        if let Chunk::Auto | Chunk::None = self.chunk {
+            self.chunk = Chunk::Infer;
+        }
+        self
+    }
+
+    /// A short alias for `compress_preset(Preset::Fast)`.
+    pub fn compress_fast(&mut self) -> &mut Self {
+        self.compress_preset(Preset::Fast)
+    }
+
+    /// A short alias for `compress_preset(Preset::Balanced)`.
+    pub fn compress_balanced(&mut self) -> &mut Self {
+        self.compress_preset(Preset::Balanced)
+    }
+
+    /// A short alias for `compress_preset(Preset::Best)`.
+    pub fn compress_best(&mut self) -> &mut Self {
+        self.compress_preset(Preset::Best)
+    }
+
+    fn make_dcpl(&self, datatype: &Datatype, shape: &Dataspace) -> Result<PropertyList> {
         h5lock!({
             let dcpl = self.filters.to_dcpl(datatype)?;
             let id = dcpl.id();
+            let resizable = shape.extents().is_resizable();
 
             h5try!(H5Pset_obj_track_times(id, self.track_times as _));
 
@@ -355,15 +769,26 @@ impl<T: H5Type> DatasetBuilder<T> {
                 h5try!(H5Pset_fill_value(id, datatype.id(), fill_value as *const _ as *const _));
             }
 
+            if let Some(attr_phase_change) = self.attr_phase_change {
+                attr_phase_change.set(id)?;
+            }
+
+            #[cfg(hdf5_1_10_5)]
+            {
+                if let Some(minimize) = self.minimize_header {
+                    h5try!(H5Pset_dset_no_attrs_hint(id, minimize as _));
+                }
+            }
+
             if let Chunk::None = self.chunk {
                 ensure!(
                     !self.filters.has_filters(),
                     "Chunking must be enabled when filters are present"
                 );
-                ensure!(!self.resizable, "Chunking must be enabled for resizable datasets");
+                ensure!(!resizable, "Chunking must be enabled for resizable datasets");
             } else {
                 let no_chunk = if let Chunk::Auto = self.chunk {
-                    !self.filters.has_filters() && !self.resizable
+                    !self.filters.has_filters() && !resizable
                 } else {
                     false
                 };
@@ -372,7 +797,7 @@ impl<T: H5Type> DatasetBuilder<T> {
 
                     let dims = match self.chunk {
                         Chunk::Manual(ref c) => c.clone(),
-                        _ => infer_chunk_size(&shape, datatype.size()),
+                        _ => infer_chunk_size(shape, datatype.size()),
                     };
 
                     ensure!(
@@ -387,7 +812,7 @@ impl<T: H5Type> DatasetBuilder<T> {
                         dims
                     );
 
-                    if !self.resizable {
+                    if !resizable {
                         ensure!(
                             dims.iter().zip(shape.dims().iter()).all(|(&c, &s)| c <= s),
                             "Invalid chunk: {:?} (must not exceed data shape in any dimension)",
@@ -407,29 +832,29 @@ impl<T: H5Type> DatasetBuilder<T> {
         })
     }
 
-    fn make_lcpl(&self) -> Result<PropertyList> {
-        h5lock!({
-            let lcpl = PropertyList::from_id(h5try!(H5Pcreate(*H5P_LINK_CREATE)))?;
-            h5call!(H5Pset_create_intermediate_group(lcpl.id(), 1)).and(Ok(lcpl))
-        })
+    fn finalize<D: Dimension>(&self, name: Option<&str>, shape: D) -> Result<Dataset> {
+        self.finalize_with_space(name, &Dataspace::try_new(&shape, self.resizable)?)
     }
 
-    fn finalize<D: Dimension>(&self, name: Option<&str>, shape: D) -> Result<Dataset> {
-        let type_descriptor = if self.packed {
-            <T as H5Type>::type_descriptor().to_packed_repr()
-        } else {
-            <T as H5Type>::type_descriptor().to_c_repr()
-        };
-        h5lock!({
+    fn finalize_with_space(&self, name: Option<&str>, dataspace: &Dataspace) -> Result<Dataset> {
+        let type_descriptor = stored_descriptor::<T>(self.packed);
+        #[cfg(feature = "log")]
+        let started = Instant::now();
+        let dataset = h5lock!({
             let datatype = Datatype::from_descriptor(&type_descriptor)?;
             let parent = try_ref_clone!(self.parent);
 
-            let dataspace = Dataspace::try_new(&shape, self.resizable)?;
-            let dcpl = self.make_dcpl(&datatype, &shape)?;
+            let dcpl = self.make_dcpl(&datatype, dataspace)?;
+            let dapl = self.dapl.finish()?;
+            let datatype = datatype.with_storage_options(
+                self.string_padding,
+                self.string_encoding,
+                self.byte_order,
+            )?;
 
             match name {
                 Some(name) => {
-                    let lcpl = self.make_lcpl()?;
+                    let lcpl = self.lcpl.finish()?;
                     let name = to_cstring(name)?;
                     Dataset::from_id(h5try!(H5Dcreate2(
                         parent.id(),
@@ -438,7 +863,7 @@ impl<T: H5Type> DatasetBuilder<T> {
                         dataspace.id(),
                         lcpl.id(),
                         dcpl.id(),
-                        H5P_DEFAULT
+                        dapl.id()
                     )))
                 }
                 _ => Dataset::from_id(h5try!(H5Dcreate_anon(
@@ -446,10 +871,19 @@ impl<T: H5Type> DatasetBuilder<T> {
                     datatype.id(),
                     dataspace.id(),
                     dcpl.id(),
-                    H5P_DEFAULT
+                    dapl.id()
                 ))),
             }
-        })
+        })?;
+        h5log!(
+            debug,
+            "created dataset {:?} with shape {:?} in {} in {:?}",
+            dataset.name(),
+            dataspace.dims(),
+            dataset.filename(),
+            started.elapsed()
+        );
+        Ok(dataset)
     }
 
     /// Create the dataset and link it into the file structure.
@@ -461,9 +895,27 @@ impl<T: H5Type> DatasetBuilder<T> {
     pub fn create_anon<D: Dimension>(&self, shape: D) -> Result<Dataset> {
         self.finalize(None, shape)
     }
+
+    /// Create the dataset with the given current and maximum axis sizes and link it into
+    /// the file structure, e.g. `create_with_extents("x", (Extent::new(10).max(1000), 3))`.
+    ///
+    /// This takes precedence over `resizable()`; datasets with any resizable axis require
+    /// chunking.
+    pub fn create_with_extents<E: Into<Extents>>(&self, name: &str, extents: E) -> Result<Dataset> {
+        self.finalize_with_space(Some(name), &Dataspace::try_with_extents(extents)?)
+    }
+
+    /// Create a dataset with a null dataspace (holding no elements) and link it into the
+    /// file structure.
+    ///
+    /// Null datasets cannot be chunked or resized, so filters may not be enabled (and the
+    /// `resizable()` setting is ignored).
+    pub fn create_null(&self, name: &str) -> Result<Dataset> {
+        self.finalize_with_space(Some(name), &Dataspace::try_new_null()?)
+    }
 }
 
-fn infer_chunk_size<D: Dimension>(shape: &D, typesize: usize) -> Vec<Ix> {
+pub(crate) fn infer_chunk_size<D: Dimension>(shape: &D, typesize: usize) -> Vec<Ix> {
     // This algorithm is borrowed from h5py, though the idea originally comes from PyTables.
 
     const CHUNK_BASE: f64 = (16 * 1024) as _;
@@ -507,16 +959,24 @@ pub mod tests {
     use std::fs;
     use std::io::Read;
 
-    use hdf5_sys::{h5d::H5Dwrite, h5s::H5S_ALL};
-
-    use crate::filters::{gzip_available, szip_available};
+    use hdf5_sys::{
+        h5d::H5Dwrite,
+        h5s::H5S_ALL,
+        h5z::{
+            H5Z_class2_t, H5Z_filter_t, H5Zregister, H5Zunregister, H5Z_CLASS_T_VERS,
+            H5Z_FLAG_REVERSE,
+        },
+    };
+
+    use crate::filters::{gzip_available, szip_available, zstd_available, Preset, ZSTD_FILTER_ID};
+    use crate::hl::plist::file_create::AttrPhaseChangeInfo;
     use crate::internal_prelude::*;
 
     use super::infer_chunk_size;
 
     #[test]
     pub fn test_infer_chunk_size() {
-        assert_eq!(infer_chunk_size(&(), 1), vec![]);
+        assert_eq!(infer_chunk_size(&(), 1), Vec::<Ix>::new());
         assert_eq!(infer_chunk_size(&0, 1), vec![1]);
         assert_eq!(infer_chunk_size(&(1,), 1), vec![1]);
 
@@ -642,7 +1102,7 @@ pub mod tests {
             assert_eq!(d.is_scalar(), false);
 
             let d = file.new_dataset::<u8>().create_anon(()).unwrap();
-            assert_eq!(d.shape(), vec![]);
+            assert_eq!(d.shape(), Vec::<Ix>::new());
             assert_eq!(d.size(), 1);
             assert_eq!(d.ndim(), 0);
             assert_eq!(d.is_scalar(), true);
@@ -716,6 +1176,155 @@ pub mod tests {
         })
     }
 
+    #[test]
+    pub fn test_compress_presets() {
+        if !gzip_available() && !zstd_available() {
+            return;
+        }
+        with_tmp_file(|file| {
+            let check = |ds: Dataset, preset: Preset| {
+                assert!(ds.is_chunked());
+                let filters = ds.filters();
+                assert!(filters.get_shuffle());
+                assert_eq!(filters.get_szip(), None);
+                if zstd_available() {
+                    assert_eq!(filters.get_gzip(), None);
+                    assert_eq!(filters.get_user().len(), 1);
+                    assert_eq!(filters.get_user()[0].id(), ZSTD_FILTER_ID);
+                } else {
+                    assert_eq!(filters.get_gzip(), Some(preset.gzip_level()));
+                    assert!(filters.get_user().is_empty());
+                }
+            };
+            let ds = file.new_dataset::<u32>().compress_fast().create_anon(100).unwrap();
+            check(ds, Preset::Fast);
+            let ds = file.new_dataset::<u32>().compress_balanced().create_anon(100).unwrap();
+            check(ds, Preset::Balanced);
+            let ds = file.new_dataset::<u32>().compress_best().create_anon(100).unwrap();
+            check(ds, Preset::Best);
+            let ds = file.new_dataset::<u32>().gzip(2).compress_best().create_anon(100).unwrap();
+            check(ds, Preset::Best);
+
+            let ds = file.new_dataset::<u32>().chunk(10).compress_fast().create_anon(100).unwrap();
+            assert_eq!(ds.chunks(), Some(vec![10]));
+            let ds = file.new_dataset::<u32>().no_chunk().compress_fast().create_anon(100).unwrap();
+            check(ds, Preset::Fast);
+
+            let ds = file.new_dataset::<u32>().fletcher32(true).compress_fast().create_anon(100);
+            assert!(ds.unwrap().filters().get_fletcher32());
+        })
+    }
+
+    #[test]
+    pub fn test_scale_offset_float() {
+        with_tmp_path(|path| {
+            let data: Vec<f64> = (0..1000).map(|i| f64::from(i) / 7.).collect();
+            {
+                let file = File::create(&path).unwrap();
+                let ds = file
+                    .new_dataset::<f64>()
+                    .scale_offset(3)
+                    .chunk(100)
+                    .create("foo", data.len())
+                    .unwrap();
+                ds.write_raw(&data).unwrap();
+                assert_eq!(ds.filters().get_scale_offset(), Some(3));
+            }
+
+            let file = File::open(&path).unwrap();
+            let read: Vec<f64> = file.dataset("foo").unwrap().read_raw().unwrap();
+            assert_eq!(read.len(), data.len());
+            let max_err = data.iter().zip(&read).map(|(a, b)| (a - b).abs()).fold(0., f64::max);
+            assert!(max_err > 0.);
+            assert!(max_err <= 0.5e-3);
+        })
+    }
+
+    const TEST_FILTER_ID: H5Z_filter_t = 32101;
+
+    extern "C" fn test_filter(
+        flags: c_uint, _cd_nelmts: size_t, _cd_values: *const c_uint, nbytes: size_t,
+        _buf_size: *mut size_t, buf: *mut *mut c_void,
+    ) -> size_t {
+        // identity filter that refuses to encode chunks starting with a zero byte
+        if flags & H5Z_FLAG_REVERSE == 0 && unsafe { *(*buf as *const u8) } == 0 {
+            0
+        } else {
+            nbytes
+        }
+    }
+
+    #[test]
+    pub fn test_read_resilient() {
+        with_tmp_path(|path| {
+            let name = b"test\0";
+            let cls = H5Z_class2_t {
+                version: H5Z_CLASS_T_VERS as _,
+                id: TEST_FILTER_ID,
+                encoder_present: 1,
+                decoder_present: 1,
+                name: name.as_ptr() as *const _,
+                can_apply: None,
+                set_local: None,
+                filter: Some(test_filter),
+            };
+            h5call!(H5Zregister(&cls as *const _ as *const _)).unwrap();
+
+            let data: Vec<u8> = (0..40).map(|i| i / 10).collect();
+            {
+                let mut filters = Filters::new();
+                filters.user(TEST_FILTER_ID, &[]).optional(TEST_FILTER_ID, true);
+                let file = File::create(&path).unwrap();
+                let ds = file
+                    .new_dataset::<u8>()
+                    .filters(&filters)
+                    .fill_value(42)
+                    .chunk(10)
+                    .create("foo", 40)
+                    .unwrap();
+                ds.write_raw(&data).unwrap();
+                let read = ds.read_resilient::<u8>().unwrap();
+                assert!(read.is_complete());
+                assert_eq!(read.data.as_slice().unwrap(), data.as_slice());
+            }
+
+            h5call!(H5Zunregister(TEST_FILTER_ID)).unwrap();
+
+            let file = File::open(&path).unwrap();
+            let ds = file.dataset("foo").unwrap();
+            assert!(ds.read_raw::<u8>().is_err());
+
+            let read = ds.read_resilient::<u8>().unwrap();
+            assert_eq!(read.failed.as_slice().unwrap(), &[false, true, true, true]);
+            assert_eq!(read.num_failed(), 3);
+            assert!(!read.is_complete());
+
+            let mut expected = vec![42; 40];
+            expected[..10].copy_from_slice(&data[..10]);
+            assert_eq!(read.data.as_slice().unwrap(), expected.as_slice());
+        })
+    }
+
+    #[test]
+    pub fn test_read_resilient_contiguous() {
+        with_tmp_file(|file| {
+            let ds = file.new_dataset::<i32>().create("foo", (2, 3)).unwrap();
+            ds.write_raw(&[1, 2, 3, 4, 5, 6]).unwrap();
+            let read = ds.read_resilient::<i32>().unwrap();
+            assert_eq!(read.failed.shape(), &[1, 1]);
+            assert!(read.is_complete());
+            assert_eq!(read.data.as_slice().unwrap(), &[1, 2, 3, 4, 5, 6]);
+
+            let ds = file.new_dataset::<i32>().create("bar", ()).unwrap();
+            ds.write_scalar(&7).unwrap();
+            let read = ds.read_resilient::<i32>().unwrap();
+            assert!(read.is_complete());
+            assert_eq!(read.data.as_slice().unwrap(), &[7]);
+
+            assert_err!(file.dataset("foo").unwrap().read_resilient::<bool>(), "conversion path");
+        })
+    }
+
     #[test]
     pub fn test_resizable() {
         with_tmp_file(|file| {
@@ -731,6 +1340,79 @@ pub mod tests {
         })
     }
 
+    #[test]
+    pub fn test_bounded_extents() {
+        with_tmp_file(|file| {
+            let extents = (Extent::new(10).max(100), 3);
+            let ds = file.new_dataset::<u32>().create_with_extents("foo", extents).unwrap();
+            assert!(ds.is_resizable() && ds.is_chunked());
+            assert_eq!(ds.shape(), vec![10, 3]);
+            assert_eq!(ds.space().unwrap().maxdims(), vec![100, 3]);
+            assert_eq!(ds.space().unwrap().extents(), (Extent::new(10).max(100), 3).into());
+            ds.resize((100, 3)).unwrap();
+            assert_eq!(ds.shape(), vec![100, 3]);
+            assert_err!(ds.resize((101, 3)), "dimension cannot exceed the existing maximal size");
+            assert_err!(ds.resize((100, 4)), "(new: 4 max: 3)");
+
+            let ds = file.new_dataset::<u32>().create_with_extents("bar", Extent::unlimited(0));
+            assert_eq!(ds.unwrap().space().unwrap().extents().maxdims(), vec![None]);
+            let ds = file.new_dataset::<u32>().resizable(true).create_with_extents("baz", 2);
+            assert!(!ds.unwrap().is_resizable());
+            assert_err!(
+                file.new_dataset::<u32>().create_with_extents("qux", Extent::new(5).max(4)),
+                "maximum extent 4 is smaller than current extent 5"
+            );
+            assert_err!(
+                file.new_dataset::<u32>()
+                    .no_chunk()
+                    .create_with_extents("qux", Extent::new(5).max(6)),
+                "Chunking must be enabled for resizable datasets"
+            );
+        })
+    }
+
+    #[test]
+    pub fn test_attr_phase_change() {
+        with_tmp_file(|file| {
+            let ds = file.new_dataset::<u8>().create("foo", 1).unwrap();
+            assert_eq!(ds.attr_phase_change().unwrap(), AttrPhaseChangeInfo::default());
+
+            let ds = file.new_dataset::<u8>().attr_phase_change(0, 0).create("bar", 1).unwrap();
+            let info = ds.attr_phase_change().unwrap();
+            assert_eq!((info.max_compact, info.min_dense), (0, 0));
+            for i in 0..20 {
+                ds.set_attr(&format!("attr{}", i), &i).unwrap();
+            }
+            assert_eq!(ds.num_attrs().unwrap(), 20);
+            assert_eq!(ds.get_attr::<i32>("attr13").unwrap(), 13);
+
+            assert_err!(
+                file.new_dataset::<u8>().attr_phase_change(4, 8).create("baz", 1),
+                "max compact value must be >= min dense value"
+            );
+        })
+    }
+
+    #[test]
+    #[cfg(hdf5_1_10_5)]
+    pub fn test_minimize_header() {
+        with_tmp_file(|file| {
+            let ds = file.new_dataset::<u8>().create("foo", 1).unwrap();
+            assert!(!ds.dcpl().unwrap().minimize_header());
+            let ds = file.new_dataset::<u8>().minimize_header(true).create("bar", 1).unwrap();
+            assert!(ds.dcpl().unwrap().minimize_header());
+            ds.new_attr::<u32>().create("a", ()).unwrap().write_scalar(&1).unwrap();
+            assert_eq!(ds.attr("a").unwrap().read_scalar::<u32>().unwrap(), 1);
+
+            file.set_minimize_dataset_headers(true).unwrap();
+            let ds = file.new_dataset::<u8>().minimize_header(false).create("baz", 1).unwrap();
+            assert!(!ds.dcpl().unwrap().minimize_header());
+            let dcpl = file.dataset("bar").unwrap().dcpl().unwrap();
+            let ds = file.new_dataset::<u8>().set_dcpl(&dcpl).unwrap().create("qux", 1).unwrap();
+            assert!(ds.dcpl().unwrap().minimize_header());
+        })
+    }
+
     #[test]
     pub fn test_track_times() {
         with_tmp_file(|file| {
@@ -795,6 +1477,32 @@ pub mod tests {
         })
     }
 
+    #[test]
+    #[cfg(hdf5_1_10_3)]
+    pub fn test_read_raw_chunk() {
+        with_tmp_file(|file| {
+            let arr = ndarray::Array2::from_shape_fn((4, 6), |(i, j)| (i * 6 + j) as u8);
+            let ds = file.new_dataset::<u8>().chunk((2, 3)).create("x", (4, 6)).unwrap();
+            ds.write(&arr).unwrap();
+            let (buf, mask) = ds.read_raw_chunk(&[2, 3]).unwrap();
+            assert_eq!(mask, 0);
+            assert_eq!(buf, vec![15, 16, 17, 21, 22, 23]);
+            assert_err!(ds.read_raw_chunk(&[0]), "chunk offset has 1 dims, dataset has 2");
+
+            let ds = file.new_dataset::<u8>().create("y", 3).unwrap();
+            assert_err!(ds.read_raw_chunk(&[0]), "dataset is not chunked");
+
+            if gzip_available() {
+                let ds =
+                    file.new_dataset::<u8>().chunk((4, 6)).gzip(9).create("z", (4, 6)).unwrap();
+                ds.write(&ndarray::Array2::<u8>::zeros((4, 6))).unwrap();
+                let (compressed, filter_mask) = ds.read_raw_chunk(&[0, 0]).unwrap();
+                assert_eq!(filter_mask, 0);
+                assert!(!compressed.is_empty() && compressed.len() < 24);
+            }
+        })
+    }
+
     #[test]
     pub fn test_datatype() {
         with_tmp_file(|file| {
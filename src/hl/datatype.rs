@@ -2,32 +2,45 @@ use std::borrow::Borrow;
 use std::cmp::{Ordering, PartialEq, PartialOrd};
 use std::fmt::{self, Debug, Display};
 use std::ops::Deref;
+use std::slice;
 
+#[cfg(feature = "hl")]
+use hdf5_sys::h5lt::{H5LTdtype_to_text, H5LT_DDL};
 use hdf5_sys::h5t::{
-    H5T_cdata_t, H5T_class_t, H5T_cset_t, H5T_order_t, H5T_str_t, H5Tarray_create2,
-    H5Tcompiler_conv, H5Tcopy, H5Tcreate, H5Tenum_create, H5Tenum_insert, H5Tequal, H5Tfind,
-    H5Tget_array_dims2, H5Tget_array_ndims, H5Tget_class, H5Tget_cset, H5Tget_member_name,
-    H5Tget_member_offset, H5Tget_member_type, H5Tget_member_value, H5Tget_nmembers, H5Tget_order,
-    H5Tget_sign, H5Tget_size, H5Tget_super, H5Tinsert, H5Tis_variable_str, H5Tset_cset,
-    H5Tset_size, H5Tset_strpad, H5Tvlen_create, H5T_VARIABLE,
+    H5T_bkg_t, H5T_cdata_t, H5T_class_t, H5T_conv_t, H5T_cset_t, H5T_order_t, H5T_str_t,
+    H5Tarray_create2, H5Tcommitted, H5Tcompiler_conv, H5Tcopy, H5Tcreate, H5Tenum_create,
+    H5Tenum_insert, H5Tequal, H5Tfind, H5Tget_array_dims2, H5Tget_array_ndims, H5Tget_class,
+    H5Tget_create_plist, H5Tget_cset, H5Tget_ebias, H5Tget_fields, H5Tget_member_name,
+    H5Tget_member_offset, H5Tget_member_type, H5Tget_member_value, H5Tget_nmembers, H5Tget_offset,
+    H5Tget_order, H5Tget_precision, H5Tget_sign, H5Tget_size, H5Tget_strpad, H5Tget_super,
+    H5Tget_tag, H5Tinsert, H5Tis_variable_str, H5Tregister, H5Tset_cset, H5Tset_ebias,
+    H5Tset_fields, H5Tset_offset, H5Tset_order, H5Tset_precision, H5Tset_size, H5Tset_strpad,
+    H5Tset_tag, H5Tunregister, H5Tvlen_create, H5T_PERS_SOFT, H5T_VARIABLE,
 };
 use hdf5_types::{
-    CompoundField, CompoundType, EnumMember, EnumType, FloatSize, H5Type, IntSize, TypeDescriptor,
+    BitfieldType, CompoundField, CompoundType, EnumMember, EnumType, FloatSize, H5Type, IntSize,
+    OpaqueType, ReferenceType, TypeDescriptor,
 };
 
-use crate::globals::{H5T_C_S1, H5T_NATIVE_INT, H5T_NATIVE_INT8};
+use crate::globals::{
+    H5T_C_S1, H5T_NATIVE_INT, H5T_NATIVE_INT8, H5T_STD_REF_DSETREG, H5T_STD_REF_OBJ,
+};
+use crate::hl::plist::attribute_create::CharEncoding;
+use crate::hl::plist::datatype_create::DatatypeCreate;
 use crate::internal_prelude::*;
 
 #[cfg(target_endian = "big")]
 use crate::globals::{
-    H5T_IEEE_F32BE, H5T_IEEE_F64BE, H5T_STD_I16BE, H5T_STD_I32BE, H5T_STD_I64BE, H5T_STD_I8BE,
-    H5T_STD_U16BE, H5T_STD_U32BE, H5T_STD_U64BE, H5T_STD_U8BE,
+    H5T_IEEE_F32BE, H5T_IEEE_F64BE, H5T_STD_B16BE, H5T_STD_B32BE, H5T_STD_B64BE, H5T_STD_B8BE,
+    H5T_STD_I16BE, H5T_STD_I32BE, H5T_STD_I64BE, H5T_STD_I8BE, H5T_STD_U16BE, H5T_STD_U32BE,
+    H5T_STD_U64BE, H5T_STD_U8BE,
 };
 
 #[cfg(target_endian = "little")]
 use crate::globals::{
-    H5T_IEEE_F32LE, H5T_IEEE_F64LE, H5T_STD_I16LE, H5T_STD_I32LE, H5T_STD_I64LE, H5T_STD_I8LE,
-    H5T_STD_U16LE, H5T_STD_U32LE, H5T_STD_U64LE, H5T_STD_U8LE,
+    H5T_IEEE_F32LE, H5T_IEEE_F64LE, H5T_STD_B16LE, H5T_STD_B32LE, H5T_STD_B64LE, H5T_STD_B8LE,
+    H5T_STD_I16LE, H5T_STD_I32LE, H5T_STD_I64LE, H5T_STD_I8LE, H5T_STD_U16LE, H5T_STD_U32LE,
+    H5T_STD_U64LE, H5T_STD_U8LE,
 };
 
 #[cfg(target_endian = "big")]
@@ -153,7 +166,354 @@ impl From<H5T_order_t> for ByteOrder {
     }
 }
 
+/// Padding of strings shorter than the size of a fixed-length string datatype.
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub enum StringPadding {
+    /// Null-terminated, as in C; the terminator takes up one byte of the size.
+    NullTerm,
+    /// Padded with null bytes; strings of full size are not terminated.
+    NullPad,
+    /// Padded with spaces, as in Fortran.
+    SpacePad,
+}
+
+impl StringPadding {
+    pub(crate) fn from_raw(padding: H5T_str_t) -> Option<Self> {
+        match padding {
+            H5T_str_t::H5T_STR_NULLTERM => Some(StringPadding::NullTerm),
+            H5T_str_t::H5T_STR_NULLPAD => Some(StringPadding::NullPad),
+            H5T_str_t::H5T_STR_SPACEPAD => Some(StringPadding::SpacePad),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_raw(self) -> H5T_str_t {
+        match self {
+            StringPadding::NullTerm => H5T_str_t::H5T_STR_NULLTERM,
+            StringPadding::NullPad => H5T_str_t::H5T_STR_NULLPAD,
+            StringPadding::SpacePad => H5T_str_t::H5T_STR_SPACEPAD,
+        }
+    }
+}
+
+/// Class of a datatype.
+#[derive(Copy, Debug, Clone, PartialEq, Eq)]
+pub enum TypeClass {
+    Integer,
+    Float,
+    Time,
+    String,
+    Bitfield,
+    Opaque,
+    Compound,
+    Reference,
+    Enum,
+    VarLen,
+    Array,
+}
+
+impl TypeClass {
+    fn from_raw(class: H5T_class_t) -> Option<Self> {
+        use hdf5_sys::h5t::H5T_class_t::*;
+
+        match class {
+            H5T_INTEGER => Some(TypeClass::Integer),
+            H5T_FLOAT => Some(TypeClass::Float),
+            H5T_TIME => Some(TypeClass::Time),
+            H5T_STRING => Some(TypeClass::String),
+            H5T_BITFIELD => Some(TypeClass::Bitfield),
+            H5T_OPAQUE => Some(TypeClass::Opaque),
+            H5T_COMPOUND => Some(TypeClass::Compound),
+            H5T_REFERENCE => Some(TypeClass::Reference),
+            H5T_ENUM => Some(TypeClass::Enum),
+            H5T_VLEN => Some(TypeClass::VarLen),
+            H5T_ARRAY => Some(TypeClass::Array),
+            _ => None,
+        }
+    }
+}
+
+/// Member of a compound datatype.
+#[derive(Clone, Debug)]
+pub struct CompoundMember {
+    pub name: String,
+    /// Byte offset of the member within the compound type.
+    pub offset: usize,
+    pub datatype: Datatype,
+}
+
+/// Returns the descriptor of the datatype `T` is stored as: its explicit stored layout if it
+/// has one, or else the packed or C layout of its in-memory descriptor.
+pub(crate) fn stored_descriptor<T: H5Type>(packed: bool) -> TypeDescriptor {
+    T::stored_type_descriptor().unwrap_or_else(|| {
+        if packed {
+            T::type_descriptor().to_packed_repr()
+        } else {
+            T::type_descriptor().to_c_repr()
+        }
+    })
+}
+
+/// Bit layout of a floating-point datatype, see `Datatype::float_layout()`.
+///
+/// Positions of the sign bit, exponent and mantissa are counted in bits from `offset`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FloatLayout {
+    /// Size of the datatype in bytes.
+    pub size: usize,
+    /// Number of significant bits.
+    pub precision: usize,
+    /// Bit offset of the first significant bit.
+    pub offset: usize,
+    pub sign_pos: usize,
+    pub exp_pos: usize,
+    pub exp_size: usize,
+    pub mant_pos: usize,
+    pub mant_size: usize,
+    pub exp_bias: usize,
+}
+
+impl FloatLayout {
+    /// Returns the size of the native float type values of this layout are read as:
+    /// `f32` if its exponent and mantissa fit, `f64` otherwise.
+    pub fn native_size(&self) -> FloatSize {
+        if self.exp_size <= 8 && self.mant_size <= 23 {
+            FloatSize::U4
+        } else {
+            FloatSize::U8
+        }
+    }
+}
+
+/// Creates a copy of the datatype with the given byte order, descending into nested types
+/// since `H5Tset_order()` can't be applied to enums (or compounds containing them).
+unsafe fn reorder_type(dtype: &Datatype, order: H5T_order_t) -> Result<Datatype> {
+    use hdf5_sys::h5t::H5T_class_t::*;
+
+    let id = dtype.id();
+    let reorder_super = || reorder_type(&Datatype::from_id(H5Tget_super(id))?, order);
+    match H5Tget_class(id) {
+        H5T_ENUM => {
+            let base = Datatype::from_id(H5Tget_super(id))?;
+            let swap = H5Tget_order(base.id()) != order;
+            let enum_dt =
+                Datatype::from_id(h5try!(H5Tenum_create(reorder_type(&base, order)?.id())))?;
+            for idx in 0..h5try!(H5Tget_nmembers(id)) as _ {
+                let mut value = [0_u8; 16];
+                h5try!(H5Tget_member_value(id, idx, value.as_mut_ptr() as *mut _));
+                if swap {
+                    value[..base.size()].reverse();
+                }
+                let name = H5Tget_member_name(id, idx);
+                ensure!(!name.is_null(), "Invalid enum member name");
+                let res = H5Tenum_insert(enum_dt.id(), name, value.as_ptr() as *const _);
+                libc::free(name as *mut _);
+                h5check(res)?;
+            }
+            Ok(enum_dt)
+        }
+        H5T_COMPOUND => {
+            let compound_dt = Datatype::from_id(h5try!(H5Tcreate(H5T_COMPOUND, dtype.size())))?;
+            for idx in 0..h5try!(H5Tget_nmembers(id)) as _ {
+                let member = Datatype::from_id(h5try!(H5Tget_member_type(id, idx)))?;
+                let member = reorder_type(&member, order)?;
+                let offset = H5Tget_member_offset(id, idx);
+                let name = H5Tget_member_name(id, idx);
+                ensure!(!name.is_null(), "Invalid compound member name");
+                let res = H5Tinsert(compound_dt.id(), name, offset, member.id());
+                libc::free(name as *mut _);
+                h5check(res)?;
+            }
+            Ok(compound_dt)
+        }
+        H5T_ARRAY => {
+            let ndims = h5try!(H5Tget_array_ndims(id));
+            let mut dims = vec![0 as hsize_t; ndims as _];
+            h5try!(H5Tget_array_dims2(id, dims.as_mut_ptr()));
+            let base = reorder_super()?;
+            Datatype::from_id(h5try!(H5Tarray_create2(base.id(), ndims as _, dims.as_ptr())))
+        }
+        H5T_VLEN => Datatype::from_id(h5try!(H5Tvlen_create(reorder_super()?.id()))),
+        H5T_STRING | H5T_OPAQUE | H5T_REFERENCE => Datatype::from_id(h5try!(H5Tcopy(id))),
+        _ => {
+            let reordered = Datatype::from_id(h5try!(H5Tcopy(id)))?;
+            h5try!(H5Tset_order(reordered.id(), order));
+            Ok(reordered)
+        }
+    }
+}
+
+/// Checks that values of the stored datatype `file` can be read as `mem`, descending into
+/// compound members (`defaults` lists the members allowed to be missing at this level)
+/// and arrays; `path` names the checked member for diagnostics.
+fn check_compatible(
+    file: &Datatype, mem: &TypeDescriptor, path: &str, defaults: &[&str],
+) -> Result<()> {
+    use hdf5_types::TypeDescriptor as TD;
+
+    let at = |path: &str| if path.is_empty() { String::new() } else { format!("{}: ", path) };
+    let join =
+        |name: &str| if path.is_empty() { name.to_owned() } else { format!("{}.{}", path, name) };
+    match (file.class()?, mem) {
+        (TypeClass::Compound, TD::Compound(compound)) => {
+            let members = file.compound_members()?;
+            for field in &compound.fields {
+                match members.iter().find(|m| m.name == field.name) {
+                    Some(member) => {
+                        check_compatible(&member.datatype, &field.ty, &join(&field.name), &[])?
+                    }
+                    None if defaults.contains(&field.name.as_str()) => {}
+                    None => {
+                        fail!("{}member {:?} not found in stored datatype", at(path), field.name)
+                    }
+                }
+            }
+            Ok(())
+        }
+        (TypeClass::Array, TD::FixedArray(ty, len)) => {
+            let dims = file.array_dims()?;
+            let stored_len: usize = dims.iter().product();
+            ensure!(
+                stored_len == *len,
+                "{}expected array of length {}, stored array has dims {:?}",
+                at(path),
+                len,
+                dims
+            );
+            check_compatible(&file.base_type()?, ty, &format!("{}[]", path), &[])
+        }
+        (TypeClass::VarLen, TD::VarLenArray(ty)) => {
+            check_compatible(&file.base_type()?, ty, &format!("{}[]", path), &[])
+        }
+        (TypeClass::String, TD::FixedAscii(_)) | (TypeClass::String, TD::VarLenAscii) => {
+            ensure!(
+                file.char_encoding() != Some(CharEncoding::Utf8),
+                "{}can't read UTF-8 strings as ASCII",
+                at(path)
+            );
+            Ok(())
+        }
+        (TypeClass::String, TD::FixedUnicode(_)) | (TypeClass::String, TD::VarLenUnicode) => Ok(()),
+        (TypeClass::Enum, TD::Enum(enum_type)) => {
+            let unknown: Vec<_> = file
+                .enum_members()?
+                .into_iter()
+                .map(|m| m.name)
+                .filter(|name| !enum_type.members.iter().any(|m| &m.name == name))
+                .collect();
+            ensure!(
+                unknown.is_empty(),
+                "{}stored enum members {:?} have no match",
+                at(path),
+                unknown
+            );
+            Ok(())
+        }
+        (class, _) => {
+            let mem_dtype = Datatype::from_descriptor(mem)?;
+            ensure!(
+                file.is_convertible_to(&mem_dtype),
+                "{}can't convert stored {:?} datatype to {:?}",
+                at(path),
+                class,
+                mem
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Element-wise conversion between two datatypes, used by HDF5 as a soft conversion
+/// function once registered via [`Datatype::register_soft_conversion()`].
+///
+/// [`Datatype::register_soft_conversion()`]: struct.Datatype.html#method.register_soft_conversion
+pub trait SoftConversion: 'static {
+    /// Returns `true` if this conversion handles the given pair of datatypes.
+    ///
+    /// HDF5 queries soft conversions for every source and destination pair of the
+    /// registered type classes; by default, all such pairs are accepted.
+    fn accepts(_src: &Datatype, _dst: &Datatype) -> bool {
+        true
+    }
+
+    /// Converts a single element; `src` and `dst` are sized as the respective datatypes.
+    fn convert(src: &[u8], dst: &mut [u8]) -> Result<()>;
+}
+
+fn convert_elements<C: SoftConversion>(
+    src_id: hid_t, dst_id: hid_t, nelmts: usize, buf_stride: usize, buf: *mut u8,
+) -> Result<()> {
+    let (src_size, dst_size) = h5lock!((H5Tget_size(src_id), H5Tget_size(dst_id)));
+    ensure!(src_size > 0 && dst_size > 0, "Invalid datatype size");
+    let (src_stride, dst_stride) =
+        if buf_stride == 0 { (src_size, dst_size) } else { (buf_stride, buf_stride) };
+    // elements are converted in place, so go backwards if they grow
+    let mut src = vec![0_u8; src_size];
+    let mut convert_at = |i: usize| unsafe {
+        src.copy_from_slice(slice::from_raw_parts(buf.add(i * src_stride), src_size));
+        C::convert(&src, slice::from_raw_parts_mut(buf.add(i * dst_stride), dst_size))
+    };
+    if dst_stride > src_stride {
+        (0..nelmts).rev().try_for_each(&mut convert_at)
+    } else {
+        (0..nelmts).try_for_each(&mut convert_at)
+    }
+}
+
+extern "C" fn soft_conversion<C: SoftConversion>(
+    src_id: hid_t, dst_id: hid_t, cdata: *mut H5T_cdata_t, nelmts: size_t, buf_stride: size_t,
+    _bkg_stride: size_t, buf: *mut c_void, _bkg: *mut c_void, _dxpl: hid_t,
+) -> herr_t {
+    use hdf5_sys::h5t::H5T_cmd_t::*;
+
+    let res = catch_callback_panic(Err(Error::from("panic")), || unsafe {
+        match (*cdata).command {
+            H5T_CONV_INIT => {
+                (*cdata).need_bkg = H5T_bkg_t::H5T_BKG_NO;
+                let src = Datatype::from_id(h5try!(H5Tcopy(src_id)))?;
+                let dst = Datatype::from_id(h5try!(H5Tcopy(dst_id)))?;
+                ensure!(C::accepts(&src, &dst), "Conversion not applicable");
+                Ok(())
+            }
+            H5T_CONV_CONV => {
+                convert_elements::<C>(src_id, dst_id, nelmts, buf_stride, buf as *mut u8)
+            }
+            H5T_CONV_FREE => Ok(()),
+        }
+    });
+    if res.is_ok() {
+        0
+    } else {
+        -1
+    }
+}
+
 impl Datatype {
+    /// Returns `true` if the datatype has been committed to a file as a named datatype.
+    pub fn is_committed(&self) -> bool {
+        h5call!(H5Tcommitted(self.id())).map_or(false, |committed| committed > 0)
+    }
+
+    /// Returns a named location referring to a committed datatype (e.g. in order to
+    /// attach attributes to it).
+    pub fn as_location(&self) -> Result<Location> {
+        ensure!(self.is_committed(), "datatype is not committed");
+        Ok(Location::from_handle(self.handle().clone()))
+    }
+
+    /// Returns a copy of the datatype creation property list.
+    ///
+    /// Note that HDF5 doesn't persist the creation properties of committed datatypes, so
+    /// the returned list holds the defaults rather than the properties used to commit it.
+    pub fn create_plist(&self) -> Result<DatatypeCreate> {
+        h5lock!(DatatypeCreate::from_id(h5try!(H5Tget_create_plist(self.id()))))
+    }
+
+    /// A short alias for `create_plist()`.
+    pub fn tcpl(&self) -> Result<DatatypeCreate> {
+        self.create_plist()
+    }
+
     /// Get the total size of the datatype in bytes.
     pub fn size(&self) -> usize {
         h5call!(H5Tget_size(self.id())).unwrap_or(0) as usize
@@ -164,6 +524,106 @@ impl Datatype {
         h5lock!(H5Tget_order(self.id())).into()
     }
 
+    /// Returns the padding of a string datatype, or `None` for other datatypes.
+    pub fn string_padding(&self) -> Option<StringPadding> {
+        h5lock!({
+            if H5Tget_class(self.id()) == H5T_class_t::H5T_STRING {
+                StringPadding::from_raw(H5Tget_strpad(self.id()))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns a copy of a string datatype using the given padding.
+    ///
+    /// When data is read or written, HDF5 converts between paddings, so fixed-length
+    /// strings from e.g. Fortran producers can be read into null-padded Rust strings.
+    pub fn with_string_padding(&self, padding: StringPadding) -> Result<Self> {
+        h5lock!({
+            ensure!(
+                H5Tget_class(self.id()) == H5T_class_t::H5T_STRING,
+                "string padding can only be set for string datatypes"
+            );
+            let datatype = Self::from_id(h5try!(H5Tcopy(self.id())))?;
+            h5try!(H5Tset_strpad(datatype.id(), padding.to_raw()));
+            Ok(datatype)
+        })
+    }
+
+    /// Returns the character set of a string datatype, or `None` for other datatypes.
+    pub fn char_encoding(&self) -> Option<CharEncoding> {
+        h5lock!({
+            if H5Tget_class(self.id()) == H5T_class_t::H5T_STRING {
+                Some(H5Tget_cset(self.id()).into())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns a copy of a string datatype using the given character set.
+    pub fn with_char_encoding(&self, encoding: CharEncoding) -> Result<Self> {
+        h5lock!({
+            ensure!(
+                H5Tget_class(self.id()) == H5T_class_t::H5T_STRING,
+                "character set can only be set for string datatypes"
+            );
+            let datatype = Self::from_id(h5try!(H5Tcopy(self.id())))?;
+            h5try!(H5Tset_cset(datatype.id(), encoding.into()));
+            Ok(datatype)
+        })
+    }
+
+    /// Relabels a string memory type with the character set of the file type.
+    ///
+    /// HDF5 can't convert between ASCII and UTF-8 strings, but the stored bytes are the
+    /// same for ASCII text, so UTF-8 strings can always be read from ASCII ones. Reading
+    /// UTF-8 as ASCII fails; writing is allowed either way, since the stored character set
+    /// has been chosen explicitly. Strings nested in other types are not relabeled.
+    pub(crate) fn with_char_encoding_of(self, file_dtype: &Self, reading: bool) -> Result<Self> {
+        match (self.char_encoding(), file_dtype.char_encoding()) {
+            (Some(CharEncoding::Ascii), Some(CharEncoding::Utf8)) if reading => {
+                fail!("can't read UTF-8 strings as ASCII")
+            }
+            (Some(mem), Some(file)) if mem != file => self.with_char_encoding(file),
+            _ => Ok(self),
+        }
+    }
+
+    /// Returns a copy of the datatype using the given byte order.
+    ///
+    /// Compound, array, enum and variable-length array types are converted recursively;
+    /// only `LittleEndian` and `BigEndian` can be set.
+    pub fn with_byte_order(&self, order: ByteOrder) -> Result<Self> {
+        let order = match order {
+            ByteOrder::LittleEndian => H5T_order_t::H5T_ORDER_LE,
+            ByteOrder::BigEndian => H5T_order_t::H5T_ORDER_BE,
+            _ => fail!("byte order must be little-endian or big-endian, got {:?}", order),
+        };
+        h5lock!(reorder_type(self, order))
+    }
+
+    /// Applies storage options selected in a dataset or attribute builder.
+    pub(crate) fn with_storage_options(
+        self, padding: Option<StringPadding>, encoding: Option<CharEncoding>,
+        order: Option<ByteOrder>,
+    ) -> Result<Self> {
+        let mut datatype = self;
+        if let Some(padding) = padding {
+            datatype = datatype.with_string_padding(padding)?;
+        }
+        if let Some(encoding) = encoding {
+            datatype = datatype.with_char_encoding(encoding)?;
+        }
+        if let Some(order) = order {
+            datatype = datatype.with_byte_order(order)?;
+        }
+        Ok(datatype)
+    }
+
+    /// Returns the kind of conversion path from this datatype to `dst`, or `None` if the
+    /// conversion isn't supported (including by any registered soft conversions).
     pub fn conv_path<D>(&self, dst: D) -> Option<Conversion>
     where
         D: Borrow<Self>,
@@ -196,6 +656,50 @@ impl Datatype {
         Self::from_type::<T>().ok().and_then(|dtype| dtype.conv_path(self))
     }
 
+    /// Returns `true` if values of this datatype can be converted to `dst`.
+    pub fn is_convertible_to<D: Borrow<Self>>(&self, dst: D) -> bool {
+        self.conv_path(dst).is_some()
+    }
+
+    /// Checks whether values of this (stored) datatype can be read as `T`.
+    ///
+    /// Compound types are compared member by member, so that the error pinpoints the
+    /// first incompatible member (e.g. `"a.b: can't convert stored ..."`); members of `T`
+    /// with defaults may be missing, and all stored enum members must exist in `T`.
+    pub fn compatible_with<T: H5Type>(&self) -> Result<()> {
+        check_compatible(self, &T::type_descriptor(), "", T::default_members())
+    }
+
+    /// Registers a soft conversion from datatypes of this class to those of the class of
+    /// `dst`, which HDF5 then uses transparently during reads and writes.
+    ///
+    /// `C::accepts()` narrows down the pairs of datatypes the conversion applies to; a
+    /// previously registered conversion with the same `name` isn't replaced, but later
+    /// registrations take precedence.
+    pub fn register_soft_conversion<C, D>(&self, name: &str, dst: D) -> Result<()>
+    where
+        C: SoftConversion,
+        D: Borrow<Self>,
+    {
+        let name = to_cstring(name)?;
+        let func: H5T_conv_t = Some(soft_conversion::<C>);
+        h5call!(H5Tregister(H5T_PERS_SOFT, name.as_ptr(), self.id(), dst.borrow().id(), func))
+            .and(Ok(()))
+    }
+
+    /// Removes the soft conversion `C` registered under `name` for the type classes of this
+    /// datatype and `dst`; conversion paths already using it are discarded.
+    pub fn unregister_soft_conversion<C, D>(&self, name: &str, dst: D) -> Result<()>
+    where
+        C: SoftConversion,
+        D: Borrow<Self>,
+    {
+        let name = to_cstring(name)?;
+        let func: H5T_conv_t = Some(soft_conversion::<C>);
+        h5call!(H5Tunregister(H5T_PERS_SOFT, name.as_ptr(), self.id(), dst.borrow().id(), func))
+            .and(Ok(()))
+    }
+
     pub fn is<T: H5Type>(&self) -> bool {
         Self::from_type::<T>().ok().map_or(false, |dtype| &dtype == self)
     }
@@ -213,6 +717,152 @@ impl Datatype {
         }
     }
 
+    /// Returns names of the members if this is a compound datatype, or an empty vector.
+    pub(crate) fn compound_member_names(&self) -> Result<Vec<String>> {
+        if self.class()? == TypeClass::Compound {
+            Ok(self.compound_members()?.into_iter().map(|member| member.name).collect())
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Returns the class of the datatype.
+    pub fn class(&self) -> Result<TypeClass> {
+        TypeClass::from_raw(h5lock!(H5Tget_class(self.id())))
+            .ok_or_else(|| "Invalid datatype class".into())
+    }
+
+    pub(crate) fn ensure_class(&self, class: TypeClass) -> Result<()> {
+        let actual = self.class()?;
+        ensure!(actual == class, "expected {:?} datatype, got {:?}", class, actual);
+        Ok(())
+    }
+
+    /// Returns the members of a compound datatype in the order of their indices.
+    pub fn compound_members(&self) -> Result<Vec<CompoundMember>> {
+        self.ensure_class(TypeClass::Compound)?;
+        h5lock!({
+            let id = self.id();
+            let mut members = Vec::new();
+            for idx in 0..h5try!(H5Tget_nmembers(id)) as _ {
+                let datatype = Self::from_id(h5try!(H5Tget_member_type(id, idx)))?;
+                let raw_name = H5Tget_member_name(id, idx);
+                ensure!(!raw_name.is_null(), "Invalid compound member name");
+                let name = string_from_cstr(raw_name);
+                libc::free(raw_name as *mut _);
+                let offset = H5Tget_member_offset(id, idx);
+                members.push(CompoundMember { name, offset, datatype });
+            }
+            Ok(members)
+        })
+    }
+
+    /// Returns the members of an enum datatype with their values.
+    ///
+    /// Values are converted to native byte order; negative values of signed enums are
+    /// stored as their two's complement truncated to the size of the base type.
+    pub fn enum_members(&self) -> Result<Vec<EnumMember>> {
+        self.ensure_class(TypeClass::Enum)?;
+        h5lock!({
+            let id = self.id();
+            let base_dt = self.base_type()?;
+            // member values are stored in the byte order of the base type
+            let swap = H5Tget_order(base_dt.id()) != H5Tget_order(*H5T_NATIVE_INT);
+            let mut members = Vec::new();
+            for idx in 0..h5try!(H5Tget_nmembers(id)) as _ {
+                let mut buf = [0_u8; 16];
+                h5try!(H5Tget_member_value(id, idx, buf.as_mut_ptr() as *mut _));
+                if swap {
+                    buf[..base_dt.size()].reverse();
+                }
+                let mut value = [0_u8; 8];
+                value.copy_from_slice(&buf[..8]);
+                let value = u64::from_ne_bytes(value);
+                let name = H5Tget_member_name(id, idx);
+                ensure!(!name.is_null(), "Invalid enum member name");
+                members.push(EnumMember { name: string_from_cstr(name), value });
+                libc::free(name as *mut _);
+            }
+            Ok(members)
+        })
+    }
+
+    /// Returns the dimensions of an array datatype.
+    pub fn array_dims(&self) -> Result<Vec<Ix>> {
+        self.ensure_class(TypeClass::Array)?;
+        h5lock!({
+            let ndims = h5try!(H5Tget_array_ndims(self.id()));
+            let mut dims = vec![0 as hsize_t; ndims as _];
+            h5try!(H5Tget_array_dims2(self.id(), dims.as_mut_ptr()));
+            Ok(dims.into_iter().map(|d| d as _).collect())
+        })
+    }
+
+    /// Returns the base type of an enum, array or variable-length array datatype.
+    pub fn base_type(&self) -> Result<Self> {
+        match self.class()? {
+            TypeClass::Enum | TypeClass::Array | TypeClass::VarLen => {
+                Self::from_id(h5lock!(H5Tget_super(self.id())))
+            }
+            class => fail!("{:?} datatype has no base type", class),
+        }
+    }
+
+    /// Returns the bit layout of a floating-point datatype.
+    pub fn float_layout(&self) -> Result<FloatLayout> {
+        self.ensure_class(TypeClass::Float)?;
+        h5lock!({
+            let id = self.id();
+            let (mut sign_pos, mut exp_pos, mut exp_size, mut mant_pos, mut mant_size) =
+                (0, 0, 0, 0, 0);
+            h5try!(H5Tget_fields(
+                id,
+                &mut sign_pos,
+                &mut exp_pos,
+                &mut exp_size,
+                &mut mant_pos,
+                &mut mant_size
+            ));
+            Ok(FloatLayout {
+                size: h5try!(H5Tget_size(id)),
+                precision: h5try!(H5Tget_precision(id)),
+                offset: h5try!(H5Tget_offset(id)) as _,
+                sign_pos,
+                exp_pos,
+                exp_size,
+                mant_pos,
+                mant_size,
+                exp_bias: H5Tget_ebias(id),
+            })
+        })
+    }
+
+    /// Creates a floating-point datatype with a custom bit layout (in native byte order).
+    ///
+    /// Values of such datatypes can be converted to and from `f32` and `f64`.
+    pub fn from_float_layout(layout: &FloatLayout) -> Result<Self> {
+        h5lock!({
+            let dtype = Self::from_id(be_le!(H5T_IEEE_F64BE, H5T_IEEE_F64LE))?;
+            let id = dtype.id();
+            if layout.size > dtype.size() {
+                h5try!(H5Tset_size(id, layout.size));
+            }
+            // shrink the type only after the new fields and precision have been set
+            let l = layout;
+            h5try!(H5Tset_fields(id, l.sign_pos, l.exp_pos, l.exp_size, l.mant_pos, l.mant_size));
+            h5try!(H5Tset_precision(id, l.precision));
+            h5try!(H5Tset_offset(id, l.offset));
+            h5try!(H5Tset_size(id, l.size));
+            h5try!(H5Tset_ebias(id, l.exp_bias));
+            Ok(dtype)
+        })
+    }
+
+    /// Returns `true` if this is a variable-length string datatype.
+    pub fn is_variable_str(&self) -> bool {
+        h5call!(H5Tis_variable_str(self.id())).map_or(false, |is_variable| is_variable > 0)
+    }
+
     pub fn to_descriptor(&self) -> Result<TypeDescriptor> {
         use hdf5_sys::h5t::{H5T_class_t::*, H5T_sign_t::*};
         use hdf5_types::TypeDescriptor as TD;
@@ -230,20 +880,11 @@ impl Datatype {
                     let size = IntSize::from_int(size).ok_or("Invalid size of integer datatype")?;
                     Ok(if signed { TD::Integer(size) } else { TD::Unsigned(size) })
                 }
-                H5T_FLOAT => {
-                    let size = FloatSize::from_int(size).ok_or("Invalid size of float datatype")?;
-                    Ok(TD::Float(size))
-                }
+                // non-IEEE layouts are described by the native type they're read as
+                H5T_FLOAT => Ok(TD::Float(self.float_layout()?.native_size())),
                 H5T_ENUM => {
-                    let mut members: Vec<EnumMember> = Vec::new();
-                    for idx in 0..h5try!(H5Tget_nmembers(id)) as _ {
-                        let mut value: u64 = 0;
-                        h5try!(H5Tget_member_value(id, idx, &mut value as *mut _ as *mut _));
-                        let name = H5Tget_member_name(id, idx);
-                        members.push(EnumMember { name: string_from_cstr(name), value });
-                        libc::free(name as *mut _);
-                    }
-                    let base_dt = Self::from_id(H5Tget_super(id))?;
+                    let members = self.enum_members()?;
+                    let base_dt = self.base_type()?;
                     let (size, signed) = match base_dt.to_descriptor()? {
                         TD::Integer(size) => Ok((size, true)),
                         TD::Unsigned(size) => Ok((size, false)),
@@ -260,32 +901,28 @@ impl Datatype {
                     }
                 }
                 H5T_COMPOUND => {
-                    let mut fields: Vec<CompoundField> = Vec::new();
-                    for idx in 0..h5try!(H5Tget_nmembers(id)) as _ {
-                        let name = H5Tget_member_name(id, idx);
-                        let offset = h5try!(H5Tget_member_offset(id, idx));
-                        let ty = Self::from_id(h5try!(H5Tget_member_type(id, idx)))?;
-                        fields.push(CompoundField {
-                            name: string_from_cstr(name),
-                            ty: ty.to_descriptor()?,
-                            offset: offset as _,
-                            index: idx as _,
-                        });
-                        libc::free(name as *mut _);
-                    }
+                    let mut fields = self
+                        .compound_members()?
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, member)| {
+                            let ty = member.datatype.to_descriptor()?;
+                            Ok(CompoundField {
+                                name: member.name,
+                                ty,
+                                offset: member.offset,
+                                index,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    // descriptors list fields in memory order, member indices are kept
+                    fields.sort_by_key(|f| f.offset);
                     Ok(TD::Compound(CompoundType { fields, size }))
                 }
-                H5T_ARRAY => {
-                    let base_dt = Self::from_id(H5Tget_super(id))?;
-                    let ndims = h5try!(H5Tget_array_ndims(id));
-                    if ndims == 1 {
-                        let mut len: hsize_t = 0;
-                        h5try!(H5Tget_array_dims2(id, &mut len as *mut _));
-                        Ok(TD::FixedArray(Box::new(base_dt.to_descriptor()?), len as _))
-                    } else {
-                        Err("Multi-dimensional array datatypes are not supported".into())
-                    }
-                }
+                H5T_ARRAY => match self.array_dims()?.as_slice() {
+                    &[len] => Ok(TD::FixedArray(Box::new(self.base_type()?.to_descriptor()?), len)),
+                    _ => Err("Multi-dimensional array datatypes are not supported".into()),
+                },
                 H5T_STRING => {
                     let is_variable = h5try!(H5Tis_variable_str(id)) == 1;
                     let encoding = h5lock!(H5Tget_cset(id));
@@ -301,11 +938,40 @@ impl Datatype {
                     let base_dt = Self::from_id(H5Tget_super(id))?;
                     Ok(TD::VarLenArray(Box::new(base_dt.to_descriptor()?)))
                 }
+                H5T_BITFIELD => {
+                    let size =
+                        IntSize::from_int(size).ok_or("Invalid size of bitfield datatype")?;
+                    let precision = h5try!(H5Tget_precision(id)) as usize;
+                    let offset = h5try!(H5Tget_offset(id)) as usize;
+                    Ok(TD::Bitfield(BitfieldType { size, precision, offset }))
+                }
+                H5T_REFERENCE => {
+                    if h5try!(H5Tequal(id, *H5T_STD_REF_OBJ)) > 0 {
+                        Ok(TD::Reference(ReferenceType::Object))
+                    } else if h5try!(H5Tequal(id, *H5T_STD_REF_DSETREG)) > 0 {
+                        Ok(TD::Reference(ReferenceType::DatasetRegion))
+                    } else {
+                        Err("Unsupported reference datatype".into())
+                    }
+                }
+                H5T_OPAQUE => {
+                    let tag = H5Tget_tag(id);
+                    ensure!(!tag.is_null(), "Invalid tag of opaque datatype");
+                    let opaque = OpaqueType { size, tag: string_from_cstr(tag) };
+                    libc::free(tag as *mut _);
+                    Ok(TD::Opaque(opaque))
+                }
                 _ => Err("Unsupported datatype class".into()),
             }
         })
     }
 
+    /// Creates a datatype from its textual description in HDF5 DDL format, as returned by
+    /// `to_text()` or printed by `h5dump`; see `TypeDescriptor::from_ddl_str()`.
+    pub fn from_text(text: &str) -> Result<Self> {
+        Self::from_descriptor(&TypeDescriptor::from_ddl_str(text)?)
+    }
+
     pub fn from_type<T: H5Type>() -> Result<Self> {
         Self::from_descriptor(&<T as H5Type>::type_descriptor())
     }
@@ -327,6 +993,13 @@ impl Datatype {
             Ok(string_id)
         }
 
+        unsafe fn int128_type(int_id: hid_t) -> Result<hid_t> {
+            // there are no predefined 128-bit integer types, so widen a 64-bit one
+            h5try!(H5Tset_size(int_id, 16));
+            h5try!(H5Tset_precision(int_id, 128));
+            Ok(int_id)
+        }
+
         let datatype_id: Result<_> = h5lock!({
             match *desc {
                 TD::Integer(size) => Ok(match size {
@@ -334,16 +1007,18 @@ impl Datatype {
                     IntSize::U2 => be_le!(H5T_STD_I16BE, H5T_STD_I16LE),
                     IntSize::U4 => be_le!(H5T_STD_I32BE, H5T_STD_I32LE),
                     IntSize::U8 => be_le!(H5T_STD_I64BE, H5T_STD_I64LE),
+                    IntSize::U16 => int128_type(be_le!(H5T_STD_I64BE, H5T_STD_I64LE))?,
                 }),
                 TD::Unsigned(size) => Ok(match size {
                     IntSize::U1 => be_le!(H5T_STD_U8BE, H5T_STD_U8LE),
                     IntSize::U2 => be_le!(H5T_STD_U16BE, H5T_STD_U16LE),
                     IntSize::U4 => be_le!(H5T_STD_U32BE, H5T_STD_U32LE),
                     IntSize::U8 => be_le!(H5T_STD_U64BE, H5T_STD_U64LE),
+                    IntSize::U16 => int128_type(be_le!(H5T_STD_U64BE, H5T_STD_U64LE))?,
                 }),
                 TD::Float(size) => Ok(match size {
                     FloatSize::U4 => be_le!(H5T_IEEE_F32BE, H5T_IEEE_F32LE),
-                    FloatSize::U8 => be_le!(H5T_IEEE_I16BE, H5T_IEEE_F64LE),
+                    FloatSize::U8 => be_le!(H5T_IEEE_F64BE, H5T_IEEE_F64LE),
                 }),
                 TD::Boolean => {
                     let bool_id = h5try!(H5Tenum_create(*H5T_NATIVE_INT8));
@@ -373,11 +1048,14 @@ impl Datatype {
                     Ok(enum_id)
                 }
                 TD::Compound(ref compound_type) => {
-                    let compound_id = h5try!(H5Tcreate(H5T_class_t::H5T_COMPOUND, 1));
-                    for field in &compound_type.fields {
+                    let size = compound_type.size.max(1);
+                    let compound_id = h5try!(H5Tcreate(H5T_class_t::H5T_COMPOUND, size));
+                    // insert members in declaration order, so that member indices match
+                    let mut fields: Vec<_> = compound_type.fields.iter().collect();
+                    fields.sort_by_key(|f| f.index);
+                    for field in fields {
                         let name = to_cstring(field.name.as_ref())?;
                         let field_dt = Self::from_descriptor(&field.ty)?;
-                        h5try!(H5Tset_size(compound_id, field.offset + field.ty.size()));
                         h5try!(H5Tinsert(compound_id, name.as_ptr(), field.offset, field_dt.id()));
                     }
                     h5try!(H5Tset_size(compound_id, compound_type.size));
@@ -396,9 +1074,49 @@ impl Datatype {
                 }
                 TD::VarLenAscii => string_type(None, H5T_cset_t::H5T_CSET_ASCII),
                 TD::VarLenUnicode => string_type(None, H5T_cset_t::H5T_CSET_UTF8),
+                TD::Bitfield(ref bitfield) => {
+                    let bitfield_id = match bitfield.size {
+                        IntSize::U1 => be_le!(H5T_STD_B8BE, H5T_STD_B8LE),
+                        IntSize::U2 => be_le!(H5T_STD_B16BE, H5T_STD_B16LE),
+                        IntSize::U4 => be_le!(H5T_STD_B32BE, H5T_STD_B32LE),
+                        IntSize::U8 => be_le!(H5T_STD_B64BE, H5T_STD_B64LE),
+                        IntSize::U16 => int128_type(be_le!(H5T_STD_B64BE, H5T_STD_B64LE))?,
+                    };
+                    h5try!(H5Tset_precision(bitfield_id, bitfield.precision));
+                    h5try!(H5Tset_offset(bitfield_id, bitfield.offset));
+                    Ok(bitfield_id)
+                }
+                TD::Reference(ReferenceType::Object) => Ok(h5try!(H5Tcopy(*H5T_STD_REF_OBJ))),
+                TD::Reference(ReferenceType::DatasetRegion) => {
+                    Ok(h5try!(H5Tcopy(*H5T_STD_REF_DSETREG)))
+                }
+                TD::Opaque(ref opaque) => {
+                    let tag = to_cstring(opaque.tag.as_ref())?;
+                    let opaque_id = h5try!(H5Tcreate(H5T_class_t::H5T_OPAQUE, opaque.size));
+                    h5try!(H5Tset_tag(opaque_id, tag.as_ptr()));
+                    Ok(opaque_id)
+                }
             }
         });
 
         Self::from_id(datatype_id?)
     }
 }
+
+#[cfg(feature = "hl")]
+impl Datatype {
+    /// Returns the textual description of the datatype in HDF5 DDL format, e.g.
+    /// `H5T_IEEE_F64LE` or `H5T_COMPOUND { H5T_STD_I32LE "a" : 0; ... }`.
+    ///
+    /// The text can be turned back into a datatype via `from_text()`; this requires the
+    /// HDF5 high-level library (`hl` feature).
+    pub fn to_text(&self) -> Result<String> {
+        h5lock!({
+            let mut len: size_t = 0;
+            h5try!(H5LTdtype_to_text(self.id(), std::ptr::null_mut(), H5LT_DDL, &mut len));
+            let mut buf: Vec<c_char> = vec![0; len.max(1)];
+            h5try!(H5LTdtype_to_text(self.id(), buf.as_mut_ptr(), H5LT_DDL, &mut len));
+            Ok(string_from_cstr(buf.as_ptr()))
+        })
+    }
+}
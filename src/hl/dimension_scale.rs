@@ -0,0 +1,186 @@
+//! Dimension scales and dimension labels (requires the `hl` feature).
+//!
+//! A dimension scale is a dataset holding coordinate values along an axis of other
+//! datasets, e.g. the latitudes of a gridded field; attaching scales and labelling axes
+//! is how netCDF-4 files describe coordinate variables. A scale may be attached to any
+//! number of datasets and a dataset axis may have any number of scales attached.
+
+use std::ptr;
+
+use hdf5_sys::h5ds::{
+    H5DS_iterate_t, H5DSattach_scale, H5DSdetach_scale, H5DSget_label, H5DSget_num_scales,
+    H5DSget_scale_name, H5DSis_attached, H5DSis_scale, H5DSiterate_scales, H5DSset_label,
+    H5DSset_scale,
+};
+use hdf5_sys::h5i::H5Iinc_ref;
+
+use crate::internal_prelude::*;
+
+impl Dataset {
+    fn check_dim(&self, dim: usize) -> Result<c_uint> {
+        let ndim = self.ndim();
+        ensure!(dim < ndim, "dimension {} is out of bounds for dataset of rank {}", dim, ndim);
+        Ok(dim as _)
+    }
+
+    /// Converts this dataset into a dimension scale with the given name; the name may be
+    /// empty.
+    pub fn make_scale(&self, name: &str) -> Result<()> {
+        let name = if name.is_empty() { None } else { Some(to_cstring(name)?) };
+        let name_ptr = name.as_ref().map_or(ptr::null(), |name| name.as_ptr());
+        h5call!(H5DSset_scale(self.id(), name_ptr)).and(Ok(()))
+    }
+
+    /// Returns `true` if this dataset is a dimension scale.
+    pub fn is_scale(&self) -> bool {
+        let _e = silence_errors();
+        h5lock!(H5DSis_scale(self.id())) > 0
+    }
+
+    /// Returns the name of this dimension scale, or an empty string if it has no name.
+    pub fn scale_name(&self) -> Result<String> {
+        ensure!(self.is_scale(), "dataset is not a dimension scale");
+        h5lock!(get_h5_str(|m, s| H5DSget_scale_name(self.id(), m, s)))
+    }
+
+    /// Attaches a dimension scale to the given dimension of this dataset.
+    pub fn attach_scale(&self, scale: &Self, dim: usize) -> Result<()> {
+        let dim = self.check_dim(dim)?;
+        h5call!(H5DSattach_scale(self.id(), scale.id(), dim)).and(Ok(()))
+    }
+
+    /// Detaches a dimension scale from the given dimension of this dataset.
+    pub fn detach_scale(&self, scale: &Self, dim: usize) -> Result<()> {
+        let dim = self.check_dim(dim)?;
+        h5call!(H5DSdetach_scale(self.id(), scale.id(), dim)).and(Ok(()))
+    }
+
+    /// Returns `true` if the dimension scale is attached to the given dimension.
+    pub fn is_scale_attached(&self, scale: &Self, dim: usize) -> Result<bool> {
+        let dim = self.check_dim(dim)?;
+        h5call!(H5DSis_attached(self.id(), scale.id(), dim)).map(|attached| attached > 0)
+    }
+
+    /// Returns the number of dimension scales attached to the given dimension.
+    pub fn num_scales(&self, dim: usize) -> Result<usize> {
+        let dim = self.check_dim(dim)?;
+        h5call!(H5DSget_num_scales(self.id(), dim)).map(|n| n as _)
+    }
+
+    /// Returns the dimension scales attached to the given dimension, in the order they
+    /// were attached.
+    pub fn scales(&self, dim: usize) -> Result<Vec<Self>> {
+        extern "C" fn scales_callback(
+            _dset: hid_t, _dim: c_uint, scale: hid_t, visitor_data: *mut c_void,
+        ) -> herr_t {
+            catch_callback_panic(-1, || {
+                let scales = unsafe { &mut *(visitor_data as *mut Vec<hid_t>) };
+                // the scale is closed once the callback returns
+                if unsafe { H5Iinc_ref(scale) } < 0 {
+                    return -1;
+                }
+                scales.push(scale);
+                0 // Continue iteration
+            })
+        }
+
+        let dim = self.check_dim(dim)?;
+        let callback_fn: H5DS_iterate_t = Some(scales_callback);
+        h5lock!({
+            if H5DSget_num_scales(self.id(), dim) <= 0 {
+                // iterating over a dimension without scales is reported as an error
+                return Ok(vec![]);
+            }
+            let mut ids: Vec<hid_t> = Vec::new();
+            let ids_ptr = &mut ids as *mut Vec<hid_t> as *mut c_void;
+            let ret = H5DSiterate_scales(self.id(), dim, ptr::null_mut(), callback_fn, ids_ptr);
+            let scales = ids.into_iter().map(Self::from_id).collect::<Result<Vec<_>>>()?;
+            h5check(ret)?;
+            Ok(scales)
+        })
+    }
+
+    /// Sets the label of the given dimension.
+    pub fn set_dim_label(&self, dim: usize, label: &str) -> Result<()> {
+        let dim = self.check_dim(dim)?;
+        let label = to_cstring(label)?;
+        h5call!(H5DSset_label(self.id(), dim, label.as_ptr())).and(Ok(()))
+    }
+
+    /// Returns the label of the given dimension, or an empty string if it has no label.
+    pub fn dim_label(&self, dim: usize) -> Result<String> {
+        let dim = self.check_dim(dim)?;
+        h5lock!(get_h5_str(|m, s| H5DSget_label(self.id(), dim, m, s)))
+    }
+
+    /// Returns the labels of all dimensions, see `dim_label()`.
+    pub fn dim_labels(&self) -> Result<Vec<String>> {
+        (0..self.ndim()).map(|dim| self.dim_label(dim)).collect()
+    }
+}
+
+impl Group {
+    /// Creates a one-dimensional dataset holding the given coordinate values and converts
+    /// it into a dimension scale with the same name.
+    pub fn create_scale<T: H5Type>(&self, name: &str, values: &[T]) -> Result<Dataset> {
+        let dataset = self.new_dataset::<T>().create(name, values.len())?;
+        dataset.write_raw(values)?;
+        dataset.make_scale(name)?;
+        Ok(dataset)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_dimension_scales() {
+        with_tmp_file(|file| {
+            let data = file.new_dataset::<f32>().create("data", (2, 3)).unwrap();
+            let lat = file.create_scale("lat", &[10., 20.]).unwrap();
+            let lon = file.create_scale("lon", &[1., 2., 3.]).unwrap();
+            let alt = file.new_dataset::<f64>().create("alt", 3).unwrap();
+            assert!(lat.is_scale());
+            assert!(!data.is_scale() && !alt.is_scale());
+            assert_eq!(lat.scale_name().unwrap(), "lat");
+            alt.make_scale("").unwrap();
+            assert!(alt.is_scale());
+            assert_eq!(alt.scale_name().unwrap(), "");
+            assert_err!(data.scale_name(), "not a dimension scale");
+
+            assert!(data.scales(0).unwrap().is_empty());
+            data.attach_scale(&lat, 0).unwrap();
+            data.attach_scale(&lon, 1).unwrap();
+            data.attach_scale(&alt, 1).unwrap();
+            assert!(data.is_scale_attached(&lon, 1).unwrap());
+            assert!(!data.is_scale_attached(&lon, 0).unwrap());
+            assert_eq!(data.num_scales(0).unwrap(), 1);
+            assert_eq!(data.num_scales(1).unwrap(), 2);
+            let names = |dim| -> Vec<String> {
+                data.scales(dim).unwrap().iter().map(|s| s.scale_name().unwrap()).collect()
+            };
+            assert_eq!(names(0), vec!["lat"]);
+            assert_eq!(names(1), vec!["lon", ""]);
+            assert_eq!(data.scales(0).unwrap()[0].read_raw::<f64>().unwrap(), vec![10., 20.]);
+
+            data.detach_scale(&alt, 1).unwrap();
+            assert_eq!(names(1), vec!["lon"]);
+            assert!(!data.is_scale_attached(&alt, 1).unwrap());
+            assert_err!(data.attach_scale(&lat, 2), "dimension 2 is out of bounds");
+        })
+    }
+
+    #[test]
+    pub fn test_dimension_labels() {
+        with_tmp_file(|file| {
+            let data = file.new_dataset::<u8>().create("data", (2, 3, 4)).unwrap();
+            assert_eq!(data.dim_labels().unwrap(), vec!["", "", ""]);
+            data.set_dim_label(0, "time").unwrap();
+            data.set_dim_label(2, "x").unwrap();
+            assert_eq!(data.dim_label(0).unwrap(), "time");
+            assert_eq!(data.dim_labels().unwrap(), vec!["time", "", "x"]);
+            assert_err!(data.set_dim_label(3, "y"), "out of bounds");
+        })
+    }
+}
@@ -1,14 +1,22 @@
 use std::fmt::{self, Debug};
 use std::ops::Deref;
 use std::path::Path;
+#[cfg(feature = "log")]
+use std::time::Instant;
 
+#[cfg(hdf5_1_10_0)]
+use hdf5_sys::h5f::H5F_ACC_SWMR_READ;
 use hdf5_sys::h5f::{
     H5Fclose, H5Fcreate, H5Fflush, H5Fget_access_plist, H5Fget_create_plist, H5Fget_filesize,
     H5Fget_freespace, H5Fget_intent, H5Fget_obj_count, H5Fget_obj_ids, H5Fopen, H5F_ACC_DEFAULT,
     H5F_ACC_EXCL, H5F_ACC_RDONLY, H5F_ACC_RDWR, H5F_ACC_TRUNC, H5F_OBJ_ALL, H5F_OBJ_FILE,
     H5F_SCOPE_LOCAL,
 };
+#[cfg(hdf5_1_10_5)]
+use hdf5_sys::h5f::{H5Fget_dset_no_attrs_hint, H5Fset_dset_no_attrs_hint};
 
+#[cfg(feature = "mpio")]
+use crate::hl::plist::file_access::AsMpiComm;
 use crate::hl::plist::{
     file_access::{FileAccess, FileAccessBuilder},
     file_create::{FileCreate, FileCreateBuilder},
@@ -102,6 +110,16 @@ impl File {
         FileBuilder::new().open_as(filename, mode)
     }
 
+    /// Opens a file as read-only for SWMR (single-writer/multiple-reader) access, file
+    /// must exist.
+    ///
+    /// Data flushed by a process writing the file in SWMR mode can then be read while the
+    /// file is being written; see `Dataset::watch()`.
+    #[cfg(hdf5_1_10_0)]
+    pub fn open_swmr<P: AsRef<Path>>(filename: P) -> Result<Self> {
+        FileBuilder::new().open_swmr(filename)
+    }
+
     /// Opens a file with custom file-access and file-creation options.
     pub fn with_options() -> FileBuilder {
         FileBuilder::new()
@@ -127,6 +145,22 @@ impl File {
         h5lock!(self.fcpl().map(|p| p.userblock()).unwrap_or(0))
     }
 
+    /// Returns `true` if datasets created in the file get minimized object headers by
+    /// default (or `false` if the file handle is invalid).
+    #[cfg(hdf5_1_10_5)]
+    pub fn minimizes_dataset_headers(&self) -> bool {
+        h5get!(H5Fget_dset_no_attrs_hint(self.id()): hbool_t).map_or(false, |x| x > 0)
+    }
+
+    /// Sets whether datasets created in the file get minimized object headers by
+    /// default; this setting is not stored in the file and only applies to this handle.
+    ///
+    /// It can be overridden for individual datasets, see `DatasetBuilder::minimize_header()`.
+    #[cfg(hdf5_1_10_5)]
+    pub fn set_minimize_dataset_headers(&self, minimize: bool) -> Result<()> {
+        h5call!(H5Fset_dset_no_attrs_hint(self.id(), minimize as _)).and(Ok(()))
+    }
+
     /// Flushes the file to the storage medium.
     pub fn flush(&self) -> Result<()> {
         h5call!(H5Fflush(self.id(), H5F_SCOPE_LOCAL)).and(Ok(()))
@@ -252,7 +286,9 @@ impl FileBuilder {
             OpenMode::CreateExcl | OpenMode::Append => H5F_ACC_EXCL,
         };
         let fname_ptr = filename.as_ptr();
-        h5lock!({
+        #[cfg(feature = "log")]
+        let started = Instant::now();
+        let file = h5lock!({
             let fapl = self.fapl.finish()?;
             match mode {
                 OpenMode::Read | OpenMode::ReadWrite => {
@@ -263,7 +299,40 @@ impl FileBuilder {
                     File::from_id(h5try!(H5Fcreate(fname_ptr, flags, fcpl.id(), fapl.id())))
                 }
             }
-        })
+        })?;
+        h5log!(
+            debug,
+            "opened file {} in {:?} mode in {:?}",
+            filename.to_string_lossy(),
+            mode,
+            started.elapsed()
+        );
+        Ok(file)
+    }
+
+    /// Opens a file as read-only for SWMR access, see `File::open_swmr()`.
+    #[cfg(hdf5_1_10_0)]
+    pub fn open_swmr<P: AsRef<Path>>(&self, filename: P) -> Result<File> {
+        let filename = filename.as_ref();
+        let filename = to_cstring(
+            filename
+                .to_str()
+                .ok_or_else(|| format!("Invalid UTF-8 in file name: {:?}", filename))?,
+        )?;
+        #[cfg(feature = "log")]
+        let started = Instant::now();
+        let file = h5lock!({
+            let fapl = self.fapl.finish()?;
+            let flags = H5F_ACC_RDONLY | H5F_ACC_SWMR_READ;
+            File::from_id(h5try!(H5Fopen(filename.as_ptr(), flags, fapl.id())))
+        })?;
+        h5log!(
+            debug,
+            "opened file {} in SWMR read mode in {:?}",
+            filename.to_string_lossy(),
+            started.elapsed()
+        );
+        Ok(file)
     }
 
     // File Access Property List
@@ -308,6 +377,29 @@ impl FileBuilder {
         self.with_access_plist(func)
     }
 
+    /// Uses the MPI-IO file driver with the given communicator and info object.
+    ///
+    /// This is a shortcut for `fapl().mpio(comm, info)`. The file has to be opened or
+    /// created collectively by all processes in the communicator, and so do all calls
+    /// modifying its structure (e.g. creating groups, datasets and attributes); raw
+    /// data transfers are independent unless requested otherwise via `collective()`
+    /// on the dataset reader/writer. The communicator can be a raw `MPI_Comm` or any
+    /// type implementing `AsMpiComm`.
+    #[cfg(feature = "mpio")]
+    pub fn mpio<C: AsMpiComm>(&mut self, comm: C, info: Option<mpi_sys::MPI_Info>) -> &mut Self {
+        self.fapl.mpio(comm, info);
+        self
+    }
+
+    /// Sets the default raw data chunk cache parameters for all datasets in the file.
+    ///
+    /// This is a shortcut for `fapl().chunk_cache(nslots, nbytes, w0)`; datasets can
+    /// still override it via the chunk cache setting of their access property list.
+    pub fn chunk_cache(&mut self, nslots: usize, nbytes: usize, w0: f64) -> &mut Self {
+        self.fapl.chunk_cache(nslots, nbytes, w0);
+        self
+    }
+
     // File Creation Property List
 
     /// Sets current file creation property list to a given one.
@@ -356,6 +448,7 @@ pub mod tests {
     use crate::internal_prelude::*;
     use std::fs;
     use std::io::{Read, Write};
+    use std::path::Path;
 
     #[test]
     pub fn test_is_read_only() {
@@ -499,6 +592,75 @@ pub mod tests {
         })
     }
 
+    #[test]
+    pub fn test_create_plist_tuning() {
+        use crate::hl::plist::file_create::{SharedMessageIndex, SharedMessageType};
+
+        let write = |path: &Path, shared: bool| {
+            let mut builder = FileBuilder::new();
+            builder.with_fcpl(|p| p.sizes(4, 4).sym_k(8, 4).istore_k(64));
+            if shared {
+                let types = SharedMessageType::ATTRIBUTE | SharedMessageType::DATATYPE;
+                let index = SharedMessageIndex { message_types: types, min_message_size: 0 };
+                builder.fcpl().shared_mesg_indexes(&[index]);
+            }
+            let file = builder.create(path).unwrap();
+            for i in 0..100 {
+                let ds = file.new_dataset::<u8>().create(&format!("{}", i), 1).unwrap();
+                let attr = ds.new_attr::<[u64; 64]>().create("x", ()).unwrap();
+                attr.write_scalar(&[7_u64; 64]).unwrap();
+            }
+            file.size()
+        };
+        with_tmp_dir(|dir| {
+            let (shared, unshared) = (dir.join("shared.h5"), dir.join("unshared.h5"));
+            assert!(write(&shared, true) < write(&unshared, false));
+
+            let fcpl = File::open(&shared).unwrap().fcpl().unwrap();
+            assert_eq!((fcpl.sizes().sizeof_addr, fcpl.sizes().sizeof_size), (4, 4));
+            assert_eq!((fcpl.sym_k().tree_rank, fcpl.sym_k().node_size), (8, 4));
+            assert_eq!(fcpl.istore_k(), 64);
+            assert_eq!(fcpl.shared_mesg_indexes().len(), 1);
+            assert!(File::open(&unshared)
+                .unwrap()
+                .fcpl()
+                .unwrap()
+                .shared_mesg_indexes()
+                .is_empty());
+        })
+    }
+
+    #[test]
+    #[cfg(hdf5_1_10_5)]
+    pub fn test_minimize_dataset_headers() {
+        let write = |path: &Path, minimize: bool| {
+            let file = File::create(path).unwrap();
+            assert!(!file.minimizes_dataset_headers());
+            file.set_minimize_dataset_headers(minimize).unwrap();
+            assert_eq!(file.minimizes_dataset_headers(), minimize);
+            for i in 0..100 {
+                file.new_dataset::<u8>().create(&format!("{}", i), 1).unwrap();
+            }
+            file.size()
+        };
+        with_tmp_dir(|dir| {
+            let (minimized, default) = (dir.join("minimized.h5"), dir.join("default.h5"));
+            assert!(write(&minimized, true) < write(&default, false));
+            assert!(!File::open(&minimized).unwrap().minimizes_dataset_headers());
+        })
+    }
+
+    #[test]
+    pub fn test_chunk_cache() {
+        with_tmp_path(|path| {
+            let file = FileBuilder::new().chunk_cache(1031, 4 << 20, 0.5).create(&path).unwrap();
+            let cache = file.fapl().unwrap().chunk_cache();
+            assert_eq!((cache.nslots, cache.nbytes, cache.w0), (1031, 4 << 20, 0.5));
+            let ds = file.new_dataset::<u8>().chunk(10).create("x", 100).unwrap();
+            assert_eq!(ds.dapl().unwrap().chunk_cache(), cache);
+        })
+    }
+
     #[test]
     pub fn test_close_automatic() {
         // File going out of scope should just close its own handle
@@ -4,15 +4,21 @@ use std::ops::Deref;
 use hdf5_sys::{
     h5::{hsize_t, H5_index_t, H5_iter_order_t},
     h5d::H5Dopen2,
-    h5g::{H5G_info_t, H5Gcreate2, H5Gget_info, H5Gopen2},
+    h5g::{H5G_info_t, H5Gcreate2, H5Gget_create_plist, H5Gget_info, H5Gopen2},
     h5l::{
         H5L_info_t, H5L_iterate_t, H5Lcreate_hard, H5Lcreate_soft, H5Ldelete, H5Lexists,
         H5Literate, H5Lmove, H5L_SAME_LOC,
     },
-    h5p::{H5Pcreate, H5Pset_create_intermediate_group},
+    h5o::H5Ocopy,
+    h5t::{H5Tcommit2, H5Topen2},
 };
 
-use crate::globals::H5P_LINK_CREATE;
+use crate::hl::plist::dataset_access::DatasetAccess;
+use crate::hl::plist::datatype_access::DatatypeAccess;
+use crate::hl::plist::datatype_create::DatatypeCreate;
+use crate::hl::plist::group_create::GroupCreate;
+use crate::hl::plist::link_create::LinkCreate;
+use crate::hl::plist::object_copy::ObjectCopy;
 use crate::internal_prelude::*;
 
 /// Represents the HDF5 group object.
@@ -61,11 +67,8 @@ fn group_info(id: hid_t) -> Result<H5G_info_t> {
     h5call!(H5Gget_info(id, info)).and(Ok(unsafe { *info }))
 }
 
-fn make_lcpl() -> Result<PropertyList> {
-    h5lock!({
-        let lcpl = PropertyList::from_id(h5try!(H5Pcreate(*H5P_LINK_CREATE)))?;
-        h5call!(H5Pset_create_intermediate_group(lcpl.id(), 1)).and(Ok(lcpl))
-    })
+fn make_lcpl() -> Result<LinkCreate> {
+    LinkCreate::build().create_intermediate_group(true).finish()
 }
 
 impl Group {
@@ -81,24 +84,61 @@ impl Group {
 
     /// Create a new group in a file or group.
     pub fn create_group(&self, name: &str) -> Result<Self> {
+        self.create_group_with_lcpl(name, &make_lcpl()?)
+    }
+
+    /// Create a new group in a file or group using the given link creation properties,
+    /// e.g. to store a non-ASCII group name as UTF-8.
+    ///
+    /// Unlike `create_group()`, missing intermediate groups are only created if this is
+    /// enabled in the property list.
+    pub fn create_group_with_lcpl(&self, name: &str, lcpl: &LinkCreate) -> Result<Self> {
+        self.create_group_with(name, lcpl, &GroupCreate::try_new()?)
+    }
+
+    /// Create a new group in a file or group using the given link and group creation
+    /// properties, e.g. those of an existing group; see `create_group_with_lcpl()`.
+    pub fn create_group_with(
+        &self, name: &str, lcpl: &LinkCreate, gcpl: &GroupCreate,
+    ) -> Result<Self> {
         // TODO: &mut self?
-        h5lock!({
-            let lcpl = make_lcpl()?;
+        let group = h5lock!({
             let name = to_cstring(name)?;
             Self::from_id(h5try!(H5Gcreate2(
                 self.id(),
                 name.as_ptr(),
                 lcpl.id(),
-                H5P_DEFAULT,
+                gcpl.id(),
                 H5P_DEFAULT
             )))
-        })
+        })?;
+        h5log!(debug, "created group {} in {}", group.name(), group.filename());
+        Ok(group)
+    }
+
+    /// Returns a copy of the group creation property list.
+    ///
+    /// Note that HDF5 only retrieves the link storage properties (such as link creation
+    /// order) of existing groups; the remaining properties hold their default values.
+    pub fn create_plist(&self) -> Result<GroupCreate> {
+        h5lock!(GroupCreate::from_id(h5try!(H5Gget_create_plist(self.id()))))
+    }
+
+    /// A short alias for `create_plist()`.
+    pub fn gcpl(&self) -> Result<GroupCreate> {
+        self.create_plist()
     }
 
     /// Opens an existing group in a file or group.
     pub fn group(&self, name: &str) -> Result<Self> {
-        let name = to_cstring(name)?;
-        Self::from_id(h5try!(H5Gopen2(self.id(), name.as_ptr(), H5P_DEFAULT)))
+        let c_name = to_cstring(name)?;
+        h5call!(H5Gopen2(self.id(), c_name.as_ptr(), H5P_DEFAULT))
+            .and_then(Self::from_id)
+            .with_context(|| self.member_context("opening group", name))
+            .map(|group| {
+                h5log!(debug, "opened group {} in {}", group.name(), group.filename());
+                group
+            })
     }
 
     /// Creates a soft link. Note: `src` and `dst` are relative to the current object.
@@ -145,6 +185,33 @@ impl Group {
         .and(Ok(()))
     }
 
+    /// Copies an object (recursively, for groups) into a file or group, which may be in
+    /// another file. Note: `src` is relative to the current object, `dst` to `dst_group`.
+    pub fn copy_object(&self, src: &str, dst_group: &Self, dst: &str) -> Result<()> {
+        self.copy_object_with(src, dst_group, dst, &ObjectCopy::try_new()?)
+    }
+
+    /// Copies an object into a file or group using the given object copy properties,
+    /// e.g. to copy a group without its members' attributes; see `copy_object()`.
+    pub fn copy_object_with(
+        &self, src: &str, dst_group: &Self, dst: &str, ocpypl: &ObjectCopy,
+    ) -> Result<()> {
+        h5lock!({
+            let lcpl = make_lcpl()?;
+            let src = to_cstring(src)?;
+            let dst = to_cstring(dst)?;
+            h5call!(H5Ocopy(
+                self.id(),
+                src.as_ptr(),
+                dst_group.id(),
+                dst.as_ptr(),
+                ocpypl.id(),
+                lcpl.id()
+            ))
+            .and(Ok(()))
+        })
+    }
+
     /// Removes a link to an object from this file or group.
     pub fn unlink(&self, name: &str) -> Result<()> {
         // TODO: &mut self?
@@ -166,10 +233,69 @@ impl Group {
         DatasetBuilder::<T>::new(self)
     }
 
+    fn open_dataset(&self, name: &str, dapl_id: hid_t) -> Result<Dataset> {
+        let c_name = to_cstring(name)?;
+        h5call!(H5Dopen2(self.id(), c_name.as_ptr(), dapl_id))
+            .and_then(Dataset::from_id)
+            .with_context(|| self.member_context("opening dataset", name))
+            .map(|dataset| {
+                h5log!(debug, "opened dataset {} in {}", dataset.name(), dataset.filename());
+                dataset
+            })
+    }
+
     /// Opens an existing dataset in the file or group.
     pub fn dataset(&self, name: &str) -> Result<Dataset> {
-        let name = to_cstring(name)?;
-        Dataset::from_id(h5try!(H5Dopen2(self.id(), name.as_ptr(), H5P_DEFAULT)))
+        self.open_dataset(name, H5P_DEFAULT)
+    }
+
+    /// Opens an existing dataset in the file or group with the given access properties,
+    /// e.g. to tune its chunk cache.
+    pub fn dataset_with_access(&self, name: &str, dapl: &DatasetAccess) -> Result<Dataset> {
+        self.open_dataset(name, dapl.id())
+    }
+
+    /// Commits a datatype to the file under a given name, turning it into a named
+    /// datatype that can have attributes attached to it.
+    pub fn commit_datatype(&self, name: &str, datatype: &Datatype) -> Result<()> {
+        self.commit_datatype_with(name, datatype, &make_lcpl()?, &DatatypeCreate::try_new()?)
+    }
+
+    /// Commits a datatype to the file under a given name using the given link and
+    /// datatype creation properties, e.g. to store a non-ASCII name as UTF-8.
+    pub fn commit_datatype_with(
+        &self, name: &str, datatype: &Datatype, lcpl: &LinkCreate, tcpl: &DatatypeCreate,
+    ) -> Result<()> {
+        h5lock!({
+            let name = to_cstring(name)?;
+            h5call!(H5Tcommit2(
+                self.id(),
+                name.as_ptr(),
+                datatype.id(),
+                lcpl.id(),
+                tcpl.id(),
+                H5P_DEFAULT
+            ))
+            .and(Ok(()))
+        })
+    }
+
+    fn open_datatype(&self, name: &str, tapl_id: hid_t) -> Result<Datatype> {
+        let c_name = to_cstring(name)?;
+        h5call!(H5Topen2(self.id(), c_name.as_ptr(), tapl_id))
+            .and_then(Datatype::from_id)
+            .with_context(|| self.member_context("opening datatype", name))
+    }
+
+    /// Opens an existing named datatype in the file or group.
+    pub fn datatype(&self, name: &str) -> Result<Datatype> {
+        self.open_datatype(name, H5P_DEFAULT)
+    }
+
+    /// Opens an existing named datatype in the file or group with the given access
+    /// properties.
+    pub fn datatype_with_access(&self, name: &str, tapl: &DatatypeAccess) -> Result<Datatype> {
+        self.open_datatype(name, tapl.id())
     }
 
     /// Returns names of all the members in the group, non-recursively.
@@ -177,11 +303,13 @@ impl Group {
         extern "C" fn members_callback(
             _id: hid_t, name: *const c_char, _info: *const H5L_info_t, op_data: *mut c_void,
         ) -> herr_t {
-            let other_data: &mut Vec<String> = unsafe { &mut *(op_data as *mut Vec<String>) };
+            catch_callback_panic(-1, || {
+                let other_data: &mut Vec<String> = unsafe { &mut *(op_data as *mut Vec<String>) };
 
-            other_data.push(string_from_cstr(name));
+                other_data.push(string_from_cstr(name));
 
-            0 // Continue iteration
+                0 // Continue iteration
+            })
         }
 
         let callback_fn: H5L_iterate_t = Some(members_callback);
@@ -204,8 +332,24 @@ impl Group {
 
 #[cfg(test)]
 pub mod tests {
+    use hdf5_sys::h5l::{H5L_info_t, H5Lget_info};
+    use hdf5_sys::h5t::H5T_cset_t;
+
+    use crate::hl::plist::dataset_access::DatasetAccess;
+    use crate::hl::plist::datatype_access::DatatypeAccess;
+    use crate::hl::plist::datatype_create::DatatypeCreate;
+    use crate::hl::plist::group_create::GroupCreate;
+    use crate::hl::plist::link_create::{CharEncoding, LinkCreate};
+    use crate::hl::plist::object_copy::ObjectCopy;
     use crate::internal_prelude::*;
 
+    fn link_cset(group: &Group, name: &str) -> H5T_cset_t {
+        let name = to_cstring(name).unwrap();
+        let mut info = H5L_info_t::default();
+        h5call!(H5Lget_info(group.id(), name.as_ptr(), &mut info, H5P_DEFAULT)).unwrap();
+        info.cset
+    }
+
     #[test]
     pub fn test_debug() {
         with_tmp_file(|file| {
@@ -385,6 +529,46 @@ pub mod tests {
         });
     }
 
+    #[test]
+    pub fn test_error_context() {
+        with_tmp_path(|path| {
+            let filename = path.to_str().unwrap().to_owned();
+            let file = File::create(&path).unwrap();
+            let group = file.create_group("a").unwrap();
+            group.new_dataset::<u32>().create("ds", 3).unwrap();
+            let err = group.group("b/c").err().unwrap();
+            assert!(err
+                .description()
+                .starts_with(&format!("opening group /a/b/c in {}: ", filename)));
+            assert_eq!(
+                err.stack().unwrap().context().unwrap(),
+                format!("opening group /a/b/c in {}", filename)
+            );
+            assert_err!(
+                file.dataset("/a/foo"),
+                &format!("opening dataset /a/foo in {}: H5Dopen2()", filename)
+            );
+            assert_err!(group.datatype("foo"), "opening datatype /a/foo in ");
+            let dapl = DatasetAccess::try_new().unwrap();
+            assert_err!(group.dataset_with_access("foo", &dapl), "opening dataset /a/foo in ");
+            let tapl = DatatypeAccess::try_new().unwrap();
+            assert_err!(group.datatype_with_access("foo", &tapl), "opening datatype /a/foo in ");
+            assert_err!(
+                group.attr("foo"),
+                &format!("opening attribute foo of /a in {}: ", filename)
+            );
+            drop(group);
+            drop(file);
+
+            let file = File::open(&path).unwrap();
+            let ds = file.dataset("a/ds").unwrap();
+            assert_err!(
+                ds.write(&[1_u32, 2, 3]),
+                &format!("writing /a/ds in {}: H5Dwrite()", filename)
+            );
+        })
+    }
+
     #[test]
     pub fn test_get_member_names() {
         with_tmp_file(|file| {
@@ -400,4 +584,107 @@ pub mod tests {
             assert_eq!(file.member_names().unwrap(), vec!["a", "b"]);
         })
     }
+
+    #[test]
+    pub fn test_link_create_plist() {
+        with_tmp_file(|file| {
+            let utf8 = LinkCreate::build().char_encoding(CharEncoding::Utf8).finish().unwrap();
+            let group = file.create_group_with_lcpl("gr\u{fc}\u{df}e", &utf8).unwrap();
+            assert_eq!(link_cset(&file, "gr\u{fc}\u{df}e"), H5T_cset_t::H5T_CSET_UTF8);
+            file.create_group("ascii").unwrap();
+            assert_eq!(link_cset(&file, "ascii"), H5T_cset_t::H5T_CSET_ASCII);
+            assert_err!(file.create_group_with_lcpl("a/b", &utf8), "unable to create group");
+            assert_eq!(file.member_names().unwrap(), vec!["ascii", "gr\u{fc}\u{df}e"]);
+
+            group
+                .new_dataset::<u8>()
+                .with_lcpl(|p| p.char_encoding(CharEncoding::Utf8))
+                .create("x/\u{3b4}", 1)
+                .unwrap();
+            assert_eq!(link_cset(&group, "x/\u{3b4}"), H5T_cset_t::H5T_CSET_UTF8);
+            assert_eq!(link_cset(&group, "x"), H5T_cset_t::H5T_CSET_ASCII);
+            let lcpl = LinkCreate::build().create_intermediate_group(false).finish().unwrap();
+            assert_err!(
+                group.new_dataset::<u8>().set_lcpl(&lcpl).unwrap().create("y/z", 1),
+                "unable to create dataset"
+            );
+        })
+    }
+
+    #[test]
+    pub fn test_copy_object() {
+        with_tmp_file(|file| {
+            let ds = file.new_dataset::<u32>().create("a/b/x", 3).unwrap();
+            ds.write(&[1, 2, 3]).unwrap();
+            ds.set_attr("units", &7_u8).unwrap();
+            file.link_soft("/a/b/x", "a/y").unwrap();
+
+            file.copy_object("a", &file, "deep/c").unwrap();
+            let ds = file.dataset("deep/c/b/x").unwrap();
+            assert_eq!(ds.read_raw::<u32>().unwrap(), vec![1, 2, 3]);
+            assert_eq!(ds.get_attr::<u8>("units").unwrap(), 7);
+
+            let ocpypl =
+                ObjectCopy::build().without_attrs(true).expand_soft_links(true).finish().unwrap();
+            file.copy_object_with("a", &file, "d", &ocpypl).unwrap();
+            assert_eq!(file.dataset("d/b/x").unwrap().num_attrs().unwrap(), 0);
+            assert_eq!(file.dataset("d/y").unwrap().read_raw::<u32>().unwrap(), vec![1, 2, 3]);
+
+            let ocpypl = ObjectCopy::build().shallow_hierarchy(true).finish().unwrap();
+            file.copy_object_with("a", &file, "e", &ocpypl).unwrap();
+            assert!(file.group("e/b").unwrap().is_empty());
+
+            // only the unexpanded soft link still points to the original dataset
+            file.dataset("a/b/x").unwrap().write(&[4, 5, 6]).unwrap();
+            assert_eq!(file.dataset("deep/c/y").unwrap().read_raw::<u32>().unwrap(), vec![4, 5, 6]);
+            assert_eq!(file.dataset("d/y").unwrap().read_raw::<u32>().unwrap(), vec![1, 2, 3]);
+
+            assert_err!(file.copy_object("z", &file, "f"), "unable to copy object");
+        })
+    }
+
+    #[test]
+    pub fn test_commit_datatype_with() {
+        with_tmp_file(|file| {
+            let dtype = Datatype::from_type::<u32>().unwrap();
+            let lcpl = LinkCreate::build().char_encoding(CharEncoding::Utf8).finish().unwrap();
+            let tcpl = DatatypeCreate::build().attr_phase_change(0, 0).finish().unwrap();
+            file.commit_datatype_with("gr\u{f6}\u{df}e", &dtype, &lcpl, &tcpl).unwrap();
+            assert_eq!(link_cset(&file, "gr\u{f6}\u{df}e"), H5T_cset_t::H5T_CSET_UTF8);
+
+            let tapl = DatatypeAccess::try_new().unwrap();
+            let dtype = file.datatype_with_access("gr\u{f6}\u{df}e", &tapl).unwrap();
+            assert!(dtype.is_committed());
+            assert_eq!(dtype.to_descriptor().unwrap(), u32::type_descriptor());
+            assert_eq!(dtype.tcpl().unwrap().class().unwrap(), PropertyListClass::DatatypeCreate);
+            assert_err!(
+                file.commit_datatype_with("x/y", &dtype, &lcpl, &tcpl),
+                "unable to commit datatype"
+            );
+        })
+    }
+
+    #[test]
+    pub fn test_group_create_plist() {
+        with_tmp_file(|file| {
+            let lcpl = LinkCreate::try_new().unwrap();
+            let gcpl = GroupCreate::build()
+                .link_creation_order(true, true)
+                .link_phase_change(16, 10)
+                .finish()
+                .unwrap();
+            let group = file.create_group_with("a", &lcpl, &gcpl).unwrap();
+            let order = group.gcpl().unwrap().link_creation_order();
+            assert!(order.tracked && order.indexed);
+            assert_eq!(group.gcpl().unwrap().link_phase_change().max_compact, 16);
+            let order =
+                file.create_group("b").unwrap().create_plist().unwrap().link_creation_order();
+            assert!(!order.tracked && !order.indexed);
+
+            let gcpl = group.create_plist().unwrap();
+            let other = file.create_group_with("c", &lcpl, &gcpl).unwrap();
+            assert!(other.gcpl().unwrap().link_creation_order().tracked);
+            assert_eq!(other.gcpl().unwrap().link_phase_change().min_dense, 10);
+        })
+    }
 }
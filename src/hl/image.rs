@@ -0,0 +1,475 @@
+//! Images and palettes following the HDF5 image specification (requires the `hl` feature).
+//!
+//! Images are stored as datasets of unsigned bytes with attributes describing their
+//! layout, so that image viewers such as HDFView display them as pictures. Indexed
+//! (8-bit) images have shape `(height, width)` and use palettes to map indices (pixel
+//! values) to colors; true-color (24-bit) images have shape `(height, width, 3)` if
+//! pixel-interlaced or `(3, height, width)` if plane-interlaced.
+//!
+//! `RawImage` holds grayscale, RGB or RGBA pixels row by row with the channels of each
+//! pixel together, the layout used by `image::ImageBuffer`, and can be stored with either
+//! interlace mode via `Group::create_image()`.
+
+use std::borrow::Cow;
+use std::ffi::CString;
+
+use ndarray::{ArrayD, ArrayView2, ArrayView3, Ix3, IxDyn};
+
+use hdf5_sys::h5::hssize_t;
+use hdf5_sys::h5im::{
+    H5IMget_image_info, H5IMget_npalettes, H5IMget_palette, H5IMget_palette_info, H5IMis_image,
+    H5IMis_palette, H5IMlink_palette, H5IMmake_image_24bit, H5IMmake_image_8bit, H5IMmake_palette,
+    H5IMread_image, H5IMunlink_palette,
+};
+use hdf5_sys::h5lt::H5LTset_attribute_string;
+
+use crate::internal_prelude::*;
+
+/// Layout of the color components of true-color images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Interlace {
+    /// Components of each pixel are stored together, shape `(height, width, 3)`.
+    Pixel,
+    /// Each component is stored as a separate plane, shape `(3, height, width)`.
+    Plane,
+}
+
+impl Interlace {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Pixel => "INTERLACE_PIXEL",
+            Self::Plane => "INTERLACE_PLANE",
+        }
+    }
+
+    fn to_cstring(self) -> CString {
+        CString::new(self.name()).unwrap()
+    }
+
+    fn from_str(name: &str) -> Option<Self> {
+        match name {
+            "INTERLACE_PIXEL" => Some(Self::Pixel),
+            "INTERLACE_PLANE" => Some(Self::Plane),
+            _ => None,
+        }
+    }
+}
+
+/// Information about a stored image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub width: usize,
+    pub height: usize,
+    /// Number of color planes: 1 for indexed images and 3 for true-color ones.
+    pub planes: usize,
+    /// Layout of the color components; `None` for indexed images.
+    pub interlace: Option<Interlace>,
+    /// Number of palettes attached to the image.
+    pub palettes: usize,
+}
+
+impl ImageInfo {
+    /// Returns the shape of the image dataset.
+    pub fn shape(&self) -> Vec<usize> {
+        match self.interlace {
+            _ if self.planes == 1 => vec![self.height, self.width],
+            Some(Interlace::Plane) => vec![self.planes, self.height, self.width],
+            _ => vec![self.height, self.width, self.planes],
+        }
+    }
+}
+
+/// Color channels of the pixels of a `RawImage`, named after `image::ColorType`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorType {
+    /// 8-bit grayscale.
+    L8,
+    /// 8-bit RGB.
+    Rgb8,
+    /// 8-bit RGB with an alpha channel.
+    Rgba8,
+}
+
+impl ColorType {
+    /// Returns the number of channels of each pixel.
+    pub fn channels(self) -> usize {
+        match self {
+            Self::L8 => 1,
+            Self::Rgb8 => 3,
+            Self::Rgba8 => 4,
+        }
+    }
+
+    fn from_channels(channels: usize) -> Option<Self> {
+        match channels {
+            1 => Some(Self::L8),
+            3 => Some(Self::Rgb8),
+            4 => Some(Self::Rgba8),
+            _ => None,
+        }
+    }
+}
+
+/// An 8-bit image with its pixels stored row by row and the channels of each pixel together.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RawImage {
+    pub width: usize,
+    pub height: usize,
+    pub color: ColorType,
+    pub data: Vec<u8>,
+}
+
+impl RawImage {
+    /// Creates an image from its pixels, failing if their number doesn't match the size.
+    pub fn new(width: usize, height: usize, color: ColorType, data: Vec<u8>) -> Result<Self> {
+        let len = width * height * color.channels();
+        ensure!(data.len() == len, "expected {} bytes of pixel data, got {}", len, data.len());
+        Ok(Self { width, height, color, data })
+    }
+
+    fn view(&self) -> ArrayView3<u8> {
+        ArrayView3::from_shape((self.height, self.width, self.color.channels()), &self.data)
+            .unwrap()
+    }
+}
+
+const NOT_CONTIGUOUS: &str = "input array is not in standard layout or is not contiguous";
+
+impl Group {
+    /// Creates an indexed (8-bit) image from an array of shape `(height, width)`.
+    ///
+    /// Pixel values are indices into the image palette, see `link_palette()`.
+    pub fn create_image_indexed<'a, A>(&self, name: &str, data: A) -> Result<Dataset>
+    where
+        A: Into<ArrayView2<'a, u8>>,
+    {
+        let data = data.into();
+        let (height, width) = data.dim();
+        let buf = data.as_slice().ok_or(NOT_CONTIGUOUS)?;
+        let c_name = to_cstring(name)?;
+        h5lock!({
+            h5try!(H5IMmake_image_8bit(
+                self.id(),
+                c_name.as_ptr(),
+                width as _,
+                height as _,
+                buf.as_ptr()
+            ));
+            self.dataset(name)
+        })
+    }
+
+    /// Creates a true-color (24-bit RGB) image from an array of shape `(height, width, 3)`
+    /// for pixel interlace or `(3, height, width)` for plane interlace.
+    pub fn create_image_truecolor<'a, A>(
+        &self, name: &str, data: A, interlace: Interlace,
+    ) -> Result<Dataset>
+    where
+        A: Into<ArrayView3<'a, u8>>,
+    {
+        let data = data.into();
+        let (height, width, planes) = match (interlace, data.dim()) {
+            (Interlace::Pixel, (h, w, p)) | (Interlace::Plane, (p, h, w)) => (h, w, p),
+        };
+        ensure!(planes == 3, "expected 3 color planes, got {}", planes);
+        let buf = data.as_slice().ok_or(NOT_CONTIGUOUS)?;
+        let c_name = to_cstring(name)?;
+        let c_interlace = interlace.to_cstring();
+        h5lock!({
+            h5try!(H5IMmake_image_24bit(
+                self.id(),
+                c_name.as_ptr(),
+                width as _,
+                height as _,
+                c_interlace.as_ptr(),
+                buf.as_ptr()
+            ));
+            self.dataset(name)
+        })
+    }
+
+    /// Creates an image from grayscale, RGB or RGBA pixels.
+    ///
+    /// Grayscale images are stored as 8-bit images of the `IMAGE_GRAYSCALE` subclass and
+    /// ignore `interlace`; RGB and RGBA images are stored as true-color images with three
+    /// or four color planes.
+    pub fn create_image(
+        &self, name: &str, image: &RawImage, interlace: Interlace,
+    ) -> Result<Dataset> {
+        if image.color == ColorType::L8 {
+            let pixels = ArrayView2::from_shape((image.height, image.width), &image.data)?;
+            return h5lock!({
+                let ds = self.create_image_indexed(name, pixels)?;
+                self.set_image_attr(name, "IMAGE_SUBCLASS", "IMAGE_GRAYSCALE")?;
+                ds.new_attr::<u8>().create_scalar("IMAGE_WHITE_IS_ZERO", &0)?;
+                Ok(ds)
+            });
+        }
+        let data: Cow<[u8]> = match interlace {
+            Interlace::Pixel => image.data.as_slice().into(),
+            Interlace::Plane => image.view().permuted_axes([2, 0, 1]).iter().copied().collect(),
+        };
+        let planes = ArrayView3::from_shape(planes_shape(image, interlace), &data)?;
+        if image.color == ColorType::Rgb8 {
+            return self.create_image_truecolor(name, planes, interlace);
+        }
+        // the library only makes images with three color planes, so the dataset and its
+        // attributes are set up the same way by hand
+        h5lock!({
+            let ds = self.new_dataset::<u8>().create(name, planes.dim())?;
+            ds.write(planes)?;
+            self.set_image_attr(name, "CLASS", "IMAGE")?;
+            self.set_image_attr(name, "IMAGE_VERSION", "1.2")?;
+            self.set_image_attr(name, "IMAGE_SUBCLASS", "IMAGE_TRUECOLOR")?;
+            self.set_image_attr(name, "INTERLACE_MODE", interlace.name())?;
+            Ok(ds)
+        })
+    }
+
+    fn set_image_attr(&self, name: &str, attr: &str, value: &str) -> Result<()> {
+        let (name, attr, value) = (to_cstring(name)?, to_cstring(attr)?, to_cstring(value)?);
+        h5call!(H5LTset_attribute_string(self.id(), name.as_ptr(), attr.as_ptr(), value.as_ptr()))
+            .and(Ok(()))
+    }
+
+    /// Reads the named image as grayscale, RGB or RGBA pixels, depending on its number of
+    /// color planes; plane-interlaced images are converted to the pixel layout.
+    ///
+    /// Indexed images are read as grayscale, with the pixel values being palette indices.
+    pub fn read_raw_image(&self, name: &str) -> Result<RawImage> {
+        let info = self.image_info(name)?;
+        let color = ColorType::from_channels(info.planes)
+            .ok_or_else(|| format!("unsupported number of color planes: {}", info.planes))?;
+        let pixels = self.read_image(name)?;
+        let data = match info.interlace {
+            Some(Interlace::Plane) => {
+                let planes = pixels.into_dimensionality::<Ix3>()?;
+                planes.permuted_axes([1, 2, 0]).iter().copied().collect()
+            }
+            _ => pixels.into_raw_vec(),
+        };
+        RawImage::new(info.width, info.height, color, data)
+    }
+
+    /// Returns `true` if the named dataset is an image.
+    pub fn is_image(&self, name: &str) -> bool {
+        let _e = silence_errors();
+        to_cstring(name)
+            .ok()
+            .map_or(false, |name| h5lock!(H5IMis_image(self.id(), name.as_ptr())) > 0)
+    }
+
+    /// Returns the dimensions, layout and number of palettes of the named image.
+    pub fn image_info(&self, name: &str) -> Result<ImageInfo> {
+        let c_name = to_cstring(name)?;
+        let (mut width, mut height, mut planes) = (0, 0, 0);
+        let mut interlace: Vec<c_char> = vec![0; 32];
+        let mut npals: hssize_t = 0;
+        h5try!(H5IMget_image_info(
+            self.id(),
+            c_name.as_ptr(),
+            &mut width,
+            &mut height,
+            &mut planes,
+            interlace.as_mut_ptr(),
+            &mut npals
+        ));
+        Ok(ImageInfo {
+            width: width as _,
+            height: height as _,
+            planes: planes as _,
+            interlace: Interlace::from_str(&string_from_cstr(interlace.as_ptr())),
+            palettes: npals as _,
+        })
+    }
+
+    /// Reads the named image into an array with the shape of the image dataset; see
+    /// `ImageInfo::shape()`.
+    pub fn read_image(&self, name: &str) -> Result<ArrayD<u8>> {
+        let c_name = to_cstring(name)?;
+        h5lock!({
+            let shape = self.image_info(name)?.shape();
+            let mut buf = vec![0_u8; shape.iter().product()];
+            h5try!(H5IMread_image(self.id(), c_name.as_ptr(), buf.as_mut_ptr()));
+            ArrayD::from_shape_vec(IxDyn(&shape), buf).map_err(Into::into)
+        })
+    }
+
+    /// Creates a palette dataset mapping pixel values of indexed images to RGB colors.
+    pub fn create_palette(&self, name: &str, colors: &[[u8; 3]]) -> Result<Dataset> {
+        ensure!(!colors.is_empty(), "palette must not be empty");
+        let c_name = to_cstring(name)?;
+        let dims: [hsize_t; 2] = [colors.len() as _, 3];
+        h5lock!({
+            h5try!(H5IMmake_palette(
+                self.id(),
+                c_name.as_ptr(),
+                dims.as_ptr(),
+                colors.as_ptr() as *const _
+            ));
+            self.dataset(name)
+        })
+    }
+
+    /// Returns `true` if the named dataset is a palette.
+    pub fn is_palette(&self, name: &str) -> bool {
+        let _e = silence_errors();
+        to_cstring(name)
+            .ok()
+            .map_or(false, |name| h5lock!(H5IMis_palette(self.id(), name.as_ptr())) > 0)
+    }
+
+    /// Attaches a palette to an image; both have to be located in this group.
+    pub fn link_palette(&self, image: &str, palette: &str) -> Result<()> {
+        let (image, palette) = (to_cstring(image)?, to_cstring(palette)?);
+        h5call!(H5IMlink_palette(self.id(), image.as_ptr(), palette.as_ptr())).and(Ok(()))
+    }
+
+    /// Detaches a palette from an image, without removing the palette dataset itself.
+    ///
+    /// Note that the library detaches all other palettes of the image as well, so the
+    /// ones that should be kept have to be attached again afterwards.
+    pub fn unlink_palette(&self, image: &str, palette: &str) -> Result<()> {
+        let (image, palette) = (to_cstring(image)?, to_cstring(palette)?);
+        h5call!(H5IMunlink_palette(self.id(), image.as_ptr(), palette.as_ptr())).and(Ok(()))
+    }
+
+    /// Reads all palettes attached to the named image, in the order they were attached.
+    pub fn read_palettes(&self, image: &str) -> Result<Vec<Vec<[u8; 3]>>> {
+        let c_image = to_cstring(image)?;
+        h5lock!({
+            let mut npals: hssize_t = 0;
+            h5try!(H5IMget_npalettes(self.id(), c_image.as_ptr(), &mut npals));
+            (0..npals as c_int)
+                .map(|i| {
+                    let mut dims: [hsize_t; 2] = [0, 0];
+                    h5try!(H5IMget_palette_info(self.id(), c_image.as_ptr(), i, dims.as_mut_ptr()));
+                    ensure!(dims[1] == 3, "expected an RGB palette, got {} components", dims[1]);
+                    let mut colors = vec![[0_u8; 3]; dims[0] as _];
+                    h5try!(H5IMget_palette(
+                        self.id(),
+                        c_image.as_ptr(),
+                        i,
+                        colors.as_mut_ptr() as *mut _
+                    ));
+                    Ok(colors)
+                })
+                .collect()
+        })
+    }
+}
+
+fn planes_shape(image: &RawImage, interlace: Interlace) -> (usize, usize, usize) {
+    let (height, width, planes) = (image.height, image.width, image.color.channels());
+    match interlace {
+        Interlace::Pixel => (height, width, planes),
+        Interlace::Plane => (planes, height, width),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use ndarray::{arr2, Array3};
+
+    use super::{ColorType, ImageInfo, Interlace, RawImage};
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_indexed_image() {
+        with_tmp_file(|file| {
+            let pixels = arr2(&[[0_u8, 1, 2], [2, 1, 0]]);
+            let ds = file.create_image_indexed("img", &pixels).unwrap();
+            assert_eq!(ds.shape(), vec![2, 3]);
+            assert!(file.is_image("img"));
+            assert!(!file.is_palette("img"));
+            assert!(!file.is_image("foo"));
+            assert_eq!(ds.attr("CLASS").unwrap().read_string().unwrap(), "IMAGE");
+
+            let colors = [[0, 0, 0], [255, 0, 0], [0, 0, 255]];
+            file.create_palette("pal", &colors).unwrap();
+            file.create_palette("gray", &[[0, 0, 0], [128, 128, 128], [255, 255, 255]]).unwrap();
+            assert!(file.is_palette("pal"));
+            file.link_palette("img", "pal").unwrap();
+            file.link_palette("img", "gray").unwrap();
+
+            let info = file.image_info("img").unwrap();
+            let expected =
+                ImageInfo { width: 3, height: 2, planes: 1, interlace: None, palettes: 2 };
+            assert_eq!(info, expected);
+            assert_eq!(file.read_image("img").unwrap(), pixels.clone().into_dyn());
+            let palettes = file.read_palettes("img").unwrap();
+            assert_eq!(palettes.len(), 2);
+            assert_eq!(palettes[0], colors);
+
+            file.unlink_palette("img", "gray").unwrap();
+            assert_eq!(file.image_info("img").unwrap().palettes, 0);
+            assert!(file.is_palette("pal"));
+            assert_err!(file.create_palette("x", &[]), "palette must not be empty");
+            assert_err!(file.create_image_indexed("x", pixels.t()), "not in standard layout");
+        })
+    }
+
+    #[test]
+    pub fn test_truecolor_image() {
+        with_tmp_file(|file| {
+            let pixels = Array3::from_shape_fn((4, 5, 3), |(i, j, k)| (i * 15 + j * 3 + k) as u8);
+            file.create_image_truecolor("pixel", &pixels, Interlace::Pixel).unwrap();
+            let info = file.image_info("pixel").unwrap();
+            assert_eq!((info.width, info.height, info.planes), (5, 4, 3));
+            assert_eq!(info.interlace, Some(Interlace::Pixel));
+            assert_eq!(file.read_image("pixel").unwrap(), pixels.clone().into_dyn());
+
+            let planes = Array3::from_shape_fn((3, 4, 5), |(k, i, j)| pixels[[i, j, k]]);
+            file.create_image_truecolor("plane", &planes, Interlace::Plane).unwrap();
+            let info = file.image_info("plane").unwrap();
+            assert_eq!(info.interlace, Some(Interlace::Plane));
+            assert_eq!(info.shape(), vec![3, 4, 5]);
+            assert_eq!(file.read_image("plane").unwrap(), planes.into_dyn());
+
+            assert_err!(
+                file.create_image_truecolor("x", &pixels, Interlace::Plane),
+                "expected 3 color planes, got 4"
+            );
+        })
+    }
+
+    #[test]
+    pub fn test_raw_image() {
+        with_tmp_file(|file| {
+            for &color in &[ColorType::L8, ColorType::Rgb8, ColorType::Rgba8] {
+                for &interlace in &[Interlace::Pixel, Interlace::Plane] {
+                    let channels = color.channels();
+                    let data = (0..4 * 5 * channels).map(|i| (i * 7) as u8).collect();
+                    let image = RawImage::new(5, 4, color, data).unwrap();
+                    let name = format!("{:?}-{:?}", color, interlace);
+                    let ds = file.create_image(&name, &image, interlace).unwrap();
+                    assert!(file.is_image(&name));
+
+                    let info = file.image_info(&name).unwrap();
+                    assert_eq!((info.width, info.height, info.planes), (5, 4, channels));
+                    assert_eq!(ds.shape(), info.shape());
+                    if color == ColorType::L8 {
+                        assert_eq!(info.interlace, None);
+                        let subclass = ds.attr("IMAGE_SUBCLASS").unwrap().read_string().unwrap();
+                        assert_eq!(subclass, "IMAGE_GRAYSCALE");
+                    } else {
+                        assert_eq!(info.interlace, Some(interlace));
+                        let pixels = file.read_image(&name).unwrap();
+                        let (i, j, k) = (3, 2, channels - 1);
+                        let value = match interlace {
+                            Interlace::Pixel => pixels[[i, j, k]],
+                            Interlace::Plane => pixels[[k, i, j]],
+                        };
+                        assert_eq!(value, image.data[(i * 5 + j) * channels + k]);
+                    }
+                    assert_eq!(file.read_raw_image(&name).unwrap(), image);
+                }
+            }
+
+            assert_err!(
+                RawImage::new(2, 2, ColorType::Rgb8, vec![0; 4]),
+                "expected 12 bytes of pixel data, got 4"
+            );
+        })
+    }
+}
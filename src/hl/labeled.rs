@@ -0,0 +1,316 @@
+//! Datasets labelled by their dimension scales (requires the `hl` feature).
+//!
+//! A `LabeledDataset` reads the dimension labels and the coordinate values of the
+//! dimension scales attached to a dataset, so that parts of the dataset can be selected
+//! by dimension name and coordinate value rather than by index, similar to xarray:
+//!
+//! ```ignore
+//! let temperature = file.dataset("temperature")?.labeled()?;
+//! let subset = temperature.select("time", 10.0..20.0)?.select("lat", ..0.0)?;
+//! let values = subset.read::<f32>()?;
+//! ```
+//!
+//! A dimension is named by its label if it has one, and otherwise by the name of the
+//! first dimension scale attached to it (or `dim_N` if there is none). Coordinates are the
+//! values of the first attached scale, converted to `f64`; dimensions without numeric
+//! coordinates can only be selected by index.
+
+use std::ops::{Bound, Range, RangeBounds};
+
+use ndarray::ArrayD;
+
+use crate::internal_prelude::*;
+
+/// A dimension of a `LabeledDataset`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LabeledAxis {
+    name: String,
+    len: usize,
+    coords: Option<Vec<f64>>,
+}
+
+impl LabeledAxis {
+    /// Returns the name of the dimension.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the length of the dimension.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the dimension has zero length.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the coordinate values along the dimension, if any.
+    pub fn coords(&self) -> Option<&[f64]> {
+        self.coords.as_deref()
+    }
+
+    /// Returns the range of indices whose coordinates lie within the given bounds.
+    fn index_range<R: RangeBounds<f64>>(&self, range: &R) -> Result<Range<usize>> {
+        let coords = match self.coords {
+            Some(ref coords) => coords,
+            None => fail!("dimension '{}' has no numeric coordinates", self.name),
+        };
+        let contains = |x: f64| {
+            let above = match range.start_bound() {
+                Bound::Included(&start) => x >= start,
+                Bound::Excluded(&start) => x > start,
+                Bound::Unbounded => true,
+            };
+            let below = match range.end_bound() {
+                Bound::Included(&end) => x <= end,
+                Bound::Excluded(&end) => x < end,
+                Bound::Unbounded => true,
+            };
+            above && below
+        };
+        let first = match coords.iter().position(|&x| contains(x)) {
+            Some(first) => first,
+            None => return Ok(0..0),
+        };
+        let end = coords.iter().rposition(|&x| contains(x)).map_or(first, |last| last + 1);
+        ensure!(
+            coords[first..end].iter().all(|&x| contains(x)),
+            "coordinates of dimension '{}' are not monotonic",
+            self.name
+        );
+        Ok(first..end)
+    }
+}
+
+/// A dataset together with the names and coordinates of its dimensions.
+#[derive(Clone, Debug)]
+pub struct LabeledDataset {
+    dataset: Dataset,
+    axes: Vec<LabeledAxis>,
+}
+
+impl LabeledDataset {
+    /// Reads the dimension labels and coordinates of a dataset.
+    pub fn new(dataset: &Dataset) -> Result<Self> {
+        let shape = dataset.shape();
+        let mut axes = Vec::with_capacity(shape.len());
+        for (dim, &len) in shape.iter().enumerate() {
+            let scale = dataset.scales(dim)?.into_iter().next();
+            let label = dataset.dim_label(dim)?;
+            let name = if !label.is_empty() {
+                label
+            } else if let Some(ref scale) = scale {
+                scale.name().rsplit('/').next().unwrap_or_default().to_owned()
+            } else {
+                format!("dim_{}", dim)
+            };
+            let coords = match scale {
+                Some(scale) if scale.shape() == [len] => {
+                    let _e = silence_errors();
+                    scale.read_raw::<f64>().ok()
+                }
+                _ => None,
+            };
+            ensure!(
+                !axes.iter().any(|axis: &LabeledAxis| axis.name == name),
+                "duplicate dimension name '{}'",
+                name
+            );
+            axes.push(LabeledAxis { name, len, coords });
+        }
+        Ok(Self { dataset: dataset.clone(), axes })
+    }
+
+    /// Returns the underlying dataset.
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+
+    /// Returns the dimensions of the dataset.
+    pub fn axes(&self) -> &[LabeledAxis] {
+        &self.axes
+    }
+
+    /// Returns the dimension with the given name.
+    pub fn axis(&self, name: &str) -> Option<&LabeledAxis> {
+        self.axes.iter().find(|axis| axis.name == name)
+    }
+
+    /// Returns a selection of the whole dataset.
+    pub fn all(&self) -> LabeledSelection {
+        let ranges = self.axes.iter().map(|axis| 0..axis.len).collect();
+        LabeledSelection { labeled: self, ranges }
+    }
+
+    /// Selects the elements whose coordinates along the named dimension lie within the
+    /// given range; see `LabeledSelection::select()`.
+    pub fn select<R: RangeBounds<f64>>(&self, dim: &str, range: R) -> Result<LabeledSelection> {
+        self.all().select(dim, range)
+    }
+
+    /// Selects a range of indices along the named dimension; see
+    /// `LabeledSelection::select_index()`.
+    pub fn select_index(&self, dim: &str, range: Range<usize>) -> Result<LabeledSelection> {
+        self.all().select_index(dim, range)
+    }
+
+    /// Reads the whole dataset.
+    pub fn read<T: H5Type>(&self) -> Result<ArrayD<T>> {
+        self.dataset.read_dyn()
+    }
+}
+
+/// A rectangular part of a `LabeledDataset`, built up by selecting ranges along its
+/// dimensions; nothing is read until `read()` is called.
+#[derive(Clone, Debug)]
+pub struct LabeledSelection<'a> {
+    labeled: &'a LabeledDataset,
+    ranges: Vec<Range<usize>>,
+}
+
+impl<'a> LabeledSelection<'a> {
+    fn dim_index(&self, dim: &str) -> Result<usize> {
+        let axes = &self.labeled.axes;
+        axes.iter().position(|axis| axis.name == dim).ok_or_else(|| {
+            let names: Vec<_> = axes.iter().map(|axis| axis.name.as_str()).collect();
+            format!("no dimension named '{}' (dimensions: {:?})", dim, names).into()
+        })
+    }
+
+    /// Narrows the selection along the named dimension to the elements whose coordinates
+    /// lie within the given range, which may select nothing.
+    ///
+    /// The coordinates within the range have to be contiguous (which is the case for
+    /// monotonic coordinates) since selections are rectangular.
+    pub fn select<R: RangeBounds<f64>>(self, dim: &str, range: R) -> Result<Self> {
+        let index = self.dim_index(dim)?;
+        let range = self.labeled.axes[index].index_range(&range)?;
+        Ok(self.narrow(index, range))
+    }
+
+    /// Narrows the selection along the named dimension to a range of indices.
+    pub fn select_index(self, dim: &str, range: Range<usize>) -> Result<Self> {
+        let index = self.dim_index(dim)?;
+        let len = self.labeled.axes[index].len;
+        ensure!(
+            range.start <= range.end && range.end <= len,
+            "index range {:?} is out of bounds for dimension '{}' of length {}",
+            range,
+            dim,
+            len
+        );
+        Ok(self.narrow(index, range))
+    }
+
+    fn narrow(mut self, index: usize, range: Range<usize>) -> Self {
+        let current = &mut self.ranges[index];
+        let start = current.start.max(range.start);
+        *current = start..current.end.min(range.end).max(start);
+        self
+    }
+
+    /// Returns the shape of the selection.
+    pub fn shape(&self) -> Vec<Ix> {
+        self.ranges.iter().map(ExactSizeIterator::len).collect()
+    }
+
+    /// Returns the selected index ranges along all dimensions.
+    pub fn ranges(&self) -> &[Range<usize>] {
+        &self.ranges
+    }
+
+    /// Returns the selected coordinate values along the named dimension, if it has any.
+    pub fn coords(&self, dim: &str) -> Result<Option<&'a [f64]>> {
+        let index = self.dim_index(dim)?;
+        let axes = &self.labeled.axes;
+        Ok(axes[index].coords().map(|coords| &coords[self.ranges[index].clone()]))
+    }
+
+    /// Reads the selected elements.
+    pub fn read<T: H5Type>(&self) -> Result<ArrayD<T>> {
+        let shape = self.shape();
+        if shape.is_empty() {
+            return self.labeled.read();
+        }
+        let values = if shape.contains(&0) {
+            vec![]
+        } else {
+            let start: Vec<_> = self.ranges.iter().map(|range| range.start).collect();
+            let space = self.labeled.dataset.space()?;
+            space.select_hyperslab(&start, None, &shape, None)?;
+            self.labeled.dataset.read_selection(&space)?
+        };
+        Ok(ArrayD::from_shape_vec(shape, values)?)
+    }
+}
+
+impl Dataset {
+    /// Reads the dimension names and coordinates of this dataset; see `LabeledDataset`.
+    pub fn labeled(&self) -> Result<LabeledDataset> {
+        LabeledDataset::new(self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use ndarray::{arr2, Array2};
+
+    use super::LabeledAxis;
+    use crate::internal_prelude::*;
+
+    fn make_dataset(file: &File) -> Dataset {
+        let data = file.new_dataset::<f32>().create("temperature", (4, 3)).unwrap();
+        data.write(&Array2::from_shape_fn((4, 3), |(i, j)| (i * 10 + j) as f32)).unwrap();
+        let time = file.create_scale("time", &[0_u32, 10, 20, 30]).unwrap();
+        let lat = file.create_scale("latitude", &[45.0_f64, 0.0, -45.0]).unwrap();
+        data.attach_scale(&time, 0).unwrap();
+        data.attach_scale(&lat, 1).unwrap();
+        data.set_dim_label(1, "lat").unwrap();
+        data
+    }
+
+    #[test]
+    pub fn test_labeled_axes() {
+        with_tmp_file(|file| {
+            let labeled = make_dataset(&file).labeled().unwrap();
+            let names: Vec<_> = labeled.axes().iter().map(LabeledAxis::name).collect();
+            assert_eq!(names, vec!["time", "lat"]);
+            assert_eq!(labeled.axis("time").unwrap().coords(), Some(&[0., 10., 20., 30.][..]));
+            assert_eq!(labeled.axis("lat").unwrap().len(), 3);
+            assert!(labeled.axis("latitude").is_none());
+
+            let plain = file.new_dataset::<u8>().create("plain", (2, 2)).unwrap().labeled();
+            let plain = plain.unwrap();
+            assert_eq!(plain.axes()[1].name(), "dim_1");
+            assert_eq!(plain.axes()[1].coords(), None);
+            assert_err!(plain.select("dim_0", 0.0..1.0), "dimension 'dim_0' has no numeric");
+            assert_eq!(plain.select_index("dim_0", 1..2).unwrap().shape(), vec![1, 2]);
+        })
+    }
+
+    #[test]
+    pub fn test_labeled_select() {
+        with_tmp_file(|file| {
+            let labeled = make_dataset(&file).labeled().unwrap();
+            let subset = labeled.select("time", 10.0..30.0).unwrap();
+            assert_eq!(subset.shape(), vec![2, 3]);
+            assert_eq!(subset.coords("time").unwrap(), Some(&[10., 20.][..]));
+            let subset = subset.select("lat", ..=0.0).unwrap();
+            assert_eq!(subset.ranges(), &[1..3, 1..3]);
+            assert_eq!(subset.coords("lat").unwrap(), Some(&[0., -45.][..]));
+            let values = subset.read::<f32>().unwrap();
+            assert_eq!(values.into_dimensionality().unwrap(), arr2(&[[11., 12.], [21., 22.]]));
+
+            let subset = labeled.select_index("lat", 0..2).unwrap().select("lat", -50.0..1.0);
+            assert_eq!(subset.unwrap().ranges(), &[0..4, 1..2]);
+            let empty = labeled.select("time", 100.0..).unwrap();
+            assert_eq!(empty.shape(), vec![0, 3]);
+            assert!(empty.read::<f32>().unwrap().is_empty());
+            assert_eq!(labeled.all().read::<f32>().unwrap(), labeled.read::<f32>().unwrap());
+
+            assert_err!(labeled.select("depth", ..), "no dimension named 'depth'");
+            assert_err!(labeled.select_index("time", 2..5), "out of bounds");
+        })
+    }
+}
@@ -2,12 +2,28 @@ use std::fmt::{self, Debug};
 use std::ops::Deref;
 use std::ptr;
 
+#[cfg(not(hdf5_1_10_3))]
+use hdf5_sys::h5o::H5Oget_info;
+#[cfg(hdf5_1_10_3)]
+use hdf5_sys::h5o::{H5Oget_info2, H5O_INFO_NUM_ATTRS};
+#[cfg(not(hdf5_1_10_0))]
+use hdf5_sys::h5r::H5Rdereference;
+#[cfg(hdf5_1_10_0)]
+use hdf5_sys::h5r::H5Rdereference2;
 use hdf5_sys::{
+    h5a::{H5Adelete, H5Aexists, H5Aopen, H5Aopen_by_idx, H5Arename},
     h5f::H5Fget_name,
-    h5i::{H5Iget_file_id, H5Iget_name},
-    h5o::{H5Oget_comment, H5Oset_comment},
+    h5i::{H5Idec_ref, H5Iget_file_id, H5Iget_name},
+    h5o::{H5O_info_t, H5Oget_comment, H5Oset_comment},
+    h5r::{H5R_type_t, H5Rcreate, H5Rget_region, H5R_DATASET_REGION, H5R_OBJECT},
 };
 
+use hdf5_types::{ObjectReference, RegionReference, VarLenUnicode};
+
+use crate::hl::attribute::{
+    attr_names, copy_attr, to_varlen_unicode, AttrOrder, AttributeBuilder, AttributeIter,
+};
+use crate::hl::datatype::stored_descriptor;
 use crate::internal_prelude::*;
 
 /// Named location (file, group, dataset, named datatype).
@@ -61,6 +77,17 @@ impl Location {
         h5lock!(get_h5_str(|m, s| H5Fget_name(self.id(), m, s)).unwrap_or_else(|_| "".to_string()))
     }
 
+    /// Describes an operation on a member of this object (e.g. a dataset in a group or an
+    /// attribute) for error messages, e.g. "opening /foo/bar in data.h5".
+    pub(crate) fn member_context(&self, op: &str, name: &str) -> String {
+        let path = if name.starts_with('/') {
+            name.to_owned()
+        } else {
+            format!("{}/{}", self.name().trim_end_matches('/'), name)
+        };
+        format!("{} {} in {}", op, path, self.filename())
+    }
+
     /// Returns a handle to the file containing the named object (or the file itself).
     pub fn file(&self) -> Result<File> {
         File::from_id(h5try!(H5Iget_file_id(self.id())))
@@ -85,6 +112,234 @@ impl Location {
         // TODO: &mut self?
         h5call!(H5Oset_comment(self.id(), ptr::null_mut())).and(Ok(()))
     }
+
+    /// Instantiates a new attribute builder.
+    pub fn new_attr<T: H5Type>(&self) -> AttributeBuilder<T> {
+        AttributeBuilder::<T>::new(self)
+    }
+
+    /// Opens an existing attribute attached to the named object.
+    pub fn attr(&self, name: &str) -> Result<Attribute> {
+        let c_name = to_cstring(name)?;
+        h5call!(H5Aopen(self.id(), c_name.as_ptr(), H5P_DEFAULT))
+            .and_then(Attribute::from_id)
+            .with_context(|| {
+                format!("opening attribute {} of {} in {}", name, self.name(), self.filename())
+            })
+    }
+
+    /// Opens the attribute at a given position in the specified order.
+    ///
+    /// This allows processing objects with many attributes one at a time without
+    /// collecting all of their names upfront; see also `num_attrs()`.
+    pub fn attr_by_index(&self, index: usize, order: AttrOrder) -> Result<Attribute> {
+        let (idx_type, order) = order.to_raw();
+        let obj_name = to_cstring(".")?;
+        Attribute::from_id(h5try!(H5Aopen_by_idx(
+            self.id(),
+            obj_name.as_ptr(),
+            idx_type,
+            order,
+            index as _,
+            H5P_DEFAULT,
+            H5P_DEFAULT
+        )))
+    }
+
+    /// Returns the number of attributes attached to the named object.
+    pub fn num_attrs(&self) -> Result<usize> {
+        let mut info = H5O_info_t::default();
+        #[cfg(not(hdf5_1_10_3))]
+        h5call!(H5Oget_info(self.id(), &mut info))?;
+        #[cfg(hdf5_1_10_3)]
+        h5call!(H5Oget_info2(self.id(), &mut info, H5O_INFO_NUM_ATTRS))?;
+        Ok(info.num_attrs as _)
+    }
+
+    /// Returns names of all the attributes attached to the named object, in ascending
+    /// alphanumeric order.
+    pub fn attr_names(&self) -> Result<Vec<String>> {
+        attr_names(self, AttrOrder::Name)
+    }
+
+    /// Returns names of all the attributes attached to the named object in a given order.
+    pub fn attr_names_ordered(&self, order: AttrOrder) -> Result<Vec<String>> {
+        attr_names(self, order)
+    }
+
+    /// Returns an iterator over all the attributes attached to the named object, in
+    /// ascending alphanumeric order of their names.
+    pub fn attrs(&self) -> Result<AttributeIter> {
+        AttributeIter::new(self, AttrOrder::Name)
+    }
+
+    /// Returns an iterator over all the attributes attached to the named object in a
+    /// given order.
+    pub fn attrs_ordered(&self, order: AttrOrder) -> Result<AttributeIter> {
+        AttributeIter::new(self, order)
+    }
+
+    /// Returns `true` if an attribute with a given name is attached to the named object.
+    pub fn attr_exists(&self, name: &str) -> bool {
+        (|| -> Result<bool> {
+            let name = to_cstring(name)?;
+            Ok(h5call!(H5Aexists(self.id(), name.as_ptr()))? > 0)
+        })()
+        .unwrap_or(false)
+    }
+
+    /// Opens an attribute attached to the named object if it exists.
+    ///
+    /// Unlike `attr()`, a missing attribute is not treated as an error, so the HDF5 error
+    /// stack is left untouched; this is useful for reading optional metadata.
+    pub fn attr_opt(&self, name: &str) -> Result<Option<Attribute>> {
+        let c_name = to_cstring(name)?;
+        h5lock!({
+            if h5call!(H5Aexists(self.id(), c_name.as_ptr()))? > 0 {
+                self.attr(name).map(Some)
+            } else {
+                Ok(None)
+            }
+        })
+    }
+
+    /// Reads a scalar attribute with a given name, or returns the provided default value
+    /// if the attribute doesn't exist; see `attr_opt()`.
+    pub fn read_attr_or<T: H5Type>(&self, name: &str, default: T) -> Result<T> {
+        self.attr_opt(name)?.map_or(Ok(default), |attr| attr.read_scalar())
+    }
+
+    /// Sets a scalar attribute, creating it if it doesn't exist yet.
+    ///
+    /// An existing attribute is overwritten in place if it is scalar and has the same
+    /// datatype as the value; otherwise, it is deleted and recreated.
+    pub fn set_attr<T: H5Type>(&self, name: &str, value: &T) -> Result<Attribute> {
+        h5lock!({
+            if self.attr_exists(name) {
+                let attr = self.attr(name)?;
+                let datatype = Datatype::from_descriptor(&stored_descriptor::<T>(false))?;
+                if attr.is_scalar() && attr.dtype()? == datatype {
+                    attr.write_scalar(value)?;
+                    return Ok(attr);
+                }
+                drop(attr);
+                self.delete_attr(name)?;
+            }
+            AttributeBuilder::<T>::new(self).create_scalar(name, value)
+        })
+    }
+
+    /// Reads a scalar attribute with a given name.
+    pub fn get_attr<T: H5Type>(&self, name: &str) -> Result<T> {
+        self.attr(name)?.read_scalar()
+    }
+
+    /// Sets a scalar variable-length UTF-8 string attribute, creating it if it doesn't
+    /// exist yet; see `set_attr()`.
+    pub fn set_attr_str(&self, name: &str, value: &str) -> Result<Attribute> {
+        self.set_attr(name, &to_varlen_unicode(value)?)
+    }
+
+    /// Sets a one-dimensional variable-length UTF-8 string attribute, replacing any
+    /// existing attribute with the same name.
+    ///
+    /// Handles to the attribute being replaced should be dropped beforehand, since the
+    /// library may otherwise keep serving their contents under the same name.
+    pub fn set_attr_strings<S: AsRef<str>>(&self, name: &str, values: &[S]) -> Result<Attribute> {
+        let values: Vec<_> =
+            values.iter().map(|s| to_varlen_unicode(s.as_ref())).collect::<Result<_>>()?;
+        h5lock!({
+            if self.attr_exists(name) {
+                self.delete_attr(name)?;
+            }
+            AttributeBuilder::<VarLenUnicode>::new(self).with_data(name, values.as_slice())
+        })
+    }
+
+    /// Reads a scalar string attribute with a given name; see `Attribute::read_string()`.
+    pub fn get_attr_string(&self, name: &str) -> Result<String> {
+        self.attr(name)?.read_string()
+    }
+
+    /// Reads all elements of a string attribute with a given name; see
+    /// `Attribute::read_strings()`.
+    pub fn get_attr_strings(&self, name: &str) -> Result<Vec<String>> {
+        self.attr(name)?.read_strings()
+    }
+
+    /// Copies all attributes of the named object to another named object, preserving their
+    /// datatypes and dataspaces.
+    ///
+    /// Fails if the destination already has an attribute with the same name as one of the
+    /// attributes being copied; attributes copied prior to the failure are retained.
+    pub fn copy_attrs_to(&self, other: &Self) -> Result<()> {
+        for attr in self.attrs()? {
+            copy_attr(&attr?, other)?;
+        }
+        Ok(())
+    }
+
+    /// Removes an attribute with a given name from the named object.
+    pub fn delete_attr(&self, name: &str) -> Result<()> {
+        let name = to_cstring(name)?;
+        h5call!(H5Adelete(self.id(), name.as_ptr())).and(Ok(()))
+    }
+
+    /// Renames an attribute attached to the named object.
+    pub fn rename_attr(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let old_name = to_cstring(old_name)?;
+        let new_name = to_cstring(new_name)?;
+        h5call!(H5Arename(self.id(), old_name.as_ptr(), new_name.as_ptr())).and(Ok(()))
+    }
+
+    /// Creates an object reference to the object at `name` relative to this location
+    /// (`"."` refers to the named object itself).
+    pub fn reference(&self, name: &str) -> Result<ObjectReference> {
+        let name = to_cstring(name)?;
+        let mut reference = ObjectReference::default();
+        let ptr = &mut reference as *mut _ as *mut _;
+        h5try!(H5Rcreate(ptr, self.id(), name.as_ptr(), H5R_OBJECT, -1));
+        Ok(reference)
+    }
+
+    /// Opens the object pointed to by an object reference created in the same file.
+    pub fn dereference<T: ObjectClass>(&self, reference: &ObjectReference) -> Result<T> {
+        ensure!(!reference.is_null(), "null object reference");
+        let ptr = reference as *const _ as *const _;
+        h5lock!({
+            let id = h5try!(dereference(self.id(), H5R_OBJECT, ptr));
+            // don't leak the opened object if it's not of the requested class; once the
+            // id is wrapped by `from_id()`, the handle releases it on failure instead
+            if T::is_valid_id_type(get_id_type(id)) {
+                T::from_id(id)
+            } else {
+                H5Idec_ref(id);
+                fail!("Invalid {} id: {}", T::NAME, id)
+            }
+        })
+    }
+
+    /// Opens the dataset pointed to by a region reference created in the same file, along
+    /// with its dataspace with the referenced region selected.
+    pub fn dereference_region(&self, reference: &RegionReference) -> Result<(Dataset, Dataspace)> {
+        ensure!(!reference.is_null(), "null region reference");
+        let ptr = reference as *const _ as *const _;
+        h5lock!({
+            let dataset =
+                Dataset::from_id(h5try!(dereference(self.id(), H5R_DATASET_REGION, ptr)))?;
+            let space =
+                Dataspace::from_id(h5try!(H5Rget_region(self.id(), H5R_DATASET_REGION, ptr)))?;
+            Ok((dataset, space))
+        })
+    }
+}
+
+unsafe fn dereference(loc_id: hid_t, ref_type: H5R_type_t, reference: *const c_void) -> hid_t {
+    #[cfg(hdf5_1_10_0)]
+    let id = H5Rdereference2(loc_id, H5P_DEFAULT, ref_type, reference);
+    #[cfg(not(hdf5_1_10_0))]
+    let id = H5Rdereference(loc_id, ref_type, reference);
+    id
 }
 
 #[cfg(test)]
@@ -1,15 +1,29 @@
+pub mod attribute;
 pub mod container;
 pub mod dataset;
 pub mod datatype;
+#[cfg(feature = "hl")]
+pub mod dimension_scale;
 pub mod file;
 pub mod group;
+#[cfg(feature = "hl")]
+pub mod image;
+#[cfg(feature = "hl")]
+pub mod labeled;
 pub mod location;
+#[cfg(feature = "hl")]
+pub mod netcdf;
 pub mod object;
+#[cfg(feature = "hl")]
+pub mod packet_table;
 pub mod plist;
 pub mod space;
+#[cfg(feature = "hl")]
+pub mod table;
 
 pub use self::{
-    container::{Container, Reader, Writer},
+    attribute::{Attribute, AttributeBuilder},
+    container::{Container, DynArray, Reader, StringArena, UnknownEnumValue, Writer},
     dataset::{Dataset, DatasetBuilder},
     datatype::{Conversion, Datatype},
     file::{File, FileBuilder, OpenMode},
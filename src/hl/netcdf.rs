@@ -0,0 +1,301 @@
+//! Helpers following the netCDF-4 conventions (requires the `hl` feature).
+//!
+//! netCDF-4 files are HDF5 files in which dimensions are stored as dimension scales tagged
+//! with a file-wide `_Netcdf4Dimid` attribute, and variables are datasets with a dimension
+//! scale attached to each of their axes. A dimension either has a coordinate variable (a
+//! one-dimensional dataset of the same name holding its values) or is a placeholder
+//! dataset without values. Dimensions are visible in the group they are defined in and
+//! all of its subgroups. Auxiliary coordinates of a variable are listed in its CF
+//! `coordinates` attribute.
+//!
+//! Files written via these helpers open as regular netCDF-4 files in tools such as
+//! `ncdump`, netCDF4-python or xarray.
+
+use std::collections::HashSet;
+
+use crate::describe::Member;
+use crate::internal_prelude::*;
+use crate::stats::object_key;
+
+const DIMID_ATTR: &str = "_Netcdf4Dimid";
+const COORDINATES_ATTR: &str = "coordinates";
+const DIM_WITHOUT_VARIABLE: &str = "This is a netCDF dimension but not a netCDF variable.";
+
+/// Number of elements per chunk of unlimited dimensions, as used by the netCDF library.
+const UNLIMITED_CHUNK_SIZE: usize = 1024;
+
+fn basename(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn parent_path(path: &str) -> Option<&str> {
+    if path == "/" {
+        return None;
+    }
+    path.rfind('/').map(|n| if n == 0 { "/" } else { &path[..n] })
+}
+
+/// Returns the largest `_Netcdf4Dimid` in use in the group and its subgroups.
+fn max_dimid(
+    group: &Group, visited: &mut HashSet<(std::os::raw::c_ulong, haddr_t)>,
+) -> Result<i32> {
+    let mut max = -1;
+    for name in group.member_names()? {
+        match Member::open(group, &name)? {
+            Member::Group(subgroup) => {
+                if object_key(subgroup.id()).map_or(true, |key| visited.insert(key)) {
+                    max = max.max(max_dimid(&subgroup, visited)?);
+                }
+            }
+            Member::Dataset(dataset) => max = max.max(dataset.read_attr_or(DIMID_ATTR, -1)?),
+            _ => {}
+        }
+    }
+    Ok(max)
+}
+
+fn make_nc_dimension(dataset: Dataset, len: usize) -> Result<Dataset> {
+    dataset.make_scale(&format!("{}{:>10}", DIM_WITHOUT_VARIABLE, len))?;
+    dataset.set_nc_dimid()?;
+    Ok(dataset)
+}
+
+impl Dataset {
+    /// Turns this one-dimensional dataset into a netCDF coordinate variable, i.e. a
+    /// dimension with the same name as the dataset whose values it holds.
+    pub fn make_nc_coordinate(&self) -> Result<()> {
+        ensure!(self.ndim() == 1, "coordinate variables must be one-dimensional");
+        let name = self.name();
+        self.make_scale(basename(&name))?;
+        self.set_nc_dimid()
+    }
+
+    fn set_nc_dimid(&self) -> Result<()> {
+        let file = self.file()?;
+        let dimid = max_dimid(&file, &mut HashSet::new())? + 1;
+        self.set_attr(DIMID_ATTR, &dimid).map(drop)
+    }
+
+    /// Returns `true` if this dataset is a netCDF dimension (with or without a coordinate
+    /// variable).
+    pub fn is_nc_dimension(&self) -> bool {
+        self.is_scale() && self.ndim() == 1
+    }
+
+    /// Attaches the named netCDF dimensions to the axes of this dataset, making it a
+    /// netCDF variable.
+    ///
+    /// Dimensions are looked up in the group containing the dataset and then in each of
+    /// its ancestors. The lengths of the axes have to match the dimension lengths, except
+    /// for unlimited dimensions. Dimensions previously attached to the axes are detached;
+    /// a coordinate variable is its own dimension and isn't attached to itself.
+    pub fn set_nc_dimensions(&self, dims: &[&str]) -> Result<()> {
+        let shape = self.shape();
+        ensure!(
+            dims.len() == shape.len(),
+            "expected {} dimensions for dataset of rank {}, got {}",
+            shape.len(),
+            shape.len(),
+            dims.len()
+        );
+        let file = self.file()?;
+        let path = self.name();
+        let mut scales = Vec::with_capacity(dims.len());
+        for &dim in dims {
+            let mut group_path = parent_path(&path);
+            let scale = loop {
+                let group = match group_path {
+                    Some(group_path) => file.group(group_path)?,
+                    None => fail!("netCDF dimension '{}' not found", dim),
+                };
+                if group.link_exists(dim) {
+                    if let Ok(scale) = group.dataset(dim) {
+                        if scale.is_nc_dimension() {
+                            break scale;
+                        }
+                    }
+                }
+                group_path = group_path.and_then(parent_path);
+            };
+            scales.push(scale);
+        }
+        for (axis, (scale, &dim)) in scales.iter().zip(dims).enumerate() {
+            let space = scale.space()?;
+            let (len, unlimited) = (space.dims()[0], space.maxdims()[0] == H5S_UNLIMITED as Ix);
+            ensure!(
+                unlimited || shape[axis] == len,
+                "axis {} has length {}, netCDF dimension '{}' has length {}",
+                axis,
+                shape[axis],
+                dim,
+                len
+            );
+        }
+        for (axis, scale) in scales.iter().enumerate() {
+            let key = object_key(scale.id());
+            for attached in self.scales(axis)? {
+                if object_key(attached.id()) != key {
+                    self.detach_scale(&attached, axis)?;
+                }
+            }
+            if key != object_key(self.id()) && !self.is_scale_attached(scale, axis)? {
+                self.attach_scale(scale, axis)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the names of the netCDF dimensions attached to the axes of this dataset,
+    /// or `None` for axes without dimensions.
+    pub fn nc_dimensions(&self) -> Result<Vec<Option<String>>> {
+        let path = self.name();
+        (0..self.ndim())
+            .map(|axis| {
+                if axis == 0 && self.is_nc_dimension() {
+                    return Ok(Some(basename(&path).to_owned()));
+                }
+                let scales = self.scales(axis)?;
+                Ok(scales.first().map(|scale| basename(&scale.name()).to_owned()))
+            })
+            .collect()
+    }
+
+    /// Sets the CF `coordinates` attribute listing the auxiliary coordinate variables of
+    /// this dataset.
+    pub fn set_nc_coordinates(&self, coordinates: &[&str]) -> Result<()> {
+        for name in coordinates {
+            ensure!(
+                !name.is_empty() && !name.contains(char::is_whitespace),
+                "invalid coordinate variable name: {:?}",
+                name
+            );
+        }
+        self.set_attr_str(COORDINATES_ATTR, &coordinates.join(" ")).map(drop)
+    }
+
+    /// Returns the names listed in the CF `coordinates` attribute, if any.
+    pub fn nc_coordinates(&self) -> Result<Vec<String>> {
+        match self.attr_opt(COORDINATES_ATTR)? {
+            Some(attr) => Ok(attr.read_string()?.split_whitespace().map(Into::into).collect()),
+            None => Ok(vec![]),
+        }
+    }
+}
+
+impl Group {
+    /// Creates a netCDF dimension of the given length without a coordinate variable.
+    pub fn create_nc_dimension(&self, name: &str, len: usize) -> Result<Dataset> {
+        let dataset = self.new_dataset::<f32>().create(name, len)?;
+        make_nc_dimension(dataset, len)
+    }
+
+    /// Creates an unlimited netCDF dimension of the given initial length without a
+    /// coordinate variable; it can be extended via `Dataset::resize()`.
+    pub fn create_nc_unlimited_dimension(&self, name: &str, len: usize) -> Result<Dataset> {
+        let dataset = self
+            .new_dataset::<f32>()
+            .chunk(UNLIMITED_CHUNK_SIZE)
+            .resizable(true)
+            .create(name, len)?;
+        make_nc_dimension(dataset, len)
+    }
+
+    /// Creates a netCDF coordinate variable holding the given values, which also defines
+    /// a dimension of the same name; see `Dataset::make_nc_coordinate()`.
+    pub fn create_nc_coordinate<T: H5Type>(&self, name: &str, values: &[T]) -> Result<Dataset> {
+        let dataset = self.new_dataset::<T>().create(name, values.len())?;
+        dataset.write_raw(values)?;
+        dataset.make_nc_coordinate()?;
+        Ok(dataset)
+    }
+
+    /// Returns the names and lengths of the netCDF dimensions defined in this group, in
+    /// the order they were defined.
+    pub fn nc_dimensions(&self) -> Result<Vec<(String, usize)>> {
+        let mut dims = Vec::new();
+        for name in self.member_names()? {
+            if let Member::Dataset(dataset) = Member::open(self, &name)? {
+                if dataset.is_nc_dimension() {
+                    let dimid = dataset.read_attr_or(DIMID_ATTR, i32::MAX)?;
+                    dims.push((dimid, name, dataset.shape()[0]));
+                }
+            }
+        }
+        dims.sort();
+        Ok(dims.into_iter().map(|(_, name, len)| (name, len)).collect())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_nc_dimensions() {
+        with_tmp_file(|file| {
+            let time = file.create_nc_unlimited_dimension("time", 2).unwrap();
+            let lat = file.create_nc_coordinate("lat", &[-45.0_f32, 0.0, 45.0]).unwrap();
+            let x = file.create_nc_dimension("x", 4).unwrap();
+            assert!(time.is_nc_dimension() && lat.is_nc_dimension() && x.is_nc_dimension());
+            assert_eq!(time.get_attr::<i32>("_Netcdf4Dimid").unwrap(), 0);
+            assert_eq!(lat.get_attr::<i32>("_Netcdf4Dimid").unwrap(), 1);
+            assert_eq!(lat.scale_name().unwrap(), "lat");
+            assert_eq!(
+                x.scale_name().unwrap(),
+                "This is a netCDF dimension but not a netCDF variable.         4"
+            );
+            assert_eq!(
+                file.nc_dimensions().unwrap(),
+                vec![("time".to_owned(), 2), ("lat".to_owned(), 3), ("x".to_owned(), 4)]
+            );
+            assert_eq!(lat.nc_dimensions().unwrap(), vec![Some("lat".to_owned())]);
+
+            let group = file.create_group("sub").unwrap();
+            let y = group.create_nc_dimension("y", 4).unwrap();
+            assert_eq!(y.get_attr::<i32>("_Netcdf4Dimid").unwrap(), 3);
+            let data = group.new_dataset::<f64>().create("data", (1, 3, 4)).unwrap();
+            data.set_nc_dimensions(&["time", "lat", "y"]).unwrap();
+            let dims = data.nc_dimensions().unwrap();
+            assert_eq!(dims, vec![Some("time".into()), Some("lat".into()), Some("y".into())]);
+            assert!(data.is_scale_attached(&time, 0).unwrap());
+            data.set_nc_dimensions(&["time", "lat", "x"]).unwrap();
+            assert_eq!(data.nc_dimensions().unwrap()[2], Some("x".into()));
+            assert_eq!(data.num_scales(2).unwrap(), 1);
+            assert_eq!(group.nc_dimensions().unwrap(), vec![("y".to_owned(), 4)]);
+            lat.set_nc_dimensions(&["lat"]).unwrap();
+            assert_eq!(lat.num_scales(0).unwrap(), 0);
+
+            let other = file.new_dataset::<u8>().create("other", (2, 4)).unwrap();
+            assert_eq!(other.nc_dimensions().unwrap(), vec![None, None]);
+            assert_err!(other.set_nc_dimensions(&["lat"]), "expected 2 dimensions");
+            assert_err!(other.set_nc_dimensions(&["time", "y"]), "dimension 'y' not found");
+            assert_err!(
+                other.set_nc_dimensions(&["lat", "x"]),
+                "axis 0 has length 2, netCDF dimension 'lat' has length 3"
+            );
+            assert_err!(other.set_nc_dimensions(&["time", "other"]), "'other' not found");
+
+            let bounded = Extent::new(3).max(20);
+            let z = file.new_dataset::<f32>().create_with_extents("z", bounded).unwrap();
+            super::make_nc_dimension(z, 3).unwrap();
+            assert_err!(
+                other.set_nc_dimensions(&["z", "x"]),
+                "axis 0 has length 2, netCDF dimension 'z' has length 3"
+            );
+        })
+    }
+
+    #[test]
+    pub fn test_nc_coordinates() {
+        with_tmp_file(|file| {
+            let data = file.new_dataset::<f32>().create("data", 2).unwrap();
+            assert!(data.nc_coordinates().unwrap().is_empty());
+            data.set_nc_coordinates(&["lon", "lat"]).unwrap();
+            assert_eq!(data.get_attr_string("coordinates").unwrap(), "lon lat");
+            assert_eq!(data.nc_coordinates().unwrap(), vec!["lon", "lat"]);
+            assert_err!(data.set_nc_coordinates(&["a b"]), "invalid coordinate variable name");
+            let matrix = file.new_dataset::<f32>().create("matrix", (2, 2)).unwrap();
+            assert_err!(matrix.make_nc_coordinate(), "must be one-dimensional");
+        })
+    }
+}
@@ -0,0 +1,247 @@
+//! Packet tables for appending fixed- or variable-length records (requires the `hl`
+//! feature).
+//!
+//! A packet table is a one-dimensional chunked dataset that is only ever appended to,
+//! which makes it suitable for high-rate logging of events. Packets can be read back
+//! sequentially via an index kept by the table, or at arbitrary positions. Variable-length
+//! packets are stored using `VarLenArray` (or variable-length string) packet types.
+
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+use hdf5_sys::h5i::H5Iinc_ref;
+use hdf5_sys::h5pt::{
+    H5PTappend, H5PTclose, H5PTcreate, H5PTcreate_index, H5PTget_dataset, H5PTget_index,
+    H5PTget_next, H5PTget_num_packets, H5PTis_varlen, H5PTopen, H5PTread_packets, H5PTset_index,
+};
+
+use crate::filters::Filters;
+use crate::internal_prelude::*;
+
+/// A packet table with packets of type `T`.
+///
+/// The table is closed when dropped. All handles opened to the same table share the
+/// underlying dataset but keep their own read index.
+pub struct PacketTable<T> {
+    id: hid_t,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Debug for PacketTable<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _e = silence_errors();
+        let mut formatter = f.debug_struct("PacketTable");
+        formatter.field("len", &self.len().unwrap_or(0));
+        formatter.field("index", &self.index().unwrap_or(0));
+        formatter.finish()
+    }
+}
+
+impl<T> Drop for PacketTable<T> {
+    fn drop(&mut self) {
+        h5lock!(H5PTclose(self.id));
+    }
+}
+
+impl<T> PacketTable<T> {
+    fn from_id(id: hid_t) -> Self {
+        Self { id, _marker: PhantomData }
+    }
+
+    /// Returns the underlying dataset.
+    pub fn dataset(&self) -> Result<Dataset> {
+        h5lock!({
+            // the dataset is owned by the packet table, so a new reference is needed
+            let id = h5try!(H5PTget_dataset(self.id));
+            h5try!(H5Iinc_ref(id));
+            Dataset::from_id(id)
+        })
+    }
+
+    /// Returns the number of packets in the table.
+    pub fn len(&self) -> Result<usize> {
+        h5get!(H5PTget_num_packets(self.id): hsize_t).map(|n| n as _)
+    }
+
+    /// Returns `true` if the table contains no packets.
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Returns `true` if the packets are of variable length.
+    pub fn is_varlen(&self) -> bool {
+        h5lock!(H5PTis_varlen(self.id)) > 0
+    }
+
+    /// Returns the index of the next packet to be read by `read_next()`.
+    pub fn index(&self) -> Result<usize> {
+        h5get!(H5PTget_index(self.id): hsize_t).map(|n| n as _)
+    }
+
+    /// Sets the index of the next packet to be read by `read_next()`.
+    pub fn set_index(&self, index: usize) -> Result<()> {
+        h5try!(H5PTset_index(self.id, index as _));
+        Ok(())
+    }
+
+    /// Resets the index of the next packet to be read to the first packet.
+    pub fn reset_index(&self) -> Result<()> {
+        h5try!(H5PTcreate_index(self.id));
+        Ok(())
+    }
+}
+
+impl<T: H5Type> PacketTable<T> {
+    fn check_type(self) -> Result<Self> {
+        let dtype = self.dataset()?.dtype()?.to_descriptor()?;
+        ensure!(
+            dtype == T::type_descriptor(),
+            "packet type mismatch: table has {:?}, requested {:?}",
+            dtype,
+            T::type_descriptor()
+        );
+        Ok(self)
+    }
+
+    /// Appends packets to the end of the table.
+    pub fn append(&self, packets: &[T]) -> Result<()> {
+        if !packets.is_empty() {
+            h5try!(H5PTappend(self.id, packets.len(), packets.as_ptr() as *const _));
+        }
+        Ok(())
+    }
+
+    /// Reads `count` packets starting at the given position.
+    pub fn read(&self, start: usize, count: usize) -> Result<Vec<T>> {
+        let len = self.len()?;
+        ensure!(
+            start.checked_add(count).map_or(false, |end| end <= len),
+            "packets {}..{} are out of bounds for table of {} packets",
+            start,
+            start.saturating_add(count),
+            len
+        );
+        let mut packets = Vec::with_capacity(count);
+        if count > 0 {
+            h5try!(H5PTread_packets(self.id, start as _, count, packets.as_mut_ptr() as *mut _));
+            unsafe { packets.set_len(count) };
+        }
+        Ok(packets)
+    }
+
+    /// Reads all packets of the table.
+    pub fn read_all(&self) -> Result<Vec<T>> {
+        self.read(0, self.len()?)
+    }
+
+    /// Reads up to `count` packets starting at the current index and advances the index
+    /// past them; an empty vector is returned once the end of the table is reached.
+    pub fn read_next(&self, count: usize) -> Result<Vec<T>> {
+        h5lock!({
+            let count = count.min(self.len()?.saturating_sub(self.index()?));
+            let mut packets = Vec::with_capacity(count);
+            if count > 0 {
+                h5try!(H5PTget_next(self.id, count, packets.as_mut_ptr() as *mut _));
+                unsafe { packets.set_len(count) };
+            }
+            Ok(packets)
+        })
+    }
+}
+
+impl Group {
+    /// Creates a new packet table with the given number of packets per chunk.
+    pub fn create_packet_table<T: H5Type>(
+        &self, name: &str, chunk_size: usize,
+    ) -> Result<PacketTable<T>> {
+        self.create_packet_table_with_filters(name, chunk_size, &Filters::default())
+    }
+
+    /// Creates a new packet table whose chunks are processed by the given filters, e.g.
+    /// to compress the packets.
+    pub fn create_packet_table_with_filters<T: H5Type>(
+        &self, name: &str, chunk_size: usize, filters: &Filters,
+    ) -> Result<PacketTable<T>> {
+        ensure!(chunk_size > 0, "chunk size must be positive");
+        let name = to_cstring(name)?;
+        let dtype = Datatype::from_type::<T>()?;
+        h5lock!({
+            let dcpl = filters.to_dcpl(&dtype)?;
+            let id = h5try!(H5PTcreate(
+                self.id(),
+                name.as_ptr(),
+                dtype.id(),
+                chunk_size as _,
+                dcpl.id()
+            ));
+            Ok(PacketTable::from_id(id))
+        })
+    }
+
+    /// Opens an existing packet table; the packet type has to match the stored one.
+    pub fn packet_table<T: H5Type>(&self, name: &str) -> Result<PacketTable<T>> {
+        let name = to_cstring(name)?;
+        h5lock!({
+            let id = h5try!(H5PTopen(self.id(), name.as_ptr()));
+            PacketTable::from_id(id).check_type()
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use hdf5_types::VarLenArray;
+
+    use crate::filters::{gzip_available, Filters};
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_packet_table() {
+        with_tmp_file(|file| {
+            let table = file.create_packet_table::<u32>("events", 4).unwrap();
+            assert!(table.is_empty().unwrap());
+            assert!(!table.is_varlen());
+            table.append(&[1, 2, 3]).unwrap();
+            table.append(&(4..10).collect::<Vec<_>>()).unwrap();
+            assert_eq!(table.len().unwrap(), 9);
+            assert_eq!(table.dataset().unwrap().shape(), vec![9]);
+            drop(table);
+
+            let table = file.packet_table::<u32>("events").unwrap();
+            assert_eq!(table.read_all().unwrap(), (1..10).collect::<Vec<_>>());
+            assert_eq!(table.read(2, 3).unwrap(), vec![3, 4, 5]);
+            assert_eq!(table.read_next(4).unwrap(), vec![1, 2, 3, 4]);
+            assert_eq!(table.index().unwrap(), 4);
+            assert_eq!(table.read_next(10).unwrap(), vec![5, 6, 7, 8, 9]);
+            assert!(table.read_next(1).unwrap().is_empty());
+            table.set_index(7).unwrap();
+            assert_eq!(table.read_next(1).unwrap(), vec![8]);
+            table.reset_index().unwrap();
+            assert_eq!(table.read_next(1).unwrap(), vec![1]);
+
+            assert_err!(table.read(8, 2), "out of bounds");
+            assert_err!(file.packet_table::<u64>("events"), "packet type mismatch");
+            assert_err!(file.create_packet_table::<u8>("x", 0), "chunk size must be positive");
+        })
+    }
+
+    #[test]
+    pub fn test_packet_table_varlen() {
+        with_tmp_file(|file| {
+            let mut filters = Filters::new();
+            if gzip_available() {
+                filters.gzip(4);
+            }
+            let table = file
+                .create_packet_table_with_filters::<VarLenArray<u16>>("packets", 16, &filters)
+                .unwrap();
+            assert!(table.is_varlen());
+            let packets: Vec<VarLenArray<u16>> =
+                (0..5).map(|n| (0..n).collect::<Vec<u16>>().into()).collect();
+            table.append(&packets).unwrap();
+            assert_eq!(table.read_all().unwrap(), packets);
+            assert_eq!(table.read_next(2).unwrap(), &packets[..2]);
+            assert_eq!(table.dataset().unwrap().filters(), filters);
+        })
+    }
+}
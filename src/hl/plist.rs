@@ -4,14 +4,47 @@ use std::ptr;
 use std::str::FromStr;
 
 use hdf5_sys::h5p::{
-    H5Pcopy, H5Pequal, H5Pexist, H5Pget_class, H5Pget_class_name, H5Pget_nprops, H5Piterate,
+    H5Pcopy, H5Pequal, H5Pexist, H5Pget, H5Pget_class, H5Pget_class_name, H5Pget_nprops,
+    H5Pget_size, H5Piterate, H5Pset,
 };
+#[cfg(hdf5_1_10_0)]
+use hdf5_sys::h5p::{H5Pdecode, H5Pencode};
 
 use crate::internal_prelude::*;
 
+use self::{
+    attribute_create::AttributeCreate, dataset_access::DatasetAccess,
+    dataset_create::DatasetCreate, dataset_transfer::DatasetTransfer,
+    datatype_access::DatatypeAccess, datatype_create::DatatypeCreate, file_access::FileAccess,
+    file_create::FileCreate, group_create::GroupCreate, link_create::LinkCreate,
+    object_copy::ObjectCopy,
+};
+
+/// Implements `decode()` for a typed property list.
+macro_rules! impl_decode {
+    ($ty:ident) => {
+        impl $ty {
+            /// Decodes a property list serialized via `encode()`, failing if it is of a
+            /// different class.
+            #[cfg(hdf5_1_10_0)]
+            pub fn decode(buf: &[u8]) -> Result<Self> {
+                $crate::hl::plist::decode_plist(buf)
+            }
+        }
+    };
+}
+
+pub mod attribute_create;
 pub mod dataset_access;
+pub mod dataset_create;
+pub mod dataset_transfer;
+pub mod datatype_access;
+pub mod datatype_create;
 pub mod file_access;
 pub mod file_create;
+pub mod group_create;
+pub mod link_create;
+pub mod object_copy;
 
 /// Represents the HDF5 property list.
 #[repr(transparent)]
@@ -151,11 +184,47 @@ impl FromStr for PropertyListClass {
     }
 }
 
+/// Debug representation of a raw property value as a list of hex bytes.
+struct RawValue(Vec<u8>);
+
+impl Debug for RawValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes: Vec<_> = self.0.iter().map(|b| format!("{:02x}", b)).collect();
+        write!(f, "[{}]", bytes.join(" "))
+    }
+}
+
+/// Debug representation of the raw values of all properties in a property list.
+struct RawProperties<'a>(&'a PropertyList, PropertyListClass);
+
+impl Debug for RawProperties<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _e = silence_errors();
+        let name = format!("{:?}", self.1);
+        let mut formatter = f.debug_struct(&name);
+        for property in self.0.property_names() {
+            match self.0.get_raw(&property) {
+                Ok(value) => formatter.field(&property, &RawValue(value)),
+                Err(err) => formatter.field(&property, &err),
+            };
+        }
+        formatter.finish()
+    }
+}
+
 #[allow(clippy::len_without_is_empty)]
 impl PropertyList {
     /// Copies the property list.
     pub fn copy(&self) -> Self {
-        Self::from_id(h5lock!(H5Pcopy(self.id()))).unwrap_or_else(|_| Self::invalid())
+        self.try_clone().unwrap_or_else(|_| Self::invalid())
+    }
+
+    /// Copies the property list, returning an error if it cannot be copied.
+    ///
+    /// Unlike `clone()`, which shares the underlying property list, this produces an
+    /// independent property list that can be modified without affecting the original.
+    pub fn try_clone(&self) -> Result<Self> {
+        Self::from_id(h5try!(H5Pcopy(self.id())))
     }
 
     /// Queries whether a property name exists in the property list.
@@ -166,24 +235,101 @@ impl PropertyList {
             .map_or(false, |r| r > 0)
     }
 
-    /// Iterates over properties in the property list, returning their names.
+    /// An alias for `property_names()`.
     pub fn properties(&self) -> Vec<String> {
+        self.property_names()
+    }
+
+    /// Iterates over properties in the property list, returning their names.
+    ///
+    /// This includes properties that have no typed accessors (e.g. those registered by
+    /// VOL connectors or newer library versions), which can be accessed via `get_raw()`.
+    pub fn property_names(&self) -> Vec<String> {
         extern "C" fn callback(_: hid_t, name: *const c_char, data: *mut c_void) -> herr_t {
-            let data = unsafe { &mut *(data as *mut Vec<String>) };
-            let name = string_from_cstr(name);
-            if !name.is_empty() {
-                data.push(name);
-            }
-            0
+            catch_callback_panic(-1, || {
+                let data = unsafe { &mut *(data as *mut Vec<String>) };
+                let name = string_from_cstr(name);
+                if !name.is_empty() {
+                    data.push(name);
+                }
+                0
+            })
         }
 
         let mut data = Vec::new();
         let data_ptr: *mut c_void = &mut data as *mut _ as *mut _;
 
-        h5lock!(H5Piterate(self.id(), ptr::null_mut(), Some(callback), data_ptr));
+        let _ = h5call!(H5Piterate(self.id(), ptr::null_mut(), Some(callback), data_ptr));
         data
     }
 
+    /// Returns the size in bytes of the value of a property.
+    pub fn property_size(&self, property: &str) -> Result<usize> {
+        let property = to_cstring(property)?;
+        let mut size: size_t = 0;
+        h5try!(H5Pget_size(self.id(), property.as_ptr(), &mut size));
+        Ok(size as _)
+    }
+
+    /// Returns the raw bytes of the value of a property, as stored in the property list.
+    ///
+    /// Note that values of some properties hold pointers, in which case the returned
+    /// bytes are only meaningful to the library.
+    pub fn get_raw(&self, property: &str) -> Result<Vec<u8>> {
+        let size = self.property_size(property)?;
+        let property = to_cstring(property)?;
+        let mut buf = vec![0_u8; size];
+        h5try!(H5Pget(self.id(), property.as_ptr(), buf.as_mut_ptr() as *mut _));
+        Ok(buf)
+    }
+
+    /// Sets the value of a property from raw bytes, which must match the property size.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must form a valid value of the property; in particular, properties
+    /// holding pointers must not be set this way unless the pointers are valid.
+    pub unsafe fn set_raw(&self, property: &str, value: &[u8]) -> Result<()> {
+        let size = self.property_size(property)?;
+        ensure!(
+            value.len() == size,
+            "invalid size of property {:?}: expected {}, got {}",
+            property,
+            size,
+            value.len()
+        );
+        let property = to_cstring(property)?;
+        let mut buf = value.to_vec();
+        h5try!(H5Pset(self.id(), property.as_ptr(), buf.as_mut_ptr() as *mut _));
+        Ok(())
+    }
+
+    /// Serializes the property list into a buffer, which can be decoded later via
+    /// `decode()` (possibly in a different process).
+    ///
+    /// Properties that have no serialized form (e.g. file drivers other than the
+    /// default ones, or callbacks) are not preserved.
+    #[cfg(hdf5_1_10_0)]
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        h5lock!({
+            let mut size: size_t = 0;
+            h5try!(H5Pencode(self.id(), ptr::null_mut(), &mut size));
+            let mut buf = vec![0_u8; size];
+            h5try!(H5Pencode(self.id(), buf.as_mut_ptr() as *mut _, &mut size));
+            buf.truncate(size);
+            Ok(buf)
+        })
+    }
+
+    /// Decodes a property list serialized via `encode()`.
+    ///
+    /// Typed property lists provide their own `decode()` methods which also check
+    /// the property list class, e.g. `FileAccess::decode()`.
+    #[cfg(hdf5_1_10_0)]
+    pub fn decode(buf: &[u8]) -> Result<Self> {
+        decode_plist(buf)
+    }
+
     /// Returns the current number of properties in the property list.
     pub fn len(&self) -> usize {
         h5get_d!(H5Pget_nprops(self.id()): size_t)
@@ -202,10 +348,55 @@ impl PropertyList {
             PropertyListClass::from_str(&name)
         })
     }
+
+    /// Returns true if the property list is of the given class.
+    pub fn is_a(&self, class: PropertyListClass) -> bool {
+        self.class().ok() == Some(class)
+    }
+
+    /// Returns a detailed multi-line description of the property list, listing every
+    /// property along with its value.
+    ///
+    /// Property lists of classes that have typed wrappers (e.g. `FileAccess`) are
+    /// described in terms of their decoded values, same as their `Debug` output; for
+    /// other classes, raw bytes of all properties are listed instead (see `get_raw()`).
+    pub fn describe(&self) -> String {
+        use self::PropertyListClass as C;
+
+        let class = match self.class() {
+            Ok(class) => class,
+            Err(_) => return format!("{:?}", self),
+        };
+        unsafe {
+            match class {
+                C::AttributeCreate => format!("{:#?}", self.transmute::<AttributeCreate>()),
+                C::DatasetAccess => format!("{:#?}", self.transmute::<DatasetAccess>()),
+                C::DatasetCreate => format!("{:#?}", self.transmute::<DatasetCreate>()),
+                C::DataTransfer => format!("{:#?}", self.transmute::<DatasetTransfer>()),
+                C::DatatypeAccess => format!("{:#?}", self.transmute::<DatatypeAccess>()),
+                C::DatatypeCreate => format!("{:#?}", self.transmute::<DatatypeCreate>()),
+                C::FileAccess => format!("{:#?}", self.transmute::<FileAccess>()),
+                C::FileCreate => format!("{:#?}", self.transmute::<FileCreate>()),
+                C::GroupCreate => format!("{:#?}", self.transmute::<GroupCreate>()),
+                C::LinkCreate => format!("{:#?}", self.transmute::<LinkCreate>()),
+                C::ObjectCopy => format!("{:#?}", self.transmute::<ObjectCopy>()),
+                _ => format!("{:#?}", RawProperties(self, class)),
+            }
+        }
+    }
+}
+
+#[cfg(hdf5_1_10_0)]
+pub(crate) fn decode_plist<T: ObjectClass>(buf: &[u8]) -> Result<T> {
+    // the encoded form starts with the encoding version and the property list class
+    ensure!(buf.len() >= 2, "invalid encoded property list: too short");
+    T::from_id(h5try!(H5Pdecode(buf.as_ptr() as *const _)))
 }
 
 #[cfg(test)]
 pub mod tests {
+    use std::mem;
+
     use hdf5_sys::h5p::H5Pcreate;
 
     use crate::globals::{H5P_FILE_ACCESS, H5P_FILE_CREATE};
@@ -224,6 +415,9 @@ pub mod tests {
         let (fapl, fcpl) = make_plists();
         assert_eq!(fapl.class().unwrap(), PropertyListClass::FileAccess);
         assert_eq!(fcpl.class().unwrap(), PropertyListClass::FileCreate);
+        assert!(fapl.is_a(PropertyListClass::FileAccess));
+        assert!(!fapl.is_a(PropertyListClass::FileCreate));
+        assert!(!PropertyList::invalid().is_a(PropertyListClass::FileAccess));
     }
 
     #[test]
@@ -253,6 +447,70 @@ pub mod tests {
         assert_eq!(fapl_c.refcount(), 1);
         assert_eq!(fapl, fapl_c);
         assert_ne!(fapl.id(), fapl_c.id());
+
+        let fapl_c = fapl.try_clone().unwrap();
+        assert_eq!(fapl, fapl_c);
+        assert_ne!(fapl.id(), fapl_c.id());
+        assert_err!(PropertyList::invalid().try_clone(), "not property object");
+    }
+
+    #[test]
+    pub fn test_raw_properties() {
+        use crate::hl::plist::file_access::FileAccess;
+
+        let fapl = FileAccess::try_new().unwrap();
+        assert!(fapl.property_names().contains(&"sieve_buf_size".to_owned()));
+        assert_eq!(fapl.property_size("sieve_buf_size").unwrap(), mem::size_of::<size_t>());
+        let raw = fapl.get_raw("sieve_buf_size").unwrap();
+        assert_eq!(raw, fapl.sieve_buf_size().to_ne_bytes());
+
+        unsafe { fapl.set_raw("sieve_buf_size", &4096_usize.to_ne_bytes()).unwrap() };
+        assert_eq!(fapl.sieve_buf_size(), 4096);
+        assert_err!(
+            unsafe { fapl.set_raw("sieve_buf_size", &[0_u8]) },
+            "invalid size of property \"sieve_buf_size\""
+        );
+        assert_err!(fapl.get_raw("foo"), "can't find property");
+    }
+
+    #[test]
+    #[cfg(hdf5_1_10_0)]
+    pub fn test_encode_decode() {
+        use crate::hl::plist::file_access::FileAccess;
+        use crate::hl::plist::file_create::FileCreate;
+
+        let fapl = FileAccess::build().sieve_buf_size(4096).finish().unwrap();
+        let buf = fapl.encode().unwrap();
+        let decoded = FileAccess::decode(&buf).unwrap();
+        assert_eq!(decoded, fapl);
+        assert_eq!(decoded.sieve_buf_size(), 4096);
+        assert_eq!(
+            PropertyList::decode(&buf).unwrap().class().unwrap(),
+            PropertyListClass::FileAccess
+        );
+
+        assert_err!(FileCreate::decode(&buf), "expected file create property list");
+        assert_err!(FileAccess::decode(&buf[..1]), "invalid encoded property list: too short");
+    }
+
+    #[test]
+    pub fn test_describe() {
+        use crate::globals::H5P_LINK_ACCESS;
+        use crate::hl::plist::file_access::FileAccess;
+
+        let fapl = FileAccess::build().sieve_buf_size(4096).finish().unwrap();
+        let desc = fapl.describe();
+        assert!(desc.starts_with("FileAccess {\n"));
+        assert!(desc.contains("    sieve_buf_size: 4096,\n"));
+        assert_eq!(desc, format!("{:#?}", fapl));
+
+        let lapl = PropertyList::from_id(h5call!(H5Pcreate(*H5P_LINK_ACCESS)).unwrap()).unwrap();
+        let desc = lapl.describe();
+        assert!(desc.starts_with("LinkAccess {\n"));
+        let nlinks: Vec<_> = 16_usize.to_ne_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        assert!(desc.contains(&format!("    max soft links: [{}],\n", nlinks.join(" "))));
+
+        assert_eq!(PropertyList::invalid().describe(), "<HDF5 property list: invalid id>");
     }
 
     #[test]
@@ -0,0 +1,168 @@
+//! Attribute creation properties.
+
+use std::fmt::{self, Debug};
+use std::ops::Deref;
+
+use hdf5_sys::h5p::{H5Pcreate, H5Pget_char_encoding, H5Pset_char_encoding};
+use hdf5_sys::h5t::H5T_cset_t;
+
+use crate::globals::H5P_ATTRIBUTE_CREATE;
+use crate::internal_prelude::*;
+
+/// Attribute creation properties.
+#[repr(transparent)]
+pub struct AttributeCreate(Handle);
+
+impl ObjectClass for AttributeCreate {
+    const NAME: &'static str = "attribute create property list";
+    const VALID_TYPES: &'static [H5I_type_t] = &[H5I_GENPROP_LST];
+
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+
+    fn validate(&self) -> Result<()> {
+        let class = self.class()?;
+        if class != PropertyListClass::AttributeCreate {
+            fail!("expected attribute create property list, got {:?}", class);
+        }
+        Ok(())
+    }
+}
+
+impl Debug for AttributeCreate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _e = silence_errors();
+        let mut formatter = f.debug_struct("AttributeCreate");
+        formatter.field("char_encoding", &self.char_encoding());
+        formatter.finish()
+    }
+}
+
+impl Deref for AttributeCreate {
+    type Target = PropertyList;
+
+    fn deref(&self) -> &PropertyList {
+        unsafe { self.transmute() }
+    }
+}
+
+impl PartialEq for AttributeCreate {
+    fn eq(&self, other: &Self) -> bool {
+        <PropertyList as PartialEq>::eq(self, other)
+    }
+}
+
+impl Eq for AttributeCreate {}
+
+impl Clone for AttributeCreate {
+    fn clone(&self) -> Self {
+        unsafe { self.deref().clone().cast() }
+    }
+}
+
+/// Character encoding used for object names.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharEncoding {
+    Ascii,
+    Utf8,
+}
+
+impl Default for CharEncoding {
+    fn default() -> Self {
+        Self::Ascii
+    }
+}
+
+impl From<H5T_cset_t> for CharEncoding {
+    fn from(cset: H5T_cset_t) -> Self {
+        match cset {
+            H5T_cset_t::H5T_CSET_UTF8 => Self::Utf8,
+            _ => Self::Ascii,
+        }
+    }
+}
+
+impl From<CharEncoding> for H5T_cset_t {
+    fn from(encoding: CharEncoding) -> Self {
+        match encoding {
+            CharEncoding::Ascii => Self::H5T_CSET_ASCII,
+            CharEncoding::Utf8 => Self::H5T_CSET_UTF8,
+        }
+    }
+}
+
+/// Builder used to create attribute creation property list.
+#[derive(Clone, Debug, Default)]
+pub struct AttributeCreateBuilder {
+    char_encoding: Option<CharEncoding>,
+}
+
+impl AttributeCreateBuilder {
+    /// Creates a new attribute creation property list builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new builder from an existing property list.
+    pub fn from_plist(plist: &AttributeCreate) -> Result<Self> {
+        let mut builder = Self::default();
+        builder.char_encoding(plist.get_char_encoding()?);
+        Ok(builder)
+    }
+
+    /// Sets the character encoding used for the attribute name.
+    ///
+    /// Attribute names containing non-ASCII characters should be created with UTF-8
+    /// encoding so that other applications (e.g. h5py) decode them correctly.
+    pub fn char_encoding(&mut self, encoding: CharEncoding) -> &mut Self {
+        self.char_encoding = Some(encoding);
+        self
+    }
+
+    fn populate_plist(&self, id: hid_t) -> Result<()> {
+        if let Some(v) = self.char_encoding {
+            h5try!(H5Pset_char_encoding(id, v.into()));
+        }
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<AttributeCreate> {
+        h5lock!({
+            let plist = AttributeCreate::try_new()?;
+            self.populate_plist(plist.id())?;
+            Ok(plist)
+        })
+    }
+}
+
+/// Attribute creation property list.
+impl AttributeCreate {
+    pub fn try_new() -> Result<Self> {
+        Self::from_id(h5try!(H5Pcreate(*H5P_ATTRIBUTE_CREATE)))
+    }
+
+    pub fn copy(&self) -> Self {
+        unsafe { self.deref().copy().cast() }
+    }
+
+    pub fn build() -> AttributeCreateBuilder {
+        AttributeCreateBuilder::new()
+    }
+
+    #[doc(hidden)]
+    pub fn get_char_encoding(&self) -> Result<CharEncoding> {
+        h5get!(H5Pget_char_encoding(self.id()): H5T_cset_t).map(Into::into)
+    }
+
+    /// Retrieves the character encoding used for the attribute name.
+    pub fn char_encoding(&self) -> CharEncoding {
+        self.get_char_encoding().unwrap_or_else(|_| CharEncoding::default())
+    }
+}
+
+impl_decode!(AttributeCreate);
@@ -13,6 +13,8 @@ use hdf5_sys::h5p::{H5Pcreate, H5Pget_chunk_cache, H5Pset_chunk_cache};
 use hdf5_sys::h5p::{H5Pget_all_coll_metadata_ops, H5Pset_all_coll_metadata_ops};
 #[cfg(hdf5_1_8_17)]
 use hdf5_sys::h5p::{H5Pget_efile_prefix, H5Pset_efile_prefix};
+#[cfg(hdf5_1_10_2)]
+use hdf5_sys::h5p::{H5Pget_virtual_prefix, H5Pset_virtual_prefix};
 #[cfg(hdf5_1_10_0)]
 use hdf5_sys::{
     h5d::H5D_vds_view_t,
@@ -63,6 +65,8 @@ impl Debug for DatasetAccess {
             formatter.field("virtual_view", &self.virtual_view());
             formatter.field("virtual_printf_gap", &self.virtual_printf_gap());
         }
+        #[cfg(hdf5_1_10_2)]
+        formatter.field("virtual_prefix", &self.virtual_prefix());
         #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
         formatter.field("all_coll_metadata_ops", &self.all_coll_metadata_ops());
         formatter.finish()
@@ -135,6 +139,8 @@ pub struct DatasetAccessBuilder {
     virtual_view: Option<VirtualView>,
     #[cfg(hdf5_1_10_0)]
     virtual_printf_gap: Option<usize>,
+    #[cfg(hdf5_1_10_2)]
+    virtual_prefix: Option<String>,
     #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
     all_coll_metadata_ops: Option<bool>,
 }
@@ -160,6 +166,11 @@ impl DatasetAccessBuilder {
             builder.virtual_view(plist.get_virtual_view()?);
             builder.virtual_printf_gap(plist.get_virtual_printf_gap()?);
         }
+        #[cfg(hdf5_1_10_2)]
+        {
+            let v = plist.get_virtual_prefix()?;
+            builder.virtual_prefix(&v);
+        }
         #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
         builder.all_coll_metadata_ops(plist.get_all_coll_metadata_ops()?);
         Ok(builder)
@@ -188,6 +199,15 @@ impl DatasetAccessBuilder {
         self
     }
 
+    /// Sets the prefix prepended to relative paths of the source files of a virtual
+    /// dataset; `${ORIGIN}` expands to the directory of the file holding the dataset.
+    #[cfg(hdf5_1_10_2)]
+    pub fn virtual_prefix(&mut self, prefix: &str) -> &mut Self {
+        self.virtual_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets whether metadata reads when opening the dataset are collective.
     #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
     pub fn all_coll_metadata_ops(&mut self, is_collective: bool) -> &mut Self {
         self.all_coll_metadata_ops = Some(is_collective);
@@ -214,6 +234,13 @@ impl DatasetAccessBuilder {
                 h5try!(H5Pset_virtual_printf_gap(id, v as _));
             }
         }
+        #[cfg(hdf5_1_10_2)]
+        {
+            if let Some(ref v) = self.virtual_prefix {
+                let v = to_cstring(v.as_ref())?;
+                h5try!(H5Pset_virtual_prefix(id, v.as_ptr()));
+            }
+        }
         #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
         {
             if let Some(v) = self.all_coll_metadata_ops {
@@ -294,6 +321,17 @@ impl DatasetAccess {
         self.get_virtual_printf_gap().unwrap_or(0)
     }
 
+    #[cfg(hdf5_1_10_2)]
+    #[doc(hidden)]
+    pub fn get_virtual_prefix(&self) -> Result<String> {
+        h5lock!(get_h5_str(|m, s| H5Pget_virtual_prefix(self.id(), m, s)))
+    }
+
+    #[cfg(hdf5_1_10_2)]
+    pub fn virtual_prefix(&self) -> String {
+        self.get_virtual_prefix().ok().unwrap_or_else(|| "".into())
+    }
+
     #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
     #[doc(hidden)]
     pub fn get_all_coll_metadata_ops(&self) -> Result<bool> {
@@ -305,3 +343,5 @@ impl DatasetAccess {
         self.get_all_coll_metadata_ops().unwrap_or(false)
     }
 }
+
+impl_decode!(DatasetAccess);
@@ -0,0 +1,190 @@
+//! Dataset creation properties.
+
+use std::fmt::{self, Debug};
+use std::ops::Deref;
+
+use hdf5_sys::h5d::H5D_layout_t;
+#[cfg(hdf5_1_10_5)]
+use hdf5_sys::h5p::H5Pget_dset_no_attrs_hint;
+use hdf5_sys::h5p::{H5Pcreate, H5Pget_chunk, H5Pget_layout, H5Pget_obj_track_times};
+use hdf5_sys::h5s::H5S_MAX_RANK;
+
+use super::file_create::AttrPhaseChangeInfo;
+use crate::filters::Filters;
+use crate::globals::H5P_DATASET_CREATE;
+use crate::internal_prelude::*;
+
+/// Dataset creation properties.
+///
+/// Dataset creation properties are set via `DatasetBuilder`; this wrapper allows
+/// inspecting them, e.g. as returned by `Dataset::create_plist()`, and passing them on
+/// to `DatasetBuilder::set_create_plist()`.
+#[repr(transparent)]
+pub struct DatasetCreate(Handle);
+
+impl ObjectClass for DatasetCreate {
+    const NAME: &'static str = "dataset create property list";
+    const VALID_TYPES: &'static [H5I_type_t] = &[H5I_GENPROP_LST];
+
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+
+    fn validate(&self) -> Result<()> {
+        let class = self.class()?;
+        if class != PropertyListClass::DatasetCreate {
+            fail!("expected dataset create property list, got {:?}", class);
+        }
+        Ok(())
+    }
+}
+
+impl Debug for DatasetCreate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _e = silence_errors();
+        let mut formatter = f.debug_struct("DatasetCreate");
+        formatter.field("layout", &self.layout());
+        formatter.field("chunk", &self.chunk());
+        formatter.field("filters", &self.filters());
+        formatter.field("obj_track_times", &self.obj_track_times());
+        formatter.field("attr_phase_change", &self.attr_phase_change());
+        #[cfg(hdf5_1_10_5)]
+        formatter.field("minimize_header", &self.minimize_header());
+        formatter.finish()
+    }
+}
+
+impl Deref for DatasetCreate {
+    type Target = PropertyList;
+
+    fn deref(&self) -> &PropertyList {
+        unsafe { self.transmute() }
+    }
+}
+
+impl PartialEq for DatasetCreate {
+    fn eq(&self, other: &Self) -> bool {
+        <PropertyList as PartialEq>::eq(self, other)
+    }
+}
+
+impl Eq for DatasetCreate {}
+
+impl Clone for DatasetCreate {
+    fn clone(&self) -> Self {
+        unsafe { self.deref().clone().cast() }
+    }
+}
+
+/// Storage layout of the raw data of a dataset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+    /// Raw data is stored in the object header.
+    Compact,
+    /// Raw data is stored contiguously in the file.
+    Contiguous,
+    /// Raw data is stored in separately allocated chunks.
+    Chunked,
+    /// Raw data is mapped from other datasets.
+    #[cfg(hdf5_1_10_0)]
+    Virtual,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self::Contiguous
+    }
+}
+
+impl Layout {
+    fn from_raw(layout: H5D_layout_t) -> Result<Self> {
+        Ok(match layout {
+            H5D_layout_t::H5D_COMPACT => Self::Compact,
+            H5D_layout_t::H5D_CONTIGUOUS => Self::Contiguous,
+            H5D_layout_t::H5D_CHUNKED => Self::Chunked,
+            #[cfg(hdf5_1_10_0)]
+            H5D_layout_t::H5D_VIRTUAL => Self::Virtual,
+            _ => fail!("invalid dataset layout: {:?}", layout),
+        })
+    }
+}
+
+/// Dataset creation property list.
+impl DatasetCreate {
+    pub fn try_new() -> Result<Self> {
+        Self::from_id(h5try!(H5Pcreate(*H5P_DATASET_CREATE)))
+    }
+
+    pub fn copy(&self) -> Self {
+        unsafe { self.deref().copy().cast() }
+    }
+
+    #[doc(hidden)]
+    pub fn get_layout(&self) -> Result<Layout> {
+        Layout::from_raw(h5lock!(H5Pget_layout(self.id())))
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.get_layout().unwrap_or_else(|_| Layout::default())
+    }
+
+    #[doc(hidden)]
+    pub fn get_chunk(&self) -> Result<Option<Vec<Ix>>> {
+        if self.get_layout()? != Layout::Chunked {
+            return Ok(None);
+        }
+        let mut dims: Vec<hsize_t> = vec![0; H5S_MAX_RANK as _];
+        let ndim = h5try!(H5Pget_chunk(self.id(), H5S_MAX_RANK as _, dims.as_mut_ptr()));
+        Ok(Some(dims[..ndim as _].iter().map(|&x| x as _).collect()))
+    }
+
+    /// Returns the chunk shape if the layout is chunked.
+    pub fn chunk(&self) -> Option<Vec<Ix>> {
+        self.get_chunk().unwrap_or(None)
+    }
+
+    #[doc(hidden)]
+    pub fn get_filters(&self) -> Result<Filters> {
+        Filters::from_dcpl(self)
+    }
+
+    pub fn filters(&self) -> Filters {
+        self.get_filters().unwrap_or_else(|_| Filters::default())
+    }
+
+    #[doc(hidden)]
+    pub fn get_obj_track_times(&self) -> Result<bool> {
+        h5get!(H5Pget_obj_track_times(self.id()): hbool_t).map(|x| x > 0)
+    }
+
+    pub fn obj_track_times(&self) -> bool {
+        self.get_obj_track_times().unwrap_or(true)
+    }
+
+    #[doc(hidden)]
+    pub fn get_attr_phase_change(&self) -> Result<AttrPhaseChangeInfo> {
+        AttrPhaseChangeInfo::get(self.id())
+    }
+
+    pub fn attr_phase_change(&self) -> AttrPhaseChangeInfo {
+        self.get_attr_phase_change().unwrap_or_else(|_| AttrPhaseChangeInfo::default())
+    }
+
+    #[cfg(hdf5_1_10_5)]
+    #[doc(hidden)]
+    pub fn get_minimize_header(&self) -> Result<bool> {
+        h5get!(H5Pget_dset_no_attrs_hint(self.id()): hbool_t).map(|x| x > 0)
+    }
+
+    /// Returns `true` if the dataset object header is created minimized.
+    #[cfg(hdf5_1_10_5)]
+    pub fn minimize_header(&self) -> bool {
+        self.get_minimize_header().unwrap_or(false)
+    }
+}
+
+impl_decode!(DatasetCreate);
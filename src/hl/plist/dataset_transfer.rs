@@ -0,0 +1,294 @@
+//! Dataset transfer properties.
+
+/*
+Not implemented:
+- H5P{set,get}_data_transform
+- H5P{set,get}_btree_ratios
+- H5P{set,get}_vlen_mem_manager, H5Pset_filter_callback (due to having to deal with raw C
+  extern callbacks; the conversion exception callback is only exposed as an abort switch)
+- H5Pset_dxpl_mpio_{collective_opt,chunk_opt,chunk_opt_num,chunk_opt_ratio}
+*/
+
+use std::fmt::{self, Debug};
+use std::ops::Deref;
+use std::ptr;
+
+#[cfg(h5_have_parallel)]
+use hdf5_sys::h5p::{H5FD_mpio_xfer_t, H5Pget_dxpl_mpio, H5Pset_dxpl_mpio};
+use hdf5_sys::h5p::{
+    H5Pcreate, H5Pget_buffer, H5Pget_edc_check, H5Pget_hyper_vector_size, H5Pget_type_conv_cb,
+    H5Pset_buffer, H5Pset_edc_check, H5Pset_hyper_vector_size, H5Pset_type_conv_cb,
+};
+use hdf5_sys::h5t::{H5T_conv_except_func_t, H5T_conv_except_t, H5T_conv_ret_t};
+use hdf5_sys::h5z::H5Z_EDC_t;
+
+use crate::globals::H5P_DATASET_XFER;
+use crate::internal_prelude::*;
+
+/// Dataset transfer properties.
+#[repr(transparent)]
+pub struct DatasetTransfer(Handle);
+
+impl ObjectClass for DatasetTransfer {
+    const NAME: &'static str = "dataset transfer property list";
+    const VALID_TYPES: &'static [H5I_type_t] = &[H5I_GENPROP_LST];
+
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+
+    fn validate(&self) -> Result<()> {
+        let class = self.class()?;
+        if class != PropertyListClass::DataTransfer {
+            fail!("expected dataset transfer property list, got {:?}", class);
+        }
+        Ok(())
+    }
+}
+
+impl Debug for DatasetTransfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _e = silence_errors();
+        let mut formatter = f.debug_struct("DatasetTransfer");
+        formatter.field("buffer_size", &self.buffer_size());
+        formatter.field("hyper_vector_size", &self.hyper_vector_size());
+        formatter.field("edc_check", &self.edc_check());
+        formatter.field("abort_on_conv_exception", &self.abort_on_conv_exception());
+        #[cfg(h5_have_parallel)]
+        formatter.field("mpio_transfer_mode", &self.mpio_transfer_mode());
+        formatter.finish()
+    }
+}
+
+impl Deref for DatasetTransfer {
+    type Target = PropertyList;
+
+    fn deref(&self) -> &PropertyList {
+        unsafe { self.transmute() }
+    }
+}
+
+impl PartialEq for DatasetTransfer {
+    fn eq(&self, other: &Self) -> bool {
+        <PropertyList as PartialEq>::eq(self, other)
+    }
+}
+
+impl Eq for DatasetTransfer {}
+
+impl Clone for DatasetTransfer {
+    fn clone(&self) -> Self {
+        unsafe { self.deref().clone().cast() }
+    }
+}
+
+/// MPI-IO transfer mode.
+#[cfg(h5_have_parallel)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TransferMode {
+    Independent,
+    Collective,
+}
+
+#[cfg(h5_have_parallel)]
+impl Default for TransferMode {
+    fn default() -> Self {
+        TransferMode::Independent
+    }
+}
+
+#[cfg(h5_have_parallel)]
+impl From<H5FD_mpio_xfer_t> for TransferMode {
+    fn from(mode: H5FD_mpio_xfer_t) -> Self {
+        match mode {
+            H5FD_mpio_xfer_t::H5FD_MPIO_COLLECTIVE => TransferMode::Collective,
+            _ => TransferMode::Independent,
+        }
+    }
+}
+
+#[cfg(h5_have_parallel)]
+impl From<TransferMode> for H5FD_mpio_xfer_t {
+    fn from(mode: TransferMode) -> Self {
+        match mode {
+            TransferMode::Collective => H5FD_mpio_xfer_t::H5FD_MPIO_COLLECTIVE,
+            _ => H5FD_mpio_xfer_t::H5FD_MPIO_INDEPENDENT,
+        }
+    }
+}
+
+extern "C" fn abort_conv_exception(
+    _except_type: H5T_conv_except_t, _src_id: hid_t, _dst_id: hid_t, _src_buf: *mut c_void,
+    _dst_buf: *mut c_void, _user_data: *mut c_void,
+) -> H5T_conv_ret_t {
+    H5T_conv_ret_t::H5T_CONV_ABORT
+}
+
+/// Builder used to create dataset transfer property list.
+#[derive(Clone, Debug, Default)]
+pub struct DatasetTransferBuilder {
+    buffer_size: Option<usize>,
+    hyper_vector_size: Option<usize>,
+    edc_check: Option<bool>,
+    abort_on_conv_exception: Option<bool>,
+    #[cfg(h5_have_parallel)]
+    mpio_transfer_mode: Option<TransferMode>,
+}
+
+impl DatasetTransferBuilder {
+    /// Creates a new dataset transfer property list builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new builder from an existing property list.
+    pub fn from_plist(plist: &DatasetTransfer) -> Result<Self> {
+        let mut builder = Self::default();
+        builder.buffer_size(plist.get_buffer_size()?);
+        builder.hyper_vector_size(plist.get_hyper_vector_size()?);
+        builder.edc_check(plist.get_edc_check()?);
+        builder.abort_on_conv_exception(plist.get_abort_on_conv_exception()?);
+        #[cfg(h5_have_parallel)]
+        builder.mpio_transfer_mode(plist.get_mpio_transfer_mode()?);
+        Ok(builder)
+    }
+
+    /// Sets the size in bytes of the type conversion and background buffers.
+    pub fn buffer_size(&mut self, size: usize) -> &mut Self {
+        self.buffer_size = Some(size);
+        self
+    }
+
+    /// Sets the number of I/O vectors used when transferring hyperslab selections.
+    pub fn hyper_vector_size(&mut self, size: usize) -> &mut Self {
+        self.hyper_vector_size = Some(size);
+        self
+    }
+
+    /// Sets whether checksums (e.g. Fletcher32) are verified when reading.
+    pub fn edc_check(&mut self, check: bool) -> &mut Self {
+        self.edc_check = Some(check);
+        self
+    }
+
+    /// Sets whether type conversion exceptions (overflows, truncation, NaN and
+    /// infinities converted to integers, etc.) abort the transfer with an error,
+    /// instead of being handled by the library's default clamping.
+    pub fn abort_on_conv_exception(&mut self, abort: bool) -> &mut Self {
+        self.abort_on_conv_exception = Some(abort);
+        self
+    }
+
+    /// Sets the MPI-IO transfer mode.
+    #[cfg(h5_have_parallel)]
+    pub fn mpio_transfer_mode(&mut self, mode: TransferMode) -> &mut Self {
+        self.mpio_transfer_mode = Some(mode);
+        self
+    }
+
+    fn populate_plist(&self, id: hid_t) -> Result<()> {
+        if let Some(v) = self.buffer_size {
+            h5try!(H5Pset_buffer(id, v as _, ptr::null_mut(), ptr::null_mut()));
+        }
+        if let Some(v) = self.hyper_vector_size {
+            h5try!(H5Pset_hyper_vector_size(id, v as _));
+        }
+        if let Some(v) = self.edc_check {
+            let check = if v { H5Z_EDC_t::H5Z_ENABLE_EDC } else { H5Z_EDC_t::H5Z_DISABLE_EDC };
+            h5try!(H5Pset_edc_check(id, check));
+        }
+        if let Some(v) = self.abort_on_conv_exception {
+            let func: H5T_conv_except_func_t = if v { Some(abort_conv_exception) } else { None };
+            h5try!(H5Pset_type_conv_cb(id, func, ptr::null_mut()));
+        }
+        #[cfg(h5_have_parallel)]
+        {
+            if let Some(v) = self.mpio_transfer_mode {
+                h5try!(H5Pset_dxpl_mpio(id, v.into()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<DatasetTransfer> {
+        h5lock!({
+            let plist = DatasetTransfer::try_new()?;
+            self.populate_plist(plist.id())?;
+            Ok(plist)
+        })
+    }
+}
+
+/// Dataset transfer property list.
+impl DatasetTransfer {
+    pub fn try_new() -> Result<Self> {
+        Self::from_id(h5try!(H5Pcreate(*H5P_DATASET_XFER)))
+    }
+
+    pub fn copy(&self) -> Self {
+        unsafe { self.deref().copy().cast() }
+    }
+
+    pub fn build() -> DatasetTransferBuilder {
+        DatasetTransferBuilder::new()
+    }
+
+    #[doc(hidden)]
+    pub fn get_buffer_size(&self) -> Result<usize> {
+        h5call!(H5Pget_buffer(self.id(), ptr::null_mut(), ptr::null_mut())).map(|x| x as _)
+    }
+
+    pub fn buffer_size(&self) -> usize {
+        self.get_buffer_size().unwrap_or(1024 * 1024)
+    }
+
+    #[doc(hidden)]
+    pub fn get_hyper_vector_size(&self) -> Result<usize> {
+        h5get!(H5Pget_hyper_vector_size(self.id()): size_t).map(|x| x as _)
+    }
+
+    pub fn hyper_vector_size(&self) -> usize {
+        self.get_hyper_vector_size().unwrap_or(1024)
+    }
+
+    #[doc(hidden)]
+    pub fn get_edc_check(&self) -> Result<bool> {
+        h5call!(H5Pget_edc_check(self.id()) as c_int)
+            .map(|x| x != H5Z_EDC_t::H5Z_DISABLE_EDC as c_int)
+    }
+
+    pub fn edc_check(&self) -> bool {
+        self.get_edc_check().unwrap_or(true)
+    }
+
+    #[doc(hidden)]
+    pub fn get_abort_on_conv_exception(&self) -> Result<bool> {
+        let mut func: H5T_conv_except_func_t = None;
+        let mut data = ptr::null_mut();
+        h5try!(H5Pget_type_conv_cb(self.id(), &mut func, &mut data));
+        Ok(func == Some(abort_conv_exception))
+    }
+
+    pub fn abort_on_conv_exception(&self) -> bool {
+        self.get_abort_on_conv_exception().unwrap_or(false)
+    }
+
+    #[cfg(h5_have_parallel)]
+    #[doc(hidden)]
+    pub fn get_mpio_transfer_mode(&self) -> Result<TransferMode> {
+        let mut mode = H5FD_mpio_xfer_t::H5FD_MPIO_INDEPENDENT;
+        h5try!(H5Pget_dxpl_mpio(self.id(), &mut mode));
+        Ok(mode.into())
+    }
+
+    #[cfg(h5_have_parallel)]
+    pub fn mpio_transfer_mode(&self) -> TransferMode {
+        self.get_mpio_transfer_mode().unwrap_or_else(|_| TransferMode::default())
+    }
+}
+
+impl_decode!(DatasetTransfer);
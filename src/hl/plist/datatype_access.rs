@@ -0,0 +1,144 @@
+//! Datatype access properties.
+
+use std::fmt::{self, Debug};
+use std::ops::Deref;
+
+use hdf5_sys::h5p::H5Pcreate;
+#[cfg(all(hdf5_1_10_0, h5_have_parallel))]
+use hdf5_sys::h5p::{H5Pget_all_coll_metadata_ops, H5Pset_all_coll_metadata_ops};
+
+use crate::globals::H5P_DATATYPE_ACCESS;
+use crate::internal_prelude::*;
+
+/// Datatype access properties.
+///
+/// Other than collective metadata reads in parallel builds, there are no properties
+/// specific to accessing committed datatypes; the property list is accepted by
+/// `Group::datatype_with_access()`.
+#[repr(transparent)]
+pub struct DatatypeAccess(Handle);
+
+impl ObjectClass for DatatypeAccess {
+    const NAME: &'static str = "datatype access property list";
+    const VALID_TYPES: &'static [H5I_type_t] = &[H5I_GENPROP_LST];
+
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+
+    fn validate(&self) -> Result<()> {
+        let class = self.class()?;
+        if class != PropertyListClass::DatatypeAccess {
+            fail!("expected datatype access property list, got {:?}", class);
+        }
+        Ok(())
+    }
+}
+
+impl Debug for DatatypeAccess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _e = silence_errors();
+        let mut formatter = f.debug_struct("DatatypeAccess");
+        #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
+        formatter.field("all_coll_metadata_ops", &self.all_coll_metadata_ops());
+        formatter.finish()
+    }
+}
+
+impl Deref for DatatypeAccess {
+    type Target = PropertyList;
+
+    fn deref(&self) -> &PropertyList {
+        unsafe { self.transmute() }
+    }
+}
+
+impl PartialEq for DatatypeAccess {
+    fn eq(&self, other: &Self) -> bool {
+        <PropertyList as PartialEq>::eq(self, other)
+    }
+}
+
+impl Eq for DatatypeAccess {}
+
+impl Clone for DatatypeAccess {
+    fn clone(&self) -> Self {
+        unsafe { self.deref().clone().cast() }
+    }
+}
+
+/// Builder used to create datatype access property list.
+#[derive(Clone, Debug, Default)]
+pub struct DatatypeAccessBuilder {
+    #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
+    all_coll_metadata_ops: Option<bool>,
+}
+
+impl DatatypeAccessBuilder {
+    /// Creates a new datatype access property list builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new builder from an existing property list.
+    #[cfg_attr(not(all(hdf5_1_10_0, h5_have_parallel)), allow(unused_mut, unused_variables))]
+    pub fn from_plist(plist: &DatatypeAccess) -> Result<Self> {
+        let mut builder = Self::default();
+        #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
+        builder.all_coll_metadata_ops(plist.get_all_coll_metadata_ops()?);
+        Ok(builder)
+    }
+
+    /// Sets whether metadata reads when opening the datatype are collective.
+    #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
+    pub fn all_coll_metadata_ops(&mut self, is_collective: bool) -> &mut Self {
+        self.all_coll_metadata_ops = Some(is_collective);
+        self
+    }
+
+    #[allow(clippy::unused_self)]
+    pub fn finish(&self) -> Result<DatatypeAccess> {
+        h5lock!({
+            let plist = DatatypeAccess::try_new()?;
+            #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
+            {
+                if let Some(v) = self.all_coll_metadata_ops {
+                    h5try!(H5Pset_all_coll_metadata_ops(plist.id(), v as _));
+                }
+            }
+            Ok(plist)
+        })
+    }
+}
+
+/// Datatype access property list.
+impl DatatypeAccess {
+    pub fn try_new() -> Result<Self> {
+        Self::from_id(h5try!(H5Pcreate(*H5P_DATATYPE_ACCESS)))
+    }
+
+    pub fn copy(&self) -> Self {
+        unsafe { self.deref().copy().cast() }
+    }
+
+    pub fn build() -> DatatypeAccessBuilder {
+        DatatypeAccessBuilder::new()
+    }
+
+    #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
+    #[doc(hidden)]
+    pub fn get_all_coll_metadata_ops(&self) -> Result<bool> {
+        h5get!(H5Pget_all_coll_metadata_ops(self.id()): hbool_t).map(|x| x > 0)
+    }
+
+    #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
+    pub fn all_coll_metadata_ops(&self) -> bool {
+        self.get_all_coll_metadata_ops().unwrap_or(false)
+    }
+}
+
+impl_decode!(DatatypeAccess);
@@ -0,0 +1,161 @@
+//! Datatype creation properties.
+
+use std::fmt::{self, Debug};
+use std::ops::Deref;
+
+use hdf5_sys::h5p::{H5Pcreate, H5Pget_obj_track_times, H5Pset_obj_track_times};
+
+use super::file_create::AttrPhaseChangeInfo;
+use crate::globals::H5P_DATATYPE_CREATE;
+use crate::internal_prelude::*;
+
+/// Datatype creation properties.
+///
+/// Note that the character encoding of the datatype name is a link creation property,
+/// see `LinkCreate`.
+#[repr(transparent)]
+pub struct DatatypeCreate(Handle);
+
+impl ObjectClass for DatatypeCreate {
+    const NAME: &'static str = "datatype create property list";
+    const VALID_TYPES: &'static [H5I_type_t] = &[H5I_GENPROP_LST];
+
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+
+    fn validate(&self) -> Result<()> {
+        let class = self.class()?;
+        if class != PropertyListClass::DatatypeCreate {
+            fail!("expected datatype create property list, got {:?}", class);
+        }
+        Ok(())
+    }
+}
+
+impl Debug for DatatypeCreate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _e = silence_errors();
+        let mut formatter = f.debug_struct("DatatypeCreate");
+        formatter.field("obj_track_times", &self.obj_track_times());
+        formatter.field("attr_phase_change", &self.attr_phase_change());
+        formatter.finish()
+    }
+}
+
+impl Deref for DatatypeCreate {
+    type Target = PropertyList;
+
+    fn deref(&self) -> &PropertyList {
+        unsafe { self.transmute() }
+    }
+}
+
+impl PartialEq for DatatypeCreate {
+    fn eq(&self, other: &Self) -> bool {
+        <PropertyList as PartialEq>::eq(self, other)
+    }
+}
+
+impl Eq for DatatypeCreate {}
+
+impl Clone for DatatypeCreate {
+    fn clone(&self) -> Self {
+        unsafe { self.deref().clone().cast() }
+    }
+}
+
+/// Builder used to create datatype creation property list.
+#[derive(Clone, Debug, Default)]
+pub struct DatatypeCreateBuilder {
+    obj_track_times: Option<bool>,
+    attr_phase_change: Option<AttrPhaseChangeInfo>,
+}
+
+impl DatatypeCreateBuilder {
+    /// Creates a new datatype creation property list builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new builder from an existing property list.
+    pub fn from_plist(plist: &DatatypeCreate) -> Result<Self> {
+        let mut builder = Self::default();
+        builder.obj_track_times(plist.get_obj_track_times()?);
+        let v = plist.get_attr_phase_change()?;
+        builder.attr_phase_change(v.max_compact, v.min_dense);
+        Ok(builder)
+    }
+
+    /// Sets whether the access, modification, change and birth times of the datatype
+    /// object are recorded.
+    pub fn obj_track_times(&mut self, track_times: bool) -> &mut Self {
+        self.obj_track_times = Some(track_times);
+        self
+    }
+
+    /// Sets attribute storage phase change thresholds.
+    ///
+    /// For further details, see [`AttrPhaseChangeInfo`](struct.AttrPhaseChangeInfo.html).
+    pub fn attr_phase_change(&mut self, max_compact: u32, min_dense: u32) -> &mut Self {
+        self.attr_phase_change = Some(AttrPhaseChangeInfo { max_compact, min_dense });
+        self
+    }
+
+    fn populate_plist(&self, id: hid_t) -> Result<()> {
+        if let Some(v) = self.obj_track_times {
+            h5try!(H5Pset_obj_track_times(id, v as _));
+        }
+        if let Some(v) = self.attr_phase_change {
+            v.set(id)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<DatatypeCreate> {
+        h5lock!({
+            let plist = DatatypeCreate::try_new()?;
+            self.populate_plist(plist.id())?;
+            Ok(plist)
+        })
+    }
+}
+
+/// Datatype creation property list.
+impl DatatypeCreate {
+    pub fn try_new() -> Result<Self> {
+        Self::from_id(h5try!(H5Pcreate(*H5P_DATATYPE_CREATE)))
+    }
+
+    pub fn copy(&self) -> Self {
+        unsafe { self.deref().copy().cast() }
+    }
+
+    pub fn build() -> DatatypeCreateBuilder {
+        DatatypeCreateBuilder::new()
+    }
+
+    #[doc(hidden)]
+    pub fn get_obj_track_times(&self) -> Result<bool> {
+        h5get!(H5Pget_obj_track_times(self.id()): hbool_t).map(|x| x > 0)
+    }
+
+    pub fn obj_track_times(&self) -> bool {
+        self.get_obj_track_times().unwrap_or(true)
+    }
+
+    #[doc(hidden)]
+    pub fn get_attr_phase_change(&self) -> Result<AttrPhaseChangeInfo> {
+        AttrPhaseChangeInfo::get(self.id())
+    }
+
+    pub fn attr_phase_change(&self) -> AttrPhaseChangeInfo {
+        self.get_attr_phase_change().unwrap_or_else(|_| AttrPhaseChangeInfo::default())
+    }
+}
+
+impl_decode!(DatatypeCreate);
@@ -395,6 +395,24 @@ mod mpio {
 
     use super::{c_int, Result};
 
+    /// Types that can be used as an MPI communicator by the MPI-IO file driver.
+    pub trait AsMpiComm {
+        /// Returns the raw handle of the communicator.
+        fn as_mpi_comm(&self) -> MPI_Comm;
+    }
+
+    impl AsMpiComm for MPI_Comm {
+        fn as_mpi_comm(&self) -> MPI_Comm {
+            *self
+        }
+    }
+
+    impl<T: AsMpiComm + ?Sized> AsMpiComm for &T {
+        fn as_mpi_comm(&self) -> MPI_Comm {
+            (**self).as_mpi_comm()
+        }
+    }
+
     #[derive(Debug)]
     pub struct MpioDriver {
         pub comm: MPI_Comm,
@@ -1047,6 +1065,7 @@ impl FileAccessBuilder {
         self
     }
 
+    /// Sets the default raw data chunk cache parameters for all datasets in the file.
     pub fn chunk_cache(&mut self, nslots: usize, nbytes: usize, w0: f64) -> &mut Self {
         self.chunk_cache = Some(ChunkCache { nslots, nbytes, w0 });
         self
@@ -1112,12 +1131,17 @@ impl FileAccessBuilder {
         self
     }
 
+    /// Sets whether metadata reads are performed collectively by all processes.
+    ///
+    /// By default each process reads metadata independently, which can overwhelm
+    /// parallel file systems such as Lustre when many processes open the same objects.
     #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
     pub fn all_coll_metadata_ops(&mut self, is_collective: bool) -> &mut Self {
         self.all_coll_metadata_ops = Some(is_collective);
         self
     }
 
+    /// Sets whether metadata cache flushes are written collectively by all processes.
     #[cfg(all(hdf5_1_10_0, h5_have_parallel))]
     pub fn coll_metadata_write(&mut self, is_collective: bool) -> &mut Self {
         self.coll_metadata_write = Some(is_collective);
@@ -1223,8 +1247,9 @@ impl FileAccessBuilder {
     }
 
     #[cfg(feature = "mpio")]
-    pub fn mpio(&mut self, comm: mpi_sys::MPI_Comm, info: Option<mpi_sys::MPI_Info>) -> &mut Self {
+    pub fn mpio<C: AsMpiComm>(&mut self, comm: C, info: Option<mpi_sys::MPI_Info>) -> &mut Self {
         // We use .unwrap() here since MPI will almost surely terminate the process anyway.
+        let comm = comm.as_mpi_comm();
         self.driver(&FileDriver::Mpio(MpioDriver::try_new(comm, info).unwrap()))
     }
 
@@ -1272,7 +1297,7 @@ impl FileAccessBuilder {
 
     fn set_multi(id: hid_t, drv: &MultiDriver) -> Result<()> {
         const N: usize = H5FD_MEM_NTYPES as _;
-        debug_assert_eq!(FD_MEM_TYPES.len(), N as _);
+        debug_assert_eq!(FD_MEM_TYPES.len(), N);
 
         drv.validate()?;
 
@@ -1520,7 +1545,7 @@ impl FileAccess {
     #[doc(hidden)]
     fn get_multi(&self) -> Result<MultiDriver> {
         const N: usize = H5FD_MEM_NTYPES as _;
-        debug_assert_eq!(FD_MEM_TYPES.len(), N as _);
+        debug_assert_eq!(FD_MEM_TYPES.len(), N);
         let mut memb_map: [H5F_mem_t; N] = unsafe { mem::zeroed() };
         let mut memb_fapl: [hid_t; N] = unsafe { mem::zeroed() };
         let mut memb_name: [*const c_char; N] = unsafe { mem::zeroed() };
@@ -1821,3 +1846,5 @@ impl FileAccess {
         self.get_libver_bounds().ok().unwrap_or_else(LibVerBounds::default)
     }
 }
+
+impl_decode!(FileAccess);
@@ -12,10 +12,11 @@ use hdf5_sys::h5o::{
     H5O_SHMESG_NONE_FLAG, H5O_SHMESG_PLINE_FLAG, H5O_SHMESG_SDSPACE_FLAG,
 };
 use hdf5_sys::h5p::{
-    H5Pcreate, H5Pget_istore_k, H5Pget_shared_mesg_index, H5Pget_shared_mesg_nindexes,
-    H5Pget_shared_mesg_phase_change, H5Pget_sizes, H5Pget_sym_k, H5Pget_userblock, H5Pset_istore_k,
-    H5Pset_shared_mesg_index, H5Pset_shared_mesg_nindexes, H5Pset_shared_mesg_phase_change,
-    H5Pset_sym_k, H5Pset_userblock,
+    H5Pcreate, H5Pget_attr_phase_change, H5Pget_istore_k, H5Pget_shared_mesg_index,
+    H5Pget_shared_mesg_nindexes, H5Pget_shared_mesg_phase_change, H5Pget_sizes, H5Pget_sym_k,
+    H5Pget_userblock, H5Pset_attr_phase_change, H5Pset_istore_k, H5Pset_shared_mesg_index,
+    H5Pset_shared_mesg_nindexes, H5Pset_shared_mesg_phase_change, H5Pset_sizes, H5Pset_sym_k,
+    H5Pset_userblock,
 };
 #[cfg(hdf5_1_10_1)]
 use hdf5_sys::h5p::{
@@ -62,7 +63,8 @@ impl Debug for FileCreate {
             .field("sym_k", &self.sym_k())
             .field("istore_k", &self.istore_k())
             .field("shared_mesg_phase_change", &self.shared_mesg_phase_change())
-            .field("shared_mesg_indexes", &self.shared_mesg_indexes());
+            .field("shared_mesg_indexes", &self.shared_mesg_indexes())
+            .field("attr_phase_change", &self.attr_phase_change());
         #[cfg(hdf5_1_10_1)]
         {
             formatter
@@ -211,14 +213,56 @@ impl Default for FileSpaceStrategy {
     }
 }
 
+/// Attribute storage phase change thresholds.
+///
+/// Attributes of an object are initially stored compactly in the object header.
+/// When the number of attributes exceeds `max_compact`, they are moved to dense
+/// storage (a fractal heap indexed by a B-tree), which scales much better for objects
+/// with a large number of attributes. If the number of attributes subsequently falls
+/// below `min_dense`, they are moved back to compact storage.
+///
+/// If `max_compact` is set to 0 (zero), dense storage is used from the start. The
+/// default values are 8 and 6, respectively.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AttrPhaseChangeInfo {
+    /// Maximum number of attributes to be stored in compact storage.
+    pub max_compact: u32,
+    /// Minimum number of attributes to be stored in dense storage.
+    pub min_dense: u32,
+}
+
+impl Default for AttrPhaseChangeInfo {
+    fn default() -> Self {
+        Self { max_compact: 8, min_dense: 6 }
+    }
+}
+
+impl AttrPhaseChangeInfo {
+    pub(crate) fn get(plist_id: hid_t) -> Result<Self> {
+        h5get!(H5Pget_attr_phase_change(plist_id): c_uint, c_uint).map(
+            |(max_compact, min_dense)| Self {
+                max_compact: max_compact as _,
+                min_dense: min_dense as _,
+            },
+        )
+    }
+
+    pub(crate) fn set(self, plist_id: hid_t) -> Result<()> {
+        h5call!(H5Pset_attr_phase_change(plist_id, self.max_compact as _, self.min_dense as _))
+            .and(Ok(()))
+    }
+}
+
 /// Builder used to create file creation property list.
 #[derive(Clone, Debug, Default)]
 pub struct FileCreateBuilder {
     userblock: Option<u64>,
+    sizes: Option<SizeofInfo>,
     sym_k: Option<SymbolTableInfo>,
     istore_k: Option<u32>,
     shared_mesg_phase_change: Option<PhaseChangeInfo>,
     shared_mesg_indexes: Option<Vec<SharedMessageIndex>>,
+    attr_phase_change: Option<AttrPhaseChangeInfo>,
     #[cfg(hdf5_1_10_1)]
     file_space_page_size: Option<u64>,
     #[cfg(hdf5_1_10_1)]
@@ -235,12 +279,16 @@ impl FileCreateBuilder {
     pub fn from_plist(plist: &FileCreate) -> Result<Self> {
         let mut builder = Self::default();
         builder.userblock(plist.get_userblock()?);
+        let v = plist.get_sizes()?;
+        builder.sizes(v.sizeof_addr, v.sizeof_size);
         let v = plist.get_sym_k()?;
         builder.sym_k(v.tree_rank, v.node_size);
         builder.istore_k(plist.get_istore_k()?);
         let v = plist.get_shared_mesg_phase_change()?;
         builder.shared_mesg_phase_change(v.max_list, v.min_btree);
         builder.shared_mesg_indexes(&plist.get_shared_mesg_indexes()?);
+        let v = plist.get_attr_phase_change()?;
+        builder.attr_phase_change(v.max_compact, v.min_dense);
         #[cfg(hdf5_1_10_1)]
         {
             builder.file_space_page_size(plist.get_file_space_page_size()?);
@@ -259,6 +307,15 @@ impl FileCreateBuilder {
         self
     }
 
+    /// Sets the byte size of the offsets and lengths used to address objects in the file.
+    ///
+    /// Valid values are 2, 4, 8, 16 and 32; passing in zero retains the current value.
+    /// The default for both is 8.
+    pub fn sizes(&mut self, sizeof_addr: usize, sizeof_size: usize) -> &mut Self {
+        self.sizes = Some(SizeofInfo { sizeof_addr, sizeof_size });
+        self
+    }
+
     /// Sets the size of parameters used to control the symbol table nodes.
     ///
     /// Passing in a value of zero (0) for one of the parameters (`tree_rank` or
@@ -305,6 +362,14 @@ impl FileCreateBuilder {
         self
     }
 
+    /// Sets attribute storage phase change thresholds for the root group.
+    ///
+    /// For further details, see [`AttrPhaseChangeInfo`](struct.AttrPhaseChangeInfo.html).
+    pub fn attr_phase_change(&mut self, max_compact: u32, min_dense: u32) -> &mut Self {
+        self.attr_phase_change = Some(AttrPhaseChangeInfo { max_compact, min_dense });
+        self
+    }
+
     #[cfg(hdf5_1_10_1)]
     /// Sets the file space page size.
     ///
@@ -331,6 +396,9 @@ impl FileCreateBuilder {
         if let Some(v) = self.userblock {
             h5try!(H5Pset_userblock(id, v as _));
         }
+        if let Some(v) = self.sizes {
+            h5try!(H5Pset_sizes(id, v.sizeof_addr as _, v.sizeof_size as _));
+        }
         if let Some(v) = self.sym_k {
             h5try!(H5Pset_sym_k(id, v.tree_rank as _, v.node_size as _));
         }
@@ -351,6 +419,9 @@ impl FileCreateBuilder {
                 ));
             }
         }
+        if let Some(v) = self.attr_phase_change {
+            v.set(id)?;
+        }
         #[cfg(hdf5_1_10_1)]
         {
             if let Some(v) = self.file_space_page_size {
@@ -434,6 +505,11 @@ impl FileCreate {
         )
     }
 
+    #[doc(hidden)]
+    pub fn get_attr_phase_change(&self) -> Result<AttrPhaseChangeInfo> {
+        AttrPhaseChangeInfo::get(self.id())
+    }
+
     #[doc(hidden)]
     pub fn get_shared_mesg_indexes(&self) -> Result<Vec<SharedMessageIndex>> {
         let n = h5get_d!(H5Pget_shared_mesg_nindexes(self.id()): c_uint);
@@ -503,6 +579,11 @@ impl FileCreate {
         self.get_shared_mesg_phase_change().unwrap_or_else(|_| PhaseChangeInfo::default())
     }
 
+    /// Retrieves attribute storage phase change thresholds for the root group.
+    pub fn attr_phase_change(&self) -> AttrPhaseChangeInfo {
+        self.get_attr_phase_change().unwrap_or_else(|_| AttrPhaseChangeInfo::default())
+    }
+
     /// Retrieves configuration settings for shared message indexes.
     pub fn shared_mesg_indexes(&self) -> Vec<SharedMessageIndex> {
         self.get_shared_mesg_indexes().unwrap_or_else(|_| Vec::new())
@@ -520,3 +601,5 @@ impl FileCreate {
         self.get_file_space_strategy().unwrap_or_else(|_| FileSpaceStrategy::default())
     }
 }
+
+impl_decode!(FileCreate);
@@ -0,0 +1,275 @@
+//! Group creation properties.
+
+use std::fmt::{self, Debug};
+use std::ops::Deref;
+
+use hdf5_sys::h5p::{
+    H5Pcreate, H5Pget_link_creation_order, H5Pget_link_phase_change, H5Pget_local_heap_size_hint,
+    H5Pget_obj_track_times, H5Pset_link_creation_order, H5Pset_link_phase_change,
+    H5Pset_local_heap_size_hint, H5Pset_obj_track_times, H5P_CRT_ORDER_INDEXED,
+    H5P_CRT_ORDER_TRACKED,
+};
+
+use super::file_create::AttrPhaseChangeInfo;
+use crate::globals::H5P_GROUP_CREATE;
+use crate::internal_prelude::*;
+
+/// Group creation properties.
+#[repr(transparent)]
+pub struct GroupCreate(Handle);
+
+impl ObjectClass for GroupCreate {
+    const NAME: &'static str = "group create property list";
+    const VALID_TYPES: &'static [H5I_type_t] = &[H5I_GENPROP_LST];
+
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+
+    fn validate(&self) -> Result<()> {
+        let class = self.class()?;
+        if class != PropertyListClass::GroupCreate {
+            fail!("expected group create property list, got {:?}", class);
+        }
+        Ok(())
+    }
+}
+
+impl Debug for GroupCreate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _e = silence_errors();
+        let mut formatter = f.debug_struct("GroupCreate");
+        formatter.field("local_heap_size_hint", &self.local_heap_size_hint());
+        formatter.field("link_phase_change", &self.link_phase_change());
+        formatter.field("link_creation_order", &self.link_creation_order());
+        formatter.field("obj_track_times", &self.obj_track_times());
+        formatter.field("attr_phase_change", &self.attr_phase_change());
+        formatter.finish()
+    }
+}
+
+impl Deref for GroupCreate {
+    type Target = PropertyList;
+
+    fn deref(&self) -> &PropertyList {
+        unsafe { self.transmute() }
+    }
+}
+
+impl PartialEq for GroupCreate {
+    fn eq(&self, other: &Self) -> bool {
+        <PropertyList as PartialEq>::eq(self, other)
+    }
+}
+
+impl Eq for GroupCreate {}
+
+impl Clone for GroupCreate {
+    fn clone(&self) -> Self {
+        unsafe { self.deref().clone().cast() }
+    }
+}
+
+/// Link storage phase change thresholds of a group.
+///
+/// Links are stored compactly in the group's object header until their number exceeds
+/// `max_compact`, at which point they are moved to dense storage (a fractal heap and a
+/// B-tree index); they are moved back once their number falls below `min_dense`. The
+/// default values are 8 and 6, respectively.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinkPhaseChangeInfo {
+    /// Maximum number of links to be stored in compact storage.
+    pub max_compact: u32,
+    /// Minimum number of links to be stored in dense storage.
+    pub min_dense: u32,
+}
+
+impl Default for LinkPhaseChangeInfo {
+    fn default() -> Self {
+        Self { max_compact: 8, min_dense: 6 }
+    }
+}
+
+/// Creation order tracking and indexing of links in a group.
+///
+/// Tracking the creation order of links is required for iterating over them in that
+/// order; indexing (which requires tracking) makes such iteration efficient.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LinkCreationOrder {
+    /// Whether the creation order of links is tracked.
+    pub tracked: bool,
+    /// Whether the creation order of links is indexed.
+    pub indexed: bool,
+}
+
+/// Builder used to create group creation property list.
+#[derive(Clone, Debug, Default)]
+pub struct GroupCreateBuilder {
+    local_heap_size_hint: Option<usize>,
+    link_phase_change: Option<LinkPhaseChangeInfo>,
+    link_creation_order: Option<LinkCreationOrder>,
+    obj_track_times: Option<bool>,
+    attr_phase_change: Option<AttrPhaseChangeInfo>,
+}
+
+impl GroupCreateBuilder {
+    /// Creates a new group creation property list builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new builder from an existing property list.
+    pub fn from_plist(plist: &GroupCreate) -> Result<Self> {
+        let mut builder = Self::default();
+        builder.local_heap_size_hint(plist.get_local_heap_size_hint()?);
+        let v = plist.get_link_phase_change()?;
+        builder.link_phase_change(v.max_compact, v.min_dense);
+        let v = plist.get_link_creation_order()?;
+        builder.link_creation_order(v.tracked, v.indexed);
+        builder.obj_track_times(plist.get_obj_track_times()?);
+        let v = plist.get_attr_phase_change()?;
+        builder.attr_phase_change(v.max_compact, v.min_dense);
+        Ok(builder)
+    }
+
+    /// Sets the anticipated size of the local heap holding link names of an old-style
+    /// group (1 by default).
+    pub fn local_heap_size_hint(&mut self, size_hint: usize) -> &mut Self {
+        self.local_heap_size_hint = Some(size_hint);
+        self
+    }
+
+    /// Sets link storage phase change thresholds.
+    ///
+    /// For further details, see [`LinkPhaseChangeInfo`](struct.LinkPhaseChangeInfo.html).
+    pub fn link_phase_change(&mut self, max_compact: u32, min_dense: u32) -> &mut Self {
+        self.link_phase_change = Some(LinkPhaseChangeInfo { max_compact, min_dense });
+        self
+    }
+
+    /// Sets whether the creation order of links is tracked and indexed.
+    ///
+    /// For further details, see [`LinkCreationOrder`](struct.LinkCreationOrder.html).
+    pub fn link_creation_order(&mut self, tracked: bool, indexed: bool) -> &mut Self {
+        self.link_creation_order = Some(LinkCreationOrder { tracked, indexed });
+        self
+    }
+
+    /// Sets whether the access, modification, change and birth times of the group are
+    /// recorded.
+    pub fn obj_track_times(&mut self, track_times: bool) -> &mut Self {
+        self.obj_track_times = Some(track_times);
+        self
+    }
+
+    /// Sets attribute storage phase change thresholds.
+    ///
+    /// For further details, see [`AttrPhaseChangeInfo`](struct.AttrPhaseChangeInfo.html).
+    pub fn attr_phase_change(&mut self, max_compact: u32, min_dense: u32) -> &mut Self {
+        self.attr_phase_change = Some(AttrPhaseChangeInfo { max_compact, min_dense });
+        self
+    }
+
+    fn populate_plist(&self, id: hid_t) -> Result<()> {
+        if let Some(v) = self.local_heap_size_hint {
+            h5try!(H5Pset_local_heap_size_hint(id, v as _));
+        }
+        if let Some(v) = self.link_phase_change {
+            h5try!(H5Pset_link_phase_change(id, v.max_compact as _, v.min_dense as _));
+        }
+        if let Some(v) = self.link_creation_order {
+            let tracked = if v.tracked { H5P_CRT_ORDER_TRACKED } else { 0 };
+            let indexed = if v.indexed { H5P_CRT_ORDER_INDEXED } else { 0 };
+            h5try!(H5Pset_link_creation_order(id, tracked | indexed));
+        }
+        if let Some(v) = self.obj_track_times {
+            h5try!(H5Pset_obj_track_times(id, v as _));
+        }
+        if let Some(v) = self.attr_phase_change {
+            v.set(id)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<GroupCreate> {
+        h5lock!({
+            let plist = GroupCreate::try_new()?;
+            self.populate_plist(plist.id())?;
+            Ok(plist)
+        })
+    }
+}
+
+/// Group creation property list.
+impl GroupCreate {
+    pub fn try_new() -> Result<Self> {
+        Self::from_id(h5try!(H5Pcreate(*H5P_GROUP_CREATE)))
+    }
+
+    pub fn copy(&self) -> Self {
+        unsafe { self.deref().copy().cast() }
+    }
+
+    pub fn build() -> GroupCreateBuilder {
+        GroupCreateBuilder::new()
+    }
+
+    #[doc(hidden)]
+    pub fn get_local_heap_size_hint(&self) -> Result<usize> {
+        h5get!(H5Pget_local_heap_size_hint(self.id()): size_t).map(|x| x as _)
+    }
+
+    pub fn local_heap_size_hint(&self) -> usize {
+        self.get_local_heap_size_hint().unwrap_or(0)
+    }
+
+    #[doc(hidden)]
+    pub fn get_link_phase_change(&self) -> Result<LinkPhaseChangeInfo> {
+        h5get!(H5Pget_link_phase_change(self.id()): c_uint, c_uint).map(
+            |(max_compact, min_dense)| LinkPhaseChangeInfo {
+                max_compact: max_compact as _,
+                min_dense: min_dense as _,
+            },
+        )
+    }
+
+    pub fn link_phase_change(&self) -> LinkPhaseChangeInfo {
+        self.get_link_phase_change().unwrap_or_else(|_| LinkPhaseChangeInfo::default())
+    }
+
+    #[doc(hidden)]
+    pub fn get_link_creation_order(&self) -> Result<LinkCreationOrder> {
+        h5get!(H5Pget_link_creation_order(self.id()): c_uint).map(|flags| LinkCreationOrder {
+            tracked: flags & H5P_CRT_ORDER_TRACKED != 0,
+            indexed: flags & H5P_CRT_ORDER_INDEXED != 0,
+        })
+    }
+
+    pub fn link_creation_order(&self) -> LinkCreationOrder {
+        self.get_link_creation_order().unwrap_or_else(|_| LinkCreationOrder::default())
+    }
+
+    #[doc(hidden)]
+    pub fn get_obj_track_times(&self) -> Result<bool> {
+        h5get!(H5Pget_obj_track_times(self.id()): hbool_t).map(|x| x > 0)
+    }
+
+    pub fn obj_track_times(&self) -> bool {
+        self.get_obj_track_times().unwrap_or(true)
+    }
+
+    #[doc(hidden)]
+    pub fn get_attr_phase_change(&self) -> Result<AttrPhaseChangeInfo> {
+        AttrPhaseChangeInfo::get(self.id())
+    }
+
+    pub fn attr_phase_change(&self) -> AttrPhaseChangeInfo {
+        self.get_attr_phase_change().unwrap_or_else(|_| AttrPhaseChangeInfo::default())
+    }
+}
+
+impl_decode!(GroupCreate);
@@ -0,0 +1,249 @@
+//! Object copy properties.
+
+/*
+Not implemented:
+- H5P{add,free}_merge_committed_dtype_path, H5P{set,get}_mcdt_search_cb
+*/
+
+use std::fmt::{self, Debug};
+use std::ops::Deref;
+
+#[cfg(hdf5_1_8_9)]
+use hdf5_sys::h5o::H5O_COPY_MERGE_COMMITTED_DTYPE_FLAG;
+use hdf5_sys::h5o::{
+    H5O_COPY_EXPAND_EXT_LINK_FLAG, H5O_COPY_EXPAND_REFERENCE_FLAG, H5O_COPY_EXPAND_SOFT_LINK_FLAG,
+    H5O_COPY_PRESERVE_NULL_FLAG, H5O_COPY_SHALLOW_HIERARCHY_FLAG, H5O_COPY_WITHOUT_ATTR_FLAG,
+};
+use hdf5_sys::h5p::{H5Pcreate, H5Pget_copy_object, H5Pset_copy_object};
+
+use crate::globals::H5P_OBJECT_COPY;
+use crate::internal_prelude::*;
+
+/// Object copy properties.
+#[repr(transparent)]
+pub struct ObjectCopy(Handle);
+
+impl ObjectClass for ObjectCopy {
+    const NAME: &'static str = "object copy property list";
+    const VALID_TYPES: &'static [H5I_type_t] = &[H5I_GENPROP_LST];
+
+    fn from_handle(handle: Handle) -> Self {
+        Self(handle)
+    }
+
+    fn handle(&self) -> &Handle {
+        &self.0
+    }
+
+    fn validate(&self) -> Result<()> {
+        let class = self.class()?;
+        if class != PropertyListClass::ObjectCopy {
+            fail!("expected object copy property list, got {:?}", class);
+        }
+        Ok(())
+    }
+}
+
+impl Debug for ObjectCopy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let _e = silence_errors();
+        let mut formatter = f.debug_struct("ObjectCopy");
+        formatter.field("shallow_hierarchy", &self.shallow_hierarchy());
+        formatter.field("expand_soft_links", &self.expand_soft_links());
+        formatter.field("expand_ext_links", &self.expand_ext_links());
+        formatter.field("expand_references", &self.expand_references());
+        formatter.field("without_attrs", &self.without_attrs());
+        formatter.field("preserve_null", &self.preserve_null());
+        #[cfg(hdf5_1_8_9)]
+        formatter.field("merge_committed_dtypes", &self.merge_committed_dtypes());
+        formatter.finish()
+    }
+}
+
+impl Deref for ObjectCopy {
+    type Target = PropertyList;
+
+    fn deref(&self) -> &PropertyList {
+        unsafe { self.transmute() }
+    }
+}
+
+impl PartialEq for ObjectCopy {
+    fn eq(&self, other: &Self) -> bool {
+        <PropertyList as PartialEq>::eq(self, other)
+    }
+}
+
+impl Eq for ObjectCopy {}
+
+impl Clone for ObjectCopy {
+    fn clone(&self) -> Self {
+        unsafe { self.deref().clone().cast() }
+    }
+}
+
+/// Builder used to create object copy property list.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectCopyBuilder {
+    flags: c_uint,
+}
+
+impl ObjectCopyBuilder {
+    /// Creates a new object copy property list builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new builder from an existing property list.
+    pub fn from_plist(plist: &ObjectCopy) -> Result<Self> {
+        Ok(Self { flags: plist.get_flags()? })
+    }
+
+    fn flag(&mut self, flag: c_uint, value: bool) -> &mut Self {
+        if value {
+            self.flags |= flag;
+        } else {
+            self.flags &= !flag;
+        }
+        self
+    }
+
+    /// Copies only the immediate members of a group instead of the whole hierarchy.
+    pub fn shallow_hierarchy(&mut self, shallow: bool) -> &mut Self {
+        self.flag(H5O_COPY_SHALLOW_HIERARCHY_FLAG, shallow)
+    }
+
+    /// Copies the objects pointed to by soft links instead of the links themselves.
+    pub fn expand_soft_links(&mut self, expand: bool) -> &mut Self {
+        self.flag(H5O_COPY_EXPAND_SOFT_LINK_FLAG, expand)
+    }
+
+    /// Copies the objects pointed to by external links instead of the links themselves.
+    pub fn expand_ext_links(&mut self, expand: bool) -> &mut Self {
+        self.flag(H5O_COPY_EXPAND_EXT_LINK_FLAG, expand)
+    }
+
+    /// Copies the objects pointed to by references and updates the references.
+    pub fn expand_references(&mut self, expand: bool) -> &mut Self {
+        self.flag(H5O_COPY_EXPAND_REFERENCE_FLAG, expand)
+    }
+
+    /// Copies objects without their attributes.
+    pub fn without_attrs(&mut self, without: bool) -> &mut Self {
+        self.flag(H5O_COPY_WITHOUT_ATTR_FLAG, without)
+    }
+
+    /// Copies attributes with a null dataspace (instead of dropping them).
+    pub fn preserve_null(&mut self, preserve: bool) -> &mut Self {
+        self.flag(H5O_COPY_PRESERVE_NULL_FLAG, preserve)
+    }
+
+    /// Reuses matching committed datatypes in the destination file instead of copying.
+    #[cfg(hdf5_1_8_9)]
+    pub fn merge_committed_dtypes(&mut self, merge: bool) -> &mut Self {
+        self.flag(H5O_COPY_MERGE_COMMITTED_DTYPE_FLAG, merge)
+    }
+
+    fn populate_plist(&self, id: hid_t) -> Result<()> {
+        h5try!(H5Pset_copy_object(id, self.flags));
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<ObjectCopy> {
+        h5lock!({
+            let plist = ObjectCopy::try_new()?;
+            self.populate_plist(plist.id())?;
+            Ok(plist)
+        })
+    }
+}
+
+/// Object copy property list.
+impl ObjectCopy {
+    pub fn try_new() -> Result<Self> {
+        Self::from_id(h5try!(H5Pcreate(*H5P_OBJECT_COPY)))
+    }
+
+    pub fn copy(&self) -> Self {
+        unsafe { self.deref().copy().cast() }
+    }
+
+    pub fn build() -> ObjectCopyBuilder {
+        ObjectCopyBuilder::new()
+    }
+
+    fn get_flags(&self) -> Result<c_uint> {
+        h5get!(H5Pget_copy_object(self.id()): c_uint)
+    }
+
+    fn get_flag(&self, flag: c_uint) -> Result<bool> {
+        self.get_flags().map(|flags| flags & flag != 0)
+    }
+
+    #[doc(hidden)]
+    pub fn get_shallow_hierarchy(&self) -> Result<bool> {
+        self.get_flag(H5O_COPY_SHALLOW_HIERARCHY_FLAG)
+    }
+
+    pub fn shallow_hierarchy(&self) -> bool {
+        self.get_shallow_hierarchy().unwrap_or(false)
+    }
+
+    #[doc(hidden)]
+    pub fn get_expand_soft_links(&self) -> Result<bool> {
+        self.get_flag(H5O_COPY_EXPAND_SOFT_LINK_FLAG)
+    }
+
+    pub fn expand_soft_links(&self) -> bool {
+        self.get_expand_soft_links().unwrap_or(false)
+    }
+
+    #[doc(hidden)]
+    pub fn get_expand_ext_links(&self) -> Result<bool> {
+        self.get_flag(H5O_COPY_EXPAND_EXT_LINK_FLAG)
+    }
+
+    pub fn expand_ext_links(&self) -> bool {
+        self.get_expand_ext_links().unwrap_or(false)
+    }
+
+    #[doc(hidden)]
+    pub fn get_expand_references(&self) -> Result<bool> {
+        self.get_flag(H5O_COPY_EXPAND_REFERENCE_FLAG)
+    }
+
+    pub fn expand_references(&self) -> bool {
+        self.get_expand_references().unwrap_or(false)
+    }
+
+    #[doc(hidden)]
+    pub fn get_without_attrs(&self) -> Result<bool> {
+        self.get_flag(H5O_COPY_WITHOUT_ATTR_FLAG)
+    }
+
+    pub fn without_attrs(&self) -> bool {
+        self.get_without_attrs().unwrap_or(false)
+    }
+
+    #[doc(hidden)]
+    pub fn get_preserve_null(&self) -> Result<bool> {
+        self.get_flag(H5O_COPY_PRESERVE_NULL_FLAG)
+    }
+
+    pub fn preserve_null(&self) -> bool {
+        self.get_preserve_null().unwrap_or(false)
+    }
+
+    #[cfg(hdf5_1_8_9)]
+    #[doc(hidden)]
+    pub fn get_merge_committed_dtypes(&self) -> Result<bool> {
+        self.get_flag(H5O_COPY_MERGE_COMMITTED_DTYPE_FLAG)
+    }
+
+    #[cfg(hdf5_1_8_9)]
+    pub fn merge_committed_dtypes(&self) -> bool {
+        self.get_merge_committed_dtypes().unwrap_or(false)
+    }
+}
+
+impl_decode!(ObjectCopy);
@@ -1,17 +1,34 @@
 use std::convert::AsRef;
 use std::fmt::{self, Debug};
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 use std::ptr;
 
 use ndarray::SliceOrIndex;
 
+use hdf5_sys::h5::hssize_t;
 use hdf5_sys::h5s::{
-    H5Scopy, H5Screate_simple, H5Sget_simple_extent_dims, H5Sget_simple_extent_ndims,
-    H5Sselect_hyperslab, H5S_SELECT_SET,
+    H5S_sel_type, H5Scopy, H5Screate, H5Screate_simple, H5Sdecode, H5Sencode, H5Sget_select_bounds,
+    H5Sget_select_npoints, H5Sget_select_type, H5Sget_simple_extent_dims,
+    H5Sget_simple_extent_ndims, H5Sget_simple_extent_type, H5Soffset_simple, H5Sselect_all,
+    H5Sselect_elements, H5Sselect_hyperslab, H5Sselect_none, H5Sselect_valid, H5S_NULL,
+    H5S_SELECT_SET,
 };
 
 use crate::internal_prelude::*;
 
+/// Kind of the current selection of a dataspace, see `Dataspace::selection_type()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectionType {
+    /// Nothing is selected.
+    None,
+    /// Individual elements are selected.
+    Points,
+    /// One or more hyperslabs are selected.
+    Hyperslabs,
+    /// The entire extent is selected.
+    All,
+}
+
 /// Represents the HDF5 dataspace object.
 #[repr(transparent)]
 #[derive(Clone)]
@@ -30,7 +47,9 @@ impl ObjectClass for Dataspace {
     }
 
     fn short_repr(&self) -> Option<String> {
-        if self.ndim() == 1 {
+        if self.is_null() {
+            Some("null".to_owned())
+        } else if self.ndim() == 1 {
             Some(format!("({},)", self.dims()[0]))
         } else {
             let dims = self.dims().iter().map(ToString::to_string).collect::<Vec<_>>().join(", ");
@@ -54,11 +73,33 @@ impl Deref for Dataspace {
 }
 
 impl Dataspace {
-    /// Copies the dataspace.
+    /// Copies the dataspace, including its current selection and offset.
     pub fn copy(&self) -> Self {
         Self::from_id(h5lock!(H5Scopy(self.id()))).unwrap_or_else(|_| Self::invalid())
     }
 
+    /// Serializes the dataspace, including its extent and current selection, into bytes.
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        h5lock!({
+            let mut len: size_t = 0;
+            h5try!(H5Sencode(self.id(), ptr::null_mut(), &mut len));
+            let mut buf = vec![0_u8; len];
+            h5try!(H5Sencode(self.id(), buf.as_mut_ptr() as *mut _, &mut len));
+            Ok(buf)
+        })
+    }
+
+    /// Reconstructs a dataspace, including its selection, from bytes produced by `encode()`.
+    ///
+    /// Since the encoded form carries no overall length, only the header of the buffer can
+    /// be validated; the buffer must not be truncated.
+    pub fn decode<B: AsRef<[u8]>>(buf: B) -> Result<Self> {
+        let buf = buf.as_ref();
+        // encoded dataspaces start with the dataspace message type id
+        ensure!(buf.len() > 2 && buf[0] == 1, "invalid encoded dataspace");
+        Self::from_id(h5try!(H5Sdecode(buf.as_ptr() as *const _)))
+    }
+
     /// Select a slice (known as a 'hyperslab' in HDF5 terminology) of the Dataspace.
     /// Returns the shape of array that is capable of holding the resulting slice.
     /// Useful when you want to read a subset of a dataset.
@@ -93,6 +134,143 @@ impl Dataspace {
         Ok(shape_vec)
     }
 
+    /// Selects a hyperslab of the dataspace, replacing the current selection.
+    ///
+    /// Along each dimension, `count` blocks of `block` elements (a single element by
+    /// default) are selected, starting at `start` and spaced `stride` elements apart
+    /// (in turn defaulting to the block size). All arguments must have one entry per
+    /// dimension of the dataspace.
+    ///
+    /// The selection is kept by the dataspace, so that it can be passed to e.g.
+    /// `Container::read_selection()` for any number of datasets of the same shape.
+    pub fn select_hyperslab(
+        &self, start: &[Ix], stride: Option<&[Ix]>, count: &[Ix], block: Option<&[Ix]>,
+    ) -> Result<()> {
+        let ndim = self.ndim();
+        let to_hsize = |arg: &[Ix], name: &str| -> Result<Vec<hsize_t>> {
+            ensure!(
+                arg.len() == ndim,
+                "hyperslab {} has {} dims, dataspace has {} dims",
+                name,
+                arg.len(),
+                ndim
+            );
+            Ok(arg.iter().map(|&x| x as _).collect())
+        };
+        let start = to_hsize(start, "start")?;
+        let count = to_hsize(count, "count")?;
+        let stride = stride.map(|s| to_hsize(s, "stride")).transpose()?;
+        let block = block.map(|b| to_hsize(b, "block")).transpose()?;
+        if let Some(ref stride) = stride {
+            ensure!(stride.iter().all(|&s| s >= 1), "hyperslab stride must be >= 1");
+        }
+        h5try!(H5Sselect_hyperslab(
+            self.id(),
+            H5S_SELECT_SET,
+            start.as_ptr(),
+            stride.as_ref().map_or(ptr::null(), Vec::as_ptr),
+            count.as_ptr(),
+            block.as_ref().map_or(ptr::null(), Vec::as_ptr),
+        ));
+        Ok(())
+    }
+
+    /// Selects individual elements of the dataspace by their coordinates, replacing the
+    /// current selection.
+    ///
+    /// Each coordinate must have one entry per dimension of the dataspace; elements are
+    /// read and written in the order in which they are listed.
+    pub fn select_elements<C: AsRef<[Ix]>>(&self, coords: &[C]) -> Result<()> {
+        let ndim = self.ndim();
+        ensure!(!coords.is_empty(), "no elements to select (use select_none() instead)");
+        let mut flat: Vec<hsize_t> = Vec::with_capacity(coords.len() * ndim);
+        for coord in coords {
+            let coord = coord.as_ref();
+            ensure!(
+                coord.len() == ndim,
+                "coordinate {:?} has {} dims, dataspace has {} dims",
+                coord,
+                coord.len(),
+                ndim
+            );
+            flat.extend(coord.iter().map(|&x| x as hsize_t));
+        }
+        h5try!(H5Sselect_elements(self.id(), H5S_SELECT_SET, coords.len() as _, flat.as_ptr()));
+        Ok(())
+    }
+
+    /// Selects the entire extent of the dataspace.
+    pub fn select_all(&self) -> Result<()> {
+        h5try!(H5Sselect_all(self.id()));
+        Ok(())
+    }
+
+    /// Clears the selection, so that no elements are selected.
+    pub fn select_none(&self) -> Result<()> {
+        h5try!(H5Sselect_none(self.id()));
+        Ok(())
+    }
+
+    /// Returns the number of elements in the current selection.
+    pub fn selection_npoints(&self) -> Result<usize> {
+        to_ix(h5try!(H5Sget_select_npoints(self.id())) as _, "number of selected elements")
+    }
+
+    /// Shifts the current selection by `offset` (one entry per dimension) relative to its
+    /// original position, replacing any previously set offset.
+    ///
+    /// The offset applies to I/O and to `selection_bounds()`, which allows sliding a single
+    /// selection across a dataset; see also `with_offset()` and `is_selection_valid()`.
+    pub fn set_offset(&self, offset: &[isize]) -> Result<()> {
+        let ndim = self.ndim();
+        ensure!(
+            offset.len() == ndim,
+            "offset has {} dims, dataspace has {} dims",
+            offset.len(),
+            ndim
+        );
+        let offset: Vec<hssize_t> = offset.iter().map(|&x| x as _).collect();
+        h5try!(H5Soffset_simple(self.id(), offset.as_ptr()));
+        Ok(())
+    }
+
+    /// Copies the dataspace along with its selection, shifting the selection by `offset`.
+    pub fn with_offset(&self, offset: &[isize]) -> Result<Self> {
+        let space = Self::from_id(h5try!(H5Scopy(self.id())))?;
+        space.set_offset(offset)?;
+        Ok(space)
+    }
+
+    /// Returns `true` if the current selection, with its offset applied, lies entirely
+    /// within the extent of the dataspace.
+    pub fn is_selection_valid(&self) -> bool {
+        h5lock!(H5Sselect_valid(self.id())) > 0
+    }
+
+    /// Returns the kind of the current selection.
+    pub fn selection_type(&self) -> Result<SelectionType> {
+        Ok(match h5lock!(H5Sget_select_type(self.id())) {
+            H5S_sel_type::H5S_SEL_NONE => SelectionType::None,
+            H5S_sel_type::H5S_SEL_POINTS => SelectionType::Points,
+            H5S_sel_type::H5S_SEL_HYPERSLABS => SelectionType::Hyperslabs,
+            H5S_sel_type::H5S_SEL_ALL => SelectionType::All,
+            _ => fail!("invalid selection type"),
+        })
+    }
+
+    /// Returns the bounding box of the current selection, as a range of coordinates along
+    /// each dimension.
+    ///
+    /// Fails if nothing is selected.
+    pub fn selection_bounds(&self) -> Result<Vec<Range<Ix>>> {
+        ensure!(self.selection_npoints()? > 0, "cannot get bounds of an empty selection");
+        let ndim = self.ndim();
+        let mut start: Vec<hsize_t> = vec![0; ndim];
+        let mut end: Vec<hsize_t> = vec![0; ndim];
+        h5try!(H5Sget_select_bounds(self.id(), start.as_mut_ptr(), end.as_mut_ptr()));
+        Ok(start.into_iter().zip(end).map(|(s, e)| s as Ix..e as Ix + 1).collect())
+    }
+
     fn get_start_stride_count(v: &SliceOrIndex, len: Ix) -> Result<(u64, u64, u64)> {
         match v {
             SliceOrIndex::Slice { start, end, step } => {
@@ -123,6 +301,72 @@ impl Dataspace {
         Self::from_id(h5try!(H5Screate_simple(rank as _, dims.as_ptr(), max_dims.as_ptr())))
     }
 
+    /// Creates a null dataspace, which has no dimensions and contains no elements.
+    ///
+    /// Unlike a scalar dataspace (also having no dimensions but a single element), this
+    /// is meant for datasets/attributes which carry no data by design.
+    pub fn try_new_null() -> Result<Self> {
+        Self::from_id(h5try!(H5Screate(H5S_NULL)))
+    }
+
+    /// Returns `true` if this is a null dataspace.
+    pub fn is_null(&self) -> bool {
+        h5lock!(H5Sget_simple_extent_type(self.id())) == H5S_NULL
+    }
+
+    /// Creates a simple dataspace with the given current and maximum axis sizes.
+    pub fn try_with_extents<E: Into<Extents>>(extents: E) -> Result<Self> {
+        let extents = extents.into();
+        let mut dims: Vec<hsize_t> = vec![];
+        let mut max_dims: Vec<hsize_t> = vec![];
+        for extent in extents.as_slice() {
+            if let Some(max) = extent.max {
+                ensure!(
+                    max >= extent.dim,
+                    "maximum extent {} is smaller than current extent {}",
+                    max,
+                    extent.dim
+                );
+            }
+            dims.push(extent.dim as _);
+            max_dims.push(extent.max.map_or(H5S_UNLIMITED, |max| max as _));
+        }
+        Self::from_id(h5try!(H5Screate_simple(
+            extents.ndim() as _,
+            dims.as_ptr(),
+            max_dims.as_ptr()
+        )))
+    }
+
+    /// Returns the current and maximum axis sizes of the dataspace.
+    pub fn extents(&self) -> Extents {
+        self.dims()
+            .into_iter()
+            .zip(self.maxdims())
+            .map(|(dim, max)| Extent { dim, max: Some(max).filter(|&m| m != H5S_UNLIMITED as Ix) })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn raw_dims(&self) -> Vec<hsize_t> {
+        let ndim = self.ndim();
+        if ndim > 0 {
+            let mut dims: Vec<hsize_t> = vec![0; ndim];
+            if h5call!(H5Sget_simple_extent_dims(self.id(), dims.as_mut_ptr(), ptr::null_mut()))
+                .is_ok()
+            {
+                return dims;
+            }
+        }
+        vec![]
+    }
+
+    /// Returns the dimensions of the dataspace, failing if any of them overflows `Ix`
+    /// (which can only happen on 32-bit targets); `dims()` truncates instead.
+    pub fn try_dims(&self) -> Result<Vec<Ix>> {
+        self.raw_dims().into_iter().map(|dim| to_ix(dim, "dataspace dimension")).collect()
+    }
+
     pub fn maxdims(&self) -> Vec<Ix> {
         let ndim = self.ndim();
         if ndim > 0 {
@@ -139,8 +383,10 @@ impl Dataspace {
         vec![]
     }
 
+    /// Returns `true` if any axis is unlimited; see `extents().is_resizable()` for axes
+    /// with a bounded maximum size.
     pub fn resizable(&self) -> bool {
-        self.maxdims().iter().any(|&x| x == H5S_UNLIMITED as _)
+        self.maxdims().iter().any(|&x| x == H5S_UNLIMITED as Ix)
     }
 }
 
@@ -150,24 +396,32 @@ impl Dimension for Dataspace {
     }
 
     fn dims(&self) -> Vec<Ix> {
-        let ndim = self.ndim();
-        if ndim > 0 {
-            let mut dims: Vec<hsize_t> = Vec::with_capacity(ndim);
-            unsafe {
-                dims.set_len(ndim);
-            }
-            if h5call!(H5Sget_simple_extent_dims(self.id(), dims.as_mut_ptr(), ptr::null_mut()))
-                .is_ok()
-            {
-                return dims.iter().cloned().map(|x| x as _).collect();
-            }
+        self.raw_dims().iter().map(|&x| x as _).collect()
+    }
+
+    fn try_size(&self) -> Result<Ix> {
+        if self.is_null() {
+            return Ok(0);
         }
-        vec![]
+        let dims = self.raw_dims();
+        match dims.iter().try_fold(1_u64, |size, &dim| size.checked_mul(dim)) {
+            Some(size) => to_ix(size, "number of elements"),
+            None => fail!("number of elements in dataspace {:?} overflows u64", dims),
+        }
+    }
+}
+
+/// Converts a 64-bit count to `Ix`, which is narrower on 32-bit targets.
+fn to_ix(value: u64, what: &str) -> Result<Ix> {
+    if value > Ix::max_value() as u64 {
+        fail!("{} ({}) overflows usize", what, value);
     }
+    Ok(value as _)
 }
 
 #[cfg(test)]
 pub mod tests {
+    use super::SelectionType;
     use crate::internal_prelude::*;
 
     #[test]
@@ -186,6 +440,21 @@ pub mod tests {
         assert_eq!(f(&(3, 4)), (2, vec![3, 4], 12));
         assert_eq!(f(vec![2, 3]), (2, vec![2, 3], 6));
         assert_eq!(f(&vec![4, 5]), (2, vec![4, 5], 20));
+        assert_eq!(f([2, 3, 4]), (3, vec![2, 3, 4], 24));
+        assert_eq!(f(&[1_usize, 2][..]), (2, vec![1, 2], 2));
+        assert_eq!(f(&[3_u64, 4][..]), (2, vec![3, 4], 12));
+        assert_eq!(f(2..7), (1, vec![5], 5));
+        #[allow(clippy::reversed_empty_ranges)]
+        assert_eq!(f(7..2), (1, vec![0], 0));
+        assert_eq!(f(1..=3), (1, vec![3], 3));
+        assert_eq!(f(3..=3), (1, vec![1], 1));
+        assert_eq!(f(std::ops::RangeInclusive::new(3, 2)), (1, vec![0], 0));
+        assert_eq!(f(..4), (1, vec![4], 4));
+        assert_eq!(f(ndarray::Ix0()), (0, vec![], 1));
+        assert_eq!(f(ndarray::Ix2(2, 3)), (2, vec![2, 3], 6));
+        assert_eq!(f(ndarray::IxDyn(&[4, 1, 2])), (3, vec![4, 1, 2], 8));
+        let arr = ndarray::Array3::<u8>::zeros((1, 2, 3));
+        assert_eq!(f(arr.raw_dim()), (3, vec![1, 2, 3], 6));
     }
 
     #[test]
@@ -209,7 +478,7 @@ pub mod tests {
         let d = Dataspace::try_new((5, 6), true).unwrap();
         assert_eq!((d.ndim(), d.dims(), d.size()), (2, vec![5, 6], 30));
 
-        assert_eq!(Dataspace::try_new((), true).unwrap().dims(), vec![]);
+        assert_eq!(Dataspace::try_new((), true).unwrap().dims(), Vec::<Ix>::new());
 
         assert_err!(Dataspace::from_id(H5I_INVALID_HID), "Invalid dataspace id");
 
@@ -222,8 +491,110 @@ pub mod tests {
         assert_eq!(Dataspace::try_new((5, 6), false).unwrap().resizable(), false);
         assert_eq!(
             Dataspace::try_new((5, 6), true).unwrap().maxdims(),
-            vec![H5S_UNLIMITED as _, H5S_UNLIMITED as _]
+            vec![H5S_UNLIMITED as Ix, H5S_UNLIMITED as Ix]
         );
         assert_eq!(Dataspace::try_new((5, 6), true).unwrap().resizable(), true);
     }
+
+    #[test]
+    pub fn test_null() -> Result<()> {
+        let null = Dataspace::try_new_null()?;
+        assert!(null.is_null());
+        assert_eq!((null.ndim(), null.dims(), null.size()), (0, vec![], 0));
+        assert_eq!(null.maxdims(), Vec::<Ix>::new());
+        assert_eq!(format!("{:?}", null), "<HDF5 dataspace: null>");
+        assert!(Dataspace::decode(null.encode()?)?.is_null());
+
+        let scalar = Dataspace::try_new((), false)?;
+        assert!(!scalar.is_null());
+        assert_eq!(scalar.size(), 1);
+        assert!(!Dataspace::try_new(0, false)?.is_null());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_selection_introspection() -> Result<()> {
+        let space = Dataspace::try_new((4, 5), false)?;
+        assert_eq!(space.selection_type()?, SelectionType::All);
+        assert_eq!(space.selection_npoints()?, 20);
+        assert_eq!(space.selection_bounds()?, vec![0..4, 0..5]);
+
+        space.select_hyperslab(&[1, 0], Some(&[2, 3]), &[2, 2], None)?;
+        assert_eq!(space.selection_type()?, SelectionType::Hyperslabs);
+        assert_eq!(space.selection_npoints()?, 4);
+        assert_eq!(space.selection_bounds()?, vec![1..4, 0..4]);
+
+        space.select_elements(&[[3, 1], [0, 2]])?;
+        assert_eq!(space.selection_type()?, SelectionType::Points);
+        assert_eq!(space.selection_bounds()?, vec![0..4, 1..3]);
+
+        space.select_none()?;
+        assert_eq!(space.selection_type()?, SelectionType::None);
+        assert_eq!(space.selection_npoints()?, 0);
+        assert_err!(space.selection_bounds(), "cannot get bounds of an empty selection");
+
+        let scalar = Dataspace::try_new((), false)?;
+        assert_eq!(scalar.selection_bounds()?, vec![]);
+        assert_eq!(Dataspace::try_new_null()?.selection_npoints()?, 0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_offset() -> Result<()> {
+        let space = Dataspace::try_new((4, 6), false)?;
+        space.select_hyperslab(&[0, 0], None, &[2, 3], None)?;
+        let shifted = space.with_offset(&[2, 3])?;
+        assert_eq!(shifted.selection_bounds()?, vec![2..4, 3..6]);
+        assert!(shifted.is_selection_valid());
+        assert_eq!(space.selection_bounds()?, vec![0..2, 0..3]);
+
+        let copy = shifted.copy();
+        assert_eq!(copy.selection_bounds()?, vec![2..4, 3..6]);
+        copy.set_offset(&[3, 0])?;
+        assert!(!copy.is_selection_valid());
+        copy.set_offset(&[-1, 0])?;
+        assert!(!copy.is_selection_valid());
+        copy.set_offset(&[0, 0])?;
+        assert_eq!(copy.selection_bounds()?, vec![0..2, 0..3]);
+
+        assert_err!(space.set_offset(&[1]), "offset has 1 dims, dataspace has 2 dims");
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_try_size() -> Result<()> {
+        let space = Dataspace::try_new((1 << 20, 1 << 20, 1 << 20), false)?;
+        assert_eq!(space.try_dims()?, vec![1 << 20, 1 << 20, 1 << 20]);
+        assert_eq!(space.try_size()?, 1 << 60);
+        let space = Dataspace::try_new((1 << 40, 1 << 40), false)?;
+        assert_err!(
+            space.try_size(),
+            "number of elements in dataspace [1099511627776, 1099511627776] overflows u64"
+        );
+        assert_eq!(Dataspace::try_new_null()?.try_size()?, 0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_encode_decode() -> Result<()> {
+        let space = Dataspace::try_new((4, 5), true)?;
+        space.select_hyperslab(&[1, 1], None, &[2, 3], None)?;
+        let buf = space.encode()?;
+        let decoded = Dataspace::decode(&buf)?;
+        assert_ne!(decoded.id(), space.id());
+        assert_eq!(decoded.dims(), vec![4, 5]);
+        assert_eq!(decoded.maxdims(), space.maxdims());
+        assert_eq!(decoded.selection_npoints()?, 6);
+        assert_eq!(decoded.encode()?, buf);
+
+        space.select_elements(&[[0, 0], [3, 4]])?;
+        assert_eq!(Dataspace::decode(space.encode()?)?.selection_npoints()?, 2);
+
+        let scalar = Dataspace::try_new((), false)?;
+        assert_eq!(Dataspace::decode(scalar.encode()?)?.ndim(), 0);
+
+        assert_err!(Dataspace::decode(&[]), "invalid encoded dataspace");
+        assert_err!(Dataspace::decode(&[3, 0, 8, 0]), "invalid encoded dataspace");
+        Ok(())
+    }
 }
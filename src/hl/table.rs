@@ -0,0 +1,380 @@
+//! Tables following the HDF5 table specification (requires the `hl` feature).
+//!
+//! A table is a one-dimensional chunked dataset of a compound type whose members are the
+//! fields of the table, annotated with attributes (title, field names) so that other
+//! applications using the HDF5 table API (e.g. PyTables, HDFView) can read it. Records
+//! are typed via `H5Type`: a record type has to be a compound type with fields of the
+//! same names and types as the table fields, in any order.
+
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::ptr;
+
+use hdf5_sys::h5tb::{
+    H5TBappend_records, H5TBdelete_record, H5TBget_table_info, H5TBinsert_record, H5TBmake_table,
+    H5TBread_fields_name, H5TBread_records, H5TBwrite_fields_name, H5TBwrite_records,
+};
+use hdf5_types::{CompoundField, TypeDescriptor};
+
+use crate::internal_prelude::*;
+
+/// Offsets and sizes of the table fields within a record type, in table field order.
+struct RecordLayout {
+    size: usize,
+    offsets: Vec<size_t>,
+    sizes: Vec<size_t>,
+}
+
+fn record_fields<T: H5Type>() -> Result<(usize, Vec<CompoundField>)> {
+    match T::type_descriptor() {
+        TypeDescriptor::Compound(ct) => {
+            let mut fields = ct.fields;
+            fields.sort_by_key(|f| f.index);
+            Ok((ct.size, fields))
+        }
+        _ => fail!("expected a compound record type"),
+    }
+}
+
+/// Builder used to create tables.
+#[derive(Clone)]
+pub struct TableBuilder<T> {
+    parent: Group,
+    title: String,
+    chunk_size: usize,
+    compress: bool,
+    _marker: PhantomData<T>,
+}
+
+impl<T: H5Type> TableBuilder<T> {
+    /// Creates a new table builder bound to the parent group.
+    pub fn new(parent: &Group) -> Self {
+        Self {
+            parent: parent.clone(),
+            title: String::new(),
+            chunk_size: 1024,
+            compress: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets the title of the table (empty by default).
+    pub fn title(&mut self, title: &str) -> &mut Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets the number of records per chunk (1024 by default).
+    pub fn chunk_size(&mut self, chunk_size: usize) -> &mut Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Enables deflate compression of the table.
+    pub fn compress(&mut self, compress: bool) -> &mut Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Creates an empty table.
+    pub fn create(&self, name: &str) -> Result<Table> {
+        self.with_data(name, &[])
+    }
+
+    /// Creates a table filled with the provided records; field names and types are taken
+    /// from the record type.
+    pub fn with_data(&self, name: &str, records: &[T]) -> Result<Table> {
+        ensure!(self.chunk_size > 0, "chunk size must be positive");
+        let (size, fields) = record_fields::<T>()?;
+        let names =
+            fields.iter().map(|f| to_cstring(f.name.as_str())).collect::<Result<Vec<_>>>()?;
+        let mut name_ptrs: Vec<_> = names.iter().map(|name| name.as_ptr()).collect();
+        let offsets: Vec<size_t> = fields.iter().map(|f| f.offset as _).collect();
+        let types =
+            fields.iter().map(|f| Datatype::from_descriptor(&f.ty)).collect::<Result<Vec<_>>>()?;
+        let type_ids: Vec<hid_t> = types.iter().map(|t| t.id()).collect();
+        let (c_title, c_name) = (to_cstring(self.title.as_str())?, to_cstring(name)?);
+        let buf = if records.is_empty() { ptr::null() } else { records.as_ptr() as *const _ };
+        h5try!(H5TBmake_table(
+            c_title.as_ptr(),
+            self.parent.id(),
+            c_name.as_ptr(),
+            fields.len() as _,
+            records.len() as _,
+            size as _,
+            name_ptrs.as_mut_ptr(),
+            offsets.as_ptr(),
+            type_ids.as_ptr(),
+            self.chunk_size as _,
+            ptr::null_mut(),
+            self.compress as _,
+            buf
+        ));
+        self.parent.table(name)
+    }
+}
+
+/// A table stored according to the HDF5 table specification.
+#[derive(Clone)]
+pub struct Table {
+    parent: Group,
+    name: CString,
+    dataset: Dataset,
+}
+
+impl Table {
+    /// Returns the underlying dataset.
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+
+    /// Returns the title of the table.
+    pub fn title(&self) -> Result<String> {
+        self.dataset.attr("TITLE")?.read_string()
+    }
+
+    /// Returns the names of the table fields.
+    pub fn field_names(&self) -> Result<Vec<String>> {
+        let members = self.dataset.dtype()?.compound_members()?;
+        Ok(members.into_iter().map(|m| m.name).collect())
+    }
+
+    /// Returns the number of records in the table.
+    pub fn len(&self) -> Result<usize> {
+        let (mut nfields, mut nrecords) = (0, 0);
+        h5try!(H5TBget_table_info(
+            self.parent.id(),
+            self.name.as_ptr(),
+            &mut nfields,
+            &mut nrecords
+        ));
+        Ok(nrecords as _)
+    }
+
+    /// Returns `true` if the table has no records.
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|len| len == 0)
+    }
+
+    /// Matches the fields of a record type to the given table fields by name.
+    fn layout<T: H5Type>(&self, names: Option<&[&str]>) -> Result<RecordLayout> {
+        let members = self.dataset.dtype()?.compound_members()?;
+        let member = |name: &str| match members.iter().find(|m| m.name == name) {
+            Some(member) => Ok(member),
+            None => fail!("table has no field {:?}", name),
+        };
+        let members = match names {
+            Some(names) => names.iter().map(|&name| member(name)).collect::<Result<Vec<_>>>()?,
+            None => members.iter().collect(),
+        };
+        let (size, fields) = match (T::type_descriptor(), names) {
+            (TypeDescriptor::Compound(ct), _) => (ct.size, ct.fields),
+            (ty, Some(&[name])) => (ty.size(), vec![CompoundField::new(name, ty, 0, 0)]),
+            _ => fail!("expected a compound record type"),
+        };
+        ensure!(
+            fields.len() == members.len(),
+            "record type has {} fields, expected {}",
+            fields.len(),
+            members.len()
+        );
+        let mut layout = RecordLayout { size, offsets: vec![], sizes: vec![] };
+        for member in members {
+            let field = match fields.iter().find(|f| f.name == member.name) {
+                Some(field) => field,
+                None => fail!("record type has no field {:?}", member.name),
+            };
+            let stored = member.datatype.to_descriptor()?;
+            ensure!(
+                stored == field.ty,
+                "field {:?} is stored as {:?}, record type has {:?}",
+                member.name,
+                stored,
+                field.ty
+            );
+            layout.offsets.push(field.offset as _);
+            layout.sizes.push(field.ty.size() as _);
+        }
+        Ok(layout)
+    }
+
+    fn check_range(&self, start: usize, count: usize) -> Result<()> {
+        let len = self.len()?;
+        ensure!(
+            start.checked_add(count).map_or(false, |end| end <= len),
+            "records {}..{} are out of bounds for table of {} records",
+            start,
+            start.saturating_add(count),
+            len
+        );
+        Ok(())
+    }
+
+    /// Appends records to the end of the table.
+    pub fn append<T: H5Type>(&self, records: &[T]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let layout = self.layout::<T>(None)?;
+        h5try!(H5TBappend_records(
+            self.parent.id(),
+            self.name.as_ptr(),
+            records.len() as _,
+            layout.size as _,
+            layout.offsets.as_ptr(),
+            layout.sizes.as_ptr(),
+            records.as_ptr() as *const _
+        ));
+        Ok(())
+    }
+
+    /// Overwrites records starting at the given index; the table is not extended.
+    pub fn write<T: H5Type>(&self, start: usize, records: &[T]) -> Result<()> {
+        let layout = self.layout::<T>(None)?;
+        self.check_range(start, records.len())?;
+        if records.is_empty() {
+            return Ok(());
+        }
+        h5try!(H5TBwrite_records(
+            self.parent.id(),
+            self.name.as_ptr(),
+            start as _,
+            records.len() as _,
+            layout.size as _,
+            layout.offsets.as_ptr(),
+            layout.sizes.as_ptr(),
+            records.as_ptr() as *const _
+        ));
+        Ok(())
+    }
+
+    /// Inserts records before the given index, shifting the following records.
+    pub fn insert<T: H5Type>(&self, start: usize, records: &[T]) -> Result<()> {
+        let layout = self.layout::<T>(None)?;
+        self.check_range(start, 0)?;
+        if records.is_empty() {
+            return Ok(());
+        }
+        h5try!(H5TBinsert_record(
+            self.parent.id(),
+            self.name.as_ptr(),
+            start as _,
+            records.len() as _,
+            layout.size as _,
+            layout.offsets.as_ptr(),
+            layout.sizes.as_ptr(),
+            records.as_ptr() as *mut _
+        ));
+        Ok(())
+    }
+
+    /// Deletes `count` records starting at the given index, shifting the following
+    /// records.
+    pub fn delete(&self, start: usize, count: usize) -> Result<()> {
+        self.check_range(start, count)?;
+        if count == 0 {
+            return Ok(());
+        }
+        h5try!(H5TBdelete_record(self.parent.id(), self.name.as_ptr(), start as _, count as _));
+        Ok(())
+    }
+
+    /// Reads all records of the table.
+    pub fn read<T: H5Type>(&self) -> Result<Vec<T>> {
+        self.read_records(0, self.len()?)
+    }
+
+    /// Reads `count` records starting at the given index.
+    pub fn read_records<T: H5Type>(&self, start: usize, count: usize) -> Result<Vec<T>> {
+        let layout = self.layout::<T>(None)?;
+        self.check_range(start, count)?;
+        let mut records = Vec::with_capacity(count);
+        if count > 0 {
+            h5try!(H5TBread_records(
+                self.parent.id(),
+                self.name.as_ptr(),
+                start as _,
+                count as _,
+                layout.size as _,
+                layout.offsets.as_ptr(),
+                layout.sizes.as_ptr(),
+                records.as_mut_ptr() as *mut _
+            ));
+            unsafe { records.set_len(count) };
+        }
+        Ok(records)
+    }
+
+    /// Reads a subset of fields of `count` records starting at the given index.
+    ///
+    /// The record type has to be a compound type with fields matching the selected
+    /// fields by name, unless a single field is selected, in which case it may also be
+    /// the type of the field itself.
+    pub fn read_fields<T: H5Type>(
+        &self, fields: &[&str], start: usize, count: usize,
+    ) -> Result<Vec<T>> {
+        let layout = self.layout::<T>(Some(fields))?;
+        self.check_range(start, count)?;
+        let c_fields = to_cstring(fields.join(","))?;
+        let mut records = Vec::with_capacity(count);
+        if count > 0 {
+            h5try!(H5TBread_fields_name(
+                self.parent.id(),
+                self.name.as_ptr(),
+                c_fields.as_ptr(),
+                start as _,
+                count as _,
+                layout.size as _,
+                layout.offsets.as_ptr(),
+                layout.sizes.as_ptr(),
+                records.as_mut_ptr() as *mut _
+            ));
+            unsafe { records.set_len(count) };
+        }
+        Ok(records)
+    }
+
+    /// Overwrites a subset of fields of records starting at the given index; see
+    /// `read_fields()` for the requirements on the record type.
+    pub fn write_fields<T: H5Type>(
+        &self, fields: &[&str], start: usize, records: &[T],
+    ) -> Result<()> {
+        let layout = self.layout::<T>(Some(fields))?;
+        self.check_range(start, records.len())?;
+        if records.is_empty() {
+            return Ok(());
+        }
+        let c_fields = to_cstring(fields.join(","))?;
+        h5try!(H5TBwrite_fields_name(
+            self.parent.id(),
+            self.name.as_ptr(),
+            c_fields.as_ptr(),
+            start as _,
+            records.len() as _,
+            layout.size as _,
+            layout.offsets.as_ptr(),
+            layout.sizes.as_ptr(),
+            records.as_ptr() as *const _
+        ));
+        Ok(())
+    }
+}
+
+impl Group {
+    /// Instantiates a new table builder.
+    pub fn new_table<T: H5Type>(&self) -> TableBuilder<T> {
+        TableBuilder::new(self)
+    }
+
+    /// Opens an existing table in the group.
+    pub fn table(&self, name: &str) -> Result<Table> {
+        let dataset = self.dataset(name)?;
+        ensure!(
+            dataset.attr_opt("CLASS")?.map(|attr| attr.read_string()).transpose()?.as_deref()
+                == Some("TABLE"),
+            "dataset {:?} is not a table",
+            name
+        );
+        Ok(Table { parent: self.clone(), name: to_cstring(name)?, dataset })
+    }
+}
@@ -0,0 +1,613 @@
+//! JSON export and import of attributes and small datasets (requires the `json` feature).
+//!
+//! Values are converted to JSON objects annotated with their datatype and shape, so that
+//! they can be stored back with the same types, e.g.
+//!
+//! ```text
+//! {"dtype": "u16", "shape": [2, 3], "value": [[1, 2, 3], [4, 5, 6]]}
+//! ```
+//!
+//! with the value nested according to the shape (a scalar value isn't nested at all, and
+//! the shape and value of a null dataspace are `null`). Datatypes are named as in
+//! `describe`: `bool`, `i8` to `i128`, `u8` to `u128`, `f32`, `f64`, `utf8` and `ascii` for
+//! variable-length strings and `utf8(N)` and `ascii(N)` for fixed-length strings of `N`
+//! bytes; enums are written as `{"enum": "u8", "members": [["RED", 0], ...]}`, compounds
+//! as `{"compound": [["x", "f64"], ...]}` and fixed-size arrays as
+//! `{"array": "f32", "len": 3}`. Variable-length arrays and opaque, bitfield and
+//! reference types are not supported.
+//!
+//! Enum values are written as member names (or as numbers if they match no member),
+//! compound values as objects and fixed-size arrays as arrays. Non-finite floats, which
+//! JSON can't represent, are written as the strings `"NaN"`, `"inf"` and `"-inf"`.
+//!
+//! When importing attributes, plain JSON values without annotations are accepted too, so
+//! that metadata can be written by hand: booleans, integers (stored as `i64`, or `u64` if
+//! too large), other numbers (`f64`) and strings (`utf8`), and arrays of them, nested
+//! arrays becoming multi-dimensional attributes.
+
+use std::convert::TryFrom;
+use std::ffi::CString;
+
+use serde_json::{Map, Number, Value};
+
+use hdf5_sys::h5a::{H5Acreate2, H5Awrite};
+use hdf5_sys::h5d::{H5Dcreate2, H5Dwrite};
+use hdf5_types::{
+    CompoundField, CompoundType, DynValue, EnumMember, EnumType, FloatSize, IntSize,
+    TypeDescriptor as TD,
+};
+
+use crate::describe::type_name;
+use crate::internal_prelude::*;
+
+fn int_size(bits: &str) -> Option<IntSize> {
+    match bits {
+        "8" => Some(IntSize::U1),
+        "16" => Some(IntSize::U2),
+        "32" => Some(IntSize::U4),
+        "64" => Some(IntSize::U8),
+        "128" => Some(IntSize::U16),
+        _ => None,
+    }
+}
+
+/// Parses a simple datatype name such as `i32` or `utf8(10)`.
+fn parse_type_name(name: &str) -> Option<TD> {
+    let fixed_len = |prefix: &str| {
+        let len = name.strip_prefix(prefix)?.strip_prefix('(')?.strip_suffix(')')?;
+        len.parse::<usize>().ok()
+    };
+    Some(match name {
+        "bool" => TD::Boolean,
+        "f32" => TD::Float(FloatSize::U4),
+        "f64" => TD::Float(FloatSize::U8),
+        "utf8" => TD::VarLenUnicode,
+        "ascii" => TD::VarLenAscii,
+        _ if name.starts_with('i') => TD::Integer(int_size(&name[1..])?),
+        _ if name.starts_with('u') && !name.starts_with("utf8") => {
+            TD::Unsigned(int_size(&name[1..])?)
+        }
+        _ if name.starts_with("utf8") => TD::FixedUnicode(fixed_len("utf8")?),
+        _ if name.starts_with("ascii") => TD::FixedAscii(fixed_len("ascii")?),
+        _ => return None,
+    })
+}
+
+/// Returns the JSON annotation of a datatype, see the module documentation.
+fn dtype_to_json(ty: &TD) -> Result<Value> {
+    Ok(match ty {
+        TD::Integer(_)
+        | TD::Unsigned(_)
+        | TD::Float(_)
+        | TD::Boolean
+        | TD::FixedAscii(_)
+        | TD::FixedUnicode(_)
+        | TD::VarLenAscii
+        | TD::VarLenUnicode => Value::String(type_name(ty)),
+        TD::Enum(enum_type) => {
+            let members = enum_type.members.iter().map(|member| {
+                let value = if enum_type.signed {
+                    // values of signed enums read from files aren't sign-extended
+                    let shift = 64_usize.saturating_sub(enum_type.size as usize * 8);
+                    Value::from(((member.value << shift) as i64) >> shift)
+                } else {
+                    Value::from(member.value)
+                };
+                Value::Array(vec![member.name.clone().into(), value])
+            });
+            let mut map = Map::new();
+            map.insert("enum".into(), type_name(&enum_type.base_type()).into());
+            map.insert("members".into(), Value::Array(members.collect()));
+            Value::Object(map)
+        }
+        TD::Compound(compound) => {
+            let mut fields = compound.fields.clone();
+            fields.sort_by_key(|field| field.index);
+            let fields = fields
+                .iter()
+                .map(|field| {
+                    Ok(Value::Array(vec![field.name.clone().into(), dtype_to_json(&field.ty)?]))
+                })
+                .collect::<Result<_>>()?;
+            let mut map = Map::new();
+            map.insert("compound".into(), Value::Array(fields));
+            Value::Object(map)
+        }
+        TD::FixedArray(ty, len) => {
+            let mut map = Map::new();
+            map.insert("array".into(), dtype_to_json(ty)?);
+            map.insert("len".into(), Value::from(*len));
+            Value::Object(map)
+        }
+        _ => fail!("unsupported datatype for JSON: {}", type_name(ty)),
+    })
+}
+
+/// Parses the JSON annotation of a datatype, see the module documentation.
+fn dtype_from_json(json: &Value) -> Result<TD> {
+    let invalid = || format!("invalid datatype: {}", json);
+    if let Value::String(name) = json {
+        return parse_type_name(name).ok_or_else(invalid).map_err(Into::into);
+    }
+    let map = json.as_object().ok_or_else(invalid)?;
+    let ty = if let Some(base) = map.get("enum") {
+        let (size, signed) = match dtype_from_json(base)? {
+            TD::Integer(size) => (size, true),
+            TD::Unsigned(size) => (size, false),
+            _ => fail!("invalid enum base type: {}", base),
+        };
+        let members = map.get("members").and_then(Value::as_array).ok_or_else(invalid)?;
+        let members = members
+            .iter()
+            .map(|member| match member.as_array().map(Vec::as_slice) {
+                Some([Value::String(name), value]) => {
+                    let value = value.as_u64().or_else(|| value.as_i64().map(|v| v as u64));
+                    Some(EnumMember { name: name.clone(), value: value? })
+                }
+                _ => None,
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(invalid)?;
+        TD::Enum(EnumType { size, signed, members })
+    } else if let Some(fields) = map.get("compound") {
+        let fields = fields.as_array().ok_or_else(invalid)?;
+        let mut compound = CompoundType { fields: Vec::with_capacity(fields.len()), size: 0 };
+        for (index, field) in fields.iter().enumerate() {
+            match field.as_array().map(Vec::as_slice) {
+                Some([Value::String(name), ty]) => {
+                    compound.fields.push(CompoundField::new(name, dtype_from_json(ty)?, 0, index));
+                }
+                _ => fail!(invalid()),
+            }
+        }
+        TD::Compound(compound.to_c_repr())
+    } else if let Some(base) = map.get("array") {
+        let len = map.get("len").and_then(Value::as_u64).ok_or_else(invalid)?;
+        TD::FixedArray(Box::new(dtype_from_json(base)?), len as _)
+    } else {
+        fail!(invalid())
+    };
+    Ok(ty.to_c_repr())
+}
+
+/// Converts a value of type `ty` to JSON.
+fn value_to_json(value: &DynValue, ty: &TD) -> Result<Value> {
+    Ok(match (value, ty) {
+        (DynValue::Bool(v), _) => Value::Bool(*v),
+        (DynValue::Int(v), _) | (DynValue::Enum { name: None, value: v }, _) => {
+            i64::try_from(*v).map(Value::from).map_err(|_| format!("{} is out of range", v))?
+        }
+        (DynValue::UInt(v), _) => {
+            u64::try_from(*v).map(Value::from).map_err(|_| format!("{} is out of range", v))?
+        }
+        (DynValue::Float(v), _) if v.is_nan() => "NaN".into(),
+        (DynValue::Float(v), _) if v.is_infinite() => (if *v > 0. { "inf" } else { "-inf" }).into(),
+        (DynValue::Float(v), TD::Float(FloatSize::U4)) => {
+            // use the shortest representation of the single-precision value
+            let v: f64 = (*v as f32).to_string().parse().unwrap_or(*v);
+            Value::Number(Number::from_f64(v).unwrap())
+        }
+        (DynValue::Float(v), _) => Value::Number(Number::from_f64(*v).unwrap()),
+        (DynValue::Enum { name: Some(name), .. }, _) | (DynValue::String(name), _) => {
+            Value::String(name.clone())
+        }
+        (DynValue::Array(items), TD::FixedArray(ty, _)) => {
+            Value::Array(items.iter().map(|item| value_to_json(item, ty)).collect::<Result<_>>()?)
+        }
+        (DynValue::Compound(values), TD::Compound(compound)) => {
+            let mut map = Map::new();
+            for (name, value) in values {
+                let field = compound.fields.iter().find(|field| &field.name == name).unwrap();
+                map.insert(name.clone(), value_to_json(value, &field.ty)?);
+            }
+            Value::Object(map)
+        }
+        _ => fail!("unsupported datatype for JSON: {}", type_name(ty)),
+    })
+}
+
+macro_rules! write_int {
+    ($buf:expr, $value:expr, $size:expr, $($variant:ident => $ty:ty),*) => {
+        match $size {
+            $(IntSize::$variant => {
+                let converted = <$ty>::try_from($value).ok()?;
+                write_bytes($buf, &converted.to_ne_bytes());
+            })*
+        }
+    };
+}
+
+fn write_bytes(buf: &mut [u8], bytes: &[u8]) {
+    buf[..bytes.len()].copy_from_slice(bytes);
+}
+
+fn write_signed(buf: &mut [u8], value: i128, size: IntSize) -> Option<()> {
+    write_int!(buf, value, size, U1 => i8, U2 => i16, U4 => i32, U8 => i64, U16 => i128);
+    Some(())
+}
+
+fn write_unsigned(buf: &mut [u8], value: u128, size: IntSize) -> Option<()> {
+    write_int!(buf, value, size, U1 => u8, U2 => u16, U4 => u32, U8 => u64, U16 => u128);
+    Some(())
+}
+
+fn parse_float(json: &Value) -> Option<f64> {
+    match json {
+        Value::String(s) if s == "NaN" => Some(f64::NAN),
+        Value::String(s) if s == "inf" => Some(f64::INFINITY),
+        Value::String(s) if s == "-inf" => Some(f64::NEG_INFINITY),
+        _ => json.as_f64(),
+    }
+}
+
+/// Encodes a JSON value of type `ty` into `buf`, returning `None` if it isn't valid for
+/// the type. Variable-length strings are stored as pointers into `strings`.
+fn encode(json: &Value, ty: &TD, buf: &mut [u8], strings: &mut Vec<CString>) -> Option<()> {
+    match ty {
+        TD::Integer(size) => write_signed(buf, json.as_i64()?.into(), *size)?,
+        TD::Unsigned(size) => write_unsigned(buf, json.as_u64()?.into(), *size)?,
+        TD::Float(FloatSize::U4) => write_bytes(buf, &(parse_float(json)? as f32).to_ne_bytes()),
+        TD::Float(FloatSize::U8) => write_bytes(buf, &parse_float(json)?.to_ne_bytes()),
+        TD::Boolean => buf[0] = json.as_bool()? as u8,
+        TD::Enum(enum_type) => {
+            let value = match json {
+                Value::String(name) => {
+                    enum_type.members.iter().find(|member| &member.name == name)?.value
+                }
+                _ => json.as_u64().or_else(|| json.as_i64().map(|v| v as u64))?,
+            };
+            if enum_type.signed {
+                write_signed(buf, (value as i64).into(), enum_type.size)?;
+            } else {
+                write_unsigned(buf, value.into(), enum_type.size)?;
+            }
+        }
+        TD::Compound(compound) => {
+            let map = json.as_object()?;
+            for field in &compound.fields {
+                encode(map.get(&field.name)?, &field.ty, &mut buf[field.offset..], strings)?;
+            }
+        }
+        TD::FixedArray(ty, len) => {
+            let items = json.as_array().filter(|items| items.len() == *len)?;
+            for (i, item) in items.iter().enumerate() {
+                encode(item, ty, &mut buf[i * ty.size()..], strings)?;
+            }
+        }
+        TD::FixedAscii(len) | TD::FixedUnicode(len) => {
+            let text = json.as_str()?;
+            if text.len() > *len || (*ty == TD::FixedAscii(*len) && !text.is_ascii()) {
+                return None;
+            }
+            write_bytes(buf, text.as_bytes());
+        }
+        TD::VarLenAscii | TD::VarLenUnicode => {
+            let text = json.as_str()?;
+            if *ty == TD::VarLenAscii && !text.is_ascii() {
+                return None;
+            }
+            let string = CString::new(text).ok()?;
+            write_bytes(buf, &(string.as_ptr() as usize).to_ne_bytes());
+            strings.push(string);
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+/// Nests flat values in arrays according to the shape.
+fn nest(values: &mut impl Iterator<Item = Value>, shape: &[Ix]) -> Value {
+    match shape.split_first() {
+        None => values.next().unwrap_or(Value::Null),
+        Some((&len, rest)) => Value::Array((0..len).map(|_| nest(values, rest)).collect()),
+    }
+}
+
+/// Flattens values nested according to the shape.
+fn flatten<'a>(json: &'a Value, shape: &[Ix], values: &mut Vec<&'a Value>) -> Result<()> {
+    match shape.split_first() {
+        None => values.push(json),
+        Some((&len, rest)) => match json.as_array() {
+            Some(items) if items.len() == len => {
+                for item in items {
+                    flatten(item, rest, values)?;
+                }
+            }
+            _ => fail!("value doesn't match shape {:?}: {}", shape, json),
+        },
+    }
+    Ok(())
+}
+
+/// Infers the shape of plain nested arrays from their first elements.
+fn infer_shape(json: &Value) -> Vec<Ix> {
+    let mut shape = Vec::new();
+    let mut json = json;
+    while let Some(items) = json.as_array() {
+        shape.push(items.len());
+        match items.first() {
+            Some(first) => json = first,
+            None => break,
+        }
+    }
+    shape
+}
+
+/// Infers the datatype of plain JSON values, see the module documentation.
+fn infer_dtype(values: &[&Value]) -> Result<TD> {
+    let all = |f: fn(&Value) -> bool| values.iter().all(|v| f(v));
+    Ok(if all(Value::is_boolean) {
+        TD::Boolean
+    } else if all(Value::is_i64) {
+        TD::Integer(IntSize::U8)
+    } else if all(Value::is_u64) {
+        TD::Unsigned(IntSize::U8)
+    } else if all(Value::is_number) {
+        TD::Float(FloatSize::U8)
+    } else if all(Value::is_string) {
+        TD::VarLenUnicode
+    } else {
+        fail!("can't infer a datatype for {:?} without a dtype annotation", values)
+    })
+}
+
+/// A value parsed from its JSON representation, ready to be written.
+struct Parsed {
+    dtype: Datatype,
+    space: Dataspace,
+    buf: Vec<u8>,
+    _strings: Vec<CString>,
+}
+
+impl Parsed {
+    fn new(json: &Value) -> Result<Self> {
+        let annotated = json.as_object().filter(|map| map.contains_key("dtype"));
+        let (ty, shape, value) = match annotated {
+            Some(map) => {
+                let ty = dtype_from_json(&map["dtype"])?;
+                let value = map.get("value").unwrap_or(&Value::Null);
+                let shape = match map.get("shape") {
+                    Some(Value::Null) => {
+                        let dtype = Datatype::from_descriptor(&ty)?;
+                        let space = Dataspace::try_new_null()?;
+                        return Ok(Self { dtype, space, buf: vec![], _strings: vec![] });
+                    }
+                    Some(shape) => {
+                        let invalid = || format!("invalid shape: {}", shape);
+                        let shape = shape.as_array().ok_or_else(invalid)?;
+                        let shape = shape.iter().map(|dim| dim.as_u64().map(|dim| dim as Ix));
+                        shape.collect::<Option<Vec<_>>>().ok_or_else(invalid)?
+                    }
+                    None => vec![],
+                };
+                (Some(ty), shape, value)
+            }
+            None if json.is_object() => fail!("expected a dtype annotation: {}", json),
+            None => (None, infer_shape(json), json),
+        };
+        let mut values = Vec::new();
+        flatten(value, &shape, &mut values)?;
+        let ty = match ty {
+            Some(ty) => ty,
+            None => infer_dtype(&values)?,
+        };
+        let mut buf = vec![0_u8; buffer_size(ty.size(), values.len())?];
+        let mut strings = Vec::new();
+        for (i, value) in values.iter().enumerate() {
+            let encoded = encode(value, &ty, &mut buf[i * ty.size()..], &mut strings);
+            ensure!(encoded.is_some(), "invalid value for type {}: {}", type_name(&ty), value);
+        }
+        let dtype = Datatype::from_descriptor(&ty)?;
+        let space = Dataspace::try_new(shape, false)?;
+        Ok(Self { dtype, space, buf, _strings: strings })
+    }
+
+    fn buf_ptr(&self) -> *const c_void {
+        self.buf.as_ptr() as *const _
+    }
+}
+
+impl Container {
+    /// Converts the dataset or attribute to its annotated JSON representation, see the
+    /// module documentation; the whole of it is read, so this is meant for small data.
+    pub fn to_json(&self) -> Result<Value> {
+        let ty = self.dtype()?.to_descriptor()?;
+        let mut map = Map::new();
+        map.insert("dtype".into(), dtype_to_json(&ty)?);
+        let space = self.space()?;
+        if space.is_null() {
+            map.insert("shape".into(), Value::Null);
+            map.insert("value".into(), Value::Null);
+        } else {
+            let array = self.read_dyn_array()?;
+            let shape = array.shape().to_vec();
+            let values =
+                array.iter().map(|value| value_to_json(value, &ty)).collect::<Result<Vec<_>>>()?;
+            map.insert("shape".into(), shape.clone().into());
+            map.insert("value".into(), nest(&mut values.into_iter(), &shape));
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+impl Location {
+    /// Converts all attributes to a JSON object mapping their names to their annotated
+    /// JSON representations, see the module documentation.
+    pub fn attrs_to_json(&self) -> Result<Value> {
+        let mut map = Map::new();
+        for name in self.attr_names()? {
+            let json = self.attr(&name)?.to_json().map_err(|err| format!("{}: {}", name, err))?;
+            map.insert(name, json);
+        }
+        Ok(Value::Object(map))
+    }
+
+    /// Creates attributes from a JSON object mapping their names to either annotated JSON
+    /// representations or plain JSON values, replacing existing attributes with the same
+    /// names; see the module documentation.
+    ///
+    /// All values are parsed before any attribute is written.
+    pub fn attrs_from_json(&self, json: &Value) -> Result<()> {
+        let map = json.as_object().ok_or("expected a JSON object mapping attribute names")?;
+        let parsed = map
+            .iter()
+            .map(|(name, json)| {
+                Ok((name, Parsed::new(json).map_err(|e| format!("{}: {}", name, e))?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (name, parsed) in parsed {
+            if self.attr_exists(name) {
+                self.delete_attr(name)?;
+            }
+            let name = to_cstring(name.as_str())?;
+            h5lock!({
+                let attr = Attribute::from_id(h5try!(H5Acreate2(
+                    self.id(),
+                    name.as_ptr(),
+                    parsed.dtype.id(),
+                    parsed.space.id(),
+                    H5P_DEFAULT,
+                    H5P_DEFAULT
+                )))?;
+                if parsed.buf.is_empty() {
+                    Ok(())
+                } else {
+                    h5call!(H5Awrite(attr.id(), parsed.dtype.id(), parsed.buf_ptr())).and(Ok(()))
+                }
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Group {
+    /// Creates a dataset from its JSON representation, as returned by `to_json()`, or from
+    /// plain JSON values; see the module documentation.
+    pub fn dataset_from_json(&self, name: &str, json: &Value) -> Result<Dataset> {
+        let parsed = Parsed::new(json)?;
+        let name = to_cstring(name)?;
+        h5lock!({
+            let dataset = Dataset::from_id(h5try!(H5Dcreate2(
+                self.id(),
+                name.as_ptr(),
+                parsed.dtype.id(),
+                parsed.space.id(),
+                H5P_DEFAULT,
+                H5P_DEFAULT,
+                H5P_DEFAULT
+            )))?;
+            if !parsed.buf.is_empty() {
+                let (dtype, ptr) = (parsed.dtype.id(), parsed.buf_ptr());
+                h5try!(H5Dwrite(dataset.id(), dtype, H5S_ALL, H5S_ALL, H5P_DEFAULT, ptr));
+            }
+            Ok(dataset)
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use ndarray::arr2;
+    use serde_json::json;
+
+    use hdf5_types::VarLenUnicode;
+
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_attrs_json() {
+        with_tmp_file(|file| {
+            let group = file.create_group("g").unwrap();
+            group.set_attr("count", &3_u16).unwrap();
+            group.set_attr_str("title", "run 1").unwrap();
+            group.new_attr::<f64>().create("scale", (2, 2)).unwrap();
+            group.attr("scale").unwrap().write(&arr2(&[[0.5, f64::NAN], [1.0, 2.0]])).unwrap();
+            group.set_attr("point", &(0.1_f32, [-1_i16, 7])).unwrap();
+
+            let json = group.attrs_to_json().unwrap();
+            assert_eq!(json["count"], json!({"dtype": "u16", "shape": [], "value": 3}));
+            assert_eq!(json["title"], json!({"dtype": "utf8", "shape": [], "value": "run 1"}));
+            assert_eq!(json["scale"]["value"], json!([[0.5, "NaN"], [1.0, 2.0]]));
+            assert_eq!(
+                json["point"],
+                json!({
+                    "dtype": {"compound": [["0", "f32"], ["1", {"array": "i16", "len": 2}]]},
+                    "shape": [],
+                    "value": {"0": 0.1, "1": [-1, 7]},
+                })
+            );
+
+            // round trip via a JSON string
+            let copy = file.create_group("copy").unwrap();
+            let text = serde_json::to_string(&json).unwrap();
+            copy.attrs_from_json(&serde_json::from_str(&text).unwrap()).unwrap();
+            assert_eq!(copy.attrs_to_json().unwrap(), json);
+            let point = copy.attr("point").unwrap().read_scalar::<(f32, [i16; 2])>().unwrap();
+            assert_eq!(point, (0.1, [-1, 7]));
+            assert_eq!(copy.attr("count").unwrap().dtype().unwrap().size(), 2);
+
+            // plain values, replacing existing attributes
+            let plain =
+                json!({"count": 10, "title": "run 2", "grid": [[1, 2], [3, 4]], "ok": true});
+            copy.attrs_from_json(&plain).unwrap();
+            assert_eq!(copy.attr("count").unwrap().read_scalar::<i64>().unwrap(), 10);
+            let title = copy.attr("title").unwrap().read_scalar::<VarLenUnicode>().unwrap();
+            assert_eq!(title.as_str(), "run 2");
+            assert_eq!(
+                copy.attr("grid").unwrap().read_2d::<i64>().unwrap(),
+                arr2(&[[1, 2], [3, 4]])
+            );
+            assert!(copy.attr("ok").unwrap().read_scalar::<bool>().unwrap());
+
+            assert_err!(copy.attrs_from_json(&json!([1])), "expected a JSON object");
+            assert_err!(copy.attrs_from_json(&json!({"a": [1, "x"]})), "a: can't infer a datatype");
+            assert_err!(copy.attrs_from_json(&json!({"a": [[1], [2, 3]]})), "doesn't match shape");
+            assert_err!(
+                copy.attrs_from_json(&json!({"a": {"b": 1}})),
+                "expected a dtype annotation"
+            );
+            let bad = json!({"a": {"dtype": "u8", "value": 300}});
+            assert_err!(copy.attrs_from_json(&bad), "a: invalid value for type u8: 300");
+            let bad = json!({"a": {"dtype": "u7", "value": 3}});
+            assert_err!(copy.attrs_from_json(&bad), "invalid datatype: \"u7\"");
+            assert!(!copy.attr_exists("a"));
+        })
+    }
+
+    #[test]
+    pub fn test_dataset_json() {
+        with_tmp_file(|file| {
+            let ds = file.new_dataset::<i32>().create("data", (2, 3)).unwrap();
+            ds.write(&arr2(&[[1, 2, 3], [4, 5, 6]])).unwrap();
+            let json = ds.to_json().unwrap();
+            assert_eq!(
+                json,
+                json!({"dtype": "i32", "shape": [2, 3], "value": [[1, 2, 3], [4, 5, 6]]})
+            );
+            let copy = file.dataset_from_json("copy", &json).unwrap();
+            assert_eq!(copy.read_2d::<i32>().unwrap(), ds.read_2d::<i32>().unwrap());
+            assert_eq!(copy.to_json().unwrap(), json);
+
+            let color = json!({"enum": "i8", "members": [["red", -1], ["green", 2]]});
+            let colors = json!({"dtype": color, "shape": [3], "value": ["green", "red", 5]});
+            let ds = file.dataset_from_json("colors", &colors).unwrap();
+            assert_eq!(ds.read_raw::<i8>().unwrap(), vec![2, -1, 5]);
+            assert_eq!(ds.to_json().unwrap(), colors);
+
+            let strings = json!({"dtype": "ascii(4)", "shape": [2], "value": ["ab", "cdef"]});
+            let ds = file.dataset_from_json("strings", &strings).unwrap();
+            assert_eq!(ds.to_json().unwrap(), strings);
+            let empty = json!({"dtype": "f64", "shape": [0], "value": []});
+            assert_eq!(file.dataset_from_json("empty", &empty).unwrap().to_json().unwrap(), empty);
+            let null = json!({"dtype": "u8", "shape": null, "value": null});
+            let ds = file.dataset_from_json("null", &null).unwrap();
+            assert!(ds.is_null());
+            assert_eq!(ds.to_json().unwrap(), null);
+
+            let bad = json!({"dtype": "ascii(2)", "shape": [1], "value": ["abc"]});
+            assert_err!(file.dataset_from_json("bad", &bad), "invalid value for type ascii(2)");
+            let refs = file.new_dataset::<hdf5_types::VarLenArray<u8>>().create("vlen", 1);
+            assert_err!(refs.unwrap().to_json(), "unsupported datatype for JSON: [u8]");
+        })
+    }
+}
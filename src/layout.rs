@@ -0,0 +1,418 @@
+//! Declarative creation of file layouts.
+//!
+//! A `Layout` declares the groups, datasets and attributes to be created in a group (or
+//! file) up front, and then creates all of them at once, replacing long sequences of
+//! imperative setup calls:
+//!
+//! ```ignore
+//! let mut layout = file.new_layout();
+//! layout.group("/raw").attr_str("/raw", "instrument", "spectrometer");
+//! layout
+//!     .dataset::<u32, _>("/raw/counts", (Extent::unlimited(0), 1024))
+//!     .chunk((16, 1024))
+//!     .compress_balanced();
+//! layout.dataset::<f64, _>("/calibration", 1024);
+//! layout.attr("/calibration", "version", 3_u16);
+//!
+//! let handles = layout.create()?;
+//! let counts = handles.dataset::<u32>("/raw/counts")?;
+//! ```
+//!
+//! Datasets are configured with the usual `DatasetBuilder`. Parent groups of declared
+//! paths are created as needed; groups that already exist are reused, but declared
+//! datasets and attributes must not exist yet.
+//!
+//! Creation is all-or-nothing: the whole layout is checked against the existing contents
+//! of the group before anything is created, and if creating any of the objects fails,
+//! the objects and attributes created so far are removed again, leaving the structure of
+//! the file as it was (the space they occupied in the file is not reclaimed).
+
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug};
+
+use hdf5_types::TypeDescriptor as TD;
+
+use crate::describe::type_name;
+use crate::internal_prelude::*;
+
+fn normalize(path: &str) -> String {
+    let components: Vec<_> = path.split('/').filter(|c| !c.is_empty()).collect();
+    format!("/{}", components.join("/"))
+}
+
+/// Returns the normalised paths of all ancestors of a normalised path, outermost first.
+fn ancestors(path: &str) -> impl Iterator<Item = &str> {
+    path.match_indices('/').skip(1).map(move |(i, _)| &path[..i])
+}
+
+trait DatasetEntry {
+    fn create(&self, path: &str) -> Result<Dataset>;
+    fn descriptor(&self) -> TD;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct TypedEntry<T> {
+    builder: DatasetBuilder<T>,
+    extents: Extents,
+}
+
+impl<T: H5Type> DatasetEntry for TypedEntry<T> {
+    fn create(&self, path: &str) -> Result<Dataset> {
+        self.builder.create_with_extents(path, self.extents.clone())
+    }
+
+    fn descriptor(&self) -> TD {
+        T::type_descriptor()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+type AttrWriter = Box<dyn Fn(&Location, &str) -> Result<()>>;
+
+/// A declaration of groups, datasets and attributes, see the module documentation.
+pub struct Layout {
+    parent: Group,
+    groups: BTreeSet<String>,
+    datasets: BTreeMap<String, Box<dyn DatasetEntry>>,
+    attrs: BTreeMap<(String, String), AttrWriter>,
+}
+
+impl Debug for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Layout")
+            .field("parent", &self.parent)
+            .field("groups", &self.groups)
+            .field("datasets", &self.datasets.keys().collect::<Vec<_>>())
+            .field("attrs", &self.attrs.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Layout {
+    /// Creates an empty layout to be created in the given group.
+    pub fn new(parent: &Group) -> Self {
+        Self {
+            parent: parent.clone(),
+            groups: BTreeSet::new(),
+            datasets: BTreeMap::new(),
+            attrs: BTreeMap::new(),
+        }
+    }
+
+    /// Declares a group; declaring a dataset at the same path replaces it.
+    pub fn group(&mut self, path: &str) -> &mut Self {
+        let path = normalize(path);
+        self.datasets.remove(&path);
+        self.groups.insert(path);
+        self
+    }
+
+    /// Declares a dataset with the given shape (or extents, to make it resizable),
+    /// returning its builder; declaring a group or dataset at the same path replaces it.
+    pub fn dataset<T: H5Type, E: Into<Extents>>(
+        &mut self, path: &str, extents: E,
+    ) -> &mut DatasetBuilder<T> {
+        let path = normalize(path);
+        self.groups.remove(&path);
+        let entry = TypedEntry { builder: self.parent.new_dataset::<T>(), extents: extents.into() };
+        self.datasets.insert(path.clone(), Box::new(entry));
+        let entry = self.datasets.get_mut(&path).unwrap();
+        &mut entry.as_any_mut().downcast_mut::<TypedEntry<T>>().unwrap().builder
+    }
+
+    fn add_attr<F>(&mut self, path: &str, name: &str, write: F) -> &mut Self
+    where
+        F: Fn(&Location, &str) -> Result<()> + 'static,
+    {
+        self.attrs.insert((normalize(path), name.into()), Box::new(write));
+        self
+    }
+
+    /// Declares a scalar attribute of the object at the given path, which is either
+    /// declared in the layout or already exists (`"/"` is the group itself).
+    pub fn attr<T: H5Type>(&mut self, path: &str, name: &str, value: T) -> &mut Self {
+        self.add_attr(path, name, move |loc, name| loc.set_attr(name, &value).and(Ok(())))
+    }
+
+    /// Declares a variable-length UTF-8 string attribute, see `attr()`.
+    pub fn attr_str(&mut self, path: &str, name: &str, value: &str) -> &mut Self {
+        let value = value.to_owned();
+        self.add_attr(path, name, move |loc, name| loc.set_attr_str(name, &value).and(Ok(())))
+    }
+
+    /// Returns all group paths to be created or reused, parents first.
+    fn all_groups(&self) -> BTreeSet<&str> {
+        let declared = self.groups.iter().chain(self.datasets.keys());
+        let mut groups: BTreeSet<&str> = declared.flat_map(|path| ancestors(path)).collect();
+        groups.extend(self.groups.iter().map(String::as_str));
+        groups
+    }
+
+    /// Checks the whole layout against the existing contents of the parent group,
+    /// returning the paths of the groups that already exist.
+    fn check(&self) -> Result<BTreeSet<String>> {
+        let mut existing: BTreeSet<_> = vec!["/".to_owned()].into_iter().collect();
+        for path in self.datasets.keys() {
+            let prefix = format!("{}/", path);
+            let mut declared = self.groups.iter().chain(self.datasets.keys());
+            if let Some(inner) = declared.find(|p| p.starts_with(&prefix)) {
+                fail!("dataset '{}' can't contain '{}'", path, inner);
+            }
+            ensure!(!self.parent.link_exists(&path[1..]), "'{}' already exists", path);
+        }
+        for path in self.all_groups() {
+            if path != "/" && self.parent.link_exists(&path[1..]) {
+                let _e = silence_errors();
+                ensure!(
+                    self.parent.group(&path[1..]).is_ok(),
+                    "'{}' already exists and is not a group",
+                    path
+                );
+                existing.insert(path.to_owned());
+            }
+        }
+        for (path, name) in self.attrs.keys() {
+            let declared = self.groups.contains(path) || self.datasets.contains_key(path);
+            if declared && !existing.contains(path) {
+                continue;
+            }
+            let object = match open_object(&self.parent, path) {
+                Ok(object) => object,
+                Err(_) if !declared => fail!("'{}' is neither declared nor existing", path),
+                Err(err) => return Err(err),
+            };
+            ensure!(
+                !object.location().attr_exists(name),
+                "attribute '{}' of '{}' already exists",
+                name,
+                path
+            );
+        }
+        Ok(existing)
+    }
+
+    /// Creates all declared objects and attributes, see the module documentation.
+    pub fn create(&self) -> Result<LayoutHandles> {
+        let existing = self.check()?;
+        let mut created = Created::default();
+        self.create_all(&existing, &mut created).map_err(|err| {
+            created.rollback(&self.parent);
+            format!("creating layout in '{}': {}", self.parent.name(), err).into()
+        })
+    }
+
+    fn create_all(
+        &self, existing: &BTreeSet<String>, created: &mut Created,
+    ) -> Result<LayoutHandles> {
+        let mut handles = LayoutHandles::default();
+        for path in self.all_groups() {
+            let group = if path == "/" {
+                self.parent.clone()
+            } else if existing.contains(path) {
+                self.parent.group(&path[1..])?
+            } else {
+                let group = self.parent.create_group(&path[1..])?;
+                created.links.push(path.to_owned());
+                group
+            };
+            if self.groups.contains(path) {
+                handles.groups.insert(path.to_owned(), group);
+            }
+        }
+        for (path, entry) in &self.datasets {
+            let dataset = entry.create(&path[1..])?;
+            created.links.push(path.clone());
+            handles.datasets.insert(path.clone(), (dataset, entry.descriptor()));
+        }
+        for ((path, name), write) in &self.attrs {
+            let object = match (handles.groups.get(path), handles.datasets.get(path)) {
+                (Some(group), _) => Object::Group(group.clone()),
+                (_, Some((dataset, _))) => Object::Dataset(dataset.clone()),
+                _ => open_object(&self.parent, path)?,
+            };
+            write(object.location(), name)?;
+            // attributes of created objects are removed along with them
+            if !created.links.contains(path) {
+                created.attrs.push((path.clone(), name.clone()));
+            }
+        }
+        Ok(handles)
+    }
+}
+
+enum Object {
+    Group(Group),
+    Dataset(Dataset),
+}
+
+/// Opens the group or dataset at a normalised path relative to the parent group.
+fn open_object(parent: &Group, path: &str) -> Result<Object> {
+    if path == "/" {
+        return Ok(Object::Group(parent.clone()));
+    }
+    let _e = silence_errors();
+    match parent.group(&path[1..]) {
+        Ok(group) => Ok(Object::Group(group)),
+        Err(_) => Ok(Object::Dataset(parent.dataset(&path[1..])?)),
+    }
+}
+
+impl Object {
+    fn location(&self) -> &Location {
+        match self {
+            Self::Group(group) => group,
+            Self::Dataset(dataset) => dataset,
+        }
+    }
+}
+
+/// Objects created so far, to be removed if creating the layout fails.
+#[derive(Default)]
+struct Created {
+    links: Vec<String>,
+    attrs: Vec<(String, String)>,
+}
+
+impl Created {
+    fn rollback(&self, parent: &Group) {
+        let _e = silence_errors();
+        for (path, name) in self.attrs.iter().rev() {
+            if let Ok(object) = open_object(parent, path) {
+                let _ = object.location().delete_attr(name);
+            }
+        }
+        for path in self.links.iter().rev() {
+            let _ = parent.unlink(&path[1..]);
+        }
+    }
+}
+
+/// Handles to the groups and datasets declared in a `Layout`, by normalised path
+/// (e.g. `"/raw/counts"`).
+#[derive(Clone, Debug, Default)]
+pub struct LayoutHandles {
+    groups: BTreeMap<String, Group>,
+    datasets: BTreeMap<String, (Dataset, TD)>,
+}
+
+impl LayoutHandles {
+    /// Returns the declared group at the given path.
+    pub fn group(&self, path: &str) -> Result<Group> {
+        let path = normalize(path);
+        match self.groups.get(&path) {
+            Some(group) => Ok(group.clone()),
+            None => fail!("no group '{}' declared in the layout", path),
+        }
+    }
+
+    /// Returns the declared dataset at the given path, checking that it was declared
+    /// with the element type `T`.
+    pub fn dataset<T: H5Type>(&self, path: &str) -> Result<Dataset> {
+        let path = normalize(path);
+        match self.datasets.get(&path) {
+            Some((dataset, descriptor)) => {
+                let requested = T::type_descriptor();
+                ensure!(
+                    *descriptor == requested,
+                    "dataset '{}' was declared as {}, not {}",
+                    path,
+                    type_name(descriptor),
+                    type_name(&requested)
+                );
+                Ok(dataset.clone())
+            }
+            None => fail!("no dataset '{}' declared in the layout", path),
+        }
+    }
+
+    /// Returns the paths of all declared groups and datasets.
+    pub fn paths(&self) -> Vec<&str> {
+        let paths: BTreeSet<_> = self.groups.keys().chain(self.datasets.keys()).collect();
+        paths.into_iter().map(String::as_str).collect()
+    }
+}
+
+impl Group {
+    /// Instantiates an empty layout to be created in this group, see `Layout`.
+    pub fn new_layout(&self) -> Layout {
+        Layout::new(self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use hdf5_types::VarLenUnicode;
+
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_layout_create() {
+        with_tmp_file(|file| {
+            file.create_group("existing").unwrap();
+            let mut layout = file.new_layout();
+            layout.group("raw/meta").attr_str("/raw/meta", "instrument", "spectrometer");
+            layout
+                .dataset::<u32, _>("/raw/counts", (Extent::unlimited(0), 8))
+                .chunk((4, 8))
+                .gzip(4);
+            layout.dataset::<f64, _>("existing/calibration/", 8);
+            layout.attr("existing/calibration", "version", 3_u16).attr("/", "format", 2_u8);
+            let handles = layout.create().unwrap();
+
+            assert_eq!(handles.paths(), vec!["/existing/calibration", "/raw/counts", "/raw/meta"]);
+            let counts = handles.dataset::<u32>("raw/counts").unwrap();
+            assert_eq!((counts.shape(), counts.chunks()), (vec![0, 8], Some(vec![4, 8])));
+            assert!(counts.is_resizable());
+            assert_eq!(counts.filters().get_gzip(), Some(4));
+            let calibration = file.dataset("existing/calibration").unwrap();
+            assert_eq!(calibration.attr("version").unwrap().read_scalar::<u16>().unwrap(), 3);
+            assert_eq!(file.attr("format").unwrap().read_scalar::<u8>().unwrap(), 2);
+            let meta = handles.group("/raw/meta").unwrap();
+            let instrument = meta.attr("instrument").unwrap().read_scalar::<VarLenUnicode>();
+            assert_eq!(instrument.unwrap().as_str(), "spectrometer");
+
+            assert_err!(handles.dataset::<i32>("/raw/counts"), "declared as u32, not i32");
+            assert_err!(handles.group("/raw"), "no group '/raw' declared");
+            assert_err!(handles.dataset::<u32>("/raw/meta"), "no dataset '/raw/meta' declared");
+        })
+    }
+
+    #[test]
+    pub fn test_layout_atomic() {
+        with_tmp_file(|file| {
+            file.new_dataset::<u8>().create("data", 4).unwrap();
+            file.create_group("group").unwrap().set_attr("taken", &1_u8).unwrap();
+
+            let mut layout = file.new_layout();
+            layout.dataset::<u8, _>("data", 4);
+            assert_err!(layout.create(), "'/data' already exists");
+            let mut layout = file.new_layout();
+            layout.group("data/inner");
+            assert_err!(layout.create(), "'/data' already exists and is not a group");
+            let mut layout = file.new_layout();
+            layout.attr("group", "taken", 2_u8);
+            assert_err!(layout.create(), "attribute 'taken' of '/group' already exists");
+            let mut layout = file.new_layout();
+            layout.attr("missing", "attr", 2_u8);
+            assert_err!(layout.create(), "'/missing' is neither declared nor existing");
+            let mut layout = file.new_layout();
+            layout.dataset::<u8, _>("a", 4);
+            layout.group("a/b");
+            assert_err!(layout.create(), "dataset '/a' can't contain '/a/b'");
+
+            // a failure halfway through removes everything created so far
+            let mut layout = file.new_layout();
+            layout.group("new/group").attr("/group", "added", 1_u8);
+            layout.dataset::<u8, _>("new/ok", 4);
+            layout.dataset::<u8, _>("new/wrong", 4).chunk(8);
+            assert_err!(layout.create(), "creating layout in '/': Invalid chunk");
+            assert!(!file.link_exists("new"));
+            assert!(!file.group("group").unwrap().attr_exists("added"));
+            assert_eq!(file.member_names().unwrap(), vec!["data", "group"]);
+        })
+    }
+}
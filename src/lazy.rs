@@ -0,0 +1,522 @@
+//! Lazily loaded arrays for browsing datasets larger than memory.
+//!
+//! `Dataset::as_lazy()` returns a `LazyArray`, which reads nothing up front; elements and
+//! slices are read on access one chunk at a time, and the chunks read are kept in an LRU
+//! cache bounded by a memory budget so that repeated accesses to nearby elements don't
+//! hit the file again:
+//!
+//! ```ignore
+//! let lazy = file.dataset("images")?.as_lazy::<u16>()?.with_memory_budget(256 << 20);
+//! let pixel = lazy.get((1000, 20, 30))?;
+//! let row = lazy.read_slice_1d(s![1000, 20, ..])?;
+//! ```
+//!
+//! Chunked datasets are cached in units of their chunks. Contiguous datasets are cached
+//! in blocks of the shape that would be picked by `DatasetBuilder::chunk_auto()`.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Debug};
+use std::mem;
+
+use ndarray::{Array, Array1, Array2, SliceInfo, SliceOrIndex};
+
+use crate::hl::dataset::infer_chunk_size;
+use crate::internal_prelude::*;
+
+/// Memory budget of a newly created `LazyArray`, in bytes (64 MiB).
+pub const DEFAULT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+
+/// Cache statistics of a `LazyArray`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LazyStats {
+    /// Number of chunk accesses served from the cache.
+    pub hits: u64,
+    /// Number of chunk accesses that had to read the chunk from the file.
+    pub misses: u64,
+    /// Number of chunks currently cached.
+    pub cached_chunks: usize,
+    /// Total size of the currently cached chunks, in bytes.
+    pub cached_bytes: usize,
+}
+
+struct CachedChunk<T> {
+    data: Vec<T>,
+    shape: Vec<Ix>,
+    last_used: u64,
+}
+
+struct ChunkCache<T> {
+    chunks: HashMap<Vec<Ix>, CachedChunk<T>>,
+    bytes: usize,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl<T> ChunkCache<T> {
+    fn new() -> Self {
+        Self { chunks: HashMap::new(), bytes: 0, clock: 0, hits: 0, misses: 0 }
+    }
+
+    /// Evicts the least recently used chunks until at most `limit` bytes are cached.
+    fn evict(&mut self, limit: usize) {
+        while self.bytes > limit {
+            let oldest = self.chunks.iter().min_by_key(|(_, chunk)| chunk.last_used);
+            let key = match oldest {
+                Some((key, _)) => key.clone(),
+                None => break,
+            };
+            if let Some(chunk) = self.chunks.remove(&key) {
+                self.bytes -= chunk.data.len() * mem::size_of::<T>();
+            }
+        }
+    }
+}
+
+/// An array view of a dataset that reads and caches chunks on demand.
+///
+/// The cache is bounded by a memory budget (see `with_memory_budget()`) that counts the
+/// in-memory size of the cached elements, not including any variable-length data they
+/// point to; the most recently used chunk is always kept, even if it alone exceeds the
+/// budget.
+///
+/// The shape of the dataset is read when the lazy array is created; if the dataset is
+/// resized afterwards, a new lazy array has to be created to see the new elements. The
+/// cache isn't shared between threads, so `LazyArray` is not `Sync`.
+pub struct LazyArray<T> {
+    dataset: Dataset,
+    shape: Vec<Ix>,
+    chunk: Vec<Ix>,
+    budget: usize,
+    cache: RefCell<ChunkCache<T>>,
+}
+
+impl<T> Debug for LazyArray<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LazyArray")
+            .field("dataset", &self.dataset)
+            .field("shape", &self.shape)
+            .field("chunk", &self.chunk)
+            .field("budget", &self.budget)
+            .finish()
+    }
+}
+
+/// Chunk index along an axis and the (output position, offset within the chunk) pairs of
+/// the indices selected within it.
+type ChunkGroup = (Ix, Vec<(Ix, Ix)>);
+
+/// Calls `func` with every index within `lens` in row-major order.
+fn for_each_index<F>(lens: &[Ix], mut func: F) -> Result<()>
+where
+    F: FnMut(&[Ix]) -> Result<()>,
+{
+    if lens.contains(&0) {
+        return Ok(());
+    }
+    let mut index = vec![0; lens.len()];
+    loop {
+        func(&index)?;
+        let mut axis = lens.len();
+        loop {
+            if axis == 0 {
+                return Ok(());
+            }
+            axis -= 1;
+            index[axis] += 1;
+            if index[axis] < lens[axis] {
+                break;
+            }
+            index[axis] = 0;
+        }
+    }
+}
+
+fn row_major_strides(shape: &[Ix]) -> Vec<Ix> {
+    let mut strides = vec![1; shape.len()];
+    for axis in (1..shape.len()).rev() {
+        strides[axis - 1] = strides[axis] * shape[axis];
+    }
+    strides
+}
+
+/// Returns the indices selected by `item` along an axis of length `len`, following the
+/// semantics of `ndarray` slicing.
+fn axis_indices(item: &SliceOrIndex, axis: usize, len: Ix) -> Result<Vec<Ix>> {
+    let resolve = |x: isize| if x < 0 { x + len as isize } else { x };
+    match *item {
+        SliceOrIndex::Index(index) => {
+            let resolved = resolve(index);
+            ensure!(
+                resolved >= 0 && resolved < len as isize,
+                "index {} is out of bounds for axis {} with size {}",
+                index,
+                axis,
+                len
+            );
+            Ok(vec![resolved as _])
+        }
+        SliceOrIndex::Slice { start, end, step } => {
+            let (first, last) = (resolve(start), end.map_or(len as isize, resolve));
+            ensure!(
+                first >= 0 && first as Ix <= len && last >= 0 && last as Ix <= len,
+                "slice {}..{} is out of bounds for axis {} with size {}",
+                start,
+                end.map_or_else(String::new, |end| end.to_string()),
+                axis,
+                len
+            );
+            ensure!(step != 0, "slice step cannot be zero");
+            let range = first as Ix..(last as Ix).max(first as Ix);
+            let stride = step.abs() as usize;
+            Ok(if step > 0 {
+                range.step_by(stride).collect()
+            } else {
+                range.rev().step_by(stride).collect()
+            })
+        }
+    }
+}
+
+impl<T: H5Type + Clone> LazyArray<T> {
+    fn new(dataset: &Dataset) -> Result<Self> {
+        let file_dtype = dataset.dtype()?;
+        file_dtype.ensure_convertible(&Datatype::from_type::<T>()?, Conversion::Soft)?;
+        let shape = dataset.shape();
+        let chunk = match dataset.chunks() {
+            Some(chunk) => chunk,
+            None if shape.contains(&0) => vec![1; shape.len()],
+            None => infer_chunk_size(&shape, mem::size_of::<T>().max(1)),
+        };
+        Ok(Self {
+            dataset: dataset.clone(),
+            shape,
+            chunk,
+            budget: DEFAULT_MEMORY_BUDGET,
+            cache: RefCell::new(ChunkCache::new()),
+        })
+    }
+
+    /// Sets the memory budget of the chunk cache, in bytes.
+    pub fn with_memory_budget(mut self, budget: usize) -> Self {
+        self.budget = budget;
+        self.cache.get_mut().evict(budget);
+        self
+    }
+
+    /// Returns the memory budget of the chunk cache, in bytes.
+    pub fn memory_budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Returns the underlying dataset.
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+
+    /// Returns the shape of the array.
+    pub fn shape(&self) -> &[Ix] {
+        &self.shape
+    }
+
+    /// Returns the number of dimensions of the array.
+    pub fn ndim(&self) -> usize {
+        self.shape.len()
+    }
+
+    /// Returns the total number of elements in the array.
+    pub fn size(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    /// Returns `true` if the array has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.size() == 0
+    }
+
+    /// Returns the shape of the blocks in which the array is read and cached.
+    pub fn chunk_shape(&self) -> &[Ix] {
+        &self.chunk
+    }
+
+    /// Returns the cache statistics.
+    pub fn stats(&self) -> LazyStats {
+        let cache = self.cache.borrow();
+        LazyStats {
+            hits: cache.hits,
+            misses: cache.misses,
+            cached_chunks: cache.chunks.len(),
+            cached_bytes: cache.bytes,
+        }
+    }
+
+    /// Drops all cached chunks (the statistics are kept).
+    pub fn clear_cache(&self) {
+        self.cache.borrow_mut().evict(0);
+    }
+
+    fn read_chunk(&self, key: &[Ix]) -> Result<CachedChunk<T>> {
+        let start: Vec<Ix> = key.iter().zip(&self.chunk).map(|(&k, &c)| k * c).collect();
+        let shape: Vec<Ix> = start
+            .iter()
+            .zip(&self.chunk)
+            .zip(&self.shape)
+            .map(|((&start, &chunk), &len)| chunk.min(len - start))
+            .collect();
+        let data = if self.shape.is_empty() {
+            vec![self.dataset.read_scalar()?]
+        } else {
+            let space = self.dataset.space()?;
+            space.select_hyperslab(&start, None, &shape, None)?;
+            self.dataset.read_selection(&space)?
+        };
+        Ok(CachedChunk { data, shape, last_used: 0 })
+    }
+
+    /// Calls `func` with the chunk at the given chunk grid position, reading it if needed.
+    fn with_chunk<R, F>(&self, key: &[Ix], func: F) -> Result<R>
+    where
+        F: FnOnce(&CachedChunk<T>) -> R,
+    {
+        let mut cache = self.cache.borrow_mut();
+        let cache = &mut *cache;
+        cache.clock += 1;
+        if let Some(chunk) = cache.chunks.get_mut(key) {
+            cache.hits += 1;
+            chunk.last_used = cache.clock;
+            return Ok(func(chunk));
+        }
+        cache.misses += 1;
+        let mut chunk = self.read_chunk(key)?;
+        chunk.last_used = cache.clock;
+        let bytes = chunk.data.len() * mem::size_of::<T>();
+        cache.evict(self.budget.saturating_sub(bytes));
+        cache.bytes += bytes;
+        Ok(func(cache.chunks.entry(key.to_vec()).or_insert(chunk)))
+    }
+
+    /// Returns the element at the given index.
+    pub fn get<D: Dimension>(&self, index: D) -> Result<T> {
+        let index = index.dims();
+        ensure!(
+            index.len() == self.ndim(),
+            "index has {} dims, dataset has {}",
+            index.len(),
+            self.ndim()
+        );
+        ensure!(
+            index.iter().zip(&self.shape).all(|(&i, &len)| i < len),
+            "index {:?} is out of bounds for shape {:?}",
+            index,
+            self.shape
+        );
+        let key: Vec<Ix> = index.iter().zip(&self.chunk).map(|(&i, &c)| i / c).collect();
+        self.with_chunk(&key, |chunk| {
+            let strides = row_major_strides(&chunk.shape);
+            let offset = index.iter().zip(&self.chunk).zip(strides).map(|((&i, &c), s)| i % c * s);
+            chunk.data[offset.sum::<Ix>()].clone()
+        })
+    }
+
+    /// Reads a slice of the array, reading only the chunks it intersects.
+    ///
+    /// The slice has the same semantics as when slicing an `ndarray` array with `s![]`,
+    /// including negative steps; as with `Container::read_slice()`, the dimensionality
+    /// `D` must match the dimensionality of the slice after indexed axes are dropped.
+    pub fn read_slice<S, D>(&self, slice: &SliceInfo<S, D>) -> Result<Array<T, D>>
+    where
+        S: AsRef<[SliceOrIndex]>,
+        D: ndarray::Dimension,
+    {
+        let slice: &[SliceOrIndex] = slice.as_ref();
+        ensure!(
+            slice.len() == self.ndim(),
+            "slice dimension mismatch: dataset has {} dims, slice has {} dims",
+            self.ndim(),
+            slice.len()
+        );
+        let mut indices = Vec::with_capacity(slice.len());
+        let mut out_shape = Vec::with_capacity(slice.len());
+        for (axis, (item, &len)) in slice.iter().zip(&self.shape).enumerate() {
+            let axis_indices = axis_indices(item, axis, len)?;
+            if item.is_slice() {
+                out_shape.push(axis_indices.len());
+            }
+            indices.push(axis_indices);
+        }
+        if let Some(ndim) = D::NDIM {
+            ensure!(
+                ndim == out_shape.len(),
+                "ndim mismatch: slice outputs dims {}, output type dims {}",
+                out_shape.len(),
+                ndim
+            );
+        }
+
+        // Group the selected indices along each axis by chunk, as pairs of positions in
+        // the output and offsets within the chunk, so that each chunk is only read once.
+        let groups: Vec<Vec<ChunkGroup>> = indices
+            .iter()
+            .zip(&self.chunk)
+            .map(|(axis_indices, &chunk)| {
+                let mut groups: BTreeMap<Ix, Vec<(Ix, Ix)>> = BTreeMap::new();
+                for (pos, &index) in axis_indices.iter().enumerate() {
+                    groups.entry(index / chunk).or_default().push((pos, index % chunk));
+                }
+                groups.into_iter().collect()
+            })
+            .collect();
+
+        let lens: Vec<Ix> = indices.iter().map(Vec::len).collect();
+        let strides = row_major_strides(&lens);
+        let mut out: Vec<Option<T>> = vec![None; lens.iter().product()];
+        let num_groups: Vec<Ix> = groups.iter().map(Vec::len).collect();
+        for_each_index(&num_groups, |group_index| {
+            let key: Vec<Ix> = group_index.iter().zip(&groups).map(|(&g, gs)| gs[g].0).collect();
+            let members: Vec<&[(Ix, Ix)]> =
+                group_index.iter().zip(&groups).map(|(&g, gs)| gs[g].1.as_slice()).collect();
+            let num_members: Vec<Ix> = members.iter().map(|m| m.len()).collect();
+            self.with_chunk(&key, |chunk| {
+                let chunk_strides = row_major_strides(&chunk.shape);
+                for_each_index(&num_members, |member_index| {
+                    let (mut dst, mut src) = (0, 0);
+                    for (axis, &m) in member_index.iter().enumerate() {
+                        let (pos, offset) = members[axis][m];
+                        dst += pos * strides[axis];
+                        src += offset * chunk_strides[axis];
+                    }
+                    out[dst] = Some(chunk.data[src].clone());
+                    Ok(())
+                })
+            })?
+        })?;
+
+        let out: Option<Vec<T>> = out.into_iter().collect();
+        let out = out.ok_or("lazy slice read did not cover the whole slice")?;
+        Ok(Array::from_shape_vec(out_shape, out)?.into_dimensionality()?)
+    }
+
+    /// Reads the given `slice` of the array into a 1-dimensional array; see `read_slice()`.
+    pub fn read_slice_1d<S>(&self, slice: &SliceInfo<S, ndarray::Ix1>) -> Result<Array1<T>>
+    where
+        S: AsRef<[SliceOrIndex]>,
+    {
+        self.read_slice(slice)
+    }
+
+    /// Reads the given `slice` of the array into a 2-dimensional array; see `read_slice()`.
+    pub fn read_slice_2d<S>(&self, slice: &SliceInfo<S, ndarray::Ix2>) -> Result<Array2<T>>
+    where
+        S: AsRef<[SliceOrIndex]>,
+    {
+        self.read_slice(slice)
+    }
+}
+
+impl Dataset {
+    /// Returns a lazily loaded view of the dataset that reads and caches chunks on
+    /// demand; see `LazyArray`.
+    pub fn as_lazy<T: H5Type + Clone>(&self) -> Result<LazyArray<T>> {
+        LazyArray::new(self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use ndarray::{arr1, arr2, s, Array2};
+
+    use hdf5_types::VarLenUnicode;
+
+    use super::LazyStats;
+    use crate::internal_prelude::*;
+
+    fn make_dataset(file: &File) -> Dataset {
+        let ds = file.new_dataset::<u32>().chunk((4, 3)).create("grid", (10, 8)).unwrap();
+        ds.write(&Array2::from_shape_fn((10, 8), |(i, j)| (i * 10 + j) as u32)).unwrap();
+        ds
+    }
+
+    #[test]
+    pub fn test_lazy_get() {
+        with_tmp_file(|file| {
+            let lazy = make_dataset(&file).as_lazy::<u32>().unwrap();
+            assert_eq!(lazy.shape(), &[10, 8]);
+            assert_eq!(lazy.chunk_shape(), &[4, 3]);
+            assert_eq!((lazy.ndim(), lazy.size(), lazy.is_empty()), (2, 80, false));
+            assert_eq!(lazy.stats(), LazyStats::default());
+
+            assert_eq!(lazy.get((0, 0)).unwrap(), 0);
+            assert_eq!(lazy.get((2, 1)).unwrap(), 21);
+            assert_eq!(lazy.get([9, 7]).unwrap(), 97);
+            assert_eq!(lazy.get((8, 6)).unwrap(), 86);
+            let stats = lazy.stats();
+            assert_eq!((stats.hits, stats.misses, stats.cached_chunks), (2, 2, 2));
+            // the edge chunk at (8, 6) only holds 2 x 2 elements
+            assert_eq!(stats.cached_bytes, (12 + 4) * 4);
+
+            assert_err!(lazy.get((10, 0)), "index [10, 0] is out of bounds for shape [10, 8]");
+            assert_err!(lazy.get(1), "index has 1 dims, dataset has 2");
+            lazy.clear_cache();
+            assert_eq!(lazy.stats().cached_bytes, 0);
+            assert_eq!(lazy.stats().misses, 2);
+
+            let ds = file.new_dataset::<i16>().create("scalar", ()).unwrap();
+            ds.write_scalar(&-7).unwrap();
+            assert_eq!(ds.as_lazy::<i64>().unwrap().get(()).unwrap(), -7);
+            assert_err!(ds.as_lazy::<VarLenUnicode>(), "no conversion paths found");
+        })
+    }
+
+    #[test]
+    pub fn test_lazy_read_slice() {
+        with_tmp_file(|file| {
+            let ds = make_dataset(&file);
+            let lazy = ds.as_lazy::<u32>().unwrap();
+            let data = ds.read_2d::<u32>().unwrap();
+            for slice in &[s![.., ..], s![1..9;3, 2..], s![..;-2, -3..-1], s![5..5, ..]] {
+                assert_eq!(lazy.read_slice(slice).unwrap(), data.slice(slice));
+            }
+            assert_eq!(lazy.read_slice_1d(s![3, 2..5]).unwrap(), arr1(&[32, 33, 34]));
+            assert_eq!(lazy.read_slice_2d(s![8.., ..2]).unwrap(), arr2(&[[80, 81], [90, 91]]));
+            assert_eq!(lazy.stats().cached_chunks, 9);
+
+            assert_err!(lazy.read_slice_1d(s![3, 2..9]), "slice 2..9 is out of bounds for axis 1");
+            assert_err!(lazy.read_slice_1d(s![..]), "slice dimension mismatch");
+
+            let contiguous = file.new_dataset::<u8>().create("contiguous", (0, 3)).unwrap();
+            let lazy = contiguous.as_lazy::<u8>().unwrap();
+            assert_eq!(lazy.chunk_shape(), &[1, 1]);
+            assert!(lazy.read_slice_2d(s![.., ..]).unwrap().is_empty());
+        })
+    }
+
+    #[test]
+    pub fn test_lazy_memory_budget() {
+        with_tmp_file(|file| {
+            let ds = make_dataset(&file);
+            let lazy = ds.as_lazy::<u32>().unwrap().with_memory_budget(2 * 12 * 4);
+            assert_eq!(lazy.memory_budget(), 96);
+            lazy.get((0, 0)).unwrap();
+            lazy.get((0, 3)).unwrap();
+            lazy.get((0, 1)).unwrap();
+            lazy.get((4, 0)).unwrap(); // evicts the chunk at (0, 3)
+            assert_eq!(lazy.stats().cached_chunks, 2);
+            lazy.get((0, 2)).unwrap();
+            lazy.get((0, 4)).unwrap();
+            let stats = lazy.stats();
+            assert_eq!((stats.hits, stats.misses, stats.cached_bytes), (2, 4, 96));
+
+            // a chunk larger than the budget is still cached by itself
+            let lazy = lazy.with_memory_budget(10);
+            assert_eq!(lazy.stats().cached_chunks, 0);
+            assert_eq!(lazy.read_slice_2d(s![..2, ..2]).unwrap(), arr2(&[[0, 1], [10, 11]]));
+            assert_eq!(lazy.stats().cached_chunks, 1);
+
+            let contiguous = file.new_dataset::<u16>().create("contiguous", (50, 20)).unwrap();
+            let lazy = contiguous.as_lazy::<u16>().unwrap();
+            assert_eq!(lazy.chunk_shape(), &[50, 20]);
+            assert_eq!(lazy.get((49, 19)).unwrap(), 0);
+        })
+    }
+}
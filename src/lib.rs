@@ -19,13 +19,21 @@ compile_error!("Enabling \"mpio\" feature requires HDF5 library built with MPI s
 mod export {
     pub use crate::{
         class::from_id,
-        dim::{Dimension, Ix},
-        error::{silence_errors, Error, Result},
+        diff::diff,
+        dim::{Dimension, Extent, Extents, Ix},
+        error::{
+            reset_error_handler, set_error_handler, silence_errors, silence_errors_scoped, Error,
+            ErrorFrame, ErrorKind, ErrorStack, Result,
+        },
         filters::Filters,
         hl::{
-            Container, Conversion, Dataset, DatasetBuilder, Dataspace, Datatype, File, FileBuilder,
-            Group, Location, Object, PropertyList, Reader, Writer,
+            Attribute, AttributeBuilder, Container, Conversion, Dataset, DatasetBuilder, Dataspace,
+            Datatype, DynArray, File, FileBuilder, Group, Location, Object, PropertyList, Reader,
+            StringArena, UnknownEnumValue, Writer,
         },
+        repack::repack,
+        slice::SliceExpr,
+        sync::sync,
     };
 
     pub use hdf5_derive::H5Type;
@@ -35,9 +43,28 @@ mod export {
         pub use hdf5_types::*;
     }
 
+    pub mod attribute {
+        pub use crate::hl::attribute::{AttrOrder, Attribute, AttributeBuilder, AttributeIter};
+        pub use crate::hl::plist::attribute_create::*;
+    }
+
     pub mod dataset {
-        pub use crate::hl::dataset::{Chunk, Dataset, DatasetBuilder};
+        pub use crate::hl::dataset::{Chunk, Dataset, DatasetBuilder, ResilientRead};
         pub use crate::hl::plist::dataset_access::*;
+        pub use crate::hl::plist::dataset_create::*;
+        pub use crate::hl::plist::dataset_transfer::*;
+    }
+
+    pub mod datatype {
+        pub use crate::hl::datatype::{
+            ByteOrder, CompoundMember, Conversion, Datatype, FloatLayout, SoftConversion,
+            StringPadding, TypeClass,
+        };
+        pub use crate::hl::plist::attribute_create::CharEncoding;
+    }
+
+    pub mod dataspace {
+        pub use crate::hl::space::{Dataspace, SelectionType};
     }
 
     pub mod file {
@@ -46,21 +73,73 @@ mod export {
         pub use crate::hl::plist::file_create::*;
     }
 
+    #[cfg(feature = "hl")]
+    pub mod image {
+        pub use crate::hl::image::{ColorType, ImageInfo, Interlace, RawImage};
+    }
+
+    #[cfg(feature = "hl")]
+    pub mod labeled {
+        pub use crate::hl::labeled::{LabeledAxis, LabeledDataset, LabeledSelection};
+    }
+
+    #[cfg(feature = "hl")]
+    pub mod packet_table {
+        pub use crate::hl::packet_table::PacketTable;
+    }
+
     pub mod plist {
+        pub use crate::hl::plist::attribute_create::AttributeCreate;
         pub use crate::hl::plist::dataset_access::DatasetAccess;
+        pub use crate::hl::plist::dataset_create::DatasetCreate;
+        pub use crate::hl::plist::dataset_transfer::DatasetTransfer;
+        pub use crate::hl::plist::datatype_access::DatatypeAccess;
+        pub use crate::hl::plist::datatype_create::DatatypeCreate;
         pub use crate::hl::plist::file_access::FileAccess;
-        pub use crate::hl::plist::file_create::FileCreate;
+        pub use crate::hl::plist::file_create::{AttrPhaseChangeInfo, FileCreate};
+        pub use crate::hl::plist::group_create::GroupCreate;
+        pub use crate::hl::plist::link_create::LinkCreate;
+        pub use crate::hl::plist::object_copy::ObjectCopy;
         pub use crate::hl::plist::{PropertyList, PropertyListClass};
 
+        pub mod attribute_create {
+            pub use crate::hl::plist::attribute_create::*;
+        }
         pub mod dataset_access {
             pub use crate::hl::plist::dataset_access::*;
         }
+        pub mod dataset_create {
+            pub use crate::hl::plist::dataset_create::*;
+        }
+        pub mod dataset_transfer {
+            pub use crate::hl::plist::dataset_transfer::*;
+        }
+        pub mod datatype_access {
+            pub use crate::hl::plist::datatype_access::*;
+        }
+        pub mod datatype_create {
+            pub use crate::hl::plist::datatype_create::*;
+        }
         pub mod file_access {
             pub use crate::hl::plist::file_access::*;
         }
         pub mod file_create {
             pub use crate::hl::plist::file_create::*;
         }
+        pub mod group_create {
+            pub use crate::hl::plist::group_create::*;
+        }
+        pub mod link_create {
+            pub use crate::hl::plist::link_create::*;
+        }
+        pub mod object_copy {
+            pub use crate::hl::plist::object_copy::*;
+        }
+    }
+
+    #[cfg(feature = "hl")]
+    pub mod table {
+        pub use crate::hl::table::{Table, TableBuilder};
     }
 }
 
@@ -71,13 +150,36 @@ mod macros;
 #[macro_use]
 mod class;
 
+pub mod buffers;
+pub mod csv;
+pub mod describe;
+pub mod diff;
 mod dim;
 mod error;
-mod filters;
+pub mod filters;
 mod globals;
 mod handle;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod layout;
+pub mod lazy;
+pub mod logger;
+pub mod pandas;
+#[cfg(all(feature = "par-chunks", hdf5_1_10_3))]
+pub mod par_chunks;
+pub mod repack;
+pub mod schema;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod slice;
+pub mod stats;
 mod sync;
+pub mod task;
+pub mod timestamp;
 mod util;
+pub mod verify;
+#[cfg(hdf5_1_10_0)]
+pub mod watch;
 
 mod hl;
 
@@ -96,13 +198,13 @@ mod internal_prelude {
     pub use crate::{
         class::ObjectClass,
         dim::Dimension,
-        error::{h5check, silence_errors},
+        error::{catch_callback_panic, h5check, silence_errors, ResultExt},
         export::*,
         handle::{get_id_type, is_valid_user_id, Handle},
         hl::plist::PropertyListClass,
         util::{
-            get_h5_str, string_from_cstr, string_from_fixed_bytes, string_to_fixed_bytes,
-            to_cstring,
+            buffer_size, get_h5_str, string_from_cstr, string_from_fixed_bytes,
+            string_to_fixed_bytes, to_cstring,
         },
     };
 
@@ -138,9 +240,86 @@ pub fn is_library_threadsafe() -> bool {
     }
 }
 
+/// Limits on the sizes of the library's internal free lists, in bytes.
+///
+/// `None` means no limit. The limits apply to the regular, array and block free lists,
+/// both to their total sizes ("global") and to the size of each individual list.
+/// The default values are the ones the library starts with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FreeListLimits {
+    pub regular_global: Option<usize>,
+    pub regular_list: Option<usize>,
+    pub array_global: Option<usize>,
+    pub array_list: Option<usize>,
+    pub block_global: Option<usize>,
+    pub block_list: Option<usize>,
+}
+
+impl Default for FreeListLimits {
+    fn default() -> Self {
+        Self {
+            regular_global: Some(1 << 20),
+            regular_list: Some(64 << 10),
+            array_global: Some(4 << 20),
+            array_list: Some(256 << 10),
+            block_global: Some(16 << 20),
+            block_list: Some(1 << 20),
+        }
+    }
+}
+
+/// Frees all unused memory held by the library's internal free lists.
+///
+/// The memory is returned to the system allocator; this can be called at quiescent
+/// points of long-running processes to bound their memory usage.
+pub fn garbage_collect() -> Result<()> {
+    use hdf5_sys::h5::H5garbage_collect;
+    h5call!(H5garbage_collect()).map(|_| ())
+}
+
+/// Sets limits on the sizes of the library's internal free lists.
+///
+/// Memory freed beyond the limits is returned to the system allocator instead of
+/// being kept for reuse. The limits only apply to memory freed after this call.
+pub fn set_free_list_limits(limits: &FreeListLimits) -> Result<()> {
+    use self::internal_prelude::c_int;
+    use hdf5_sys::h5::H5set_free_list_limits;
+    let lim = |v: Option<usize>| v.map_or(-1, |v| v.min(c_int::max_value() as _) as c_int);
+    h5call!(H5set_free_list_limits(
+        lim(limits.regular_global),
+        lim(limits.regular_list),
+        lim(limits.array_global),
+        lim(limits.array_list),
+        lim(limits.block_global),
+        lim(limits.block_list)
+    ))
+    .map(|_| ())
+}
+
+/// Frees memory allocated by the library and handed over to the caller.
+///
+/// # Safety
+///
+/// The pointer must have been allocated by the HDF5 library (e.g. returned by one of
+/// the raw `hdf5_sys` functions documented as requiring the caller to free it) and
+/// must not be used afterwards.
+#[cfg(hdf5_1_8_13)]
+pub unsafe fn free_memory(mem: *mut self::internal_prelude::c_void) -> Result<()> {
+    use hdf5_sys::h5::H5free_memory;
+    h5call!(H5free_memory(mem)).map(|_| ())
+}
+
 #[cfg(test)]
 pub mod tests {
-    use crate::library_version;
+    use crate::{garbage_collect, library_version, set_free_list_limits, FreeListLimits};
+
+    #[test]
+    pub fn test_free_lists() {
+        let limits = FreeListLimits { block_global: None, ..FreeListLimits::default() };
+        set_free_list_limits(&limits).unwrap();
+        set_free_list_limits(&FreeListLimits::default()).unwrap();
+        garbage_collect().unwrap();
+    }
 
     #[test]
     pub fn test_library_version() {
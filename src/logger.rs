@@ -0,0 +1,376 @@
+//! Buffered logging of records to extendable datasets.
+//!
+//! A `RecordLogger` appends records of an `H5Type` to a one-dimensional, chunked and
+//! resizable dataset. Records are buffered in memory and written in batches when the
+//! buffer is full, when the flush interval has elapsed since the last write (checked
+//! whenever a record is logged), on `flush()`, and when the logger is closed or dropped:
+//!
+//! ```ignore
+//! #[derive(H5Type)]
+//! #[repr(C)]
+//! struct Sample {
+//!     time: f64,
+//!     temperature: f32,
+//! }
+//!
+//! let file = File::with_options().fapl(|p| p.libver_latest()).create("log.h5")?;
+//! let mut logger = file
+//!     .new_record_logger::<Sample>()
+//!     .buffer_size(100)
+//!     .flush_interval(Duration::from_secs(1))
+//!     .swmr(true)
+//!     .create("samples")?;
+//! logger.log(Sample { time: 0.0, temperature: 21.5 })?;
+//! ```
+//!
+//! In SWMR (single-writer/multiple-reader) mode, the file is switched to SWMR writing once
+//! the dataset has been created, so that readers opening the file with
+//! `File::open_swmr()` see the records as they are flushed, e.g. via `Dataset::watch()`.
+//! This requires the file to have been opened with the latest library version bounds, and
+//! readers only see objects that existed when SWMR writing was started.
+
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use ndarray::s;
+
+#[cfg(not(hdf5_1_10_0))]
+use hdf5_sys::h5f::{H5F_scope_t::H5F_SCOPE_LOCAL, H5Fflush};
+#[cfg(hdf5_1_10_0)]
+use hdf5_sys::{
+    h5d::H5Dflush,
+    h5f::{H5Fget_intent, H5Fstart_swmr_write, H5F_ACC_SWMR_WRITE},
+};
+
+use crate::internal_prelude::*;
+
+/// Number of records buffered by a `RecordLogger` unless configured otherwise.
+pub const DEFAULT_BUFFER_SIZE: usize = 1024;
+
+/// Builder for `RecordLogger`, see `Group::new_record_logger()`.
+#[derive(Clone)]
+pub struct RecordLoggerBuilder<T> {
+    parent: Group,
+    buffer_size: usize,
+    flush_interval: Option<Duration>,
+    chunk: Option<usize>,
+    filters: Filters,
+    swmr: bool,
+    _marker: PhantomData<T>,
+}
+
+impl<T: H5Type> RecordLoggerBuilder<T> {
+    fn new(parent: &Group) -> Self {
+        Self {
+            parent: parent.clone(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            flush_interval: None,
+            chunk: None,
+            filters: Filters::default(),
+            swmr: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets the number of records buffered before they're written (at least one).
+    pub fn buffer_size(&mut self, buffer_size: usize) -> &mut Self {
+        self.buffer_size = buffer_size.max(1);
+        self
+    }
+
+    /// Sets the maximum time buffered records are kept before they're written.
+    pub fn flush_interval(&mut self, interval: Duration) -> &mut Self {
+        self.flush_interval = Some(interval);
+        self
+    }
+
+    /// Sets the number of records per chunk of a newly created dataset (the buffer size
+    /// by default).
+    pub fn chunk(&mut self, chunk: usize) -> &mut Self {
+        self.chunk = Some(chunk);
+        self
+    }
+
+    /// Sets the filters used when creating the dataset.
+    pub fn filters(&mut self, filters: &Filters) -> &mut Self {
+        self.filters = filters.clone();
+        self
+    }
+
+    /// Sets whether to switch the file to SWMR writing mode, see the module documentation.
+    #[cfg(hdf5_1_10_0)]
+    pub fn swmr(&mut self, swmr: bool) -> &mut Self {
+        self.swmr = swmr;
+        self
+    }
+
+    /// Creates a new, empty dataset and returns a logger appending to it.
+    pub fn create(&self, name: &str) -> Result<RecordLogger<T>> {
+        let dataset = self
+            .parent
+            .new_dataset::<T>()
+            .chunk(self.chunk.unwrap_or(self.buffer_size).max(1))
+            .resizable(true)
+            .filters(&self.filters)
+            .create(name, 0)?;
+        self.finish(dataset)
+    }
+
+    /// Opens an existing dataset and returns a logger appending to its end.
+    ///
+    /// The dataset must be one-dimensional and resizable, and its datatype must be
+    /// convertible to and from `T`.
+    pub fn open(&self, name: &str) -> Result<RecordLogger<T>> {
+        let dataset = self.parent.dataset(name)?;
+        ensure!(
+            dataset.ndim() == 1,
+            "record logger datasets must be 1-dimensional, '{}' has shape {:?}",
+            name,
+            dataset.shape()
+        );
+        ensure!(dataset.is_resizable(), "dataset '{}' is not resizable", name);
+        let file_dtype = dataset.dtype()?;
+        Datatype::from_type::<T>()?.ensure_convertible(&file_dtype, Conversion::Soft)?;
+        self.finish(dataset)
+    }
+
+    fn finish(&self, dataset: Dataset) -> Result<RecordLogger<T>> {
+        if self.swmr {
+            start_swmr_write(&dataset.file()?)?;
+        }
+        Ok(RecordLogger {
+            written: dataset.size(),
+            dataset,
+            buffer: Vec::with_capacity(self.buffer_size),
+            buffer_size: self.buffer_size,
+            flush_interval: self.flush_interval,
+            last_flush: Instant::now(),
+            swmr: self.swmr,
+        })
+    }
+}
+
+#[cfg(hdf5_1_10_0)]
+fn start_swmr_write(file: &File) -> Result<()> {
+    h5lock!({
+        let mut intent = 0;
+        h5try!(H5Fget_intent(file.id(), &mut intent));
+        if intent & H5F_ACC_SWMR_WRITE == 0 {
+            h5call!(H5Fstart_swmr_write(file.id())).map_err(|err| {
+                format!(
+                    "unable to start SWMR writing (the file must be opened for writing with \
+                     the latest library version bounds): {}",
+                    err
+                )
+            })?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(not(hdf5_1_10_0))]
+fn start_swmr_write(_file: &File) -> Result<()> {
+    fail!("SWMR requires HDF5 1.10.0 or later")
+}
+
+/// Appends buffered records to a dataset, see the module documentation.
+pub struct RecordLogger<T: H5Type> {
+    dataset: Dataset,
+    buffer: Vec<T>,
+    buffer_size: usize,
+    flush_interval: Option<Duration>,
+    last_flush: Instant,
+    written: usize,
+    swmr: bool,
+}
+
+impl<T: H5Type> Debug for RecordLogger<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RecordLogger")
+            .field("dataset", &self.dataset)
+            .field("written", &self.written)
+            .field("buffered", &self.buffer.len())
+            .field("swmr", &self.swmr)
+            .finish()
+    }
+}
+
+impl<T: H5Type> Drop for RecordLogger<T> {
+    fn drop(&mut self) {
+        // errors can't be reported from here; use `close()` to handle them
+        let _e = silence_errors();
+        let _ = self.flush();
+    }
+}
+
+impl<T: H5Type> RecordLogger<T> {
+    /// Returns the dataset the records are appended to.
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+
+    /// Returns the total number of records, including the buffered ones.
+    pub fn len(&self) -> usize {
+        self.written + self.buffer.len()
+    }
+
+    /// Returns `true` if no records have been logged.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of records that are buffered but not yet written.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns `true` if the file was switched to SWMR writing mode by this logger.
+    pub fn is_swmr(&self) -> bool {
+        self.swmr
+    }
+
+    /// Logs a record, writing the buffered records if the buffer is full or the flush
+    /// interval has elapsed.
+    pub fn log(&mut self, record: T) -> Result<()> {
+        self.buffer.push(record);
+        let expired = self.flush_interval.map_or(false, |t| self.last_flush.elapsed() >= t);
+        if self.buffer.len() >= self.buffer_size || expired {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Logs several records, see `log()`.
+    pub fn log_all<I: IntoIterator<Item = T>>(&mut self, records: I) -> Result<()> {
+        for record in records {
+            self.log(record)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered records to the dataset and flushes it to the file, so that
+    /// they become visible to SWMR readers.
+    ///
+    /// If writing fails, the records are kept in the buffer and written by the next flush.
+    pub fn flush(&mut self) -> Result<()> {
+        self.last_flush = Instant::now();
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let (start, end) = (self.written, self.written + self.buffer.len());
+        self.dataset.resize(end)?;
+        self.dataset.write_slice(&self.buffer[..], s![start..end])?;
+        self.buffer.clear();
+        self.written = end;
+        flush_dataset(&self.dataset)
+    }
+
+    /// Writes the buffered records and closes the logger, returning any error; dropping
+    /// the logger also writes them but ignores errors.
+    pub fn close(mut self) -> Result<()> {
+        self.flush()
+    }
+}
+
+#[cfg(hdf5_1_10_0)]
+fn flush_dataset(dataset: &Dataset) -> Result<()> {
+    h5call!(H5Dflush(dataset.id())).and(Ok(()))
+}
+
+#[cfg(not(hdf5_1_10_0))]
+fn flush_dataset(dataset: &Dataset) -> Result<()> {
+    h5call!(H5Fflush(dataset.id(), H5F_SCOPE_LOCAL)).and(Ok(()))
+}
+
+impl Group {
+    /// Instantiates a new record logger builder, see `RecordLogger`.
+    pub fn new_record_logger<T: H5Type>(&self) -> RecordLoggerBuilder<T> {
+        RecordLoggerBuilder::new(self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::hl::plist::file_access::LibraryVersion;
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_record_logger() {
+        with_tmp_file(|file| {
+            let mut logger = file.new_record_logger::<(u32, f64)>().buffer_size(3).create("log");
+            let logger = logger.as_mut().unwrap();
+            assert!(logger.is_empty());
+            assert_eq!(logger.dataset().chunks(), Some(vec![3]));
+            logger.log((0, 0.5)).unwrap();
+            logger.log((1, 1.5)).unwrap();
+            assert_eq!((logger.len(), logger.buffered(), logger.dataset().size()), (2, 2, 0));
+            logger.log((2, 2.5)).unwrap();
+            assert_eq!((logger.len(), logger.buffered(), logger.dataset().size()), (3, 0, 3));
+            logger.log_all((3..8).map(|i| (i, f64::from(i) + 0.5))).unwrap();
+            assert_eq!((logger.buffered(), logger.dataset().size()), (2, 6));
+            logger.flush().unwrap();
+            let records = file.dataset("log").unwrap().read_raw::<(u32, f64)>().unwrap();
+            assert_eq!(records.len(), 8);
+            assert_eq!(records[7], (7, 7.5));
+
+            // appending to an existing dataset, flushing the buffer on drop
+            {
+                let mut logger = file.new_record_logger::<(u32, f64)>().open("log").unwrap();
+                assert_eq!(logger.len(), 8);
+                logger.log((8, 8.5)).unwrap();
+            }
+            assert_eq!(file.dataset("log").unwrap().size(), 9);
+
+            file.new_dataset::<u8>().create("fixed", 4).unwrap();
+            assert_err!(file.new_record_logger::<u8>().open("fixed"), "is not resizable");
+            file.new_dataset::<u8>().resizable(true).create("grid", (1, 2)).unwrap();
+            assert_err!(file.new_record_logger::<u8>().open("grid"), "must be 1-dimensional");
+        })
+    }
+
+    #[test]
+    pub fn test_record_logger_interval() {
+        with_tmp_file(|file| {
+            let mut builder = file.new_record_logger::<u16>();
+            builder.flush_interval(Duration::from_millis(20));
+            let mut logger = builder.create("log").unwrap();
+            logger.log(1).unwrap();
+            assert_eq!(logger.buffered(), 1);
+            thread::sleep(Duration::from_millis(30));
+            logger.log(2).unwrap();
+            assert_eq!((logger.buffered(), logger.dataset().size()), (0, 2));
+            logger.log(3).unwrap();
+            logger.close().unwrap();
+            assert_eq!(file.dataset("log").unwrap().read_raw::<u16>().unwrap(), vec![1, 2, 3]);
+        })
+    }
+
+    #[test]
+    #[cfg(hdf5_1_10_0)]
+    pub fn test_record_logger_swmr() {
+        with_tmp_path(|path| {
+            let file = File::create(&path).unwrap();
+            assert_err!(
+                file.new_record_logger::<i32>().swmr(true).create("log"),
+                "unable to start SWMR writing"
+            );
+            drop(file);
+
+            let latest = LibraryVersion::latest();
+            let mut builder = File::with_options();
+            builder.fapl().libver_bounds(latest, latest);
+            let file = builder.create(&path).unwrap();
+            let mut logger = file.new_record_logger::<i32>().swmr(true).create("log").unwrap();
+            assert!(logger.is_swmr());
+            logger.log_all(vec![1, 2, 3]).unwrap();
+            logger.flush().unwrap();
+
+            let reader = builder.open_swmr(&path).unwrap();
+            assert_eq!(reader.dataset("log").unwrap().read_raw::<i32>().unwrap(), vec![1, 2, 3]);
+        })
+    }
+}
@@ -12,6 +12,17 @@ macro_rules! fail {
     );
 }
 
+/// Emits a log record with the `hdf5` target if the `log` feature is enabled; the
+/// arguments are not evaluated otherwise.
+macro_rules! h5log {
+    ($lvl:ident, $($arg:tt)+) => {
+        #[cfg(feature = "log")]
+        {
+            log::$lvl!(target: "hdf5", $($arg)+);
+        }
+    };
+}
+
 macro_rules! try_ref_clone {
     ($expr:expr) => {
         match $expr {
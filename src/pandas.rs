@@ -0,0 +1,736 @@
+//! Readers for data frames written by pandas (`DataFrame.to_hdf()` or `HDFStore`).
+//!
+//! pandas stores each frame in a group named after its key, in one of two layouts:
+//!
+//! - `fixed` (`pandas_type` is `frame`): the row index and column labels are stored in
+//!   the `axis1` and `axis0` datasets, and the values are split into blocks of columns of
+//!   the same type, each stored as a two-dimensional `block{N}_values` dataset along with
+//!   the labels of its columns in `block{N}_items`. Columns of object dtype (e.g. Python
+//!   strings) are pickled and can't be read.
+//! - `table` (`pandas_type` is `frame_table`): the frame is a PyTables table, i.e. a
+//!   compound dataset named `table` with an `index` field, `values_block_{N}` array fields
+//!   holding blocks of columns, and a scalar field for each data column. Column labels and
+//!   types are recorded in attributes of the table (some of them pickled by PyTables).
+//!
+//! `read_frame()` detects the layout and returns the frame as a `Frame` holding a typed
+//! `Column` for the index and for each column. Multi-indexes are not supported.
+
+use std::convert::TryFrom;
+
+use hdf5_sys::h5a::H5Aread;
+use hdf5_types::{DynValue, IntSize, TypeDescriptor as TD};
+
+use crate::internal_prelude::*;
+
+/// Values of a column or of the row index of a data frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Column {
+    Bool(Vec<bool>),
+    Int(Vec<i64>),
+    UInt(Vec<u64>),
+    Float(Vec<f64>),
+    String(Vec<String>),
+    /// Timestamps as nanoseconds since the Unix epoch (`datetime64[ns]`).
+    DateTime(Vec<i64>),
+}
+
+impl Column {
+    /// Returns the number of values in the column.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Bool(v) => v.len(),
+            Self::Int(v) | Self::DateTime(v) => v.len(),
+            Self::UInt(v) => v.len(),
+            Self::Float(v) => v.len(),
+            Self::String(v) => v.len(),
+        }
+    }
+
+    /// Returns `true` if the column has no values.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn as_bool(&self) -> Option<&[bool]> {
+        if let Self::Bool(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_int(&self) -> Option<&[i64]> {
+        if let Self::Int(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_uint(&self) -> Option<&[u64]> {
+        if let Self::UInt(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_float(&self) -> Option<&[f64]> {
+        if let Self::Float(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&[String]> {
+        if let Self::String(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_datetime(&self) -> Option<&[i64]> {
+        if let Self::DateTime(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    fn empty_like(ty: &TD, datetime: bool) -> Result<Self> {
+        Ok(match ty {
+            TD::Integer(_) if datetime => Self::DateTime(vec![]),
+            TD::Integer(_) => Self::Int(vec![]),
+            TD::Unsigned(_) => Self::UInt(vec![]),
+            TD::Float(_) => Self::Float(vec![]),
+            TD::Boolean | TD::Bitfield(_) => Self::Bool(vec![]),
+            TD::Enum(enum_type) if enum_type.members.len() <= 2 => Self::Bool(vec![]),
+            TD::FixedAscii(_) | TD::FixedUnicode(_) | TD::VarLenAscii | TD::VarLenUnicode => {
+                Self::String(vec![])
+            }
+            TD::VarLenArray(ty) if **ty == TD::Unsigned(IntSize::U1) => {
+                fail!("object (pickled) values are not supported")
+            }
+            _ => fail!("unsupported column type: {:?}", ty),
+        })
+    }
+
+    fn push(&mut self, value: &DynValue) -> Result<()> {
+        match (self, value) {
+            (Self::Bool(v), DynValue::Bool(x)) => v.push(*x),
+            (Self::Bool(v), DynValue::UInt(x)) => v.push(*x != 0),
+            (Self::Bool(v), DynValue::Enum { value: x, .. }) => v.push(*x != 0),
+            (Self::Int(v), DynValue::Int(x)) | (Self::DateTime(v), DynValue::Int(x)) => {
+                v.push(i64::try_from(*x).map_err(|_| format!("value out of range: {}", x))?)
+            }
+            (Self::UInt(v), DynValue::UInt(x)) => {
+                v.push(u64::try_from(*x).map_err(|_| format!("value out of range: {}", x))?)
+            }
+            (Self::Float(v), DynValue::Float(x)) => v.push(*x),
+            (Self::String(v), DynValue::String(x)) => v.push(x.clone()),
+            (_, value) => fail!("unexpected value: {:?}", value),
+        }
+        Ok(())
+    }
+
+    fn labels(&self) -> Vec<String> {
+        match self {
+            Self::Bool(v) => v.iter().map(ToString::to_string).collect(),
+            Self::Int(v) | Self::DateTime(v) => v.iter().map(ToString::to_string).collect(),
+            Self::UInt(v) => v.iter().map(ToString::to_string).collect(),
+            Self::Float(v) => v.iter().map(ToString::to_string).collect(),
+            Self::String(v) => v.clone(),
+        }
+    }
+}
+
+/// A data frame read via `read_frame()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    index: Column,
+    columns: Vec<(String, Column)>,
+}
+
+impl Frame {
+    /// Returns the row index.
+    pub fn index(&self) -> &Column {
+        &self.index
+    }
+
+    /// Returns the columns with their labels, in frame order.
+    pub fn columns(&self) -> &[(String, Column)] {
+        &self.columns
+    }
+
+    /// Returns the column labels, in frame order; non-string labels are formatted.
+    pub fn column_names(&self) -> Vec<&str> {
+        self.columns.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Returns the column with the given label.
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|(n, _)| n == name).map(|(_, column)| column)
+    }
+
+    /// Returns the number of rows.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Returns `true` if the frame has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+}
+
+/// Reads the data frame stored by pandas under the given key, e.g. `"df"` after
+/// `df.to_hdf(path, "df")`; see the module documentation for the supported layouts.
+pub fn read_frame(store: &Group, key: &str) -> Result<Frame> {
+    let group = store.group(key)?;
+    match attr_string(&group, "pandas_type")?.as_deref() {
+        Some("frame") => read_fixed_frame(&group),
+        Some("frame_table") => read_table_frame(&group),
+        Some(other) => fail!("unsupported pandas_type: {:?}", other),
+        None => fail!("group '{}' is not a pandas object (no pandas_type)", key),
+    }
+    .map_err(|err| format!("unable to read pandas frame '{}': {}", key, err).into())
+}
+
+fn attr_string(loc: &Location, name: &str) -> Result<Option<String>> {
+    loc.attr_opt(name)?.map(|attr| attr.read_string()).transpose()
+}
+
+/// Reads the raw bytes of a scalar string attribute, which may hold non-UTF-8 data.
+fn attr_bytes(loc: &Location, name: &str) -> Result<Option<Vec<u8>>> {
+    let attr = match loc.attr_opt(name)? {
+        Some(attr) => attr,
+        None => return Ok(None),
+    };
+    let dtype = attr.dtype()?;
+    match dtype.to_descriptor()? {
+        TD::FixedAscii(len) | TD::FixedUnicode(len) if attr.is_scalar() => {
+            let mut buf = vec![0_u8; len];
+            h5try!(H5Aread(attr.id(), dtype.id(), buf.as_mut_ptr() as *mut c_void));
+            while buf.last() == Some(&0) {
+                buf.pop();
+            }
+            Ok(Some(buf))
+        }
+        _ => attr.read_string().map(|s| Some(s.into_bytes())),
+    }
+}
+
+fn attr_flag(loc: &Location, name: &str) -> Result<bool> {
+    Ok(match loc.attr_opt(name)? {
+        Some(attr) => match attr.read_dyn_value()? {
+            DynValue::Bool(x) => x,
+            DynValue::Int(x) | DynValue::Enum { value: x, .. } => x != 0,
+            DynValue::UInt(x) => x != 0,
+            value => fail!("invalid value of attribute '{}': {:?}", name, value),
+        },
+        None => false,
+    })
+}
+
+fn attr_int(loc: &Location, name: &str) -> Result<usize> {
+    match loc.attr(name)?.read_dyn_value()? {
+        DynValue::Int(x) if x >= 0 => Ok(x as _),
+        DynValue::UInt(x) => Ok(x as _),
+        value => fail!("invalid value of attribute '{}': {:?}", name, value),
+    }
+}
+
+fn is_datetime(kind: Option<String>) -> bool {
+    kind.map_or(false, |kind| kind.starts_with("datetime64"))
+}
+
+/// Returns the value of the `shape` attribute that pandas attaches to empty arrays,
+/// which are stored with a single placeholder element per axis.
+fn empty_shape(dataset: &Dataset) -> Result<Option<Vec<Ix>>> {
+    match dataset.attr_opt("shape")? {
+        Some(attr) => {
+            let dims = attr
+                .read_dyn_array()?
+                .iter()
+                .map(|dim| match dim {
+                    DynValue::Int(x) => Ok(*x as _),
+                    DynValue::UInt(x) => Ok(*x as _),
+                    _ => Err(Error::from("invalid 'shape' attribute")),
+                })
+                .collect::<Result<Vec<Ix>>>()?;
+            Ok(if dims.contains(&0) { Some(dims) } else { None })
+        }
+        None => Ok(None),
+    }
+}
+
+/// Reads an index dataset (`axis0`, `axis1` or `block{N}_items`) of the fixed layout.
+fn read_index(group: &Group, name: &str) -> Result<Column> {
+    let dataset = group.dataset(name)?;
+    ensure!(dataset.ndim() == 1, "index '{}' must be one-dimensional", name);
+    let datetime = is_datetime(attr_string(&dataset, "kind")?);
+    let mut column = Column::empty_like(&dataset.dtype()?.to_descriptor()?, datetime)?;
+    if empty_shape(&dataset)?.is_none() {
+        for value in dataset.read_dyn_array()?.iter() {
+            column.push(value)?;
+        }
+    }
+    Ok(column)
+}
+
+/// Reads a `block{N}_values` dataset of the fixed layout as a list of columns.
+fn read_block(dataset: &Dataset, ncols: usize, nrows: usize) -> Result<Vec<Column>> {
+    let datetime = is_datetime(attr_string(dataset, "value_type")?);
+    let empty = Column::empty_like(&dataset.dtype()?.to_descriptor()?, datetime)?;
+    let mut columns = vec![empty; ncols];
+    if empty_shape(dataset)?.is_some() {
+        return Ok(columns);
+    }
+    let transposed = attr_flag(dataset, "transposed")?;
+    let expected = if transposed { vec![nrows, ncols] } else { vec![ncols, nrows] };
+    ensure!(
+        dataset.shape() == expected,
+        "block '{}' has shape {:?}, expected {:?}",
+        dataset.name(),
+        dataset.shape(),
+        expected
+    );
+    for (ix, value) in dataset.read_dyn_array()?.indexed_iter() {
+        columns[if transposed { ix[1] } else { ix[0] }].push(value)?;
+    }
+    Ok(columns)
+}
+
+fn read_fixed_frame(group: &Group) -> Result<Frame> {
+    for axis in &["axis0_variety", "axis1_variety"] {
+        let variety = attr_string(group, axis)?;
+        ensure!(
+            variety.as_deref().map_or(true, |v| v == "regular"),
+            "multi-indexes are not supported"
+        );
+    }
+    let names = read_index(group, "axis0")?.labels();
+    let index = read_index(group, "axis1")?;
+    let mut columns: Vec<Option<Column>> = vec![None; names.len()];
+    for block in 0..attr_int(group, "nblocks")? {
+        let items = read_index(group, &format!("block{}_items", block))?.labels();
+        let dataset = group.dataset(&format!("block{}_values", block))?;
+        for (item, column) in items.iter().zip(read_block(&dataset, items.len(), index.len())?) {
+            let position = names.iter().position(|name| name == item);
+            let slot = position.and_then(|n| columns.get_mut(n).filter(|c| c.is_none()));
+            match slot {
+                Some(slot) => *slot = Some(column),
+                None => fail!("unexpected column {:?} in block {}", item, block),
+            }
+        }
+    }
+    let columns = names
+        .into_iter()
+        .zip(columns)
+        .map(|(name, column)| match column {
+            Some(column) => Ok((name, column)),
+            None => Err(format!("column {:?} not found in any block", name).into()),
+        })
+        .collect::<Result<_>>()?;
+    Ok(Frame { index, columns })
+}
+
+fn read_line<'a>(data: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    let end = *pos + data[*pos..].iter().position(|&b| b == b'\n')?;
+    let line = &data[*pos..end];
+    *pos = end + 1;
+    Some(line)
+}
+
+/// A Python value pickled by PyTables, which stores attributes that aren't NumPy arrays
+/// (such as lists of column labels) using pickle protocol 0. Tuples are read as lists.
+#[derive(Clone, Debug, PartialEq)]
+enum Pickled {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    List(Vec<Pickled>),
+    Dict(Vec<(Pickled, Pickled)>),
+}
+
+impl Pickled {
+    fn as_str(&self) -> Option<&str> {
+        if let Self::Str(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    fn as_list(&self) -> Option<&[Self]> {
+        if let Self::List(items) = self {
+            Some(items)
+        } else {
+            None
+        }
+    }
+
+    /// Formats a list of labels the way `Column::labels()` does.
+    fn labels(&self) -> Option<Vec<String>> {
+        self.as_list()?
+            .iter()
+            .map(|label| match label {
+                Self::Str(s) => Some(s.clone()),
+                Self::Int(x) => Some(x.to_string()),
+                Self::Float(x) => Some(x.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Decodes a `raw-unicode-escape` string: `\uXXXX` and `\UXXXXXXXX` escapes, with all
+/// other bytes being Latin-1 characters.
+fn decode_raw_unicode(bytes: &[u8]) -> Option<String> {
+    let mut s = String::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let len = match bytes.get(i..i + 2) {
+            Some(b"\\u") => 4,
+            Some(b"\\U") => 8,
+            _ => {
+                s.push(char::from(bytes[i]));
+                i += 1;
+                continue;
+            }
+        };
+        let hex = std::str::from_utf8(bytes.get(i + 2..i + 2 + len)?).ok()?;
+        s.push(std::char::from_u32(u32::from_str_radix(hex, 16).ok()?)?);
+        i += 2 + len;
+    }
+    Some(s)
+}
+
+/// Decodes the Python `repr()` of a (byte) string, as used by the `S` opcode.
+fn decode_repr(repr: &[u8]) -> Option<String> {
+    let quote = *repr.first().filter(|&&q| q == b'\'' || q == b'"')?;
+    ensure_opt(repr.len() >= 2 && repr[repr.len() - 1] == quote)?;
+    let (mut bytes, mut iter) = (Vec::new(), repr[1..repr.len() - 1].iter());
+    while let Some(&b) = iter.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        bytes.push(match *iter.next()? {
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'x' => {
+                let hex = [*iter.next()?, *iter.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            }
+            other => other,
+        });
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn ensure_opt(condition: bool) -> Option<()> {
+    if condition {
+        Some(())
+    } else {
+        None
+    }
+}
+
+/// Unpickles the given data, returning `None` if it uses unsupported opcodes (e.g. for
+/// class instances) or is malformed.
+fn unpickle(data: &[u8]) -> Option<Pickled> {
+    enum Item {
+        Mark,
+        Value(Pickled),
+    }
+
+    fn pop(stack: &mut Vec<Item>) -> Option<Pickled> {
+        match stack.pop()? {
+            Item::Value(value) => Some(value),
+            Item::Mark => None,
+        }
+    }
+
+    fn pop_mark(stack: &mut Vec<Item>) -> Option<Vec<Pickled>> {
+        let mark = stack.iter().rposition(|item| matches!(item, Item::Mark))?;
+        let items = stack.split_off(mark + 1);
+        stack.pop();
+        items
+            .into_iter()
+            .map(|item| if let Item::Value(v) = item { Some(v) } else { None })
+            .collect()
+    }
+
+    fn pairs(items: Vec<Pickled>) -> Option<Vec<(Pickled, Pickled)>> {
+        ensure_opt(items.len() % 2 == 0)?;
+        let mut iter = items.into_iter();
+        let mut pairs = Vec::new();
+        while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+            pairs.push((key, value));
+        }
+        Some(pairs)
+    }
+
+    let mut stack = Vec::new();
+    let mut memo = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let opcode = data[pos];
+        pos += 1;
+        let value = match opcode {
+            b'(' => {
+                stack.push(Item::Mark);
+                continue;
+            }
+            b'N' => Pickled::None,
+            b'I' => match read_line(data, &mut pos)? {
+                b"00" => Pickled::Bool(false),
+                b"01" => Pickled::Bool(true),
+                line => Pickled::Int(std::str::from_utf8(line).ok()?.parse().ok()?),
+            },
+            b'L' => {
+                let line = std::str::from_utf8(read_line(data, &mut pos)?).ok()?;
+                Pickled::Int(line.trim_end_matches('L').parse().ok()?)
+            }
+            b'F' => {
+                Pickled::Float(std::str::from_utf8(read_line(data, &mut pos)?).ok()?.parse().ok()?)
+            }
+            b'V' => Pickled::Str(decode_raw_unicode(read_line(data, &mut pos)?)?),
+            b'S' => Pickled::Str(decode_repr(read_line(data, &mut pos)?)?),
+            b'l' | b't' => Pickled::List(pop_mark(&mut stack)?),
+            b']' | b')' => Pickled::List(vec![]),
+            b'd' => Pickled::Dict(pairs(pop_mark(&mut stack)?)?),
+            b'}' => Pickled::Dict(vec![]),
+            b'a' | b'e' | b's' | b'u' => {
+                let items = match opcode {
+                    b'a' => vec![pop(&mut stack)?],
+                    b's' => {
+                        let value = pop(&mut stack)?;
+                        vec![pop(&mut stack)?, value]
+                    }
+                    _ => pop_mark(&mut stack)?,
+                };
+                match (stack.last_mut()?, opcode) {
+                    (Item::Value(Pickled::List(list)), b'a')
+                    | (Item::Value(Pickled::List(list)), b'e') => list.extend(items),
+                    (Item::Value(Pickled::Dict(dict)), b's')
+                    | (Item::Value(Pickled::Dict(dict)), b'u') => dict.extend(pairs(items)?),
+                    _ => return None,
+                }
+                continue;
+            }
+            b'p' => {
+                let key = read_line(data, &mut pos)?.to_vec();
+                match stack.last()? {
+                    Item::Value(value) => memo.push((key, value.clone())),
+                    Item::Mark => return None,
+                }
+                continue;
+            }
+            b'g' => {
+                let key = read_line(data, &mut pos)?;
+                memo.iter().rev().find(|(k, _)| k == key)?.1.clone()
+            }
+            b'0' => {
+                pop(&mut stack)?;
+                continue;
+            }
+            b'.' => return pop(&mut stack),
+            _ => return None,
+        };
+        stack.push(Item::Value(value));
+    }
+    None
+}
+
+fn pickled_attr(loc: &Location, name: &str) -> Result<Option<Pickled>> {
+    Ok(attr_bytes(loc, name)?.and_then(|bytes| unpickle(&bytes)))
+}
+
+/// A field of a pandas table and the frame columns it holds.
+struct TableField {
+    names: Vec<String>,
+    columns: Vec<Column>,
+    is_array: bool,
+}
+
+fn read_table_frame(group: &Group) -> Result<Frame> {
+    let table = group.dataset("table")?;
+    ensure!(table.ndim() == 1, "'table' must be one-dimensional");
+    let mut fields = match table.dtype()?.to_descriptor()? {
+        TD::Compound(compound) => compound.fields,
+        _ => fail!("'table' must be of a compound type"),
+    };
+    fields.sort_by_key(|f| f.index);
+
+    let index_cols = pickled_attr(group, "index_cols")?;
+    let index_name = index_cols
+        .as_ref()
+        .and_then(|cols| cols.as_list()?.first()?.as_list()?.get(1)?.as_str())
+        .unwrap_or("index");
+    let index_pos = fields
+        .iter()
+        .position(|f| f.name == index_name)
+        .ok_or_else(|| format!("index field '{}' not found", index_name))?;
+
+    let mut layout = Vec::with_capacity(fields.len());
+    for (i, field) in fields.iter().enumerate() {
+        let kind = format!("{}_kind", field.name);
+        let (ty, len, is_array) = match field.ty {
+            TD::FixedArray(ref ty, len) => (&**ty, len, true),
+            ref ty => (ty, 1, false),
+        };
+        let (datetime, names) = if i == index_pos {
+            (is_datetime(attr_string(&table, &kind)?), vec![field.name.clone()])
+        } else {
+            let dtype = attr_string(&table, &format!("{}_dtype", field.name))?;
+            let labels = pickled_attr(&table, &kind)?.and_then(|kind| kind.labels());
+            let names = labels.filter(|labels| labels.len() == len).unwrap_or_else(|| {
+                if is_array {
+                    (0..len).map(|n| format!("{}_{}", field.name, n)).collect()
+                } else {
+                    vec![field.name.clone()]
+                }
+            });
+            (is_datetime(dtype), names)
+        };
+        let column = Column::empty_like(ty, datetime)
+            .map_err(|err| format!("field '{}': {}", field.name, err))?;
+        layout.push(TableField { names, columns: vec![column; len], is_array });
+    }
+
+    for row in table.read_dyn_array()?.iter() {
+        let values = match row {
+            DynValue::Compound(values) => values,
+            _ => fail!("unexpected non-compound value"),
+        };
+        for (field, (_, value)) in layout.iter_mut().zip(values) {
+            match value {
+                DynValue::Array(items) if field.is_array => {
+                    for (column, item) in field.columns.iter_mut().zip(items) {
+                        column.push(item)?;
+                    }
+                }
+                value => field.columns[0].push(value)?,
+            }
+        }
+    }
+
+    let index = layout.remove(index_pos).columns.remove(0);
+    let mut columns: Vec<(String, Column)> =
+        layout.into_iter().flat_map(|f| f.names.into_iter().zip(f.columns)).collect();
+    // non_index_axes is a list of (axis, labels) pairs holding the frame column order
+    let order = pickled_attr(group, "non_index_axes")?
+        .and_then(|axes| axes.as_list()?.first()?.as_list()?.get(1)?.labels());
+    if let Some(order) = order {
+        let position = |name: &str| order.iter().position(|n| n == name);
+        if columns.len() == order.len() && columns.iter().all(|(name, _)| position(name).is_some())
+        {
+            columns.sort_by_key(|(name, _)| position(name));
+        }
+    }
+    Ok(Frame { index, columns })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use hdf5_types::{FixedAscii, VarLenArray};
+
+    use super::{read_frame, unpickle, Column, Pickled};
+    use crate::internal_prelude::*;
+
+    fn set_str(loc: &Location, name: &str, value: &str) {
+        loc.set_attr(name, &FixedAscii::<[u8; 32]>::from_ascii(value).unwrap()).unwrap();
+    }
+
+    fn write_labels(group: &Group, name: &str, labels: &[&str]) {
+        let labels: Vec<_> =
+            labels.iter().map(|s| FixedAscii::<[u8; 8]>::from_ascii(s).unwrap()).collect();
+        let ds = group.new_dataset::<FixedAscii<[u8; 8]>>().create(name, labels.len()).unwrap();
+        ds.write(&labels).unwrap();
+        set_str(&ds, "kind", "string");
+    }
+
+    #[test]
+    pub fn test_unpickle() {
+        let s = |s: &str| Pickled::Str(s.into());
+        assert_eq!(
+            unpickle(b"(lp0\nVA\np1\naVB\np2\na."),
+            Some(Pickled::List(vec![s("A"), s("B")]))
+        );
+        assert_eq!(
+            unpickle(b"(lp0\n(I1\n(lp1\nVx\np2\naI3\natp3\na."),
+            Some(Pickled::List(vec![Pickled::List(vec![
+                Pickled::Int(1),
+                Pickled::List(vec![s("x"), Pickled::Int(3)])
+            ])]))
+        );
+        assert_eq!(unpickle(b"Vcaf\xe9 \\u20ac\\u005c\np0\n."), Some(s("caf\u{e9} \u{20ac}\\")));
+        assert_eq!(unpickle(b"S'a\\'b\\x41'\np0\n."), Some(s("a'bA")));
+        assert_eq!(
+            unpickle(b"(dp0\nVk\np1\n(lp2\nNaI01\naF1.5\nasg1\ng2\ns."),
+            Some(Pickled::Dict(vec![
+                (
+                    s("k"),
+                    Pickled::List(vec![Pickled::None, Pickled::Bool(true), Pickled::Float(1.5)])
+                ),
+                (s("k"), Pickled::List(vec![])),
+            ]))
+        );
+        assert_eq!(unpickle(b"cnumpy\ndtype\n."), None);
+        assert_eq!(unpickle(b"(lp0\nVA\n"), None);
+    }
+
+    #[test]
+    pub fn test_read_fixed_frame() {
+        with_tmp_file(|file| {
+            let group = file.create_group("df").unwrap();
+            set_str(&group, "pandas_type", "frame");
+            set_str(&group, "axis0_variety", "regular");
+            set_str(&group, "axis1_variety", "regular");
+            group.set_attr("nblocks", &2_i64).unwrap();
+            write_labels(&group, "axis0", &["a", "b", "c"]);
+            let index = group.new_dataset::<i64>().create("axis1", 3).unwrap();
+            index.write(&[10, 20, 30]).unwrap();
+            set_str(&index, "kind", "integer");
+
+            write_labels(&group, "block0_items", &["c", "a"]);
+            let values = ndarray::arr2(&[[0.5, 1.], [1.5, 2.], [2.5, 3.]]);
+            let block = group.new_dataset::<f64>().create("block0_values", (3, 2)).unwrap();
+            block.write(&values).unwrap();
+            block.set_attr("transposed", &true).unwrap();
+            write_labels(&group, "block1_items", &["b"]);
+            let block = group.new_dataset::<i64>().create("block1_values", (1, 3)).unwrap();
+            block.write(&ndarray::arr2(&[[1_i64, 2, 3]])).unwrap();
+            set_str(&block, "value_type", "datetime64");
+
+            let frame = read_frame(&file, "df").unwrap();
+            assert_eq!(frame.len(), 3);
+            assert_eq!(frame.index(), &Column::Int(vec![10, 20, 30]));
+            assert_eq!(frame.column_names(), vec!["a", "b", "c"]);
+            assert_eq!(frame.column("a").unwrap().as_float(), Some(&[1., 2., 3.][..]));
+            assert_eq!(frame.column("b").unwrap().as_datetime(), Some(&[1, 2, 3][..]));
+            assert_eq!(frame.column("c").unwrap(), &Column::Float(vec![0.5, 1.5, 2.5]));
+            assert!(frame.column("d").is_none());
+
+            drop(block);
+            group.unlink("block1_values").unwrap();
+            group.new_dataset::<VarLenArray<u8>>().create("block1_values", 1).unwrap();
+            assert_err!(read_frame(&file, "df"), "unable to read pandas frame 'df': object");
+            group.unlink("block1_values").unwrap();
+            group.new_dataset::<i64>().create("block1_values", (3, 3)).unwrap();
+            assert_err!(read_frame(&file, "df"), "has shape [3, 3], expected [1, 3]");
+            set_str(&group, "axis1_variety", "multi");
+            assert_err!(read_frame(&file, "df"), "multi-indexes are not supported");
+            set_str(&group, "pandas_type", "series");
+            assert_err!(read_frame(&file, "df"), "unsupported pandas_type: \"series\"");
+            assert_err!(read_frame(&file, "/"), "not a pandas object");
+        })
+    }
+}
@@ -0,0 +1,329 @@
+//! Parallel decoding of chunked datasets (requires the `par-chunks` feature).
+//!
+//! `Dataset::par_read_chunks()` reads a chunked dataset by fetching its raw, still
+//! compressed chunks with direct chunk reads, which is cheap compared to decoding them,
+//! and decoding them on a pool of worker threads:
+//!
+//! ```ignore
+//! let images = file.dataset("images")?.par_read_chunks::<u16>()?;
+//! ```
+//!
+//! Only fetching the raw chunks takes the library lock. The workers don't call into the
+//! library at all, so no handles are shared with them and this works the same way with
+//! non-threadsafe builds of the library.
+//!
+//! Chunks are decoded in Rust, which is supported for the deflate (gzip) and shuffle
+//! filters, and for types stored exactly as they are in memory (no conversion and no
+//! variable-length data). Other datasets, and datasets with chunks that haven't been
+//! allocated yet, are read normally instead.
+
+use std::mem;
+use std::ptr;
+use std::sync::mpsc::{self, TrySendError};
+use std::sync::Arc;
+use std::thread;
+
+use ndarray::ArrayD;
+use parking_lot::Mutex;
+
+use hdf5_types::TypeDescriptor as TD;
+
+use crate::filters::{dcpl_pipeline, Filter};
+use crate::internal_prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Decoder {
+    Deflate,
+    Shuffle(usize),
+}
+
+/// Returns the decoders of the filter pipeline of a dataset, in the order the filters
+/// are applied when writing, or `None` if any of the filters is not supported.
+fn pipeline(dataset: &Dataset) -> Result<Option<Vec<Decoder>>> {
+    let dcpl = dataset.dcpl()?;
+    let mut decoders = Vec::new();
+    for (filter, _) in dcpl_pipeline(&dcpl)? {
+        decoders.push(match filter {
+            Filter::Deflate(_) => Decoder::Deflate,
+            // the library sets the element size to the size of the dataset type
+            Filter::Shuffle => Decoder::Shuffle(dataset.dtype()?.size()),
+            _ => return Ok(None),
+        });
+    }
+    Ok(Some(decoders))
+}
+
+/// Returns `true` if values of the type are stored as plain bytes, without any pointers.
+fn is_plain(desc: &TD) -> bool {
+    match desc {
+        TD::Integer(_)
+        | TD::Unsigned(_)
+        | TD::Float(_)
+        | TD::Boolean
+        | TD::Enum(_)
+        | TD::FixedAscii(_)
+        | TD::FixedUnicode(_)
+        | TD::Opaque(_)
+        | TD::Bitfield(_) => true,
+        TD::Compound(compound) => compound.fields.iter().all(|field| is_plain(&field.ty)),
+        TD::FixedArray(ty, _) => is_plain(ty),
+        _ => false,
+    }
+}
+
+/// Reverses the shuffle filter, which stores the bytes of equal significance of all the
+/// elements together.
+fn unshuffle(data: &[u8], elem_size: usize) -> Vec<u8> {
+    let n = if elem_size > 1 { data.len() / elem_size } else { 0 };
+    let mut out = vec![0; data.len()];
+    for j in 0..elem_size.min(data.len()) {
+        for i in 0..n {
+            out[i * elem_size + j] = data[j * n + i];
+        }
+    }
+    // trailing bytes that don't form a whole element are stored as is
+    out[n * elem_size..].copy_from_slice(&data[n * elem_size..]);
+    out
+}
+
+/// Decodes a raw chunk of `size` bytes; `filter_mask` has bits set for the filters that
+/// were skipped when the chunk was written.
+fn decode(
+    mut data: Vec<u8>, decoders: &[Decoder], filter_mask: u32, size: usize,
+) -> Result<Vec<u8>> {
+    for (i, decoder) in decoders.iter().enumerate().rev() {
+        if filter_mask & (1 << i) != 0 {
+            continue;
+        }
+        data = match *decoder {
+            Decoder::Deflate => miniz_oxide::inflate::decompress_to_vec_zlib(&data)
+                .map_err(|err| format!("unable to decompress chunk: {:?}", err))?,
+            Decoder::Shuffle(elem_size) => unshuffle(&data, elem_size),
+        };
+    }
+    ensure!(data.len() == size, "decoded chunk has {} bytes, expected {}", data.len(), size);
+    Ok(data)
+}
+
+/// Copies a decoded chunk starting at `start` into the buffer of the whole dataset,
+/// clipping it at the edges of the dataset.
+unsafe fn copy_chunk(
+    chunk: &[u8], start: &[Ix], chunk_dims: &[Ix], shape: &[Ix], elem_size: usize, out: *mut u8,
+) {
+    let ndim = shape.len();
+    let extent: Vec<Ix> = (0..ndim).map(|i| chunk_dims[i].min(shape[i] - start[i])).collect();
+    let row_size = extent[ndim - 1] * elem_size;
+    // position of the current row within the chunk
+    let mut index = vec![0; ndim - 1];
+    loop {
+        let (mut src, mut dst) = (0, 0);
+        for i in 0..ndim - 1 {
+            src = src * chunk_dims[i] + index[i];
+            dst = dst * shape[i] + start[i] + index[i];
+        }
+        src *= chunk_dims[ndim - 1];
+        dst = dst * shape[ndim - 1] + start[ndim - 1];
+        ptr::copy_nonoverlapping(
+            chunk.as_ptr().add(src * elem_size),
+            out.add(dst * elem_size),
+            row_size,
+        );
+        let mut i = ndim - 1;
+        loop {
+            if i == 0 {
+                return;
+            }
+            i -= 1;
+            index[i] += 1;
+            if index[i] < extent[i] {
+                break;
+            }
+            index[i] = 0;
+        }
+    }
+}
+
+/// Returns the number of worker threads used by `Dataset::par_read_chunks()`: the number
+/// of online processors where it can be determined, or 1 otherwise.
+pub fn default_threads() -> usize {
+    #[cfg(unix)]
+    {
+        let n = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+        if n > 0 {
+            return n as _;
+        }
+    }
+    1
+}
+
+type Job = (usize, Vec<u8>, u32);
+
+impl Dataset {
+    /// Reads the dataset, decoding its chunks in parallel (see the module documentation)
+    /// on `default_threads()` worker threads.
+    pub fn par_read_chunks<T: H5Type>(&self) -> Result<ArrayD<T>> {
+        self.par_read_chunks_with(default_threads())
+    }
+
+    /// Reads the dataset, decoding its chunks in parallel on the given number of worker
+    /// threads, see `par_read_chunks()`.
+    pub fn par_read_chunks_with<T: H5Type>(&self, threads: usize) -> Result<ArrayD<T>> {
+        let shape = self.shape();
+        let chunk_dims = match self.chunks() {
+            Some(chunk_dims) if !shape.contains(&0) => chunk_dims,
+            _ => return self.read_dyn(),
+        };
+        let desc = T::type_descriptor();
+        if !is_plain(&desc) || self.as_reader().will_convert::<T>()? {
+            return self.read_dyn();
+        }
+        let decoders = match pipeline(self)? {
+            Some(decoders) => decoders,
+            None => return self.read_dyn(),
+        };
+        let grid: Vec<Ix> = shape.iter().zip(&chunk_dims).map(|(&s, &c)| (s + c - 1) / c).collect();
+        let n_chunks = grid.iter().product::<Ix>();
+        if self.num_chunks() != Some(n_chunks) {
+            // unallocated chunks are read as the fill value by the library
+            return self.read_dyn();
+        }
+
+        let elem_size = mem::size_of::<T>();
+        let chunk_size = buffer_size(elem_size, chunk_dims.iter().product())?;
+        let size = shape.iter().product::<Ix>();
+        let mut out: Vec<T> = Vec::with_capacity(size);
+        let out_ptr = out.as_mut_ptr() as *mut u8;
+        unsafe { ptr::write_bytes(out_ptr, 0, buffer_size(elem_size, size)?) };
+        let offsets: Vec<Vec<Ix>> = (0..n_chunks)
+            .map(|mut index| {
+                let mut offset = vec![0; grid.len()];
+                for i in (0..grid.len()).rev() {
+                    offset[i] = (index % grid[i]) * chunk_dims[i];
+                    index /= grid[i];
+                }
+                offset
+            })
+            .collect();
+
+        let threads = threads.max(1);
+        let (job_tx, job_rx) = mpsc::sync_channel::<Job>(2 * threads);
+        let (result_tx, result_rx) = mpsc::channel::<(usize, Result<Vec<u8>>)>();
+        let (job_rx, decoders) = (Arc::new(Mutex::new(job_rx)), Arc::new(decoders));
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                let (job_rx, result_tx) = (Arc::clone(&job_rx), result_tx.clone());
+                let decoders = Arc::clone(&decoders);
+                thread::spawn(move || loop {
+                    let job = job_rx.lock().recv();
+                    match job {
+                        Ok((index, raw, mask)) => {
+                            let decoded = decode(raw, &decoders, mask, chunk_size);
+                            if result_tx.send((index, decoded)).is_err() {
+                                break;
+                            }
+                        }
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+        drop(result_tx);
+
+        let mut copied = 0;
+        let mut copy = |(index, decoded): (usize, Result<Vec<u8>>)| -> Result<()> {
+            let offset: &[Ix] = &offsets[index];
+            let decoded = decoded.map_err(|err| format!("chunk at {:?}: {}", offset, err))?;
+            unsafe { copy_chunk(&decoded, offset, &chunk_dims, &shape, elem_size, out_ptr) };
+            copied += 1;
+            Ok(())
+        };
+        for (index, offset) in offsets.iter().enumerate() {
+            let (raw, mask) = self.read_raw_chunk(offset)?;
+            let mut job = (index, raw, mask);
+            loop {
+                match job_tx.try_send(job) {
+                    Ok(()) => break,
+                    Err(TrySendError::Full(full)) => {
+                        job = full;
+                        copy(result_rx.recv().map_err(|_| "chunk decoding thread panicked")?)?;
+                    }
+                    Err(TrySendError::Disconnected(_)) => fail!("chunk decoding thread panicked"),
+                }
+            }
+            while let Ok(result) = result_rx.try_recv() {
+                copy(result)?;
+            }
+        }
+        drop(job_tx);
+        for result in result_rx.iter() {
+            copy(result)?;
+        }
+        ensure!(copied == n_chunks, "chunk decoding thread panicked");
+        for worker in workers {
+            worker.join().map_err(|_| "chunk decoding thread panicked")?;
+        }
+
+        unsafe {
+            out.set_len(size);
+            // skipped fields of compound types are not stored
+            if let TD::Compound(_) = desc {
+                for value in &mut out {
+                    T::fill_defaults(value, &[]);
+                }
+            }
+        }
+        Ok(ArrayD::from_shape_vec(shape, out)?)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use ndarray::{s, Array2, ArrayD};
+
+    use super::{pipeline, unshuffle, Decoder};
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_unshuffle() {
+        assert_eq!(unshuffle(&[1, 3, 5, 2, 4, 6, 7], 2), vec![1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(unshuffle(&[1, 2, 3], 4), vec![1, 2, 3]);
+        assert_eq!(unshuffle(&[1, 2, 3], 1), vec![1, 2, 3]);
+    }
+
+    #[test]
+    pub fn test_par_read_chunks() {
+        with_tmp_file(|file| {
+            let data = Array2::from_shape_fn((37, 23), |(i, j)| (i * 100 + j) as u16);
+            let mut builder = file.new_dataset::<u16>();
+            let ds = builder.chunk((8, 5)).gzip(4).shuffle(true).create("a", (37, 23)).unwrap();
+            ds.write(&data).unwrap();
+            let decoders = vec![Decoder::Shuffle(2), Decoder::Deflate];
+            assert_eq!(pipeline(&ds).unwrap(), Some(decoders));
+            assert_eq!(ds.num_chunks(), Some(25));
+            let read = ds.par_read_chunks_with::<u16>(3).unwrap();
+            assert_eq!(read, data.clone().into_dyn());
+            assert_eq!(ds.par_read_chunks::<u16>().unwrap(), data.clone().into_dyn());
+
+            let pairs = ArrayD::from_shape_fn(vec![30], |i| (i[0] as f64 / 2.0, i[0] as i32));
+            let ds = file.new_dataset::<(f64, i32)>().chunk(7).gzip(9).create("b", 30).unwrap();
+            ds.write(&pairs).unwrap();
+            assert!(!ds.as_reader().will_convert::<(f64, i32)>().unwrap());
+            assert_eq!(ds.par_read_chunks_with::<(f64, i32)>(2).unwrap(), pairs);
+
+            // these are read normally: a conversion, an unsupported filter, unallocated
+            // chunks and a contiguous dataset
+            let ds = file.dataset("a").unwrap();
+            assert_eq!(ds.par_read_chunks::<u32>().unwrap(), data.mapv(u32::from).into_dyn());
+            let mut builder = file.new_dataset::<u16>();
+            let ds = builder.chunk((8, 5)).fletcher32(true).create("c", (37, 23)).unwrap();
+            ds.write(&data).unwrap();
+            assert_eq!(ds.par_read_chunks::<u16>().unwrap(), data.clone().into_dyn());
+            let ds = file.new_dataset::<u16>().chunk(4).fill_value(7).create("d", 10).unwrap();
+            ds.write_slice(&[1, 2], s![0..2]).unwrap();
+            assert_eq!(ds.par_read_chunks::<u16>().unwrap().into_raw_vec()[..5], [1, 2, 7, 7, 7]);
+            let ds = file.new_dataset::<u16>().create("e", (37, 23)).unwrap();
+            ds.write(&data).unwrap();
+            assert_eq!(ds.par_read_chunks::<u16>().unwrap(), data.into_dyn());
+        })
+    }
+}
@@ -0,0 +1,416 @@
+//! Copying whole files while rewriting dataset storage settings, similar to `h5repack`.
+//!
+//! `repack()` recreates the structure of the source file in a new file: groups,
+//! attributes, named datatypes, datasets, soft links and external links. Datasets are
+//! rewritten with the chunking and filters given by `RepackOptions`; settings can be
+//! overridden per path, where a rule for a group path applies to all datasets below it and
+//! more specific paths take precedence. Datasets without any applicable setting are copied
+//! as they are. Objects with several hard links are copied once, using the rule for the
+//! path visited first (members are visited in alphabetical order), and linked again.
+
+use std::cmp;
+use std::path::Path;
+
+use hdf5_sys::h5d::{H5D_layout_t, H5Dcreate2, H5Dread, H5Dvlen_reclaim, H5Dwrite};
+use hdf5_sys::h5l::H5Lcreate_external;
+use hdf5_sys::h5p::{H5Premove_filter, H5Pset_chunk, H5Pset_layout};
+use hdf5_sys::h5t::H5Tcopy;
+use hdf5_sys::h5z::H5Z_FILTER_ALL;
+
+use crate::describe::Member;
+use crate::hl::dataset::{infer_chunk_size, Chunk};
+use crate::hl::plist::dataset_create::Layout;
+use crate::hl::plist::file_access::LibraryVersion;
+use crate::hl::plist::link_create::LinkCreate;
+use crate::internal_prelude::*;
+use crate::stats::object_key;
+
+/// Approximate number of bytes of dataset elements transferred at once.
+const COPY_BUFFER_SIZE: usize = 16 << 20;
+
+/// Storage settings applied to datasets by `repack()`; unset settings are inherited from
+/// less specific rules or, failing that, from the source dataset.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RepackRule {
+    chunk: Option<Chunk>,
+    filters: Option<Filters>,
+}
+
+impl RepackRule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the chunk shape, which must have the rank of the datasets it applies to.
+    pub fn chunk<D: Dimension>(&mut self, chunk: D) -> &mut Self {
+        self.chunk = Some(Chunk::Manual(chunk.dims()));
+        self
+    }
+
+    /// Chooses the chunk shape automatically based on the dataset shape.
+    pub fn chunk_auto(&mut self) -> &mut Self {
+        self.chunk = Some(Chunk::Auto);
+        self
+    }
+
+    /// Stores the datasets contiguously; they must not be filtered or resizable.
+    pub fn no_chunk(&mut self) -> &mut Self {
+        self.chunk = Some(Chunk::None);
+        self
+    }
+
+    /// Replaces the filter pipeline; `Filters::default()` removes all filters.
+    pub fn filters(&mut self, filters: &Filters) -> &mut Self {
+        self.filters = Some(filters.clone());
+        self
+    }
+
+    fn merge(&mut self, other: &Self) {
+        if other.chunk.is_some() {
+            self.chunk = other.chunk.clone();
+        }
+        if other.filters.is_some() {
+            self.filters = other.filters.clone();
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chunk.is_none() && self.filters.is_none()
+    }
+}
+
+/// Options for `repack()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RepackOptions {
+    rules: Vec<(String, RepackRule)>,
+    libver_bounds: Option<(LibraryVersion, LibraryVersion)>,
+}
+
+impl RepackOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the rule applied to all datasets, equivalent to `path("/", rule)`.
+    pub fn all(&mut self, rule: &RepackRule) -> &mut Self {
+        self.path("/", rule)
+    }
+
+    /// Sets the rule applied to the dataset at the given path, or to all datasets below
+    /// the group at the given path.
+    pub fn path(&mut self, path: &str, rule: &RepackRule) -> &mut Self {
+        let path = format!("/{}", path.trim_matches('/'));
+        self.rules.retain(|(p, _)| *p != path);
+        self.rules.push((path, rule.clone()));
+        self
+    }
+
+    /// Sets the library version bounds of the new file, which determine the object
+    /// format versions it is written with.
+    pub fn libver_bounds(&mut self, low: LibraryVersion, high: LibraryVersion) -> &mut Self {
+        self.libver_bounds = Some((low, high));
+        self
+    }
+
+    fn rule_for(&self, path: &str) -> RepackRule {
+        let mut rules: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|(prefix, _)| {
+                prefix == "/"
+                    || path == prefix
+                    || (path.starts_with(prefix.as_str()) && path[prefix.len()..].starts_with('/'))
+            })
+            .collect();
+        rules.sort_by_key(|(prefix, _)| prefix.len());
+        let mut rule = RepackRule::new();
+        for (_, other) in rules {
+            rule.merge(other);
+        }
+        rule
+    }
+}
+
+/// Copies the file at `src` into a new file at `dst`, which is truncated if it exists,
+/// rewriting datasets according to `options`; see the module documentation.
+pub fn repack<P: AsRef<Path>, Q: AsRef<Path>>(
+    src: P, dst: Q, options: &RepackOptions,
+) -> Result<()> {
+    let src = File::open(src)?;
+    let mut builder = File::with_options();
+    builder.set_fcpl(&src.fcpl()?)?;
+    if let Some((low, high)) = options.libver_bounds {
+        builder.with_fapl(|fapl| fapl.libver_bounds(low, high));
+    }
+    let dst = builder.create(dst)?;
+    let mut repacker = Repacker { options, copied: Vec::new() };
+    repacker.group(&src, &dst, "")?;
+    dst.flush()
+}
+
+struct Repacker<'a> {
+    options: &'a RepackOptions,
+    /// Locations of the source objects copied so far and their paths in the new file.
+    copied: Vec<((std::os::raw::c_ulong, haddr_t), String)>,
+}
+
+impl Repacker<'_> {
+    /// Links an object that has already been copied, returning `false` if it hasn't.
+    fn link_copied(&mut self, object: &Object, dst: &Group, name: &str) -> Result<bool> {
+        let key = match object_key(object.id()) {
+            Some(key) => key,
+            None => return Ok(false),
+        };
+        if let Some((_, path)) = self.copied.iter().find(|(k, _)| *k == key) {
+            dst.link_hard(path, name).and(Ok(true))
+        } else {
+            self.copied.push((key, format!("{}/{}", dst.name().trim_end_matches('/'), name)));
+            Ok(false)
+        }
+    }
+
+    fn group(&mut self, src: &Group, dst: &Group, path: &str) -> Result<()> {
+        src.copy_attrs_to(dst)?;
+        for name in src.member_names()? {
+            let path = format!("{}/{}", path, name);
+            match Member::open(src, &name)? {
+                Member::Group(group) => {
+                    if !self.link_copied(&group, dst, &name)? {
+                        let lcpl = LinkCreate::try_new()?;
+                        let new_group = dst.create_group_with(&name, &lcpl, &group.gcpl()?)?;
+                        self.group(&group, &new_group, &path)?;
+                    }
+                }
+                Member::Dataset(dataset) => {
+                    if !self.link_copied(&dataset, dst, &name)? {
+                        let rule = self.options.rule_for(&path);
+                        if rule.is_empty() || dataset.ndim() == 0 {
+                            src.copy_object(&name, dst, &name)?;
+                        } else {
+                            let new_dataset = rewrite_dataset(&dataset, dst, &name, &rule)
+                                .map_err(|err| format!("unable to repack {}: {}", path, err))?;
+                            dataset.copy_attrs_to(&new_dataset)?;
+                        }
+                    }
+                }
+                Member::Datatype(datatype) => {
+                    if !self.link_copied(&datatype, dst, &name)? {
+                        src.copy_object(&name, dst, &name)?;
+                    }
+                }
+                Member::SoftLink(target) => dst.link_soft(&target, &name)?,
+                Member::ExternalLink { file, path } => {
+                    let (file, path) = (to_cstring(file)?, to_cstring(path)?);
+                    let name = to_cstring(name.as_str())?;
+                    h5try!(H5Lcreate_external(
+                        file.as_ptr(),
+                        path.as_ptr(),
+                        dst.id(),
+                        name.as_ptr(),
+                        H5P_DEFAULT,
+                        H5P_DEFAULT
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn rewrite_dataset(src: &Dataset, dst: &Group, name: &str, rule: &RepackRule) -> Result<Dataset> {
+    let space = src.space()?;
+    let shape = space.dims();
+    let resizable = space.extents().is_resizable();
+    let dcpl = src.dcpl()?.copy();
+    let datatype = h5lock!(Datatype::from_id(h5try!(H5Tcopy(src.dtype()?.id()))))?;
+
+    let filtered = match rule.filters {
+        Some(ref filters) => {
+            h5try!(H5Premove_filter(dcpl.id(), H5Z_FILTER_ALL as _));
+            filters.apply_to_dcpl(&dcpl, &datatype)?;
+            filters.has_filters()
+        }
+        None => dcpl.filters().has_filters(),
+    };
+
+    let layout = dcpl.layout();
+    let inferred = || {
+        if shape.contains(&0) {
+            vec![1; shape.len()]
+        } else {
+            infer_chunk_size(&shape, datatype.size())
+        }
+    };
+    let chunk = match rule.chunk {
+        Some(Chunk::Manual(ref dims)) => Some(dims.clone()),
+        Some(Chunk::None) => None,
+        Some(_) => Some(inferred()),
+        None if layout == Layout::Chunked => dcpl.chunk(),
+        None if filtered || resizable => Some(inferred()),
+        None => None,
+    };
+
+    if let Some(dims) = chunk {
+        ensure!(
+            dims.len() == shape.len(),
+            "invalid chunk ndim: expected {}, got {}",
+            shape.len(),
+            dims.len()
+        );
+        ensure!(dims.iter().all(|&d| d > 0), "invalid chunk: {:?} (must be positive)", dims);
+        if !resizable {
+            ensure!(
+                dims.iter().zip(&shape).all(|(&c, &s)| c <= s.max(1)),
+                "invalid chunk: {:?} (must not exceed data shape in any dimension)",
+                dims
+            );
+        }
+        let c_dims: Vec<hsize_t> = dims.iter().map(|&d| d as _).collect();
+        h5try!(H5Pset_chunk(dcpl.id(), c_dims.len() as _, c_dims.as_ptr()));
+    } else if layout == Layout::Chunked || rule.chunk.is_some() {
+        ensure!(!filtered, "chunking must be enabled when filters are present");
+        ensure!(!resizable, "chunking must be enabled for resizable datasets");
+        h5try!(H5Pset_layout(dcpl.id(), H5D_layout_t::H5D_CONTIGUOUS));
+    }
+
+    let dataset = h5lock!({
+        let name = to_cstring(name)?;
+        Dataset::from_id(h5try!(H5Dcreate2(
+            dst.id(),
+            name.as_ptr(),
+            datatype.id(),
+            space.id(),
+            H5P_DEFAULT,
+            dcpl.id(),
+            H5P_DEFAULT
+        )))
+    })?;
+    copy_data(src, &dataset, &datatype, &shape)?;
+    Ok(dataset)
+}
+
+/// Copies all elements in blocks along the first axis, without converting them.
+fn copy_data(src: &Dataset, dst: &Dataset, datatype: &Datatype, shape: &[Ix]) -> Result<()> {
+    if shape.is_empty() || shape.contains(&0) {
+        return Ok(());
+    }
+    let row_size = datatype.size() * shape[1..].iter().product::<Ix>();
+    let rows = cmp::max(1, COPY_BUFFER_SIZE / cmp::max(1, row_size));
+    let mut buf: Vec<u8> = Vec::new();
+    for start in (0..shape[0]).step_by(rows) {
+        let count = cmp::min(rows, shape[0] - start);
+        let mut block = shape.to_vec();
+        block[0] = count;
+        let mut offset = vec![0; shape.len()];
+        offset[0] = start;
+        let file_space = src.space()?;
+        file_space.select_hyperslab(&offset, None, &[1].repeat(shape.len()), Some(&block))?;
+        let mem_space = Dataspace::try_new(&block, false)?;
+        buf.resize(buffer_size(row_size, count)?, 0);
+        let buf_ptr = buf.as_mut_ptr() as *mut c_void;
+        h5lock!({
+            let (tp, mem, file) = (datatype.id(), mem_space.id(), file_space.id());
+            h5try!(H5Dread(src.id(), tp, mem, file, H5P_DEFAULT, buf_ptr));
+            let written = h5check(H5Dwrite(dst.id(), tp, mem, file, H5P_DEFAULT, buf_ptr));
+            h5try!(H5Dvlen_reclaim(tp, mem, H5P_DEFAULT, buf_ptr));
+            written.map(drop)
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{repack, RepackOptions, RepackRule};
+    use crate::diff::{diff, DiffOptions};
+    use crate::filters::gzip_available;
+    use crate::hl::plist::dataset_create::Layout;
+    use crate::hl::plist::file_access::LibraryVersion;
+    use crate::internal_prelude::*;
+    use crate::stats::object_key;
+    use crate::types::VarLenUnicode;
+
+    fn populate(file: &File) {
+        file.set_attr_str("title", "repack").unwrap();
+        let data: Vec<u32> = (0..1000).collect();
+        let ds = file.new_dataset::<u32>().create("grp/a", 1000).unwrap();
+        ds.write(&data).unwrap();
+        ds.set_attr("scale", &2_u8).unwrap();
+        let ds = file.new_dataset::<f64>().chunk((5, 5)).create("grp/b", (10, 20)).unwrap();
+        ds.write(&ndarray::Array2::from_shape_fn((10, 20), |(i, j)| (i * j) as f64)).unwrap();
+        let strings: Vec<VarLenUnicode> = vec!["x".parse().unwrap(), "yz".parse().unwrap()];
+        file.new_dataset::<VarLenUnicode>().create("c", 2).unwrap().write(&strings).unwrap();
+        file.new_dataset::<u8>().create("scalar", ()).unwrap();
+        file.link_hard("grp/a", "a_again").unwrap();
+        file.link_soft("/grp/b", "b_link").unwrap();
+    }
+
+    #[test]
+    pub fn test_repack() {
+        with_tmp_dir(|dir| {
+            let (src_path, dst_path) = (dir.join("src.h5"), dir.join("dst.h5"));
+            populate(&File::create(&src_path).unwrap());
+
+            let mut filters = Filters::new();
+            filters.shuffle(true);
+            if gzip_available() {
+                filters.gzip(6);
+            }
+            let mut options = RepackOptions::new();
+            options
+                .all(RepackRule::new().filters(&filters))
+                .path("/grp/b/", RepackRule::new().chunk((2, 20)))
+                .path("/c", RepackRule::new().filters(&Filters::default()).no_chunk())
+                .libver_bounds(LibraryVersion::V18, LibraryVersion::latest());
+            repack(&src_path, &dst_path, &options).unwrap();
+
+            let (src, dst) = (File::open(&src_path).unwrap(), File::open(&dst_path).unwrap());
+            let report = diff(&src, &dst, DiffOptions::default()).unwrap();
+            assert!(report.is_identical(), "{}", report);
+
+            let a = dst.dataset("grp/a").unwrap();
+            assert_eq!(a.filters(), filters);
+            assert!(a.chunks().is_some());
+            let b = dst.dataset("grp/b").unwrap();
+            assert_eq!((b.chunks(), b.filters()), (Some(vec![2, 20]), filters));
+            let c = dst.dataset("c").unwrap();
+            assert_eq!(c.dcpl().unwrap().layout(), Layout::Contiguous);
+            assert!(!c.filters().has_filters());
+            let a_again = dst.dataset("a_again").unwrap();
+            assert_eq!(object_key(a_again.id()), object_key(a.id()));
+            assert_eq!(dst.dataset("b_link").unwrap().chunks(), Some(vec![2, 20]));
+            assert_eq!(dst.dataset("grp/a").unwrap().get_attr::<u8>("scale").unwrap(), 2);
+        })
+    }
+
+    #[test]
+    pub fn test_repack_plain_copy_and_errors() {
+        with_tmp_dir(|dir| {
+            let (src_path, dst_path) = (dir.join("src.h5"), dir.join("dst.h5"));
+            populate(&File::create(&src_path).unwrap());
+            repack(&src_path, &dst_path, &RepackOptions::new()).unwrap();
+            {
+                let (src, dst) = (File::open(&src_path).unwrap(), File::open(&dst_path).unwrap());
+                assert!(diff(&src, &dst, DiffOptions::default()).unwrap().is_identical());
+                assert_eq!(dst.dataset("grp/b").unwrap().chunks(), Some(vec![5, 5]));
+                let offset = dst.dataset("grp/a").unwrap().offset();
+                assert!(offset.is_some());
+                assert_eq!(dst.dataset("a_again").unwrap().offset(), offset);
+            }
+
+            let mut options = RepackOptions::new();
+            options.path("grp/b", RepackRule::new().chunk((2, 2, 2)));
+            assert_err!(repack(&src_path, &dst_path, &options), "unable to repack /grp/b");
+            options.path("grp/b", RepackRule::new().chunk((20, 20)));
+            assert_err!(repack(&src_path, &dst_path, &options), "must not exceed data shape");
+            let mut options = RepackOptions::new();
+            options
+                .all(RepackRule::new().filters(Filters::new().shuffle(true)))
+                .path("grp/b", RepackRule::new().no_chunk());
+            assert_err!(
+                repack(&src_path, &dst_path, &options),
+                "chunking must be enabled when filters are present"
+            );
+        })
+    }
+}
@@ -0,0 +1,595 @@
+//! Declarative validation of the structure of files.
+//!
+//! A `Schema` lists the groups and datasets a file is expected to contain, by path,
+//! along with constraints on their datatypes, shapes and attributes. `Schema::validate()`
+//! checks a file (or any group) against it and reports every violation found, so that
+//! malformed files can be rejected with precise messages:
+//!
+//! ```ignore
+//! let mut schema = Schema::new();
+//! schema.group("/raw").attr("instrument", AttrRule::new().dtype_class(TypeClass::String));
+//! schema
+//!     .dataset("/raw/counts")
+//!     .dtype::<u32>()
+//!     .shape(&[None, Some(1024)])
+//!     .attr("units", AttrRule::new().scalar());
+//! schema.dataset("/raw/mask").dtype::<bool>().optional(true);
+//!
+//! let report = schema.validate(&file)?;
+//! if !report.is_valid() {
+//!     eprintln!("rejecting malformed file:\n{}", report);
+//! }
+//! ```
+//!
+//! Paths are relative to the validated group. Parent groups of declared paths don't need
+//! to be declared themselves; a path whose parent is missing is reported as missing.
+//! Soft and external links are followed.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+use hdf5_types::TypeDescriptor as TD;
+
+use crate::describe::type_name;
+use crate::hl::datatype::TypeClass;
+use crate::internal_prelude::*;
+
+#[derive(Clone, Debug, PartialEq)]
+enum TypeRule {
+    Exact(TD),
+    Class(TypeClass),
+}
+
+fn class_name(class: TypeClass) -> String {
+    format!("{:?}", class).to_lowercase()
+}
+
+impl TypeRule {
+    fn expected(&self) -> String {
+        match self {
+            Self::Exact(ty) => type_name(ty),
+            Self::Class(class) => class_name(*class),
+        }
+    }
+
+    /// Returns the name of the datatype if it doesn't satisfy the rule.
+    fn mismatch(&self, dtype: &Datatype) -> Result<Option<String>> {
+        let descriptor = dtype.to_descriptor().ok();
+        let matches = match self {
+            Self::Exact(ty) => descriptor.as_ref() == Some(ty),
+            Self::Class(class) => dtype.class()? == *class,
+        };
+        Ok(if matches {
+            None
+        } else if let Some(descriptor) = descriptor {
+            Some(type_name(&descriptor))
+        } else {
+            Some(class_name(dtype.class()?))
+        })
+    }
+}
+
+/// Constraints shared by datasets and attributes.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Constraints {
+    dtype: Option<TypeRule>,
+    ndim: Option<usize>,
+    shape: Option<Vec<Option<Ix>>>,
+}
+
+impl Constraints {
+    fn check(&self, container: &Container, path: &str, violations: &mut Vec<Violation>) {
+        if let Err(err) = self.check_container(container, path, violations) {
+            let message = err.to_string();
+            violations.push(Violation::Error { path: path.into(), message });
+        }
+    }
+
+    fn check_container(
+        &self, container: &Container, path: &str, violations: &mut Vec<Violation>,
+    ) -> Result<()> {
+        if let Some(ref rule) = self.dtype {
+            if let Some(found) = rule.mismatch(&container.dtype()?)? {
+                let (path, expected) = (path.into(), rule.expected());
+                violations.push(Violation::Dtype { path, expected, found });
+            }
+        }
+        let shape = container.shape();
+        if let Some(ref expected) = self.shape {
+            let matches = expected.len() == shape.len()
+                && expected.iter().zip(&shape).all(|(e, &s)| e.map_or(true, |e| e == s));
+            if !matches {
+                let (path, expected) = (path.into(), expected.clone());
+                violations.push(Violation::Shape { path, expected, found: shape });
+            }
+        } else if let Some(expected) = self.ndim {
+            if expected != shape.len() {
+                let path = path.into();
+                violations.push(Violation::Ndim { path, expected, found: shape.len() });
+            }
+        }
+        Ok(())
+    }
+}
+
+macro_rules! impl_constraints {
+    ($ty:ty) => {
+        impl $ty {
+            /// Requires the datatype to be exactly the one of `T`.
+            pub fn dtype<T: H5Type>(&mut self) -> &mut Self {
+                self.dtype_descriptor(&T::type_descriptor())
+            }
+
+            /// Requires the datatype to be exactly the given one.
+            pub fn dtype_descriptor(&mut self, descriptor: &TD) -> &mut Self {
+                self.constraints.dtype = Some(TypeRule::Exact(descriptor.clone()));
+                self
+            }
+
+            /// Requires the datatype to be of the given class, e.g. any string or any
+            /// integer type.
+            pub fn dtype_class(&mut self, class: TypeClass) -> &mut Self {
+                self.constraints.dtype = Some(TypeRule::Class(class));
+                self
+            }
+
+            /// Requires the given number of dimensions.
+            pub fn ndim(&mut self, ndim: usize) -> &mut Self {
+                self.constraints.ndim = Some(ndim);
+                self
+            }
+
+            /// Requires the given shape, where `None` allows any size along an axis.
+            pub fn shape(&mut self, shape: &[Option<Ix>]) -> &mut Self {
+                self.constraints.shape = Some(shape.to_vec());
+                self
+            }
+
+            /// Requires a scalar (zero-dimensional) shape.
+            pub fn scalar(&mut self) -> &mut Self {
+                self.shape(&[])
+            }
+
+            /// Sets whether the object may be missing; the constraints only apply if it
+            /// exists.
+            pub fn optional(&mut self, optional: bool) -> &mut Self {
+                self.optional = optional;
+                self
+            }
+        }
+    };
+}
+
+/// Constraints on an attribute, see `GroupRule::attr()` and `DatasetRule::attr()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AttrRule {
+    constraints: Constraints,
+    optional: bool,
+}
+
+impl AttrRule {
+    /// Creates a rule requiring the attribute to exist, without further constraints.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl_constraints!(AttrRule);
+
+fn check_attrs(
+    location: &Location, attrs: &[(String, AttrRule)], path: &str, violations: &mut Vec<Violation>,
+) {
+    for (name, rule) in attrs {
+        let attr_path = format!("{}@{}", path, name);
+        if !location.attr_exists(name) {
+            if !rule.optional {
+                violations.push(Violation::Missing { path: attr_path, kind: "attribute" });
+            }
+            continue;
+        }
+        match location.attr(name) {
+            Ok(attr) => rule.constraints.check(&attr, &attr_path, violations),
+            Err(err) => {
+                let message = err.to_string();
+                violations.push(Violation::Error { path: attr_path, message });
+            }
+        }
+    }
+}
+
+/// Constraints on a group, see `Schema::group()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GroupRule {
+    attrs: Vec<(String, AttrRule)>,
+    optional: bool,
+    strict: bool,
+}
+
+impl GroupRule {
+    /// Requires an attribute satisfying the given rule.
+    pub fn attr(&mut self, name: &str, rule: &AttrRule) -> &mut Self {
+        self.attrs.retain(|(attr, _)| attr != name);
+        self.attrs.push((name.into(), rule.clone()));
+        self
+    }
+
+    /// Sets whether the group may be missing; the constraints only apply if it exists.
+    pub fn optional(&mut self, optional: bool) -> &mut Self {
+        self.optional = optional;
+        self
+    }
+
+    /// Sets whether members of the group that aren't declared in the schema are
+    /// reported as violations.
+    pub fn strict(&mut self, strict: bool) -> &mut Self {
+        self.strict = strict;
+        self
+    }
+}
+
+/// Constraints on a dataset, see `Schema::dataset()`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DatasetRule {
+    constraints: Constraints,
+    chunked: Option<bool>,
+    attrs: Vec<(String, AttrRule)>,
+    optional: bool,
+}
+
+impl DatasetRule {
+    /// Requires the dataset to be chunked, or not to be chunked.
+    pub fn chunked(&mut self, chunked: bool) -> &mut Self {
+        self.chunked = Some(chunked);
+        self
+    }
+
+    /// Requires an attribute satisfying the given rule.
+    pub fn attr(&mut self, name: &str, rule: &AttrRule) -> &mut Self {
+        self.attrs.retain(|(attr, _)| attr != name);
+        self.attrs.push((name.into(), rule.clone()));
+        self
+    }
+}
+
+impl_constraints!(DatasetRule);
+
+#[derive(Clone, Debug, PartialEq)]
+enum Rule {
+    Group(GroupRule),
+    Dataset(DatasetRule),
+}
+
+/// A single violation found by `Schema::validate()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Violation {
+    /// A required group, dataset or attribute doesn't exist.
+    Missing { path: String, kind: &'static str },
+    /// The object exists but is of a different kind, e.g. a group instead of a dataset.
+    WrongKind { path: String, expected: &'static str, found: &'static str },
+    /// The datatype doesn't match.
+    Dtype { path: String, expected: String, found: String },
+    /// The number of dimensions doesn't match.
+    Ndim { path: String, expected: usize, found: usize },
+    /// The shape doesn't match; `None` stands for any size along an axis.
+    Shape { path: String, expected: Vec<Option<Ix>>, found: Vec<Ix> },
+    /// The dataset is chunked when it shouldn't be, or the other way round.
+    Chunked { path: String, expected: bool },
+    /// A member of a strict group isn't declared in the schema.
+    Unexpected { path: String },
+    /// The object couldn't be read.
+    Error { path: String, message: String },
+}
+
+impl Violation {
+    /// Returns the path of the offending object; attributes are given as `path@name`.
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Missing { path, .. }
+            | Self::WrongKind { path, .. }
+            | Self::Dtype { path, .. }
+            | Self::Ndim { path, .. }
+            | Self::Shape { path, .. }
+            | Self::Chunked { path, .. }
+            | Self::Unexpected { path }
+            | Self::Error { path, .. } => path,
+        }
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Missing { path, kind } => write!(f, "{}: missing {}", path, kind),
+            Self::WrongKind { path, expected, found } => {
+                write!(f, "{}: expected {}, found {}", path, expected, found)
+            }
+            Self::Dtype { path, expected, found } => {
+                write!(f, "{}: expected dtype {}, found {}", path, expected, found)
+            }
+            Self::Ndim { path, expected, found } => {
+                write!(f, "{}: expected {} dimensions, found {}", path, expected, found)
+            }
+            Self::Shape { path, expected, found } => {
+                let expected: Vec<_> = expected
+                    .iter()
+                    .map(|e| e.map_or_else(|| "*".into(), |e| e.to_string()))
+                    .collect();
+                write!(f, "{}: expected shape [{}], found {:?}", path, expected.join(", "), found)
+            }
+            Self::Chunked { path, expected: true } => {
+                write!(f, "{}: expected a chunked dataset", path)
+            }
+            Self::Chunked { path, expected: false } => {
+                write!(f, "{}: expected a dataset that isn't chunked", path)
+            }
+            Self::Unexpected { path } => write!(f, "{}: not declared in schema", path),
+            Self::Error { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+/// Result of `Schema::validate()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Violations ordered by the path of the declaration they were found for.
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    /// Returns `true` if no violations were found.
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for violation in &self.violations {
+            writeln!(f, "{}", violation)?;
+        }
+        write!(f, "{} violations", self.violations.len())
+    }
+}
+
+/// The expected structure of a file, see the module documentation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Schema {
+    rules: BTreeMap<String, Rule>,
+}
+
+/// Normalises a path to the form `/a/b`, with `/` standing for the root.
+fn normalize(path: &str) -> String {
+    let components: Vec<_> = path.split('/').filter(|c| !c.is_empty()).collect();
+    format!("/{}", components.join("/"))
+}
+
+enum Object {
+    Group(Group),
+    Dataset(Dataset),
+    Other(&'static str),
+}
+
+/// Opens the object at a normalised path, following links; returns `None` if it (or one
+/// of its parent groups) doesn't exist.
+fn open(root: &Group, path: &str) -> Option<Object> {
+    let mut object = Object::Group(root.clone());
+    for name in path.split('/').filter(|c| !c.is_empty()) {
+        let parent = match object {
+            Object::Group(group) => group,
+            _ => return None,
+        };
+        if !parent.link_exists(name) {
+            return None;
+        }
+        let _e = silence_errors();
+        object = if let Ok(group) = parent.group(name) {
+            Object::Group(group)
+        } else if let Ok(dataset) = parent.dataset(name) {
+            Object::Dataset(dataset)
+        } else if parent.datatype(name).is_ok() {
+            Object::Other("named datatype")
+        } else {
+            Object::Other("dangling link")
+        };
+    }
+    Some(object)
+}
+
+impl Schema {
+    /// Creates an empty schema.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares a group at the given path, returning its rule for adding constraints.
+    ///
+    /// Declaring the same path again returns the existing rule (replacing it if it was
+    /// declared as a dataset).
+    pub fn group(&mut self, path: &str) -> &mut GroupRule {
+        let rule = self.rules.entry(normalize(path)).or_insert_with(|| Rule::Group(<_>::default()));
+        if let Rule::Dataset(_) = rule {
+            *rule = Rule::Group(GroupRule::default());
+        }
+        match rule {
+            Rule::Group(rule) => rule,
+            Rule::Dataset(_) => unreachable!(),
+        }
+    }
+
+    /// Declares a dataset at the given path, returning its rule for adding constraints.
+    ///
+    /// Declaring the same path again returns the existing rule (replacing it if it was
+    /// declared as a group).
+    pub fn dataset(&mut self, path: &str) -> &mut DatasetRule {
+        let rule =
+            self.rules.entry(normalize(path)).or_insert_with(|| Rule::Dataset(<_>::default()));
+        if let Rule::Group(_) = rule {
+            *rule = Rule::Dataset(DatasetRule::default());
+        }
+        match rule {
+            Rule::Dataset(rule) => rule,
+            Rule::Group(_) => unreachable!(),
+        }
+    }
+
+    /// Checks a file or group against the schema, see the module documentation.
+    ///
+    /// Problems reading individual objects are reported as `Violation::Error`.
+    pub fn validate(&self, root: &Group) -> Result<ValidationReport> {
+        let mut violations = Vec::new();
+        for (path, rule) in &self.rules {
+            let (expected, optional) = match rule {
+                Rule::Group(rule) => ("group", rule.optional),
+                Rule::Dataset(rule) => ("dataset", rule.optional),
+            };
+            let object = match open(root, path) {
+                Some(object) => object,
+                None if optional => continue,
+                None => {
+                    violations.push(Violation::Missing { path: path.clone(), kind: expected });
+                    continue;
+                }
+            };
+            match (rule, object) {
+                (Rule::Group(rule), Object::Group(group)) => {
+                    self.check_group(rule, &group, path, &mut violations)?;
+                }
+                (Rule::Dataset(rule), Object::Dataset(dataset)) => {
+                    rule.constraints.check(&dataset, path, &mut violations);
+                    if let Some(expected) = rule.chunked {
+                        if dataset.is_chunked() != expected {
+                            violations.push(Violation::Chunked { path: path.clone(), expected });
+                        }
+                    }
+                    check_attrs(&dataset, &rule.attrs, path, &mut violations);
+                }
+                (_, object) => {
+                    let found = match object {
+                        Object::Group(_) => "group",
+                        Object::Dataset(_) => "dataset",
+                        Object::Other(kind) => kind,
+                    };
+                    violations.push(Violation::WrongKind { path: path.clone(), expected, found });
+                }
+            }
+        }
+        Ok(ValidationReport { violations })
+    }
+
+    fn check_group(
+        &self, rule: &GroupRule, group: &Group, path: &str, violations: &mut Vec<Violation>,
+    ) -> Result<()> {
+        check_attrs(group, &rule.attrs, path, violations);
+        if rule.strict {
+            for name in group.member_names()? {
+                let member = normalize(&format!("{}/{}", path, name));
+                if !self.rules.contains_key(&member) {
+                    violations.push(Violation::Unexpected { path: member });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use hdf5_types::VarLenUnicode;
+
+    use super::{AttrRule, Schema, Violation};
+    use crate::hl::datatype::TypeClass;
+    use crate::internal_prelude::*;
+
+    fn make_schema() -> Schema {
+        let mut schema = Schema::new();
+        schema.group("/").attr("version", AttrRule::new().dtype_class(TypeClass::Integer));
+        schema.group("raw/").attr("instrument", AttrRule::new().dtype_class(TypeClass::String));
+        schema
+            .dataset("/raw/counts")
+            .dtype::<u32>()
+            .shape(&[None, Some(4)])
+            .chunked(true)
+            .attr("units", AttrRule::new().dtype::<VarLenUnicode>().scalar());
+        schema.dataset("/raw/mask").dtype::<bool>().ndim(2).optional(true);
+        schema
+    }
+
+    fn make_file(file: &File) {
+        file.set_attr("version", &2_u8).unwrap();
+        let raw = file.create_group("raw").unwrap();
+        raw.set_attr_str("instrument", "xyz").unwrap();
+        let counts = raw.new_dataset::<u32>().chunk((1, 4)).create("counts", (3, 4)).unwrap();
+        counts.set_attr_str("units", "counts").unwrap();
+    }
+
+    #[test]
+    pub fn test_schema_valid() {
+        with_tmp_file(|file| {
+            make_file(&file);
+            let schema = make_schema();
+            let report = schema.validate(&file).unwrap();
+            assert!(report.is_valid(), "{}", report);
+            assert_eq!(report.to_string(), "0 violations");
+
+            let mut strict = schema.clone();
+            strict.group("/raw").strict(true);
+            assert!(strict.validate(&file).unwrap().is_valid());
+            file.group("raw").unwrap().create_group("extra").unwrap();
+            assert_eq!(
+                strict.validate(&file).unwrap().violations,
+                vec![Violation::Unexpected { path: "/raw/extra".into() }]
+            );
+            assert!(schema.validate(&file).unwrap().is_valid());
+            assert!(!schema.validate(&file.group("raw").unwrap()).unwrap().is_valid());
+        })
+    }
+
+    #[test]
+    pub fn test_schema_violations() {
+        with_tmp_file(|file| {
+            file.set_attr("version", &1.5_f32).unwrap();
+            let raw = file.create_group("raw").unwrap();
+            let counts = raw.new_dataset::<i64>().create("counts", (3, 5)).unwrap();
+            counts.new_attr::<u8>().create("units", 2).unwrap();
+            raw.create_group("mask").unwrap();
+
+            let mut schema = make_schema();
+            schema.dataset("/calibration").ndim(1);
+            schema.dataset("/calibration/gain");
+            let report = schema.validate(&file).unwrap();
+            let messages: Vec<_> = report.violations.iter().map(ToString::to_string).collect();
+            assert_eq!(
+                messages,
+                vec![
+                    "/@version: expected dtype integer, found f32",
+                    "/calibration: missing dataset",
+                    "/calibration/gain: missing dataset",
+                    "/raw@instrument: missing attribute",
+                    "/raw/counts: expected dtype u32, found i64",
+                    "/raw/counts: expected shape [*, 4], found [3, 5]",
+                    "/raw/counts: expected a chunked dataset",
+                    "/raw/counts@units: expected dtype utf8, found u8",
+                    "/raw/counts@units: expected shape [], found [2]",
+                    "/raw/mask: expected dataset, found group",
+                ]
+            );
+            assert_eq!(report.violations[3].path(), "/raw@instrument");
+            assert!(report.to_string().ends_with("\n10 violations"));
+        })
+    }
+
+    #[test]
+    pub fn test_schema_declarations() {
+        let mut schema = Schema::new();
+        schema.dataset("a").ndim(1);
+        schema.dataset("/a/").ndim(2);
+        schema.group("a");
+        assert_eq!(schema.rules.len(), 1);
+        assert!(schema.rules.contains_key("/a"));
+        schema.dataset("//b//c").attr("x", &AttrRule::new()).attr("x", AttrRule::new().scalar());
+        match &schema.rules["/b/c"] {
+            super::Rule::Dataset(rule) => assert_eq!(rule.attrs.len(), 1),
+            super::Rule::Group(_) => panic!("expected a dataset rule"),
+        }
+    }
+}
@@ -0,0 +1,653 @@
+//! Storing serializable values in groups (requires the `serde` feature).
+//!
+//! `to_group()` stores the fields of a struct (or the entries of a map with string keys)
+//! as members of a group, and `from_group()` reads them back:
+//!
+//! - scalars (booleans, numbers, strings and unit enum variants) become scalar attributes;
+//! - sequences of scalars of the same type become one-dimensional datasets;
+//! - nested structs and maps become subgroups, and so do all other sequences, with their
+//!   elements stored under their indices;
+//! - `None` and unit values are not stored at all, so they are read back as missing
+//!   fields.
+//!
+//! Existing attributes and members with the same names are replaced; other contents of
+//! the group are left untouched, and so `from_group()` ignores any members that don't
+//! correspond to fields of the struct being read. Enum variants with data are not
+//! supported.
+
+use std::fmt::Display;
+
+use serde_crate::de::value::{MapDeserializer, SeqDeserializer};
+use serde_crate::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, Visitor,
+};
+use serde_crate::forward_to_deserialize_any;
+use serde_crate::ser::{self, Impossible, Serialize};
+
+use hdf5_types::{FloatSize, IntSize, TypeDescriptor, VarLenAscii, VarLenUnicode};
+
+use crate::hl::attribute::to_varlen_unicode;
+use crate::internal_prelude::*;
+
+impl ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::Internal(msg.to_string())
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::Internal(msg.to_string())
+    }
+}
+
+/// Stores a struct or a map with string keys in a group.
+pub fn to_group<T: Serialize + ?Sized>(value: &T, group: &Group) -> Result<()> {
+    match value.serialize(ValueSerializer)? {
+        Value::Map(entries) => write_map(group, &entries),
+        _ => fail!("expected a struct or a map"),
+    }
+}
+
+/// Reads a struct or a map stored in a group via `to_group()`.
+pub fn from_group<T: DeserializeOwned>(group: &Group) -> Result<T> {
+    T::deserialize(GroupDeserializer(group.clone()))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Value {
+    None,
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    F32(f32),
+    F64(f64),
+    Str(String),
+    Seq(Vec<Value>),
+    Map(Vec<(String, Value)>),
+}
+
+fn clear_member(group: &Group, name: &str) -> Result<()> {
+    if group.attr_exists(name) {
+        group.delete_attr(name)?;
+    }
+    if group.link_exists(name) {
+        group.unlink(name)?;
+    }
+    Ok(())
+}
+
+fn write_map(group: &Group, entries: &[(String, Value)]) -> Result<()> {
+    for (name, value) in entries {
+        write_value(group, name, value)?;
+    }
+    Ok(())
+}
+
+fn write_value(group: &Group, name: &str, value: &Value) -> Result<()> {
+    macro_rules! write_attr {
+        ($($variant:ident),*) => {
+            match value {
+                $(Value::$variant(v) => group.set_attr(name, v).map(drop),)*
+                Value::Str(v) => group.set_attr_str(name, v).map(drop),
+                Value::Seq(items) => write_seq(group, name, items),
+                Value::Map(entries) => write_map(&group.create_group(name)?, entries),
+                Value::None => Ok(()),
+            }
+        };
+    }
+    h5lock!({
+        clear_member(group, name)?;
+        write_attr!(Bool, I8, I16, I32, I64, U8, U16, U32, U64, F32, F64)
+    })
+}
+
+fn write_dataset<T: H5Type>(group: &Group, name: &str, data: &[T]) -> Result<()> {
+    group.new_dataset::<T>().create(name, data.len())?.write_raw(data)
+}
+
+fn write_seq(group: &Group, name: &str, items: &[Value]) -> Result<()> {
+    macro_rules! write_column {
+        ($($variant:ident => $ty:ty),*) => {
+            match items.first() {
+                None => return write_dataset::<u8>(group, name, &[]),
+                $(Some(Value::$variant(_)) => {
+                    let data: Option<Vec<$ty>> = items.iter().map(|v| match v {
+                        Value::$variant(v) => Some(*v),
+                        _ => None,
+                    }).collect();
+                    if let Some(data) = data {
+                        return write_dataset(group, name, &data);
+                    }
+                })*
+                _ => {}
+            }
+        };
+    }
+    write_column!(
+        Bool => bool, I8 => i8, I16 => i16, I32 => i32, I64 => i64, U8 => u8, U16 => u16,
+        U32 => u32, U64 => u64, F32 => f32, F64 => f64
+    );
+    if items.iter().all(|v| matches!(v, Value::Str(_))) {
+        let data = items
+            .iter()
+            .filter_map(|v| if let Value::Str(s) = v { Some(to_varlen_unicode(s)) } else { None })
+            .collect::<Result<Vec<_>>>()?;
+        return write_dataset(group, name, &data);
+    }
+    let group = group.create_group(name)?;
+    for (i, item) in items.iter().enumerate() {
+        write_value(&group, &i.to_string(), item)?;
+    }
+    Ok(())
+}
+
+struct ValueSerializer;
+
+struct SeqSerializer(Vec<Value>);
+
+struct MapSerializer {
+    entries: Vec<(String, Value)>,
+    key: Option<String>,
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = Error;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = Impossible<Value, Error>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = Impossible<Value, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Value> {
+        Ok(Value::I8(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Value> {
+        Ok(Value::I16(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Value> {
+        Ok(Value::I32(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Value> {
+        Ok(Value::I64(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Value> {
+        Ok(Value::U8(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Value> {
+        Ok(Value::U16(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Value> {
+        Ok(Value::U32(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Value> {
+        Ok(Value::U64(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Value> {
+        Ok(Value::F32(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Value> {
+        Ok(Value::F64(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Value> {
+        Ok(Value::Str(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Value> {
+        Ok(Value::Str(v.to_owned()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+        Ok(Value::Seq(v.iter().copied().map(Value::U8).collect()))
+    }
+
+    fn serialize_none(self) -> Result<Value> {
+        Ok(Value::None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Value> {
+        Ok(Value::None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+        Ok(Value::None)
+    }
+
+    fn serialize_unit_variant(
+        self, _name: &'static str, _index: u32, variant: &'static str,
+    ) -> Result<Value> {
+        Ok(Value::Str(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self, _name: &'static str, value: &T,
+    ) -> Result<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self, name: &'static str, _index: u32, variant: &'static str, _value: &T,
+    ) -> Result<Value> {
+        fail!("enum variants with data are not supported: {}::{}", name, variant)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SeqSerializer> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self, name: &'static str, _index: u32, variant: &'static str, _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        fail!("enum variants with data are not supported: {}::{}", name, variant)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer> {
+        Ok(MapSerializer { entries: Vec::with_capacity(len.unwrap_or(0)), key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<MapSerializer> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self, name: &'static str, _index: u32, variant: &'static str, _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        fail!("enum variants with data are not supported: {}::{}", name, variant)
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Seq(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        match key.serialize(ValueSerializer)? {
+            Value::Str(key) => self.key = Some(key),
+            _ => fail!("map keys must be strings"),
+        }
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let key = self.key.take().ok_or("map value serialized before its key")?;
+        self.entries.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Map(self.entries))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = Value;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self, key: &'static str, value: &T,
+    ) -> Result<()> {
+        self.entries.push((key.to_owned(), value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<Value> {
+        Ok(Value::Map(self.entries))
+    }
+}
+
+fn read_value(container: &Container) -> Result<Value> {
+    macro_rules! read {
+        ($ty:ty, $variant:ident) => {
+            container.read_raw::<$ty>()?.into_iter().map(Value::$variant).collect()
+        };
+    }
+    let values: Vec<Value> = match container.dtype()?.to_descriptor()? {
+        TypeDescriptor::Boolean => read!(bool, Bool),
+        TypeDescriptor::Integer(IntSize::U1) => read!(i8, I8),
+        TypeDescriptor::Integer(IntSize::U2) => read!(i16, I16),
+        TypeDescriptor::Integer(IntSize::U4) => read!(i32, I32),
+        TypeDescriptor::Integer(IntSize::U8) => read!(i64, I64),
+        TypeDescriptor::Unsigned(IntSize::U1) => read!(u8, U8),
+        TypeDescriptor::Unsigned(IntSize::U2) => read!(u16, U16),
+        TypeDescriptor::Unsigned(IntSize::U4) => read!(u32, U32),
+        TypeDescriptor::Unsigned(IntSize::U8) => read!(u64, U64),
+        TypeDescriptor::Float(FloatSize::U4) => read!(f32, F32),
+        TypeDescriptor::Float(FloatSize::U8) => read!(f64, F64),
+        TypeDescriptor::VarLenUnicode => container
+            .read_raw::<VarLenUnicode>()?
+            .into_iter()
+            .map(|s| Value::Str(s.as_str().to_owned()))
+            .collect(),
+        TypeDescriptor::VarLenAscii => container
+            .read_raw::<VarLenAscii>()?
+            .into_iter()
+            .map(|s| Value::Str(s.as_str().to_owned()))
+            .collect(),
+        descriptor => fail!("unsupported datatype: {:?}", descriptor),
+    };
+    if container.is_scalar() {
+        Ok(values.into_iter().next().unwrap_or(Value::None))
+    } else {
+        Ok(Value::Seq(values))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+impl<'de> Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Self::None => visitor.visit_none(),
+            Self::Bool(v) => visitor.visit_bool(v),
+            Self::I8(v) => visitor.visit_i8(v),
+            Self::I16(v) => visitor.visit_i16(v),
+            Self::I32(v) => visitor.visit_i32(v),
+            Self::I64(v) => visitor.visit_i64(v),
+            Self::U8(v) => visitor.visit_u8(v),
+            Self::U16(v) => visitor.visit_u16(v),
+            Self::U32(v) => visitor.visit_u32(v),
+            Self::U64(v) => visitor.visit_u64(v),
+            Self::F32(v) => visitor.visit_f32(v),
+            Self::F64(v) => visitor.visit_f64(v),
+            Self::Str(v) => visitor.visit_string(v),
+            Self::Seq(items) => {
+                let mut seq = SeqDeserializer::new(items.into_iter());
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Self::Map(entries) => {
+                let mut map = MapDeserializer::new(entries.into_iter());
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self {
+            Self::None => visitor.visit_none(),
+            value => visitor.visit_some(value),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self, _name: &'static str, visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self, name: &'static str, _variants: &'static [&'static str], visitor: V,
+    ) -> Result<V::Value> {
+        match self {
+            Self::Str(variant) => visitor.visit_enum(variant.into_deserializer()),
+            _ => fail!("expected a string for a variant of enum {}", name),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+struct GroupDeserializer(Group);
+
+struct GroupMap {
+    group: Group,
+    names: std::vec::IntoIter<String>,
+    name: Option<String>,
+}
+
+impl GroupDeserializer {
+    fn visit_names<'de, V: Visitor<'de>>(self, names: Vec<String>, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(GroupMap { group: self.0, names: names.into_iter(), name: None })
+    }
+}
+
+impl<'de> Deserializer<'de> for GroupDeserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut names = self.0.attr_names()?;
+        names.extend(self.0.member_names()?);
+        self.visit_names(names, visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self, _name: &'static str, fields: &'static [&'static str], visitor: V,
+    ) -> Result<V::Value> {
+        let names = fields
+            .iter()
+            .filter(|&&name| self.0.attr_exists(name) || self.0.link_exists(name))
+            .map(|&name| name.to_owned())
+            .collect();
+        self.visit_names(names, visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self, _name: &'static str, visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut names = self.0.attr_names()?;
+        names.extend(self.0.member_names()?);
+        let mut indices = names
+            .into_iter()
+            .map(|name| match name.parse::<usize>() {
+                Ok(index) => Ok((index, name)),
+                Err(_) => Err(Error::from(format!("expected a sequence, found member {:?}", name))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        indices.sort();
+        let group = self.0;
+        let members = indices.into_iter().map(|(_, name)| Member { group: group.clone(), name });
+        let mut seq = SeqDeserializer::new(members);
+        let value = visitor.visit_seq(&mut seq)?;
+        seq.end()?;
+        Ok(value)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self, _name: &'static str, _len: usize, visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct map enum identifier ignored_any
+    }
+}
+
+impl<'de> MapAccess<'de> for GroupMap {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.names.next() {
+            Some(name) => {
+                self.name = Some(name.clone());
+                seed.deserialize(name.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        let name = self.name.take().ok_or("map value requested before its key")?;
+        seed.deserialize(Member { group: self.group.clone(), name })
+    }
+}
+
+/// A named attribute or member of a group.
+struct Member {
+    group: Group,
+    name: String,
+}
+
+enum Node {
+    Value(Value),
+    Group(Group),
+}
+
+impl Member {
+    fn load(&self) -> Result<Node> {
+        h5lock!({
+            if let Some(attr) = self.group.attr_opt(&self.name)? {
+                return read_value(&*attr).map(Node::Value);
+            }
+            let group = {
+                let _e = silence_errors();
+                self.group.group(&self.name).ok()
+            };
+            match group {
+                Some(group) => Ok(Node::Group(group)),
+                None => read_value(&*self.group.dataset(&self.name)?).map(Node::Value),
+            }
+        })
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Member {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! forward_to_node {
+    ($($method:ident($($arg:ident: $ty:ty),*)),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value> {
+                match self.load()? {
+                    Node::Value(value) => value.$method($($arg,)* visitor),
+                    Node::Group(group) => GroupDeserializer(group).$method($($arg,)* visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Member {
+    type Error = Error;
+
+    forward_to_node! {
+        deserialize_any(),
+        deserialize_seq(),
+        deserialize_tuple(len: usize),
+        deserialize_tuple_struct(name: &'static str, len: usize),
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]),
+        deserialize_enum(name: &'static str, variants: &'static [&'static str])
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self, _name: &'static str, visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf
+        unit unit_struct map identifier ignored_any
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use ndarray::{IxDyn, SliceInfo, SliceOrIndex};
+
+use crate::internal_prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SliceItem {
+    Index(isize),
+    Slice { start: Option<isize>, end: Option<isize>, step: isize },
+    Ellipsis,
+}
+
+/// A slice expression written in NumPy syntax, e.g. `"0:100:2, ..., 5"`.
+///
+/// Each comma-separated component is either an index, a `start:end:step` slice (all
+/// parts optional; negative `start`/`end` count from the end of the axis) or an
+/// ellipsis `...` standing for as many full slices as needed; axes not covered by
+/// the expression are selected in full. Negative steps are not supported.
+///
+/// As with NumPy, the expression is only resolved against a shape once it's applied,
+/// see `resolve()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SliceExpr(Vec<SliceItem>);
+
+fn parse_int(s: &str, expr: &str) -> Result<Option<isize>> {
+    let s = s.trim();
+    if s.is_empty() {
+        Ok(None)
+    } else {
+        s.parse().map(Some).map_err(|_| {
+            format!("invalid slice expression {:?}: expected integer, got {:?}", expr, s).into()
+        })
+    }
+}
+
+impl FromStr for SliceExpr {
+    type Err = Error;
+
+    fn from_str(expr: &str) -> Result<Self> {
+        let mut parts: Vec<_> = expr.split(',').map(str::trim).collect();
+        if parts.len() > 1 && parts.last() == Some(&"") {
+            parts.pop();
+        }
+        if parts == [""] {
+            return Ok(Self::default());
+        }
+        let mut items = Vec::with_capacity(parts.len());
+        for part in parts {
+            let item = if part == "..." {
+                ensure!(
+                    !items.contains(&SliceItem::Ellipsis),
+                    "invalid slice expression {:?}: multiple ellipses",
+                    expr
+                );
+                SliceItem::Ellipsis
+            } else if part.contains(':') {
+                let bounds: Vec<_> = part.split(':').collect();
+                ensure!(
+                    bounds.len() <= 3,
+                    "invalid slice expression {:?}: too many colons in {:?}",
+                    expr,
+                    part
+                );
+                let step = match bounds.get(2) {
+                    Some(step) => parse_int(step, expr)?.unwrap_or(1),
+                    None => 1,
+                };
+                ensure!(step != 0, "invalid slice expression {:?}: step cannot be zero", expr);
+                ensure!(
+                    step > 0,
+                    "invalid slice expression {:?}: negative steps are not supported",
+                    expr
+                );
+                SliceItem::Slice {
+                    start: parse_int(bounds[0], expr)?,
+                    end: parse_int(bounds[1], expr)?,
+                    step,
+                }
+            } else {
+                match parse_int(part, expr)? {
+                    Some(index) => SliceItem::Index(index),
+                    None => fail!("invalid slice expression {:?}: empty component", expr),
+                }
+            };
+            items.push(item);
+        }
+        Ok(Self(items))
+    }
+}
+
+impl Display for SliceExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let opt = |x: Option<isize>| x.map_or_else(String::new, |x| x.to_string());
+        let items: Vec<_> = self
+            .0
+            .iter()
+            .map(|item| match *item {
+                SliceItem::Index(index) => index.to_string(),
+                SliceItem::Slice { start, end, step: 1 } => format!("{}:{}", opt(start), opt(end)),
+                SliceItem::Slice { start, end, step } => {
+                    format!("{}:{}:{}", opt(start), opt(end), step)
+                }
+                SliceItem::Ellipsis => "...".to_owned(),
+            })
+            .collect();
+        write!(f, "{}", items.join(", "))
+    }
+}
+
+impl SliceExpr {
+    /// Resolves the expression against `shape`, producing a slice that may be passed to
+    /// e.g. `Container::read_slice()` or `Dataspace::select_slice()`.
+    ///
+    /// Slice bounds are clamped to the axis sizes as in NumPy, but indices out of
+    /// bounds are an error.
+    pub fn resolve(&self, shape: &[Ix]) -> Result<SliceInfo<Vec<SliceOrIndex>, IxDyn>> {
+        let explicit = self.0.iter().filter(|&&item| item != SliceItem::Ellipsis).count();
+        ensure!(
+            explicit <= shape.len(),
+            "too many indices in slice expression: {} for {} dims",
+            explicit,
+            shape.len()
+        );
+        let full = SliceItem::Slice { start: None, end: None, step: 1 };
+        let mut items = Vec::with_capacity(shape.len());
+        for &item in &self.0 {
+            if item == SliceItem::Ellipsis {
+                items.extend((explicit..shape.len()).map(|_| full));
+            } else {
+                items.push(item);
+            }
+        }
+        items.resize(shape.len(), full);
+
+        let mut indices = Vec::with_capacity(shape.len());
+        for (axis, (item, &len)) in items.into_iter().zip(shape).enumerate() {
+            let len = len as isize;
+            let clamp = |x: isize| if x < 0 { (x + len).max(0) } else { x.min(len) };
+            indices.push(match item {
+                SliceItem::Index(index) => {
+                    let resolved = if index < 0 { index + len } else { index };
+                    ensure!(
+                        resolved >= 0 && resolved < len,
+                        "index {} is out of bounds for axis {} with size {}",
+                        index,
+                        axis,
+                        len
+                    );
+                    SliceOrIndex::Index(resolved)
+                }
+                SliceItem::Slice { start, end, step } => SliceOrIndex::Slice {
+                    start: start.map_or(0, clamp),
+                    end: Some(end.map_or(len, clamp)),
+                    step,
+                },
+                SliceItem::Ellipsis => unreachable!(),
+            });
+        }
+        Ok(SliceInfo::new(indices)?)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use ndarray::SliceOrIndex;
+
+    use super::SliceExpr;
+    use crate::internal_prelude::*;
+
+    fn resolve(expr: &str, shape: &[Ix]) -> Result<Vec<SliceOrIndex>> {
+        let slice = expr.parse::<SliceExpr>()?.resolve(shape)?;
+        let indices: &[SliceOrIndex] = slice.as_ref();
+        Ok(indices.to_vec())
+    }
+
+    fn slice(start: isize, end: isize, step: isize) -> SliceOrIndex {
+        SliceOrIndex::Slice { start, end: Some(end), step }
+    }
+
+    #[test]
+    pub fn test_slice_expr() -> Result<()> {
+        use self::SliceOrIndex::Index;
+
+        assert_eq!(
+            resolve("0:100:2, ..., 5", &[200, 3, 4, 6])?,
+            vec![slice(0, 100, 2), slice(0, 3, 1), slice(0, 4, 1), Index(5)]
+        );
+        assert_eq!(resolve("", &[2, 3])?, vec![slice(0, 2, 1), slice(0, 3, 1)]);
+        assert_eq!(resolve("1", &[2, 3])?, vec![Index(1), slice(0, 3, 1)]);
+        assert_eq!(resolve("-1,", &[2])?, vec![Index(1)]);
+        assert_eq!(resolve(" :, ::3 ", &[2, 10])?, vec![slice(0, 2, 1), slice(0, 10, 3)]);
+        assert_eq!(resolve("-3:, :-1", &[5, 5])?, vec![slice(2, 5, 1), slice(0, 4, 1)]);
+        assert_eq!(resolve("2:100, -100:1", &[5, 5])?, vec![slice(2, 5, 1), slice(0, 1, 1)]);
+        assert_eq!(resolve("..., 0", &[4])?, vec![Index(0)]);
+        assert_eq!(resolve("...", &[])?, vec![]);
+
+        let expr: SliceExpr = "0:100:2, ..., 5, :-1, 3:".parse()?;
+        assert_eq!(expr.to_string(), "0:100:2, ..., 5, :-1, 3:");
+        assert_eq!(expr.to_string().parse::<SliceExpr>()?, expr);
+
+        assert_err!(resolve("1, 2, 3", &[2, 3]), "too many indices in slice expression: 3 for 2");
+        assert_err!(resolve("2", &[2]), "index 2 is out of bounds for axis 0 with size 2");
+        assert_err!(resolve("-3", &[2]), "index -3 is out of bounds for axis 0 with size 2");
+        assert_err!("..., ...".parse::<SliceExpr>(), "multiple ellipses");
+        assert_err!("::0".parse::<SliceExpr>(), "step cannot be zero");
+        assert_err!("::-1".parse::<SliceExpr>(), "negative steps are not supported");
+        assert_err!("1:2:3:4".parse::<SliceExpr>(), "too many colons in \"1:2:3:4\"");
+        assert_err!("a:b".parse::<SliceExpr>(), "expected integer, got \"a\"");
+        assert_err!("1,,2".parse::<SliceExpr>(), "empty component");
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+//! Opt-in I/O statistics for datasets.
+//!
+//! Once enabled via `enable()`, every read and write of a dataset made through this
+//! crate is recorded, and the accumulated counters can be retrieved with
+//! `Dataset::io_stats()`. Datasets are identified by their location in the file, so
+//! the statistics are shared by all handles to the same dataset, including ones
+//! opened later. Transfers are timed as a whole, including datatype conversion and
+//! filtering done by the library.
+//!
+//! Besides the logical reads and writes, which only count transfers that succeeded,
+//! the number of `H5Dread` and `H5Dwrite` calls issued is recorded separately, so
+//! calls that were rejected by the library show up too. Chunk cache hit rates are
+//! not recorded: HDF5 1.10 exposes statistics for the metadata cache only
+//! (`H5Fget_mdc_hit_rate`), and the raw data chunk cache has no public counters.
+
+use std::collections::HashMap;
+use std::os::raw::c_ulong;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use hdf5_sys::h5o::H5O_info_t;
+#[cfg(not(hdf5_1_10_3))]
+use hdf5_sys::h5o::H5Oget_info;
+#[cfg(hdf5_1_10_3)]
+use hdf5_sys::h5o::{H5Oget_info2, H5O_INFO_BASIC};
+use hdf5_sys::h5t::H5Tequal;
+
+use crate::internal_prelude::*;
+
+/// Accumulated I/O statistics of a dataset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IoStats {
+    /// Number of reads.
+    pub reads: u64,
+    /// Number of writes.
+    pub writes: u64,
+    /// Number of `H5Dread` calls, including failed ones.
+    pub h5d_reads: u64,
+    /// Number of `H5Dwrite` calls, including failed ones.
+    pub h5d_writes: u64,
+    /// Number of bytes read, in the memory representation.
+    pub bytes_read: u64,
+    /// Number of bytes written, in the memory representation.
+    pub bytes_written: u64,
+    /// Time spent in reads.
+    pub read_time: Duration,
+    /// Time spent in writes.
+    pub write_time: Duration,
+    /// Number of reads and writes that involved a datatype conversion.
+    pub conversions: u64,
+    /// Time spent in reads and writes that involved a datatype conversion.
+    pub conversion_time: Duration,
+}
+
+type Key = (c_ulong, haddr_t);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref STATS: Mutex<HashMap<Key, IoStats>> = Mutex::new(HashMap::new());
+}
+
+/// Starts recording I/O statistics.
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Stops recording I/O statistics; the statistics recorded so far are kept.
+pub fn disable() {
+    ENABLED.store(false, Ordering::SeqCst);
+}
+
+/// Returns `true` if I/O statistics are being recorded.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// Discards all recorded I/O statistics.
+pub fn reset() {
+    STATS.lock().clear();
+}
+
+pub(crate) fn object_key(obj_id: hid_t) -> Option<Key> {
+    let mut info = H5O_info_t::default();
+    h5lock!({
+        let _e = silence_errors();
+        #[cfg(not(hdf5_1_10_3))]
+        let ret = H5Oget_info(obj_id, &mut info);
+        #[cfg(hdf5_1_10_3)]
+        let ret = H5Oget_info2(obj_id, &mut info, H5O_INFO_BASIC);
+        if ret < 0 {
+            None
+        } else {
+            Some((info.fileno, info.addr))
+        }
+    })
+}
+
+pub(crate) fn record_call(obj: &Container, write: bool) {
+    if obj.is_attr() {
+        return;
+    }
+    if let Some(key) = object_key(obj.id()) {
+        let mut stats = STATS.lock();
+        let stats = stats.entry(key).or_default();
+        if write {
+            stats.h5d_writes += 1;
+        } else {
+            stats.h5d_reads += 1;
+        }
+    }
+}
+
+pub(crate) fn record(
+    obj: &Container, write: bool, tp_id: hid_t, mspace_id: hid_t, elapsed: Duration,
+) {
+    if obj.is_attr() {
+        return;
+    }
+    let key = match object_key(obj.id()) {
+        Some(key) => key,
+        None => return,
+    };
+    let bytes = obj.transfer_size(tp_id, mspace_id) as u64;
+    let converted = obj.dtype().map_or(false, |dtype| h5lock!(H5Tequal(dtype.id(), tp_id)) <= 0);
+    let mut stats = STATS.lock();
+    let stats = stats.entry(key).or_default();
+    if write {
+        stats.writes += 1;
+        stats.bytes_written += bytes;
+        stats.write_time += elapsed;
+    } else {
+        stats.reads += 1;
+        stats.bytes_read += bytes;
+        stats.read_time += elapsed;
+    }
+    if converted {
+        stats.conversions += 1;
+        stats.conversion_time += elapsed;
+    }
+}
+
+pub(crate) fn get(ds: &Dataset) -> IoStats {
+    object_key(ds.id()).and_then(|key| STATS.lock().get(&key).copied()).unwrap_or_default()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{disable, enable, reset, IoStats};
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_io_stats() {
+        with_tmp_file(|file| {
+            let ds = file.new_dataset::<i32>().create("x", 10).unwrap();
+            let attr = ds.new_attr::<i32>().create("a", 10).unwrap();
+            assert_eq!(ds.io_stats(), IoStats::default());
+
+            ds.write(&[1; 10]).unwrap();
+            assert_eq!(ds.io_stats(), IoStats::default());
+
+            enable();
+            ds.write(&[1; 10]).unwrap();
+            ds.read_raw::<i32>().unwrap();
+            ds.read_raw::<i64>().unwrap();
+            attr.write(&[1; 10]).unwrap();
+            let other = file.new_dataset::<u8>().create("y", 1).unwrap();
+            other.write(&[1_u8]).unwrap();
+            disable();
+            ds.read_raw::<i32>().unwrap();
+
+            let stats = file.dataset("x").unwrap().io_stats();
+            assert_eq!(stats.writes, 1);
+            assert_eq!(stats.reads, 2);
+            assert_eq!(stats.bytes_written, 40);
+            assert_eq!(stats.bytes_read, 120);
+            assert_eq!(stats.conversions, 1);
+            assert!(stats.conversion_time <= stats.read_time);
+            assert_eq!(other.io_stats().bytes_written, 1);
+
+            assert_eq!(stats.h5d_writes, 1);
+            assert_eq!(stats.h5d_reads, 2);
+
+            reset();
+            assert_eq!(ds.io_stats(), IoStats::default());
+        });
+
+        // kept in the same test since the statistics and the switch are global
+        with_tmp_path(|path| {
+            File::create(&path).unwrap().new_dataset::<i32>().create("x", 3).unwrap();
+            let ds = File::open(&path).unwrap().dataset("x").unwrap();
+            enable();
+            assert!(ds.write(&[1, 2, 3]).is_err());
+            ds.read_raw::<i32>().unwrap();
+            disable();
+
+            let stats = ds.io_stats();
+            assert_eq!((stats.writes, stats.h5d_writes), (0, 1));
+            assert_eq!((stats.reads, stats.h5d_reads), (1, 1));
+            assert_eq!(stats.bytes_written, 0);
+            reset();
+        })
+    }
+}
@@ -2,6 +2,13 @@ use lazy_static::lazy_static;
 use parking_lot::ReentrantMutex;
 
 /// Guards the execution of the provided closure with a recursive static mutex.
+///
+/// This is the same global lock that every call into the HDF5 library takes, so holding
+/// it across a sequence of operations (e.g. writing many small attributes) avoids paying
+/// the locking overhead for each of them and makes the whole sequence atomic with
+/// respect to other threads using this crate. The lock is reentrant, so any crate
+/// functions may be called from within the closure; it is not released until the
+/// closure returns, so avoid blocking on other threads that may need it.
 pub fn sync<T, F>(func: F) -> T
 where
     F: FnOnce() -> T,
@@ -10,14 +17,22 @@ where
         static ref LOCK: ReentrantMutex<()> = ReentrantMutex::new(());
     }
     let _guard = LOCK.lock();
+    crate::error::init_error_handler_locked();
     func()
 }
 
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
     use lazy_static::lazy_static;
     use parking_lot::ReentrantMutex;
 
+    use super::sync;
+
     #[test]
     pub fn test_reentrant_mutex() {
         lazy_static! {
@@ -32,4 +47,63 @@ mod tests {
         let g4 = LOCK.lock();
         assert_eq!(*g4, ());
     }
+
+    #[test]
+    pub fn test_sync_excludes_other_threads() {
+        let done = Arc::new(AtomicBool::new(false));
+        let handle = sync(|| {
+            let flag = done.clone();
+            let handle = thread::spawn(move || sync(|| flag.store(true, Ordering::SeqCst)));
+            thread::sleep(Duration::from_millis(50));
+            assert!(!done.load(Ordering::SeqCst));
+            assert_eq!(sync(|| 42), 42);
+            handle
+        });
+        handle.join().unwrap();
+        assert!(done.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    pub fn test_handles_are_send_sync() {
+        use crate::plist::{DatasetCreate, FileAccess};
+        use crate::{Attribute, Dataset, Dataspace, Datatype, File, Group, PropertyList};
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<File>();
+        assert_send_sync::<Group>();
+        assert_send_sync::<Dataset>();
+        assert_send_sync::<Attribute>();
+        assert_send_sync::<Datatype>();
+        assert_send_sync::<Dataspace>();
+        assert_send_sync::<PropertyList>();
+        assert_send_sync::<FileAccess>();
+        assert_send_sync::<DatasetCreate>();
+    }
+
+    #[test]
+    pub fn test_handles_across_threads() {
+        use crate::internal_prelude::*;
+
+        with_tmp_file(|file| {
+            let ds = file.new_dataset::<u32>().create("x", 100).unwrap();
+            ds.write(&(0..100).collect::<Vec<u32>>()).unwrap();
+            let ds = Arc::new(ds);
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    let (ds, file) = (ds.clone(), file.clone());
+                    thread::spawn(move || {
+                        for _ in 0..20 {
+                            let copy = ds.as_ref().clone();
+                            assert_eq!(copy.read_raw::<u32>().unwrap()[99], 99);
+                            assert_eq!(file.dataset("x").unwrap().size(), 100);
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+            assert_eq!(ds.refcount(), 1);
+        })
+    }
 }
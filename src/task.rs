@@ -0,0 +1,270 @@
+//! Async-friendly wrappers running library calls on a dedicated I/O thread.
+//!
+//! All calls into the HDF5 library are serialized via a global lock and may block for
+//! a long time (e.g. when reading large compressed datasets), which would stall an
+//! async executor if done directly from a task. The wrappers in this module instead
+//! send each operation to a single background thread and return a [`Task`](struct.Task.html)
+//! future that resolves with its result; the futures don't depend on any particular
+//! executor.
+//!
+//! Note that in threadsafe builds `silence_errors()` only applies to the calling thread;
+//! to silence errors of an operation, call it from within the closure passed to
+//! [`spawn()`](fn.spawn.html).
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{mpsc, Arc};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use lazy_static::lazy_static;
+use ndarray::ArrayD;
+use parking_lot::Mutex;
+
+use crate::internal_prelude::*;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Shared<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// A future resolving to the result of an operation run on the I/O thread.
+///
+/// The operation is started as soon as the task is created and runs to completion
+/// even if the task is dropped before that.
+pub struct Task<T> {
+    shared: Arc<Mutex<Shared<T>>>,
+}
+
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+        let mut shared = self.shared.lock();
+        match shared.result.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => panic::resume_unwind(payload),
+            None => {
+                shared.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+fn io_thread() -> mpsc::Sender<Job> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    thread::Builder::new()
+        .name("hdf5-io".into())
+        .spawn(move || {
+            for job in receiver {
+                job();
+            }
+        })
+        .expect("failed to spawn HDF5 I/O thread");
+    sender
+}
+
+/// Runs a closure on the I/O thread, returning a future resolving to its result.
+///
+/// Panics in the closure are propagated when the returned task is polled.
+pub fn spawn<T, F>(func: F) -> Task<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    lazy_static! {
+        static ref SENDER: Mutex<mpsc::Sender<Job>> = Mutex::new(io_thread());
+    }
+    let shared = Arc::new(Mutex::new(Shared { result: None, waker: None }));
+    let task = Task { shared: shared.clone() };
+    let job = move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(func));
+        let waker = {
+            let mut shared = shared.lock();
+            shared.result = Some(result);
+            shared.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    };
+    SENDER.lock().send(Box::new(job)).expect("HDF5 I/O thread has terminated");
+    task
+}
+
+/// Async wrapper for [`hdf5::File`](../struct.File.html).
+#[derive(Clone, Debug)]
+pub struct File(crate::File);
+
+impl File {
+    /// Opens a file as read-only, file must exist.
+    pub fn open<P: AsRef<Path>>(filename: P) -> Task<Result<Self>> {
+        let filename: PathBuf = filename.as_ref().into();
+        spawn(move || crate::File::open(filename).map(Self))
+    }
+
+    /// Opens a file as read/write, file must exist.
+    pub fn open_rw<P: AsRef<Path>>(filename: P) -> Task<Result<Self>> {
+        let filename: PathBuf = filename.as_ref().into();
+        spawn(move || crate::File::open_rw(filename).map(Self))
+    }
+
+    /// Creates a file, truncates if exists.
+    pub fn create<P: AsRef<Path>>(filename: P) -> Task<Result<Self>> {
+        let filename: PathBuf = filename.as_ref().into();
+        spawn(move || crate::File::create(filename).map(Self))
+    }
+
+    /// Opens an existing dataset in the file.
+    pub fn dataset(&self, name: &str) -> Task<Result<Dataset>> {
+        let (file, name) = (self.0.clone(), name.to_owned());
+        spawn(move || file.dataset(&name).map(Dataset))
+    }
+
+    /// Flushes the file to the storage medium.
+    pub fn flush(&self) -> Task<Result<()>> {
+        let file = self.0.clone();
+        spawn(move || file.flush())
+    }
+
+    /// Returns the wrapped file.
+    pub fn inner(&self) -> &crate::File {
+        &self.0
+    }
+
+    /// Unwraps the wrapped file.
+    pub fn into_inner(self) -> crate::File {
+        self.0
+    }
+}
+
+impl From<crate::File> for File {
+    fn from(file: crate::File) -> Self {
+        Self(file)
+    }
+}
+
+/// Async wrapper for [`hdf5::Dataset`](../struct.Dataset.html).
+#[derive(Clone, Debug)]
+pub struct Dataset(crate::Dataset);
+
+impl Dataset {
+    /// Reads the dataset into a dynamic-dimensional array.
+    pub fn read<T: H5Type + Send>(&self) -> Task<Result<ArrayD<T>>> {
+        let ds = self.0.clone();
+        spawn(move || ds.read_dyn())
+    }
+
+    /// Reads the dataset into a vector in memory order.
+    pub fn read_raw<T: H5Type + Send>(&self) -> Task<Result<Vec<T>>> {
+        let ds = self.0.clone();
+        spawn(move || ds.read_raw())
+    }
+
+    /// Writes an array into the dataset; the shapes must match.
+    pub fn write<T: H5Type + Send>(&self, arr: ArrayD<T>) -> Task<Result<()>> {
+        let ds = self.0.clone();
+        spawn(move || ds.write(&arr))
+    }
+
+    /// Writes a vector into the dataset in memory order; the sizes must match.
+    pub fn write_raw<T: H5Type + Send>(&self, data: Vec<T>) -> Task<Result<()>> {
+        let ds = self.0.clone();
+        spawn(move || ds.write_raw(&data))
+    }
+
+    /// Returns the wrapped dataset.
+    pub fn inner(&self) -> &crate::Dataset {
+        &self.0
+    }
+
+    /// Unwraps the wrapped dataset.
+    pub fn into_inner(self) -> crate::Dataset {
+        self.0
+    }
+}
+
+impl From<crate::Dataset> for Dataset {
+    fn from(ds: crate::Dataset) -> Self {
+        Self(ds)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake};
+    use std::thread::{self, Thread};
+
+    use ndarray::{arr1, arr2};
+
+    use super::{spawn, File};
+    use crate::internal_prelude::*;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(mut future: F) -> F::Output {
+        let waker = Arc::new(ThreadWaker(thread::current())).into();
+        let mut cx = Context::from_waker(&waker);
+        let mut future = unsafe { Pin::new_unchecked(&mut future) };
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_spawn() {
+        assert_eq!(
+            block_on(spawn(|| thread::current().name().map(ToOwned::to_owned))).unwrap(),
+            "hdf5-io"
+        );
+        let tasks: Vec<_> = (0..10).map(|i| spawn(move || i * 2)).collect();
+        let results: Vec<_> = tasks.into_iter().map(block_on).collect();
+        assert_eq!(results, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    pub fn test_spawn_panic() {
+        let result = std::panic::catch_unwind(|| block_on(spawn(|| panic!("oops"))));
+        assert!(result.is_err());
+        assert_eq!(block_on(spawn(|| 1)), 1);
+    }
+
+    #[test]
+    pub fn test_file_dataset() {
+        with_tmp_path(|path| {
+            let file = crate::File::create(&path).unwrap();
+            file.new_dataset::<i32>().create("x", (2, 3)).unwrap();
+            drop(file);
+
+            block_on(async {
+                let file = File::open_rw(&path).await.unwrap();
+                let ds = file.dataset("x").await.unwrap();
+                let arr = arr2(&[[1, 2, 3], [4, 5, 6]]).into_dyn();
+                ds.write(arr.clone()).await.unwrap();
+                assert_eq!(ds.read::<i32>().await.unwrap(), arr);
+                ds.write_raw(vec![6, 5, 4, 3, 2, 1]).await.unwrap();
+                assert_eq!(ds.read_raw::<i32>().await.unwrap(), vec![6, 5, 4, 3, 2, 1]);
+                assert_err!(ds.write(arr1(&[1]).into_dyn()).await, "shape mismatch");
+                assert_err!(file.dataset("y").await, "object 'y' doesn't exist");
+                file.flush().await.unwrap();
+            });
+        })
+    }
+}
@@ -0,0 +1,155 @@
+//! Storing timestamps as integer offsets from the Unix epoch.
+//!
+//! Timestamps are written as 64-bit signed integers counting units since
+//! 1970-01-01 00:00:00 UTC; the unit is recorded in a `units` string attribute using
+//! the CF convention (e.g. `"seconds since 1970-01-01 00:00:00"`), so that the data can
+//! also be decoded by netCDF-aware tools such as xarray.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::internal_prelude::*;
+
+/// Name of the attribute holding the time unit of a timestamp dataset.
+pub const UNITS_ATTR: &str = "units";
+
+/// Resolution of stored timestamps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeUnit {
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+}
+
+impl TimeUnit {
+    /// Returns the CF-style `units` attribute value for this unit.
+    pub fn units(self) -> &'static str {
+        match self {
+            Self::Seconds => "seconds since 1970-01-01 00:00:00",
+            Self::Milliseconds => "milliseconds since 1970-01-01 00:00:00",
+            Self::Microseconds => "microseconds since 1970-01-01 00:00:00",
+            Self::Nanoseconds => "nanoseconds since 1970-01-01 00:00:00",
+        }
+    }
+
+    /// Parses a `units` attribute value written by [`units()`](#method.units).
+    pub fn from_units(units: &str) -> Option<Self> {
+        [Self::Seconds, Self::Milliseconds, Self::Microseconds, Self::Nanoseconds]
+            .iter()
+            .cloned()
+            .find(|unit| unit.units() == units.trim())
+    }
+
+    fn nanos(self) -> u128 {
+        match self {
+            Self::Seconds => 1_000_000_000,
+            Self::Milliseconds => 1_000_000,
+            Self::Microseconds => 1_000,
+            Self::Nanoseconds => 1,
+        }
+    }
+}
+
+/// Converts a point in time to a (possibly negative) offset from the Unix epoch.
+///
+/// Sub-unit precision is truncated towards the epoch; fails if the offset doesn't fit
+/// into `i64` (e.g. for nanoseconds, beyond years 1677-2262).
+pub fn to_epoch(time: SystemTime, unit: TimeUnit) -> Result<i64> {
+    let (nanos, negative) = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => (d.as_nanos(), false),
+        Err(e) => (e.duration().as_nanos(), true),
+    };
+    let value = nanos / unit.nanos();
+    ensure!(value <= i64::MAX as u128, "timestamp out of range for {:?}", unit);
+    let value = value as i64;
+    Ok(if negative { -value } else { value })
+}
+
+/// Converts an offset from the Unix epoch to a point in time.
+pub fn from_epoch(value: i64, unit: TimeUnit) -> SystemTime {
+    let nanos = u128::from(value.unsigned_abs()) * unit.nanos();
+    let duration = Duration::new((nanos / 1_000_000_000) as u64, (nanos % 1_000_000_000) as u32);
+    if value < 0 {
+        UNIX_EPOCH - duration
+    } else {
+        UNIX_EPOCH + duration
+    }
+}
+
+/// Returns the time unit recorded in the `units` attribute of a dataset.
+pub fn time_unit(dataset: &Dataset) -> Result<TimeUnit> {
+    let units = dataset.get_attr_string(UNITS_ATTR)?;
+    TimeUnit::from_units(&units)
+        .ok_or_else(|| format!("unsupported time units: {:?}", units).into())
+}
+
+/// Writes timestamps into an integer dataset and records their unit.
+///
+/// The dataset shape must match the number of timestamps; the `units` attribute is
+/// created or replaced.
+pub fn write_timestamps(dataset: &Dataset, times: &[SystemTime], unit: TimeUnit) -> Result<()> {
+    let values = times.iter().map(|&t| to_epoch(t, unit)).collect::<Result<Vec<_>>>()?;
+    dataset.write_raw(&values)?;
+    dataset.set_attr_str(UNITS_ATTR, unit.units())?;
+    Ok(())
+}
+
+/// Reads timestamps from an integer dataset using the unit from its `units` attribute.
+pub fn read_timestamps(dataset: &Dataset) -> Result<Vec<SystemTime>> {
+    let unit = time_unit(dataset)?;
+    Ok(dataset.read_raw::<i64>()?.into_iter().map(|v| from_epoch(v, unit)).collect())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+
+    #[test]
+    pub fn test_epoch_conversion() {
+        let t = UNIX_EPOCH + Duration::new(1_500_000_000, 123_456_789);
+        assert_eq!(to_epoch(t, TimeUnit::Seconds).unwrap(), 1_500_000_000);
+        assert_eq!(to_epoch(t, TimeUnit::Milliseconds).unwrap(), 1_500_000_000_123);
+        assert_eq!(to_epoch(t, TimeUnit::Nanoseconds).unwrap(), 1_500_000_000_123_456_789);
+        assert_eq!(
+            from_epoch(1_500_000_000_123_456, TimeUnit::Microseconds),
+            t - Duration::new(0, 789)
+        );
+
+        let t = UNIX_EPOCH - Duration::new(10, 0);
+        assert_eq!(to_epoch(t, TimeUnit::Milliseconds).unwrap(), -10_000);
+        assert_eq!(from_epoch(-10_000, TimeUnit::Milliseconds), t);
+
+        let t = UNIX_EPOCH + Duration::new(10_000_000_000, 0);
+        assert_err!(to_epoch(t, TimeUnit::Nanoseconds), "timestamp out of range for Nanoseconds");
+
+        assert_eq!(
+            TimeUnit::from_units("seconds since 1970-01-01 00:00:00"),
+            Some(TimeUnit::Seconds)
+        );
+        assert_eq!(TimeUnit::from_units("days since 1970-01-01"), None);
+    }
+
+    #[test]
+    pub fn test_timestamps() {
+        with_tmp_file(|file| {
+            let times = vec![
+                UNIX_EPOCH + Duration::new(1_600_000_000, 250_000_000),
+                UNIX_EPOCH - Duration::new(86400, 0),
+            ];
+            let ds = file.new_dataset::<i64>().create("t", 2).unwrap();
+            write_timestamps(&ds, &times, TimeUnit::Milliseconds).unwrap();
+            assert_eq!(ds.read_raw::<i64>().unwrap(), vec![1_600_000_000_250, -86_400_000]);
+            assert_eq!(
+                ds.get_attr_string("units").unwrap(),
+                "milliseconds since 1970-01-01 00:00:00"
+            );
+            assert_eq!(time_unit(&ds).unwrap(), TimeUnit::Milliseconds);
+            assert_eq!(read_timestamps(&ds).unwrap(), times);
+
+            ds.set_attr_str("units", "days since 1970-01-01").unwrap();
+            assert_err!(read_timestamps(&ds), "unsupported time units: \"days since 1970-01-01\"");
+        })
+    }
+}
@@ -41,6 +41,14 @@ pub fn string_to_fixed_bytes(s: &str, buf: &mut [c_char]) {
     }
 }
 
+/// Returns the size in bytes of a buffer holding `count` elements of `elem_size` bytes.
+pub fn buffer_size(elem_size: usize, count: usize) -> Result<usize> {
+    match elem_size.checked_mul(count) {
+        Some(size) => Ok(size),
+        None => fail!("buffer of {} elements of {} bytes overflows usize", count, elem_size),
+    }
+}
+
 #[doc(hidden)]
 pub fn get_h5_str<T, F>(func: F) -> Result<String>
 where
@@ -0,0 +1,487 @@
+//! Integrity checks of whole files, similar to a lightweight `h5check`.
+//!
+//! `File::verify()` walks every object reachable from the root group and reads all of
+//! the data stored in the file: every allocated chunk of chunked datasets (one at a time,
+//! so that a damaged chunk is reported individually and the other chunks are still
+//! checked), the data of contiguous and compact datasets, and every attribute. Checksums
+//! of datasets using the Fletcher32 filter are verified while reading.
+//!
+//! Problems are collected into a `VerifyReport` rather than returned as errors, so that a
+//! single damaged object doesn't prevent checking the rest of the file. Besides data that
+//! can't be read, the report lists datasets whose filter pipeline requires filters that
+//! aren't available (their data is then not read) and soft or external links whose
+//! targets don't exist.
+
+use std::cmp;
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::os::raw::c_ulong;
+
+use hdf5_sys::h5a::H5Aread;
+use hdf5_sys::h5d::{H5Dread, H5Dvlen_reclaim};
+use hdf5_sys::h5o::H5Oexists_by_name;
+use hdf5_sys::h5z::H5Z_filter_t;
+
+use crate::describe::Member;
+use crate::filters;
+use crate::hl::plist::dataset_transfer::DatasetTransfer;
+use crate::internal_prelude::*;
+use crate::stats::object_key;
+
+/// Size of the buffer used to read contiguous datasets, in bytes.
+const READ_BUFFER_SIZE: usize = 16 << 20;
+
+/// A single problem found by `File::verify()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// A chunk of a chunked dataset couldn't be read, e.g. because it failed to
+    /// decompress or its checksum didn't match.
+    UnreadableChunk { path: String, offset: Vec<Ix>, message: String },
+    /// The data of a contiguous or compact dataset couldn't be read.
+    UnreadableData { path: String, message: String },
+    /// An attribute couldn't be read; the path is given as `path@name`.
+    UnreadableAttribute { path: String, message: String },
+    /// A filter in the dataset's filter pipeline isn't available.
+    MissingFilter { path: String, id: H5Z_filter_t, name: String },
+    /// A soft or external link points to an object that doesn't exist; external link
+    /// targets are given as `file:path`.
+    DanglingLink { path: String, target: String },
+    /// The object couldn't be opened or its members couldn't be listed.
+    Error { path: String, message: String },
+}
+
+impl Problem {
+    /// Returns the path of the object the problem was found in.
+    pub fn path(&self) -> &str {
+        match self {
+            Self::UnreadableChunk { path, .. }
+            | Self::UnreadableData { path, .. }
+            | Self::UnreadableAttribute { path, .. }
+            | Self::MissingFilter { path, .. }
+            | Self::DanglingLink { path, .. }
+            | Self::Error { path, .. } => path,
+        }
+    }
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnreadableChunk { path, offset, message } => {
+                write!(f, "{}: unreadable chunk at {:?}: {}", path, offset, message)
+            }
+            Self::UnreadableData { path, message } => {
+                write!(f, "{}: unreadable data: {}", path, message)
+            }
+            Self::UnreadableAttribute { path, message } => {
+                write!(f, "{}: unreadable attribute: {}", path, message)
+            }
+            Self::MissingFilter { path, id, name } if name.is_empty() => {
+                write!(f, "{}: filter {} is not available", path, id)
+            }
+            Self::MissingFilter { path, id, name } => {
+                write!(f, "{}: filter {} ({}) is not available", path, id, name)
+            }
+            Self::DanglingLink { path, target } => {
+                write!(f, "{}: dangling link to {}", path, target)
+            }
+            Self::Error { path, message } => write!(f, "{}: {}", path, message),
+        }
+    }
+}
+
+/// Result of `File::verify()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Problems in the order they were found; members are visited in name order.
+    pub problems: Vec<Problem>,
+    /// Number of objects (groups, datasets, named datatypes and links) visited.
+    pub objects: usize,
+    /// Number of datasets whose data was read.
+    pub datasets: usize,
+    /// Number of chunks read successfully.
+    pub chunks: usize,
+}
+
+impl VerifyReport {
+    /// Returns `true` if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for problem in &self.problems {
+            writeln!(f, "{}", problem)?;
+        }
+        write!(
+            f,
+            "{} objects, {} datasets and {} chunks checked, {} problems",
+            self.objects,
+            self.datasets,
+            self.chunks,
+            self.problems.len()
+        )
+    }
+}
+
+impl File {
+    /// Reads every object and all stored data of the file, reporting anything that can't
+    /// be read; see `hdf5::verify`.
+    ///
+    /// Fails only if the root group can't be listed; problems with individual objects are
+    /// collected into the returned report.
+    pub fn verify(&self) -> Result<VerifyReport> {
+        let dxpl = DatasetTransfer::build().edc_check(true).finish()?;
+        let mut verifier = Verifier { report: VerifyReport::default(), seen: HashSet::new(), dxpl };
+        verifier.group(self, "")?;
+        Ok(verifier.report)
+    }
+}
+
+/// Returns `true` if the target of the link `name` exists.
+fn link_target_exists(group: &Group, name: &str) -> bool {
+    let _e = silence_errors();
+    to_cstring(name)
+        .map(|name| h5lock!(H5Oexists_by_name(group.id(), name.as_ptr(), H5P_DEFAULT)) > 0)
+        .unwrap_or(false)
+}
+
+struct Verifier {
+    report: VerifyReport,
+    seen: HashSet<(c_ulong, haddr_t)>,
+    dxpl: DatasetTransfer,
+}
+
+impl Verifier {
+    fn push(&mut self, problem: Problem) {
+        self.report.problems.push(problem);
+    }
+
+    /// Returns `true` the first time an object is visited.
+    fn is_new(&mut self, id: hid_t) -> bool {
+        object_key(id).map_or(true, |key| self.seen.insert(key))
+    }
+
+    fn group(&mut self, group: &Group, path: &str) -> Result<()> {
+        if !self.is_new(group.id()) {
+            return Ok(());
+        }
+        self.attrs(group, if path.is_empty() { "/" } else { path });
+        for name in group.member_names()? {
+            let path = format!("{}/{}", path, name);
+            self.report.objects += 1;
+            match Member::open(group, &name) {
+                Ok(Member::Group(member)) => {
+                    if let Err(err) = self.group(&member, &path) {
+                        let message = err.to_string();
+                        self.push(Problem::Error { path, message });
+                    }
+                }
+                Ok(Member::Dataset(member)) => {
+                    if self.is_new(member.id()) {
+                        self.dataset(&member, &path);
+                        self.attrs(&member, &path);
+                    }
+                }
+                Ok(Member::Datatype(_)) => {}
+                Ok(Member::SoftLink(target)) => {
+                    if !link_target_exists(group, &name) {
+                        self.push(Problem::DanglingLink { path, target });
+                    }
+                }
+                Ok(Member::ExternalLink { file, path: target }) => {
+                    if !link_target_exists(group, &name) {
+                        let target = format!("{}:{}", file, target);
+                        self.push(Problem::DanglingLink { path, target });
+                    }
+                }
+                Err(err) => self.push(Problem::Error { path, message: err.to_string() }),
+            }
+        }
+        Ok(())
+    }
+
+    fn attrs(&mut self, location: &Location, path: &str) {
+        let names = match location.attr_names() {
+            Ok(names) => names,
+            Err(err) => {
+                let message = err.to_string();
+                return self.push(Problem::Error { path: path.into(), message });
+            }
+        };
+        for name in names {
+            if let Err(err) = location.attr(&name).and_then(|attr| read_attr(&attr)) {
+                let path = format!("{}@{}", path, name);
+                self.push(Problem::UnreadableAttribute { path, message: err.to_string() });
+            }
+        }
+    }
+
+    fn dataset(&mut self, dataset: &Dataset, path: &str) {
+        let pipeline = match dataset.dcpl().and_then(|dcpl| filters::raw_pipeline(&dcpl)) {
+            Ok(pipeline) => pipeline,
+            Err(err) => {
+                let message = err.to_string();
+                return self.push(Problem::Error { path: path.into(), message });
+            }
+        };
+        let mut missing = false;
+        for filters::RawFilter { id, name, .. } in pipeline {
+            if !filters::is_available(id) {
+                self.push(Problem::MissingFilter { path: path.into(), id, name });
+                missing = true;
+            }
+        }
+        if missing {
+            return;
+        }
+        self.report.datasets += 1;
+        if let Err(err) = self.dataset_data(dataset, path) {
+            let message = err.to_string();
+            self.push(Problem::UnreadableData { path: path.into(), message });
+        }
+    }
+
+    /// Reads all data of a dataset, chunk by chunk if it's chunked; only errors not
+    /// attributable to a single chunk are returned.
+    fn dataset_data(&mut self, dataset: &Dataset, path: &str) -> Result<()> {
+        let datatype = dataset.dtype()?;
+        if dataset.is_null() {
+            return Ok(());
+        }
+        let shape = dataset.shape();
+        if shape.is_empty() {
+            return read_block(dataset, &datatype, &self.dxpl, None);
+        }
+        let chunk = if let Some(chunk) = dataset.chunks() {
+            chunk
+        } else {
+            return read_rows(dataset, &datatype, &self.dxpl, &shape);
+        };
+        for offset in chunk_offsets(dataset, &shape, &chunk)? {
+            let block: Vec<Ix> =
+                offset.iter().zip(&chunk).zip(&shape).map(|((&o, &c), &s)| c.min(s - o)).collect();
+            match read_block(dataset, &datatype, &self.dxpl, Some((&offset, &block))) {
+                Ok(()) => self.report.chunks += 1,
+                Err(err) => {
+                    let (path, message) = (path.into(), err.to_string());
+                    self.push(Problem::UnreadableChunk { path, offset, message });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the offsets of the allocated chunks of a dataset.
+#[cfg(hdf5_1_10_5)]
+fn chunk_offsets(dataset: &Dataset, _shape: &[Ix], _chunk: &[Ix]) -> Result<Vec<Vec<Ix>>> {
+    let num_chunks = dataset.num_chunks().ok_or("unable to get the number of chunks")?;
+    (0..num_chunks)
+        .map(|index| {
+            let info = dataset.chunk_info(index).ok_or("unable to get chunk info")?;
+            Ok(info.offset.iter().map(|&x| x as _).collect())
+        })
+        .collect()
+}
+
+/// Returns the offsets of all chunks of a dataset, allocated or not (HDF5 versions
+/// before 1.10.5 can't list the allocated chunks).
+#[cfg(not(hdf5_1_10_5))]
+fn chunk_offsets(_dataset: &Dataset, shape: &[Ix], chunk: &[Ix]) -> Result<Vec<Vec<Ix>>> {
+    let mut offsets = vec![vec![]];
+    for (&len, &chunk) in shape.iter().zip(chunk) {
+        let starts: Vec<Ix> = (0..len).step_by(chunk.max(1)).collect();
+        offsets = offsets
+            .into_iter()
+            .flat_map(|offset| {
+                starts.iter().map(move |&start| {
+                    let mut offset = offset.clone();
+                    offset.push(start);
+                    offset
+                })
+            })
+            .collect();
+    }
+    Ok(offsets)
+}
+
+/// Reads all elements of a contiguous or compact dataset in blocks along the first axis.
+fn read_rows(
+    dataset: &Dataset, datatype: &Datatype, dxpl: &DatasetTransfer, shape: &[Ix],
+) -> Result<()> {
+    let row_size = datatype.size() * shape[1..].iter().product::<Ix>();
+    let rows = cmp::max(1, READ_BUFFER_SIZE / cmp::max(1, row_size));
+    for start in (0..shape[0]).step_by(rows) {
+        let mut offset = vec![0; shape.len()];
+        offset[0] = start;
+        let mut block = shape.to_vec();
+        block[0] = cmp::min(rows, shape[0] - start);
+        read_block(dataset, datatype, dxpl, Some((&offset, &block)))?;
+    }
+    Ok(())
+}
+
+/// Reads a block of a dataset (or all of it) without converting the elements.
+fn read_block(
+    dataset: &Dataset, datatype: &Datatype, dxpl: &DatasetTransfer,
+    selection: Option<(&[Ix], &[Ix])>,
+) -> Result<()> {
+    let (file_space, mem_space, count) = match selection {
+        Some((offset, block)) => {
+            let file_space = dataset.space()?;
+            file_space.select_hyperslab(offset, None, block, None)?;
+            (file_space, Dataspace::try_new(block, false)?, block.iter().product())
+        }
+        None => (dataset.space()?, dataset.space()?, dataset.size()),
+    };
+    let mut buf: Vec<u8> = vec![0; buffer_size(datatype.size(), count)?];
+    let buf_ptr = buf.as_mut_ptr() as *mut c_void;
+    h5lock!({
+        let (tp, mem, file) = (datatype.id(), mem_space.id(), file_space.id());
+        h5try!(H5Dread(dataset.id(), tp, mem, file, dxpl.id(), buf_ptr));
+        h5try!(H5Dvlen_reclaim(tp, mem, H5P_DEFAULT, buf_ptr));
+        Ok(())
+    })
+}
+
+fn read_attr(attr: &Attribute) -> Result<()> {
+    let (datatype, space) = (attr.dtype()?, attr.space()?);
+    let mut buf: Vec<u8> = vec![0; buffer_size(datatype.size(), attr.size())?];
+    let buf_ptr = buf.as_mut_ptr() as *mut c_void;
+    h5lock!({
+        h5try!(H5Aread(attr.id(), datatype.id(), buf_ptr));
+        h5try!(H5Dvlen_reclaim(datatype.id(), space.id(), H5P_DEFAULT, buf_ptr));
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::fs::OpenOptions;
+    use std::io::{Read, Seek, SeekFrom, Write};
+    use std::ptr;
+
+    use ndarray::Array2;
+
+    use hdf5_sys::h5d::H5Dcreate2;
+    use hdf5_sys::h5l::H5Lcreate_external;
+    use hdf5_sys::h5p::{H5Pset_chunk, H5Pset_filter};
+    use hdf5_sys::h5z::H5Z_FLAG_OPTIONAL;
+
+    use super::Problem;
+    use crate::hl::plist::dataset_create::DatasetCreate;
+    use crate::internal_prelude::*;
+
+    #[cfg(hdf5_1_10_5)]
+    fn corrupt(path: &std::path::Path, addr: u64) {
+        let mut file = OpenOptions::new().read(true).write(true).open(path).unwrap();
+        let mut byte = [0_u8];
+        file.seek(SeekFrom::Start(addr)).unwrap();
+        file.read_exact(&mut byte).unwrap();
+        file.seek(SeekFrom::Start(addr)).unwrap();
+        file.write_all(&[byte[0] ^ 0xff]).unwrap();
+    }
+
+    #[test]
+    pub fn test_verify_clean_file() {
+        with_tmp_file(|file| {
+            let group = file.create_group("a/b").unwrap();
+            let ds = group.new_dataset::<u16>().chunk(5).create("x", 12).unwrap();
+            ds.write(&(0..12).collect::<Vec<u16>>()).unwrap();
+            ds.new_attr::<i32>().create("attr", ()).unwrap().write_scalar(&1).unwrap();
+            let ds = file.new_dataset::<f64>().create("contiguous", (3, 2)).unwrap();
+            ds.write(&Array2::<f64>::zeros((3, 2))).unwrap();
+            file.new_dataset::<u8>().chunk(4).create("unallocated", 100).unwrap();
+            file.link_hard("a/b/x", "x_again").unwrap();
+            file.link_soft("/a/b", "soft").unwrap();
+
+            let report = file.verify().unwrap();
+            assert!(report.is_ok(), "{}", report);
+            assert_eq!((report.objects, report.datasets, report.chunks), (7, 3, 3));
+            assert_eq!(
+                report.to_string(),
+                "7 objects, 3 datasets and 3 chunks checked, 0 problems"
+            );
+        })
+    }
+
+    #[test]
+    pub fn test_verify_missing_filter() {
+        with_tmp_file(|file| {
+            let dcpl = DatasetCreate::try_new().unwrap();
+            let dtype = Datatype::from_type::<u8>().unwrap();
+            let space = Dataspace::try_new(8, false).unwrap();
+            let name = to_cstring("filtered").unwrap();
+            let _e = silence_errors();
+            h5lock!({
+                h5call!(H5Pset_chunk(dcpl.id(), 1, [4].as_ptr())).unwrap();
+                h5call!(H5Pset_filter(dcpl.id(), 32000, H5Z_FLAG_OPTIONAL, 0, ptr::null()))
+                    .unwrap();
+                let (tp, sp) = (dtype.id(), space.id());
+                let id = h5call!(H5Dcreate2(file.id(), name.as_ptr(), tp, sp, 0, dcpl.id(), 0));
+                Dataset::from_id(id.unwrap()).unwrap();
+            });
+
+            let report = file.verify().unwrap();
+            assert_eq!(
+                report.problems,
+                vec![Problem::MissingFilter {
+                    path: "/filtered".into(),
+                    id: 32000,
+                    name: "".into()
+                }]
+            );
+            assert_eq!(report.datasets, 0);
+            assert_eq!(report.problems[0].to_string(), "/filtered: filter 32000 is not available");
+        })
+    }
+
+    #[test]
+    #[cfg(hdf5_1_10_5)]
+    pub fn test_verify_problems() {
+        with_tmp_path(|filename| {
+            let addr = {
+                let file = File::create(&filename).unwrap();
+                let ds = file.new_dataset::<u32>().chunk(10).fletcher32(true).create("data", 30);
+                let ds = ds.unwrap();
+                ds.write(&(0..30).collect::<Vec<u32>>()).unwrap();
+                file.link_soft("/missing", "dangling").unwrap();
+                let (target, name) = (to_cstring("/x").unwrap(), to_cstring("external").unwrap());
+                let file_name = to_cstring("missing.h5").unwrap();
+                h5call!(H5Lcreate_external(
+                    file_name.as_ptr(),
+                    target.as_ptr(),
+                    file.id(),
+                    name.as_ptr(),
+                    H5P_DEFAULT,
+                    H5P_DEFAULT
+                ))
+                .unwrap();
+                file.flush().unwrap();
+                let chunk = (0..3).filter_map(|i| ds.chunk_info(i)).find(|c| c.offset == [10]);
+                chunk.unwrap().addr
+            };
+            corrupt(&filename, addr + 4);
+
+            let file = File::open(&filename).unwrap();
+            let report = file.verify().unwrap();
+            assert_eq!(report.chunks, 2);
+            assert_eq!(report.problems.len(), 3, "{}", report);
+            assert_eq!(
+                report.problems[0],
+                Problem::DanglingLink { path: "/dangling".into(), target: "/missing".into() }
+            );
+            match &report.problems[1] {
+                Problem::UnreadableChunk { path, offset, .. } => {
+                    assert_eq!((path.as_str(), offset.as_slice()), ("/data", &[10][..]));
+                }
+                problem => panic!("unexpected problem: {}", problem),
+            }
+            assert_eq!(report.problems[2].to_string(), "/external: dangling link to missing.h5:/x");
+            assert!(!report.is_ok());
+        })
+    }
+}
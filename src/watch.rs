@@ -0,0 +1,226 @@
+//! Following datasets as they grow.
+//!
+//! `Dataset::watch()` returns a `Watcher`, an iterator which periodically refreshes a
+//! dataset and yields the rows (along the first axis) appended since it last looked, e.g.
+//! to monitor a file that's being written by another process in SWMR mode (see
+//! `File::open_swmr()` and `RecordLogger`):
+//!
+//! ```ignore
+//! let file = File::open_swmr("log.h5")?;
+//! let watcher = file
+//!     .dataset("samples")?
+//!     .watch::<Sample>()?
+//!     .with_interval(Duration::from_millis(50))
+//!     .with_timeout(Duration::from_secs(10));
+//! for rows in watcher {
+//!     for sample in rows?.iter() {
+//!         println!("{:?}", sample);
+//!     }
+//! }
+//! ```
+//!
+//! By default, only the rows appended after the watcher was created are yielded. Without
+//! a timeout, the iterator never ends; with one, it ends once no rows have been appended
+//! for that long.
+
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ndarray::ArrayD;
+
+use hdf5_sys::h5d::H5Drefresh;
+
+use crate::internal_prelude::*;
+
+/// Interval between polls of a `Watcher` unless configured otherwise.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Yields the rows appended to a dataset, see the module documentation.
+pub struct Watcher<T> {
+    dataset: Dataset,
+    position: usize,
+    interval: Duration,
+    timeout: Option<Duration>,
+    last_growth: Instant,
+    _marker: PhantomData<T>,
+}
+
+impl<T> Debug for Watcher<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Watcher")
+            .field("dataset", &self.dataset)
+            .field("position", &self.position)
+            .field("interval", &self.interval)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+impl<T: H5Type> Watcher<T> {
+    /// Creates a watcher starting at the current end of a dataset, which must have at
+    /// least one dimension.
+    pub fn new(dataset: &Dataset) -> Result<Self> {
+        ensure!(dataset.ndim() > 0, "can't watch scalar dataset {}", dataset.name());
+        let mut watcher = Self {
+            dataset: dataset.clone(),
+            position: 0,
+            interval: DEFAULT_POLL_INTERVAL,
+            timeout: None,
+            last_growth: Instant::now(),
+            _marker: PhantomData,
+        };
+        watcher.position = watcher.rows()?;
+        Ok(watcher)
+    }
+
+    /// Sets the interval between polls.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sets the time after which iterating ends if no rows have been appended.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the first row to be yielded, e.g. 0 to start with the existing rows.
+    pub fn with_start(mut self, row: usize) -> Self {
+        self.position = row;
+        self
+    }
+
+    /// Returns the watched dataset.
+    pub fn dataset(&self) -> &Dataset {
+        &self.dataset
+    }
+
+    /// Returns the index of the next row to be yielded.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Refreshes the dataset and returns its current number of rows.
+    fn rows(&self) -> Result<usize> {
+        h5call!(H5Drefresh(self.dataset.id()))?;
+        Ok(self.dataset.shape()[0])
+    }
+
+    /// Refreshes the dataset once and reads the rows appended since the last poll, if any,
+    /// without waiting.
+    ///
+    /// If the dataset has shrunk, reading continues at its new end.
+    pub fn poll(&mut self) -> Result<Option<ArrayD<T>>> {
+        let rows = self.rows()?;
+        if rows <= self.position {
+            self.position = rows;
+            return Ok(None);
+        }
+        let mut shape = self.dataset.shape();
+        shape[0] = rows - self.position;
+        let values = if shape.contains(&0) {
+            vec![]
+        } else {
+            let mut start = vec![0; shape.len()];
+            start[0] = self.position;
+            let space = self.dataset.space()?;
+            space.select_hyperslab(&start, None, &shape, None)?;
+            self.dataset.read_selection(&space)?
+        };
+        self.position = rows;
+        self.last_growth = Instant::now();
+        Ok(Some(ArrayD::from_shape_vec(shape, values)?))
+    }
+}
+
+impl<T: H5Type> Iterator for Watcher<T> {
+    type Item = Result<ArrayD<T>>;
+
+    /// Waits until rows are appended and returns them.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.poll() {
+                Ok(Some(rows)) => return Some(Ok(rows)),
+                Ok(None) => {}
+                Err(err) => return Some(Err(err)),
+            }
+            if self.timeout.map_or(false, |t| self.last_growth.elapsed() >= t) {
+                return None;
+            }
+            thread::sleep(self.interval);
+        }
+    }
+}
+
+impl Dataset {
+    /// Returns an iterator over the rows appended to this dataset, see `Watcher`.
+    pub fn watch<T: H5Type>(&self) -> Result<Watcher<T>> {
+        Watcher::new(self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::{Duration, Instant};
+
+    use ndarray::{arr1, arr2, s};
+
+    use crate::hl::plist::file_access::LibraryVersion;
+    use crate::internal_prelude::*;
+
+    #[test]
+    pub fn test_watch_poll() {
+        with_tmp_file(|file| {
+            let ds = file.new_dataset::<u16>().chunk((2, 2)).resizable(true).create("x", (1, 2));
+            let ds = ds.unwrap();
+            ds.write(&arr2(&[[1, 2]])).unwrap();
+            let mut watcher = ds.watch::<u16>().unwrap();
+            assert_eq!(watcher.position(), 1);
+            assert_eq!(watcher.poll().unwrap(), None);
+
+            ds.resize((3, 2)).unwrap();
+            ds.write_slice(&arr2(&[[3, 4], [5, 6]]), s![1..3, ..]).unwrap();
+            let rows = watcher.poll().unwrap().unwrap();
+            assert_eq!(rows.into_dimensionality().unwrap(), arr2(&[[3, 4], [5, 6]]));
+            assert_eq!((watcher.position(), watcher.poll().unwrap()), (3, None));
+
+            let mut watcher = ds.watch::<u16>().unwrap().with_start(0);
+            assert_eq!(watcher.poll().unwrap().unwrap().shape(), &[3, 2]);
+            ds.resize((2, 2)).unwrap();
+            assert_eq!((watcher.poll().unwrap(), watcher.position()), (None, 2));
+
+            let scalar = file.new_dataset::<u16>().create("scalar", ()).unwrap();
+            assert_err!(scalar.watch::<u16>(), "can't watch scalar dataset");
+        })
+    }
+
+    #[test]
+    pub fn test_watch_swmr() {
+        with_tmp_path(|path| {
+            let latest = LibraryVersion::latest();
+            let mut builder = File::with_options();
+            builder.fapl().libver_bounds(latest, latest);
+            let file = builder.create(&path).unwrap();
+            let mut logger = file.new_record_logger::<i32>().swmr(true).create("log").unwrap();
+            logger.log_all(vec![1, 2]).unwrap();
+            logger.flush().unwrap();
+
+            let reader = builder.open_swmr(&path).unwrap();
+            let watcher = reader.dataset("log").unwrap().watch::<i32>().unwrap();
+            let mut watcher = watcher.with_interval(Duration::from_millis(5)).with_start(1);
+            assert_eq!(watcher.next().unwrap().unwrap(), arr1(&[2]).into_dyn());
+            logger.log_all(vec![3, 4, 5]).unwrap();
+            logger.flush().unwrap();
+            // the timeout counts from the last growth, which happens after `start`
+            let start = Instant::now();
+            assert_eq!(watcher.next().unwrap().unwrap(), arr1(&[3, 4, 5]).into_dyn());
+
+            let mut watcher = watcher.with_timeout(Duration::from_millis(30));
+            assert!(watcher.next().is_none());
+            assert!(start.elapsed() >= Duration::from_millis(30));
+        })
+    }
+}
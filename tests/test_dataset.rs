@@ -5,6 +5,7 @@ use rand::prelude::{Rng, SeedableRng, SmallRng};
 
 use hdf5_types::TypeDescriptor;
 
+#[macro_use]
 mod common;
 
 use self::common::gen::{gen_arr, gen_slice, Enum, FixedStruct, Gen, TupleStruct, VarLenStruct};
@@ -257,3 +258,450 @@ fn test_read_write_tuples() -> hdf5::Result<()> {
     test_read_write::<(i8, u64, f32)>()?;
     Ok(())
 }
+
+#[test]
+fn test_read_dyn_values() -> hdf5::Result<()> {
+    use hdf5::types::{DynValue, VarLenArray};
+    use hdf5::H5Type;
+
+    #[derive(H5Type, Clone, Copy)]
+    #[repr(C)]
+    struct Pair(bool, Enum);
+
+    let file = new_in_memory_file()?;
+    let data = [Pair(true, Enum::X), Pair(false, Enum::Y)];
+    let ds = file.new_dataset::<Pair>().create("tuples", (1, 2))?;
+    ds.write(&ndarray::arr2(&[data]))?;
+    let values = ds.read_dyn_array()?;
+    assert_eq!(values.shape(), &[1, 2]);
+    let enum_value = |name: &str, value| DynValue::Enum { name: Some(name.to_owned()), value };
+    assert_eq!(
+        values[[0, 0]],
+        DynValue::Compound(vec![
+            ("0".to_owned(), DynValue::Bool(true)),
+            ("1".to_owned(), enum_value("X", -2)),
+        ])
+    );
+    assert_eq!(
+        values[[0, 1]],
+        DynValue::Compound(vec![
+            ("0".to_owned(), DynValue::Bool(false)),
+            ("1".to_owned(), enum_value("Y", 3)),
+        ])
+    );
+
+    let ds = file.new_dataset::<VarLenArray<u8>>().create("varlen", 1)?;
+    ds.write(&[VarLenArray::from_slice(&[1_u8, 2])])?;
+    assert_eq!(
+        ds.read_dyn_array()?.into_raw_vec(),
+        vec![DynValue::Array(vec![DynValue::UInt(1), DynValue::UInt(2)])]
+    );
+    assert_err!(ds.read_dyn_value(), "ndim mismatch: expected scalar, got 1");
+
+    ds.set_attr_str("note", "hello")?;
+    assert_eq!(ds.attr("note")?.read_dyn_value()?, DynValue::String("hello".to_owned()));
+    ds.set_attr("count", &7_i32)?;
+    assert_eq!(ds.attr("count")?.read_dyn_value()?, DynValue::Int(7));
+    Ok(())
+}
+
+#[test]
+fn test_read_default_skip_fields() -> hdf5::Result<()> {
+    use hdf5::H5Type;
+
+    #[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Old {
+        a: i32,
+        b: f64,
+    }
+
+    #[derive(H5Type, Clone, Debug, PartialEq)]
+    #[repr(C)]
+    struct New {
+        a: i32,
+        #[hdf5(default)]
+        c: u16,
+        #[hdf5(skip)]
+        label: String,
+    }
+
+    #[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Strict {
+        a: i32,
+        c: u16,
+    }
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<Old>().create("old", 2)?;
+    ds.write(&[Old { a: 1, b: 0.5 }, Old { a: 2, b: 1.5 }])?;
+    let new = |a, c| New { a, c, label: String::new() };
+    assert_eq!(ds.read_raw::<New>()?, vec![new(1, 0), new(2, 0)]);
+    assert_err!(ds.read_raw::<Strict>(), "missing compound members without default: [\"c\"]");
+
+    let ds = file.new_dataset::<New>().create("new", 1)?;
+    ds.write(&[New { a: 3, c: 4, label: "ignored".to_owned() }])?;
+    assert_eq!(ds.read_raw::<New>()?, vec![new(3, 4)]);
+
+    let attr = file.new_attr::<Old>().create_scalar("attr", &Old { a: 5, b: 0.0 })?;
+    assert_eq!(attr.read_scalar::<New>()?, new(5, 0));
+    Ok(())
+}
+
+#[test]
+fn test_skip_fields_identical_type() -> hdf5::Result<()> {
+    use hdf5::H5Type;
+    use hdf5_sys::h5d::{H5Dclose, H5Dcreate2, H5Dread};
+    use hdf5_sys::{h5p::H5P_DEFAULT as PD, h5s::H5S_ALL};
+
+    #[derive(H5Type, Clone, Debug, PartialEq)]
+    #[repr(C)]
+    struct Rec {
+        a: i32,
+        #[hdf5(skip)]
+        cache: Vec<u64>,
+        b: f64,
+    }
+
+    // the dataset is created with the exact memory type, so no conversion takes place
+    let file = new_in_memory_file()?;
+    let (dtype, space) = (hdf5::Datatype::from_type::<Rec>()?, hdf5::Dataspace::try_new(2, false)?);
+    let name = b"rec\0".as_ptr() as _;
+    let id = unsafe { H5Dcreate2(file.id(), name, dtype.id(), space.id(), PD, PD, PD) };
+    assert!(id >= 0 && unsafe { H5Dclose(id) } >= 0);
+    let ds = file.dataset("rec")?;
+    assert!(!ds.as_reader().will_convert::<Rec>()?);
+    assert!(!ds.as_writer().will_convert::<Rec>()?);
+    ds.write_raw(&[Rec { a: 1, cache: vec![1, 2], b: 0.5 }, Rec { a: 2, cache: vec![3], b: 1.5 }])?;
+
+    let rec = |a, b| Rec { a, cache: vec![], b };
+    assert_eq!(ds.read_raw::<Rec>()?, vec![rec(1, 0.5), rec(2, 1.5)]);
+    let mut buf = vec![Rec { a: 0, cache: vec![4, 5, 6], b: 0.0 }];
+    ds.read_raw_into(&mut buf)?;
+    assert_eq!(buf, vec![rec(1, 0.5), rec(2, 1.5)]);
+    ds.write_slice(&[Rec { a: 3, cache: vec![7], b: 2.5 }], s![1..])?;
+    assert_eq!(ds.read_raw::<Rec>()?, vec![rec(1, 0.5), rec(3, 2.5)]);
+
+    // the bytes of the skipped field (and any padding) are not written as is
+    let fields = match Rec::type_descriptor() {
+        TypeDescriptor::Compound(compound) => compound.fields,
+        _ => unreachable!(),
+    };
+    let size = std::mem::size_of::<Rec>();
+    let mut raw = vec![0xff_u8; 2 * size];
+    let ptr = raw.as_mut_ptr() as _;
+    assert!(unsafe { H5Dread(ds.id(), dtype.id(), H5S_ALL, H5S_ALL, PD, ptr) } >= 0);
+    for (i, &byte) in raw.iter().enumerate() {
+        let i = i % size;
+        if !fields.iter().any(|f| (f.offset..f.offset + f.ty.size()).contains(&i)) {
+            assert_eq!(byte, 0);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_read_write_selection() -> hdf5::Result<()> {
+    use hdf5::Dataspace;
+
+    let file = new_in_memory_file()?;
+    let arr = Array2::from_shape_fn((4, 5), |(i, j)| (i * 10 + j) as i32);
+    let ds1 = file.new_dataset::<i32>().create("a", (4, 5))?;
+    ds1.write(&arr)?;
+    let ds2 = file.new_dataset::<i32>().create("b", (4, 5))?;
+    ds2.write(&arr.map(|x| -x))?;
+
+    let space = Dataspace::try_new((4, 5), false)?;
+    space.select_hyperslab(&[1, 0], Some(&[2, 3]), &[2, 2], None)?;
+    assert_eq!(space.selection_npoints()?, 4);
+    assert_eq!(ds1.read_selection::<i32>(&space)?, vec![10, 13, 30, 33]);
+    assert_eq!(ds2.read_selection::<i32>(&space)?, vec![-10, -13, -30, -33]);
+    space.select_hyperslab(&[0, 1], None, &[1, 2], Some(&[2, 1]))?;
+    assert_eq!(ds1.read_selection::<i32>(&space)?, vec![1, 2, 11, 12]);
+
+    space.select_elements(&[[3, 4], [0, 0], [2, 1]])?;
+    assert_eq!(ds1.read_selection::<i32>(&space)?, vec![34, 0, 21]);
+    ds2.write_selection(&[7, 8, 9], &space)?;
+    assert_eq!(ds2.read_selection::<i32>(&space)?, vec![7, 8, 9]);
+    assert_eq!(ds2.read_2d::<i32>()?[[0, 1]], -1);
+    assert_err!(
+        ds2.write_selection(&[1, 2], &space),
+        "length mismatch when writing selection: memory = 2, selection = 3"
+    );
+
+    space.select_none()?;
+    assert_eq!(space.selection_npoints()?, 0);
+    assert_eq!(ds1.read_selection::<i32>(&space)?, Vec::<i32>::new());
+    space.select_all()?;
+    assert_eq!(ds1.read_selection::<i32>(&space)?, arr.into_raw_vec());
+
+    let other = Dataspace::try_new((5, 4), false)?;
+    assert_err!(
+        ds1.read_selection::<i32>(&other),
+        "selection shape mismatch: dataset = [4, 5], selection = [5, 4]"
+    );
+    assert_err!(
+        other.select_hyperslab(&[0], None, &[1, 1], None),
+        "hyperslab start has 1 dims, dataspace has 2 dims"
+    );
+    assert_err!(other.select_elements(&[vec![0, 0, 0]]), "coordinate [0, 0, 0] has 3 dims");
+    Ok(())
+}
+
+#[test]
+fn test_null_dataspace() -> hdf5::Result<()> {
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<f64>().create_null("null")?;
+    assert!(ds.is_null() && !ds.is_scalar());
+    assert_eq!((ds.ndim(), ds.shape(), ds.size()), (0, vec![], 0));
+    assert_eq!(ds.read_raw::<f64>()?, Vec::<f64>::new());
+    ds.write_raw(&[] as &[f64])?;
+    assert_err!(ds.read_scalar::<f64>(), "cannot access elements of a null dataspace");
+    assert_err!(ds.read_dyn::<f64>(), "cannot access elements of a null dataspace");
+    assert_err!(ds.write_scalar(&1.0), "cannot access elements of a null dataspace");
+    assert_err!(ds.write_raw(&[1.0]), "length mismatch when writing: memory = 1, destination = 0");
+    assert_err!(
+        file.new_dataset::<f64>().compress_fast().create_null("chunked"),
+        "Chunking cannot be enabled for scalar datasets"
+    );
+
+    let attr = ds.new_attr::<i32>().create_null("empty")?;
+    assert!(ds.attr("empty")?.is_null());
+    assert_eq!(attr.read_raw::<i32>()?, Vec::<i32>::new());
+    assert_err!(attr.read_dyn_array(), "cannot access elements of a null dataspace");
+    assert_eq!(ds.attr_names()?, vec!["empty".to_owned()]);
+
+    let scalar = file.new_dataset::<f64>().create("scalar", ())?;
+    assert!(scalar.is_scalar() && !scalar.is_null());
+    assert_eq!(scalar.size(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_read_slice_expr() -> hdf5::Result<()> {
+    use hdf5::SliceExpr;
+
+    let file = new_in_memory_file()?;
+    let arr = Array2::from_shape_fn((4, 6), |(i, j)| (i * 10 + j) as u16);
+    let ds = file.new_dataset::<u16>().create("a", (4, 6))?;
+    ds.write(&arr)?;
+    let expr: SliceExpr = "1::2, ..., -2".parse()?;
+    let slice = expr.resolve(&ds.shape())?;
+    assert_eq!(ds.read_slice::<u16, _, IxDyn>(&slice)?.into_raw_vec(), vec![14, 34]);
+    let slice = "..., :4:3".parse::<SliceExpr>()?.resolve(&ds.shape())?;
+    assert_eq!(ds.read_slice::<u16, _, IxDyn>(&slice)?.shape(), &[4, 2]);
+    Ok(())
+}
+
+#[test]
+fn test_read_tiles_with_offset() -> hdf5::Result<()> {
+    use hdf5::Dataspace;
+
+    let file = new_in_memory_file()?;
+    let arr = Array2::from_shape_fn((4, 6), |(i, j)| (i * 10 + j) as i32);
+    let ds = file.new_dataset::<i32>().create("a", (4, 6))?;
+    ds.write(&arr)?;
+
+    let tile = Dataspace::try_new((4, 6), false)?;
+    tile.select_hyperslab(&[0, 0], None, &[2, 3], None)?;
+    let mut sums = vec![];
+    for i in 0..2 {
+        for j in 0..2 {
+            let space = tile.with_offset(&[i * 2, j * 3])?;
+            sums.push(ds.read_selection::<i32>(&space)?.iter().sum::<i32>());
+        }
+    }
+    assert_eq!(sums, vec![36, 54, 156, 174]);
+    Ok(())
+}
+
+#[test]
+fn test_dataset_access_plist() -> hdf5::Result<()> {
+    use hdf5::plist::DatasetAccess;
+
+    let file = new_in_memory_file()?;
+    let ds = file
+        .new_dataset::<u8>()
+        .chunk((10, 10))
+        .with_dapl(|p| p.chunk_cache(101, 1 << 20, 0.25))
+        .create("a", (100, 100))?;
+    let cache = ds.dapl()?.chunk_cache();
+    assert_eq!((cache.nslots, cache.nbytes, cache.w0), (101, 1 << 20, 0.25));
+    drop(ds);
+
+    let dapl = DatasetAccess::build().chunk_cache(211, 1 << 16, 0.5).finish()?;
+    let ds = file.dataset_with_access("a", &dapl)?;
+    let cache = ds.access_plist()?.chunk_cache();
+    assert_eq!((cache.nslots, cache.nbytes, cache.w0), (211, 1 << 16, 0.5));
+    assert_eq!(ds.shape(), vec![100, 100]);
+    Ok(())
+}
+
+#[test]
+fn test_dataset_create_plist() -> hdf5::Result<()> {
+    use hdf5::dataset::Layout;
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<u16>().no_chunk().create("a", (10, 20))?;
+    let dcpl = ds.dcpl()?;
+    assert_eq!(dcpl.layout(), Layout::Contiguous);
+    assert_eq!(dcpl.chunk(), None);
+
+    let ds = file
+        .new_dataset::<u16>()
+        .chunk((5, 4))
+        .shuffle(true)
+        .track_times(true)
+        .attr_phase_change(0, 0)
+        .create("b", (10, 20))?;
+    let dcpl = ds.create_plist()?;
+    assert_eq!(dcpl.layout(), Layout::Chunked);
+    assert_eq!(dcpl.chunk(), Some(vec![5, 4]));
+    assert_eq!(dcpl.filters(), ds.filters());
+    assert!(dcpl.obj_track_times());
+
+    let copy = file.new_dataset::<u16>().set_dcpl(&dcpl)?.create("c", (10, 20))?;
+    assert_eq!(copy.chunks(), Some(vec![5, 4]));
+    assert!(copy.filters().get_shuffle());
+    assert!(copy.tracks_times());
+    assert_eq!(copy.attr_phase_change()?.max_compact, 0);
+    Ok(())
+}
+
+#[test]
+fn test_dataset_transfer_plist() -> hdf5::Result<()> {
+    use hdf5::plist::DatasetTransfer;
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<f64>().create("a", 3)?;
+    ds.write(&[1.5, 1e20, -2.0])?;
+
+    let clamped: Vec<i32> = ds.as_reader().read_raw()?;
+    assert_eq!(clamped, vec![1, i32::MAX, -2]);
+
+    let dxpl = DatasetTransfer::build().abort_on_conv_exception(true).finish()?;
+    assert!(ds.as_reader().transfer_plist(&dxpl).read_raw::<i32>().is_err());
+    assert_eq!(ds.as_reader().dxpl(&dxpl).read_raw::<f64>()?, vec![1.5, 1e20, -2.0]);
+
+    let ds = file.new_dataset::<i8>().create("b", 2)?;
+    assert!(ds.as_writer().dxpl(&dxpl).write_raw(&[1_i32, 1000]).is_err());
+    ds.as_writer().dxpl(&dxpl).write_raw(&[1_i32, -100])?;
+    assert_eq!(ds.read_raw::<i8>()?, vec![1, -100]);
+    Ok(())
+}
+
+#[test]
+fn test_read_into_vec() -> hdf5::Result<()> {
+    use hdf5::Dataspace;
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<i32>().create("a", 6)?;
+    ds.write(&[1, 2, 3, 4, 5, 6])?;
+
+    let mut vec: Vec<i32> = Vec::with_capacity(10);
+    let ptr = vec.as_ptr();
+    ds.read_raw_into(&mut vec)?;
+    assert_eq!(vec, vec![1, 2, 3, 4, 5, 6]);
+    ds.as_reader().read_raw_into(&mut vec)?;
+    assert_eq!(vec, vec![1, 2, 3, 4, 5, 6]);
+
+    let space = Dataspace::try_new(6, false)?;
+    space.select_elements(&[[4], [1]])?;
+    ds.read_selection_into(&space, &mut vec)?;
+    assert_eq!(vec, vec![5, 2]);
+    space.select_none()?;
+    ds.read_selection_into(&space, &mut vec)?;
+    assert!(vec.is_empty());
+    assert_eq!(vec.as_ptr(), ptr);
+
+    let mut strings = vec![hdf5::types::VarLenUnicode::new()];
+    assert!(ds.read_raw_into(&mut strings).is_err());
+    assert!(strings.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_write_strided() -> hdf5::Result<()> {
+    use hdf5::Dataspace;
+    use ndarray::{Array3, Axis};
+
+    let file = new_in_memory_file()?;
+    let big = Array2::from_shape_fn((6, 8), |(i, j)| (i * 10 + j) as i32);
+
+    let view = big.slice(s![1..5;2, 2..7]);
+    let ds = file.new_dataset::<i32>().create("a", (2, 5))?;
+    ds.write(&view)?;
+    assert_eq!(ds.read_2d::<i32>()?, view);
+
+    let view = big.slice(s![.., ..;3]);
+    let ds = file.new_dataset::<i32>().create("b", (6, 3))?;
+    ds.write(&view)?;
+    assert_eq!(ds.read_2d::<i32>()?, view);
+    ds.write_slice(&big.slice(s![..3;2, 1..;3]), s![1..3, ..])?;
+    assert_eq!(ds.read_2d::<i32>()?.slice(s![1..3, ..]), big.slice(s![..3;2, 1..;3]));
+
+    let ds = file.new_dataset::<i32>().create("c", 4)?;
+    ds.write_raw(big.index_axis(Axis(0), 1).slice(s![..;2]))?;
+    assert_eq!(ds.read_raw::<i32>()?, vec![10, 12, 14, 16]);
+    ds.write_raw(big.column(3).slice(s![..4]))?;
+    assert_eq!(ds.read_raw::<i32>()?, vec![3, 13, 23, 33]);
+
+    let space = Dataspace::try_new(4, false)?;
+    space.select_elements(&[[3], [0]])?;
+    ds.write_selection(big.slice(s![0, ..;7]), &space)?;
+    assert_eq!(ds.read_raw::<i32>()?, vec![7, 13, 23, 0]);
+
+    let cube = Array3::from_shape_fn((4, 1, 6), |(i, j, k)| (i * 100 + j * 10 + k) as i32);
+    let view = cube.slice(s![..;2, .., 1..;2]);
+    let ds = file.new_dataset::<i32>().create("d", (2, 1, 3))?;
+    ds.write(&view)?;
+    assert_eq!(ds.read_dyn::<i32>()?, view.into_dyn());
+
+    let ds = file.new_dataset::<i32>().create("e", (8, 6))?;
+    assert_err!(ds.write(&big.t()), "input array is not in standard layout");
+    assert_err!(ds.write(&big.slice(s![..;-1, ..])), "input array is not in standard layout");
+    assert_err!(
+        ds.as_writer().write_raw(big.slice(s![.., 0])),
+        "length mismatch when writing: memory = 6, destination = 48"
+    );
+    let attr = ds.new_attr::<i32>().create("a", 3)?;
+    assert_err!(attr.write(&big.slice(s![0, ..6;2])), "input array is not in standard layout");
+    Ok(())
+}
+
+#[test]
+fn test_will_convert() -> hdf5::Result<()> {
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<i32>().create("a", 3)?;
+    assert!(!ds.as_writer().will_convert::<i32>()?);
+    assert!(ds.as_writer().will_convert::<i16>()?);
+    ds.write(&[1, 2, 3])?;
+    assert!(!ds.as_reader().will_convert::<i32>()?);
+    assert!(ds.as_reader().will_convert::<i64>()?);
+    assert!(ds.as_reader().will_convert::<f64>()?);
+    assert_eq!(ds.as_reader().no_convert().read_raw::<i32>()?, vec![1, 2, 3]);
+
+    let ds = file.new_dataset::<u8>().create("b", 1)?;
+    assert!(!ds.as_reader().will_convert::<u8>()?);
+    assert!(ds.as_reader().will_convert::<i8>()?);
+    assert!(ds.as_reader().will_convert::<FixedStruct>()?);
+    Ok(())
+}
+
+#[test]
+#[cfg(hdf5_1_10_0)]
+fn test_dataset_create_plist_encode() -> hdf5::Result<()> {
+    use hdf5::plist::DatasetCreate;
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<u16>().chunk((5, 4)).shuffle(true).create("a", (10, 20))?;
+    let buf = ds.dcpl()?.encode()?;
+
+    let dcpl = DatasetCreate::decode(&buf)?;
+    assert_eq!(dcpl.chunk(), Some(vec![5, 4]));
+    let copy = file.new_dataset::<u16>().set_dcpl(&dcpl)?.create("b", (10, 20))?;
+    assert_eq!(copy.chunks(), Some(vec![5, 4]));
+    assert_eq!(copy.filters(), ds.filters());
+    Ok(())
+}
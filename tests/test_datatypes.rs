@@ -30,6 +30,8 @@ pub fn test_datatype_roundtrip() {
     check_roundtrip!(f64, TD::Float(FloatSize::U8));
     check_roundtrip!(bool, TD::Boolean);
     check_roundtrip!([bool; 5], TD::FixedArray(Box::new(TD::Boolean), 5));
+    check_roundtrip!([f32; 4097], TD::FixedArray(Box::new(TD::Float(FloatSize::U4)), 4097));
+    check_roundtrip!(FixedAscii<[_; 1001]>, TD::FixedAscii(1001));
     check_roundtrip!(VarLenArray<bool>, TD::VarLenArray(Box::new(TD::Boolean)));
     check_roundtrip!(FixedAscii<[_; 5]>, TD::FixedAscii(5));
     check_roundtrip!(FixedUnicode<[_; 5]>, TD::FixedUnicode(5));
@@ -86,6 +88,862 @@ pub fn test_datatype_roundtrip() {
     check_roundtrip!(C, c_desc);
 }
 
+#[test]
+pub fn test_descriptor_roundtrip_nested() -> hdf5::Result<()> {
+    // recreating a datatype from its descriptor yields the same datatype
+    fn check(desc: &TD) -> hdf5::Result<TD> {
+        let dt = Datatype::from_descriptor(desc)?;
+        let roundtrip = dt.to_descriptor()?;
+        assert_eq!(Datatype::from_descriptor(&roundtrip)?, dt);
+        assert_eq!(roundtrip.size(), desc.size());
+        Ok(roundtrip)
+    }
+
+    let obj_ref = TD::Reference(ReferenceType::Object);
+    let reg_ref = TD::Reference(ReferenceType::DatasetRegion);
+    assert_eq!(check(&obj_ref)?, obj_ref);
+    assert_eq!(check(&reg_ref)?, reg_ref);
+    assert_eq!(obj_ref.size(), 8);
+    assert_eq!(reg_ref.size(), 12);
+
+    // tuple fields are reordered in memory, but members are stored in declaration order
+    let tuple = <(u8, VarLenUnicode, i64)>::type_descriptor();
+    let names: Vec<_> = Datatype::from_descriptor(&tuple)?
+        .compound_members()?
+        .into_iter()
+        .map(|m| m.name)
+        .collect();
+    assert_eq!(names, ["0", "1", "2"]);
+    assert_eq!(check(&tuple)?, tuple);
+
+    // HDF5 sorts members of compounds nested in variable-length types by offset,
+    // so only the datatypes are compared
+    let vlen = TD::VarLenArray(Box::new(tuple.clone()));
+    check(&vlen)?;
+    check(&TD::VarLenArray(Box::new(vlen.clone())))?;
+    check(&TD::FixedArray(Box::new(vlen), 3))?;
+    let desc = TD::FixedArray(Box::new(TD::VarLenAscii), 2);
+    assert_eq!(check(&desc)?, desc);
+    let desc = TD::Compound(CompoundType {
+        fields: vec![
+            CompoundField::new("r", reg_ref, 0, 0),
+            CompoundField::new("v", TD::VarLenArray(Box::new(obj_ref)), 16, 1),
+        ],
+        size: 32,
+    });
+    assert_eq!(check(&desc)?, desc);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "num-complex")]
+pub fn test_complex() -> hdf5::Result<()> {
+    use self::common::util::new_in_memory_file;
+
+    let desc = |ty: TD, size| {
+        TD::Compound(CompoundType {
+            fields: vec![
+                CompoundField::new("r", ty.clone(), 0, 0),
+                CompoundField::new("i", ty, size, 1),
+            ],
+            size: size * 2,
+        })
+    };
+    check_roundtrip!(Complex<f32>, desc(TD::Float(FloatSize::U4), 4));
+    check_roundtrip!(Complex<f64>, desc(TD::Float(FloatSize::U8), 8));
+
+    let file = new_in_memory_file()?;
+    let data = vec![Complex::new(1.0_f64, -2.0), Complex::new(0.5, 3.0)];
+    let ds = file.new_dataset::<Complex<f64>>().create("z", 2)?;
+    ds.write(&data)?;
+    assert_eq!(ds.read_raw::<Complex<f64>>()?, data);
+    assert_eq!(
+        ds.read_raw::<Complex<f32>>()?,
+        vec![Complex::new(1.0, -2.0), Complex::new(0.5, 3.0)]
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_int128() -> hdf5::Result<()> {
+    use self::common::util::new_in_memory_file;
+
+    check_roundtrip!(i128, TD::Integer(IntSize::U16));
+    check_roundtrip!(u128, TD::Unsigned(IntSize::U16));
+
+    let file = new_in_memory_file()?;
+    let data = vec![i128::min_value(), -1, 0, 1 << 100, i128::max_value()];
+    let ds = file.new_dataset::<i128>().create("i", data.len())?;
+    ds.write(&data)?;
+    assert_eq!(ds.read_raw::<i128>()?, data);
+    let data = vec![0, u128::from(u64::max_value()) + 1, u128::max_value()];
+    let ds = file.new_dataset::<u128>().create("u", data.len())?;
+    ds.write(&data)?;
+    assert_eq!(ds.read_raw::<u128>()?, data);
+
+    let ds = file.new_dataset::<i64>().create("small", 2)?;
+    ds.write(&[-5_i64, 7])?;
+    assert_eq!(ds.read_raw::<i128>()?, vec![-5, 7]);
+    let ds = file.new_dataset::<i128>().create("wide", 2)?;
+    ds.write(&[-5_i128, 7])?;
+    assert_eq!(ds.read_raw::<i32>()?, vec![-5, 7]);
+    Ok(())
+}
+
+#[test]
+pub fn test_opaque() -> hdf5::Result<()> {
+    use self::common::util::new_in_memory_file;
+
+    struct Proto;
+    impl OpaqueTag for Proto {
+        const TAG: &'static str = "application/x-protobuf";
+    }
+    struct Other;
+    impl OpaqueTag for Other {
+        const TAG: &'static str = "other";
+    }
+    type Blob = FixedOpaque<[u8; 4], Proto>;
+
+    let desc = TD::Opaque(OpaqueType { size: 4, tag: "application/x-protobuf".into() });
+    check_roundtrip!(Blob, desc);
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<Blob>().create("blobs", 2)?;
+    let data = vec![Blob::new([1, 2, 3, 4]), Blob::new([5, 6, 7, 8])];
+    ds.write(&data)?;
+    assert_eq!(ds.read_raw::<Blob>()?, data);
+    assert_err!(ds.read_raw::<FixedOpaque<[u8; 4], Other>>(), "no conversion paths found");
+    assert_eq!(
+        ds.read_dyn_array()?.into_raw_vec()[1],
+        DynValue::Opaque { tag: "application/x-protobuf".into(), data: vec![5, 6, 7, 8] }
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_bitfield() -> hdf5::Result<()> {
+    use self::common::util::new_in_memory_file;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Flags(u8);
+    unsafe impl H5Type for Flags {
+        fn type_descriptor() -> TD {
+            TD::Bitfield(BitfieldType { size: IntSize::U1, precision: 4, offset: 2 })
+        }
+    }
+
+    check_roundtrip!(Bitfield<u16>, TD::Bitfield(BitfieldType::new(IntSize::U2)));
+    check_roundtrip!(Flags, Flags::type_descriptor());
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<Bitfield<u32>>().create("b32", 2)?;
+    let data = vec![Bitfield(0xdead_beef_u32), Bitfield(1)];
+    ds.write(&data)?;
+    assert_eq!(ds.read_raw::<Bitfield<u32>>()?, data);
+    assert_eq!(ds.read_raw::<Bitfield<u64>>()?, vec![Bitfield(0xdead_beef), Bitfield(1)]);
+
+    let ds = file.new_dataset::<Flags>().create("flags", 2)?;
+    ds.write(&[Flags(0b0011_1100), Flags(0b0000_1000)])?;
+    assert_eq!(ds.read_raw::<Bitfield<u8>>()?, vec![Bitfield(0b1111), Bitfield(0b0010)]);
+    assert_eq!(ds.read_dyn_array()?.into_raw_vec(), vec![DynValue::UInt(15), DynValue::UInt(2)]);
+    Ok(())
+}
+
+#[test]
+pub fn test_string_padding() -> hdf5::Result<()> {
+    use hdf5::datatype::StringPadding;
+    use hdf5_sys::{h5d::H5Dread, h5p::H5P_DEFAULT, h5s::H5S_ALL};
+
+    use self::common::util::new_in_memory_file;
+
+    type S = FixedAscii<[u8; 4]>;
+
+    let dtype = Datatype::from_type::<S>()?;
+    assert_eq!(dtype.string_padding(), Some(StringPadding::NullPad));
+    let spaces = dtype.with_string_padding(StringPadding::SpacePad)?;
+    assert_eq!(spaces.string_padding(), Some(StringPadding::SpacePad));
+    assert_eq!(dtype.string_padding(), Some(StringPadding::NullPad));
+    assert_eq!(Datatype::from_type::<u8>()?.string_padding(), None);
+    assert_err!(
+        Datatype::from_type::<u8>()?.with_string_padding(StringPadding::NullTerm),
+        "string padding can only be set for string datatypes"
+    );
+
+    let file = new_in_memory_file()?;
+    let data = [S::from_ascii("ab").unwrap(), S::from_ascii("abcd").unwrap()];
+    let ds = file.new_dataset::<S>().string_padding(StringPadding::SpacePad).create("s", 2)?;
+    ds.write(&data)?;
+    assert_eq!(ds.dtype()?.string_padding(), Some(StringPadding::SpacePad));
+    assert_eq!(ds.read_raw::<S>()?, data);
+    let mut raw = [0_u8; 8];
+    let (id, dtype) = (ds.id(), ds.dtype()?);
+    assert!(
+        unsafe { H5Dread(id, dtype.id(), H5S_ALL, H5S_ALL, H5P_DEFAULT, raw.as_mut_ptr() as _) }
+            >= 0
+    );
+    assert_eq!(&raw, b"ab  abcd");
+
+    let ds = file.new_dataset::<S>().string_padding(StringPadding::NullTerm).create("t", 2)?;
+    ds.write(&data)?;
+    assert_eq!(ds.read_raw::<S>()?, [S::from_ascii("ab").unwrap(), S::from_ascii("abc").unwrap()]);
+
+    let attr = file.new_attr::<S>().string_padding(StringPadding::SpacePad).create("a", ())?;
+    attr.write_scalar(&data[0])?;
+    assert_eq!(attr.dtype()?.string_padding(), Some(StringPadding::SpacePad));
+    assert_eq!(attr.read_scalar::<S>()?, data[0]);
+    Ok(())
+}
+
+#[test]
+pub fn test_char_encoding() -> hdf5::Result<()> {
+    use hdf5::datatype::CharEncoding;
+
+    use self::common::util::new_in_memory_file;
+
+    assert_eq!(Datatype::from_type::<VarLenAscii>()?.char_encoding(), Some(CharEncoding::Ascii));
+    assert_eq!(
+        Datatype::from_type::<FixedUnicode<[u8; 2]>>()?.char_encoding(),
+        Some(CharEncoding::Utf8)
+    );
+    assert_eq!(Datatype::from_type::<u8>()?.char_encoding(), None);
+    let dtype = Datatype::from_type::<VarLenAscii>()?.with_char_encoding(CharEncoding::Utf8)?;
+    assert_eq!(dtype.to_descriptor()?, TD::VarLenUnicode);
+    assert_err!(
+        Datatype::from_type::<u8>()?.with_char_encoding(CharEncoding::Utf8),
+        "character set can only be set for string datatypes"
+    );
+
+    let file = new_in_memory_file()?;
+    let unicode: VarLenUnicode = "\u{3b1}\u{3b2}".parse().unwrap();
+    let ascii = VarLenAscii::from_ascii("ab").unwrap();
+
+    let ds = file
+        .new_dataset::<VarLenUnicode>()
+        .string_encoding(CharEncoding::Ascii)
+        .create("ascii", 1)?;
+    assert_eq!(ds.dtype()?.char_encoding(), Some(CharEncoding::Ascii));
+    ds.write(&[unicode.clone()])?;
+    ds.write(&[ascii.clone()])?;
+    assert_eq!(ds.read_raw::<VarLenAscii>()?, [ascii.clone()]);
+    assert_eq!(ds.read_raw::<VarLenUnicode>()?[0].as_str(), "ab");
+
+    let ds =
+        file.new_dataset::<VarLenAscii>().string_encoding(CharEncoding::Utf8).create("utf8", 1)?;
+    assert_eq!(ds.dtype()?.to_descriptor()?, TD::VarLenUnicode);
+    ds.write(&[ascii.clone()])?;
+    assert_eq!(ds.read_raw::<VarLenUnicode>()?[0].as_str(), "ab");
+    assert_err!(ds.read_raw::<VarLenAscii>(), "can't read UTF-8 strings as ASCII");
+
+    type FA = FixedAscii<[u8; 4]>;
+    let attr = file.new_attr::<FA>().string_encoding(CharEncoding::Utf8).create("a", ())?;
+    attr.write_scalar(&FA::from_ascii("xyz").unwrap())?;
+    assert_eq!(attr.dtype()?.char_encoding(), Some(CharEncoding::Utf8));
+    assert_eq!(attr.read_scalar::<FixedUnicode<[u8; 4]>>()?.as_str(), "xyz");
+    assert_eq!(attr.read_string()?, "xyz");
+    Ok(())
+}
+
+#[test]
+pub fn test_byte_order() -> hdf5::Result<()> {
+    use hdf5::datatype::ByteOrder;
+    use hdf5_sys::{h5d::H5Dread, h5p::H5P_DEFAULT, h5s::H5S_ALL};
+
+    use self::common::util::new_in_memory_file;
+
+    #[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+    #[repr(u16)]
+    enum E {
+        A = 1,
+        B = 0x0102,
+    }
+
+    #[derive(H5Type, Clone, Debug, PartialEq)]
+    #[repr(C)]
+    struct S {
+        e: E,
+        b: bool,
+        a: [f64; 2],
+        v: VarLenArray<i32>,
+    }
+
+    let native =
+        if cfg!(target_endian = "little") { ByteOrder::LittleEndian } else { ByteOrder::BigEndian };
+    assert_eq!(Datatype::from_type::<u32>()?.byte_order(), native);
+    let dtype = Datatype::from_type::<u32>()?.with_byte_order(ByteOrder::BigEndian)?;
+    assert_eq!(dtype.byte_order(), ByteOrder::BigEndian);
+    assert_eq!(dtype.to_descriptor()?, TD::Unsigned(IntSize::U4));
+    assert_err!(
+        dtype.with_byte_order(ByteOrder::Vax),
+        "byte order must be little-endian or big-endian, got Vax"
+    );
+
+    let be = Datatype::from_type::<S>()?.with_byte_order(ByteOrder::BigEndian)?;
+    assert_eq!(be.to_descriptor()?, S::type_descriptor());
+    assert_eq!(be.with_byte_order(ByteOrder::LittleEndian)?.to_descriptor()?, S::type_descriptor());
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<u32>().byte_order(ByteOrder::BigEndian).create("u32", 2)?;
+    ds.write(&[1, 0x0a0b_0c0d])?;
+    assert_eq!(ds.dtype()?.byte_order(), ByteOrder::BigEndian);
+    assert_eq!(ds.read_raw::<u32>()?, [1, 0x0a0b_0c0d]);
+    let mut raw = [0_u8; 8];
+    let (id, dtype) = (ds.id(), ds.dtype()?);
+    assert!(
+        unsafe { H5Dread(id, dtype.id(), H5S_ALL, H5S_ALL, H5P_DEFAULT, raw.as_mut_ptr() as _) }
+            >= 0
+    );
+    assert_eq!(raw, [0, 0, 0, 1, 10, 11, 12, 13]);
+
+    let data = vec![
+        S { e: E::B, b: true, a: [0.5, -2.0], v: VarLenArray::from_slice(&[1, -1]) },
+        S { e: E::A, b: false, a: [1e10, 0.0], v: VarLenArray::from_slice(&[]) },
+    ];
+    let ds = file.new_dataset::<S>().byte_order(ByteOrder::BigEndian).create("s", 2)?;
+    ds.write(&data)?;
+    assert_eq!(ds.read_raw::<S>()?, data);
+    assert_eq!(ds.dtype()?.to_descriptor()?, S::type_descriptor());
+
+    let attr = file.new_attr::<i16>().byte_order(ByteOrder::BigEndian).create("a", ())?;
+    attr.write_scalar(&-2)?;
+    assert_eq!(attr.dtype()?.byte_order(), ByteOrder::BigEndian);
+    assert_eq!(attr.read_scalar::<i16>()?, -2);
+    Ok(())
+}
+
+#[test]
+pub fn test_introspection() -> hdf5::Result<()> {
+    use hdf5::datatype::{StringPadding, TypeClass};
+    use hdf5_sys::h5t::H5Tarray_create2;
+
+    #[derive(H5Type, Clone, Copy)]
+    #[repr(i8)]
+    enum E {
+        X = -1,
+        Y = 3,
+    }
+
+    #[derive(H5Type, Clone)]
+    #[repr(C)]
+    struct S {
+        a: u8,
+        b: [[f32; 3]; 2],
+        e: E,
+        s: FixedAscii<[u8; 5]>,
+        v: VarLenArray<u16>,
+    }
+
+    let dtype = Datatype::from_type::<S>()?;
+    assert_eq!(dtype.class()?, TypeClass::Compound);
+    assert_eq!(dtype.size(), std::mem::size_of::<S>());
+    let members = dtype.compound_members()?;
+    let names: Vec<_> = members.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["a", "b", "e", "s", "v"]);
+    let offsets: Vec<_> = members.iter().map(|m| m.offset).collect();
+    assert_eq!(offsets, [0, 4, 28, 29, 40]);
+    let classes = members.iter().map(|m| m.datatype.class()).collect::<hdf5::Result<Vec<_>>>()?;
+    assert_eq!(
+        classes,
+        [
+            TypeClass::Integer,
+            TypeClass::Array,
+            TypeClass::Enum,
+            TypeClass::String,
+            TypeClass::VarLen
+        ]
+    );
+
+    let arr = &members[1].datatype;
+    assert_eq!(arr.array_dims()?, [2]);
+    assert_eq!(arr.base_type()?.array_dims()?, [3]);
+    let dims = [2, 3];
+    let f32_id = Datatype::from_type::<f32>()?;
+    let arr: Datatype = unsafe { from_id(H5Tarray_create2(f32_id.id(), 2, dims.as_ptr())) }?;
+    assert_eq!(arr.array_dims()?, [2, 3]);
+    assert_eq!(arr.base_type()?.to_descriptor()?, TD::Float(FloatSize::U4));
+    assert_err!(arr.to_descriptor(), "Multi-dimensional array datatypes are not supported");
+
+    let enum_members = members[2].datatype.enum_members()?;
+    assert_eq!(
+        enum_members,
+        [EnumMember { name: "X".into(), value: 0xff }, EnumMember { name: "Y".into(), value: 3 }]
+    );
+    assert_eq!(members[2].datatype.base_type()?.class()?, TypeClass::Integer);
+
+    let string = &members[3].datatype;
+    assert!(!string.is_variable_str());
+    assert_eq!(string.string_padding(), Some(StringPadding::NullPad));
+    assert_eq!(string.size(), 5);
+    assert!(Datatype::from_type::<VarLenUnicode>()?.is_variable_str());
+
+    assert_eq!(members[4].datatype.base_type()?.to_descriptor()?, TD::Unsigned(IntSize::U2));
+    assert_err!(dtype.enum_members(), "expected Enum datatype, got Compound");
+    assert_err!(dtype.array_dims(), "expected Array datatype, got Compound");
+    assert_err!(dtype.base_type(), "Compound datatype has no base type");
+    assert_err!(arr.compound_members(), "expected Compound datatype, got Array");
+    Ok(())
+}
+
+#[test]
+pub fn test_soft_conversion() -> hdf5::Result<()> {
+    use self::common::util::new_in_memory_file;
+    use hdf5::datatype::{Conversion, SoftConversion};
+
+    struct Q16;
+    impl OpaqueTag for Q16 {
+        const TAG: &'static str = "fixed-point/q16.16";
+    }
+    type Fixed = FixedOpaque<[u8; 4], Q16>;
+
+    struct FixedToFloat;
+    impl SoftConversion for FixedToFloat {
+        fn accepts(src: &Datatype, dst: &Datatype) -> bool {
+            src.to_descriptor().ok() == Some(Fixed::type_descriptor()) && dst.is::<f64>()
+        }
+        fn convert(src: &[u8], dst: &mut [u8]) -> hdf5::Result<()> {
+            let bits = i32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+            dst.copy_from_slice(&(f64::from(bits) / 65536.).to_ne_bytes());
+            Ok(())
+        }
+    }
+
+    struct PanickingFixedToFloat;
+    impl SoftConversion for PanickingFixedToFloat {
+        fn accepts(src: &Datatype, dst: &Datatype) -> bool {
+            src.to_descriptor().ok() == Some(Fixed::type_descriptor()) && dst.is::<f32>()
+        }
+        fn convert(_src: &[u8], _dst: &mut [u8]) -> hdf5::Result<()> {
+            panic!("not implemented")
+        }
+    }
+
+    let fixed = Datatype::from_type::<Fixed>()?;
+    let f64_dt = Datatype::from_type::<f64>()?;
+    let f32_dt = Datatype::from_type::<f32>()?;
+    assert_eq!(fixed.conv_to::<f64>(), None);
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<Fixed>().create("fixed", 3)?;
+    let values = [0x0001_8000_i32, -0x0002_0000, 0x0000_4000];
+    let data: Vec<_> = values.iter().map(|v| Fixed::new(v.to_le_bytes())).collect();
+    ds.write(&data)?;
+    assert_err!(ds.read_raw::<f64>(), "no conversion paths found");
+
+    fixed.register_soft_conversion::<FixedToFloat, _>("q16.16->f64", &f64_dt)?;
+    assert_eq!(fixed.conv_to::<f64>(), Some(Conversion::Soft));
+    assert_eq!(fixed.conv_path(&f32_dt), None);
+    assert_eq!(ds.read_raw::<f64>()?, vec![1.5, -2., 0.25]);
+
+    fixed.unregister_soft_conversion::<FixedToFloat, _>("q16.16->f64", &f64_dt)?;
+    assert_eq!(fixed.conv_to::<f64>(), None);
+
+    // panics in user code are caught in the callback and reported as errors
+    fixed.register_soft_conversion::<PanickingFixedToFloat, _>("q16.16->f32", &f32_dt)?;
+    assert_err!(ds.read_raw::<f32>(), "panic in callback: not implemented: ");
+    assert_eq!(ds.read_raw::<Fixed>()?, data);
+    fixed.unregister_soft_conversion::<PanickingFixedToFloat, _>("q16.16->f32", &f32_dt)?;
+    Ok(())
+}
+
+#[test]
+pub fn test_references() -> hdf5::Result<()> {
+    use self::common::util::new_in_memory_file;
+    use hdf5::{Dataset, Dimension, Group};
+    use hdf5_sys::h5s::H5Sget_select_npoints;
+    use ndarray::s;
+
+    #[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Entry {
+        id: u16,
+        target: ObjectReference,
+        region: RegionReference,
+    }
+
+    let desc = Entry::type_descriptor();
+    assert_eq!(Datatype::from_type::<Entry>()?.to_descriptor()?, desc);
+
+    let file = new_in_memory_file()?;
+    let group = file.create_group("entries")?;
+    let values = file.new_dataset::<f64>().create("values", (2, 3))?;
+    values.write(&ndarray::arr2(&[[1., 2., 3.], [4., 5., 6.]]))?;
+
+    let data = vec![
+        Entry {
+            id: 1,
+            target: file.reference("entries")?,
+            region: values.region_reference(s![.., 1..])?,
+        },
+        Entry { id: 2, target: group.reference("/")?, region: RegionReference::default() },
+    ];
+    let ds = file.new_dataset::<Entry>().create("table", 2)?;
+    ds.write(&data)?;
+    let read = ds.read_raw::<Entry>()?;
+    assert_eq!(read, data);
+
+    assert_eq!(file.dereference::<Group>(&read[0].target)?.name(), "/entries");
+    assert_eq!(file.dereference::<Group>(&read[1].target)?.name(), "/");
+    let refcount = group.refcount();
+    assert_err!(file.dereference::<Dataset>(&read[0].target), "Invalid dataset id");
+    assert_eq!(group.refcount(), refcount);
+    assert_err!(file.dereference::<Group>(&ObjectReference::default()), "null object reference");
+
+    let (dataset, space) = file.dereference_region(&read[0].region)?;
+    assert_eq!(dataset.name(), "/values");
+    assert_eq!(space.dims(), vec![2, 3]);
+    assert_eq!(unsafe { H5Sget_select_npoints(space.id()) }, 4);
+    assert_err!(file.dereference_region(&read[1].region), "null region reference");
+    assert_err!(values.region_reference(s![1..]), "slice dimension mismatch");
+    Ok(())
+}
+
+#[test]
+pub fn test_read_enum_by_name() -> hdf5::Result<()> {
+    use hdf5::UnknownEnumValue;
+    use hdf5_sys::{h5d::H5Dwrite, h5p::H5P_DEFAULT, h5s::H5S_ALL};
+
+    use self::common::util::new_in_memory_file;
+
+    #[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+    #[repr(u8)]
+    enum Stored {
+        Off = 0,
+        On = 1,
+        Fault = 7,
+    }
+
+    #[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+    #[repr(i16)]
+    enum State {
+        On = 10,
+        Off = 20,
+        Other = -1,
+    }
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<Stored>().create("states", 3)?;
+    ds.write(&[Stored::Off, Stored::On, Stored::Fault])?;
+
+    let read = ds.read_enum(UnknownEnumValue::Fallback(State::Other))?;
+    assert_eq!(read.into_raw_vec(), vec![State::Off, State::On, State::Other]);
+    assert_err!(ds.read_enum(UnknownEnumValue::<State>::Error), "unknown enum member: \"Fault\"");
+    assert_eq!(ds.read_enum_names(UnknownEnumValue::Error)?.into_raw_vec(), ["Off", "On", "Fault"]);
+    assert_err!(ds.read_enum(UnknownEnumValue::Fallback(1_u8)), "expected enum type");
+
+    let ds = file.new_dataset::<Stored>().create("raw", 2)?;
+    let (id, dtype) = (ds.id(), ds.dtype()?);
+    let raw = [1_u8, 5];
+    assert!(
+        unsafe { H5Dwrite(id, dtype.id(), H5S_ALL, H5S_ALL, H5P_DEFAULT, raw.as_ptr() as _) } >= 0
+    );
+    assert_err!(ds.read_enum(UnknownEnumValue::<State>::Error), "unknown enum value: 5");
+    assert_eq!(ds.read_enum_names(UnknownEnumValue::Fallback("?"))?.into_raw_vec(), ["On", "?"]);
+
+    let ds = file.new_dataset::<u8>().create("ints", 1)?;
+    assert_err!(ds.read_enum_names(UnknownEnumValue::Error), "expected Enum datatype, got Integer");
+    Ok(())
+}
+
+#[test]
+pub fn test_read_string_arena() -> hdf5::Result<()> {
+    use hdf5::datatype::StringPadding;
+
+    use self::common::util::new_in_memory_file;
+
+    let file = new_in_memory_file()?;
+    let strings: Vec<VarLenUnicode> =
+        ["", "foo", "ünïcödé", "barbaz"].iter().map(|s| s.parse().unwrap()).collect();
+    let ds = file.new_dataset::<VarLenUnicode>().create("vlen", (2, 2))?;
+    ds.write_raw(&strings)?;
+    let arena = ds.read_string_arena()?;
+    assert_eq!(arena.shape(), [2, 2]);
+    assert_eq!(arena.len(), 4);
+    assert_eq!(arena.iter().collect::<Vec<_>>(), ["", "foo", "ünïcödé", "barbaz"]);
+    assert_eq!(arena.as_str(), "fooünïcödébarbaz");
+    assert_eq!(arena.offsets(), [0, 0, 3, 14, 20]);
+    assert_eq!((arena.get(1), &arena[3], arena.get(4)), (Some("foo"), "barbaz", None));
+    assert_eq!(arena.to_array()?, ds.read_dyn::<VarLenUnicode>()?.map(|s| s.to_string()));
+
+    type S = FixedAscii<[u8; 4]>;
+    let data = [S::from_ascii("ab").unwrap(), S::from_ascii("abcd").unwrap()];
+    let ds = file.new_dataset::<S>().string_padding(StringPadding::SpacePad).create("fixed", 2)?;
+    ds.write(&data)?;
+    assert_eq!(ds.read_string_arena()?.iter().collect::<Vec<_>>(), ["ab", "abcd"]);
+
+    let attr = file.new_attr::<VarLenAscii>().create("attr", ())?;
+    attr.write_scalar(&VarLenAscii::from_ascii("scalar").unwrap())?;
+    let arena = attr.read_string_arena()?;
+    assert_eq!((arena.shape(), &arena[0]), (&[][..], "scalar"));
+
+    let ds = file.new_dataset::<u8>().create("ints", 1)?;
+    assert_err!(ds.read_string_arena(), "expected String datatype, got Integer");
+    Ok(())
+}
+
+#[test]
+pub fn test_numpy_dtype() -> hdf5::Result<()> {
+    use self::common::util::new_in_memory_file;
+
+    #[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Row {
+        index: i64,
+        value: f32,
+        label: FixedAscii<[u8; 4]>,
+    }
+
+    let desc = TD::from_numpy_str("[('index', '<i8'), ('value', '<f4'), ('label', 'S4')]")?;
+    assert_eq!(desc, Row::type_descriptor().to_packed_repr());
+
+    let file = new_in_memory_file()?;
+    let dtype = Datatype::from_descriptor(&desc)?;
+    assert_eq!(dtype.size(), 16);
+    let ds = file.new_dataset::<Row>().packed(true).create("rows", 1)?;
+    assert!(ds.dtype()?.is_convertible_to(&dtype));
+    let row = Row { index: 7, value: 0.5, label: FixedAscii::from_ascii(b"abc").unwrap() };
+    ds.write(&[row])?;
+    assert_eq!(ds.read_raw::<Row>()?, [row]);
+    Ok(())
+}
+
+#[test]
+pub fn test_datatype_text() -> hdf5::Result<()> {
+    #[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Point {
+        x: f64,
+        tags: [u8; 2],
+    }
+
+    #[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+    #[repr(i8)]
+    enum Color {
+        Red = 1,
+        Green = 2,
+    }
+
+    let text = "H5T_COMPOUND {
+        H5T_NATIVE_DOUBLE \"x\" : 0;
+        H5T_ARRAY { [2] H5T_NATIVE_UCHAR } \"tags\" : 8;
+    }";
+    let dtype = Datatype::from_text(text)?;
+    assert_eq!(dtype.size(), 10);
+    assert_eq!(dtype.to_descriptor()?, Point::type_descriptor().to_packed_repr());
+    assert_err!(
+        Datatype::from_text("H5T_FOO"),
+        "invalid DDL datatype (at 0): unsupported datatype: H5T_FOO"
+    );
+
+    #[cfg(feature = "hl")]
+    {
+        let text = Datatype::from_type::<i16>()?.to_text()?;
+        let native = if cfg!(target_endian = "little") { "LE" } else { "BE" };
+        assert_eq!(text, format!("H5T_STD_I16{}", native));
+
+        let text = Datatype::from_type::<Point>()?.to_text()?;
+        assert!(text.starts_with("H5T_COMPOUND {") && text.contains("\"tags\" : 8;"));
+        assert_eq!(Datatype::from_text(&text)?, dtype);
+
+        fn check_roundtrip<T: H5Type>() -> hdf5::Result<()> {
+            let text = Datatype::from_type::<T>()?.to_text()?;
+            assert_eq!(Datatype::from_text(&text)?.to_descriptor()?, T::type_descriptor());
+            Ok(())
+        }
+        check_roundtrip::<bool>()?;
+        check_roundtrip::<Color>()?;
+        check_roundtrip::<[[f32; 3]; 2]>()?;
+        check_roundtrip::<VarLenArray<u64>>()?;
+        check_roundtrip::<VarLenUnicode>()?;
+        check_roundtrip::<FixedAscii<[u8; 5]>>()?;
+        check_roundtrip::<ObjectReference>()?;
+        check_roundtrip::<RegionReference>()?;
+        check_roundtrip::<(u8, u8)>()?;
+    }
+    Ok(())
+}
+
+#[test]
+pub fn test_custom_float() -> hdf5::Result<()> {
+    use hdf5::datatype::FloatLayout;
+    use hdf5::{Dataset, Dataspace};
+    use hdf5_sys::{h5d::H5Dcreate2, h5d::H5Dwrite, h5p::H5P_DEFAULT, h5s::H5S_ALL};
+
+    use self::common::util::new_in_memory_file;
+
+    let f32_layout = Datatype::from_type::<f32>()?.float_layout()?;
+    assert_eq!(
+        f32_layout,
+        FloatLayout {
+            size: 4,
+            precision: 32,
+            offset: 0,
+            sign_pos: 31,
+            exp_pos: 23,
+            exp_size: 8,
+            mant_pos: 0,
+            mant_size: 23,
+            exp_bias: 127,
+        }
+    );
+    assert_err!(
+        Datatype::from_type::<u8>()?.float_layout(),
+        "expected Float datatype, got Integer"
+    );
+
+    // 24-bit float with a 7-bit exponent, as produced by some instrument firmware
+    let layout = FloatLayout {
+        size: 3,
+        precision: 24,
+        offset: 0,
+        sign_pos: 23,
+        exp_pos: 16,
+        exp_size: 7,
+        mant_pos: 0,
+        mant_size: 16,
+        exp_bias: 63,
+    };
+    let dtype = Datatype::from_float_layout(&layout)?;
+    assert_eq!(dtype.size(), 3);
+    assert_eq!(dtype.float_layout()?, layout);
+    assert_eq!(dtype.to_descriptor()?, TD::Float(FloatSize::U4));
+    assert!(dtype.conv_to::<f64>().is_some());
+
+    let file = new_in_memory_file()?;
+    let space = Dataspace::try_new(2, false)?;
+    let ds: Dataset = unsafe {
+        let name = b"f24\0".as_ptr() as *const _;
+        let (p, id) = (H5P_DEFAULT, file.id());
+        from_id(H5Dcreate2(id, name, dtype.id(), space.id(), p, p, p))?
+    };
+    let raw = [0x00_u8, 0x80, 0x3f, 0x00, 0x00, 0xbd];
+    let ptr = raw.as_ptr() as *const _;
+    assert!(unsafe { H5Dwrite(ds.id(), dtype.id(), H5S_ALL, H5S_ALL, H5P_DEFAULT, ptr) } >= 0);
+    assert_eq!(ds.dtype()?.float_layout()?, layout);
+    assert_eq!(ds.read_raw::<f32>()?, vec![1.5, -0.25]);
+    assert_eq!(ds.read_raw::<f64>()?, vec![1.5, -0.25]);
+    assert_eq!(
+        ds.read_dyn_array()?.into_raw_vec(),
+        vec![DynValue::Float(1.5), DynValue::Float(-0.25)]
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_compatible_with() -> hdf5::Result<()> {
+    #[derive(H5Type, Clone, Copy, PartialEq)]
+    #[repr(u8)]
+    enum Color {
+        Red = 1,
+        Green = 2,
+    }
+
+    #[derive(H5Type, Clone, Copy, PartialEq)]
+    #[repr(u8)]
+    enum Primary {
+        Red = 1,
+    }
+
+    #[derive(H5Type)]
+    #[repr(C)]
+    struct Inner {
+        x: u8,
+        y: FixedAscii<[u8; 4]>,
+    }
+
+    #[derive(H5Type)]
+    #[repr(C)]
+    struct Stored {
+        a: i32,
+        b: [f64; 3],
+        s: VarLenUnicode,
+        e: Color,
+        inner: Inner,
+    }
+
+    macro_rules! check {
+        ($($field:ident: $ty:ty),*) => {{
+            #[derive(H5Type)]
+            #[repr(C)]
+            struct Read {
+                $($field: $ty),*
+            }
+            Datatype::from_type::<Stored>()?.compatible_with::<Read>()
+        }};
+    }
+
+    #[derive(H5Type)]
+    #[repr(C)]
+    struct InnerFloat {
+        y: f64,
+    }
+
+    #[derive(Default, H5Type)]
+    #[repr(C)]
+    struct WithDefault {
+        a: i64,
+        #[hdf5(default)]
+        z: u8,
+    }
+
+    let stored = Datatype::from_type::<Stored>()?;
+    stored.compatible_with::<Stored>()?;
+    stored.compatible_with::<WithDefault>()?;
+    check!(a: f64, s: VarLenUnicode, e: Color)?;
+    check!(b: [f32; 3], inner: Inner)?;
+    assert_err!(check!(a: i32, z: u8), "member \"z\" not found in stored datatype");
+    assert_err!(
+        check!(inner: InnerFloat),
+        "inner.y: can't convert stored String datatype to Float(U8)"
+    );
+    assert_err!(check!(b: [f64; 2]), "b: expected array of length 2, stored array has dims [3]");
+    assert_err!(check!(s: VarLenAscii), "s: can't read UTF-8 strings as ASCII");
+    assert_err!(check!(e: Primary), "e: stored enum members [\"Green\"] have no match");
+
+    let int = Datatype::from_type::<i32>()?;
+    assert!(int.is_convertible_to(Datatype::from_type::<f64>()?));
+    assert!(!int.is_convertible_to(Datatype::from_type::<VarLenUnicode>()?));
+    assert_err!(
+        int.compatible_with::<VarLenUnicode>(),
+        "can't convert stored Integer datatype to VarLenUnicode"
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_explicit_layout() -> hdf5::Result<()> {
+    use self::common::util::new_in_memory_file;
+
+    #[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    #[hdf5(size = 16)]
+    struct Record {
+        #[hdf5(offset = 8)]
+        a: u32,
+        #[hdf5(offset = 0)]
+        b: u16,
+        #[hdf5(offset = 12)]
+        c: u8,
+    }
+
+    let file = new_in_memory_file()?;
+    let data = [Record { a: 1, b: 2, c: 3 }, Record { a: 4, b: 5, c: 6 }];
+    let ds = file.new_dataset::<Record>().packed(true).create("records", 2)?;
+    ds.write(&data)?;
+    let dtype = ds.dtype()?;
+    assert_eq!(dtype.size(), 16);
+    let members = dtype.compound_members()?;
+    let names: Vec<_> = members.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["b", "a", "c"]);
+    let offsets: Vec<_> = members.iter().map(|m| m.offset).collect();
+    assert_eq!(offsets, [0, 8, 12]);
+    assert_eq!(ds.read_raw::<Record>()?, data);
+
+    let attr = file.new_attr::<Record>().create("record", ())?;
+    attr.write_scalar(&data[1])?;
+    assert_eq!(attr.dtype()?.size(), 16);
+    assert_eq!(attr.read_scalar::<Record>()?, data[1]);
+    Ok(())
+}
+
 #[test]
 pub fn test_invalid_datatype() {
     assert_err!(from_id::<Datatype>(H5I_INVALID_HID), "Invalid datatype id");
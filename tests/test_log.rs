@@ -0,0 +1,54 @@
+#![cfg(feature = "log")]
+
+use std::sync::{Arc, Mutex};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+#[macro_use]
+mod common;
+
+use self::common::util::new_in_memory_file;
+
+struct Logger(Arc<Mutex<Vec<(Level, String)>>>);
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == "hdf5"
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push((record.level(), record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn test_log_records() -> hdf5::Result<()> {
+    let records = Arc::new(Mutex::new(Vec::new()));
+    log::set_boxed_logger(Box::new(Logger(records.clone()))).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let file = new_in_memory_file()?;
+    let ds = file.new_dataset::<u32>().create("foo", 3)?;
+    ds.write(&[1, 2, 3])?;
+    assert_eq!(ds.read_raw::<u32>()?, vec![1, 2, 3]);
+    file.create_group("bar")?;
+    assert!(file.group("baz").is_err());
+
+    let records = records.lock().unwrap();
+    let filename = file.filename();
+    let find = |level: Level, prefix: &str| {
+        records.iter().find(|(l, msg)| *l == level && msg.starts_with(prefix)).map(|r| &r.1)
+    };
+    assert!(find(Level::Debug, &format!("opened file {} in Create mode in ", filename)).is_some());
+    let created = format!("created dataset \"/foo\" with shape [3] in {} in ", filename);
+    assert!(find(Level::Debug, &created).is_some());
+    assert!(find(Level::Trace, "wrote 12 bytes to /foo in ").is_some());
+    assert!(find(Level::Trace, "read 12 bytes from /foo in ").is_some());
+    assert!(find(Level::Debug, "created group /bar in ").is_some());
+    assert!(find(Level::Error, "H5Gopen2(): unable to open group").is_some());
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use hdf5::pandas::{read_frame, Column};
+use hdf5::types::FixedAscii;
+use hdf5::{H5Type, Location};
+
+#[macro_use]
+mod common;
+
+use self::common::util::new_in_memory_file;
+
+/// Record layout of `DataFrame.to_hdf(format="table", data_columns=["count"])` for a
+/// frame with float columns `x` and `y`, a string column `name` and an integer column
+/// `count`.
+#[derive(H5Type, Clone, Copy, Debug)]
+#[repr(C)]
+struct Row {
+    index: i64,
+    values_block_0: [f64; 2],
+    values_block_1: [FixedAscii<[u8; 4]>; 1],
+    count: i64,
+}
+
+fn set_str(loc: &Location, name: &str, value: &[u8]) {
+    loc.set_attr(name, &FixedAscii::<[u8; 64]>::from_ascii(value).unwrap()).unwrap();
+}
+
+#[test]
+pub fn test_read_table_frame() {
+    let file = new_in_memory_file().unwrap();
+    let group = file.create_group("df").unwrap();
+    set_str(&group, "pandas_type", b"frame_table");
+    set_str(&group, "table_type", b"appendable_frame");
+    set_str(&group, "index_cols", b"(lp0\n(I0\nVindex\np1\ntp2\na.");
+    set_str(
+        &group,
+        "non_index_axes",
+        b"(lp0\n(I1\n(lp1\nVx\np2\naVname\np3\naVy\np4\naVcount\np5\natp6\na.",
+    );
+
+    let name = |s: &str| [FixedAscii::from_ascii(s).unwrap()];
+    let rows = vec![
+        Row { index: 7, values_block_0: [1., 2.], values_block_1: name("ab"), count: 3 },
+        Row { index: 8, values_block_0: [3., 4.], values_block_1: name("cdef"), count: -1 },
+    ];
+    let table = group.new_dataset::<Row>().create("table", 2).unwrap();
+    table.write(&rows).unwrap();
+    set_str(&table, "index_kind", b"integer");
+    set_str(&table, "values_block_0_kind", b"(lp0\nVx\np1\naVy\np2\na.");
+    set_str(&table, "values_block_0_dtype", b"float64");
+    set_str(&table, "values_block_1_kind", b"(lp0\nVname\np1\na.");
+    set_str(&table, "count_kind", b"(lp0\nVcount\np1\na.");
+
+    let frame = read_frame(&file, "df").unwrap();
+    assert_eq!(frame.index(), &Column::Int(vec![7, 8]));
+    assert_eq!(frame.column_names(), vec!["x", "name", "y", "count"]);
+    assert_eq!(frame.column("x").unwrap(), &Column::Float(vec![1., 3.]));
+    assert_eq!(frame.column("y").unwrap().as_float(), Some(&[2., 4.][..]));
+    assert_eq!(frame.column("name").unwrap().as_str().unwrap(), &["ab", "cdef"]);
+    assert_eq!(frame.column("count").unwrap().as_int(), Some(&[3, -1][..]));
+
+    // without (parseable) label attributes, columns are named after the table fields
+    table.delete_attr("values_block_0_kind").unwrap();
+    set_str(&table, "values_block_1_kind", b"ccopy_reg\n_reconstructor\n.");
+    group.delete_attr("non_index_axes").unwrap();
+    let frame = read_frame(&file, "df").unwrap();
+    assert_eq!(
+        frame.column_names(),
+        vec!["values_block_0_0", "values_block_0_1", "values_block_1_0", "count"]
+    );
+    assert_eq!(frame.len(), 2);
+}
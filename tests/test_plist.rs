@@ -3,8 +3,14 @@ extern crate mashup;
 
 use std::mem;
 
+use hdf5::attribute::*;
 use hdf5::dataset::*;
 use hdf5::file::*;
+use hdf5::plist::datatype_access::*;
+use hdf5::plist::datatype_create::*;
+use hdf5::plist::group_create::*;
+use hdf5::plist::link_create::*;
+use hdf5::plist::object_copy::*;
 use hdf5::plist::*;
 
 macro_rules! test_pl {
@@ -59,6 +65,14 @@ macro_rules! test_pl_common {
         assert_eq!(pl2.class()?, $plc);
         assert_eq!(pl2, pl);
         assert_ne!(pl2, pl_default);
+
+        #[cfg(hdf5_1_10_0)]
+        {
+            let pl3 = $cls::decode(&pl.encode()?)?;
+            assert_eq!(pl3.class()?, $plc);
+            assert_eq!(pl3, pl);
+            assert_ne!(pl3, pl_default);
+        }
     };
 }
 
@@ -86,6 +100,8 @@ fn test_fcpl_sizes() -> hdf5::Result<()> {
     let fcpl = FileCreate::try_new()?;
     assert_eq!(fcpl.sizes().sizeof_addr, mem::size_of::<hsize_t>());
     assert_eq!(fcpl.sizes().sizeof_size, mem::size_of::<hsize_t>());
+    test_pl!(FC, sizes: sizeof_addr = 4, sizeof_size = 8);
+    test_pl!(FC, sizes: sizeof_addr = 8, sizeof_size = 2);
     Ok(())
 }
 
@@ -117,6 +133,14 @@ fn test_fcpl_set_shared_mesg_change() -> hdf5::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_fcpl_set_attr_phase_change() -> hdf5::Result<()> {
+    test_pl!(FC, attr_phase_change: max_compact = 8, min_dense = 6);
+    test_pl!(FC, attr_phase_change: max_compact = 0, min_dense = 0);
+    test_pl!(FC, attr_phase_change: max_compact = 32, min_dense = 20);
+    Ok(())
+}
+
 #[test]
 fn test_fcpl_set_shared_mesg_indexes() -> hdf5::Result<()> {
     let idx = vec![SharedMessageIndex {
@@ -577,3 +601,246 @@ fn test_dapl_set_virtual_printf_gap() -> hdf5::Result<()> {
     test_pl!(DA, virtual_printf_gap: 123);
     Ok(())
 }
+
+#[test]
+#[cfg(hdf5_1_10_2)]
+fn test_dapl_set_virtual_prefix() -> hdf5::Result<()> {
+    assert_eq!(DA::try_new()?.get_virtual_prefix().unwrap(), "".to_owned());
+    assert_eq!(DA::try_new()?.virtual_prefix(), "".to_owned());
+    let mut b = DA::build();
+    b.virtual_prefix("${ORIGIN}/foo");
+    assert_eq!(b.finish()?.get_virtual_prefix()?, "${ORIGIN}/foo".to_owned());
+    Ok(())
+}
+
+type DX = DatasetTransfer;
+type DXB = DatasetTransferBuilder;
+
+#[test]
+fn test_dxpl_common() -> hdf5::Result<()> {
+    test_pl_common!(DX, PropertyListClass::DataTransfer, |b: &mut DXB| b
+        .hyper_vector_size(16)
+        .finish());
+    Ok(())
+}
+
+#[test]
+fn test_dxpl_set_buffer_size() -> hdf5::Result<()> {
+    assert_eq!(DX::try_new()?.buffer_size(), 1024 * 1024);
+    test_pl!(DX, buffer_size: 4096);
+    test_pl!(DX, buffer_size: 1 << 24);
+    Ok(())
+}
+
+#[test]
+fn test_dxpl_set_hyper_vector_size() -> hdf5::Result<()> {
+    assert_eq!(DX::try_new()?.hyper_vector_size(), 1024);
+    test_pl!(DX, hyper_vector_size: 1);
+    test_pl!(DX, hyper_vector_size: 4096);
+    Ok(())
+}
+
+#[test]
+fn test_dxpl_set_edc_check() -> hdf5::Result<()> {
+    assert!(DX::try_new()?.edc_check());
+    test_pl!(DX, edc_check: false);
+    test_pl!(DX, edc_check: true);
+    Ok(())
+}
+
+#[test]
+fn test_dxpl_set_abort_on_conv_exception() -> hdf5::Result<()> {
+    assert!(!DX::try_new()?.abort_on_conv_exception());
+    test_pl!(DX, abort_on_conv_exception: true);
+    test_pl!(DX, abort_on_conv_exception: false);
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "mpio")]
+fn test_dxpl_set_mpio_transfer_mode() -> hdf5::Result<()> {
+    test_pl!(DX, mpio_transfer_mode: TransferMode::Collective);
+    test_pl!(DX, mpio_transfer_mode: TransferMode::Independent);
+    Ok(())
+}
+
+type AC = AttributeCreate;
+type ACB = AttributeCreateBuilder;
+
+#[test]
+fn test_acpl_common() -> hdf5::Result<()> {
+    test_pl_common!(AC, PropertyListClass::AttributeCreate, |b: &mut ACB| b
+        .char_encoding(CharEncoding::Utf8)
+        .finish());
+    Ok(())
+}
+
+#[test]
+fn test_acpl_set_char_encoding() -> hdf5::Result<()> {
+    test_pl!(AC, char_encoding: CharEncoding::Ascii);
+    test_pl!(AC, char_encoding: CharEncoding::Utf8);
+    Ok(())
+}
+
+type LC = LinkCreate;
+type LCB = LinkCreateBuilder;
+
+#[test]
+fn test_lcpl_common() -> hdf5::Result<()> {
+    test_pl_common!(LC, PropertyListClass::LinkCreate, |b: &mut LCB| b
+        .char_encoding(CharEncoding::Utf8)
+        .create_intermediate_group(true)
+        .finish());
+    Ok(())
+}
+
+#[test]
+fn test_lcpl_set_char_encoding() -> hdf5::Result<()> {
+    test_pl!(LC, char_encoding: CharEncoding::Ascii);
+    test_pl!(LC, char_encoding: CharEncoding::Utf8);
+    Ok(())
+}
+
+#[test]
+fn test_lcpl_set_create_intermediate_group() -> hdf5::Result<()> {
+    assert!(!LC::try_new()?.create_intermediate_group());
+    test_pl!(LC, create_intermediate_group: true);
+    test_pl!(LC, create_intermediate_group: false);
+    Ok(())
+}
+
+type OC = ObjectCopy;
+type OCB = ObjectCopyBuilder;
+
+#[test]
+fn test_ocpypl_common() -> hdf5::Result<()> {
+    test_pl_common!(OC, PropertyListClass::ObjectCopy, |b: &mut OCB| b
+        .shallow_hierarchy(true)
+        .finish());
+    Ok(())
+}
+
+#[test]
+fn test_ocpypl_set_flags() -> hdf5::Result<()> {
+    test_pl!(OC, shallow_hierarchy: true);
+    test_pl!(OC, expand_soft_links: true);
+    test_pl!(OC, expand_ext_links: true);
+    test_pl!(OC, expand_references: true);
+    test_pl!(OC, without_attrs: true);
+    test_pl!(OC, preserve_null: true);
+    #[cfg(hdf5_1_8_9)]
+    test_pl!(OC, merge_committed_dtypes: true);
+
+    let pl = OC::build().without_attrs(true).expand_references(true).finish()?;
+    assert!(pl.without_attrs() && pl.expand_references() && !pl.shallow_hierarchy());
+    let pl = OCB::from_plist(&pl)?.without_attrs(false).finish()?;
+    assert!(!pl.without_attrs() && pl.expand_references());
+    Ok(())
+}
+
+type TA = DatatypeAccess;
+type TC = DatatypeCreate;
+type TCB = DatatypeCreateBuilder;
+
+#[test]
+fn test_tapl_common() -> hdf5::Result<()> {
+    let pl = TA::try_new()?;
+    assert_eq!(pl.class()?, PropertyListClass::DatatypeAccess);
+    assert_eq!(TA::build().finish()?, pl);
+    #[cfg(not(all(hdf5_1_10_0, feature = "mpio")))]
+    assert_eq!(format!("{:?}", pl), "DatatypeAccess");
+    Ok(())
+}
+
+#[test]
+#[cfg(all(hdf5_1_10_0, feature = "mpio"))]
+fn test_tapl_set_all_coll_metadata_ops() -> hdf5::Result<()> {
+    test_pl!(TA, all_coll_metadata_ops: true);
+    test_pl!(TA, all_coll_metadata_ops: false);
+    Ok(())
+}
+
+#[test]
+fn test_tcpl_common() -> hdf5::Result<()> {
+    test_pl_common!(TC, PropertyListClass::DatatypeCreate, |b: &mut TCB| b
+        .obj_track_times(false)
+        .finish());
+    Ok(())
+}
+
+#[test]
+fn test_tcpl_set_obj_track_times() -> hdf5::Result<()> {
+    assert!(TC::try_new()?.obj_track_times());
+    test_pl!(TC, obj_track_times: false);
+    test_pl!(TC, obj_track_times: true);
+    Ok(())
+}
+
+#[test]
+fn test_tcpl_set_attr_phase_change() -> hdf5::Result<()> {
+    test_pl!(TC, attr_phase_change: max_compact = 32, min_dense = 8);
+    test_pl!(TC, attr_phase_change: max_compact = 0, min_dense = 0);
+    Ok(())
+}
+
+type DC = DatasetCreate;
+
+#[test]
+fn test_dcpl_common() -> hdf5::Result<()> {
+    let pl = DC::try_new()?;
+    assert_eq!(pl.class()?, PropertyListClass::DatasetCreate);
+    assert_eq!(pl.layout(), Layout::Contiguous);
+    assert_eq!(pl.chunk(), None);
+    assert_eq!(pl.filters(), hdf5::filters::Filters::default());
+    assert!(pl.obj_track_times());
+    assert_eq!(pl.copy(), pl);
+    Ok(())
+}
+
+type GC = GroupCreate;
+type GCB = GroupCreateBuilder;
+
+#[test]
+fn test_gcpl_common() -> hdf5::Result<()> {
+    test_pl_common!(GC, PropertyListClass::GroupCreate, |b: &mut GCB| b
+        .local_heap_size_hint(1024)
+        .finish());
+    Ok(())
+}
+
+#[test]
+fn test_gcpl_set_local_heap_size_hint() -> hdf5::Result<()> {
+    test_pl!(GC, local_heap_size_hint: 0);
+    test_pl!(GC, local_heap_size_hint: 4096);
+    Ok(())
+}
+
+#[test]
+fn test_gcpl_set_link_phase_change() -> hdf5::Result<()> {
+    test_pl!(GC, link_phase_change: max_compact = 8, min_dense = 6);
+    test_pl!(GC, link_phase_change: max_compact = 32, min_dense = 20);
+    Ok(())
+}
+
+#[test]
+fn test_gcpl_set_link_creation_order() -> hdf5::Result<()> {
+    test_pl!(GC, link_creation_order: tracked = true, indexed = false);
+    test_pl!(GC, link_creation_order: tracked = true, indexed = true);
+    test_pl!(GC, link_creation_order: tracked = false, indexed = false);
+    Ok(())
+}
+
+#[test]
+fn test_gcpl_set_obj_track_times() -> hdf5::Result<()> {
+    assert!(GC::try_new()?.obj_track_times());
+    test_pl!(GC, obj_track_times: false);
+    test_pl!(GC, obj_track_times: true);
+    Ok(())
+}
+
+#[test]
+fn test_gcpl_set_attr_phase_change() -> hdf5::Result<()> {
+    test_pl!(GC, attr_phase_change: max_compact = 32, min_dense = 8);
+    test_pl!(GC, attr_phase_change: max_compact = 0, min_dense = 0);
+    Ok(())
+}
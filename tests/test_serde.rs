@@ -0,0 +1,118 @@
+#![cfg(feature = "serde")]
+
+use std::collections::BTreeMap;
+
+use serde_crate::{Deserialize, Serialize};
+
+use hdf5::serde::{from_group, to_group};
+
+#[macro_use]
+mod common;
+
+use self::common::util::new_in_memory_file;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "serde_crate")]
+enum Mode {
+    Fast,
+    Accurate,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct Detector {
+    name: String,
+    gain: f32,
+    offsets: (i16, i16),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct Config {
+    version: u8,
+    enabled: bool,
+    mode: Mode,
+    label: Option<String>,
+    threshold: Option<f64>,
+    samples: Vec<i64>,
+    tags: Vec<String>,
+    primary: Detector,
+    detectors: Vec<Detector>,
+    limits: BTreeMap<String, u32>,
+}
+
+fn config() -> Config {
+    let detector = |name: &str, gain| Detector { name: name.into(), gain, offsets: (-1, 2) };
+    Config {
+        version: 3,
+        enabled: true,
+        mode: Mode::Accurate,
+        label: Some("run".into()),
+        threshold: None,
+        samples: vec![1, -2, 3],
+        tags: vec!["a".into(), "ß".into()],
+        primary: detector("main", 1.5),
+        detectors: (0..12).map(|i| detector(&format!("d{}", i), i as f32)).collect(),
+        limits: vec![("lo".into(), 1), ("hi".into(), 10)].into_iter().collect(),
+    }
+}
+
+#[test]
+pub fn test_serde_roundtrip() {
+    let file = new_in_memory_file().unwrap();
+    let group = file.create_group("config").unwrap();
+    let config = config();
+    to_group(&config, &group).unwrap();
+
+    assert_eq!(group.get_attr::<u8>("version").unwrap(), 3);
+    assert_eq!(group.get_attr_string("mode").unwrap(), "Accurate");
+    assert!(!group.attr_exists("threshold") && !group.link_exists("threshold"));
+    assert_eq!(group.dataset("samples").unwrap().read_raw::<i64>().unwrap(), vec![1, -2, 3]);
+    assert!(group.group("primary").unwrap().attr("gain").unwrap().dtype().unwrap().is::<f32>());
+    assert_eq!(group.group("detectors").unwrap().len(), 12);
+
+    assert_eq!(from_group::<Config>(&group).unwrap(), config);
+    let limits: BTreeMap<String, u64> = from_group(&group.group("limits").unwrap()).unwrap();
+    assert_eq!(limits, config.limits.iter().map(|(k, &v)| (k.clone(), v.into())).collect());
+}
+
+#[test]
+pub fn test_serde_overwrite() {
+    let file = new_in_memory_file().unwrap();
+    let group = file.create_group("config").unwrap();
+    let mut config = config();
+    to_group(&config, &group).unwrap();
+    group.new_attr::<u8>().create("extra", ()).unwrap();
+
+    config.label = None;
+    config.threshold = Some(0.5);
+    config.samples.clear();
+    config.detectors.truncate(1);
+    config.mode = Mode::Fast;
+    to_group(&config, &group).unwrap();
+    assert!(group.attr_exists("extra"));
+    assert_eq!(from_group::<Config>(&group).unwrap(), config);
+}
+
+#[test]
+pub fn test_serde_errors() {
+    #[derive(Serialize, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    enum Shape {
+        Circle(f64),
+    }
+
+    #[derive(Serialize)]
+    #[serde(crate = "serde_crate")]
+    struct Item {
+        shape: Shape,
+    }
+
+    let file = new_in_memory_file().unwrap();
+    assert_err!(to_group(&1, &file), "expected a struct or a map");
+    assert_err!(to_group(&Item { shape: Shape::Circle(1.) }, &file), "enum variants with data");
+    let mut map = BTreeMap::new();
+    map.insert(1, 2);
+    assert_err!(to_group(&map, &file), "map keys must be strings");
+    assert_err!(from_group::<Config>(&file), "missing field `version`");
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "hl")]
+
+use hdf5::H5Type;
+
+#[macro_use]
+mod common;
+
+use self::common::util::new_in_memory_file;
+
+#[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+struct Particle {
+    id: u32,
+    pos: [f64; 2],
+    charge: i8,
+}
+
+#[derive(H5Type, Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+struct Position {
+    pos: [f64; 2],
+    id: u32,
+}
+
+fn particle(id: u32) -> Particle {
+    Particle { id, pos: [id as f64, -(id as f64)], charge: (id % 3) as i8 - 1 }
+}
+
+#[test]
+pub fn test_table() {
+    let file = new_in_memory_file().unwrap();
+    let records: Vec<_> = (0..10).map(particle).collect();
+    let table = file
+        .new_table::<Particle>()
+        .title("particles")
+        .chunk_size(4)
+        .compress(true)
+        .with_data("t", &records[..5])
+        .unwrap();
+    assert_eq!(table.title().unwrap(), "particles");
+    assert_eq!(table.field_names().unwrap(), vec!["id", "pos", "charge"]);
+    assert_eq!(table.len().unwrap(), 5);
+
+    table.append(&records[5..]).unwrap();
+    let table = file.table("t").unwrap();
+    assert_eq!(table.read::<Particle>().unwrap(), records);
+    assert_eq!(table.read_records::<Particle>(8, 2).unwrap(), &records[8..]);
+
+    let pos = table.read_fields::<Position>(&["id", "pos"], 2, 3).unwrap();
+    assert_eq!(pos[0], Position { pos: [2., -2.], id: 2 });
+    let ids = table.read_fields::<u32>(&["id"], 0, 10).unwrap();
+    assert_eq!(ids, (0..10).collect::<Vec<_>>());
+
+    table.delete(1, 3).unwrap();
+    table.insert(0, &[particle(42)]).unwrap();
+    let ids = table.read_fields::<u32>(&["id"], 0, 8).unwrap();
+    assert_eq!(ids, vec![42, 0, 4, 5, 6, 7, 8, 9]);
+
+    table.write(1, &[particle(7)]).unwrap();
+    table.write_fields(&["charge"], 0, &[5_i8, 6]).unwrap();
+    let first = table.read_records::<Particle>(0, 2).unwrap();
+    assert_eq!(
+        first,
+        vec![Particle { charge: 5, ..particle(42) }, Particle { charge: 6, ..particle(7) }]
+    );
+}
+
+#[test]
+pub fn test_table_errors() {
+    let file = new_in_memory_file().unwrap();
+    let table = file.new_table::<Particle>().create("t").unwrap();
+    assert!(table.is_empty().unwrap());
+    assert_eq!(table.title().unwrap(), "");
+    assert!(table.read::<Particle>().unwrap().is_empty());
+
+    assert_err!(file.new_table::<u32>().create("x"), "expected a compound record type");
+    assert_err!(file.new_table::<Particle>().chunk_size(0).create("x"), "chunk size");
+    assert_err!(table.append(&[1_u32]), "expected a compound record type");
+    assert_err!(table.append(&[Position { pos: [0.; 2], id: 0 }]), "has 2 fields");
+    assert_err!(table.read_fields::<u32>(&["foo"], 0, 0), "table has no field \"foo\"");
+    assert_err!(table.read_fields::<u64>(&["id"], 0, 0), "field \"id\" is stored as");
+    assert_err!(table.read_records::<Particle>(0, 1), "out of bounds");
+    assert_err!(table.delete(0, 1), "out of bounds");
+
+    file.new_dataset::<u8>().create("d", 1).unwrap();
+    assert_err!(file.table("d"), "dataset \"d\" is not a table");
+}